@@ -0,0 +1,49 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use curve25519_dalek_ng::ristretto::RistrettoPoint;
+use rustorium::core::confidential::{self, ConfidentialOp, Opening, TRANSFER_GAS};
+use rustorium::core::mempool::PendingTransaction;
+
+fn transfer() -> (PendingTransaction, RistrettoPoint) {
+    let balance = Opening::deposit(1_000_000);
+    let (op, _, _) = confidential::prove_transfer("alice", "bob", 1, balance, 250_000).unwrap();
+    let tx = PendingTransaction {
+        hash: String::new(),
+        from: "alice".to_string(),
+        to: "bob".to_string(),
+        value: 0,
+        nonce: 1,
+        gas_price: 1,
+        gas_limit: TRANSFER_GAS,
+        data: op.encode(),
+        received_at: 0,
+        valid_until: None,
+        chain_id: None,
+        blob: None,
+        signature: None,
+    };
+    (tx, balance.commitment())
+}
+
+fn confidential_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("confidential");
+    group.sample_size(20);
+
+    group.bench_function("prove_transfer", |b| {
+        b.iter(|| confidential::prove_transfer("alice", "bob", 1, Opening::deposit(1_000_000), black_box(250_000)).unwrap())
+    });
+
+    let (tx, balance) = transfer();
+    let Some(Ok(ConfidentialOp::Transfer { commitment, proof })) = ConfidentialOp::of(&tx) else {
+        unreachable!("transfer() builds a transfer");
+    };
+    group.bench_function("verify_transfer", |b| {
+        b.iter(|| confidential::verify_transfer(black_box(&tx), &balance, &commitment, &proof).unwrap())
+    });
+
+    // 検証なしの通常のトランザクションとの比較用
+    group.bench_function("decode_op", |b| b.iter(|| ConfidentialOp::of(black_box(&tx))));
+    group.finish();
+}
+
+criterion_group!(benches, confidential_benchmark);
+criterion_main!(benches);
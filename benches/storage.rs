@@ -0,0 +1,80 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustorium::core::storage::{
+    StorageEngine,
+    RocksDBStorage,
+    redb_storage::{RedbStorage, StorageConfig},
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn open_backends() -> Vec<(&'static str, Arc<dyn StorageEngine>)> {
+    let dir = tempfile::tempdir().unwrap().into_path();
+    vec![
+        ("rocksdb", Arc::new(RocksDBStorage::new(dir.join("rocksdb")).unwrap())),
+        ("redb", Arc::new(RedbStorage::new(StorageConfig {
+            path: dir.join("redb").to_string_lossy().to_string(),
+            ..Default::default()
+        }).unwrap())),
+    ]
+}
+
+fn key(i: u64) -> Vec<u8> {
+    format!("bench/{:016}", i).into_bytes()
+}
+
+fn storage_benchmark(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let value = vec![0xabu8; 256];
+
+    let mut group = c.benchmark_group("storage");
+    group.sample_size(50);
+    group.measurement_time(Duration::from_secs(10));
+
+    for (name, engine) in open_backends() {
+        // 読み込み・スキャン用の事前データ
+        rt.block_on(async {
+            for i in 0..10_000 {
+                engine.put(&key(i), &value).await.unwrap();
+            }
+        });
+
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(BenchmarkId::new("put", name), &engine, |b, engine| {
+            let mut i = 10_000;
+            b.to_async(&rt).iter(|| {
+                i += 1;
+                let k = key(i);
+                let v = value.clone();
+                async move { engine.put(black_box(&k), black_box(&v)).await.unwrap() }
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("get", name), &engine, |b, engine| {
+            let mut i = 0;
+            b.to_async(&rt).iter(|| {
+                i = (i + 1) % 10_000;
+                let k = key(i);
+                async move { engine.get(black_box(&k)).await.unwrap() }
+            });
+        });
+
+        group.throughput(Throughput::Elements(100));
+        group.bench_with_input(BenchmarkId::new("scan_100", name), &engine, |b, engine| {
+            b.to_async(&rt).iter(|| async { engine.scan(black_box(&key(0)), 100).await.unwrap() });
+        });
+
+        group.bench_with_input(BenchmarkId::new("batch_100", name), &engine, |b, engine| {
+            let mut base = 1_000_000;
+            b.to_async(&rt).iter(|| {
+                base += 100;
+                let batch = (0..100).map(|i| (key(base + i), Some(value.clone()))).collect();
+                async move { engine.batch_write(black_box(batch)).await.unwrap() }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, storage_benchmark);
+criterion_main!(benches);
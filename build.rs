@@ -0,0 +1,9 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    // gRPC のストリーミング（`grpc` フィーチャー、`protoc` が必要）
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(true)
+        .compile_protos(&["proto/stream.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Multiple API endpoints with health checks, failover and sticky selection
+//!
+//! Endpoints are listed in priority order. The client keeps using the selected endpoint for as
+//! long as it works, and only moves to another one when a request to it fails. A failed endpoint
+//! is skipped for a cooldown period; when every endpoint is cooling down, the one that failed
+//! first is tried again.
+
+use anyhow::{bail, Result};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a failed endpoint is skipped
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// API endpoints in priority order
+pub struct Endpoints {
+    urls: Vec<String>,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+struct State {
+    /// Index of the selected endpoint
+    current: usize,
+    /// When each failed endpoint may be tried again
+    down_until: Vec<Option<Instant>>,
+}
+
+impl Endpoints {
+    /// Create the endpoint list (trailing slashes are removed and duplicates ignored)
+    pub fn new(urls: &[String], cooldown: Duration) -> Result<Self> {
+        let mut unique: Vec<String> = Vec::new();
+        for url in urls {
+            let url = url.trim().trim_end_matches('/').to_string();
+            if !url.is_empty() && !unique.contains(&url) {
+                unique.push(url);
+            }
+        }
+        if unique.is_empty() {
+            bail!("At least one API endpoint is required");
+        }
+        let down_until = vec![None; unique.len()];
+        Ok(Self {
+            urls: unique,
+            cooldown,
+            state: Mutex::new(State { current: 0, down_until }),
+        })
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    pub fn url(&self, index: usize) -> &str {
+        &self.urls[index]
+    }
+
+    /// The selected endpoint
+    pub fn current(&self) -> &str {
+        &self.urls[self.state.lock().unwrap().current]
+    }
+
+    /// Endpoints to try for a request, in order: the selected endpoint if it is not cooling
+    /// down, then the other available endpoints by priority, then the cooling ones by when
+    /// they failed
+    pub fn candidates(&self, now: Instant) -> Vec<usize> {
+        let state = self.state.lock().unwrap();
+        let available = |i: usize| state.down_until[i].map_or(true, |until| until <= now);
+        let mut order: Vec<usize> = Vec::with_capacity(self.urls.len());
+        if available(state.current) {
+            order.push(state.current);
+        }
+        order.extend((0..self.urls.len()).filter(|&i| i != state.current && available(i)));
+        let mut cooling: Vec<usize> = (0..self.urls.len()).filter(|&i| !available(i)).collect();
+        cooling.sort_by_key(|&i| state.down_until[i]);
+        order.extend(cooling);
+        order
+    }
+
+    /// Record a successful request and stick to the endpoint
+    pub fn mark_up(&self, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.down_until[index] = None;
+        state.current = index;
+    }
+
+    /// Record a failed request so the endpoint is skipped for the cooldown period
+    pub fn mark_down(&self, index: usize, now: Instant) {
+        self.state.lock().unwrap().down_until[index] = Some(now + self.cooldown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failover_is_sticky() {
+        let urls = ["http://eu/api/".to_string(), "http://us/api".to_string(), "http://ap/api".to_string(), "http://us/api".to_string()];
+        let endpoints = Endpoints::new(&urls, Duration::from_secs(30)).unwrap();
+        assert_eq!(endpoints.current(), "http://eu/api");
+        let now = Instant::now();
+        assert_eq!(endpoints.candidates(now), [0, 1, 2]);
+
+        // eu fails over to us, which stays selected after eu recovers
+        endpoints.mark_down(0, now);
+        assert_eq!(endpoints.candidates(now), [1, 2, 0]);
+        endpoints.mark_up(1);
+        assert_eq!(endpoints.current(), "http://us/api");
+        assert_eq!(endpoints.candidates(now + Duration::from_secs(31)), [1, 0, 2]);
+
+        // When all are cooling down, the one that failed first is tried first
+        endpoints.mark_down(1, now + Duration::from_secs(1));
+        endpoints.mark_down(2, now + Duration::from_secs(2));
+        assert_eq!(endpoints.candidates(now + Duration::from_secs(3)), [0, 1, 2]);
+
+        assert!(Endpoints::new(&[" ".to_string()], DEFAULT_COOLDOWN).is_err());
+    }
+}
@@ -1,49 +1,127 @@
+pub mod endpoints;
 pub mod models;
 
-use anyhow::Result;
-use models::{NetworkStatus, NodeStats, Block, Transaction, Account, Contract, Token};
-use reqwest::{Client, StatusCode};
+use anyhow::{anyhow, Context, Result};
+use endpoints::{Endpoints, DEFAULT_COOLDOWN};
+use models::{NetworkStatus, NodeStats, Block, Transaction, Account, Contract, ProxyRecord, Token};
+use reqwest::{Client, Method, Response, StatusCode};
 use serde_json::json;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Timeout of a health check request
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// API client for interacting with the Rustorium API
+///
+/// Requests go to the selected endpoint and fail over to the next one when it cannot be
+/// reached. Reads also fail over on timeouts and `502`/`503`/`504` responses; writes only fail
+/// over when the connection could not be made, so a transaction is never submitted twice.
 pub struct ApiClient {
     /// HTTP client
     client: Client,
-    /// API base URL
-    base_url: String,
+    /// API base URLs
+    endpoints: Endpoints,
 }
 
 impl ApiClient {
-    /// Create a new API client
-    pub fn new(base_url: &str) -> Self {
+    /// Create a new API client for one or more base URLs in priority order
+    pub fn new(base_urls: &[String]) -> Result<Self> {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
             .expect("Failed to create HTTP client");
         
-        Self {
+        Ok(Self {
             client,
-            base_url: base_url.to_string(),
-        }
+            endpoints: Endpoints::new(base_urls, DEFAULT_COOLDOWN)?,
+        })
+    }
+
+    /// The base URL requests are currently sent to
+    pub fn current_endpoint(&self) -> &str {
+        self.endpoints.current()
     }
     
-    /// Check if the API is reachable
+    /// Check all endpoints and select the first healthy one in priority order
     pub async fn check_connection(&self) -> Result<()> {
-        let url = format!("{}/network/status", self.base_url);
-        let response = self.client.get(&url).send().await?;
-        
-        if response.status() != StatusCode::OK {
-            anyhow::bail!("API returned status code: {}", response.status());
+        let checks = self.endpoints.urls().iter().map(|base_url| async move {
+            let response = self.client
+                .get(format!("{}/network/status", base_url))
+                .timeout(HEALTH_CHECK_TIMEOUT)
+                .send()
+                .await?;
+            if response.status() != StatusCode::OK {
+                anyhow::bail!("API returned status code: {}", response.status());
+            }
+            Ok(())
+        });
+        let results = futures::future::join_all(checks).await;
+
+        let now = Instant::now();
+        let mut failures = Vec::new();
+        let mut selected = None;
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(()) => {
+                    selected.get_or_insert(index);
+                }
+                Err(e) => {
+                    self.endpoints.mark_down(index, now);
+                    failures.push(format!("{}: {}", self.endpoints.url(index), e));
+                }
+            }
         }
-        
-        Ok(())
+        match selected {
+            Some(index) => {
+                self.endpoints.mark_up(index);
+                Ok(())
+            }
+            None => anyhow::bail!("No API endpoint is reachable:\n  {}", failures.join("\n  ")),
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<Response> {
+        self.send(Method::GET, path, None).await
+    }
+
+    async fn post(&self, path: &str, body: Option<&serde_json::Value>) -> Result<Response> {
+        self.send(Method::POST, path, body).await
+    }
+
+    /// Send a request, failing over to the other endpoints
+    async fn send(&self, method: Method, path: &str, body: Option<&serde_json::Value>) -> Result<Response> {
+        let idempotent = method == Method::GET;
+        let mut last_error = None;
+        for index in self.endpoints.candidates(Instant::now()) {
+            let url = format!("{}{}", self.endpoints.url(index), path);
+            let mut request = self.client.request(method.clone(), &url);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            match request.send().await {
+                Ok(response) if idempotent && is_unavailable(response.status()) => {
+                    self.endpoints.mark_down(index, Instant::now());
+                    last_error = Some(anyhow!("{} returned status code: {}", url, response.status()));
+                }
+                Ok(response) => {
+                    self.endpoints.mark_up(index);
+                    return Ok(response);
+                }
+                Err(e) if e.is_connect() || (idempotent && e.is_timeout()) => {
+                    self.endpoints.mark_down(index, Instant::now());
+                    last_error = Some(anyhow::Error::from(e).context(format!("Request to {} failed", url)));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No API endpoint is configured")))
+            .context("All API endpoints failed")
     }
     
     /// Get network status
     pub async fn get_network_status(&self) -> Result<NetworkStatus> {
-        let url = format!("{}/network/status", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let path = "/network/status";
+        let response = self.get(path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -57,8 +135,8 @@ impl ApiClient {
     
     /// Get node stats
     pub async fn get_node_stats(&self) -> Result<NodeStats> {
-        let url = format!("{}/system/stats", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let path = "/system/stats";
+        let response = self.get(path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -72,8 +150,8 @@ impl ApiClient {
     
     /// Get block by number or hash
     pub async fn get_block(&self, id: &str) -> Result<Block> {
-        let url = format!("{}/blocks/{}", self.base_url, id);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/blocks/{}", id);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -87,8 +165,8 @@ impl ApiClient {
     
     /// Get latest block
     pub async fn get_latest_block(&self) -> Result<Block> {
-        let url = format!("{}/blocks/latest", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let path = "/blocks/latest";
+        let response = self.get(path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -102,8 +180,8 @@ impl ApiClient {
     
     /// Get blocks
     pub async fn get_blocks(&self, limit: usize, offset: usize) -> Result<Vec<Block>> {
-        let url = format!("{}/blocks?limit={}&offset={}", self.base_url, limit, offset);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/blocks?limit={}&offset={}", limit, offset);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -117,8 +195,8 @@ impl ApiClient {
     
     /// Get transaction by ID
     pub async fn get_transaction(&self, id: &str) -> Result<Transaction> {
-        let url = format!("{}/transactions/{}", self.base_url, id);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/transactions/{}", id);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -132,8 +210,8 @@ impl ApiClient {
     
     /// Get transactions
     pub async fn get_transactions(&self, limit: usize, offset: usize) -> Result<Vec<Transaction>> {
-        let url = format!("{}/transactions?limit={}&offset={}", self.base_url, limit, offset);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/transactions?limit={}&offset={}", limit, offset);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -147,14 +225,14 @@ impl ApiClient {
     
     /// Create transaction
     pub async fn create_transaction(&self, from: &str, to: &str, value: f64) -> Result<Transaction> {
-        let url = format!("{}/transactions", self.base_url);
+        let path = "/transactions";
         let payload = json!({
             "from": from,
             "to": to,
             "value": value
         });
         
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self.post(path, Some(&payload)).await?;
         
         if response.status() != StatusCode::OK && response.status() != StatusCode::CREATED {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -168,8 +246,8 @@ impl ApiClient {
     
     /// Get account by address
     pub async fn get_account(&self, address: &str) -> Result<Account> {
-        let url = format!("{}/accounts/{}", self.base_url, address);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/accounts/{}", address);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -183,8 +261,8 @@ impl ApiClient {
     
     /// Create account
     pub async fn create_account(&self) -> Result<Account> {
-        let url = format!("{}/accounts", self.base_url);
-        let response = self.client.post(&url).send().await?;
+        let path = "/accounts";
+        let response = self.post(path, None).await?;
         
         if response.status() != StatusCode::OK && response.status() != StatusCode::CREATED {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -198,8 +276,8 @@ impl ApiClient {
     
     /// Get accounts
     pub async fn get_accounts(&self, limit: usize, offset: usize) -> Result<Vec<Account>> {
-        let url = format!("{}/accounts?limit={}&offset={}", self.base_url, limit, offset);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/accounts?limit={}&offset={}", limit, offset);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -213,8 +291,8 @@ impl ApiClient {
     
     /// Get contract by address
     pub async fn get_contract(&self, address: &str) -> Result<Contract> {
-        let url = format!("{}/contracts/{}", self.base_url, address);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/contracts/{}", address);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -228,7 +306,7 @@ impl ApiClient {
     
     /// Deploy contract
     pub async fn deploy_contract(&self, from: &str, bytecode: &str, abi: Option<&str>) -> Result<Contract> {
-        let url = format!("{}/contracts", self.base_url);
+        let path = "/contracts";
         let payload = json!({
             "from": from,
             "bytecode": bytecode,
@@ -237,7 +315,7 @@ impl ApiClient {
             "gas_price": 10
         });
         
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self.post(path, Some(&payload)).await?;
         
         if response.status() != StatusCode::OK && response.status() != StatusCode::CREATED {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -251,7 +329,7 @@ impl ApiClient {
     
     /// Call contract
     pub async fn call_contract(&self, address: &str, from: &str, method: &str, args: Option<&str>) -> Result<String> {
-        let url = format!("{}/contracts/{}/call", self.base_url, address);
+        let path = format!("/contracts/{}/call", address);
         let payload = json!({
             "from": from,
             "method": method,
@@ -261,7 +339,7 @@ impl ApiClient {
             "value": 0
         });
         
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self.post(&path, Some(&payload)).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -273,10 +351,24 @@ impl ApiClient {
         Ok(result)
     }
     
+    /// Get upgrade history of a proxy or implementation address
+    pub async fn get_upgrade_history(&self, address: &str) -> Result<ProxyRecord> {
+        let path = format!("/contracts/{}/upgrades", address);
+        let response = self.get(&path).await?;
+        
+        if response.status() != StatusCode::OK {
+            anyhow::bail!("API returned status code: {}", response.status());
+        }
+        
+        let record = response.json::<ProxyRecord>().await?;
+        
+        Ok(record)
+    }
+    
     /// Get contracts
     pub async fn get_contracts(&self, limit: usize, offset: usize) -> Result<Vec<Contract>> {
-        let url = format!("{}/contracts?limit={}&offset={}", self.base_url, limit, offset);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/contracts?limit={}&offset={}", limit, offset);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -290,7 +382,7 @@ impl ApiClient {
     
     /// Create token
     pub async fn create_token(&self, from: &str, name: &str, symbol: &str, token_type: &str, supply: Option<u64>) -> Result<Token> {
-        let url = format!("{}/contracts/token/create", self.base_url);
+        let path = "/contracts/token/create";
         let payload = json!({
             "from": from,
             "name": name,
@@ -301,7 +393,7 @@ impl ApiClient {
             "gas_price": 10
         });
         
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self.post(path, Some(&payload)).await?;
         
         if response.status() != StatusCode::OK && response.status() != StatusCode::CREATED {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -315,8 +407,8 @@ impl ApiClient {
     
     /// Get tokens
     pub async fn get_tokens(&self, limit: usize, offset: usize) -> Result<Vec<Token>> {
-        let url = format!("{}/tokens?limit={}&offset={}", self.base_url, limit, offset);
-        let response = self.client.get(&url).send().await?;
+        let path = format!("/tokens?limit={}&offset={}", limit, offset);
+        let response = self.get(&path).await?;
         
         if response.status() != StatusCode::OK {
             anyhow::bail!("API returned status code: {}", response.status());
@@ -327,4 +419,9 @@ impl ApiClient {
         
         Ok(tokens)
     }
-}
\ No newline at end of file
+}
+
+/// Whether the endpoint (or the load balancer in front of it) cannot serve requests right now
+fn is_unavailable(status: StatusCode) -> bool {
+    matches!(status, StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT)
+}
@@ -148,4 +148,37 @@ pub struct Token {
     pub creator: String,
     /// Creation timestamp
     pub created_at: String,
-}
\ No newline at end of file
+}
+/// Contract upgrade event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeEvent {
+    /// Version after the upgrade
+    pub version: u64,
+    /// Previous implementation address
+    pub previous_implementation: Option<String>,
+    /// New implementation address
+    pub implementation: String,
+    /// Sender of the upgrade transaction
+    pub sender: String,
+    /// Governance approvals
+    pub approvals: Vec<String>,
+    /// Upgrade timestamp (UNIX seconds)
+    pub timestamp: u64,
+}
+
+/// Upgradeable contract proxy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyRecord {
+    /// Logical contract ID
+    pub id: String,
+    /// Proxy address
+    pub proxy_address: String,
+    /// Current implementation address
+    pub implementation: String,
+    /// Upgrade authority
+    pub authority: serde_json::Value,
+    /// Current version
+    pub version: u64,
+    /// Upgrade history (oldest first)
+    pub history: Vec<UpgradeEvent>,
+}
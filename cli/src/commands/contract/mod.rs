@@ -0,0 +1,242 @@
+use crate::app::App;
+use clap::Subcommand;
+use colored::*;
+use prettytable::{format, Table};
+
+#[derive(Subcommand)]
+pub enum ContractCommands {
+    /// Get contract by address
+    Get {
+        /// Contract address
+        address: String,
+    },
+
+    /// List contracts
+    List {
+        /// Number of contracts to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+
+        /// Offset for pagination
+        #[arg(short, long, default_value = "0")]
+        offset: usize,
+    },
+
+    /// Deploy a contract
+    Deploy {
+        /// Deployer address
+        #[arg(short, long)]
+        from: String,
+
+        /// Contract bytecode (hex)
+        bytecode: String,
+
+        /// Contract ABI (JSON)
+        #[arg(long)]
+        abi: Option<String>,
+    },
+
+    /// Call a contract method
+    Call {
+        /// Contract address
+        address: String,
+
+        /// Method name
+        method: String,
+
+        /// Caller address
+        #[arg(short, long)]
+        from: String,
+
+        /// Method arguments (JSON)
+        #[arg(long)]
+        args: Option<String>,
+    },
+
+    /// Show upgrade history of a proxy or implementation address
+    Upgrades {
+        /// Proxy or implementation address
+        address: String,
+    },
+}
+
+/// Handle contract commands
+pub async fn handle_command(app: &mut App, command: ContractCommands) -> anyhow::Result<()> {
+    match command {
+        ContractCommands::Get { address } => {
+            let contract = app.api_client.get_contract(&address).await?;
+            print_contract_details(&contract);
+        }
+        ContractCommands::List { limit, offset } => {
+            let contracts = app.api_client.get_contracts(limit, offset).await?;
+            print_contract_list(&contracts);
+        }
+        ContractCommands::Deploy { from, bytecode, abi } => {
+            let contract = app.api_client.deploy_contract(&from, &bytecode, abi.as_deref()).await?;
+            println!("Contract deployed successfully:");
+            print_contract_details(&contract);
+        }
+        ContractCommands::Call { address, method, from, args } => {
+            let result = app.api_client.call_contract(&address, &from, &method, args.as_deref()).await?;
+            println!("Result: {}", result.green());
+        }
+        ContractCommands::Upgrades { address } => {
+            let record = app.api_client.get_upgrade_history(&address).await?;
+            print_upgrade_history(&record);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle contract shell commands
+pub async fn handle_shell_command(app: &mut App, args: &[&str]) -> anyhow::Result<()> {
+    if args.is_empty() {
+        display_help();
+        return Ok(());
+    }
+
+    match args[0] {
+        "get" => {
+            if args.len() < 2 {
+                println!("Usage: contract get <address>");
+                return Ok(());
+            }
+
+            let contract = app.api_client.get_contract(args[1]).await?;
+            print_contract_details(&contract);
+        }
+        "list" => {
+            let limit = args.get(1).and_then(|s| s.parse::<usize>().ok()).unwrap_or(10);
+            let offset = args.get(2).and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+
+            let contracts = app.api_client.get_contracts(limit, offset).await?;
+            print_contract_list(&contracts);
+        }
+        "deploy" => {
+            let Some(from) = app.current_account.clone() else {
+                println!("No current account. Use 'account use <address>' first.");
+                return Ok(());
+            };
+            if args.len() < 2 {
+                println!("Usage: contract deploy <bytecode> [abi]");
+                return Ok(());
+            }
+
+            let contract = app.api_client.deploy_contract(&from, args[1], args.get(2).copied()).await?;
+            println!("Contract deployed successfully:");
+            print_contract_details(&contract);
+        }
+        "call" => {
+            let Some(from) = app.current_account.clone() else {
+                println!("No current account. Use 'account use <address>' first.");
+                return Ok(());
+            };
+            if args.len() < 3 {
+                println!("Usage: contract call <address> <method> [args]");
+                return Ok(());
+            }
+
+            let result = app.api_client.call_contract(args[1], &from, args[2], args.get(3).copied()).await?;
+            println!("Result: {}", result.green());
+        }
+        "upgrades" => {
+            if args.len() < 2 {
+                println!("Usage: contract upgrades <address>");
+                return Ok(());
+            }
+
+            let record = app.api_client.get_upgrade_history(args[1]).await?;
+            print_upgrade_history(&record);
+        }
+        "help" => {
+            display_help();
+        }
+        _ => {
+            println!("Unknown contract command: {}", args[0]);
+            display_help();
+        }
+    }
+
+    Ok(())
+}
+
+/// Display help for contract commands
+pub fn display_help() {
+    println!("Contract commands:");
+    println!("  {} <address>  - Get contract by address", "get".cyan());
+    println!("  {} [limit] [offset] - List contracts", "list".cyan());
+    println!("  {} <bytecode> [abi] - Deploy a contract from the current account", "deploy".cyan());
+    println!("  {} <address> <method> [args] - Call a contract method", "call".cyan());
+    println!("  {} <address> - Show upgrade history of a proxy or implementation", "upgrades".cyan());
+    println!("  {}         - Display this help", "help".cyan());
+}
+
+/// Print contract details
+fn print_contract_details(contract: &crate::api::models::Contract) {
+    println!("Address: {}", contract.address.green());
+    println!("Creator: {}", contract.creator);
+    println!("Creation Tx: {}", contract.creation_transaction);
+
+    if let Some(block) = contract.creation_block {
+        println!("Creation Block: {}", block);
+    }
+
+    println!("Created At: {}", contract.created_at);
+    println!("Last Activity: {}", contract.last_activity);
+    println!("Bytecode: {} bytes", contract.bytecode.trim_start_matches("0x").len() / 2);
+}
+
+/// Print contract list
+fn print_contract_list(contracts: &[crate::api::models::Contract]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.set_titles(row![
+        "Address".cyan().bold(),
+        "Creator".cyan().bold(),
+        "Created At".cyan().bold()
+    ]);
+
+    for contract in contracts {
+        table.add_row(row![
+            contract.address,
+            contract.creator,
+            contract.created_at
+        ]);
+    }
+
+    table.printstd();
+}
+
+/// Print upgrade history
+fn print_upgrade_history(record: &crate::api::models::ProxyRecord) {
+    println!("Proxy: {} ({})", record.id.yellow(), record.proxy_address.green());
+    println!("Implementation: {}", record.implementation.cyan());
+    println!("Version: {}", record.version);
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    table.set_titles(row![
+        "Version".cyan().bold(),
+        "From".cyan().bold(),
+        "To".cyan().bold(),
+        "Sender".cyan().bold(),
+        "Approvals".cyan().bold(),
+        "Time".cyan().bold()
+    ]);
+
+    for event in &record.history {
+        table.add_row(row![
+            event.version,
+            event.previous_implementation.as_deref().unwrap_or("-"),
+            event.implementation,
+            event.sender,
+            event.approvals.len(),
+            event.timestamp
+        ]);
+    }
+
+    table.printstd();
+}
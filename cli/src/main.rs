@@ -13,9 +13,9 @@ use std::process;
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// API endpoint URL
-    #[arg(short, long, default_value = "http://localhost:50128")]
-    api_url: String,
+    /// API endpoint URL (repeat the flag or separate with commas to fail over between endpoints, in priority order)
+    #[arg(short, long, default_value = "http://localhost:50128", value_delimiter = ',')]
+    api_url: Vec<String>,
 
     /// Enable debug mode
     #[arg(short, long)]
@@ -95,13 +95,13 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     
     // Set up API client
-    let api_client = api::ApiClient::new(&cli.api_url);
+    let api_client = api::ApiClient::new(&cli.api_url)?;
     
-    // Check if API is reachable
+    // Check which endpoints are reachable and select the first healthy one
     match api_client.check_connection().await {
         Ok(_) => {
             if cli.debug {
-                println!("{}", "API connection successful".green());
+                println!("{} ({})", "API connection successful".green(), api_client.current_endpoint());
             }
         }
         Err(e) => {
@@ -7,6 +7,9 @@ use gluon_consensus::{Node as GluonNode, Config as GluonConfig};
 use tendermint::{Node as TendermintNode, Config as TendermintConfig};
 use tracing::{info, warn, error};
 
+#[cfg(feature = "sim")]
+pub mod sim;
+
 /// コンセンサスエンジン
 pub struct ConsensusEngine {
     gluon: GluonNode,
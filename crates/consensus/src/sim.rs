@@ -0,0 +1,515 @@
+//! 決定論的シミュレーション
+//!
+//! コンセンサス実装を仮想時間上で実行し、不変条件を検証します。
+//! 主な機能：
+//! - シード固定の乱数による再現可能な実行
+//! - 仮想クロックとイベントキュー
+//! - スクリプト化されたメッセージ遅延・ドロップ・ネットワーク分断
+//! - 安全性（同一高さで異なる決定がない）とGST後の活性の検証
+//!
+//! `cargo test -p rustorium-consensus --features sim` で実行できます。
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Debug;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use thiserror::Error;
+
+/// シミュレーション上のノードID
+pub type SimNodeId = usize;
+
+/// 仮想時間（ミリ秒）
+pub type VirtualTime = u64;
+
+/// シミュレーション対象のプロトコル実装
+pub trait SimNode {
+    type Message: Clone + Debug;
+
+    /// シミュレーション開始時に呼ばれる
+    fn on_start(&mut self, ctx: &mut SimContext<Self::Message>);
+
+    /// メッセージ受信時に呼ばれる
+    fn on_message(&mut self, from: SimNodeId, msg: Self::Message, ctx: &mut SimContext<Self::Message>);
+
+    /// タイマー発火時に呼ばれる
+    fn on_timer(&mut self, timer_id: u64, ctx: &mut SimContext<Self::Message>);
+
+    /// 決定済みの値（高さ, 値）
+    fn decisions(&self) -> &[(u64, Vec<u8>)];
+}
+
+/// ノードから見たシミュレーション環境
+pub struct SimContext<M> {
+    node: SimNodeId,
+    now: VirtualTime,
+    node_count: usize,
+    outbox: Vec<(SimNodeId, M)>,
+    timers: Vec<(VirtualTime, u64)>,
+}
+
+impl<M: Clone> SimContext<M> {
+    /// 自ノードのID
+    pub fn id(&self) -> SimNodeId {
+        self.node
+    }
+
+    /// 現在の仮想時刻
+    pub fn now(&self) -> VirtualTime {
+        self.now
+    }
+
+    /// ノード総数
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// 特定ノードへ送信
+    pub fn send(&mut self, to: SimNodeId, msg: M) {
+        self.outbox.push((to, msg));
+    }
+
+    /// 自分以外の全ノードへ送信
+    pub fn broadcast(&mut self, msg: M) {
+        for to in 0..self.node_count {
+            if to != self.node {
+                self.outbox.push((to, msg.clone()));
+            }
+        }
+    }
+
+    /// タイマーを設定
+    pub fn set_timer(&mut self, delay: VirtualTime, timer_id: u64) {
+        self.timers.push((self.now + delay, timer_id));
+    }
+}
+
+/// スクリプト化された障害
+#[derive(Debug, Clone)]
+pub enum ScriptedFault {
+    /// 指定区間のメッセージをドロップ
+    Drop {
+        from: Option<SimNodeId>,
+        to: Option<SimNodeId>,
+        start: VirtualTime,
+        end: VirtualTime,
+    },
+    /// 指定区間のメッセージに追加遅延
+    Delay {
+        from: Option<SimNodeId>,
+        to: Option<SimNodeId>,
+        extra: VirtualTime,
+        start: VirtualTime,
+        end: VirtualTime,
+    },
+    /// 指定区間、グループ内外の通信を遮断
+    Partition {
+        group: Vec<SimNodeId>,
+        start: VirtualTime,
+        end: VirtualTime,
+    },
+}
+
+impl ScriptedFault {
+    fn matches(from: Option<SimNodeId>, to: Option<SimNodeId>, src: SimNodeId, dst: SimNodeId) -> bool {
+        from.map_or(true, |f| f == src) && to.map_or(true, |t| t == dst)
+    }
+
+    fn drops(&self, src: SimNodeId, dst: SimNodeId, now: VirtualTime) -> bool {
+        match self {
+            Self::Drop { from, to, start, end } => {
+                (*start..*end).contains(&now) && Self::matches(*from, *to, src, dst)
+            }
+            Self::Partition { group, start, end } => {
+                (*start..*end).contains(&now) && (group.contains(&src) != group.contains(&dst))
+            }
+            Self::Delay { .. } => false,
+        }
+    }
+
+    fn extra_delay(&self, src: SimNodeId, dst: SimNodeId, now: VirtualTime) -> VirtualTime {
+        match self {
+            Self::Delay { from, to, extra, start, end }
+                if (*start..*end).contains(&now) && Self::matches(*from, *to, src, dst) => *extra,
+            _ => 0,
+        }
+    }
+}
+
+/// シミュレーション設定
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    /// 乱数シード
+    pub seed: u64,
+    /// Global Stabilization Time。以降はランダムなドロップが発生しない
+    pub gst: VirtualTime,
+    /// 最小遅延
+    pub min_delay: VirtualTime,
+    /// 最大遅延（GST後はこの値が遅延の上限）
+    pub max_delay: VirtualTime,
+    /// GST前のランダムドロップ率
+    pub drop_rate_before_gst: f64,
+    /// シミュレーション打ち切り時刻
+    pub max_time: VirtualTime,
+    /// スクリプト化された障害
+    pub faults: Vec<ScriptedFault>,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            gst: 0,
+            min_delay: 1,
+            max_delay: 50,
+            drop_rate_before_gst: 0.0,
+            max_time: 60_000,
+            faults: Vec::new(),
+        }
+    }
+}
+
+/// 不変条件違反
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    #[error("安全性違反: 高さ{height}でノード{a}とノード{b}の決定が異なります")]
+    Safety { height: u64, a: SimNodeId, b: SimNodeId },
+
+    #[error("活性違反: ノード{node}が時刻{deadline}までに高さ{height}を決定していません")]
+    Liveness { height: u64, node: SimNodeId, deadline: VirtualTime },
+}
+
+/// 実行トレースのエントリ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEntry {
+    Delivered { time: VirtualTime, from: SimNodeId, to: SimNodeId },
+    Dropped { time: VirtualTime, from: SimNodeId, to: SimNodeId },
+    Timer { time: VirtualTime, node: SimNodeId, timer_id: u64 },
+    Decided { time: VirtualTime, node: SimNodeId, height: u64 },
+}
+
+/// シミュレーション結果
+#[derive(Debug, Clone)]
+pub struct SimReport {
+    pub final_time: VirtualTime,
+    pub messages_delivered: u64,
+    pub messages_dropped: u64,
+    /// ノードごとの（高さ → 決定時刻）
+    pub decision_times: Vec<HashMap<u64, VirtualTime>>,
+    pub trace: Vec<TraceEntry>,
+}
+
+enum Event<M> {
+    Deliver { from: SimNodeId, to: SimNodeId, msg: M },
+    Timer { node: SimNodeId, timer_id: u64 },
+}
+
+/// 決定論的シミュレーター
+pub struct Simulation<N: SimNode> {
+    nodes: Vec<N>,
+    config: SimConfig,
+    rng: StdRng,
+    now: VirtualTime,
+    seq: u64,
+    queue: BinaryHeap<Reverse<(VirtualTime, u64)>>,
+    events: HashMap<u64, Event<N::Message>>,
+    report: SimReport,
+}
+
+impl<N: SimNode> Simulation<N> {
+    /// 新しいシミュレーションを作成
+    pub fn new(nodes: Vec<N>, config: SimConfig) -> Self {
+        let node_count = nodes.len();
+        Self {
+            nodes,
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            now: 0,
+            seq: 0,
+            queue: BinaryHeap::new(),
+            events: HashMap::new(),
+            report: SimReport {
+                final_time: 0,
+                messages_delivered: 0,
+                messages_dropped: 0,
+                decision_times: vec![HashMap::new(); node_count],
+                trace: Vec::new(),
+            },
+        }
+    }
+
+    /// ノードへの参照
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    /// `until` が真になるか、イベントが尽きるか、打ち切り時刻に達するまで実行
+    pub fn run_until(&mut self, mut until: impl FnMut(&[N]) -> bool) -> SimReport {
+        if self.now == 0 && self.seq == 0 {
+            for node in 0..self.nodes.len() {
+                self.dispatch(node, |n, ctx| n.on_start(ctx));
+            }
+        }
+
+        while let Some(Reverse((time, seq))) = self.queue.peek().copied() {
+            if time > self.config.max_time || until(&self.nodes) {
+                break;
+            }
+            self.queue.pop();
+            self.now = time;
+            let Some(event) = self.events.remove(&seq) else {
+                continue;
+            };
+
+            match event {
+                Event::Deliver { from, to, msg } => {
+                    self.report.messages_delivered += 1;
+                    self.report.trace.push(TraceEntry::Delivered { time, from, to });
+                    self.dispatch(to, |n, ctx| n.on_message(from, msg, ctx));
+                }
+                Event::Timer { node, timer_id } => {
+                    self.report.trace.push(TraceEntry::Timer { time, node, timer_id });
+                    self.dispatch(node, |n, ctx| n.on_timer(timer_id, ctx));
+                }
+            }
+        }
+
+        self.report.final_time = self.now;
+        self.report.clone()
+    }
+
+    /// 打ち切り時刻まで実行
+    pub fn run(&mut self) -> SimReport {
+        self.run_until(|_| false)
+    }
+
+    /// 安全性の検証: 同じ高さで異なる値を決定したノードがないこと
+    pub fn check_safety(&self) -> Result<(), InvariantViolation> {
+        let mut decided: HashMap<u64, (SimNodeId, &Vec<u8>)> = HashMap::new();
+        for (node, n) in self.nodes.iter().enumerate() {
+            for (height, value) in n.decisions() {
+                match decided.get(height) {
+                    Some((other, v)) if *v != value => {
+                        return Err(InvariantViolation::Safety { height: *height, a: *other, b: node });
+                    }
+                    Some(_) => {}
+                    None => {
+                        decided.insert(*height, (node, value));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// GST後の活性の検証: 全ノードが `gst + bound` までに `height` を決定していること
+    pub fn check_liveness(&self, height: u64, bound: VirtualTime) -> Result<(), InvariantViolation> {
+        let deadline = self.config.gst + bound;
+        for (node, times) in self.report.decision_times.iter().enumerate() {
+            match times.get(&height) {
+                Some(t) if *t <= deadline => {}
+                _ => return Err(InvariantViolation::Liveness { height, node, deadline }),
+            }
+        }
+        Ok(())
+    }
+
+    fn dispatch(&mut self, node: SimNodeId, f: impl FnOnce(&mut N, &mut SimContext<N::Message>)) {
+        let mut ctx = SimContext {
+            node,
+            now: self.now,
+            node_count: self.nodes.len(),
+            outbox: Vec::new(),
+            timers: Vec::new(),
+        };
+        let before = self.nodes[node].decisions().len();
+        f(&mut self.nodes[node], &mut ctx);
+
+        // 新しい決定を記録
+        let new_heights: Vec<u64> = self.nodes[node].decisions()[before..]
+            .iter()
+            .map(|(h, _)| *h)
+            .collect();
+        for height in new_heights {
+            self.report.decision_times[node].entry(height).or_insert(self.now);
+            self.report.trace.push(TraceEntry::Decided { time: self.now, node, height });
+        }
+
+        for (at, timer_id) in ctx.timers {
+            self.schedule(at, Event::Timer { node, timer_id });
+        }
+        for (to, msg) in ctx.outbox {
+            self.send(node, to, msg);
+        }
+    }
+
+    fn send(&mut self, from: SimNodeId, to: SimNodeId, msg: N::Message) {
+        let now = self.now;
+        let scripted_drop = self.config.faults.iter().any(|f| f.drops(from, to, now));
+        let random_drop = now < self.config.gst
+            && self.rng.gen_bool(self.config.drop_rate_before_gst.clamp(0.0, 1.0));
+        if scripted_drop || random_drop {
+            self.report.messages_dropped += 1;
+            self.report.trace.push(TraceEntry::Dropped { time: now, from, to });
+            return;
+        }
+
+        let base = self.rng.gen_range(self.config.min_delay..=self.config.max_delay.max(self.config.min_delay));
+        let extra: VirtualTime = self.config.faults.iter().map(|f| f.extra_delay(from, to, now)).sum();
+        self.schedule(now + base + extra, Event::Deliver { from, to, msg });
+    }
+
+    fn schedule(&mut self, at: VirtualTime, event: Event<N::Message>) {
+        let seq = self.seq;
+        self.seq += 1;
+        self.events.insert(seq, event);
+        self.queue.push(Reverse((at, seq)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用の単純な投票プロトコル
+    /// ノード0が提案し、2f+1票を集めたノードが決定する
+    #[derive(Debug, Clone)]
+    enum Msg {
+        Propose(Vec<u8>),
+        Vote(Vec<u8>),
+    }
+
+    struct VoteNode {
+        proposal: Option<Vec<u8>>,
+        votes: HashMap<SimNodeId, Vec<u8>>,
+        decided: Vec<(u64, Vec<u8>)>,
+    }
+
+    impl VoteNode {
+        fn new() -> Self {
+            Self { proposal: None, votes: HashMap::new(), decided: Vec::new() }
+        }
+
+        fn quorum(ctx: &SimContext<Msg>) -> usize {
+            let f = (ctx.node_count() - 1) / 3;
+            2 * f + 1
+        }
+
+        fn vote(&mut self, value: Vec<u8>, ctx: &mut SimContext<Msg>) {
+            self.votes.insert(ctx.id(), value.clone());
+            ctx.broadcast(Msg::Vote(value));
+            self.try_decide(ctx);
+        }
+
+        fn try_decide(&mut self, ctx: &SimContext<Msg>) {
+            if !self.decided.is_empty() {
+                return;
+            }
+            if let Some(value) = &self.proposal {
+                let count = self.votes.values().filter(|v| *v == value).count();
+                if count >= Self::quorum(ctx) {
+                    self.decided.push((1, value.clone()));
+                }
+            }
+        }
+    }
+
+    impl SimNode for VoteNode {
+        type Message = Msg;
+
+        fn on_start(&mut self, ctx: &mut SimContext<Msg>) {
+            if ctx.id() == 0 {
+                let value = b"block-1".to_vec();
+                self.proposal = Some(value.clone());
+                ctx.broadcast(Msg::Propose(value.clone()));
+                self.vote(value, ctx);
+            }
+            ctx.set_timer(100, 0);
+        }
+
+        fn on_message(&mut self, from: SimNodeId, msg: Msg, ctx: &mut SimContext<Msg>) {
+            match msg {
+                Msg::Propose(value) => {
+                    if self.proposal.is_none() {
+                        self.proposal = Some(value.clone());
+                        self.vote(value, ctx);
+                    }
+                }
+                Msg::Vote(value) => {
+                    self.votes.insert(from, value);
+                    self.try_decide(ctx);
+                }
+            }
+        }
+
+        fn on_timer(&mut self, _timer_id: u64, ctx: &mut SimContext<Msg>) {
+            // 定期的に再送してドロップから回復する
+            if let Some(value) = self.proposal.clone() {
+                if ctx.id() == 0 {
+                    ctx.broadcast(Msg::Propose(value.clone()));
+                }
+                ctx.broadcast(Msg::Vote(value));
+            }
+            ctx.set_timer(100, 0);
+        }
+
+        fn decisions(&self) -> &[(u64, Vec<u8>)] {
+            &self.decided
+        }
+    }
+
+    fn nodes(n: usize) -> Vec<VoteNode> {
+        (0..n).map(|_| VoteNode::new()).collect()
+    }
+
+    fn all_decided(nodes: &[VoteNode]) -> bool {
+        nodes.iter().all(|n| !n.decided.is_empty())
+    }
+
+    #[test]
+    fn test_same_seed_same_trace() {
+        let config = SimConfig { seed: 42, gst: 500, drop_rate_before_gst: 0.3, ..Default::default() };
+
+        let mut a = Simulation::new(nodes(4), config.clone());
+        let mut b = Simulation::new(nodes(4), config);
+        let ra = a.run_until(all_decided);
+        let rb = b.run_until(all_decided);
+
+        assert_eq!(ra.trace, rb.trace);
+        assert_eq!(ra.final_time, rb.final_time);
+    }
+
+    #[test]
+    fn test_safety_and_liveness_after_gst() {
+        for seed in 0..20 {
+            let config = SimConfig {
+                seed,
+                gst: 1_000,
+                drop_rate_before_gst: 0.5,
+                ..Default::default()
+            };
+            let mut sim = Simulation::new(nodes(4), config);
+            sim.run_until(all_decided);
+
+            sim.check_safety().unwrap();
+            sim.check_liveness(1, 500).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_partition_blocks_liveness() {
+        let config = SimConfig {
+            seed: 7,
+            max_time: 2_000,
+            faults: vec![ScriptedFault::Partition { group: vec![0, 1], start: 0, end: 10_000 }],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(nodes(4), config);
+        sim.run();
+
+        sim.check_safety().unwrap();
+        assert!(matches!(
+            sim.check_liveness(1, 1_000),
+            Err(InvariantViolation::Liveness { .. })
+        ));
+    }
+}
@@ -0,0 +1,390 @@
+//! Rustorium WASMコントラクトの手続きマクロ
+//!
+//! `rustorium-contract` から再エクスポートして使います。直接依存する必要はありません。
+//!
+//! `#[contract]` を付けた `impl` ブロックの `#[instantiate]`・`#[execute]`・`#[query]` メソッドから、
+//! 次のものを生成します。
+//! - メソッドごとの引数の構造体（JSONのオブジェクトから読み込む）
+//! - メソッド名で呼び分ける `rustorium_contract::Contract` の実装
+//! - メソッドと引数・戻り値の型を記述したスキーマ（JSON）
+//! - WASMのエントリポイント（`instantiate`・`execute`・`query`・`schema`）
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Error, FnArg, ImplItem, ImplItemFn, ItemImpl, Pat, ReturnType, Type};
+
+/// コントラクトの `impl` ブロックに付けます
+///
+/// 対象の型は `serde` で直列化できる必要があります（状態として保存されます）。
+/// `#[instantiate]` がない場合は `Default` で初期化します。
+#[proc_macro_attribute]
+pub fn contract(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return Error::new(Span::call_site(), "#[contract] does not take arguments")
+            .to_compile_error()
+            .into();
+    }
+    let mut item = parse_macro_input!(item as ItemImpl);
+    match expand(&mut item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// 状態を変更するメソッド（`&mut self`）
+#[proc_macro_attribute]
+pub fn execute(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    outside_contract("execute", item)
+}
+
+/// 状態を読み取るメソッド（`&self`）
+#[proc_macro_attribute]
+pub fn query(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    outside_contract("query", item)
+}
+
+/// 初期化するメソッド（`self` を取らず、`Self` か `Result<Self, E>` を返す）
+#[proc_macro_attribute]
+pub fn instantiate(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    outside_contract("instantiate", item)
+}
+
+/// `#[contract]` が取り除く前に展開された目印はブロックの外で使われている
+fn outside_contract(name: &str, item: TokenStream) -> TokenStream {
+    let item = TokenStream2::from(item);
+    let error = Error::new(
+        Span::call_site(),
+        format!("#[{}] must be used on a method inside a #[contract] impl block", name),
+    )
+    .to_compile_error();
+    quote!(#error #item).into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Instantiate,
+    Execute,
+    Query,
+}
+
+impl Kind {
+    fn from_ident(ident: &str) -> Option<Self> {
+        match ident {
+            "instantiate" => Some(Self::Instantiate),
+            "execute" => Some(Self::Execute),
+            "query" => Some(Self::Query),
+            _ => None,
+        }
+    }
+}
+
+/// エントリポイントとして公開するメソッド
+struct Method {
+    kind: Kind,
+    ident: syn::Ident,
+    args: Vec<(syn::Ident, Type)>,
+    output: Option<Type>,
+}
+
+impl Method {
+    /// 戻り値が `Result` か（型の最後の要素の名前で判定する）
+    fn returns_result(&self) -> bool {
+        matches!(&self.output, Some(Type::Path(path))
+            if path.path.segments.last().is_some_and(|segment| segment.ident == "Result"))
+    }
+
+    /// エラーの型が `ContractError` か（そのまま返し、取り消しの理由で包まない）
+    fn fails_with_contract_error(&self) -> bool {
+        let Some(Type::Path(path)) = &self.output else {
+            return false;
+        };
+        let Some(syn::PathArguments::AngleBracketed(generics)) = path.path.segments.last().map(|s| &s.arguments) else {
+            return false;
+        };
+        matches!(generics.args.iter().nth(1), Some(syn::GenericArgument::Type(Type::Path(error)))
+            if error.path.segments.last().is_some_and(|segment| segment.ident == "ContractError"))
+    }
+
+    fn args_struct(&self) -> syn::Ident {
+        format_ident!("__{}_args", self.ident)
+    }
+}
+
+fn expand(item: &mut ItemImpl) -> syn::Result<TokenStream2> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(Error::new(path.span(), "#[contract] must be used on an inherent impl block"));
+    }
+    if !item.generics.params.is_empty() {
+        return Err(Error::new(item.generics.span(), "#[contract] does not support generic contracts"));
+    }
+    let contract_name = match &*item.self_ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+    .ok_or_else(|| Error::new(item.self_ty.span(), "#[contract] must be used on a named type"))?;
+
+    let mut methods = Vec::new();
+    for impl_item in &mut item.items {
+        if let ImplItem::Fn(function) = impl_item {
+            if let Some(method) = take_method(function)? {
+                methods.push(method);
+            }
+        }
+    }
+
+    let instantiates: Vec<&Method> = methods.iter().filter(|m| m.kind == Kind::Instantiate).collect();
+    if let Some(extra) = instantiates.get(1) {
+        return Err(Error::new(extra.ident.span(), "a contract can have only one #[instantiate] method"));
+    }
+    let instantiate = instantiates.first().copied();
+
+    let self_ty = &item.self_ty;
+    let args_structs = methods.iter().map(args_struct);
+    let instantiate_body = match instantiate {
+        Some(method) => {
+            let args = method.args_struct();
+            let ident = &method.ident;
+            let names = method.args.iter().map(|(name, _)| name);
+            let call = quote!(<#self_ty>::#ident(#(__args.#names),*));
+            let result = if method.returns_result() && method.fails_with_contract_error() {
+                call
+            } else if method.returns_result() {
+                quote!(__rt::revert(#call))
+            } else {
+                quote!(::core::result::Result::Ok(#call))
+            };
+            quote! {
+                let __args: #args = __rt::decode_args(input)?;
+                #result
+            }
+        }
+        None => quote! {
+            __rt::no_args(input)?;
+            ::core::result::Result::Ok(<Self as ::core::default::Default>::default())
+        },
+    };
+    let execute_arms = dispatch_arms(&methods, Kind::Execute);
+    let query_arms = dispatch_arms(&methods, Kind::Query);
+    let schema = schema(&contract_name, &methods);
+
+    Ok(quote! {
+        #item
+
+        const _: () = {
+            use ::rustorium_contract::__private as __rt;
+
+            #(#args_structs)*
+
+            impl ::rustorium_contract::Contract for #self_ty {
+                const SCHEMA: &'static str = #schema;
+
+                fn instantiate(input: &[u8]) -> ::core::result::Result<Self, ::rustorium_contract::ContractError> {
+                    #instantiate_body
+                }
+
+                fn execute(&mut self, input: &[u8]) -> ::core::result::Result<::std::vec::Vec<u8>, ::rustorium_contract::ContractError> {
+                    let __call = __rt::decode_call(input)?;
+                    match __call.method.as_str() {
+                        #(#execute_arms)*
+                        __other => ::core::result::Result::Err(__rt::unknown_method(__other)),
+                    }
+                }
+
+                fn query(&self, input: &[u8]) -> ::core::result::Result<::std::vec::Vec<u8>, ::rustorium_contract::ContractError> {
+                    let __call = __rt::decode_call(input)?;
+                    match __call.method.as_str() {
+                        #(#query_arms)*
+                        __other => ::core::result::Result::Err(__rt::unknown_method(__other)),
+                    }
+                }
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn instantiate() {
+                __rt::run_instantiate::<#self_ty>()
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn execute() {
+                __rt::run_execute::<#self_ty>()
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn query() {
+                __rt::run_query::<#self_ty>()
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn schema() {
+                __rt::run_schema::<#self_ty>()
+            }
+        };
+    })
+}
+
+/// 目印の属性を取り除き、検査したメソッドを返す
+fn take_method(function: &mut ImplItemFn) -> syn::Result<Option<Method>> {
+    let mut kind = None;
+    let mut error = None;
+    function.attrs.retain(|attr| {
+        let Some(found) = attr.path().segments.last().and_then(|s| Kind::from_ident(&s.ident.to_string())) else {
+            return true;
+        };
+        if kind.is_some() {
+            error = Some(Error::new(attr.span(), "a method can have only one of #[instantiate], #[execute] and #[query]"));
+        }
+        kind = Some(found);
+        false
+    });
+    if let Some(error) = error {
+        return Err(error);
+    }
+    let Some(kind) = kind else {
+        return Ok(None);
+    };
+
+    let sig = &function.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new(sig.generics.span(), "contract methods cannot be generic"));
+    }
+    if let Some(token) = &sig.asyncness {
+        return Err(Error::new(token.span(), "contract methods cannot be async"));
+    }
+
+    let receiver = sig.receiver();
+    match (kind, receiver) {
+        (Kind::Instantiate, Some(receiver)) => {
+            return Err(Error::new(receiver.span(), "#[instantiate] methods cannot take self"));
+        }
+        (Kind::Execute, receiver) if !receiver.is_some_and(|r| r.reference.is_some() && r.mutability.is_some()) => {
+            return Err(Error::new(sig.ident.span(), "#[execute] methods must take &mut self"));
+        }
+        (Kind::Query, receiver) if !receiver.is_some_and(|r| r.reference.is_some() && r.mutability.is_none()) => {
+            return Err(Error::new(sig.ident.span(), "#[query] methods must take &self"));
+        }
+        _ => {}
+    }
+
+    let mut args = Vec::new();
+    for input in &sig.inputs {
+        let FnArg::Typed(typed) = input else {
+            continue;
+        };
+        let Pat::Ident(pat) = &*typed.pat else {
+            return Err(Error::new(typed.pat.span(), "contract method arguments must be plain identifiers"));
+        };
+        if matches!(&*typed.ty, Type::Reference(_) | Type::ImplTrait(_)) {
+            return Err(Error::new(typed.ty.span(), "contract method arguments must be owned types"));
+        }
+        args.push((pat.ident.clone(), (*typed.ty).clone()));
+    }
+
+    let output = match &sig.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some((**ty).clone()),
+    };
+    if kind == Kind::Instantiate && output.is_none() {
+        return Err(Error::new(sig.ident.span(), "#[instantiate] methods must return Self or Result<Self, E>"));
+    }
+    Ok(Some(Method { kind, ident: sig.ident.clone(), args, output }))
+}
+
+fn args_struct(method: &Method) -> TokenStream2 {
+    let ident = method.args_struct();
+    let fields = method.args.iter().map(|(name, ty)| quote!(#name: #ty));
+    quote! {
+        #[derive(::rustorium_contract::__private::serde::Deserialize)]
+        #[serde(crate = "::rustorium_contract::__private::serde", deny_unknown_fields)]
+        #[allow(non_camel_case_types)]
+        struct #ident {
+            #(#fields,)*
+        }
+    }
+}
+
+fn dispatch_arms(methods: &[Method], kind: Kind) -> Vec<TokenStream2> {
+    methods
+        .iter()
+        .filter(|method| method.kind == kind)
+        .map(|method| {
+            let name = method.ident.to_string();
+            let ident = &method.ident;
+            let args = method.args_struct();
+            let names = method.args.iter().map(|(name, _)| name);
+            let respond = if method.returns_result() && method.fails_with_contract_error() {
+                quote!(__rt::respond_contract_result)
+            } else if method.returns_result() {
+                quote!(__rt::respond_result)
+            } else {
+                quote!(__rt::respond)
+            };
+            quote! {
+                #name => {
+                    let __args: #args = __rt::args(__call.args)?;
+                    #respond(self.#ident(#(__args.#names),*))
+                }
+            }
+        })
+        .collect()
+}
+
+/// コントラクトのスキーマ（JSON）
+fn schema(contract: &str, methods: &[Method]) -> String {
+    let describe = |method: &Method| {
+        let args: Vec<String> = method
+            .args
+            .iter()
+            .map(|(name, ty)| format!(r#"{{"name":{},"type":{}}}"#, json_string(&name.to_string()), json_string(&type_name(ty))))
+            .collect();
+        let returns = method.output.as_ref().map_or("null".to_string(), |ty| json_string(&type_name(ty)));
+        format!(r#"{{"name":{},"args":[{}],"returns":{}}}"#, json_string(&method.ident.to_string()), args.join(","), returns)
+    };
+    let list = |kind: Kind| methods.iter().filter(|m| m.kind == kind).map(describe).collect::<Vec<_>>().join(",");
+    let instantiate = methods
+        .iter()
+        .find(|m| m.kind == Kind::Instantiate)
+        .map_or("null".to_string(), describe);
+    format!(
+        r#"{{"contract":{},"instantiate":{},"execute":[{}],"query":[{}]}}"#,
+        json_string(contract),
+        instantiate,
+        list(Kind::Execute),
+        list(Kind::Query),
+    )
+}
+
+/// 型をソースの表記に近い文字列にする（`Vec < u8 >` → `Vec<u8>`）
+fn type_name(ty: &Type) -> String {
+    let mut name = String::new();
+    for part in quote!(#ty).to_string().split_whitespace() {
+        // `dyn Trait` のような識別子の並びは空白を残す
+        let joins_words = name.ends_with(|c: char| c.is_alphanumeric() || c == '_')
+            && part.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        if joins_words {
+            name.push(' ');
+        }
+        name.push_str(part);
+    }
+    name
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
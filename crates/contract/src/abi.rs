@@ -0,0 +1,223 @@
+//! ホストABI
+//!
+//! ランタイムが `rustorium` モジュールとして提供する関数です。可変長の値を返す関数は
+//! 長さだけを返し、値はホストの結果バッファに置きます（`read_result` で読み出す）。
+//! WASM以外のターゲットでは [`crate::testing`] のメモリ上のホストに振り分けます。
+
+#[cfg(target_arch = "wasm32")]
+mod sys {
+    #[link(wasm_import_module = "rustorium")]
+    extern "C" {
+        pub fn input_len() -> u32;
+        pub fn read_input(ptr: *mut u8);
+        pub fn read_result(ptr: *mut u8);
+        /// 値の長さ（キーがない場合は -1）
+        pub fn storage_read(key_ptr: *const u8, key_len: u32) -> i64;
+        pub fn storage_write(key_ptr: *const u8, key_len: u32, value_ptr: *const u8, value_len: u32);
+        pub fn storage_remove(key_ptr: *const u8, key_len: u32);
+        pub fn caller() -> u32;
+        pub fn self_address() -> u32;
+        pub fn block_height() -> u64;
+        pub fn block_timestamp() -> u64;
+        /// 乱数ビーコンの長さ（実行中のブロック以降の高さは -1）
+        pub fn block_randomness(height: u64) -> i64;
+        pub fn balance(address_ptr: *const u8, address_len: u32) -> u64;
+        /// 解決したアドレスの長さ（登録されていない、または期限切れの名前は -1）
+        pub fn resolve_name(name_ptr: *const u8, name_len: u32) -> i64;
+        /// 0 は成功、1 は残高不足
+        pub fn transfer(to_ptr: *const u8, to_len: u32, amount: u64) -> u32;
+        /// トピックは改行区切り
+        pub fn emit_event(
+            name_ptr: *const u8,
+            name_len: u32,
+            topics_ptr: *const u8,
+            topics_len: u32,
+            data_ptr: *const u8,
+            data_len: u32,
+        );
+        /// 0 は正しい証明、1 は正しくない証明、2 は解釈できない入力（ガスは検証の前に請求される）
+        pub fn zk_verify(
+            system: u32,
+            curve: u32,
+            vk_ptr: *const u8,
+            vk_len: u32,
+            proof_ptr: *const u8,
+            proof_len: u32,
+            inputs_ptr: *const u8,
+            inputs_len: u32,
+        ) -> u32;
+        pub fn set_return(ptr: *const u8, len: u32);
+        pub fn abort(message_ptr: *const u8, message_len: u32) -> !;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod host {
+    use super::sys;
+
+    fn read_result(len: u32) -> Vec<u8> {
+        let mut buffer = vec![0u8; len as usize];
+        unsafe { sys::read_result(buffer.as_mut_ptr()) };
+        buffer
+    }
+
+    pub fn input() -> Vec<u8> {
+        let mut buffer = vec![0u8; unsafe { sys::input_len() } as usize];
+        unsafe { sys::read_input(buffer.as_mut_ptr()) };
+        buffer
+    }
+
+    pub fn storage_read(key: &[u8]) -> Option<Vec<u8>> {
+        let len = unsafe { sys::storage_read(key.as_ptr(), key.len() as u32) };
+        (len >= 0).then(|| read_result(len as u32))
+    }
+
+    pub fn storage_write(key: &[u8], value: &[u8]) {
+        unsafe { sys::storage_write(key.as_ptr(), key.len() as u32, value.as_ptr(), value.len() as u32) }
+    }
+
+    pub fn storage_remove(key: &[u8]) {
+        unsafe { sys::storage_remove(key.as_ptr(), key.len() as u32) }
+    }
+
+    pub fn caller() -> Vec<u8> {
+        read_result(unsafe { sys::caller() })
+    }
+
+    pub fn self_address() -> Vec<u8> {
+        read_result(unsafe { sys::self_address() })
+    }
+
+    pub fn block_height() -> u64 {
+        unsafe { sys::block_height() }
+    }
+
+    pub fn block_timestamp() -> u64 {
+        unsafe { sys::block_timestamp() }
+    }
+
+    pub fn block_randomness(height: u64) -> Option<Vec<u8>> {
+        let len = unsafe { sys::block_randomness(height) };
+        (len >= 0).then(|| read_result(len as u32))
+    }
+
+    pub fn balance(address: &[u8]) -> u64 {
+        unsafe { sys::balance(address.as_ptr(), address.len() as u32) }
+    }
+
+    pub fn resolve_name(name: &[u8]) -> Option<Vec<u8>> {
+        let len = unsafe { sys::resolve_name(name.as_ptr(), name.len() as u32) };
+        (len >= 0).then(|| read_result(len as u32))
+    }
+
+    pub fn transfer(to: &[u8], amount: u64) -> u32 {
+        unsafe { sys::transfer(to.as_ptr(), to.len() as u32, amount) }
+    }
+
+    pub fn emit_event(name: &[u8], topics: &[u8], data: &[u8]) {
+        unsafe {
+            sys::emit_event(
+                name.as_ptr(),
+                name.len() as u32,
+                topics.as_ptr(),
+                topics.len() as u32,
+                data.as_ptr(),
+                data.len() as u32,
+            )
+        }
+    }
+
+    pub fn zk_verify(system: u32, curve: u32, verifying_key: &[u8], proof: &[u8], public_inputs: &[u8]) -> u32 {
+        unsafe {
+            sys::zk_verify(
+                system,
+                curve,
+                verifying_key.as_ptr(),
+                verifying_key.len() as u32,
+                proof.as_ptr(),
+                proof.len() as u32,
+                public_inputs.as_ptr(),
+                public_inputs.len() as u32,
+            )
+        }
+    }
+
+    pub fn set_return(data: &[u8]) {
+        unsafe { sys::set_return(data.as_ptr(), data.len() as u32) }
+    }
+
+    pub fn abort(message: &[u8]) -> ! {
+        unsafe { sys::abort(message.as_ptr(), message.len() as u32) }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod host {
+    use crate::testing::with_host;
+
+    pub fn input() -> Vec<u8> {
+        with_host(|host| host.input.clone())
+    }
+
+    pub fn storage_read(key: &[u8]) -> Option<Vec<u8>> {
+        with_host(|host| host.storage.get(key).cloned())
+    }
+
+    pub fn storage_write(key: &[u8], value: &[u8]) {
+        with_host(|host| host.storage.insert(key.to_vec(), value.to_vec()));
+    }
+
+    pub fn storage_remove(key: &[u8]) {
+        with_host(|host| host.storage.remove(key));
+    }
+
+    pub fn caller() -> Vec<u8> {
+        with_host(|host| host.caller.clone().into_bytes())
+    }
+
+    pub fn self_address() -> Vec<u8> {
+        with_host(|host| host.contract.clone().into_bytes())
+    }
+
+    pub fn block_height() -> u64 {
+        with_host(|host| host.block_height)
+    }
+
+    pub fn block_timestamp() -> u64 {
+        with_host(|host| host.block_timestamp)
+    }
+
+    pub fn block_randomness(height: u64) -> Option<Vec<u8>> {
+        with_host(|host| host.randomness(height))
+    }
+
+    pub fn balance(address: &[u8]) -> u64 {
+        with_host(|host| host.balance(&String::from_utf8_lossy(address)))
+    }
+
+    pub fn resolve_name(name: &[u8]) -> Option<Vec<u8>> {
+        with_host(|host| host.names.get(&*String::from_utf8_lossy(name)).map(|address| address.clone().into_bytes()))
+    }
+
+    pub fn transfer(to: &[u8], amount: u64) -> u32 {
+        with_host(|host| host.transfer(&String::from_utf8_lossy(to), amount))
+    }
+
+    pub fn emit_event(name: &[u8], topics: &[u8], data: &[u8]) {
+        with_host(|host| host.emit_event(name, topics, data))
+    }
+
+    pub fn zk_verify(system: u32, curve: u32, verifying_key: &[u8], proof: &[u8], public_inputs: &[u8]) -> u32 {
+        with_host(|_| rustorium_zk::host_verify(system, curve, verifying_key, proof, public_inputs))
+    }
+
+    pub fn set_return(data: &[u8]) {
+        with_host(|host| host.output = data.to_vec())
+    }
+
+    pub fn abort(message: &[u8]) -> ! {
+        panic!("Contract aborted: {}", String::from_utf8_lossy(message))
+    }
+}
+
+pub use host::*;
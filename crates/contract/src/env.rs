@@ -0,0 +1,121 @@
+//! コントラクトの実行環境
+//!
+//! ストレージ、呼び出し元、ブロック、乱数ビーコン、残高とイベントをホストABIを介して扱います。
+//! アドレスは `0x` で始まる16進数の文字列です。
+
+use crate::{abi, ContractError};
+
+/// 呼び出しの入力
+pub fn input() -> Vec<u8> {
+    abi::input()
+}
+
+/// ストレージの値を取得
+pub fn storage_get(key: &[u8]) -> Option<Vec<u8>> {
+    abi::storage_read(key)
+}
+
+/// ストレージに値を保存
+pub fn storage_set(key: &[u8], value: &[u8]) {
+    abi::storage_write(key, value)
+}
+
+/// ストレージの値を削除
+pub fn storage_remove(key: &[u8]) {
+    abi::storage_remove(key)
+}
+
+/// 呼び出し元のアドレス
+pub fn caller() -> String {
+    String::from_utf8_lossy(&abi::caller()).into_owned()
+}
+
+/// このコントラクトのアドレス
+pub fn self_address() -> String {
+    String::from_utf8_lossy(&abi::self_address()).into_owned()
+}
+
+/// 実行中のブロックの高さ
+pub fn block_height() -> u64 {
+    abi::block_height()
+}
+
+/// 実行中のブロックの時刻（UNIX秒）
+pub fn block_timestamp() -> u64 {
+    abi::block_timestamp()
+}
+
+/// 確定したブロックの乱数ビーコン（実行中のブロック以降の高さは `None`）
+///
+/// 生成者の VRF 出力から導出され、ブロックハッシュと違い生成者が選ぶことはできません。
+/// ただし生成者は自分のブロックの値を先に知っているため、申し込みを受け付けたブロックより
+/// 後の高さの値を使ってください。
+pub fn block_randomness(height: u64) -> Option<[u8; 32]> {
+    abi::block_randomness(height).and_then(|value| value.try_into().ok())
+}
+
+/// アドレスの残高
+pub fn balance(address: &str) -> u64 {
+    abi::balance(address.as_bytes())
+}
+
+/// ネームサービスの名前をアドレスに解決（登録されていない、または期限切れの名前は `None`）
+pub fn resolve_name(name: &str) -> Option<String> {
+    abi::resolve_name(name.as_bytes()).map(|address| String::from_utf8_lossy(&address).into_owned())
+}
+
+/// このコントラクトの残高から送金
+pub fn transfer(to: &str, amount: u64) -> Result<(), ContractError> {
+    match abi::transfer(to.as_bytes(), amount) {
+        0 => Ok(()),
+        status => Err(ContractError::TransferFailed(status)),
+    }
+}
+
+/// イベントを発行（トピックに改行を含めることはできない）
+pub fn emit_event(name: &str, topics: &[&str], data: &[u8]) {
+    debug_assert!(topics.iter().all(|topic| !topic.contains('\n')));
+    abi::emit_event(name.as_bytes(), topics.join("\n").as_bytes(), data)
+}
+
+/// 証明系（`zk_verify` の `system`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofSystem {
+    Groth16 = 0,
+    Plonk = 1,
+}
+
+/// 楕円曲線（`zk_verify` の `curve`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Bn254 = 0,
+    Bls12_381 = 1,
+}
+
+/// ゼロ知識証明を検証
+///
+/// 検証鍵と証明はarkworksの圧縮形式、公開入力はスカラーを32バイトのビッグエンディアンで
+/// 連結したものです。ガスは入力の大きさと証明系から計算され、結果に関わらず請求されます。
+pub fn zk_verify(
+    system: ProofSystem,
+    curve: Curve,
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<bool, ContractError> {
+    match abi::zk_verify(system as u32, curve as u32, verifying_key, proof, public_inputs) {
+        0 => Ok(true),
+        1 => Ok(false),
+        _ => Err(ContractError::MalformedProof),
+    }
+}
+
+/// 呼び出しの戻り値を設定
+pub fn set_return(data: &[u8]) {
+    abi::set_return(data)
+}
+
+/// 呼び出しを中断し、状態の変更を取り消す
+pub fn abort(message: &str) -> ! {
+    abi::abort(message.as_bytes())
+}
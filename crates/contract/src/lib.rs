@@ -0,0 +1,297 @@
+//! Rustorium WASMコントラクトSDK
+//!
+//! ホストABIの `extern "C"` を直接書かずに、Rustの型とメソッドでコントラクトを記述できます。
+//!
+//! ```ignore
+//! use rustorium_contract::{contract, env, Serialize, Deserialize};
+//!
+//! #[derive(Default, Serialize, Deserialize)]
+//! pub struct Counter {
+//!     count: u64,
+//! }
+//!
+//! #[contract]
+//! impl Counter {
+//!     #[execute]
+//!     pub fn increment(&mut self, by: u64) -> u64 {
+//!         self.count += by;
+//!         env::emit_event("Incremented", &[&env::caller()], &self.count.to_be_bytes());
+//!         self.count
+//!     }
+//!
+//!     #[query]
+//!     pub fn get(&self) -> u64 {
+//!         self.count
+//!     }
+//! }
+//! ```
+//!
+//! 呼び出しの入力は `{"method": "increment", "args": {"by": 1}}` の形式のJSONで、
+//! 戻り値はJSONで返します。状態はメソッドの呼び出しの前後にストレージの `__state` から
+//! 読み込み・保存します。1つのクレートに置けるコントラクトは1つです。
+//!
+//! ノードを起動せずにテストするには [`testing::TestEnv`] を使います。
+
+use thiserror::Error;
+
+// マクロが生成する `::rustorium_contract` のパスをこのクレートのテストでも解決する
+extern crate self as rustorium_contract;
+
+mod abi;
+pub mod env;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
+
+pub use rustorium_contract_macros::{contract, execute, instantiate, query};
+pub use serde::{Deserialize, Serialize};
+
+/// コントラクトのエラー
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ContractError {
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unknown method: {0}")]
+    UnknownMethod(String),
+
+    #[error("Execution reverted: {0}")]
+    Reverted(String),
+
+    #[error("Transfer failed with status {0}")]
+    TransferFailed(u32),
+
+    #[error("Malformed verifying key, proof or public inputs")]
+    MalformedProof,
+
+    #[error("Corrupted contract state: {0}")]
+    State(String),
+}
+
+/// `#[contract]` が実装するコントラクトの入口
+///
+/// 入力と戻り値はJSONです。ホストを介さずに呼び出せるため、テストにも使えます。
+pub trait Contract: Serialize + for<'de> Deserialize<'de> {
+    /// メソッドと引数・戻り値の型を記述したスキーマ（JSON）
+    const SCHEMA: &'static str;
+
+    /// 引数のオブジェクトから初期化
+    fn instantiate(input: &[u8]) -> Result<Self, ContractError>;
+
+    /// 状態を変更するメソッドを呼び出す
+    fn execute(&mut self, input: &[u8]) -> Result<Vec<u8>, ContractError>;
+
+    /// 状態を読み取るメソッドを呼び出す
+    fn query(&self, input: &[u8]) -> Result<Vec<u8>, ContractError>;
+}
+
+/// マクロが生成するコードから使う実装の詳細
+#[doc(hidden)]
+pub mod __private {
+    use std::fmt::Display;
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use crate::{env, Contract, ContractError};
+
+    pub use serde;
+
+    /// 状態を保存するストレージのキー
+    pub const STATE_KEY: &[u8] = b"__state";
+
+    /// メソッドの呼び出し
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Call {
+        pub method: String,
+        #[serde(default)]
+        pub args: serde_json::Value,
+    }
+
+    pub fn decode_call(input: &[u8]) -> Result<Call, ContractError> {
+        serde_json::from_slice(input).map_err(|e| ContractError::InvalidInput(e.to_string()))
+    }
+
+    /// 引数のオブジェクトを読み込む（省略された場合は空のオブジェクト）
+    pub fn args<T: DeserializeOwned>(args: serde_json::Value) -> Result<T, ContractError> {
+        let args = if args.is_null() { serde_json::json!({}) } else { args };
+        serde_json::from_value(args).map_err(|e| ContractError::InvalidInput(e.to_string()))
+    }
+
+    /// 初期化の入力から引数のオブジェクトを読み込む（空の入力は空のオブジェクト）
+    pub fn decode_args<T: DeserializeOwned>(input: &[u8]) -> Result<T, ContractError> {
+        if input.is_empty() {
+            return args(serde_json::Value::Null);
+        }
+        serde_json::from_slice::<serde_json::Value>(input)
+            .map_err(|e| ContractError::InvalidInput(e.to_string()))
+            .and_then(args)
+    }
+
+    /// 引数を取らない初期化
+    pub fn no_args(input: &[u8]) -> Result<(), ContractError> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct NoArgs {}
+        decode_args::<NoArgs>(input).map(|_| ())
+    }
+
+    pub fn unknown_method(method: &str) -> ContractError {
+        ContractError::UnknownMethod(method.to_string())
+    }
+
+    pub fn respond<T: Serialize>(value: T) -> Result<Vec<u8>, ContractError> {
+        serde_json::to_vec(&value).map_err(|e| ContractError::State(e.to_string()))
+    }
+
+    /// メソッドが返したエラーは取り消しとして扱う
+    pub fn revert<T, E: Display>(result: Result<T, E>) -> Result<T, ContractError> {
+        result.map_err(|e| ContractError::Reverted(e.to_string()))
+    }
+
+    pub fn respond_result<T: Serialize, E: Display>(result: Result<T, E>) -> Result<Vec<u8>, ContractError> {
+        revert(result).and_then(respond)
+    }
+
+    pub fn respond_contract_result<T: Serialize>(result: Result<T, ContractError>) -> Result<Vec<u8>, ContractError> {
+        result.and_then(respond)
+    }
+
+    pub fn load<C: Contract>() -> Result<C, ContractError> {
+        let state = env::storage_get(STATE_KEY)
+            .ok_or_else(|| ContractError::State("contract is not instantiated".to_string()))?;
+        serde_json::from_slice(&state).map_err(|e| ContractError::State(e.to_string()))
+    }
+
+    fn save<C: Contract>(contract: &C) -> Result<(), ContractError> {
+        let state = serde_json::to_vec(contract).map_err(|e| ContractError::State(e.to_string()))?;
+        env::storage_set(STATE_KEY, &state);
+        Ok(())
+    }
+
+    /// エラーの場合は呼び出しを中断し、状態の変更を取り消させる
+    fn finish(result: Result<Vec<u8>, ContractError>) {
+        match result {
+            Ok(output) => env::set_return(&output),
+            Err(e) => env::abort(&e.to_string()),
+        }
+    }
+
+    /// 初期化して状態を保存する
+    pub fn call_instantiate<C: Contract>(input: &[u8]) -> Result<Vec<u8>, ContractError> {
+        save(&C::instantiate(input)?)?;
+        Ok(Vec::new())
+    }
+
+    /// 状態を読み込んでメソッドを呼び出し、変更した状態を保存する
+    pub fn call_execute<C: Contract>(input: &[u8]) -> Result<Vec<u8>, ContractError> {
+        let mut contract = load::<C>()?;
+        let output = contract.execute(input)?;
+        save(&contract)?;
+        Ok(output)
+    }
+
+    pub fn call_query<C: Contract>(input: &[u8]) -> Result<Vec<u8>, ContractError> {
+        load::<C>()?.query(input)
+    }
+
+    pub fn run_instantiate<C: Contract>() {
+        finish(call_instantiate::<C>(&env::input()));
+    }
+
+    pub fn run_execute<C: Contract>() {
+        finish(call_execute::<C>(&env::input()));
+    }
+
+    pub fn run_query<C: Contract>() {
+        finish(call_query::<C>(&env::input()));
+    }
+
+    pub fn run_schema<C: Contract>() {
+        env::set_return(C::SCHEMA.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    pub struct Counter {
+        count: u64,
+        owner: String,
+    }
+
+    #[contract]
+    impl Counter {
+        #[instantiate]
+        pub fn new(owner: String, start: u64) -> Result<Self, String> {
+            if owner.is_empty() {
+                return Err("owner is required".to_string());
+            }
+            Ok(Self { count: start, owner })
+        }
+
+        #[execute]
+        pub fn increment(&mut self, by: u64) -> u64 {
+            self.count += by;
+            self.count
+        }
+
+        #[execute]
+        pub fn reset(&mut self) {
+            self.count = 0;
+        }
+
+        #[query]
+        pub fn get(&self) -> u64 {
+            self.count
+        }
+
+        #[query]
+        pub fn checked(&self, limit: u64) -> Result<u64, String> {
+            if self.count > limit {
+                return Err(format!("count {} exceeds {}", self.count, limit));
+            }
+            Ok(self.count)
+        }
+
+        /// 目印のないメソッドは公開しない
+        pub fn owner(&self) -> &str {
+            &self.owner
+        }
+    }
+
+    #[test]
+    fn test_generated_dispatch_and_schema() {
+        assert_eq!(
+            Counter::instantiate(br#"{"owner":"alice"}"#).unwrap_err(),
+            ContractError::InvalidInput("missing field `start`".to_string())
+        );
+        assert_eq!(
+            Counter::instantiate(br#"{"owner":"","start":1}"#).unwrap_err(),
+            ContractError::Reverted("owner is required".to_string())
+        );
+        let mut counter = Counter::instantiate(br#"{"owner":"alice","start":5}"#).unwrap();
+        assert_eq!(counter.owner(), "alice");
+
+        assert_eq!(counter.execute(br#"{"method":"increment","args":{"by":2}}"#).unwrap(), b"7");
+        assert_eq!(counter.query(br#"{"method":"get"}"#).unwrap(), b"7");
+        assert_eq!(counter.query(br#"{"method":"checked","args":{"limit":10}}"#).unwrap(), b"7");
+        assert!(matches!(counter.query(br#"{"method":"checked","args":{"limit":3}}"#), Err(ContractError::Reverted(_))));
+        // 状態を変更するメソッドは query から呼べない
+        assert_eq!(
+            counter.query(br#"{"method":"increment","args":{"by":1}}"#).unwrap_err(),
+            ContractError::UnknownMethod("increment".to_string())
+        );
+        assert!(matches!(counter.execute(br#"{"method":"increment","args":{"by":1,"x":0}}"#), Err(ContractError::InvalidInput(_))));
+        assert_eq!(counter.execute(br#"{"method":"reset","args":null}"#).unwrap(), b"null");
+        assert_eq!(counter.count, 0);
+
+        let schema: serde_json::Value = serde_json::from_str(Counter::SCHEMA).unwrap();
+        assert_eq!(schema["contract"], "Counter");
+        assert_eq!(schema["instantiate"]["args"][0], serde_json::json!({"name": "owner", "type": "String"}));
+        assert_eq!(schema["execute"][0]["returns"], "u64");
+        assert_eq!(schema["execute"][1]["returns"], serde_json::Value::Null);
+        assert_eq!(schema["query"][1]["returns"], "Result<u64,String>");
+        assert_eq!(schema["query"].as_array().unwrap().len(), 2);
+    }
+}
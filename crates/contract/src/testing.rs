@@ -0,0 +1,375 @@
+//! コントラクトの単体テスト
+//!
+//! ノードを起動せずに、メモリ上のホストでコントラクトを初期化・呼び出します。
+//! ブロックの高さと時刻、乱数ビーコン、呼び出し元、残高、名前とストレージはテストから操作できます。
+//!
+//! ```ignore
+//! use rustorium_contract::testing::{json, TestEnv};
+//!
+//! let mut env = TestEnv::<Counter>::new();
+//! env.set_caller("0x00000000000000000000000000000000000000a1");
+//! env.instantiate(json!({})).unwrap();
+//! let count: u64 = env.execute("increment", json!({"by": 2})).unwrap();
+//! assert_eq!(count, 2);
+//! assert_eq!(env.events()[0].name, "Incremented");
+//! ```
+//!
+//! 呼び出しがエラーを返した場合は、実際のランタイムと同じくストレージ・残高・イベントの
+//! 変更を取り消します。`query` の変更は常に取り消します。`env::abort` はパニックします。
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::{__private, Contract, ContractError};
+
+pub use serde_json::json;
+
+/// 既定の呼び出し元
+pub const DEFAULT_CALLER: &str = "0x0000000000000000000000000000000000000001";
+/// 既定のコントラクトのアドレス
+pub const DEFAULT_CONTRACT: &str = "0x00000000000000000000000000000000000000c0";
+/// `advance_blocks` で進めるブロックあたりの時刻（秒）
+pub const BLOCK_TIME_SECS: u64 = 2;
+
+/// 送金の結果（ホストABIの `transfer` の戻り値）
+const TRANSFER_OK: u32 = 0;
+const TRANSFER_INSUFFICIENT_BALANCE: u32 = 1;
+
+/// 発行されたイベント
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    pub topics: Vec<String>,
+    pub data: Vec<u8>,
+}
+
+/// メモリ上のホスト
+#[derive(Debug, Clone)]
+pub(crate) struct MockHost {
+    pub(crate) input: Vec<u8>,
+    pub(crate) output: Vec<u8>,
+    pub(crate) storage: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub(crate) caller: String,
+    pub(crate) contract: String,
+    pub(crate) block_height: u64,
+    pub(crate) block_timestamp: u64,
+    randomness: BTreeMap<u64, [u8; 32]>,
+    pub(crate) names: BTreeMap<String, String>,
+    balances: BTreeMap<String, u64>,
+    events: Vec<Event>,
+}
+
+impl Default for MockHost {
+    fn default() -> Self {
+        Self {
+            input: Vec::new(),
+            output: Vec::new(),
+            storage: BTreeMap::new(),
+            caller: DEFAULT_CALLER.to_string(),
+            contract: DEFAULT_CONTRACT.to_string(),
+            block_height: 1,
+            block_timestamp: 1_700_000_000,
+            randomness: BTreeMap::new(),
+            names: BTreeMap::new(),
+            balances: BTreeMap::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl MockHost {
+    /// 実行中のブロックより前の高さの乱数
+    pub(crate) fn randomness(&self, height: u64) -> Option<Vec<u8>> {
+        self.randomness.get(&height).filter(|_| height < self.block_height).map(|value| value.to_vec())
+    }
+
+    pub(crate) fn balance(&self, address: &str) -> u64 {
+        self.balances.get(&address.to_lowercase()).copied().unwrap_or(0)
+    }
+
+    /// コントラクトの残高から送金
+    pub(crate) fn transfer(&mut self, to: &str, amount: u64) -> u32 {
+        let from = self.contract.to_lowercase();
+        let available = self.balance(&from);
+        if available < amount {
+            return TRANSFER_INSUFFICIENT_BALANCE;
+        }
+        self.balances.insert(from, available - amount);
+        let to = to.to_lowercase();
+        let received = self.balance(&to).saturating_add(amount);
+        self.balances.insert(to, received);
+        TRANSFER_OK
+    }
+
+    pub(crate) fn emit_event(&mut self, name: &[u8], topics: &[u8], data: &[u8]) {
+        let topics = String::from_utf8_lossy(topics);
+        self.events.push(Event {
+            name: String::from_utf8_lossy(name).into_owned(),
+            topics: if topics.is_empty() { Vec::new() } else { topics.split('\n').map(str::to_string).collect() },
+            data: data.to_vec(),
+        });
+    }
+}
+
+thread_local! {
+    static HOST: RefCell<Option<MockHost>> = const { RefCell::new(None) };
+}
+
+/// 有効なホストで `f` を実行（ホストABIから呼ばれる）
+pub(crate) fn with_host<R>(f: impl FnOnce(&mut MockHost) -> R) -> R {
+    HOST.with(|host| {
+        let mut host = host.borrow_mut();
+        let host = host.as_mut().expect(
+            "Host functions are only available in the Rustorium WASM runtime or inside rustorium_contract::testing::TestEnv",
+        );
+        f(host)
+    })
+}
+
+/// コントラクトのテスト環境
+pub struct TestEnv<C: Contract> {
+    host: MockHost,
+    _contract: PhantomData<C>,
+}
+
+impl<C: Contract> Default for TestEnv<C> {
+    fn default() -> Self {
+        Self { host: MockHost::default(), _contract: PhantomData }
+    }
+}
+
+impl<C: Contract> TestEnv<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以降の呼び出しの呼び出し元
+    pub fn set_caller(&mut self, address: &str) -> &mut Self {
+        self.host.caller = address.to_string();
+        self
+    }
+
+    pub fn caller(&self) -> &str {
+        &self.host.caller
+    }
+
+    pub fn set_contract_address(&mut self, address: &str) -> &mut Self {
+        self.host.contract = address.to_string();
+        self
+    }
+
+    pub fn contract_address(&self) -> &str {
+        &self.host.contract
+    }
+
+    /// ブロックの高さと時刻（UNIX秒）
+    pub fn set_block(&mut self, height: u64, timestamp: u64) -> &mut Self {
+        self.host.block_height = height;
+        self.host.block_timestamp = timestamp;
+        self
+    }
+
+    /// `blocks` ブロック進める（時刻は1ブロックあたり `BLOCK_TIME_SECS` 秒進む）
+    pub fn advance_blocks(&mut self, blocks: u64) -> &mut Self {
+        self.host.block_height += blocks;
+        self.host.block_timestamp += blocks * BLOCK_TIME_SECS;
+        self
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.host.block_height
+    }
+
+    pub fn block_timestamp(&self) -> u64 {
+        self.host.block_timestamp
+    }
+
+    /// 高さの乱数ビーコン（実行中のブロックより前の高さのみ読み取れる）
+    pub fn set_randomness(&mut self, height: u64, value: [u8; 32]) -> &mut Self {
+        self.host.randomness.insert(height, value);
+        self
+    }
+
+    pub fn set_balance(&mut self, address: &str, amount: u64) -> &mut Self {
+        self.host.balances.insert(address.to_lowercase(), amount);
+        self
+    }
+
+    pub fn balance(&self, address: &str) -> u64 {
+        self.host.balance(address)
+    }
+
+    /// ネームサービスの名前の解決先
+    pub fn set_name(&mut self, name: &str, address: &str) -> &mut Self {
+        self.host.names.insert(name.to_string(), address.to_string());
+        self
+    }
+
+    pub fn storage(&self, key: &[u8]) -> Option<&[u8]> {
+        self.host.storage.get(key).map(Vec::as_slice)
+    }
+
+    pub fn set_storage(&mut self, key: &[u8], value: &[u8]) -> &mut Self {
+        self.host.storage.insert(key.to_vec(), value.to_vec());
+        self
+    }
+
+    /// これまでに発行されたイベント
+    pub fn events(&self) -> &[Event] {
+        &self.host.events
+    }
+
+    /// 発行されたイベントを取り出して空にする
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.host.events)
+    }
+
+    /// 保存されているコントラクトの状態
+    pub fn state(&mut self) -> Result<C, ContractError> {
+        self.run(__private::load::<C>)
+    }
+
+    /// 引数のオブジェクトで初期化
+    pub fn instantiate(&mut self, args: impl Serialize) -> Result<(), ContractError> {
+        let input = encode(&args)?;
+        self.call(input, false, __private::call_instantiate::<C>).map(|_| ())
+    }
+
+    /// 状態を変更するメソッドを呼び出す
+    pub fn execute<R: DeserializeOwned>(&mut self, method: &str, args: impl Serialize) -> Result<R, ContractError> {
+        let input = encode(&json!({ "method": method, "args": args }))?;
+        self.call(input, false, __private::call_execute::<C>).and_then(|output| decode(&output))
+    }
+
+    /// 状態を読み取るメソッドを呼び出す
+    pub fn query<R: DeserializeOwned>(&mut self, method: &str, args: impl Serialize) -> Result<R, ContractError> {
+        let input = encode(&json!({ "method": method, "args": args }))?;
+        self.call(input, true, __private::call_query::<C>).and_then(|output| decode(&output))
+    }
+
+    /// この環境をホストとして `f` を実行（`env` を使う関数を直接テストする場合）
+    pub fn run<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let previous = HOST.with(|host| host.replace(Some(self.host.clone())));
+        let result = f();
+        if let Some(host) = HOST.with(|host| host.replace(previous)) {
+            self.host = host;
+        }
+        result
+    }
+
+    /// エラーの場合と `read_only` の場合は変更を取り消す
+    fn call(
+        &mut self,
+        input: Vec<u8>,
+        read_only: bool,
+        entry: fn(&[u8]) -> Result<Vec<u8>, ContractError>,
+    ) -> Result<Vec<u8>, ContractError> {
+        let snapshot = self.host.clone();
+        self.host.input = input;
+        let result = self.run(|| entry(&crate::env::input()));
+        if read_only || result.is_err() {
+            self.host = snapshot;
+        } else {
+            self.host.input.clear();
+        }
+        result
+    }
+}
+
+fn encode(value: &impl Serialize) -> Result<Vec<u8>, ContractError> {
+    serde_json::to_vec(value).map_err(|e| ContractError::InvalidInput(e.to_string()))
+}
+
+fn decode<R: DeserializeOwned>(output: &[u8]) -> Result<R, ContractError> {
+    serde_json::from_slice(output).map_err(|e| ContractError::InvalidInput(format!("Unexpected return value: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{contract, env, Deserialize};
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    pub struct Vault {
+        deposits: BTreeMap<String, u64>,
+        unlock_height: u64,
+    }
+
+    #[contract]
+    impl Vault {
+        #[instantiate]
+        pub fn new(lock_blocks: u64) -> Self {
+            Self { deposits: BTreeMap::new(), unlock_height: env::block_height() + lock_blocks }
+        }
+
+        #[execute]
+        pub fn deposit(&mut self, amount: u64) -> u64 {
+            let caller = env::caller();
+            let total = self.deposits.entry(caller.clone()).or_default();
+            *total += amount;
+            env::emit_event("Deposited", &[&caller], &amount.to_be_bytes());
+            *total
+        }
+
+        #[execute]
+        pub fn withdraw(&mut self) -> Result<u64, ContractError> {
+            if env::block_height() < self.unlock_height {
+                return Err(ContractError::Reverted("locked".to_string()));
+            }
+            let caller = env::caller();
+            let amount = self.deposits.remove(&caller).unwrap_or(0);
+            env::emit_event("Withdrawn", &[&caller], &amount.to_be_bytes());
+            env::transfer(&caller, amount)?;
+            Ok(amount)
+        }
+
+        #[query]
+        pub fn deposited(&self, owner: String) -> u64 {
+            self.deposits.get(&owner).copied().unwrap_or(0)
+        }
+    }
+
+    #[test]
+    fn test_mocked_chain_context() {
+        let alice = "0x00000000000000000000000000000000000000a1";
+        let mut env = TestEnv::<Vault>::new();
+        env.set_block(100, 1_000).set_caller(alice);
+        env.instantiate(json!({"lock_blocks": 10})).unwrap();
+        assert_eq!(env.state().unwrap().unlock_height, 110);
+
+        assert_eq!(env.execute::<u64>("deposit", json!({"amount": 40})).unwrap(), 40);
+        assert_eq!(env.query::<u64>("deposited", json!({"owner": alice})).unwrap(), 40);
+        assert_eq!(env.take_events()[0].topics, vec![alice.to_string()]);
+
+        // ロック中の取り消しは状態を変更しない
+        assert_eq!(env.execute::<u64>("withdraw", json!({})).unwrap_err(), ContractError::Reverted("locked".to_string()));
+
+        // コントラクトの残高が足りない送金は取り消され、イベントも残らない
+        env.advance_blocks(10);
+        assert_eq!(env.block_timestamp(), 1_000 + 10 * BLOCK_TIME_SECS);
+        assert_eq!(env.execute::<u64>("withdraw", json!({})).unwrap_err(), ContractError::TransferFailed(1));
+        assert!(env.events().is_empty());
+        assert_eq!(env.query::<u64>("deposited", json!({"owner": alice})).unwrap(), 40);
+
+        env.set_balance(DEFAULT_CONTRACT, 100);
+        assert_eq!(env.execute::<u64>("withdraw", json!({})).unwrap(), 40);
+        assert_eq!((env.balance(alice), env.balance(DEFAULT_CONTRACT)), (40, 60));
+        assert_eq!(env.events()[0].name, "Withdrawn");
+
+        // 環境の外ではホスト関数を使えない
+        assert!(std::panic::catch_unwind(env::block_height).is_err());
+        assert_eq!(env.run(env::block_height), 110);
+        env.set_randomness(109, [7; 32]).set_randomness(110, [8; 32]);
+        assert_eq!(env.run(|| env::block_randomness(109)), Some([7; 32]));
+        assert_eq!(env.run(|| env::block_randomness(110)), None);
+        env.set_name("alice", alice);
+        assert_eq!(env.run(|| env::resolve_name("alice")), Some(alice.to_string()));
+        assert_eq!(env.run(|| env::resolve_name("bob")), None);
+        assert_eq!(
+            env.run(|| env::zk_verify(env::ProofSystem::Groth16, env::Curve::Bn254, b"vk", b"proof", &[])),
+            Err(ContractError::MalformedProof)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! ワイヤーフォーマットのエンコード/デコード
+//!
+//! ネットワークから受信した信頼できないバイト列をデコードします。
+//! デコーダーはパニックせず、サイズ上限や不正な入力はすべてエラーとして返します。
+
+use bincode::Options;
+use serde::{Serialize, de::DeserializeOwned};
+use thiserror::Error;
+use crate::types::{Block, Transaction};
+
+/// トランザクションの最大サイズ（バイト）
+pub const MAX_TRANSACTION_SIZE: u64 = 128 * 1024;
+
+/// ブロックの最大サイズ（バイト）
+pub const MAX_BLOCK_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error("入力が空です")]
+    Empty,
+
+    #[error("入力サイズが上限を超えています: {size} > {limit}")]
+    TooLarge { size: usize, limit: u64 },
+
+    #[error("不正なエンコーディング: {0}")]
+    Malformed(String),
+}
+
+/// 上限付きのbincode設定
+fn options(limit: u64) -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(limit)
+        .reject_trailing_bytes()
+}
+
+/// 上限付きでデコード
+fn decode<T: DeserializeOwned>(bytes: &[u8], limit: u64) -> Result<T, DecodeError> {
+    if bytes.is_empty() {
+        return Err(DecodeError::Empty);
+    }
+    if bytes.len() as u64 > limit {
+        return Err(DecodeError::TooLarge { size: bytes.len(), limit });
+    }
+
+    options(limit)
+        .deserialize(bytes)
+        .map_err(|e| DecodeError::Malformed(e.to_string()))
+}
+
+/// 上限付きでエンコード
+fn encode<T: Serialize>(value: &T, limit: u64) -> Result<Vec<u8>, DecodeError> {
+    options(limit)
+        .serialize(value)
+        .map_err(|e| DecodeError::Malformed(e.to_string()))
+}
+
+/// トランザクションをデコード
+pub fn decode_transaction(bytes: &[u8]) -> Result<Transaction, DecodeError> {
+    decode(bytes, MAX_TRANSACTION_SIZE)
+}
+
+/// トランザクションをエンコード
+pub fn encode_transaction(tx: &Transaction) -> Result<Vec<u8>, DecodeError> {
+    encode(tx, MAX_TRANSACTION_SIZE)
+}
+
+/// ブロックをデコード
+pub fn decode_block(bytes: &[u8]) -> Result<Block, DecodeError> {
+    decode(bytes, MAX_BLOCK_SIZE)
+}
+
+/// ブロックをエンコード
+pub fn encode_block(block: &Block) -> Result<Vec<u8>, DecodeError> {
+    encode(block, MAX_BLOCK_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Address, Signature};
+
+    fn sample_transaction() -> Transaction {
+        Transaction {
+            from: Address([1; 20]),
+            to: Address([2; 20]),
+            data: b"hello".to_vec(),
+            signature: Signature([3; 64]),
+        }
+    }
+
+    #[test]
+    fn test_transaction_roundtrip() {
+        let tx = sample_transaction();
+        let bytes = encode_transaction(&tx).unwrap();
+        let decoded = decode_transaction(&bytes).unwrap();
+        assert_eq!(encode_transaction(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        let bytes = encode_transaction(&sample_transaction()).unwrap();
+
+        // 空の入力
+        assert!(matches!(decode_transaction(&[]), Err(DecodeError::Empty)));
+
+        // 途中で切れた入力
+        assert!(matches!(
+            decode_transaction(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Malformed(_))
+        ));
+
+        // 末尾の余分なバイト
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(decode_transaction(&trailing), Err(DecodeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_rejects_oversized_length_prefix() {
+        // データ長に巨大な値を持つ入力でも確保前にエラーになること
+        let mut bytes = vec![0u8; 40];
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(decode_transaction(&bytes), Err(DecodeError::Malformed(_))));
+
+        let oversized = vec![0u8; MAX_TRANSACTION_SIZE as usize + 1];
+        assert!(matches!(decode_transaction(&oversized), Err(DecodeError::TooLarge { .. })));
+    }
+}
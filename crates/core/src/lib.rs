@@ -1,6 +1,7 @@
 //! Rustorium Core
 //! 
 //! このクレートはRustoriumの中核機能を提供します。
+//! REST・GraphQL・JSON-RPCのAPIはノード（`rustorium` クレートの `web` モジュール）が提供します。
 
 use anyhow::Result;
 use thiserror::Error;
@@ -10,6 +11,7 @@ pub mod types;
 pub mod transaction;
 pub mod block;
 pub mod state;
+pub mod codec;
 
 #[derive(Error, Debug)]
 pub enum CoreError {
@@ -28,7 +30,6 @@ pub struct RustoriumCore {
     network: rustorium_network::NetworkManager,
     consensus: rustorium_consensus::ConsensusEngine,
     storage: rustorium_storage::StorageEngine,
-    api: rustorium_api::ApiServer,
 }
 
 impl RustoriumCore {
@@ -39,13 +40,11 @@ impl RustoriumCore {
         let network = rustorium_network::NetworkManager::new().await?;
         let consensus = rustorium_consensus::ConsensusEngine::new().await?;
         let storage = rustorium_storage::StorageEngine::new().await?;
-        let api = rustorium_api::ApiServer::new().await?;
         
         Ok(Self {
             network,
             consensus,
             storage,
-            api,
         })
     }
     
@@ -59,9 +58,6 @@ impl RustoriumCore {
         // コンセンサスの開始
         self.consensus.start().await?;
         
-        // APIサーバーの起動
-        self.api.start().await?;
-        
         info!("Rustorium node started successfully");
         Ok(())
     }
@@ -70,9 +66,6 @@ impl RustoriumCore {
     pub async fn stop(&mut self) -> Result<()> {
         info!("Stopping Rustorium node...");
         
-        // APIサーバーの停止
-        self.api.stop().await?;
-        
         // コンセンサスの停止
         self.consensus.stop().await?;
         
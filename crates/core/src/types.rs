@@ -4,22 +4,27 @@ use serde::{Serialize, Deserialize};
 
 /// トランザクションハッシュ
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct TxHash([u8; 32]);
 
 /// ブロックハッシュ
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct BlockHash([u8; 32]);
 
 /// アドレス
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Address([u8; 20]);
 
 /// 署名
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Signature([u8; 64]);
 
 /// トランザクション
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Transaction {
     /// 送信者アドレス
     pub from: Address,
@@ -33,6 +38,7 @@ pub struct Transaction {
 
 /// ブロック
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Block {
     /// ブロック番号
     pub number: u64,
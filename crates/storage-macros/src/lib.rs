@@ -0,0 +1,168 @@
+//! ストレージに保存する状態オブジェクトの導出マクロ
+//!
+//! `core::storage::typed` から再エクスポートして使います。直接依存する必要はありません。
+//!
+//! `#[derive(StateObject)]` を付けた構造体から、次のものを生成します。
+//! - キーのレイアウト（`{cf}/{キー}`）とバージョンを持つ `StateObject` の実装
+//! - `TypedStorage` を通した型付きのアクセサー（`load`・`save`・`delete`・`list`）
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, StateObject)]
+//! #[state(cf = "contract/source", version = 1)]
+//! pub struct VerifiedContract {
+//!     #[state(key)]
+//!     pub address: String,
+//!     ...
+//! }
+//! ```
+//!
+//! キーは `#[state(key)]` を付けたフィールドです。複数ある場合は宣言順のタプルになります。
+//! 古いバージョンの値を読み込むには `#[state(upgrade = "関数のパス")]` を指定します
+//! （`fn(u8, &[u8]) -> anyhow::Result<Self>`）。
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitInt, LitStr};
+
+/// バージョンの上限（バージョンのない旧形式のJSON `{` と区別するため）
+const MAX_VERSION: u8 = 100;
+
+#[proc_macro_derive(StateObject, attributes(state))]
+pub fn derive_state_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// 構造体の `#[state(...)]`
+struct Options {
+    cf: LitStr,
+    version: u8,
+    upgrade: Option<syn::Path>,
+}
+
+fn parse_options(input: &DeriveInput) -> syn::Result<Options> {
+    let mut cf = None;
+    let mut version = 1;
+    let mut upgrade = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("state")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("cf") {
+                let value: LitStr = meta.value()?.parse()?;
+                let name = value.value();
+                if name.is_empty() || name.starts_with('/') || name.ends_with('/') {
+                    return Err(meta.error("cf must be a non-empty key prefix without leading or trailing '/'"));
+                }
+                cf = Some(value);
+            } else if meta.path.is_ident("version") {
+                let value: LitInt = meta.value()?.parse()?;
+                version = value.base10_parse::<u8>()?;
+                if version == 0 || version > MAX_VERSION {
+                    return Err(Error::new(value.span(), format!("version must be between 1 and {}", MAX_VERSION)));
+                }
+            } else if meta.path.is_ident("upgrade") {
+                let value: LitStr = meta.value()?.parse()?;
+                upgrade = Some(value.parse()?);
+            } else {
+                return Err(meta.error("expected `cf`, `version` or `upgrade`"));
+            }
+            Ok(())
+        })?;
+    }
+    let cf = cf.ok_or_else(|| Error::new(Span::call_site(), "#[derive(StateObject)] requires #[state(cf = \"...\")]"))?;
+    Ok(Options { cf, version, upgrade })
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "#[derive(StateObject)] does not support generic types"));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(input.span(), "#[derive(StateObject)] can only be used on structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(data.fields.span(), "#[derive(StateObject)] requires named fields"));
+    };
+    let options = parse_options(input)?;
+
+    let mut keys = Vec::new();
+    for field in &fields.named {
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("state")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    keys.push(field);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `key`"))
+                }
+            })?;
+        }
+    }
+    if keys.is_empty() {
+        return Err(Error::new(input.ident.span(), "mark at least one field with #[state(key)]"));
+    }
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let cf = &options.cf;
+    let version = options.version;
+    let names: Vec<_> = keys.iter().map(|field| field.ident.as_ref().expect("named field")).collect();
+    let types: Vec<_> = keys.iter().map(|field| &field.ty).collect();
+    let (key_type, key_value) = match (&names[..], &types[..]) {
+        ([name], [ty]) => (quote!(#ty), quote!(::core::clone::Clone::clone(&self.#name))),
+        _ => (quote!((#(#types),*)), quote!((#(::core::clone::Clone::clone(&self.#names)),*))),
+    };
+    let upgrade = options.upgrade.map(|path| quote! {
+        fn upgrade(version: u8, data: &[u8]) -> ::anyhow::Result<Self> {
+            #path(version, data)
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            use crate::core::storage::{StorageEngine as __Storage, typed as __typed};
+
+            impl __typed::StateObject for #ident {
+                const CF: &'static str = #cf;
+                const VERSION: u8 = #version;
+                type Key = #key_type;
+
+                fn key(&self) -> Self::Key {
+                    #key_value
+                }
+
+                #upgrade
+            }
+
+            impl #ident {
+                /// キーを指定して読み込む
+                #vis async fn load(storage: &dyn __Storage, key: &#key_type) -> ::anyhow::Result<::core::option::Option<Self>> {
+                    __typed::TypedStorage::load::<Self>(storage, key).await
+                }
+
+                /// 現在のバージョンで保存する
+                #vis async fn save(&self, storage: &dyn __Storage) -> ::anyhow::Result<()> {
+                    __typed::TypedStorage::save(storage, self).await
+                }
+
+                /// キーを指定して削除する
+                #vis async fn delete(storage: &dyn __Storage, key: &#key_type) -> ::anyhow::Result<()> {
+                    __typed::TypedStorage::remove::<Self>(storage, key).await
+                }
+
+                /// キーの順に `after` の次から最大 `limit` 件読み込む
+                #vis async fn list(
+                    storage: &dyn __Storage,
+                    after: ::core::option::Option<&#key_type>,
+                    limit: usize,
+                ) -> ::anyhow::Result<::std::vec::Vec<Self>> {
+                    __typed::TypedStorage::list::<Self>(storage, after, limit).await
+                }
+            }
+        };
+    })
+}
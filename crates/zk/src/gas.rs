@@ -0,0 +1,90 @@
+//! 検証のガス
+//!
+//! 楕円曲線の演算の価格はEthereumのプリコンパイル（BN254は EIP-1108、BLS12-381は EIP-2537）に合わせ、
+//! 証明系ごとのペアリングとスカラー倍算の回数から計算します。
+//! 検証の成否や入力の不正に関わらず、ランタイムは検証の前にこのガスを請求します。
+
+use crate::{Curve, ProofSystem, SCALAR_BYTES};
+
+/// 曲線の演算の価格
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveCosts {
+    /// ペアリングの検査の固定費
+    pub pairing_base: u64,
+    /// ペアリングの1組あたり
+    pub pairing_per_pair: u64,
+    /// G1のスカラー倍算
+    pub g1_mul: u64,
+    /// G1の加算
+    pub g1_add: u64,
+}
+
+/// BN254（EIP-1108）
+pub const BN254: CurveCosts = CurveCosts {
+    pairing_base: 45_000,
+    pairing_per_pair: 34_000,
+    g1_mul: 6_000,
+    g1_add: 150,
+};
+
+/// BLS12-381（EIP-2537）
+pub const BLS12_381: CurveCosts = CurveCosts {
+    pairing_base: 37_700,
+    pairing_per_pair: 32_600,
+    g1_mul: 12_000,
+    g1_add: 375,
+};
+
+/// 入力の1バイトあたり（デシリアライズと点の部分群の検査）
+pub const PER_BYTE: u64 = 8;
+/// PLONKの公開入力の1つあたり（ラグランジュ基底の評価）
+pub const PLONK_PER_INPUT: u64 = 500;
+
+/// Groth16のペアリングの組数（`e(α, β)` の計算を含む）
+const GROTH16_PAIRS: u64 = 4;
+/// PLONKのペアリングの組数
+const PLONK_PAIRS: u64 = 2;
+/// PLONKのG1のスカラー倍算と加算の回数（線形化、バッチ開示、ペアリングの入力）
+const PLONK_G1_MULS: u64 = 18;
+const PLONK_G1_ADDS: u64 = 18;
+
+impl Curve {
+    pub fn costs(self) -> CurveCosts {
+        match self {
+            Self::Bn254 => BN254,
+            Self::Bls12_381 => BLS12_381,
+        }
+    }
+}
+
+/// 検証のガス（`input_bytes` は検証鍵・証明・公開入力の合計）
+pub fn verify_cost(system: ProofSystem, curve: Curve, input_bytes: usize, public_inputs: usize) -> u64 {
+    let costs = curve.costs();
+    let inputs = public_inputs as u64;
+    let operations = match system {
+        ProofSystem::Groth16 => {
+            costs.pairing_base
+                + GROTH16_PAIRS * costs.pairing_per_pair
+                + inputs.saturating_mul(costs.g1_mul + costs.g1_add)
+        }
+        ProofSystem::Plonk => {
+            costs.pairing_base
+                + PLONK_PAIRS * costs.pairing_per_pair
+                + PLONK_G1_MULS * costs.g1_mul
+                + PLONK_G1_ADDS * costs.g1_add
+                + inputs.saturating_mul(PLONK_PER_INPUT)
+        }
+    };
+    operations.saturating_add((input_bytes as u64).saturating_mul(PER_BYTE))
+}
+
+/// ホスト関数 `zk_verify` の引数からガスを計算
+pub fn host_verify_cost(system: u32, curve: u32, input_bytes: usize, public_input_bytes: usize) -> u64 {
+    match (ProofSystem::try_from(system), Curve::try_from(curve)) {
+        (Ok(system), Ok(curve)) => {
+            verify_cost(system, curve, input_bytes, public_input_bytes.div_ceil(SCALAR_BYTES))
+        }
+        // 検証せずに拒否する
+        _ => (input_bytes as u64).saturating_mul(PER_BYTE),
+    }
+}
@@ -0,0 +1,88 @@
+//! Groth16
+//!
+//! 検証鍵と証明はarkworksの圧縮形式（`serialize_compressed`）です。
+//! 点は曲線上にあり、素数位数の部分群に属することを検査します。
+
+use ark_ec::pairing::Pairing;
+use ark_groth16::{Groth16, Proof, VerifyingKey};
+use ark_serialize::CanonicalDeserialize;
+use crate::{read_exact, ZkError};
+
+pub(crate) fn verify<E: Pairing>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[E::ScalarField],
+) -> Result<bool, ZkError> {
+    let vk = read_exact(verifying_key, |reader| VerifyingKey::<E>::deserialize_compressed(reader))
+        .map_err(ZkError::InvalidVerifyingKey)?;
+    let expected = vk.gamma_abc_g1.len().saturating_sub(1);
+    if expected != public_inputs.len() {
+        return Err(ZkError::InputCountMismatch { expected, actual: public_inputs.len() });
+    }
+    let proof = read_exact(proof, |reader| Proof::<E>::deserialize_compressed(reader))
+        .map_err(ZkError::InvalidProof)?;
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    Groth16::<E>::verify_proof(&pvk, &proof, public_inputs).map_err(|e| ZkError::InvalidProof(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+    use ark_serialize::CanonicalSerialize;
+    use ark_snark::SNARK;
+    use crate::{gas, Curve, ProofSystem, ZkError};
+    use super::*;
+
+    /// `x * y = z`（`z` は公開入力）
+    struct Multiply<F: PrimeField> {
+        x: Option<F>,
+        y: Option<F>,
+    }
+
+    impl<F: PrimeField> ConstraintSynthesizer<F> for Multiply<F> {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let x = cs.new_witness_variable(|| self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            let y = cs.new_witness_variable(|| self.y.ok_or(SynthesisError::AssignmentMissing))?;
+            let z = cs.new_input_variable(|| {
+                Ok(self.x.ok_or(SynthesisError::AssignmentMissing)? * self.y.ok_or(SynthesisError::AssignmentMissing)?)
+            })?;
+            cs.enforce_constraint(lc!() + x, lc!() + y, lc!() + z)
+        }
+    }
+
+    fn round_trip<E: Pairing>(curve: Curve) {
+        let mut rng = ark_std::test_rng();
+        let (pk, vk) = Groth16::<E>::circuit_specific_setup(Multiply::<E::ScalarField> { x: None, y: None }, &mut rng).unwrap();
+        let (x, y) = (E::ScalarField::from(3u64), E::ScalarField::from(5u64));
+        let proof = Groth16::<E>::prove(&pk, Multiply { x: Some(x), y: Some(y) }, &mut rng).unwrap();
+
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        let input = |value: u64| E::ScalarField::from(value).into_bigint().to_bytes_be();
+
+        let verify = |inputs: &[u8]| crate::verify(ProofSystem::Groth16, curve, &vk_bytes, &proof_bytes, inputs);
+        assert_eq!(verify(&input(15)), Ok(true));
+        assert_eq!(verify(&input(16)), Ok(false));
+        assert!(matches!(verify(&[]), Err(ZkError::InputCountMismatch { expected: 1, actual: 0 })));
+        // 位数以上の値は拒否する
+        assert!(matches!(verify(&[0xff; 32]), Err(ZkError::InvalidPublicInputs(_))));
+
+        let mut truncated = proof_bytes.clone();
+        truncated.pop();
+        assert!(matches!(
+            crate::verify(ProofSystem::Groth16, curve, &vk_bytes, &truncated, &input(15)),
+            Err(ZkError::InvalidProof(_))
+        ));
+        assert!(gas::verify_cost(ProofSystem::Groth16, curve, vk_bytes.len() + proof_bytes.len() + 32, 1) > 150_000);
+    }
+
+    #[test]
+    fn test_groth16_on_both_curves() {
+        round_trip::<ark_bn254::Bn254>(Curve::Bn254);
+        round_trip::<ark_bls12_381::Bls12_381>(Curve::Bls12_381);
+    }
+}
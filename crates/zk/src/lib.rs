@@ -0,0 +1,170 @@
+//! ゼロ知識証明の検証
+//!
+//! ランタイムのホスト関数 `zk_verify` の実装です。プロトコルのフォークを待たずに、
+//! コントラクトからGroth16とPLONKの証明を検証できます。
+//! 主な機能：
+//! - Groth16（arkworksの `VerifyingKey`・`Proof` の圧縮形式）
+//! - PLONK（snarkjsと同じ検証手順とKeccak256のトランスクリプト）
+//! - BN254とBLS12-381
+//! - 検証の前に請求するガス（[`gas::verify_cost`]）
+//!
+//! 公開入力はスカラー体の要素を32バイトのビッグエンディアンで連結したものです。
+//! 体の位数以上の値は拒否します。
+
+pub mod gas;
+mod groth16;
+mod plonk;
+
+use ark_ff::{BigInteger, PrimeField};
+use thiserror::Error;
+
+pub use plonk::{PlonkProof, PlonkVerifyingKey};
+
+/// 公開入力の最大数
+pub const MAX_PUBLIC_INPUTS: usize = 1024;
+/// 公開入力の1つあたりのバイト数
+pub const SCALAR_BYTES: usize = 32;
+
+/// `zk_verify` の戻り値：証明は正しい
+pub const STATUS_VALID: u32 = 0;
+/// `zk_verify` の戻り値：証明は正しくない
+pub const STATUS_INVALID: u32 = 1;
+/// `zk_verify` の戻り値：検証鍵・証明・公開入力を解釈できない
+pub const STATUS_MALFORMED: u32 = 2;
+
+/// 楕円曲線（ホストABIの `curve`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Bn254 = 0,
+    Bls12_381 = 1,
+}
+
+impl TryFrom<u32> for Curve {
+    type Error = ZkError;
+
+    fn try_from(id: u32) -> Result<Self, ZkError> {
+        match id {
+            0 => Ok(Self::Bn254),
+            1 => Ok(Self::Bls12_381),
+            _ => Err(ZkError::UnknownCurve(id)),
+        }
+    }
+}
+
+/// 証明系（ホストABIの `system`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofSystem {
+    Groth16 = 0,
+    Plonk = 1,
+}
+
+impl TryFrom<u32> for ProofSystem {
+    type Error = ZkError;
+
+    fn try_from(id: u32) -> Result<Self, ZkError> {
+        match id {
+            0 => Ok(Self::Groth16),
+            1 => Ok(Self::Plonk),
+            _ => Err(ZkError::UnknownProofSystem(id)),
+        }
+    }
+}
+
+/// 検証のエラー（証明が正しくない場合はエラーではなく `Ok(false)`）
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ZkError {
+    #[error("Unknown curve: {0}")]
+    UnknownCurve(u32),
+
+    #[error("Unknown proof system: {0}")]
+    UnknownProofSystem(u32),
+
+    #[error("Invalid verifying key: {0}")]
+    InvalidVerifyingKey(String),
+
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
+
+    #[error("Invalid public inputs: {0}")]
+    InvalidPublicInputs(String),
+
+    #[error("Verifying key expects {expected} public inputs, got {actual}")]
+    InputCountMismatch { expected: usize, actual: usize },
+}
+
+/// 証明を検証する
+pub fn verify(
+    system: ProofSystem,
+    curve: Curve,
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<bool, ZkError> {
+    match (system, curve) {
+        (ProofSystem::Groth16, Curve::Bn254) => {
+            groth16::verify::<ark_bn254::Bn254>(verifying_key, proof, &scalars(public_inputs)?)
+        }
+        (ProofSystem::Groth16, Curve::Bls12_381) => {
+            groth16::verify::<ark_bls12_381::Bls12_381>(verifying_key, proof, &scalars(public_inputs)?)
+        }
+        (ProofSystem::Plonk, Curve::Bn254) => {
+            plonk::verify::<ark_bn254::Bn254>(verifying_key, proof, &scalars(public_inputs)?)
+        }
+        (ProofSystem::Plonk, Curve::Bls12_381) => {
+            plonk::verify::<ark_bls12_381::Bls12_381>(verifying_key, proof, &scalars(public_inputs)?)
+        }
+    }
+}
+
+/// ホスト関数 `zk_verify` の戻り値
+pub fn status(result: &Result<bool, ZkError>) -> u32 {
+    match result {
+        Ok(true) => STATUS_VALID,
+        Ok(false) => STATUS_INVALID,
+        Err(_) => STATUS_MALFORMED,
+    }
+}
+
+/// ホスト関数 `zk_verify` の本体（ガスは [`gas::verify_cost`] で事前に請求する）
+pub fn host_verify(system: u32, curve: u32, verifying_key: &[u8], proof: &[u8], public_inputs: &[u8]) -> u32 {
+    let result = ProofSystem::try_from(system)
+        .and_then(|system| Ok((system, Curve::try_from(curve)?)))
+        .and_then(|(system, curve)| verify(system, curve, verifying_key, proof, public_inputs));
+    status(&result)
+}
+
+/// 公開入力をスカラー体の要素に変換
+fn scalars<F: PrimeField>(bytes: &[u8]) -> Result<Vec<F>, ZkError> {
+    if bytes.len() % SCALAR_BYTES != 0 {
+        return Err(ZkError::InvalidPublicInputs(format!(
+            "length {} is not a multiple of {}", bytes.len(), SCALAR_BYTES
+        )));
+    }
+    if bytes.len() / SCALAR_BYTES > MAX_PUBLIC_INPUTS {
+        return Err(ZkError::InvalidPublicInputs(format!("more than {} inputs", MAX_PUBLIC_INPUTS)));
+    }
+    bytes.chunks_exact(SCALAR_BYTES)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let value = F::from_be_bytes_mod_order(chunk);
+            // 位数以上の値は剰余を取ると一致しなくなる
+            if value.into_bigint().to_bytes_be() != chunk {
+                return Err(ZkError::InvalidPublicInputs(format!("input {} is not a canonical field element", index)));
+            }
+            Ok(value)
+        })
+        .collect()
+}
+
+/// 先頭から読み込み、余りのバイトがあればエラーにする
+fn read_exact<T>(
+    bytes: &[u8],
+    read: impl FnOnce(&mut &[u8]) -> Result<T, ark_serialize::SerializationError>,
+) -> Result<T, String> {
+    let mut reader = bytes;
+    let value = read(&mut reader).map_err(|e| e.to_string())?;
+    if !reader.is_empty() {
+        return Err(format!("{} trailing bytes", reader.len()));
+    }
+    Ok(value)
+}
@@ -0,0 +1,304 @@
+//! PLONK
+//!
+//! snarkjsの `plonk_verify` と同じ手順で検証します。検証鍵と証明は arkworksの圧縮形式です。
+//! トランスクリプトはKeccak256で、G1の点は非圧縮の `x || y`（ビッグエンディアン）、
+//! スカラーは32バイトのビッグエンディアンで追加します。
+
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use sha3::{Digest, Keccak256};
+use crate::{read_exact, ZkError, MAX_PUBLIC_INPUTS, SCALAR_BYTES};
+
+/// 回路の最大の大きさ（`2^28` 行）
+const MAX_DOMAIN_SIZE: u64 = 1 << 28;
+
+/// 検証鍵（snarkjsの `verification_key.json` に対応）
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkVerifyingKey<E: Pairing> {
+    /// 評価領域の大きさ（2のべき）
+    pub n: u64,
+    pub public_inputs: u64,
+    pub k1: E::ScalarField,
+    pub k2: E::ScalarField,
+    pub qm: E::G1Affine,
+    pub ql: E::G1Affine,
+    pub qr: E::G1Affine,
+    pub qo: E::G1Affine,
+    pub qc: E::G1Affine,
+    pub s1: E::G1Affine,
+    pub s2: E::G1Affine,
+    pub s3: E::G1Affine,
+    /// `[x]₂`
+    pub x_2: E::G2Affine,
+    /// 評価領域の `n` 乗根
+    pub omega: E::ScalarField,
+}
+
+/// 証明（snarkjsの `proof.json` に対応）
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkProof<E: Pairing> {
+    pub a: E::G1Affine,
+    pub b: E::G1Affine,
+    pub c: E::G1Affine,
+    pub z: E::G1Affine,
+    pub t1: E::G1Affine,
+    pub t2: E::G1Affine,
+    pub t3: E::G1Affine,
+    pub wxi: E::G1Affine,
+    pub wxiw: E::G1Affine,
+    pub eval_a: E::ScalarField,
+    pub eval_b: E::ScalarField,
+    pub eval_c: E::ScalarField,
+    pub eval_s1: E::ScalarField,
+    pub eval_s2: E::ScalarField,
+    pub eval_zw: E::ScalarField,
+}
+
+impl<E: Pairing> PlonkVerifyingKey<E> {
+    fn check(&self) -> Result<(), String> {
+        if !self.n.is_power_of_two() || self.n < 2 || self.n > MAX_DOMAIN_SIZE {
+            return Err(format!("domain size {} is not a power of two in [2, 2^28]", self.n));
+        }
+        if self.public_inputs as usize > MAX_PUBLIC_INPUTS {
+            return Err(format!("more than {} public inputs", MAX_PUBLIC_INPUTS));
+        }
+        // ω は原始 n 乗根
+        if !self.omega.pow([self.n]).is_one() || self.omega.pow([self.n / 2]).is_one() {
+            return Err("omega is not a primitive root of unity of the domain".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// G1の座標の素体
+type G1BaseField<E> = <<<E as Pairing>::G1Affine as AffineRepr>::BaseField as Field>::BasePrimeField;
+
+/// Keccak256のトランスクリプト
+struct Transcript<E: Pairing> {
+    data: Vec<u8>,
+    _pairing: std::marker::PhantomData<E>,
+}
+
+impl<E: Pairing> Transcript<E> {
+    fn new() -> Self {
+        Self { data: Vec::new(), _pairing: std::marker::PhantomData }
+    }
+
+    fn point(&mut self, point: &E::G1Affine) {
+        let size = G1BaseField::<E>::MODULUS_BIT_SIZE.div_ceil(8) as usize;
+        match point.xy() {
+            Some((x, y)) => {
+                for element in x.to_base_prime_field_elements().chain(y.to_base_prime_field_elements()) {
+                    let bytes = element.into_bigint().to_bytes_be();
+                    self.data.extend(&bytes[bytes.len() - size..]);
+                }
+            }
+            // 無限遠点は0で埋める
+            None => self.data.extend(std::iter::repeat(0).take(2 * size)),
+        }
+    }
+
+    fn scalar(&mut self, scalar: &E::ScalarField) {
+        let bytes = scalar.into_bigint().to_bytes_be();
+        self.data.extend(&bytes[bytes.len() - SCALAR_BYTES..]);
+    }
+
+    /// チャレンジを取り出し、次のラウンドのためにリセット
+    fn challenge(&mut self) -> E::ScalarField {
+        let hash = Keccak256::digest(&self.data);
+        self.data.clear();
+        E::ScalarField::from_be_bytes_mod_order(&hash)
+    }
+}
+
+pub(crate) fn verify<E: Pairing>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[E::ScalarField],
+) -> Result<bool, ZkError> {
+    let vk = read_exact(verifying_key, |reader| PlonkVerifyingKey::<E>::deserialize_compressed(reader))
+        .map_err(ZkError::InvalidVerifyingKey)?;
+    vk.check().map_err(ZkError::InvalidVerifyingKey)?;
+    let expected = vk.public_inputs as usize;
+    if expected != public_inputs.len() {
+        return Err(ZkError::InputCountMismatch { expected, actual: public_inputs.len() });
+    }
+    let proof = read_exact(proof, |reader| PlonkProof::<E>::deserialize_compressed(reader))
+        .map_err(ZkError::InvalidProof)?;
+    Ok(check_proof(&vk, &proof, public_inputs))
+}
+
+fn check_proof<E: Pairing>(vk: &PlonkVerifyingKey<E>, proof: &PlonkProof<E>, public_inputs: &[E::ScalarField]) -> bool {
+    // チャレンジ
+    let mut transcript = Transcript::<E>::new();
+    for point in [&vk.qm, &vk.ql, &vk.qr, &vk.qo, &vk.qc, &vk.s1, &vk.s2, &vk.s3] {
+        transcript.point(point);
+    }
+    for input in public_inputs {
+        transcript.scalar(input);
+    }
+    for point in [&proof.a, &proof.b, &proof.c] {
+        transcript.point(point);
+    }
+    let beta = transcript.challenge();
+
+    transcript.scalar(&beta);
+    let gamma = transcript.challenge();
+
+    transcript.scalar(&beta);
+    transcript.scalar(&gamma);
+    transcript.point(&proof.z);
+    let alpha = transcript.challenge();
+
+    transcript.scalar(&alpha);
+    for point in [&proof.t1, &proof.t2, &proof.t3] {
+        transcript.point(point);
+    }
+    let xi = transcript.challenge();
+
+    transcript.scalar(&xi);
+    for eval in [&proof.eval_a, &proof.eval_b, &proof.eval_c, &proof.eval_s1, &proof.eval_s2, &proof.eval_zw] {
+        transcript.scalar(eval);
+    }
+    let v1 = transcript.challenge();
+    let v = [v1, v1.pow([2]), v1.pow([3]), v1.pow([4]), v1.pow([5])];
+
+    transcript.point(&proof.wxi);
+    transcript.point(&proof.wxiw);
+    let u = transcript.challenge();
+
+    let xin = xi.pow([vk.n]);
+    let zh = xin - E::ScalarField::one();
+    let n = E::ScalarField::from(vk.n);
+
+    // ラグランジュ基底 L_1..L_max(1, nPublic) の ξ での値
+    let mut lagrange = Vec::with_capacity(public_inputs.len().max(1));
+    let mut w = E::ScalarField::one();
+    for _ in 0..public_inputs.len().max(1) {
+        let Some(denominator) = (n * (xi - w)).inverse() else {
+            // ξ が評価領域の点になるのは無視できる確率でしか起きない
+            return false;
+        };
+        lagrange.push(w * zh * denominator);
+        w *= vk.omega;
+    }
+    let l1 = lagrange[0];
+    let pi = -public_inputs.iter().zip(&lagrange).map(|(input, l)| *input * l).sum::<E::ScalarField>();
+
+    let (a, b, c) = (proof.eval_a, proof.eval_b, proof.eval_c);
+    let (s1, s2, zw) = (proof.eval_s1, proof.eval_s2, proof.eval_zw);
+    let alpha2 = alpha.square();
+
+    let r0 = pi - l1 * alpha2 - alpha * (a + beta * s1 + gamma) * (b + beta * s2 + gamma) * (c + gamma) * zw;
+
+    // 線形化の多項式のコミットメント D
+    let betaxi = beta * xi;
+    let d1 = vk.qm * (a * b) + vk.ql * a + vk.qr * b + vk.qo * c + vk.qc.into_group();
+    let d2a = (a + betaxi + gamma) * (b + betaxi * vk.k1 + gamma) * (c + betaxi * vk.k2 + gamma) * alpha;
+    let d2 = proof.z * (d2a + l1 * alpha2 + u);
+    let d3 = vk.s3 * ((a + beta * s1 + gamma) * (b + beta * s2 + gamma) * alpha * beta * zw);
+    let d4 = (proof.t1.into_group() + proof.t2 * xin + proof.t3 * xin.square()) * zh;
+    let d = d1 + d2 - d3 - d4;
+
+    let f = d + proof.a * v[0] + proof.b * v[1] + proof.c * v[2] + vk.s1 * v[3] + vk.s2 * v[4];
+    let e = E::G1Affine::generator() * (-r0 + v[0] * a + v[1] * b + v[2] * c + v[3] * s1 + v[4] * s2 + u * zw);
+
+    // e(-(W_ξ + u·W_ξω), [x]₂) · e(ξ·W_ξ + uξω·W_ξω + F - E, [1]₂) = 1
+    let left = -(proof.wxi.into_group() + proof.wxiw * u);
+    let right = proof.wxi * xi + proof.wxiw * (u * xi * vk.omega) + f - e;
+    E::multi_pairing(
+        [left.into_affine(), right.into_affine()],
+        [vk.x_2, E::G2Affine::generator()],
+    )
+    .is_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ff::{FftField, UniformRand};
+    use crate::{gas, Curve, ProofSystem, ZkError};
+    use super::*;
+
+    fn key<E: Pairing>(public_inputs: u64) -> PlonkVerifyingKey<E> {
+        let mut rng = ark_std::test_rng();
+        let mut g1 = || E::G1::rand(&mut rng).into_affine();
+        let (qm, ql, qr, qo, qc, s1, s2, s3) = (g1(), g1(), g1(), g1(), g1(), g1(), g1(), g1());
+        PlonkVerifyingKey {
+            n: 8,
+            public_inputs,
+            k1: E::ScalarField::from(2u64),
+            k2: E::ScalarField::from(3u64),
+            qm, ql, qr, qo, qc, s1, s2, s3,
+            x_2: E::G2::rand(&mut ark_std::test_rng()).into_affine(),
+            omega: E::ScalarField::get_root_of_unity(8).unwrap(),
+        }
+    }
+
+    fn proof<E: Pairing>() -> PlonkProof<E> {
+        let mut rng = ark_std::test_rng();
+        let mut g1 = || E::G1::rand(&mut rng).into_affine();
+        let (a, b, c, z, t1, t2, t3, wxi, wxiw) = (g1(), g1(), g1(), g1(), g1(), g1(), g1(), g1(), g1());
+        let mut fr = || E::ScalarField::rand(&mut rng);
+        PlonkProof {
+            a, b, c, z, t1, t2, t3, wxi, wxiw,
+            eval_a: fr(),
+            eval_b: fr(),
+            eval_c: fr(),
+            eval_s1: fr(),
+            eval_s2: fr(),
+            eval_zw: fr(),
+        }
+    }
+
+    fn bytes(value: &impl CanonicalSerialize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn rejects_malformed<E: Pairing>(curve: Curve) {
+        let input = E::ScalarField::from(7u64).into_bigint().to_bytes_be();
+        let verify = |vk: &PlonkVerifyingKey<E>, proof: &[u8], inputs: &[u8]| {
+            crate::verify(ProofSystem::Plonk, curve, &bytes(vk), proof, inputs)
+        };
+        let vk = key::<E>(1);
+        let proof = bytes(&proof::<E>());
+
+        // 無関係な点は正しい証明にならない
+        assert_eq!(verify(&vk, &proof, &input), Ok(false));
+        assert!(matches!(verify(&vk, &proof, &[]), Err(ZkError::InputCountMismatch { expected: 1, actual: 0 })));
+        assert!(matches!(verify(&vk, &proof[1..], &input), Err(ZkError::InvalidProof(_))));
+
+        let mut odd = vk.clone();
+        odd.n = 6;
+        assert!(matches!(verify(&odd, &proof, &input), Err(ZkError::InvalidVerifyingKey(_))));
+        let mut not_primitive = vk.clone();
+        not_primitive.omega = E::ScalarField::one();
+        assert!(matches!(verify(&not_primitive, &proof, &input), Err(ZkError::InvalidVerifyingKey(_))));
+
+        assert!(
+            gas::verify_cost(ProofSystem::Plonk, curve, bytes(&vk).len() + proof.len() + 32, 1)
+                > gas::verify_cost(ProofSystem::Groth16, curve, bytes(&vk).len() + proof.len() + 32, 1)
+        );
+    }
+
+    #[test]
+    fn test_plonk_rejects_malformed_on_both_curves() {
+        rejects_malformed::<ark_bn254::Bn254>(Curve::Bn254);
+        rejects_malformed::<ark_bls12_381::Bls12_381>(Curve::Bls12_381);
+    }
+
+    #[test]
+    fn test_transcript_encodes_full_width_points() {
+        let mut transcript = Transcript::<ark_bls12_381::Bls12_381>::new();
+        transcript.point(&ark_bls12_381::G1Affine::generator());
+        transcript.scalar(&ark_bls12_381::Fr::one());
+        assert_eq!(transcript.data.len(), 2 * 48 + 32);
+
+        let mut transcript = Transcript::<ark_bn254::Bn254>::new();
+        transcript.point(&ark_bn254::G1Affine::zero());
+        assert_eq!(transcript.data, vec![0; 64]);
+    }
+}
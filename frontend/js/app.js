@@ -13,6 +13,51 @@ const wsPort = document.getElementById('ws-port');
 const maxPeers = document.getElementById('max-peers');
 const pendingTx = document.getElementById('pending-tx');
 const blockTime = document.getElementById('block-time');
+const shardMap = document.getElementById('shard-map');
+
+// バイト数を読みやすい単位に変換
+function formatBytes(bytes) {
+    const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+    let i = 0;
+    while (bytes >= 1024 && i < units.length - 1) {
+        bytes /= 1024;
+        i++;
+    }
+    return `${bytes.toFixed(i === 0 ? 0 : 1)} ${units[i]}`;
+}
+
+// シャードマップの更新
+async function updateShards() {
+    try {
+        const response = await fetch('/api/shards');
+        if (!response.ok) {
+            throw new Error(`HTTP error! status: ${response.status}`);
+        }
+        const shards = await response.json();
+
+        shardMap.replaceChildren(...shards.map((shard) => {
+            const row = document.createElement('tr');
+            if (shard.needs_scaling) {
+                row.classList.add('overloaded');
+            }
+            [
+                `#${shard.id}`,
+                shard.validators.length,
+                shard.account_count,
+                formatBytes(shard.storage_usage),
+                shard.tps,
+                `${(shard.cross_shard_ratio * 100).toFixed(1)}%`,
+            ].forEach((value) => {
+                const cell = document.createElement('td');
+                cell.textContent = value;
+                row.appendChild(cell);
+            });
+            return row;
+        }));
+    } catch (error) {
+        console.error('Failed to fetch shards:', error);
+    }
+}
 
 // メトリクスの更新
 async function updateMetrics() {
@@ -40,17 +85,39 @@ async function updateMetrics() {
         blockTime.textContent = `${data.performance.block_time} ms`;
 
         // ステータスを更新
-        nodeStatus.textContent = 'Connected';
+        nodeStatus.textContent = t('status_connected', 'Connected');
         nodeStatus.classList.add('connected');
     } catch (error) {
         console.error('Failed to fetch metrics:', error);
-        nodeStatus.textContent = 'Disconnected';
+        nodeStatus.textContent = t('status_disconnected', 'Disconnected');
         nodeStatus.classList.remove('connected');
     }
 }
 
+// パスキーでログインしている場合はログアウトボタンを表示
+async function initSession() {
+    const response = await fetch('/api/auth/session');
+    if (!response.ok) {
+        return;  // パスキーによるログインが無効
+    }
+    const session = await response.json();
+    if (!session.authenticated) {
+        return;
+    }
+    const logout = document.getElementById('logout');
+    logout.title = `Signed in with "${session.name}"`;
+    logout.hidden = false;
+    logout.addEventListener('click', async () => {
+        await fetch('/api/auth/logout', { method: 'POST', headers: { 'X-CSRF-Token': session.csrf_token } });
+        window.location.href = '/login.html';
+    });
+}
+
 // 定期的にメトリクスを更新
 setInterval(updateMetrics, UPDATE_INTERVAL);
+setInterval(updateShards, UPDATE_INTERVAL);
 
 // 初回更新
-updateMetrics();
\ No newline at end of file
+updateMetrics();
+updateShards();
+initSession();
\ No newline at end of file
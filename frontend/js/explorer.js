@@ -0,0 +1,342 @@
+// ブロックエクスプローラー
+//
+// URLのハッシュ（#/blocks、#/block/:id、#/tx/:hash、#/account/:address、
+// #/validators、#/validator/:address、#/wallet）で画面を切り替え、ノードのAPIから表示します。
+
+// 1ページの件数
+const PAGE_SIZE = 25;
+// バリデーターのページで提案を探す直近のブロック数
+const RECENT_BLOCKS = 100;
+// バリデーターの集計期間
+const WINDOWS = ['1h', '24h', '7d'];
+
+const explorer = document.getElementById('explorer');
+const dashboard = document.getElementById('dashboard');
+const searchForm = document.getElementById('search');
+const searchQuery = document.getElementById('search-query');
+
+// 見つからない場合のエラー
+class NotFoundError extends Error {}
+
+async function api(path) {
+    const response = await fetch(`/api${path}`);
+    if (response.status === 404) {
+        throw new NotFoundError(`Not found: ${path}`);
+    }
+    if (!response.ok) {
+        throw new Error(`HTTP error! status: ${response.status}`);
+    }
+    return response.json();
+}
+
+// 要素の作成（文字列の子要素はテキストとして追加する）
+function el(tag, attrs = {}, ...children) {
+    const element = document.createElement(tag);
+    Object.entries(attrs).forEach(([name, value]) => element.setAttribute(name, value));
+    children.flat().forEach((child) => {
+        if (child !== null && child !== undefined) {
+            element.append(child instanceof Node ? child : String(child));
+        }
+    });
+    return element;
+}
+
+function link(href, text) {
+    return el('a', { href }, text);
+}
+
+function shortHash(hash) {
+    return hash && hash.length > 18 ? `${hash.slice(0, 10)}…${hash.slice(-6)}` : hash;
+}
+
+function formatTime(seconds) {
+    return new Date(seconds * 1000).toLocaleString();
+}
+
+function blockLink(height) {
+    return link(`#/block/${height}`, height);
+}
+
+function txLink(hash) {
+    return el('span', { class: 'mono' }, link(`#/tx/${hash}`, shortHash(hash)));
+}
+
+function accountLink(address) {
+    return el('span', { class: 'mono' }, link(`#/account/${encodeURIComponent(address)}`, shortHash(address)));
+}
+
+function validatorLink(address) {
+    return el('span', { class: 'mono' }, link(`#/validator/${encodeURIComponent(address)}`, shortHash(address)));
+}
+
+function table(headers, rows) {
+    return el('table', { class: 'data-table' },
+        el('thead', {}, el('tr', {}, headers.map((header) => el('th', {}, header)))),
+        el('tbody', {}, rows.length === 0
+            ? el('tr', {}, el('td', { colspan: headers.length }, 'None'))
+            : rows.map((cells) => el('tr', {}, cells.map((cell) => el('td', {}, cell))))));
+}
+
+// 項目名と値の一覧
+function details(pairs) {
+    return el('dl', { class: 'details' },
+        pairs.map(([label, value]) => [el('dt', {}, label), el('dd', {}, value ?? '-')]));
+}
+
+function section(title, ...children) {
+    return el('section', {}, el('h2', {}, title), ...children);
+}
+
+function pager(links) {
+    return el('div', { class: 'pager' }, links.filter(Boolean));
+}
+
+function render(...sections) {
+    explorer.replaceChildren(...sections);
+}
+
+// ブロック一覧
+async function showBlocks(params) {
+    const cursor = params.get('cursor');
+    const query = new URLSearchParams({ limit: PAGE_SIZE });
+    if (cursor) {
+        query.set('cursor', cursor);
+    }
+    const page = await api(`/blocks?${query}`);
+    const first = page.items[0];
+    render(section('Blocks',
+        table(['Height', 'Hash', 'Time', 'Validator', 'Transactions', 'Gas Used', 'Base Fee'],
+            page.items.map((block) => [
+                blockLink(block.height),
+                el('span', { class: 'mono' }, link(`#/block/${block.height}`, shortHash(block.hash))),
+                formatTime(block.timestamp),
+                validatorLink(block.validator),
+                block.transaction_count,
+                block.gas_limit > 0 ? `${block.gas_used} / ${block.gas_limit}` : block.gas_used,
+                block.base_fee,
+            ])),
+        pager([
+            cursor && first && link(`#/blocks?cursor=${first.height + PAGE_SIZE}`, '← Newer'),
+            cursor && link('#/blocks', 'Latest'),
+            page.next_cursor && link(`#/blocks?cursor=${page.next_cursor}`, 'Older →'),
+        ])));
+}
+
+// ブロックの詳細（高さかハッシュ）
+async function showBlock(id) {
+    const block = /^\d+$/.test(id) ? await api(`/blocks/${id}`) : await api(`/blocks/hash/${encodeURIComponent(id)}`);
+    render(
+        section(`Block #${block.height}`,
+            details([
+                ['Hash', el('span', { class: 'mono' }, block.hash)],
+                ['Parent', block.height > 0 ? el('span', { class: 'mono' }, link(`#/block/${block.height - 1}`, block.parent_hash)) : '-'],
+                ['Time', formatTime(block.timestamp)],
+                ['Validator', validatorLink(block.validator)],
+                ['Transactions', block.transactions.length],
+                ['Gas Used', block.gas_used],
+                ['Gas Limit', block.gas_limit],
+                ['Base Fee', block.base_fee],
+                ['Receipts Root', block.receipts_root ? el('span', { class: 'mono' }, block.receipts_root) : '-'],
+                ['Signed', block.signature ? el('span', { class: 'mono' }, shortHash(block.signature.public_key)) : 'No'],
+            ]),
+            pager([
+                block.height > 0 && link(`#/block/${block.height - 1}`, '← Previous'),
+                link(`#/block/${block.height + 1}`, 'Next →'),
+            ])),
+        section('Transactions',
+            table(['Hash', 'From', 'To', 'Value', 'Gas Price', 'Gas Limit'],
+                block.transactions.map((tx) => [
+                    txLink(tx.hash),
+                    accountLink(tx.from),
+                    accountLink(tx.to),
+                    tx.value,
+                    tx.gas_price,
+                    tx.gas_limit,
+                ]))));
+}
+
+// トランザクションの詳細とレシート
+async function showTransaction(hash) {
+    const detail = await api(`/transactions/${encodeURIComponent(hash)}`);
+    const { transaction: tx, receipt } = detail;
+    render(
+        section('Transaction',
+            details([
+                ['Hash', el('span', { class: 'mono' }, tx.hash)],
+                ['Block', [blockLink(detail.block_height), ` (index ${detail.index})`]],
+                ['Time', formatTime(detail.timestamp)],
+                ['From', accountLink(tx.from)],
+                ['To', accountLink(tx.to)],
+                ['Value', tx.value],
+                ['Nonce', tx.nonce],
+                ['Gas Price', tx.gas_price],
+                ['Gas Limit', tx.gas_limit],
+                ['Data', tx.data.length > 0 ? `${tx.data.length} bytes` : '-'],
+                ['Signed', tx.signature ? 'Yes' : 'No'],
+            ])),
+        section('Receipt',
+            details([
+                ['Status', receipt.status === 1 ? 'Success' : 'Failed'],
+                ['Gas Used', receipt.gas_used],
+                ['Cumulative Gas Used', receipt.cumulative_gas_used],
+            ]),
+            table(['Contract', 'Topics', 'Data'],
+                receipt.logs.map((log) => [
+                    accountLink(log.address),
+                    el('span', { class: 'mono' }, log.topics.map(shortHash).join(', ')),
+                    // データはhexで返る
+                    `${log.data.length / 2} bytes`,
+                ]))));
+}
+
+// アカウント（残高・ノンス・トランザクション）
+async function showAccount(address, params) {
+    const cursor = params.get('cursor');
+    const query = new URLSearchParams({ limit: PAGE_SIZE });
+    if (cursor) {
+        query.set('cursor', cursor);
+    }
+    const path = `/accounts/${encodeURIComponent(address)}`;
+    const [balance, nonce, page] = await Promise.all([
+        api(`${path}/balance`),
+        api(`${path}/nonce`),
+        api(`${path}/transactions?${query}`),
+    ]);
+    const base = `#/account/${encodeURIComponent(balance.address)}`;
+    render(
+        section('Account',
+            details([
+                ['Address', el('span', { class: 'mono' }, balance.address)],
+                ['Balance', balance.balance],
+                ['Next Nonce', nonce.next_nonce],
+                ['As of Block', balance.height !== null ? blockLink(balance.height) : '-'],
+            ])),
+        section('Transactions',
+            table(['Hash', 'Block', 'Time', 'Direction', 'Counterparty', 'Value'],
+                page.items.map((tx) => [
+                    txLink(tx.hash),
+                    blockLink(tx.height),
+                    formatTime(tx.timestamp),
+                    tx.direction === 'in' ? 'In' : 'Out',
+                    accountLink(tx.counterparty),
+                    tx.value,
+                ])),
+            pager([
+                cursor && link(base, 'Latest'),
+                page.next_cursor && link(`${base}?cursor=${encodeURIComponent(page.next_cursor)}`, 'Older →'),
+            ])));
+}
+
+// バリデーター一覧
+async function showValidators(params) {
+    const selected = WINDOWS.includes(params.get('window')) ? params.get('window') : '24h';
+    const report = await api(`/validators/performance?window=${selected}&limit=1000`);
+    render(section('Validators',
+        pager(WINDOWS.map((w) => (w === selected ? el('strong', {}, w) : link(`#/validators?window=${w}`, w)))),
+        table(['Validator', 'Proposed', 'Missed', 'Miss Rate', 'Votes', 'Avg Vote Latency', 'Last Proposed'],
+            report.validators.map((v) => [
+                validatorLink(v.validator),
+                v.proposed,
+                v.missed,
+                `${(v.miss_rate * 100).toFixed(1)}%`,
+                v.votes,
+                v.avg_vote_latency_ms !== null ? `${v.avg_vote_latency_ms.toFixed(0)} ms` : '-',
+                v.last_proposed_height !== null ? blockLink(v.last_proposed_height) : '-',
+            ]))));
+}
+
+// バリデーターの詳細（期間ごとの集計と直近の提案）
+async function showValidator(address) {
+    const [reports, blocks] = await Promise.all([
+        Promise.all(WINDOWS.map((w) => api(`/validators/performance?window=${w}&validator=${encodeURIComponent(address)}`))),
+        api(`/blocks?limit=${RECENT_BLOCKS}`),
+    ]);
+    const proposals = blocks.items.filter((block) => block.validator === address);
+    render(
+        section('Validator',
+            details([
+                ['Address', el('span', { class: 'mono' }, address)],
+                ['Account', accountLink(address)],
+            ]),
+            table(['Window', 'Proposed', 'Missed', 'Miss Rate', 'Votes', 'Avg Vote Latency', 'Max Vote Latency'],
+                reports.map((report) => [report, report.validators.find((v) => v.validator === address)])
+                    .filter(([, v]) => v)
+                    .map(([report, v]) => [
+                        report.window,
+                        v.proposed,
+                        v.missed,
+                        `${(v.miss_rate * 100).toFixed(1)}%`,
+                        v.votes,
+                        v.avg_vote_latency_ms !== null ? `${v.avg_vote_latency_ms.toFixed(0)} ms` : '-',
+                        v.max_vote_latency_ms !== null ? `${v.max_vote_latency_ms} ms` : '-',
+                    ]))),
+        section(`Proposals in the Last ${RECENT_BLOCKS} Blocks`,
+            table(['Height', 'Time', 'Transactions', 'Gas Used'],
+                proposals.map((block) => [
+                    blockLink(block.height),
+                    formatTime(block.timestamp),
+                    block.transaction_count,
+                    block.gas_used,
+                ]))));
+}
+
+// 検索（高さ、トランザクションかブロックのハッシュ、アドレス）
+async function search(query) {
+    query = query.trim();
+    if (query === '') {
+        return;
+    }
+    if (/^\d+$/.test(query)) {
+        location.hash = `#/block/${query}`;
+        return;
+    }
+    if (/^(0x)?[0-9a-fA-F]{64}$/.test(query)) {
+        try {
+            await api(`/transactions/${query}`);
+            location.hash = `#/tx/${query}`;
+        } catch (error) {
+            location.hash = `#/block/${query}`;
+        }
+        return;
+    }
+    location.hash = `#/account/${encodeURIComponent(query)}`;
+}
+
+const routes = [
+    [/^\/blocks$/, (match, params) => showBlocks(params)],
+    [/^\/block\/([^/]+)$/, (match) => showBlock(decodeURIComponent(match[1]))],
+    [/^\/tx\/([^/]+)$/, (match) => showTransaction(decodeURIComponent(match[1]))],
+    [/^\/account\/([^/]+)$/, (match, params) => showAccount(decodeURIComponent(match[1]), params)],
+    [/^\/validators$/, (match, params) => showValidators(params)],
+    [/^\/validator\/([^/]+)$/, (match) => showValidator(decodeURIComponent(match[1]))],
+    [/^\/wallet$/, () => showWallet()],
+];
+
+async function route() {
+    const [path, queryString = ''] = location.hash.replace(/^#/, '').split('?');
+    const params = new URLSearchParams(queryString);
+    const found = routes.map(([pattern, view]) => [path.match(pattern), view]).find(([match]) => match);
+
+    if (!found) {
+        explorer.hidden = true;
+        dashboard.hidden = false;
+        return;
+    }
+    dashboard.hidden = true;
+    explorer.hidden = false;
+    render(section('Loading...'));
+    try {
+        await found[1](found[0], params);
+    } catch (error) {
+        console.error('Failed to load explorer page:', error);
+        render(section(error instanceof NotFoundError ? 'Not Found' : 'Error', el('p', {}, error.message)));
+    }
+}
+
+searchForm.addEventListener('submit', (event) => {
+    event.preventDefault();
+    search(searchQuery.value);
+});
+window.addEventListener('hashchange', route);
+
+route();
@@ -0,0 +1,59 @@
+// Web UIの表示言語
+//
+// ノードの /api/i18n から翻訳を読み込み、data-i18n（テキスト）と data-i18n-placeholder
+// （プレースホルダー）の要素を置き換える。翻訳がない要素はHTMLの英語のまま表示する。
+const LANGUAGE_KEY = 'rustorium.language';
+
+let messages = {};
+
+// メッセージ（翻訳がない場合は fallback）
+function t(key, fallback) {
+    return messages[key] || fallback;
+}
+
+function applyMessages() {
+    document.querySelectorAll('[data-i18n]').forEach(el => {
+        if (messages[el.dataset.i18n]) {
+            el.textContent = messages[el.dataset.i18n];
+        }
+    });
+    document.querySelectorAll('[data-i18n-placeholder]').forEach(el => {
+        if (messages[el.dataset.i18nPlaceholder]) {
+            el.placeholder = messages[el.dataset.i18nPlaceholder];
+        }
+    });
+}
+
+async function loadLanguage(language) {
+    const response = await fetch(`/api/i18n/${encodeURIComponent(language)}`);
+    if (!response.ok) {
+        throw new Error(`No translation for ${language}`);
+    }
+    messages = await response.json();
+    document.documentElement.lang = language;
+    applyMessages();
+}
+
+async function initLanguage() {
+    try {
+        const response = await fetch('/api/i18n');
+        const { language, languages } = await response.json();
+        // ブラウザで選んだ言語を優先し、なければノードの表示言語
+        const saved = localStorage.getItem(LANGUAGE_KEY);
+        const current = languages.includes(saved) ? saved : language;
+
+        const select = document.getElementById('language');
+        select.replaceChildren(...languages.map(l => new Option(l, l, false, l === current)));
+        select.hidden = false;
+        select.addEventListener('change', async () => {
+            localStorage.setItem(LANGUAGE_KEY, select.value);
+            await loadLanguage(select.value);
+        });
+
+        await loadLanguage(current);
+    } catch (error) {
+        console.error('Failed to load translations:', error);
+    }
+}
+
+initLanguage();
@@ -0,0 +1,96 @@
+// パスキーによるログインとパスキーの登録
+const message = document.getElementById('login-message');
+
+// base64urlとArrayBufferの変換
+function fromBase64Url(value) {
+    const base64 = value.replace(/-/g, '+').replace(/_/g, '/');
+    const binary = atob(base64.padEnd(Math.ceil(base64.length / 4) * 4, '='));
+    return Uint8Array.from(binary, c => c.charCodeAt(0)).buffer;
+}
+
+function toBase64Url(buffer) {
+    const binary = String.fromCharCode(...new Uint8Array(buffer));
+    return btoa(binary).replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '');
+}
+
+async function post(path, body, headers = {}) {
+    const response = await fetch(`/api/auth${path}`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json', ...headers },
+        body: JSON.stringify(body),
+    });
+    const data = await response.json().catch(() => ({}));
+    if (!response.ok) {
+        throw new Error(data.error?.message || `HTTP ${response.status}`);
+    }
+    return data;
+}
+
+// ログイン
+async function login() {
+    message.textContent = '';
+    try {
+        const { ceremony, options } = await post('/login/start', {});
+        const publicKey = options.publicKey;
+        publicKey.challenge = fromBase64Url(publicKey.challenge);
+        publicKey.allowCredentials = (publicKey.allowCredentials || [])
+            .map(c => ({ ...c, id: fromBase64Url(c.id) }));
+
+        const credential = await navigator.credentials.get({ publicKey });
+        await post('/login/finish', {
+            ceremony,
+            credential: {
+                id: credential.id,
+                rawId: toBase64Url(credential.rawId),
+                type: credential.type,
+                extensions: credential.getClientExtensionResults(),
+                response: {
+                    authenticatorData: toBase64Url(credential.response.authenticatorData),
+                    clientDataJSON: toBase64Url(credential.response.clientDataJSON),
+                    signature: toBase64Url(credential.response.signature),
+                    userHandle: credential.response.userHandle ? toBase64Url(credential.response.userHandle) : null,
+                },
+            },
+        });
+        window.location.href = '/';
+    } catch (error) {
+        message.textContent = `Sign in failed: ${error.message}`;
+    }
+}
+
+// パスキーの登録（管理者トークンが必要）
+async function register(event) {
+    event.preventDefault();
+    message.textContent = '';
+    const name = document.getElementById('register-name').value.trim();
+    const headers = { Authorization: `Bearer ${document.getElementById('register-token').value}` };
+    try {
+        const { ceremony, options } = await post('/register/start', { name }, headers);
+        const publicKey = options.publicKey;
+        publicKey.challenge = fromBase64Url(publicKey.challenge);
+        publicKey.user.id = fromBase64Url(publicKey.user.id);
+        publicKey.excludeCredentials = (publicKey.excludeCredentials || [])
+            .map(c => ({ ...c, id: fromBase64Url(c.id) }));
+
+        const credential = await navigator.credentials.create({ publicKey });
+        await post('/register/finish', {
+            ceremony,
+            credential: {
+                id: credential.id,
+                rawId: toBase64Url(credential.rawId),
+                type: credential.type,
+                extensions: credential.getClientExtensionResults(),
+                response: {
+                    attestationObject: toBase64Url(credential.response.attestationObject),
+                    clientDataJSON: toBase64Url(credential.response.clientDataJSON),
+                },
+            },
+        }, headers);
+        message.textContent = `Registered passkey "${name}". You can now sign in.`;
+    } catch (error) {
+        message.textContent = `Registration failed: ${error.message}`;
+    }
+}
+
+document.getElementById('login').addEventListener('click', login);
+document.getElementById('register').addEventListener('submit', register);
@@ -0,0 +1,182 @@
+// ウォレット（#/wallet）
+//
+// 鍵を持たない監視専用のアドレスを登録して残高を表示し、署名前のトランザクションを作成して
+// JSONかQRコードで別の端末に渡します。署名済みのJSONを読み込むとノードに送信します。
+// 秘密鍵はブラウザに渡しません（署名は `rustorium tx sign` などの外部の署名者で行う）。
+
+// 監視専用のアドレスの保存先（localStorage）
+const WATCH_ONLY_KEY = 'rustorium.watchOnly';
+
+function watchOnlyAddresses() {
+    try {
+        return JSON.parse(localStorage.getItem(WATCH_ONLY_KEY)) || [];
+    } catch (error) {
+        return [];
+    }
+}
+
+function saveWatchOnly(addresses) {
+    localStorage.setItem(WATCH_ONLY_KEY, JSON.stringify(addresses));
+}
+
+// CSRFトークン（パスキーでログインしている場合のみ必要）
+async function csrfHeaders() {
+    const response = await fetch('/api/auth/session');
+    if (!response.ok) {
+        return {};
+    }
+    const session = await response.json();
+    return session.authenticated ? { 'X-CSRF-Token': session.csrf_token } : {};
+}
+
+// エラーの本文（`{"error": {"message": ...}}`）を例外にする
+async function post(path, body, contentType = 'application/json') {
+    const response = await fetch(`/api${path}`, {
+        method: 'POST',
+        headers: { 'Content-Type': contentType, ...(await csrfHeaders()) },
+        body,
+    });
+    if (!response.ok) {
+        const error = await response.json().catch(() => null);
+        throw new Error(error?.error?.message ?? `HTTP error! status: ${response.status}`);
+    }
+    return response;
+}
+
+function download(name, text) {
+    const url = URL.createObjectURL(new Blob([text], { type: 'application/json' }));
+    el('a', { href: url, download: name }).click();
+    URL.revokeObjectURL(url);
+}
+
+function field(label, input) {
+    return el('label', {}, label, input);
+}
+
+// 監視専用のアドレスの一覧
+async function watchOnlySection() {
+    const addresses = watchOnlyAddresses();
+    const balances = await Promise.all(addresses.map((address) =>
+        api(`/accounts/${encodeURIComponent(address)}/balance`).then((b) => b.balance).catch(() => '-')));
+    const input = el('input', { type: 'text', required: '', placeholder: 'Address (hex or bech32m)' });
+    const message = el('p', { class: 'wallet-message' });
+    const form = el('form', { class: 'wallet-form' }, input, el('button', { type: 'submit' }, 'Watch'), message);
+    form.addEventListener('submit', async (event) => {
+        event.preventDefault();
+        try {
+            const { hex } = await api(`/utils/address/${encodeURIComponent(input.value.trim())}`);
+            if (!addresses.includes(hex)) {
+                saveWatchOnly([...addresses, hex]);
+            }
+            showWallet();
+        } catch (error) {
+            message.textContent = error.message;
+        }
+    });
+    const remove = (address) => {
+        const button = el('button', { type: 'button' }, 'Remove');
+        button.addEventListener('click', () => {
+            saveWatchOnly(addresses.filter((a) => a !== address));
+            showWallet();
+        });
+        return button;
+    };
+    return section('Watch-only Accounts',
+        table(['Address', 'Balance', ''], addresses.map((address, i) => [accountLink(address), balances[i], remove(address)])),
+        form);
+}
+
+// 署名前のトランザクションの作成
+function unsignedSection() {
+    const from = el('select', { required: '' }, watchOnlyAddresses().map((address) => el('option', { value: address }, address)));
+    const to = el('input', { type: 'text', required: '' });
+    const value = el('input', { type: 'number', min: '0', value: '0' });
+    const memo = el('input', { type: 'text', placeholder: 'Optional' });
+    const output = el('div', { class: 'wallet-output' });
+    const form = el('form', { class: 'wallet-form' },
+        field('From', from), field('To', to), field('Value', value), field('Memo', memo),
+        el('button', { type: 'submit' }, 'Build'));
+    form.addEventListener('submit', async (event) => {
+        event.preventDefault();
+        const request = { from: from.value, to: to.value.trim(), value: Number(value.value) };
+        if (memo.value) {
+            request.memo = { tag: 'text', payload: memo.value };
+        }
+        try {
+            const unsigned = await (await post('/transactions/unsigned', JSON.stringify(request))).json();
+            const json = JSON.stringify(unsigned);
+            const qr = URL.createObjectURL(await (await post('/utils/qr', json, 'text/plain')).blob());
+            const save = el('button', { type: 'button' }, 'Download JSON');
+            save.addEventListener('click', () => download(`${unsigned.hash}.unsigned.json`, JSON.stringify(unsigned, null, 2)));
+            output.replaceChildren(
+                el('p', {}, 'Sign this transaction on the device that holds the key, then import the signed JSON below.'),
+                el('img', { class: 'wallet-qr', src: qr, alt: 'Unsigned transaction QR code' }),
+                el('pre', { class: 'mono' }, JSON.stringify(unsigned, null, 2)),
+                save);
+        } catch (error) {
+            output.replaceChildren(el('p', { class: 'wallet-message' }, error.message));
+        }
+    });
+    return section('Build Unsigned Transaction',
+        watchOnlyAddresses().length === 0 ? el('p', {}, 'Watch an address first.') : [form, output]);
+}
+
+// 署名済みのトランザクションの読み込みと送信
+function broadcastSection() {
+    const text = el('textarea', { rows: '8', placeholder: 'Signed transaction JSON' });
+    const file = el('input', { type: 'file', accept: 'application/json' });
+    const message = el('p', { class: 'wallet-message' });
+    file.addEventListener('change', async () => {
+        text.value = await file.files[0].text();
+    });
+    const form = el('form', { class: 'wallet-form' }, text, file, el('button', { type: 'submit' }, 'Broadcast'));
+    // カメラでQRコードを読み取れるブラウザのみ
+    if ('BarcodeDetector' in window) {
+        const scan = el('button', { type: 'button' }, 'Scan QR');
+        scan.addEventListener('click', async () => {
+            try {
+                text.value = await scanQr();
+            } catch (error) {
+                message.textContent = error.message;
+            }
+        });
+        form.append(scan);
+    }
+    form.append(message);
+    form.addEventListener('submit', async (event) => {
+        event.preventDefault();
+        try {
+            const response = await (await post('/transactions', text.value)).json();
+            message.replaceChildren('Submitted ', txLink(response.hash));
+        } catch (error) {
+            message.textContent = error.message;
+        }
+    });
+    return section('Broadcast Signed Transaction', form);
+}
+
+// カメラの映像からQRコードを1つ読み取る
+async function scanQr() {
+    const stream = await navigator.mediaDevices.getUserMedia({ video: { facingMode: 'environment' } });
+    const video = el('video', { class: 'wallet-qr', playsinline: '' });
+    video.srcObject = stream;
+    explorer.append(video);
+    await video.play();
+    const detector = new BarcodeDetector({ formats: ['qr_code'] });
+    try {
+        for (;;) {
+            const [code] = await detector.detect(video);
+            if (code) {
+                return code.rawValue;
+            }
+            await new Promise((resolve) => setTimeout(resolve, 250));
+        }
+    } finally {
+        stream.getTracks().forEach((track) => track.stop());
+        video.remove();
+    }
+}
+
+async function showWallet() {
+    render(await watchOnlySection(), unsignedSection(), broadcastSection());
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustorium::core::consensus::messages::ConsensusMessage;
+
+// 合意形成のメッセージのデコード
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = ConsensusMessage::decode(data) {
+        // デコードできた入力は正規のエンコーディングなので、再エンコードでも同じバイト列になること
+        let encoded = message.encode().expect("decoded message must re-encode");
+        assert_eq!(encoded, data);
+    }
+});
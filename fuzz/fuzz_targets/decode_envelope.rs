@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustorium::core::network::quic::Message;
+
+// QUICで受信するメッセージエンベロープのデコード
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = Message::decode(data) {
+        // デコードできた入力は再エンコードでも同じバイト列になること
+        let encoded = message.encode().expect("decoded message must re-encode");
+        assert_eq!(encoded, data);
+    }
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustorium_core::codec::decode_transaction;
+
+// 任意のバイト列に対してデコーダーがパニックしないことを確認
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_transaction(data);
+});
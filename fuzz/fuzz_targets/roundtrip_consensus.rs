@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustorium::core::consensus::messages::ConsensusMessage;
+
+// 構造化された入力でエンコード/デコードの往復を確認
+fuzz_target!(|message: ConsensusMessage| {
+    let Ok(bytes) = message.encode() else {
+        return;
+    };
+    let decoded = ConsensusMessage::decode(&bytes).expect("encoded message must decode");
+    assert_eq!(decoded, message);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustorium_core::codec::{decode_transaction, encode_transaction};
+use rustorium_core::types::Transaction;
+
+// 構造化された入力でエンコード/デコードの往復を確認
+fuzz_target!(|tx: Transaction| {
+    let Ok(bytes) = encode_transaction(&tx) else {
+        return;
+    };
+    let decoded = decode_transaction(&bytes).expect("encoded transaction must decode");
+    assert_eq!(encode_transaction(&decoded).unwrap(), bytes);
+});
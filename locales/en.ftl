@@ -0,0 +1,58 @@
+# Rustorium messages (English)
+#
+# This file is the reference translation: every other locale falls back to it.
+# Copy it to <language>.ftl (e.g. fr.ftl, pt-BR.ftl) to add a translation.
+# `{ $name }` is replaced with a value when the message is shown.
+
+## Console
+welcome = Welcome to Rustorium!
+select_action = Select an action to perform:
+account = Account Management
+transaction = Transactions
+smart_contract = Smart Contracts
+blockchain = Blockchain Info
+settings = Settings
+exit = Exit
+node_status = Node Status
+network_info = Network Information
+peers = Peer Management
+language = Language
+language_changed = Language changed to { $language }
+press_enter = Press Enter to return
+press_any_key = Press any key to enter interactive mode... ({ $seconds })
+non_interactive = Non-interactive environment detected, running in background mode...
+background_mode = Continuing in background mode...
+entering_interactive = Entering interactive mode...
+exiting = Exiting...
+
+## Web UI
+nav_dashboard = Dashboard
+nav_blocks = Blocks
+nav_validators = Validators
+nav_wallet = Wallet
+search_placeholder = Block height, hash, transaction or address
+status_label = Status:
+status_connecting = Connecting...
+status_connected = Connected
+status_disconnected = Disconnected
+sign_out = Sign out
+system_metrics = System Metrics
+cpu_cores = CPU Cores
+memory = Memory
+role = Role
+network = Network
+p2p_port = P2P Port
+web_port = Web Port
+api_port = API Port
+websocket_port = WebSocket Port
+performance = Performance
+max_peers = Max Peers
+pending_transactions = Pending Transactions
+block_time = Block Time
+shards = Shards
+shard = Shard
+validators = Validators
+accounts = Accounts
+storage = Storage
+tps = TPS
+cross_shard = Cross-shard
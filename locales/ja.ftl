@@ -0,0 +1,54 @@
+# Rustorium のメッセージ（日本語）
+
+## コンソール
+welcome = Rustoriumへようこそ！
+select_action = 実行したいアクションを選択してください：
+account = アカウント管理
+transaction = トランザクション
+smart_contract = スマートコントラクト
+blockchain = ブロックチェーン情報
+settings = 設定
+exit = 終了
+node_status = ノードの状態
+network_info = ネットワーク情報
+peers = ピア管理
+language = 表示言語
+language_changed = 表示言語を { $language } に切り替えました
+press_enter = Enterキーで戻る
+press_any_key = いずれかのキーを押すとインタラクティブモードに入ります...（{ $seconds }）
+non_interactive = 非インタラクティブ環境のため、バックグラウンドで実行します...
+background_mode = バックグラウンドで実行を続けます...
+entering_interactive = インタラクティブモードに入ります...
+exiting = 終了しています...
+
+## Web UI
+nav_dashboard = ダッシュボード
+nav_blocks = ブロック
+nav_validators = バリデーター
+nav_wallet = ウォレット
+search_placeholder = ブロック高、ハッシュ、トランザクション、アドレス
+status_label = ステータス:
+status_connecting = 接続中...
+status_connected = 接続済み
+status_disconnected = 切断
+sign_out = ログアウト
+system_metrics = システムメトリクス
+cpu_cores = CPUコア数
+memory = メモリ
+role = 役割
+network = ネットワーク
+p2p_port = P2Pポート
+web_port = Webポート
+api_port = APIポート
+websocket_port = WebSocketポート
+performance = パフォーマンス
+max_peers = 最大ピア数
+pending_transactions = 保留中のトランザクション
+block_time = ブロック時間
+shards = シャード
+shard = シャード
+validators = バリデーター
+accounts = アカウント
+storage = ストレージ
+tps = TPS
+cross_shard = クロスシャード
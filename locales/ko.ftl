@@ -0,0 +1,49 @@
+# Rustorium 메시지 (한국어)
+
+## 콘솔
+welcome = Rustorium에 오신 것을 환영합니다!
+select_action = 실행할 작업을 선택하세요:
+account = 계정 관리
+transaction = 트랜잭션
+smart_contract = 스마트 컨트랙트
+blockchain = 블록체인 정보
+settings = 설정
+exit = 종료
+node_status = 노드 상태
+network_info = 네트워크 정보
+peers = 피어 관리
+language = 언어
+language_changed = 언어를 { $language }(으)로 변경했습니다
+press_enter = Enter 키를 눌러 돌아가기
+press_any_key = 아무 키나 누르면 대화형 모드로 전환합니다... ({ $seconds })
+non_interactive = 비대화형 환경이 감지되어 백그라운드 모드로 실행합니다...
+background_mode = 백그라운드 모드로 계속 실행합니다...
+entering_interactive = 대화형 모드로 전환합니다...
+exiting = 종료하는 중...
+
+## Web UI
+nav_dashboard = 대시보드
+nav_blocks = 블록
+nav_validators = 검증자
+nav_wallet = 지갑
+search_placeholder = 블록 높이, 해시, 트랜잭션 또는 주소
+status_label = 상태:
+status_connecting = 연결 중...
+status_connected = 연결됨
+status_disconnected = 연결 끊김
+sign_out = 로그아웃
+system_metrics = 시스템 지표
+cpu_cores = CPU 코어
+memory = 메모리
+role = 역할
+network = 네트워크
+performance = 성능
+max_peers = 최대 피어 수
+pending_transactions = 대기 중인 트랜잭션
+block_time = 블록 시간
+shards = 샤드
+shard = 샤드
+validators = 검증자
+accounts = 계정
+storage = 스토리지
+cross_shard = 크로스 샤드
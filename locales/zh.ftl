@@ -0,0 +1,49 @@
+# Rustorium 消息（简体中文）
+
+## 控制台
+welcome = 欢迎使用 Rustorium！
+select_action = 请选择要执行的操作：
+account = 账户管理
+transaction = 交易
+smart_contract = 智能合约
+blockchain = 区块链信息
+settings = 设置
+exit = 退出
+node_status = 节点状态
+network_info = 网络信息
+peers = 节点管理
+language = 语言
+language_changed = 语言已切换为 { $language }
+press_enter = 按回车键返回
+press_any_key = 按任意键进入交互模式...（{ $seconds }）
+non_interactive = 检测到非交互环境，将在后台运行...
+background_mode = 继续在后台运行...
+entering_interactive = 正在进入交互模式...
+exiting = 正在退出...
+
+## Web UI
+nav_dashboard = 仪表盘
+nav_blocks = 区块
+nav_validators = 验证者
+nav_wallet = 钱包
+search_placeholder = 区块高度、哈希、交易或地址
+status_label = 状态:
+status_connecting = 连接中...
+status_connected = 已连接
+status_disconnected = 已断开
+sign_out = 退出登录
+system_metrics = 系统指标
+cpu_cores = CPU 核心数
+memory = 内存
+role = 角色
+network = 网络
+performance = 性能
+max_peers = 最大节点数
+pending_transactions = 待处理交易
+block_time = 出块时间
+shards = 分片
+shard = 分片
+validators = 验证者
+accounts = 账户
+storage = 存储
+cross_shard = 跨分片
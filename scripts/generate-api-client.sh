@@ -0,0 +1,22 @@
+#!/bin/bash
+# APIのOpenAPIドキュメントからフロントエンドの型定義を生成する
+#
+# 使い方:
+#   scripts/generate-api-client.sh          # frontend/js/api.d.ts を更新
+#   scripts/generate-api-client.sh --check  # 生成結果がコミット済みの内容と異なれば失敗（CI用）
+set -e
+
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+SPEC="$ROOT/frontend/api/openapi.json"
+TYPES="$ROOT/frontend/js/api.d.ts"
+
+mkdir -p "$(dirname "$SPEC")"
+cargo run --quiet --manifest-path "$ROOT/Cargo.toml" -- openapi --output "$SPEC"
+npx --yes openapi-typescript@7 "$SPEC" --output "$TYPES"
+
+if [ "$1" = "--check" ]; then
+    if ! git -C "$ROOT" diff --exit-code -- "$SPEC" "$TYPES"; then
+        echo "API types are out of date; run scripts/generate-api-client.sh and commit the result" >&2
+        exit 1
+    fi
+fi
@@ -0,0 +1,102 @@
+//! ベンチマークツール
+//!
+//! `rustorium bench` サブコマンドの実装です。
+//! 主な機能：
+//! - ストレージバックエンドの比較計測
+//! - トランザクションスループットの負荷生成
+//! - レイテンシ分布の集計
+
+pub mod storage;
+pub mod tps;
+
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+/// レイテンシの記録
+#[derive(Debug, Default, Clone)]
+pub struct LatencyRecorder {
+    samples: Vec<Duration>,
+}
+
+impl LatencyRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// サンプルを追加
+    pub fn record(&mut self, latency: Duration) {
+        self.samples.push(latency);
+    }
+
+    /// サンプル数
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 集計結果を取得
+    pub fn summary(&self) -> LatencySummary {
+        if self.samples.is_empty() {
+            return LatencySummary::default();
+        }
+
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+
+        LatencySummary {
+            count: sorted.len() as u64,
+            mean_us: total.as_micros() as f64 / sorted.len() as f64,
+            p50_us: percentile(&sorted, 0.50).as_micros() as u64,
+            p90_us: percentile(&sorted, 0.90).as_micros() as u64,
+            p99_us: percentile(&sorted, 0.99).as_micros() as u64,
+            max_us: sorted[sorted.len() - 1].as_micros() as u64,
+        }
+    }
+}
+
+/// ソート済みサンプルからパーセンタイルを取得
+fn percentile(sorted: &[Duration], q: f64) -> Duration {
+    let rank = ((sorted.len() as f64) * q).ceil() as usize;
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+}
+
+/// レイテンシの集計結果（マイクロ秒）
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub mean_us: f64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut recorder = LatencyRecorder::new();
+        for i in 1..=100 {
+            recorder.record(Duration::from_micros(i));
+        }
+
+        let summary = recorder.summary();
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50_us, 50);
+        assert_eq!(summary.p99_us, 99);
+        assert_eq!(summary.max_us, 100);
+    }
+
+    #[test]
+    fn test_empty_summary() {
+        let summary = LatencyRecorder::new().summary();
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.p99_us, 0);
+    }
+}
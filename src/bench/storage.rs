@@ -0,0 +1,250 @@
+//! ストレージバックエンドのベンチマーク
+//!
+//! 運用環境のハードウェア上で各バックエンドの put/get/scan/batch を計測し、
+//! バックエンド選択の判断材料となる比較レポートを出力します。
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use tracing::info;
+use crate::core::storage::{
+    StorageEngine,
+    RocksDBStorage,
+    redb_storage::{RedbStorage, StorageConfig},
+    tikv::TikvStorage,
+};
+use super::{LatencyRecorder, LatencySummary};
+
+/// 計測対象のバックエンド
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Rocksdb,
+    Tikv,
+    Redb,
+}
+
+impl Backend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rocksdb => "rocksdb",
+            Self::Tikv => "tikv",
+            Self::Redb => "redb",
+        }
+    }
+}
+
+/// ベンチマーク設定
+#[derive(Debug, Clone)]
+pub struct StorageBenchConfig {
+    /// 計測対象
+    pub backends: Vec<Backend>,
+    /// 各操作の実行回数
+    pub operations: usize,
+    /// 値のサイズ（バイト）
+    pub value_size: usize,
+    /// バッチ書き込みのサイズ
+    pub batch_size: usize,
+    /// スキャン1回あたりの件数
+    pub scan_limit: usize,
+    /// ローカルバックエンドの作業ディレクトリ
+    pub work_dir: PathBuf,
+    /// TiKVのPDエンドポイント
+    pub tikv_endpoints: Vec<String>,
+}
+
+impl Default for StorageBenchConfig {
+    fn default() -> Self {
+        Self {
+            backends: vec![Backend::Rocksdb, Backend::Redb],
+            operations: 10_000,
+            value_size: 256,
+            batch_size: 100,
+            scan_limit: 100,
+            work_dir: std::env::temp_dir().join("rustorium-bench"),
+            tikv_endpoints: vec!["127.0.0.1:2379".to_string()],
+        }
+    }
+}
+
+/// 操作ごとの計測結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationReport {
+    pub operation: String,
+    /// 1秒あたりの処理キー数
+    pub throughput: f64,
+    pub latency: LatencySummary,
+}
+
+/// バックエンドごとの計測結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendReport {
+    pub backend: Backend,
+    pub operations: Vec<OperationReport>,
+}
+
+impl BackendReport {
+    fn operation(&self, name: &str) -> Option<&OperationReport> {
+        self.operations.iter().find(|o| o.operation == name)
+    }
+}
+
+/// 比較レポート
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonReport {
+    pub value_size: usize,
+    pub operations: usize,
+    pub backends: Vec<BackendReport>,
+    /// 操作ごとの最速バックエンド
+    pub fastest: Vec<(String, Backend)>,
+}
+
+impl ComparisonReport {
+    fn new(config: &StorageBenchConfig, backends: Vec<BackendReport>) -> Self {
+        let mut fastest = Vec::new();
+        for op in ["put", "get", "scan", "batch"] {
+            let best = backends
+                .iter()
+                .filter_map(|b| b.operation(op).map(|o| (b.backend, o.throughput)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((backend, _)) = best {
+                fastest.push((op.to_string(), backend));
+            }
+        }
+
+        Self {
+            value_size: config.value_size,
+            operations: config.operations,
+            backends,
+            fastest,
+        }
+    }
+
+    /// 表形式のテキストに変換
+    pub fn to_table(&self) -> String {
+        let mut out = format!(
+            "Storage benchmark ({} ops, {} byte values)\n\n{:<10} {:<8} {:>14} {:>10} {:>10} {:>10}\n",
+            self.operations, self.value_size, "backend", "op", "keys/s", "p50(us)", "p99(us)", "max(us)"
+        );
+        for backend in &self.backends {
+            for op in &backend.operations {
+                out.push_str(&format!(
+                    "{:<10} {:<8} {:>14.0} {:>10} {:>10} {:>10}\n",
+                    backend.backend.name(),
+                    op.operation,
+                    op.throughput,
+                    op.latency.p50_us,
+                    op.latency.p99_us,
+                    op.latency.max_us,
+                ));
+            }
+        }
+        if !self.fastest.is_empty() {
+            out.push('\n');
+            for (op, backend) in &self.fastest {
+                out.push_str(&format!("fastest {:<6}: {}\n", op, backend.name()));
+            }
+        }
+        out
+    }
+}
+
+/// 設定されたすべてのバックエンドを計測
+pub async fn run(config: &StorageBenchConfig) -> Result<ComparisonReport> {
+    let mut reports = Vec::new();
+    for backend in &config.backends {
+        info!("Benchmarking storage backend: {}", backend.name());
+        let engine = open_backend(*backend, config).await?;
+        let operations = bench_engine(engine.as_ref(), config).await?;
+        reports.push(BackendReport { backend: *backend, operations });
+    }
+    Ok(ComparisonReport::new(config, reports))
+}
+
+/// バックエンドを開く
+async fn open_backend(backend: Backend, config: &StorageBenchConfig) -> Result<Arc<dyn StorageEngine>> {
+    let dir = config.work_dir.join(backend.name());
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+
+    let engine: Arc<dyn StorageEngine> = match backend {
+        Backend::Rocksdb => Arc::new(RocksDBStorage::new(&dir)?),
+        Backend::Redb => Arc::new(RedbStorage::new(StorageConfig {
+            path: dir.to_string_lossy().to_string(),
+            ..Default::default()
+        })?),
+        Backend::Tikv => Arc::new(
+            TikvStorage::connect(config.tikv_endpoints.clone())
+                .await
+                .map_err(|e| anyhow!("Failed to connect to TiKV: {}", e))?,
+        ),
+    };
+    Ok(engine)
+}
+
+/// 単一バックエンドの計測
+pub async fn bench_engine(engine: &dyn StorageEngine, config: &StorageBenchConfig) -> Result<Vec<OperationReport>> {
+    let value = vec![0xabu8; config.value_size];
+    let key = |i: usize| format!("bench/{:016}", i).into_bytes();
+    let mut reports = Vec::new();
+
+    // put
+    let mut recorder = LatencyRecorder::new();
+    let started = Instant::now();
+    for i in 0..config.operations {
+        let t = Instant::now();
+        engine.put(&key(i), &value).await?;
+        recorder.record(t.elapsed());
+    }
+    reports.push(report("put", config.operations, started, &recorder));
+
+    // get
+    let mut recorder = LatencyRecorder::new();
+    let started = Instant::now();
+    for i in 0..config.operations {
+        let t = Instant::now();
+        engine.get(&key(i)).await?;
+        recorder.record(t.elapsed());
+    }
+    reports.push(report("get", config.operations, started, &recorder));
+
+    // scan
+    let scans = (config.operations / config.scan_limit.max(1)).max(1);
+    let mut recorder = LatencyRecorder::new();
+    let mut scanned = 0;
+    let started = Instant::now();
+    for i in 0..scans {
+        let t = Instant::now();
+        scanned += engine.scan(&key(i * config.scan_limit), config.scan_limit).await?.len();
+        recorder.record(t.elapsed());
+    }
+    reports.push(report("scan", scanned, started, &recorder));
+
+    // batch
+    let batches = (config.operations / config.batch_size.max(1)).max(1);
+    let mut recorder = LatencyRecorder::new();
+    let started = Instant::now();
+    for b in 0..batches {
+        let batch = (0..config.batch_size)
+            .map(|i| (key(config.operations + b * config.batch_size + i), Some(value.clone())))
+            .collect();
+        let t = Instant::now();
+        engine.batch_write(batch).await?;
+        recorder.record(t.elapsed());
+    }
+    reports.push(report("batch", batches * config.batch_size, started, &recorder));
+
+    Ok(reports)
+}
+
+fn report(operation: &str, keys: usize, started: Instant, recorder: &LatencyRecorder) -> OperationReport {
+    let elapsed = started.elapsed().as_secs_f64();
+    OperationReport {
+        operation: operation.to_string(),
+        throughput: if elapsed > 0.0 { keys as f64 / elapsed } else { 0.0 },
+        latency: recorder.summary(),
+    }
+}
@@ -0,0 +1,420 @@
+//! トランザクションスループット負荷生成
+//!
+//! 署名済みの送金トランザクションを一定レートでノードまたはdevnetに送信し、
+//! 受付・確定までのレイテンシ分布を計測します。
+//! 送金元のアカウントはシードから決まるため、同じシードで繰り返し実行できます。資金のない
+//! アカウントは拒否されるので、初回は `funder` から資金を配ってから計測します。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use ed25519_dalek::SigningKey;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use reqwest::Client;
+use serde::{Serialize, Deserialize};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use crate::core::mempool::PendingTransaction;
+use crate::core::wallet::{self, SignedTransaction};
+use super::{LatencyRecorder, LatencySummary};
+
+/// 送金のガス上限
+const TRANSFER_GAS: u64 = 21_000;
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+/// 負荷生成の設定
+#[derive(Debug, Clone)]
+pub struct TpsBenchConfig {
+    /// 送信先APIのベースURL
+    pub endpoint: String,
+    /// 送金に使うアカウント数
+    pub accounts: usize,
+    /// 目標送信レート（tx/s）
+    pub rate: u64,
+    /// 送信を続ける時間
+    pub duration: Duration,
+    /// 確定待ちのタイムアウト
+    pub confirm_timeout: Duration,
+    /// 確定確認のポーリング間隔
+    pub poll_interval: Duration,
+    /// アカウント生成用のシード
+    pub seed: u64,
+    /// ガス価格（`None` の場合はノードが受け付ける最低価格）
+    pub gas_price: Option<u64>,
+    /// 計測の前に各アカウントへ資金を配る鍵（`None` の場合は資金があること）
+    pub funder: Option<SigningKey>,
+    /// 各アカウントに配る額
+    pub fund_amount: u64,
+}
+
+impl Default for TpsBenchConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:9071/api".to_string(),
+            accounts: 1000,
+            rate: 5000,
+            duration: Duration::from_secs(30),
+            confirm_timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(200),
+            seed: 0,
+            gas_price: None,
+            funder: None,
+            fund_amount: 1_000_000,
+        }
+    }
+}
+
+/// トランザクションごとの計測結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxSample {
+    pub tx_id: Option<String>,
+    pub accepted: bool,
+    pub accept_ms: f64,
+    pub confirmed: bool,
+    pub confirm_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// 負荷生成の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TpsReport {
+    pub target_rate: u64,
+    pub achieved_rate: f64,
+    pub confirmed_rate: f64,
+    pub sent: u64,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub confirmed: u64,
+    pub timed_out: u64,
+    pub accept_latency: LatencySummary,
+    pub confirm_latency: LatencySummary,
+    #[serde(skip)]
+    pub samples: Vec<TxSample>,
+}
+
+impl TpsReport {
+    /// トランザクションごとのCSVに変換
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("tx_id,accepted,accept_ms,confirmed,confirm_ms,error\n");
+        for s in &self.samples {
+            out.push_str(&format!(
+                "{},{},{:.3},{},{},{}\n",
+                s.tx_id.as_deref().unwrap_or(""),
+                s.accepted,
+                s.accept_ms,
+                s.confirmed,
+                s.confirm_ms.map(|ms| format!("{:.3}", ms)).unwrap_or_default(),
+                s.error.as_deref().unwrap_or("").replace(',', ";"),
+            ));
+        }
+        out
+    }
+
+    /// 表形式のテキストに変換
+    pub fn to_table(&self) -> String {
+        format!(
+            "TPS benchmark\n\n\
+             target rate     : {} tx/s\n\
+             achieved rate   : {:.1} tx/s\n\
+             confirmed rate  : {:.1} tx/s\n\
+             sent/accepted   : {}/{} (rejected {})\n\
+             confirmed       : {} (timed out {})\n\
+             accept  p50/p99 : {}us / {}us\n\
+             confirm p50/p99 : {}us / {}us\n",
+            self.target_rate,
+            self.achieved_rate,
+            self.confirmed_rate,
+            self.sent,
+            self.accepted,
+            self.rejected,
+            self.confirmed,
+            self.timed_out,
+            self.accept_latency.p50_us,
+            self.accept_latency.p99_us,
+            self.confirm_latency.p50_us,
+            self.confirm_latency.p99_us,
+        )
+    }
+}
+
+/// 負荷生成用アカウント
+struct LoadAccount {
+    key: SigningKey,
+    address: String,
+    nonce: u64,
+}
+
+/// 署名に使うノードのパラメーター
+#[derive(Debug, Clone, Copy)]
+struct ChainParams {
+    gas_price: u64,
+    chain_id: u64,
+}
+
+/// `GET /accounts/{address}/nonce` の応答
+#[derive(Debug, Deserialize)]
+struct NonceResponse {
+    next_nonce: u64,
+    gas_price: u64,
+    chain_id: u64,
+}
+
+/// `POST /transactions` の応答
+#[derive(Debug, Deserialize)]
+struct SubmitResponse {
+    hash: String,
+}
+
+/// 負荷生成を実行
+pub async fn run(config: &TpsBenchConfig) -> Result<TpsReport> {
+    let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut accounts = Vec::with_capacity(config.accounts.max(2));
+    let mut params = None;
+    for _ in 0..config.accounts.max(2) {
+        let key = SigningKey::generate(&mut rng);
+        let address = wallet::address_of(&key.verifying_key());
+        let current = next_nonce(&client, config, &address).await?;
+        params.get_or_insert(ChainParams {
+            gas_price: config.gas_price.unwrap_or(current.gas_price),
+            chain_id: current.chain_id,
+        });
+        accounts.push(LoadAccount { key, address, nonce: current.next_nonce });
+    }
+    let params = params.expect("at least two accounts");
+    if let Some(funder) = &config.funder {
+        fund(&client, config, params, funder, &accounts).await?;
+    }
+    let addresses: Arc<Vec<String>> = Arc::new(accounts.iter().map(|a| a.address.clone()).collect());
+    let accounts: Vec<Arc<Mutex<LoadAccount>>> = accounts.into_iter().map(|a| Arc::new(Mutex::new(a))).collect();
+
+    info!(
+        "Generating load: {} tx/s for {:?} across {} accounts",
+        config.rate, config.duration, accounts.len()
+    );
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let period = Duration::from_secs_f64(1.0 / config.rate.max(1) as f64);
+    let mut ticker = tokio::time::interval(period);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+
+    let started = Instant::now();
+    let mut handles = Vec::new();
+    let mut sent = 0u64;
+    while started.elapsed() < config.duration {
+        ticker.tick().await;
+
+        let from = accounts[sent as usize % accounts.len()].clone();
+        let to = addresses[rng.gen_range(0..addresses.len())].clone();
+        let value = rng.gen_range(1..=1_000u64);
+        let client = client.clone();
+        let samples = samples.clone();
+        let config = config.clone();
+        handles.push(tokio::spawn(async move {
+            let sample = send_transfer(&client, &config, params, from, to, value).await;
+            samples.lock().await.push(sample);
+        }));
+        sent += 1;
+    }
+    let send_elapsed = started.elapsed().as_secs_f64();
+
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    let samples = Arc::try_unwrap(samples)
+        .map(|m| m.into_inner())
+        .unwrap_or_default();
+    Ok(summarize(config, sent, send_elapsed, samples))
+}
+
+/// アドレスの次のノンスとガス価格・チェーンID
+async fn next_nonce(client: &Client, config: &TpsBenchConfig, address: &str) -> Result<NonceResponse> {
+    Ok(client
+        .get(format!("{}/accounts/{}/nonce", config.endpoint, address))
+        .send().await?
+        .error_for_status()?
+        .json().await?)
+}
+
+/// 送金に署名して送信し、ハッシュを返す
+async fn submit(
+    client: &Client,
+    config: &TpsBenchConfig,
+    params: ChainParams,
+    key: &SigningKey,
+    to: String,
+    value: u64,
+    nonce: u64,
+) -> Result<String> {
+    let tx = PendingTransaction {
+        hash: String::new(),
+        from: wallet::address_of(&key.verifying_key()),
+        to,
+        value,
+        nonce,
+        gas_price: params.gas_price,
+        gas_limit: TRANSFER_GAS,
+        data: Vec::new(),
+        received_at: 0,
+        valid_until: None,
+        chain_id: Some(params.chain_id),
+        blob: None,
+        signature: None,
+    };
+    let response = client
+        .post(format!("{}/transactions", config.endpoint))
+        .json(&SignedTransaction::new(key, &tx))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        anyhow::bail!("status {}: {}", status, response.text().await.unwrap_or_default());
+    }
+    Ok(response.json::<SubmitResponse>().await?.hash)
+}
+
+/// 確定するまで待つ（`GET /transactions/{hash}` は確定までは404を返す）
+async fn wait_confirmed(client: &Client, config: &TpsBenchConfig, hash: &str, deadline: Instant) -> bool {
+    while Instant::now() < deadline {
+        tokio::time::sleep(config.poll_interval).await;
+        let status = client
+            .get(format!("{}/transactions/{}", config.endpoint, hash))
+            .send()
+            .await;
+        if matches!(status, Ok(resp) if resp.status().is_success()) {
+            return true;
+        }
+    }
+    false
+}
+
+/// 各アカウントに資金を配り、確定を待つ
+async fn fund(
+    client: &Client,
+    config: &TpsBenchConfig,
+    params: ChainParams,
+    funder: &SigningKey,
+    accounts: &[LoadAccount],
+) -> Result<()> {
+    let from = wallet::address_of(&funder.verifying_key());
+    let mut nonce = next_nonce(client, config, &from).await?.next_nonce;
+    info!("Funding {} accounts with {} each from {}", accounts.len(), config.fund_amount, from);
+    let mut last = None;
+    for account in accounts {
+        last = Some(submit(client, config, params, funder, account.address.clone(), config.fund_amount, nonce).await?);
+        nonce += 1;
+    }
+    // 送金元のノンスの順に確定するため、最後の送金の確定で全て確定している
+    if let Some(hash) = last {
+        if !wait_confirmed(client, config, &hash, Instant::now() + config.confirm_timeout).await {
+            anyhow::bail!("Funding transaction {} was not confirmed within {:?}", hash, config.confirm_timeout);
+        }
+    }
+    Ok(())
+}
+
+/// 送金を1件送信し、確定まで追跡
+async fn send_transfer(
+    client: &Client,
+    config: &TpsBenchConfig,
+    params: ChainParams,
+    from: Arc<Mutex<LoadAccount>>,
+    to: String,
+    value: u64,
+) -> TxSample {
+    // 同じ送信者の送金はノンスの順に届くよう、受付まで送信者をロックする
+    let (submitted, result) = {
+        let mut account = from.lock().await;
+        let submitted = Instant::now();
+        let result = submit(client, config, params, &account.key, to, value, account.nonce).await;
+        if result.is_ok() {
+            account.nonce += 1;
+        }
+        (submitted, result)
+    };
+    let accept_ms = submitted.elapsed().as_secs_f64() * 1000.0;
+
+    let tx_id = match result {
+        Ok(hash) => hash,
+        Err(e) => return rejected(accept_ms, e.to_string()),
+    };
+
+    if wait_confirmed(client, config, &tx_id, submitted + config.confirm_timeout).await {
+        return TxSample {
+            tx_id: Some(tx_id),
+            accepted: true,
+            accept_ms,
+            confirmed: true,
+            confirm_ms: Some(submitted.elapsed().as_secs_f64() * 1000.0),
+            error: None,
+        };
+    }
+
+    warn!("Transaction {} not confirmed within {:?}", tx_id, config.confirm_timeout);
+    TxSample {
+        tx_id: Some(tx_id),
+        accepted: true,
+        accept_ms,
+        confirmed: false,
+        confirm_ms: None,
+        error: Some("confirmation timeout".to_string()),
+    }
+}
+
+fn rejected(accept_ms: f64, error: String) -> TxSample {
+    TxSample {
+        tx_id: None,
+        accepted: false,
+        accept_ms,
+        confirmed: false,
+        confirm_ms: None,
+        error: Some(error),
+    }
+}
+
+/// 計測結果を集計
+fn summarize(config: &TpsBenchConfig, sent: u64, send_elapsed: f64, samples: Vec<TxSample>) -> TpsReport {
+    let mut accept = LatencyRecorder::new();
+    let mut confirm = LatencyRecorder::new();
+    let (mut accepted, mut confirmed, mut timed_out) = (0, 0, 0);
+    let mut last_confirm_ms: f64 = 0.0;
+
+    for s in &samples {
+        if s.accepted {
+            accepted += 1;
+            accept.record(Duration::from_secs_f64(s.accept_ms / 1000.0));
+        }
+        match s.confirm_ms {
+            Some(ms) => {
+                confirmed += 1;
+                confirm.record(Duration::from_secs_f64(ms / 1000.0));
+                last_confirm_ms = last_confirm_ms.max(ms);
+            }
+            None if s.accepted => timed_out += 1,
+            None => {}
+        }
+    }
+
+    let confirm_window = send_elapsed.max(last_confirm_ms / 1000.0);
+    TpsReport {
+        target_rate: config.rate,
+        achieved_rate: if send_elapsed > 0.0 { accepted as f64 / send_elapsed } else { 0.0 },
+        confirmed_rate: if confirm_window > 0.0 { confirmed as f64 / confirm_window } else { 0.0 },
+        sent,
+        accepted,
+        rejected: sent - accepted,
+        confirmed,
+        timed_out,
+        accept_latency: accept.summary(),
+        confirm_latency: confirm.summary(),
+        samples,
+    }
+}
@@ -87,8 +87,9 @@ impl InteractiveConsole {
         term.clear_screen()?;
 
         // インタラクティブモードが利用できない場合は即座にバックグラウンドモードへ
+        let locale = service_manager.locale();
         if !Self::is_interactive() {
-            println!("{}", style("Non-interactive environment detected, running in background mode...").dim());
+            println!("{}", style(locale.get_message("non_interactive")).dim());
             return Ok(());
         }
         
@@ -111,11 +112,13 @@ impl InteractiveConsole {
         let latency = service_manager.get_average_latency().await;
         let block_count = service_manager.get_block_count().await;
 
-        // ポート情報を取得
-        let base_port = service_manager.config().network.port;
-        let web_port = base_port;
-        let api_port = base_port + 1;
-        let ws_port = base_port + 2;
+        // ポート情報を取得（実際に待ち受けているポート）
+        let port = |name: &str| service_manager.endpoints()
+            .and_then(|manifest| manifest.get(name))
+            .map_or_else(|| "-".to_string(), |endpoint| endpoint.address.port().to_string());
+        let web_port = port("web");
+        let api_port = port("api");
+        let ws_port = port("websocket");
 
         // ロゴを表示（動的な情報を含む）
         let logo = STATUS_LOGO_TEMPLATE.replace(
@@ -171,7 +174,7 @@ impl InteractiveConsole {
         while countdown > 0 && !had_input {
             // カウントダウンを表示（前の行を消去してから）
             print!("\r\x1B[K{}", 
-                style(format!("Press any key to enter interactive mode... ({})", countdown)).dim()
+                style(locale.format("press_any_key", &[("seconds", &countdown.to_string())])).dim()
             );
             io::stdout().flush()?;
 
@@ -205,26 +208,25 @@ impl InteractiveConsole {
         println!();  // 改行
 
         if !had_input {
-            println!("{}", style("Continuing in background mode...").dim());
+            println!("{}", style(locale.get_message("background_mode")).dim());
             return Ok(());
         }
 
-        println!("{}", style("Entering interactive mode...").cyan());
-
-        // メインメニューを表示
-        let menu_items = vec![
-            "📊 Node Status",
-            "🌍 Network Information",
-            "📦 Blockchain Information",
-            "🔗 Peer Management",
-            "⚙️  Settings",
-            "❌ Exit",
-        ];
+        println!("{}", style(locale.get_message("entering_interactive")).cyan());
 
         let _rl = DefaultEditor::new()?;
         loop {
+            // メインメニューを表示（表示言語の切り替えを反映するため毎回作成する）
+            let menu_items = vec![
+                format!("📊 {}", locale.get_message("node_status")),
+                format!("🌍 {}", locale.get_message("network_info")),
+                format!("📦 {}", locale.get_message("blockchain")),
+                format!("🔗 {}", locale.get_message("peers")),
+                format!("⚙️  {}", locale.get_message("settings")),
+                format!("❌ {}", locale.get_message("exit")),
+            ];
             let selection = Select::with_theme(&ColorfulTheme::default())
-                .with_prompt(style("Select an option").cyan().bold().to_string())
+                .with_prompt(style(locale.get_message("select_action")).cyan().bold().to_string())
                 .items(&menu_items)
                 .default(0)
                 .interact()?;
@@ -236,7 +238,7 @@ impl InteractiveConsole {
                 3 => Self::show_peers(service_manager).await?,
                 4 => Self::show_settings(service_manager).await?,
                 5 => {
-                    println!("\n{}", style("Exiting...").dim());
+                    println!("\n{}", style(locale.get_message("exiting")).dim());
                     break;
                 }
                 _ => unreachable!(),
@@ -246,14 +248,13 @@ impl InteractiveConsole {
         Ok(())
     }
 
-    async fn show_node_status(_service_manager: &ServiceManager) -> Result<()> {
-        println!("\n{}", style("Node Status").bold().underlined());
+    async fn show_node_status(service_manager: &ServiceManager) -> Result<()> {
+        let locale = service_manager.locale();
+        println!("\n{}", style(locale.get_message("node_status")).bold().underlined());
         
         // システム情報を表示
-        let cpu_cores = sys_info::cpu_num().unwrap_or(1);
-        let memory_gb = sys_info::mem_info()
-            .map(|m| m.total / 1024 / 1024)
-            .unwrap_or(0);
+        let (cpu_cores, memory) = crate::util::cgroup::available_resources();
+        let memory_gb = memory / 1024 / 1024 / 1024;
 
         println!("  • CPU Cores:  {}", style(cpu_cores).cyan());
         println!("  • Memory:     {} GB", style(memory_gb).cyan());
@@ -262,15 +263,16 @@ impl InteractiveConsole {
 
         // 任意のキーで戻る
         Input::<String>::with_theme(&ColorfulTheme::default())
-            .with_prompt(style("Press Enter to return").dim().to_string())
+            .with_prompt(style(locale.get_message("press_enter")).dim().to_string())
             .allow_empty(true)
             .interact_text()?;
 
         Ok(())
     }
 
-    async fn show_network_info(_service_manager: &ServiceManager) -> Result<()> {
-        println!("\n{}", style("Network Information").bold().underlined());
+    async fn show_network_info(service_manager: &ServiceManager) -> Result<()> {
+        let locale = service_manager.locale();
+        println!("\n{}", style(locale.get_message("network_info")).bold().underlined());
         
         // ネットワーク情報を表示
         println!("  • Connected Peers: {}", style("5").green());
@@ -279,15 +281,16 @@ impl InteractiveConsole {
         println!();
 
         Input::<String>::with_theme(&ColorfulTheme::default())
-            .with_prompt(style("Press Enter to return").dim().to_string())
+            .with_prompt(style(locale.get_message("press_enter")).dim().to_string())
             .allow_empty(true)
             .interact_text()?;
 
         Ok(())
     }
 
-    async fn show_blockchain_info(_service_manager: &ServiceManager) -> Result<()> {
-        println!("\n{}", style("Blockchain Information").bold().underlined());
+    async fn show_blockchain_info(service_manager: &ServiceManager) -> Result<()> {
+        let locale = service_manager.locale();
+        println!("\n{}", style(locale.get_message("blockchain")).bold().underlined());
         
         // ブロックチェーン情報を表示
         println!("  • Height:        {}", style("1,234").yellow());
@@ -296,15 +299,16 @@ impl InteractiveConsole {
         println!();
 
         Input::<String>::with_theme(&ColorfulTheme::default())
-            .with_prompt(style("Press Enter to return").dim().to_string())
+            .with_prompt(style(locale.get_message("press_enter")).dim().to_string())
             .allow_empty(true)
             .interact_text()?;
 
         Ok(())
     }
 
-    async fn show_peers(_service_manager: &ServiceManager) -> Result<()> {
-        println!("\n{}", style("Connected Peers").bold().underlined());
+    async fn show_peers(service_manager: &ServiceManager) -> Result<()> {
+        let locale = service_manager.locale();
+        println!("\n{}", style(locale.get_message("peers")).bold().underlined());
         
         // ピア情報を表示
         println!("  • Peer 1:  {}", style("12D3...abc").magenta());
@@ -313,15 +317,16 @@ impl InteractiveConsole {
         println!();
 
         Input::<String>::with_theme(&ColorfulTheme::default())
-            .with_prompt(style("Press Enter to return").dim().to_string())
+            .with_prompt(style(locale.get_message("press_enter")).dim().to_string())
             .allow_empty(true)
             .interact_text()?;
 
         Ok(())
     }
 
-    async fn show_settings(_service_manager: &ServiceManager) -> Result<()> {
-        println!("\n{}", style("Settings").bold().underlined());
+    async fn show_settings(service_manager: &ServiceManager) -> Result<()> {
+        let locale = service_manager.locale();
+        println!("\n{}", style(locale.get_message("settings")).bold().underlined());
         
         // 設定情報を表示
         println!("  • Log Level:     {}", style("info").cyan());
@@ -329,10 +334,18 @@ impl InteractiveConsole {
         println!("  • Max Peers:     {}", style("50").cyan());
         println!();
 
-        Input::<String>::with_theme(&ColorfulTheme::default())
-            .with_prompt(style("Press Enter to return").dim().to_string())
-            .allow_empty(true)
-            .interact_text()?;
+        // 表示言語の切り替え（選択しない場合は現在の言語のまま）
+        let languages = locale.languages();
+        let current = locale.language();
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(style(locale.get_message("language")).cyan().bold().to_string())
+            .items(&languages)
+            .default(languages.iter().position(|l| *l == current).unwrap_or(0))
+            .interact()?;
+        if languages[selection] != current {
+            locale.set_language(&languages[selection])?;
+            println!("{}", style(locale.format("language_changed", &[("language", &languages[selection])])).green());
+        }
 
         Ok(())
     }
@@ -1,4 +1,5 @@
 pub mod console;
 pub mod options;
+pub mod setup;
 
 pub use options::AppOptions;
\ No newline at end of file
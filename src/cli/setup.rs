@@ -0,0 +1,336 @@
+//! ノードの初期設定ウィザード（`rustorium setup`）
+//!
+//! 対話形式で質問に答えるだけで、手作業の複数の手順を1回で済ませます。
+//! 主な機能：
+//! - 接続するネットワーク（メインネット・テストネット・ローカル）とノードの種類の選択
+//! - ポートとデータディレクトリの確認（使用中のポートや書き込めないディレクトリを検出）
+//! - バリデーターの場合はブロックに署名する鍵の生成とステークの設定
+//! - 検証した設定ファイルの書き込みと、systemdのユニットファイルの表示
+
+use std::fmt;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use console::style;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use crate::config::NodeConfig;
+use crate::core::wallet::Keystore;
+
+/// 接続するネットワーク
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    /// 他のノードに接続しない開発用のネットワーク
+    Local,
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [Network::Mainnet, Network::Testnet, Network::Local];
+
+    /// 最初のピアを見つけるDNSシード
+    fn dns_seeds(self) -> Vec<String> {
+        match self {
+            Network::Mainnet => vec!["mainnet.rustorium.org".to_string(), "mainnet2.rustorium.org".to_string()],
+            Network::Testnet => vec!["testnet.rustorium.org".to_string()],
+            Network::Local => Vec::new(),
+        }
+    }
+
+    /// bech32m アドレスのプレフィックス
+    fn address_prefix(self) -> &'static str {
+        match self {
+            Network::Testnet => "trsm",
+            Network::Mainnet | Network::Local => "rsm",
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "Mainnet",
+            Network::Testnet => "Testnet",
+            Network::Local => "Local (single node, no peers)",
+        })
+    }
+}
+
+/// ウィザードの回答
+#[derive(Debug, Clone)]
+pub struct SetupAnswers {
+    pub name: String,
+    pub network: Network,
+    /// バリデーターとして参加する（`false` の場合はフルノード）
+    pub validator: bool,
+    pub data_dir: PathBuf,
+    /// 基本ポート（Web UI、API は +1、WebSocket は +2）
+    pub port: u16,
+    /// ブロックに署名する鍵のアドレス（バリデーターのみ）
+    pub signing_key: Option<String>,
+    /// ステーク量（バリデーターのみ）
+    pub stake: u64,
+}
+
+impl SetupAnswers {
+    /// 回答から設定を作成
+    pub fn to_config(&self) -> NodeConfig {
+        let mut config = NodeConfig::default();
+        config.node.name = self.name.clone();
+        config.node.role = if self.validator { "validator" } else { "full" }.to_string();
+        config.node.data_dir = self.data_dir.clone();
+        config.network.port = self.port;
+        config.network.address_prefix = self.network.address_prefix().to_string();
+        config.network.peering.dns_seeds = self.network.dns_seeds();
+        config.network.bootstrap_nodes.clear();
+        config.validator.signing_key = self.signing_key.clone();
+        config.validator.stake = if self.validator { self.stake } else { 0 };
+        config
+    }
+}
+
+/// 設定を検証（ノードが起動できない設定を書き込まないため）
+pub fn validate(config: &NodeConfig) -> Result<()> {
+    let mut problems = Vec::new();
+    if !config.node.data_dir.is_absolute() {
+        problems.push(format!("data directory {} must be an absolute path", config.node.data_dir.display()));
+    }
+    let highest_offset = [config.web.port_offset, config.api.port_offset, config.websocket.port_offset]
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+    if config.network.port.checked_add(highest_offset).is_none() {
+        problems.push(format!("port {} leaves no room for the API and WebSocket ports", config.network.port));
+    }
+    if config.node.role == "validator" {
+        if config.validator.signing_key.is_none() {
+            problems.push("a validator needs a signing key".to_string());
+        }
+        if config.validator.stake < config.validator.min_stake {
+            problems.push(format!(
+                "stake {} is below the minimum stake {}", config.validator.stake, config.validator.min_stake,
+            ));
+        }
+    }
+    // 書き込む内容をそのまま読み込めることを確認する
+    if let Err(e) = toml::to_string_pretty(config).map_err(anyhow::Error::from)
+        .and_then(|s| toml::from_str::<NodeConfig>(&s).map_err(anyhow::Error::from))
+    {
+        problems.push(format!("the configuration does not round-trip: {}", e));
+    }
+    if !problems.is_empty() {
+        bail!("Invalid configuration:\n  - {}", problems.join("\n  - "));
+    }
+    Ok(())
+}
+
+/// ノードが使うポート（Web UI・API・WebSocket）
+fn ports(config: &NodeConfig) -> Vec<u16> {
+    [config.web.port_offset, config.api.port_offset, config.websocket.port_offset]
+        .into_iter()
+        .filter_map(|offset| config.network.port.checked_add(offset))
+        .collect()
+}
+
+/// 使用中のポート
+fn ports_in_use(config: &NodeConfig) -> Vec<u16> {
+    ports(config).into_iter()
+        .filter(|port| TcpListener::bind((config.network.host.as_str(), *port)).is_err())
+        .collect()
+}
+
+/// データディレクトリを作成し、書き込めることを確認
+fn prepare_data_dir(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    let probe = dir.join(".setup-probe");
+    std::fs::write(&probe, b"").with_context(|| format!("{} is not writable", dir.display()))?;
+    std::fs::remove_file(&probe)?;
+    Ok(())
+}
+
+/// systemdのユニットファイル
+///
+/// コマンドライン引数はデータディレクトリとポートの設定を上書きするため、設定と同じ値を渡します。
+pub fn systemd_unit(config: &NodeConfig, config_path: &Path, binary: &Path, user: &str) -> String {
+    let description = if config.node.role == "validator" { "Rustorium Validator" } else { "Rustorium Node" };
+    format!(
+        "[Unit]
+Description={description}
+After=network-online.target
+Wants=network-online.target
+StartLimitIntervalSec=0
+
+[Service]
+Type=simple
+User={user}
+Group={user}
+Environment=RUST_LOG=info
+ExecStart={binary} --config {config} --data-dir {data_dir} --port {port} --no-interactive
+Restart=always
+RestartSec=1
+LimitNOFILE=65535
+NoNewPrivileges=true
+ProtectSystem=full
+ReadWritePaths={data_dir}
+
+[Install]
+WantedBy=multi-user.target
+",
+        binary = binary.display(),
+        config = config_path.display(),
+        data_dir = config.node.data_dir.display(),
+        port = config.network.port,
+    )
+}
+
+/// ウィザードを実行
+///
+/// `config_path` に設定を書き込みます。既存のファイルは `force` を指定するか確認した場合のみ上書きします。
+pub async fn run(config_path: &Path, default_data_dir: &Path, user: &str, force: bool) -> Result<()> {
+    let theme = ColorfulTheme::default();
+    println!("{}", style("Rustorium node setup").cyan().bold());
+    println!("{}\n", style("Answer a few questions to create a configuration. Press Ctrl+C to abort.").dim());
+
+    if config_path.exists() && !force {
+        let overwrite = Confirm::with_theme(&theme)
+            .with_prompt(format!("{} already exists. Overwrite it?", config_path.display()))
+            .default(false)
+            .interact()?;
+        if !overwrite {
+            bail!("Aborted: {} was left unchanged", config_path.display());
+        }
+    }
+
+    let network = Network::ALL[Select::with_theme(&theme)
+        .with_prompt("Network")
+        .items(&Network::ALL)
+        .default(0)
+        .interact()?];
+    let validator = Select::with_theme(&theme)
+        .with_prompt("Node type")
+        .items(&["Full node (follows the chain and serves the API)", "Validator (produces and signs blocks)"])
+        .default(0)
+        .interact()? == 1;
+    let name: String = Input::with_theme(&theme)
+        .with_prompt("Node name (empty to derive it from the node ID)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let data_dir: PathBuf = loop {
+        let dir: String = Input::with_theme(&theme)
+            .with_prompt("Data directory")
+            .default(default_data_dir.display().to_string())
+            .interact_text()?;
+        let dir = PathBuf::from(dir);
+        if !dir.is_absolute() {
+            println!("{} Use an absolute path", style("✗").red());
+            continue;
+        }
+        match prepare_data_dir(&dir) {
+            Ok(()) => break dir,
+            Err(e) => println!("{} {:#}", style("✗").red(), e),
+        }
+    };
+
+    let mut answers = SetupAnswers {
+        name,
+        network,
+        validator,
+        data_dir,
+        port: 9070,
+        signing_key: None,
+        stake: 0,
+    };
+    loop {
+        answers.port = Input::with_theme(&theme)
+            .with_prompt("Base port (Web UI; API and WebSocket use the next two ports)")
+            .default(answers.port)
+            .interact_text()?;
+        let busy = ports_in_use(&answers.to_config());
+        if busy.is_empty() {
+            break;
+        }
+        println!("{} Ports already in use: {:?}", style("✗").red(), busy);
+    }
+
+    if validator {
+        let keystore = Keystore::new(answers.data_dir.join("keystore"));
+        let existing = keystore.list().await.unwrap_or_default();
+        let reuse = !existing.is_empty() && Confirm::with_theme(&theme)
+            .with_prompt(format!("Use an existing key from {}?", answers.data_dir.join("keystore").display()))
+            .default(true)
+            .interact()?;
+        let address = if reuse {
+            existing[Select::with_theme(&theme).with_prompt("Signing key").items(&existing).default(0).interact()?].clone()
+        } else {
+            let address = keystore.generate().await?;
+            println!("{} Generated signing key {}", style("✓").green(), address);
+            address
+        };
+        answers.signing_key = Some(address);
+
+        let min_stake = NodeConfig::default().validator.min_stake;
+        answers.stake = Input::with_theme(&theme)
+            .with_prompt(format!("Stake (minimum {})", min_stake))
+            .default(min_stake)
+            .validate_with(|stake: &u64| if *stake >= min_stake { Ok(()) } else { Err(format!("must be at least {}", min_stake)) })
+            .interact_text()?;
+    }
+
+    let config = answers.to_config();
+    validate(&config)?;
+    if let Some(parent) = config_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("Cannot create {}", parent.display()))?;
+    }
+    config.save(&config_path.to_string_lossy())?;
+    println!("\n{} Wrote {}", style("✓").green(), config_path.display());
+
+    if let Some(address) = &config.validator.signing_key {
+        println!("{} Validator signing address: {}", style("✓").green(), address);
+        println!("  {}", style("Share this address with the network operators to be added to the validator set.").dim());
+        println!("  {}", style("Back up the key file in the keystore directory; it cannot be recovered.").yellow());
+    }
+
+    let binary = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("/usr/local/bin/rustorium"));
+    let config_path = std::fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+    println!("\n{}", style("systemd unit (/etc/systemd/system/rustorium.service):").bold());
+    println!("{}", systemd_unit(&config, &config_path, &binary, user));
+    println!("{}", style("Install and start it with:").bold());
+    println!("  sudo useradd -r -s /bin/false {}", user);
+    println!("  sudo chown -R {user}:{user} {}", config.node.data_dir.display());
+    println!("  sudo systemctl daemon-reload && sudo systemctl enable --now rustorium");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answers_to_validated_config() {
+        let mut answers = SetupAnswers {
+            name: "node-1".to_string(),
+            network: Network::Testnet,
+            validator: true,
+            data_dir: PathBuf::from("/var/lib/rustorium"),
+            port: 9070,
+            signing_key: Some("ab".repeat(20)),
+            stake: 100_000,
+        };
+        let config = answers.to_config();
+        assert_eq!(config.node.role, "validator");
+        assert_eq!(config.network.address_prefix, "trsm");
+        assert_eq!(config.network.peering.dns_seeds, ["testnet.rustorium.org"]);
+        validate(&config).unwrap();
+
+        let unit = systemd_unit(&config, Path::new("/etc/rustorium/config.toml"), Path::new("/usr/local/bin/rustorium"), "rustorium");
+        assert!(unit.contains("ExecStart=/usr/local/bin/rustorium --config /etc/rustorium/config.toml --data-dir /var/lib/rustorium --port 9070 --no-interactive"));
+
+        answers.stake = 1;
+        answers.signing_key = None;
+        answers.data_dir = PathBuf::from("relative");
+        answers.port = u16::MAX;
+        let error = validate(&answers.to_config()).unwrap_err().to_string();
+        assert!(error.contains("absolute path") && error.contains("signing key") && error.contains("minimum stake") && error.contains("no room"));
+    }
+}
@@ -2,10 +2,13 @@
 //! 
 //! このモジュールは、Rustoriumノードの設定を管理します。
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use utoipa::ToSchema;
 use crate::cli::options::AppOptions;
+use crate::core::contract::AnalysisMode;
+use crate::core::mempool::AccessMode;
 
 /// ノードの設定
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -28,6 +31,57 @@ pub struct NodeConfig {
     pub storage: StorageSettings,
     /// 開発モード設定
     pub dev: DevSettings,
+    /// メモリプール設定
+    #[serde(default)]
+    pub mempool: MempoolSettings,
+    /// コントラクト設定
+    #[serde(default)]
+    pub contracts: ContractSettings,
+    /// シャーディング設定
+    #[serde(default)]
+    pub sharding: ShardingSettings,
+    /// 地理的ルーティング設定
+    #[serde(default)]
+    pub geo: GeoSettings,
+    /// チェーンデータ配信設定
+    #[serde(default)]
+    pub streaming: StreamingSettings,
+    /// AI最適化エンジン設定
+    #[serde(default)]
+    pub ai: AiSettings,
+    /// バックアップ設定
+    #[serde(default)]
+    pub backup: BackupSettings,
+    /// 読み取り専用レプリカ設定
+    #[serde(default)]
+    pub replica: RpcReplicaSettings,
+    /// コンセンサスパラメーター
+    #[serde(default)]
+    pub consensus: ConsensusSettings,
+    /// ブロブのサイドカーの保持設定
+    #[serde(default)]
+    pub blobs: BlobSettings,
+    /// テレメトリー設定（オプトイン）
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    /// 表示言語の設定
+    #[serde(default)]
+    pub i18n: I18nSettings,
+    /// 索引したチェーンデータへのSQL（`sql` フィーチャー）
+    #[serde(default)]
+    pub sql: SqlSettings,
+    /// チェーンデータの一括書き出し（`export` フィーチャー）
+    #[serde(default)]
+    pub export: ExportSettings,
+    /// ブロックとイベントの gRPC ストリーミング（`grpc` フィーチャー）
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    /// データディレクトリのディスク容量の監視
+    #[serde(default)]
+    pub disk: DiskSettings,
+    /// シャードを調整するビーコンチェーン
+    #[serde(default)]
+    pub beacon: BeaconSettings,
 }
 
 /// ノードの基本設定
@@ -35,7 +89,7 @@ pub struct NodeConfig {
 pub struct NodeSettings {
     /// ノード名（空の場合はIDから自動生成）
     pub name: String,
-    /// ノードの役割 (auto, validator, full, light)
+    /// ノードの役割 (auto, validator, full, light, rpc-replica)
     pub role: String,
     /// データディレクトリ
     pub data_dir: PathBuf,
@@ -48,7 +102,7 @@ pub struct NodeSettings {
 pub struct NetworkSettings {
     /// ネットワークの有効化
     pub enabled: bool,
-    /// ホストアドレス
+    /// 待ち受けるアドレス（P2Pと、`bind` を指定していないWeb UI・API・WebSocket、IPv6も可）
     pub host: String,
     /// 基本ポート（P2P用）
     pub port: u16,
@@ -56,6 +110,213 @@ pub struct NetworkSettings {
     pub external_addr: Option<String>,
     /// ブートストラップノード
     pub bootstrap_nodes: Vec<String>,
+    /// bech32m アドレスのプレフィックス（ネットワークごとに変える）
+    #[serde(default = "default_address_prefix")]
+    pub address_prefix: String,
+    /// ゴシップの優先制御
+    #[serde(default)]
+    pub gossip: GossipSettings,
+    /// ピアの接続数の上限と追い出し
+    #[serde(default)]
+    pub peers: PeerLimitSettings,
+    /// 発信するピアのIPアドレスの分散
+    #[serde(default)]
+    pub diversity: DiversitySettings,
+    /// ブロックの中継
+    #[serde(default)]
+    pub relay: RelaySettings,
+    /// バリデーターのセントリーノード構成
+    #[serde(default)]
+    pub sentry: SentrySettings,
+    /// DNSシードと固定ピア
+    #[serde(default)]
+    pub peering: PeeringSettings,
+}
+
+/// DNSシードと固定ピアの設定
+///
+/// DNSシードはプライベートネットワークでも最初のピアを見つけるためのDNS名で、
+/// 固定ピアは常に接続し続けるピアです。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct PeeringSettings {
+    /// DNSシード（`_service._proto.domain` はSRV、それ以外はTXTレコード、なければA・AAAAレコード）
+    pub dns_seeds: Vec<String>,
+    /// 常に接続し、切断されたら再接続するピア（`host:port`、ホスト名も可）
+    pub persistent_peers: Vec<String>,
+    /// 固定ピアへの再接続の最初の間隔（秒）
+    pub redial_min_backoff: u64,
+    /// 固定ピアへの再接続の最大の間隔（秒）
+    pub redial_max_backoff: u64,
+    /// 接続中のピアがいない場合にDNSシードを引き直す間隔（秒）
+    pub seed_refresh_interval: u64,
+}
+
+impl Default for PeeringSettings {
+    fn default() -> Self {
+        Self {
+            dns_seeds: Vec::new(),
+            persistent_peers: Vec::new(),
+            redial_min_backoff: 1,
+            redial_max_backoff: 300,
+            seed_refresh_interval: 60,
+        }
+    }
+}
+
+/// セントリーノード構成の設定
+///
+/// バリデーターを公開ネットワークから隠すため、バリデーターは設定したセントリーノードにだけ
+/// 接続し、接続を待ち受けません。セントリーノードは公開ネットワークとバリデーターの間で
+/// 合意形成のメッセージを中継します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SentrySettings {
+    /// `off`、`validator`（セントリーの背後のバリデーター）、`sentry`（セントリーノード）
+    pub mode: String,
+    /// バリデーターが接続するセントリーノードのアドレス（`host:port`、`validator` の場合）
+    pub sentry_nodes: Vec<String>,
+    /// 背後のバリデーターのアドレス（`host` または `host:port`、`sentry` の場合）
+    pub private_peers: Vec<String>,
+}
+
+impl Default for SentrySettings {
+    fn default() -> Self {
+        Self {
+            mode: "off".to_string(),
+            sentry_nodes: Vec::new(),
+            private_peers: Vec::new(),
+        }
+    }
+}
+
+/// ブロックの中継の設定
+///
+/// コンパクトブロックではヘッダーとトランザクションの短いIDだけを送り、受信側は
+/// メモリプールから復元して、持っていないトランザクションだけを要求します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct RelaySettings {
+    /// コンパクトブロックでブロックを中継する
+    pub compact_blocks: bool,
+    /// ブロックの生成時刻のこの秒数前以降に受信したトランザクションは本体も送る
+    /// （他のノードにまだ届いていない可能性が高いため）
+    pub prefill_age: u64,
+}
+
+impl Default for RelaySettings {
+    fn default() -> Self {
+        Self {
+            compact_blocks: true,
+            prefill_age: 1,
+        }
+    }
+}
+
+/// 発信するピアのIPアドレスの分散の設定
+///
+/// 同じサブネット・同じASNのピアばかりに接続すると、攻撃者が少数のネットワークから
+/// ノードを孤立させる（エクリプス攻撃）ことが容易になるため、それぞれの接続数を制限します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct DiversitySettings {
+    /// 同じサブネット（IPv4 は /24、IPv6 は /48）の発信の接続の上限（0 は無制限）
+    pub max_per_subnet: usize,
+    /// 同じASNの発信の接続の上限（0 は無制限、`asn_path` を設定した場合のみ）
+    pub max_per_asn: usize,
+    /// ASNテーブル（1行に `CIDR,ASN`）
+    pub asn_path: Option<PathBuf>,
+}
+
+impl Default for DiversitySettings {
+    fn default() -> Self {
+        Self {
+            max_per_subnet: 2,
+            max_per_asn: 4,
+            asn_path: None,
+        }
+    }
+}
+
+/// ピアの接続数の上限と追い出しの設定
+///
+/// 上限に達した状態で受信した接続は、保護されていないピアのうち評価の最も低いものを
+/// 追い出して受け入れます（接続して間もないピアしかいない場合は拒否します）。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct PeerLimitSettings {
+    /// 受信した接続の上限
+    pub max_inbound: usize,
+    /// こちらから接続する数の上限
+    pub max_outbound: usize,
+    /// 常に接続し、上限に数えず追い出さないピアのアドレス（`host:port`）
+    pub protected: Vec<String>,
+    /// 接続してからこの秒数が経つまでは追い出さない
+    pub eviction_grace: u64,
+    /// 評価におけるレイテンシーの重み
+    pub latency_weight: f64,
+    /// 評価における有用性（新しいメッセージを届けた頻度）の重み
+    pub usefulness_weight: f64,
+    /// 評価における重複メッセージの割合の重み（減点）
+    pub duplicate_weight: f64,
+}
+
+impl Default for PeerLimitSettings {
+    fn default() -> Self {
+        Self {
+            max_inbound: 40,
+            max_outbound: 16,
+            protected: Vec::new(),
+            eviction_grace: 60,
+            latency_weight: 1.0,
+            usefulness_weight: 2.0,
+            duplicate_weight: 1.0,
+        }
+    }
+}
+
+/// ゴシップの優先制御の設定
+///
+/// 投票などの合意形成のメッセージがブロックの一括転送の後ろで待たされないよう、
+/// 送信をメッセージの種類ごとのキューに分け、バリデーターのピアをステーク量で優先します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct GossipSettings {
+    /// 合意形成以外のメッセージを送るピアの数
+    pub fanout: usize,
+    /// 同時に送信中にできるメッセージの数
+    pub max_in_flight: usize,
+    /// 合意形成のメッセージのキューの容量（溢れた場合は古いものから捨てる）
+    pub consensus_queue: usize,
+    /// トランザクションのキューの容量（溢れた場合は新しいものを捨てる）
+    pub transaction_queue: usize,
+    /// ブロックのキューの容量（溢れた場合は新しいものを捨てる）
+    pub block_queue: usize,
+    /// トランザクションとブロックを交互に送る際のトランザクションの比重
+    pub transaction_weight: u32,
+    /// トランザクションとブロックを交互に送る際のブロックの比重
+    pub block_weight: u32,
+    /// バリデーターのピアのアドレス（`host:port`）とステーク量
+    pub validator_stakes: HashMap<String, u64>,
+}
+
+impl Default for GossipSettings {
+    fn default() -> Self {
+        Self {
+            fanout: 8,
+            max_in_flight: 64,
+            consensus_queue: 1024,
+            transaction_queue: 4096,
+            block_queue: 256,
+            transaction_weight: 4,
+            block_weight: 1,
+            validator_stakes: HashMap::new(),
+        }
+    }
+}
+
+fn default_address_prefix() -> String {
+    crate::core::wallet::DEFAULT_ADDRESS_PREFIX.to_string()
 }
 
 /// API設定
@@ -65,10 +326,190 @@ pub struct ApiSettings {
     pub enabled: bool,
     /// APIポートのオフセット
     pub port_offset: u16,
+    /// 待ち受けるアドレス（省略時は `network.host`）
+    #[serde(default)]
+    pub bind: Option<String>,
     /// レート制限（リクエスト/分）
     pub rate_limit: u32,
-    /// CORS設定
-    pub cors_origins: Vec<String>,
+    /// 公開API・JSON-RPC・フロントエンドのCORSポリシー
+    #[serde(default)]
+    pub cors: CorsSettings,
+    /// 管理者APIのCORSポリシー（既定では他のオリジンからのアクセスを許可しない）
+    #[serde(default = "CorsSettings::admin")]
+    pub admin_cors: CorsSettings,
+    /// 管理者APIのトークン（未設定の場合は管理者APIを無効化）
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// 権限を限定したAPIトークン（管理者トークンはすべての権限を持つ）
+    #[serde(default)]
+    pub tokens: Vec<ApiTokenSettings>,
+    /// アクセスログ
+    #[serde(default)]
+    pub access_log: AccessLogSettings,
+    /// Web UIと管理者APIのパスキー（WebAuthn）ログイン
+    #[serde(default)]
+    pub webauthn: WebAuthnSettings,
+    /// トランザクション送信の冪等キー（`Idempotency-Key`）
+    #[serde(default)]
+    pub idempotency: IdempotencySettings,
+}
+
+/// 冪等キーの設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct IdempotencySettings {
+    /// 受け付けた送信の結果を保持する秒数
+    pub ttl: u64,
+    /// 保持するキーの最大数（超えた場合は古いものから破棄する）
+    pub max_keys: usize,
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        Self {
+            ttl: 24 * 60 * 60,
+            max_keys: 100_000,
+        }
+    }
+}
+
+/// 権限を限定したAPIトークン
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiTokenSettings {
+    /// トークン（`Authorization: Bearer <token>`）
+    pub token: String,
+    /// 許可する権限（例: `mempool:read`）
+    pub scopes: Vec<String>,
+}
+
+/// メモリプールの全内容を読み取る権限
+pub const SCOPE_MEMPOOL_READ: &str = "mempool:read";
+/// `/api/sql` でクエリを実行する権限
+pub const SCOPE_SQL_QUERY: &str = "sql:query";
+/// コントラクトのソース検証を実行する権限（`contracts.public_verification` が無効の場合）
+pub const SCOPE_CONTRACTS_VERIFY: &str = "contracts:verify";
+
+/// パスキー（WebAuthn）ログイン設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct WebAuthnSettings {
+    /// パスキーログインの有効化
+    pub enabled: bool,
+    /// リライングパーティーID（Web UIのドメイン名、例: `node.example.com`）
+    pub rp_id: String,
+    /// Web UIのオリジン（例: `https://node.example.com`）
+    pub rp_origin: String,
+    /// 認証器に表示する名前
+    pub rp_name: String,
+    /// セッションの有効期間（秒）
+    pub session_ttl_secs: u64,
+    /// Web UIの表示にログインを要求する
+    pub login_required: bool,
+    /// セッションCookieに `Secure` 属性を付ける（HTTPSで公開する場合は必須）
+    pub secure_cookie: bool,
+}
+
+impl Default for WebAuthnSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rp_id: "localhost".to_string(),
+            rp_origin: "http://localhost:9070".to_string(),
+            rp_name: "Rustorium Node".to_string(),
+            session_ttl_secs: 12 * 3600,
+            login_required: true,
+            secure_cookie: true,
+        }
+    }
+}
+
+/// アクセスログ設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct AccessLogSettings {
+    /// アクセスログの有効化
+    pub enabled: bool,
+    /// 出力先（JSON Lines、未設定の場合は `access` ターゲットのログに出力）
+    pub path: Option<PathBuf>,
+    /// 成功したリクエストを記録する割合（0.0〜1.0）
+    pub sample_rate: f64,
+    /// エラー（ステータス400以上）はサンプリングせずに記録する
+    pub always_log_errors: bool,
+    /// 値を伏せ字にするパラメーター名（大文字小文字を区別しない）
+    pub redact_params: Vec<String>,
+    /// パラメーターの値を切り詰める文字数
+    pub max_param_length: usize,
+    /// クライアントのIPアドレスを記録する（IPv4 は /24、IPv6 は /48 に丸める）
+    pub log_client_ip: bool,
+}
+
+impl Default for AccessLogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            sample_rate: 1.0,
+            always_log_errors: true,
+            redact_params: [
+                "api_key", "apikey", "token", "access_token", "signature",
+                "password", "passphrase", "secret", "mnemonic", "private_key",
+            ].into_iter().map(String::from).collect(),
+            max_param_length: 64,
+            log_client_ip: true,
+        }
+    }
+}
+
+/// CORSポリシー
+///
+/// テーブルを指定する場合、`allowed_origins` は省略できません（省略による意図しない公開を防ぐため）。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CorsSettings {
+    /// 許可するオリジン（`"*"` はすべて、空の場合は同一オリジンのみ）
+    pub allowed_origins: Vec<String>,
+    /// 許可するメソッド
+    #[serde(default = "default_cors_methods")]
+    pub allowed_methods: Vec<String>,
+    /// 許可するリクエストヘッダー
+    #[serde(default = "default_cors_headers")]
+    pub allowed_headers: Vec<String>,
+    /// Cookie などの資格情報を許可する（`"*"` のオリジンとは併用できない）
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// プリフライトの結果をキャッシュする秒数
+    #[serde(default = "default_cors_max_age")]
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: default_cors_methods(),
+            allowed_headers: default_cors_headers(),
+            allow_credentials: false,
+            max_age_secs: default_cors_max_age(),
+        }
+    }
+}
+
+impl CorsSettings {
+    /// 管理者APIの既定のポリシー（同一オリジンのみ）
+    pub fn admin() -> Self {
+        Self { allowed_origins: Vec::new(), ..Self::default() }
+    }
+}
+
+fn default_cors_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "DELETE"].into_iter().map(String::from).collect()
+}
+
+fn default_cors_headers() -> Vec<String> {
+    ["content-type", "authorization"].into_iter().map(String::from).collect()
+}
+
+fn default_cors_max_age() -> u64 {
+    600
 }
 
 /// Web UI設定
@@ -78,6 +519,9 @@ pub struct WebSettings {
     pub enabled: bool,
     /// Web UIポートのオフセット
     pub port_offset: u16,
+    /// 待ち受けるアドレス（省略時は `network.host`）
+    #[serde(default)]
+    pub bind: Option<String>,
     /// 起動時にブラウザを開く
     pub open_browser: bool,
 }
@@ -89,6 +533,9 @@ pub struct WebSocketSettings {
     pub enabled: bool,
     /// WebSocketポートのオフセット
     pub port_offset: u16,
+    /// 待ち受けるアドレス（省略時は `network.host`）
+    #[serde(default)]
+    pub bind: Option<String>,
 }
 
 /// バリデーター設定
@@ -100,6 +547,12 @@ pub struct ValidatorSettings {
     pub commission: f64,
     /// 最小ステーク量
     pub min_stake: u64,
+    /// ブロックに署名する鍵のアドレス（データディレクトリの `keystore` に保存したもの）
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// シャドーモード（バリデーターの処理をすべて行うが、ブロックと投票は送信せずログと計測のみ）
+    #[serde(default)]
+    pub shadow: bool,
 }
 
 /// パフォーマンス設定
@@ -137,6 +590,685 @@ pub struct DevSettings {
     pub auto_mining: bool,
     /// ブロック生成間隔（ミリ秒）
     pub block_time: u64,
+    /// ネットワーク障害の注入（開発モードのみ）
+    #[serde(default)]
+    pub chaos: ChaosSettings,
+    /// APIを提供する記録済みのスナップショット（フィクスチャモード、`NodeConfig::fixture`）
+    #[serde(default)]
+    pub fixture: Option<PathBuf>,
+    /// 署名のないトランザクションを受け付けて確定する（ローカルの検証専用、全ノードで同じ値にする）
+    #[serde(default)]
+    pub allow_unsigned: bool,
+}
+
+/// ネットワーク障害の注入の設定
+///
+/// ローカルのdevnetで遅延・パケットロス・ネットワーク分断・帯域制限のある
+/// WANを再現し、合意形成が進み続けるかを確認するために使います。
+/// ピアのアドレスは `ip:port`（そのピアのみ）または `ip`（そのIPアドレスの全ポート）です。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ChaosSettings {
+    /// 全リンクの送信の遅延（ミリ秒）
+    pub latency_ms: u64,
+    /// 遅延に加えるランダムな揺らぎの最大値（ミリ秒）
+    pub jitter_ms: u64,
+    /// メッセージを捨てる割合（0.0〜1.0）
+    pub loss: f64,
+    /// リンクごとの帯域の上限（kbps、0 は無制限）
+    pub bandwidth_kbps: u64,
+    /// ピアごとの設定（指定した項目だけ全体の設定を上書きする）
+    pub links: Vec<LinkChaosSettings>,
+    /// 予定したネットワーク分断
+    pub partitions: Vec<PartitionSettings>,
+}
+
+/// ピアとのリンクの障害の設定
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct LinkChaosSettings {
+    /// ピアのアドレス
+    pub peer: String,
+    pub latency_ms: Option<u64>,
+    pub jitter_ms: Option<u64>,
+    pub loss: Option<f64>,
+    pub bandwidth_kbps: Option<u64>,
+}
+
+/// ネットワーク分断の予定
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct PartitionSettings {
+    /// 起動から分断を始めるまでの秒数
+    pub start: u64,
+    /// 分断を続ける秒数
+    pub duration: u64,
+    /// 分断するピアのアドレス
+    pub peers: Vec<String>,
+}
+
+/// メモリプール設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct MempoolSettings {
+    /// 保持するトランザクションの最大数
+    pub max_size: usize,
+    /// 最低ガス価格
+    pub min_gas_price: u64,
+    /// アカウントごとの保留トランザクション上限
+    pub max_pending_per_account: usize,
+    /// データフィールドの最大サイズ（バイト）
+    pub max_data_size: usize,
+    /// 負荷に応じた動的手数料フロア
+    pub adaptive_fee_floor: bool,
+    /// 動的フロアが上昇し始める使用率（0.0〜1.0）
+    pub floor_utilization_threshold: f64,
+    /// 使用率100%時のフロア倍率
+    pub floor_max_multiplier: f64,
+    /// 同じ送信者・ノンスのトランザクションを置き換えるのに必要なガス価格の上乗せ（%）
+    pub replacement_bump: u64,
+    /// アクセスリストのモード (denylist, allowlist)
+    pub access_list_mode: AccessMode,
+    /// アクセスリストのファイル（1行に1アドレス、管理者APIでの変更も書き戻す）
+    pub access_list_path: Option<PathBuf>,
+}
+
+impl Default for MempoolSettings {
+    fn default() -> Self {
+        Self {
+            max_size: 10_000,
+            min_gas_price: 1,
+            max_pending_per_account: 64,
+            max_data_size: 128 * 1024,
+            adaptive_fee_floor: true,
+            floor_utilization_threshold: 0.5,
+            floor_max_multiplier: 10.0,
+            replacement_bump: 10,
+            access_list_mode: AccessMode::Denylist,
+            access_list_path: None,
+        }
+    }
+}
+
+/// コントラクト設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ContractSettings {
+    /// ソース検証用コンパイラの配置ディレクトリ（solc-<version>, vyper-<version>）
+    pub compilers_dir: PathBuf,
+    /// コンパイルのタイムアウト（秒）
+    pub compile_timeout: u64,
+    /// デプロイ時の静的解析 (off, warn, enforce)
+    pub analysis_mode: AnalysisMode,
+    /// デプロイできるバイトコードの最大サイズ（バイト）
+    pub max_code_size: usize,
+    /// 同時に実行するソース検証（コンパイル）数の上限
+    pub max_concurrent_verifications: usize,
+    /// トークンなしでソース検証を受け付ける（無効の場合は `contracts:verify` の権限が必要）
+    pub public_verification: bool,
+}
+
+impl Default for ContractSettings {
+    fn default() -> Self {
+        Self {
+            compilers_dir: PathBuf::from("compilers"),
+            compile_timeout: 60,
+            analysis_mode: AnalysisMode::Warn,
+            max_code_size: 48 * 1024,
+            max_concurrent_verifications: 2,
+            public_verification: false,
+        }
+    }
+}
+
+/// シャーディング設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ShardingSettings {
+    /// スケーリング・再分配の確認間隔（秒）
+    pub rebalance_interval: u64,
+    /// 許容する負荷の不均衡（(最大 - 最小) / 平均）
+    pub imbalance_tolerance: f64,
+    /// 1回の再分配での最大アカウント移動数
+    pub max_moves: usize,
+    /// アカウント移動を実行する全シャード合計TPSの上限
+    pub low_traffic_tps: u32,
+    /// 負荷計算におけるTPSの重み（残りはストレージ）
+    pub tps_weight: f64,
+    /// シャードのチェックポイントをコミットする間隔（秒）
+    pub checkpoint_interval: u64,
+}
+
+impl Default for ShardingSettings {
+    fn default() -> Self {
+        Self {
+            rebalance_interval: 60,
+            imbalance_tolerance: 0.2,
+            max_moves: 1000,
+            low_traffic_tps: 1000,
+            tps_weight: 0.7,
+            checkpoint_interval: 10,
+        }
+    }
+}
+
+/// 地理的ルーティング設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct GeoSettings {
+    /// 読み取りリクエストを最寄りのレプリカへ振り分ける
+    pub enabled: bool,
+    /// 自ノードのリージョン
+    pub region: String,
+    /// 自ノードの緯度
+    pub latitude: f64,
+    /// 自ノードの経度
+    pub longitude: f64,
+    /// GeoIPテーブル（1行に `CIDR,リージョン,緯度,経度`）
+    pub geoip_path: Option<PathBuf>,
+    /// これを超える平均レイテンシー（ミリ秒）のノードは後回しにする
+    pub latency_threshold: u32,
+    /// 1リクエストで試行するノード数
+    pub max_attempts: u32,
+    /// 転送のタイムアウト（ミリ秒）
+    pub request_timeout: u64,
+    /// ノードを除外するまでの連続失敗回数
+    pub failure_threshold: u32,
+    /// 除外したノードを再び候補にするまでの時間（秒）
+    pub cooldown: u64,
+    /// 転送先のレプリカ／キャッシュノード
+    pub replicas: Vec<ReplicaSettings>,
+}
+
+impl Default for GeoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            region: "local".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            geoip_path: None,
+            latency_threshold: 200,
+            max_attempts: 3,
+            request_timeout: 2000,
+            failure_threshold: 3,
+            cooldown: 30,
+            replicas: Vec::new(),
+        }
+    }
+}
+
+/// レプリカノードの設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReplicaSettings {
+    /// ノードID
+    pub id: String,
+    /// APIのベースURL（例: `http://replica-eu:9071`）
+    pub endpoint: String,
+    pub region: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// チェーンデータ配信設定（Redpanda/Kafka）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct StreamingSettings {
+    /// 確定したブロックをトピックへ発行する
+    pub enabled: bool,
+    /// ブローカーのアドレス
+    pub brokers: Vec<String>,
+    /// ブロックのトピック
+    pub blocks_topic: String,
+    /// トランザクションのトピック
+    pub transactions_topic: String,
+    /// イベントのトピック
+    pub events_topic: String,
+    /// Schema Registry のURL（設定するとスキーマを登録してワイヤーフォーマットで発行）
+    pub schema_registry_url: Option<String>,
+    /// トランザクショナルID（設定するとブロック単位のトランザクションで発行）
+    pub transactional_id: Option<String>,
+    /// 発行のタイムアウト（ミリ秒）
+    pub timeout: u64,
+    /// librdkafka にそのまま渡す設定
+    pub client_settings: HashMap<String, String>,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brokers: vec!["localhost:9092".to_string()],
+            blocks_topic: "rustorium.blocks".to_string(),
+            transactions_topic: "rustorium.transactions".to_string(),
+            events_topic: "rustorium.events".to_string(),
+            schema_registry_url: None,
+            transactional_id: None,
+            timeout: 10000,
+            client_settings: HashMap::new(),
+        }
+    }
+}
+
+/// AI最適化エンジン設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct AiSettings {
+    /// 判定ポリシー（`heuristic` または `model`）
+    pub policy: String,
+    /// 学習済みモデルのファイル（`model` ポリシーで使用）
+    pub model_path: Option<PathBuf>,
+    /// モデルの判定を採用する最小の確信度
+    pub min_confidence: f64,
+    /// 負荷がこれを超えたらスケールアウト（`heuristic` ポリシー）
+    pub scale_out_threshold: f64,
+    /// 負荷がこれを下回ったらスケールイン（`heuristic` ポリシー）
+    pub scale_in_threshold: f64,
+    /// エラー率がこれを超えたらスケールアウト（`heuristic` ポリシー）
+    pub max_error_rate: f64,
+    /// アクションを実行せず監査ログへの記録のみ行う
+    pub dry_run: bool,
+    /// 最適化の実行間隔（秒）
+    pub interval: u64,
+    /// 障害予測の予測期間（秒）
+    pub prediction_horizon: u64,
+    /// SMART情報を確認するデバイス（`smartctl` が必要）
+    pub smart_devices: Vec<String>,
+    /// この割合のピアが1回の観測で入れ替わったら障害確率を1とする
+    pub max_peer_churn: f64,
+    /// 緩和フックを発動する障害確率
+    pub mitigation_threshold: f64,
+    /// 有効化する緩和フック（`pause_rpc`, `snapshot`）
+    pub mitigations: Vec<String>,
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        Self {
+            policy: "heuristic".to_string(),
+            model_path: None,
+            min_confidence: 0.6,
+            scale_out_threshold: 0.8,
+            scale_in_threshold: 0.2,
+            max_error_rate: 0.05,
+            dry_run: true,
+            interval: 60,
+            prediction_horizon: 3600,
+            smart_devices: Vec::new(),
+            max_peer_churn: 0.5,
+            mitigation_threshold: 0.9,
+            mitigations: Vec::new(),
+        }
+    }
+}
+
+/// バックアップ設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// 定期バックアップを有効化
+    pub enabled: bool,
+    /// 保存先（省略時は `<data_dir>/backups`）
+    pub dir: Option<PathBuf>,
+    /// フルバックアップの間隔（秒）
+    pub full_interval: u64,
+    /// 増分バックアップの間隔（秒）
+    pub incremental_interval: u64,
+    /// 保持する世代数（フルバックアップとそれに続く増分を1世代とする）
+    pub retain: usize,
+    /// チャンクサイズ（バイト）
+    pub chunk_size: usize,
+    /// S3（互換ストレージ）へのアップロード
+    pub s3: Option<S3Settings>,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            full_interval: 86400,
+            incremental_interval: 3600,
+            retain: 4,
+            chunk_size: 4 * 1024 * 1024,
+            s3: None,
+        }
+    }
+}
+
+/// S3のアップロード先（認証情報は環境変数等の標準の方法で取得）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct S3Settings {
+    pub bucket: String,
+    /// キーのプレフィックス
+    #[serde(default)]
+    pub prefix: String,
+    pub region: String,
+    /// S3互換ストレージのエンドポイント
+    pub endpoint: Option<String>,
+    /// アップロードの最大速度（バイト/秒、0は無制限）。ノードの帯域を使い切らないよう制限する
+    #[serde(default)]
+    pub max_upload_rate: u64,
+}
+
+/// 読み取り専用レプリカの役割名
+pub const ROLE_RPC_REPLICA: &str = "rpc-replica";
+
+/// 読み取り専用レプリカ設定（`node.role = "rpc-replica"` の場合に使用）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct RpcReplicaSettings {
+    /// ブロックの取得元およびトランザクションの転送先となるシーケンサー／バリデーターのAPIのベースURL
+    /// （例: `http://validator-1:9071`）
+    pub upstream: String,
+    /// 新しいブロックを確認する間隔（ミリ秒）
+    pub poll_interval: u64,
+    /// 1回の確認で取得する最大ブロック数
+    pub batch_size: u64,
+    /// 上流へのリクエストのタイムアウト（ミリ秒）
+    pub request_timeout: u64,
+    /// 空のチェーンを始める信頼するブロック（`<高さ>:<ハッシュ>`、`--trust-checkpoint`）
+    pub trust_checkpoint: Option<String>,
+    /// チェックポイントより前のブロックをバックグラウンドで取得する
+    pub backfill_history: bool,
+}
+
+impl Default for RpcReplicaSettings {
+    fn default() -> Self {
+        Self {
+            upstream: "http://localhost:9071".to_string(),
+            poll_interval: 1000,
+            batch_size: 100,
+            request_timeout: 5000,
+            trust_checkpoint: None,
+            backfill_history: false,
+        }
+    }
+}
+
+/// テレメトリー設定
+///
+/// 有効にした場合のみ、匿名化したノードの統計（バージョン、ピア数、ブロック高、OS/アーキテクチャ）を
+/// 定期的に送信します。ノード名・アドレス・IPなど個々のノードを特定できる情報は送信しません。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// 送信の有効化（既定では無効）
+    pub enabled: bool,
+    /// 送信先のURL
+    pub endpoint: String,
+    /// 送信間隔（秒）
+    pub interval: u64,
+    /// 送信のタイムアウト（ミリ秒）
+    pub request_timeout: u64,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "https://telemetry.rustorium.org/v1/reports".to_string(),
+            interval: 3600,
+            request_timeout: 10_000,
+        }
+    }
+}
+
+/// 表示言語の設定
+///
+/// メッセージファイルは `dir` の `<言語>.ftl` から読み込み、組み込みの翻訳を上書きします。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct I18nSettings {
+    /// 表示言語（例: `ja`、`pt-BR`）
+    pub language: String,
+    /// 翻訳がない場合に使う言語
+    pub fallback: String,
+    /// メッセージファイルのディレクトリ（存在しない場合は組み込みの翻訳のみ）
+    pub dir: PathBuf,
+}
+
+impl Default for I18nSettings {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            fallback: "en".to_string(),
+            dir: PathBuf::from("locales"),
+        }
+    }
+}
+
+/// 索引したチェーンデータへのSQL（`sql` フィーチャー）
+///
+/// 確定したブロックを `<data_dir>/columnar` に Parquet のセグメントとして書き出し、
+/// `/api/sql` で読み取り専用のクエリを実行します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SqlSettings {
+    /// 書き出しとSQLの有効化
+    pub enabled: bool,
+    /// 1つのセグメントのブロック数（満たない直近のブロックはメモリに保持する）
+    pub segment_blocks: u64,
+    /// 返す最大行数（超えた分は切り捨てる）
+    pub max_rows: usize,
+    /// クエリの実行時間の上限（秒）
+    pub timeout_secs: u64,
+    /// 1つのクエリが使うメモリの上限（MB）
+    pub memory_limit_mb: usize,
+    /// 同時に実行するクエリ数の上限
+    pub max_concurrent: usize,
+}
+
+impl Default for SqlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            segment_blocks: 1000,
+            max_rows: 10_000,
+            timeout_secs: 30,
+            memory_limit_mb: 512,
+            max_concurrent: 2,
+        }
+    }
+}
+
+/// チェーンデータの一括書き出し（`export` フィーチャー）
+///
+/// 確定したブロック・トランザクション・イベントを定期的に日付で分割したファイルに書き出します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ExportSettings {
+    /// 定期的な書き出しを有効化
+    pub enabled: bool,
+    /// 保存先（省略時は `<data_dir>/exports`）
+    pub dir: Option<PathBuf>,
+    /// 形式（`parquet`・`csv`）
+    pub formats: Vec<String>,
+    /// 書き出しの間隔（秒）
+    pub interval: u64,
+    /// 1つのファイルの最大ブロック数
+    pub max_blocks_per_file: u64,
+    /// S3（互換ストレージ）へのアップロード
+    pub s3: Option<S3Settings>,
+    /// アップロードしたファイルを保存先から削除する
+    pub delete_after_upload: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            formats: vec!["parquet".to_string()],
+            interval: 3600,
+            max_blocks_per_file: 100_000,
+            s3: None,
+            delete_after_upload: false,
+        }
+    }
+}
+
+/// ブロックとイベントの gRPC ストリーミング（`grpc` フィーチャー）
+///
+/// 各ストリームは `buffer` 件まで送信を先行し、受信が追いつかない場合は送信を待ちます。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct GrpcSettings {
+    /// gRPC サーバーの有効化
+    pub enabled: bool,
+    /// gRPC ポートのオフセット
+    pub port_offset: u16,
+    /// 待ち受けるアドレス（省略時は `network.host`）
+    pub bind: Option<String>,
+    /// ストリームごとに送信を先行するメッセージ数
+    pub buffer: usize,
+    /// 同時に開けるストリーム数（超えた場合は `RESOURCE_EXHAUSTED` で拒否する）
+    pub max_streams: usize,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port_offset: 4,
+            bind: None,
+            buffer: 64,
+            max_streams: 100,
+        }
+    }
+}
+
+/// データディレクトリのディスク容量の監視
+///
+/// 使用率が `cleanup_percent` を超えると古いスナップショットの削除とデータベースの圧縮を行い、
+/// `halt_percent` を超えるか空きが `min_free_mb` を下回るとブロックの確定を停止します。
+/// 確定の再開は使用率が `halt_percent` より2ポイント下がり、空きが `min_free_mb` 以上になってからです。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct DiskSettings {
+    /// 監視の有効化
+    pub enabled: bool,
+    /// 確認する間隔（秒）
+    pub interval: u64,
+    /// 削除と圧縮を始める使用率（%）
+    pub cleanup_percent: f64,
+    /// ブロックの確定を停止する使用率（%）
+    pub halt_percent: f64,
+    /// ブロックの確定を停止する空き容量（MB）
+    pub min_free_mb: u64,
+    /// 削除せずに残す障害予測のスナップショットの数
+    pub keep_snapshots: usize,
+}
+
+impl Default for DiskSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 30,
+            cleanup_percent: 85.0,
+            halt_percent: 95.0,
+            min_free_mb: 2048,
+            keep_snapshots: 1,
+        }
+    }
+}
+
+/// ビーコンチェーンの設定
+///
+/// `validators` が複数の場合は提案と投票をノード間で中継するため、各バリデーターの
+/// 署名鍵のアドレスで指定します。空の場合はこのノードだけがビーコンのバリデーターになります。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct BeaconSettings {
+    /// ビーコンのブロックを提案する間隔（秒）
+    pub block_interval: u64,
+    /// ビーコンのバリデーター（複数の場合はアドレス、1つの場合はノード名）
+    pub validators: Vec<String>,
+}
+
+impl Default for BeaconSettings {
+    fn default() -> Self {
+        Self {
+            block_interval: 5,
+            validators: Vec::new(),
+        }
+    }
+}
+
+/// コンセンサスパラメーター（ブロックの上限）
+///
+/// `gas_target` 以外はすべてのノードで同じ値にする必要があります。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ConsensusSettings {
+    /// ブロックの最大バイト数
+    pub max_block_bytes: usize,
+    /// ブロックのガス上限（動的調整が有効な場合は初期値）
+    pub block_gas_limit: u64,
+    /// ガス上限をブロック生成者の投票で調整する
+    pub dynamic_gas_limit: bool,
+    /// 動的調整の下限
+    pub min_gas_limit: u64,
+    /// 動的調整の上限
+    pub max_gas_limit: u64,
+    /// 1ブロックで変更できる割合（親のガス上限の `1/adjustment_quotient`）
+    pub adjustment_quotient: u64,
+    /// このノードが生成するブロックで投票するガス上限（省略時は現在の値を維持）
+    pub gas_target: Option<u64>,
+    /// ネットワークのチェーンID（テストネットとメインネットで異なる値にする）
+    pub chain_id: u64,
+    /// 基本手数料の初期値（0 は無効）。有効な場合、ガス価格がこれを下回るトランザクションはブロックに含められない
+    pub initial_base_fee: u64,
+    /// トランザクション1件のブロブの最大バイト数
+    pub max_blob_size: u64,
+    /// ブロックのブロブの最大バイト数（0 はブロブを無効にする）。ブロブ手数料はこの半分を目標に調整される
+    pub max_blob_bytes: u64,
+    /// ブロブ手数料（1バイトあたり）の最低値
+    pub min_blob_fee: u64,
+    /// この高さ以降のブロックはレシートのルート、生成者の署名と乱数の VRF 証明を必須にする（導入前から続くチェーンは導入する高さを設定する）
+    pub header_activation_height: u64,
+    /// ブロック0の前の残高とノンス（`system export-genesis` の形式、省略時は全て 0 から始まる）
+    pub genesis: Option<PathBuf>,
+}
+
+impl Default for ConsensusSettings {
+    fn default() -> Self {
+        Self {
+            max_block_bytes: 2 * 1024 * 1024,
+            block_gas_limit: 30_000_000,
+            dynamic_gas_limit: false,
+            min_gas_limit: 5_000_000,
+            max_gas_limit: 100_000_000,
+            adjustment_quotient: 1024,
+            gas_target: None,
+            chain_id: crate::core::wallet::DEFAULT_CHAIN_ID,
+            initial_base_fee: 0,
+            max_blob_size: 4 * 1024 * 1024,
+            max_blob_bytes: 0,
+            min_blob_fee: 1,
+            header_activation_height: 0,
+            genesis: None,
+        }
+    }
+}
+
+/// ブロブのサイドカーの保持設定
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct BlobSettings {
+    /// 確定したブロックのサイドカーを保持するブロック数
+    pub retention_blocks: u64,
+    /// 確定前のサイドカーを保持する最大バイト数（超えると古いものから破棄する）
+    pub max_pending_bytes: usize,
+}
+
+impl Default for BlobSettings {
+    fn default() -> Self {
+        Self {
+            retention_blocks: 4096,
+            max_pending_bytes: 256 * 1024 * 1024,
+        }
+    }
 }
 
 impl Default for NodeConfig {
@@ -153,31 +1285,52 @@ impl Default for NodeConfig {
                 host: "0.0.0.0".to_string(),
                 port: 9070,  // ダッシュボードポート
                 external_addr: None,
-                bootstrap_nodes: vec![
-                    // メインネットのブートストラップノード
-                    "/ip4/mainnet.rustorium.org/tcp/4001/p2p/12D3KooWQP6ubbGrRFGSbDyiCuw2mi1LMNLFPmwgGsXfGJNRvn2v".to_string(),
-                    "/ip4/mainnet2.rustorium.org/tcp/4001/p2p/12D3KooWBmT4c6YvhVYy3KmXMEGaxJXuTVqGtCwwS2GTncxSoje7".to_string(),
-                ],
+                bootstrap_nodes: Vec::new(),
+                address_prefix: default_address_prefix(),
+                gossip: GossipSettings::default(),
+                peers: PeerLimitSettings::default(),
+                diversity: DiversitySettings::default(),
+                relay: RelaySettings::default(),
+                sentry: SentrySettings::default(),
+                peering: PeeringSettings {
+                    // メインネットのDNSシード
+                    dns_seeds: vec![
+                        "mainnet.rustorium.org".to_string(),
+                        "mainnet2.rustorium.org".to_string(),
+                    ],
+                    ..Default::default()
+                },
             },
             web: WebSettings {
                 enabled: true,
                 port_offset: 0,  // 9070 (ダッシュボード)
+                bind: None,
                 open_browser: false,
             },
             api: ApiSettings {
                 enabled: true,
                 port_offset: 1,  // 9071 (API)
+                bind: None,
                 rate_limit: 1000,
-                cors_origins: vec!["*".to_string()],
+                cors: CorsSettings::default(),
+                admin_cors: CorsSettings::admin(),
+                admin_token: None,
+                tokens: Vec::new(),
+                access_log: AccessLogSettings::default(),
+                idempotency: IdempotencySettings::default(),
+                webauthn: WebAuthnSettings::default(),
             },
             websocket: WebSocketSettings {
                 enabled: true,
                 port_offset: 2,  // 9072 (WebSocket)
+                bind: None,
             },
             validator: ValidatorSettings {
                 stake: 0,
                 commission: 0.1,
                 min_stake: 100000,
+                signing_key: None,
+                shadow: false,
             },
             performance: PerformanceSettings {
                 max_peers: 50,
@@ -195,7 +1348,27 @@ impl Default for NodeConfig {
                 base_port: 8000,
                 auto_mining: false,
                 block_time: 2000,
+                chaos: ChaosSettings::default(),
+                fixture: None,
+                allow_unsigned: false,
             },
+            mempool: MempoolSettings::default(),
+            contracts: ContractSettings::default(),
+            sharding: ShardingSettings::default(),
+            geo: GeoSettings::default(),
+            streaming: StreamingSettings::default(),
+            ai: AiSettings::default(),
+            backup: BackupSettings::default(),
+            replica: RpcReplicaSettings::default(),
+            consensus: ConsensusSettings::default(),
+            blobs: BlobSettings::default(),
+            telemetry: TelemetrySettings::default(),
+            i18n: I18nSettings::default(),
+            sql: SqlSettings::default(),
+            export: ExportSettings::default(),
+            grpc: GrpcSettings::default(),
+            disk: DiskSettings::default(),
+            beacon: BeaconSettings::default(),
         }
     }
 }
@@ -238,6 +1411,7 @@ impl NodeConfig {
         self.web.port_offset = 1;
         self.api.port_offset = 2;
         self.websocket.port_offset = 3;
+        self.grpc.port_offset = 4;
 
         // テストモード設定
         if args.test {
@@ -272,16 +1446,29 @@ impl NodeConfig {
         format!("ws://localhost:{}", self.network.port + self.websocket.port_offset)
     }
 
+    /// サーバーが待ち受けるアドレス
+    ///
+    /// `bind` を指定していない場合は `network.host` を使います。`0.0.0.0`・`::`・`[::1]` のような
+    /// IPv4とIPv6のアドレスを受け付けます（ホスト名は不可）。
+    pub fn bind_addr(&self, bind: Option<&str>, port_offset: u16) -> anyhow::Result<std::net::SocketAddr> {
+        let host = bind.unwrap_or(&self.network.host);
+        let ip: std::net::IpAddr = host.trim_start_matches('[').trim_end_matches(']').parse()
+            .map_err(|_| anyhow::anyhow!("Invalid bind address {:?}: expected an IPv4 or IPv6 address", host))?;
+        let port = self.network.port.checked_add(port_offset)
+            .ok_or_else(|| anyhow::anyhow!("network.port {} + port_offset {} exceeds 65535", self.network.port, port_offset))?;
+        Ok(std::net::SocketAddr::new(ip, port))
+    }
+
     /// ノードの役割を自動判定
     pub fn detect_role(&mut self) {
-        // システム情報を取得
-        let cpu_cores = sys_info::cpu_num().unwrap_or(1);
-        let memory_gb = sys_info::mem_info().map(|m| m.total / 1024 / 1024).unwrap_or(0);
+        // システム情報を取得（コンテナの中では cgroup の制限）
+        let (cpu_cores, memory) = crate::util::cgroup::available_resources();
+        let memory_gb = memory / 1024 / 1024 / 1024;
 
         // 役割を判定
-        self.node.role = if memory_gb >= 16 && cpu_cores >= 4 {
+        self.node.role = if memory_gb >= 16 && cpu_cores >= 4.0 {
             "validator".to_string()
-        } else if memory_gb >= 8 && cpu_cores >= 2 {
+        } else if memory_gb >= 8 && cpu_cores >= 2.0 {
             "full".to_string()
         } else {
             "light".to_string()
@@ -295,6 +1482,7 @@ impl NodeConfig {
         config.node.data_dir = PathBuf::from("/tmp/rustorium/data");
         config.storage.path = PathBuf::from("/tmp/rustorium/data/storage");
         config.network.bootstrap_nodes.clear();
+        config.network.peering.dns_seeds.clear();
         config.dev.auto_mining = true;
         config.dev.block_time = 1000;
         config.performance.max_peers = 10;
@@ -302,8 +1490,41 @@ impl NodeConfig {
         config
     }
 
+    /// フィクスチャモードの設定
+    ///
+    /// 記録したスナップショット（`system snapshot` で作成）の複製を `<data_dir>/fixture` に開き、
+    /// コンセンサス・ブロック生成・P2P接続を行わずにAPIとWeb UIのみを提供します。
+    /// ホストの設定ファイルに依存しないよう、開発モードの既定値から作ります。
+    pub fn fixture(snapshot: PathBuf, data_dir: PathBuf) -> Self {
+        let mut config = Self::development();
+        config.storage.path = data_dir.join("fixture");
+        config.node.data_dir = data_dir;
+        config.network.peering.persistent_peers.clear();
+        config.dev.auto_mining = false;
+        config.dev.fixture = Some(snapshot);
+        config.web.enabled = true;
+        config.telemetry.enabled = false;
+        config.backup.enabled = false;
+        config.streaming.enabled = false;
+        config
+    }
+
     /// 設定ファイルから読み込む
     pub fn from_file(path: &str) -> anyhow::Result<Self> {
         Self::load(path)
     }
+
+    /// 読み取り専用レプリカとして動作するか
+    pub fn is_rpc_replica(&self) -> bool {
+        self.node.role == ROLE_RPC_REPLICA
+    }
+
+    /// ストレージのパス（未設定の場合は `<data_dir>/storage`）
+    pub fn storage_path(&self) -> PathBuf {
+        if self.storage.path.as_os_str().is_empty() {
+            self.node.data_dir.join("storage")
+        } else {
+            self.storage.path.clone()
+        }
+    }
 }
\ No newline at end of file
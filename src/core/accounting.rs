@@ -0,0 +1,367 @@
+//! 複式簿記の仕訳のエクスポート
+//!
+//! マテリアライズドビューのアーカイブから、指定したアカウントの期間内の取引を仕訳にします。
+//! 仕訳は借方と貸方が必ず一致し、次の勘定科目を使います。
+//! - `Wallet:<アドレス>`：エクスポートするアカウント（資産）
+//! - `External:<アドレス>`：それ以外の取引相手
+//! - `Expenses:Network Fees`：送信したトランザクションの手数料
+//! - `Income:Validator Rewards`：生成したブロックで受け取った手数料
+//!
+//! 出力はCSV（1行が仕訳の1明細）とQuickBooksのIIF（一般仕訳）です。
+//! 金額は最小単位の整数で、`decimals` を指定すると小数で出力します。
+
+use std::collections::{BTreeMap, HashSet};
+use anyhow::{Result, anyhow, bail};
+use chrono::{NaiveDate, TimeZone, Utc};
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::core::block::Chain;
+use crate::core::cache::{ArchiveRange, MaterializedViews, TxDirection};
+use crate::core::cache::views::MAX_ARCHIVE_PAGE;
+
+/// 手数料の勘定科目
+pub const FEES_ACCOUNT: &str = "Expenses:Network Fees";
+/// 報酬の勘定科目
+pub const REWARDS_ACCOUNT: &str = "Income:Validator Rewards";
+/// 1回のエクスポートで扱う最大アカウント数
+pub const MAX_ACCOUNTS: usize = 100;
+/// 小数の桁数の上限（u64 の最大値の桁数）
+pub const MAX_DECIMALS: u32 = 19;
+
+/// 仕訳の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// 送金
+    Transfer,
+    /// 送信したトランザクションの手数料
+    Fee,
+    /// ブロックの生成で受け取った手数料
+    Reward,
+}
+
+impl Category {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::Transfer => "transfer",
+            Category::Fee => "fee",
+            Category::Reward => "reward",
+        }
+    }
+}
+
+/// 仕訳の明細
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct JournalLine {
+    pub account: String,
+    pub debit: u64,
+    pub credit: u64,
+}
+
+/// 仕訳（明細の借方と貸方の合計は一致する）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JournalEntry {
+    /// 取引の識別子（トランザクションハッシュ、報酬はブロックハッシュ）
+    pub reference: String,
+    pub category: Category,
+    pub height: u64,
+    /// ブロックの生成時刻（UNIX秒）
+    pub timestamp: u64,
+    /// メモ（`<tag>:<payload>`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub lines: Vec<JournalLine>,
+}
+
+/// 勘定科目ごとの期間の合計
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AccountTotal {
+    pub account: String,
+    pub debits: u64,
+    pub credits: u64,
+}
+
+/// 期間の仕訳帳
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Ledger {
+    /// エクスポートしたアカウント
+    pub accounts: Vec<String>,
+    /// 期間（UNIX秒、両端を含む）
+    pub from: u64,
+    pub to: u64,
+    /// 時刻順の仕訳
+    pub entries: Vec<JournalEntry>,
+    /// 勘定科目ごとの合計（科目名の順）
+    pub totals: Vec<AccountTotal>,
+}
+
+/// `YYYY-MM-DD`（UTC）の期間を UNIX秒の範囲にする（終了日はその日の終わりまで）
+pub fn date_range(from: &str, to: &str) -> Result<ArchiveRange> {
+    let parse = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date '{}' (expected YYYY-MM-DD)", date));
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        bail!("The start date {} is after the end date {}", from, to);
+    }
+    let start = Utc.from_utc_datetime(&from.and_hms_opt(0, 0, 0).expect("midnight is valid")).timestamp();
+    let end = Utc.from_utc_datetime(&to.and_hms_opt(23, 59, 59).expect("23:59:59 is valid")).timestamp();
+    Ok(ArchiveRange { from: Some(start.max(0) as u64), to: Some(end.max(0) as u64) })
+}
+
+fn wallet_account(address: &str) -> String {
+    format!("Wallet:{}", address)
+}
+
+fn external_account(address: &str) -> String {
+    format!("External:{}", address)
+}
+
+/// アカウントの期間内の取引から仕訳帳を作成
+///
+/// エクスポートするアカウント同士の送金は1件の仕訳にまとめます。手数料を記録する前に
+/// アーカイブへ反映したトランザクションは、チェーンから手数料を読み直します。
+pub async fn build_ledger(
+    views: &MaterializedViews,
+    chain: &Chain,
+    accounts: &[String],
+    range: ArchiveRange,
+) -> Result<Ledger> {
+    if accounts.is_empty() {
+        bail!("At least one account is required");
+    }
+    if accounts.len() > MAX_ACCOUNTS {
+        bail!("At most {} accounts can be exported at once", MAX_ACCOUNTS);
+    }
+    let accounts: Vec<String> = accounts.iter()
+        .map(|a| a.trim().trim_start_matches("0x").to_lowercase())
+        .collect();
+    let own: HashSet<&str> = accounts.iter().map(String::as_str).collect();
+    let name = |address: &str| if own.contains(address) { wallet_account(address) } else { external_account(address) };
+
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    for account in &accounts {
+        let mut cursor = None;
+        loop {
+            let page = views.archive_transactions(account, range, cursor.as_deref(), MAX_ARCHIVE_PAGE).await?;
+            for tx in &page.items {
+                let (from, to) = match tx.direction {
+                    TxDirection::Out => (account.as_str(), tx.counterparty.as_str()),
+                    TxDirection::In => (tx.counterparty.as_str(), account.as_str()),
+                };
+                let memo = tx.memo.as_ref().map(|m| format!("{}:{}", m.tag, m.payload));
+                if tx.value > 0 && seen.insert((tx.hash.clone(), Category::Transfer)) {
+                    entries.push(JournalEntry {
+                        reference: tx.hash.clone(),
+                        category: Category::Transfer,
+                        height: tx.height,
+                        timestamp: tx.timestamp,
+                        memo: memo.clone(),
+                        lines: vec![
+                            JournalLine { account: name(to), debit: tx.value, credit: 0 },
+                            JournalLine { account: name(from), debit: 0, credit: tx.value },
+                        ],
+                    });
+                }
+                // 自分宛ての送金はアーカイブに受信として1件だけ記録される
+                if from == account.as_str() && seen.insert((tx.hash.clone(), Category::Fee)) {
+                    let fee = match tx.fee {
+                        Some(fee) => fee,
+                        None => chain.find_transaction(&tx.hash).await?
+                            .map_or(0, |detail| detail.receipt.gas_used.saturating_mul(detail.transaction.gas_price)),
+                    };
+                    if fee > 0 {
+                        entries.push(JournalEntry {
+                            reference: tx.hash.clone(),
+                            category: Category::Fee,
+                            height: tx.height,
+                            timestamp: tx.timestamp,
+                            memo,
+                            lines: vec![
+                                JournalLine { account: FEES_ACCOUNT.to_string(), debit: fee, credit: 0 },
+                                JournalLine { account: wallet_account(account), debit: 0, credit: fee },
+                            ],
+                        });
+                    }
+                }
+            }
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let mut cursor = None;
+        loop {
+            let page = views.rewards(account, range, cursor.as_deref(), MAX_ARCHIVE_PAGE).await?;
+            for reward in page.items {
+                entries.push(JournalEntry {
+                    reference: reward.hash,
+                    category: Category::Reward,
+                    height: reward.height,
+                    timestamp: reward.timestamp,
+                    memo: None,
+                    lines: vec![
+                        JournalLine { account: wallet_account(account), debit: reward.fees, credit: 0 },
+                        JournalLine { account: REWARDS_ACCOUNT.to_string(), debit: 0, credit: reward.fees },
+                    ],
+                });
+            }
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+    }
+    entries.sort_by(|a, b| (a.timestamp, a.height, &a.reference, a.category.as_str())
+        .cmp(&(b.timestamp, b.height, &b.reference, b.category.as_str())));
+
+    let mut totals: BTreeMap<String, AccountTotal> = BTreeMap::new();
+    for line in entries.iter().flat_map(|e| &e.lines) {
+        let total = totals.entry(line.account.clone()).or_insert_with(|| AccountTotal {
+            account: line.account.clone(),
+            ..Default::default()
+        });
+        total.debits = total.debits.saturating_add(line.debit);
+        total.credits = total.credits.saturating_add(line.credit);
+    }
+
+    Ok(Ledger {
+        accounts,
+        from: range.from.unwrap_or(0),
+        to: range.to.unwrap_or(u64::MAX),
+        entries,
+        totals: totals.into_values().collect(),
+    })
+}
+
+/// 最小単位の金額を `decimals` 桁の小数にする
+pub fn format_amount(amount: u64, decimals: u32) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{}.{}", whole, fraction)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn date(timestamp: u64, format: &str) -> String {
+    Utc.timestamp_opt(timestamp as i64, 0).single()
+        .map_or_else(String::new, |t| t.format(format).to_string())
+}
+
+impl Ledger {
+    /// CSV（仕訳の明細ごとに1行）
+    pub fn to_csv(&self, decimals: u32) -> String {
+        let mut out = String::from("date,timestamp,height,reference,category,account,debit,credit,memo\n");
+        for entry in &self.entries {
+            for line in &entry.lines {
+                let amount = |value: u64| if value == 0 { String::new() } else { format_amount(value, decimals) };
+                out.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},{}\n",
+                    date(entry.timestamp, "%Y-%m-%d"), entry.timestamp, entry.height, csv_field(&entry.reference),
+                    entry.category.as_str(), csv_field(&line.account), amount(line.debit), amount(line.credit),
+                    csv_field(entry.memo.as_deref().unwrap_or_default()),
+                ));
+            }
+        }
+        out
+    }
+
+    /// QuickBooks のIIF（一般仕訳、借方は正・貸方は負の金額）
+    pub fn to_iif(&self, decimals: u32) -> String {
+        // IIFはタブ区切りのため、値のタブと改行は空白にする
+        let field = |value: &str| value.replace(['\t', '\n', '\r'], " ");
+        let mut out = String::from(
+            "!TRNS\tTRNSTYPE\tDATE\tACCNT\tAMOUNT\tDOCNUM\tMEMO\n\
+             !SPL\tTRNSTYPE\tDATE\tACCNT\tAMOUNT\tDOCNUM\tMEMO\n\
+             !ENDTRNS\n",
+        );
+        for entry in &self.entries {
+            let memo = field(&match &entry.memo {
+                Some(memo) => format!("{} {} ({})", entry.category.as_str(), entry.height, memo),
+                None => format!("{} {}", entry.category.as_str(), entry.height),
+            });
+            for (index, line) in entry.lines.iter().enumerate() {
+                let amount = if line.debit > 0 {
+                    format_amount(line.debit, decimals)
+                } else {
+                    format!("-{}", format_amount(line.credit, decimals))
+                };
+                out.push_str(&format!(
+                    "{}\tGENERAL JOURNAL\t{}\t{}\t{}\t{}\t{}\n",
+                    if index == 0 { "TRNS" } else { "SPL" },
+                    date(entry.timestamp, "%m/%d/%Y"), field(&line.account), amount, field(&entry.reference), memo,
+                ));
+            }
+            out.push_str("ENDTRNS\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use ed25519_dalek::SigningKey;
+    use crate::core::block::Block;
+    use crate::core::cache::ExportedState;
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::{StorageEngine, redb_storage::RedbStorage};
+    use crate::core::wallet;
+
+    fn tx(from: &str, to: &str, value: u64, nonce: u64) -> PendingTransaction {
+        PendingTransaction { gas_price: 2, ..PendingTransaction::test_transfer(from, to, value, nonce) }.rehashed()
+    }
+
+    #[tokio::test]
+    async fn test_ledger_is_balanced_and_categorized() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let chain = Chain::open(storage.clone()).await.unwrap().with_allow_unsigned(true);
+        // 生成者は手数料を受け取るため、署名の鍵のアドレスにする
+        let (alice_key, carol_key) = (SigningKey::from_bytes(&[1; 32]), SigningKey::from_bytes(&[3; 32]));
+        let (alice, bob, carol) = (
+            wallet::address_of(&alice_key.verifying_key()),
+            "b".repeat(40),
+            wallet::address_of(&carol_key.verifying_key()),
+        );
+        let views = MaterializedViews::new(storage)
+            .with_genesis(ExportedState { balances: BTreeMap::from([(bob.clone(), 100)]), ..Default::default() });
+
+        let block = chain.next_block(alice.clone(), vec![tx(&bob, &alice, 100, 0)]).await;
+        chain.commit(chain.sign_block(block, &alice_key).await).await.unwrap();
+        let block = chain.next_block(carol.clone(), vec![tx(&alice, &bob, 30, 0)]).await;
+        chain.commit(chain.sign_block(block, &carol_key).await).await.unwrap();
+        views.catch_up(&chain).await.unwrap();
+
+        let ledger = build_ledger(&views, &chain, &[alice.clone(), bob.clone()], ArchiveRange::default()).await.unwrap();
+        let categories: Vec<Category> = ledger.entries.iter().map(|e| e.category).collect();
+        assert_eq!(categories.iter().filter(|c| **c == Category::Transfer).count(), 2);
+        assert_eq!(categories.iter().filter(|c| **c == Category::Fee).count(), 2);
+        // alice は最初のブロックの手数料を受け取り、carol の報酬は含めない
+        assert_eq!(categories.iter().filter(|c| **c == Category::Reward).count(), 1);
+        for entry in &ledger.entries {
+            let debits: u64 = entry.lines.iter().map(|l| l.debit).sum();
+            let credits: u64 = entry.lines.iter().map(|l| l.credit).sum();
+            assert_eq!(debits, credits, "{:?} is balanced", entry);
+        }
+        let fees = ledger.totals.iter().find(|t| t.account == FEES_ACCOUNT).unwrap();
+        assert_eq!(fees.debits, 2 * 42_000);
+        let alice_wallet = ledger.totals.iter().find(|t| t.account == wallet_account(&alice)).unwrap();
+        assert_eq!((alice_wallet.debits, alice_wallet.credits), (100 + 42_000, 30 + 42_000));
+
+        assert_eq!(format_amount(1_500, 3), "1.500");
+        assert_eq!(format_amount(5, 3), "0.005");
+        assert_eq!(ledger.to_csv(0).lines().count(), 1 + 2 * ledger.entries.len());
+        assert!(ledger.to_iif(0).contains("GENERAL JOURNAL"));
+    }
+}
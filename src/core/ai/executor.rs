@@ -0,0 +1,182 @@
+//! 最適化アクションの実行
+//!
+//! ドライランモードではアクションを実行せず、実行していた内容だけを記録します。
+//! 実行・スキップ・失敗はすべて監査ログに残ります。
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use tracing::{info, warn};
+use super::OptimizationAction;
+use super::policy::Decision;
+
+/// 監査ログの保持件数
+const AUDIT_LOG_CAPACITY: usize = 10_000;
+
+/// アクションを実際に適用するハンドラー
+pub trait ActionHandler: Send + Sync {
+    /// このハンドラーが扱うアクションか
+    fn handles(&self, action: OptimizationAction) -> bool;
+
+    fn apply(&self, action: OptimizationAction) -> Result<()>;
+}
+
+/// 実行結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ActionOutcome {
+    /// ドライランのため実行しなかった
+    DryRun,
+    Applied,
+    /// 扱うハンドラーがない
+    Unhandled,
+    Failed { error: String },
+}
+
+/// 監査ログのエントリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    /// 判定したポリシー
+    pub policy: String,
+    #[serde(flatten)]
+    pub decision: Decision,
+    #[serde(flatten)]
+    pub outcome: ActionOutcome,
+}
+
+/// アクション実行器
+pub struct ActionExecutor {
+    dry_run: bool,
+    handlers: Vec<Arc<dyn ActionHandler>>,
+    last_action: Option<OptimizationAction>,
+    audit_log: VecDeque<AuditEntry>,
+}
+
+impl ActionExecutor {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            handlers: Vec::new(),
+            last_action: None,
+            audit_log: VecDeque::new(),
+        }
+    }
+
+    /// ハンドラーを登録
+    pub fn register(&mut self, handler: Arc<dyn ActionHandler>) {
+        self.handlers.push(handler);
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn last_action(&self) -> Option<OptimizationAction> {
+        self.last_action
+    }
+
+    /// 判定結果を実行し、監査ログに記録
+    pub fn execute(&mut self, policy: &str, decision: Decision) -> ActionOutcome {
+        let action = decision.action;
+        let outcome = if action == OptimizationAction::Noop {
+            // 何もしないアクションは常に適用済みとして扱う
+            ActionOutcome::Applied
+        } else if self.dry_run {
+            info!(target: "audit", "[dry-run] Would {:?}: {}", action, decision.reason);
+            ActionOutcome::DryRun
+        } else {
+            match self.handlers.iter().find(|h| h.handles(action)) {
+                Some(handler) => match handler.apply(action) {
+                    Ok(()) => {
+                        info!(target: "audit", "Applied {:?}: {}", action, decision.reason);
+                        ActionOutcome::Applied
+                    }
+                    Err(e) => {
+                        warn!(target: "audit", "Failed to apply {:?}: {}", action, e);
+                        ActionOutcome::Failed { error: e.to_string() }
+                    }
+                },
+                None => {
+                    warn!(target: "audit", "No handler for {:?}: {}", action, decision.reason);
+                    ActionOutcome::Unhandled
+                }
+            }
+        };
+
+        if matches!(outcome, ActionOutcome::Applied) {
+            self.last_action = Some(action);
+        }
+        self.audit(policy, decision, outcome.clone());
+        outcome
+    }
+
+    /// 監査ログを新しい順に取得
+    pub fn audit_log(&self, limit: usize) -> Vec<AuditEntry> {
+        self.audit_log.iter().rev().take(limit).cloned().collect()
+    }
+
+    fn audit(&mut self, policy: &str, decision: Decision, outcome: ActionOutcome) {
+        if self.audit_log.len() >= AUDIT_LOG_CAPACITY {
+            self.audit_log.pop_front();
+        }
+        self.audit_log.push_back(AuditEntry {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            policy: policy.to_string(),
+            decision,
+            outcome,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counter(AtomicUsize);
+
+    impl ActionHandler for Counter {
+        fn handles(&self, action: OptimizationAction) -> bool {
+            action == OptimizationAction::ScaleOut
+        }
+
+        fn apply(&self, _action: OptimizationAction) -> Result<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn decision(action: OptimizationAction) -> Decision {
+        Decision { action, confidence: 1.0, reason: "test".to_string() }
+    }
+
+    #[test]
+    fn test_dry_run_records_without_applying() {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let mut executor = ActionExecutor::new(true);
+        executor.register(counter.clone());
+
+        assert!(matches!(executor.execute("heuristic", decision(OptimizationAction::ScaleOut)), ActionOutcome::DryRun));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+        assert_eq!(executor.last_action(), None);
+
+        executor.set_dry_run(false);
+        assert!(matches!(executor.execute("heuristic", decision(OptimizationAction::ScaleOut)), ActionOutcome::Applied));
+        assert!(matches!(executor.execute("heuristic", decision(OptimizationAction::ScaleIn)), ActionOutcome::Unhandled));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        let log = executor.audit_log(10);
+        assert_eq!(log.len(), 3);
+        assert!(matches!(log[2].outcome, ActionOutcome::DryRun));
+        assert_eq!(log[0].decision.action, OptimizationAction::ScaleIn);
+    }
+}
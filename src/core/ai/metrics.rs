@@ -0,0 +1,170 @@
+//! システムメトリクスの収集
+//!
+//! sysinfo を通じて CPU・メモリ・ディスク・ネットワークの使用状況を取得します。
+//! ディスクはデータディレクトリを含むファイルシステムのみを対象とします。
+//! コンテナの中では、CPUとメモリをホスト全体ではなく cgroup v2 の制限に対する使用率で表します。
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use serde::{Serialize, Deserialize};
+use sysinfo::{Disks, Networks, System};
+use crate::util::cgroup::Cgroup;
+
+/// ある時点のシステムメトリクス
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SystemMetrics {
+    /// 収集時刻（UNIX秒）
+    pub timestamp: u64,
+    /// CPU使用率（0.0〜1.0、全コア平均）
+    pub cpu_usage: f64,
+    /// 1分間のロードアベレージをコア数で割った値
+    pub load_per_cpu: f64,
+    /// cgroup で制限されたCPU数（制限がなければ `None`）
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+    pub memory_used: u64,
+    /// 使えるメモリ量（cgroup で制限されている場合はその上限）
+    pub memory_total: u64,
+    /// cgroup のメモリの上限（制限がなければ `None`）
+    #[serde(default)]
+    pub memory_limit: Option<u64>,
+    /// ノードプロセスの常駐メモリ
+    pub process_memory: u64,
+    /// データディレクトリのファイルシステムの使用量
+    pub disk_used: u64,
+    pub disk_total: u64,
+    /// 受信量（バイト/秒）
+    pub network_rx_rate: f64,
+    /// 送信量（バイト/秒）
+    pub network_tx_rate: f64,
+}
+
+impl SystemMetrics {
+    /// メモリ使用率（0.0〜1.0）
+    pub fn memory_usage(&self) -> f64 {
+        ratio(self.memory_used, self.memory_total)
+    }
+
+    /// ディスク使用率（0.0〜1.0）
+    pub fn disk_usage(&self) -> f64 {
+        ratio(self.disk_used, self.disk_total)
+    }
+}
+
+/// メトリクスコレクター
+pub struct MetricsCollector {
+    system: System,
+    disks: Disks,
+    networks: Networks,
+    data_dir: PathBuf,
+    cgroup: Option<Cgroup>,
+    /// 前回の cgroup のCPU使用時間（マイクロ秒）
+    last_cgroup_usage: Option<u64>,
+    /// 前回ネットワークカウンターを更新した時刻
+    last_refresh: Instant,
+}
+
+impl MetricsCollector {
+    /// データディレクトリを指定して作成
+    pub fn new(data_dir: impl AsRef<Path>) -> Self {
+        let mut system = System::new();
+        // CPU使用率は前回の更新との差分で計算されるため、基準値を取っておく
+        system.refresh_cpu();
+        let cgroup = Cgroup::detect();
+        let last_cgroup_usage = cgroup.as_ref().and_then(Cgroup::cpu_usage_usec);
+        Self {
+            system,
+            disks: Disks::new_with_refreshed_list(),
+            networks: Networks::new_with_refreshed_list(),
+            data_dir: data_dir.as_ref().canonicalize().unwrap_or_else(|_| data_dir.as_ref().to_path_buf()),
+            cgroup,
+            last_cgroup_usage,
+            last_refresh: Instant::now(),
+        }
+    }
+
+    /// 現在のメトリクスを収集
+    pub fn collect(&mut self) -> SystemMetrics {
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+        let pid = sysinfo::get_current_pid().ok();
+        if let Some(pid) = pid {
+            self.system.refresh_process(pid);
+        }
+        self.disks.refresh();
+        self.networks.refresh();
+
+        let elapsed = self.last_refresh.elapsed().as_secs_f64().max(f64::EPSILON);
+        self.last_refresh = Instant::now();
+
+        let (rx, tx) = self.networks
+            .iter()
+            .fold((0u64, 0u64), |(rx, tx), (_, data)| (rx + data.received(), tx + data.transmitted()));
+
+        let cpus = self.system.cpus().len().max(1) as f64;
+        let (disk_used, disk_total) = self.data_disk();
+
+        let mut metrics = SystemMetrics {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            cpu_usage: (self.system.global_cpu_info().cpu_usage() as f64 / 100.0).clamp(0.0, 1.0),
+            // ロードアベレージはホスト全体の値のため、cgroup の中でもホストのコア数で割る
+            load_per_cpu: System::load_average().one / cpus,
+            cpu_limit: None,
+            memory_used: self.system.used_memory(),
+            memory_total: self.system.total_memory(),
+            memory_limit: None,
+            process_memory: pid.and_then(|pid| self.system.process(pid)).map(|p| p.memory()).unwrap_or(0),
+            disk_used,
+            disk_total,
+            network_rx_rate: rx as f64 / elapsed,
+            network_tx_rate: tx as f64 / elapsed,
+        };
+
+        // ホストより小さい制限があれば、cgroup の使用量と制限で置き換える
+        if let Some(cgroup) = &self.cgroup {
+            if let Some(limit) = cgroup.cpus().filter(|&limit| limit < cpus) {
+                let usage = cgroup.cpu_usage_usec();
+                if let (Some(now), Some(before)) = (usage, self.last_cgroup_usage) {
+                    metrics.cpu_usage = (now.saturating_sub(before) as f64 / (elapsed * 1e6 * limit)).clamp(0.0, 1.0);
+                }
+                self.last_cgroup_usage = usage;
+                metrics.cpu_limit = Some(limit);
+            }
+            if let Some(limit) = cgroup.memory_limit().filter(|&limit| limit < metrics.memory_total) {
+                if let Some(used) = cgroup.memory_used() {
+                    metrics.memory_used = used;
+                }
+                metrics.memory_total = limit;
+                metrics.memory_limit = Some(limit);
+            }
+        }
+        metrics
+    }
+
+    /// データディレクトリを含むディスクの（使用量, 容量）
+    fn data_disk(&self) -> (u64, u64) {
+        disk_usage(&self.disks, &self.data_dir).unwrap_or((0, 0))
+    }
+}
+
+/// `path` を含むディスクの（使用量, 容量）
+pub fn disk_usage(disks: &Disks, path: &Path) -> Option<(u64, u64)> {
+    // マウントポイントが最も長く一致するものを選ぶ
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| (disk.total_space().saturating_sub(disk.available_space()), disk.total_space()))
+}
+
+fn ratio(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 / total as f64
+    }
+}
@@ -1,62 +1,202 @@
-use anyhow::Result;
+//! AI最適化エンジン
+//!
+//! システムメトリクスを定期的に収集し、ポリシーが決定したアクションを実行します。
+//!
+//! 主な機能：
+//! - sysinfo による CPU・メモリ・ディスク・ネットワークの収集
+//! - 差し替え可能な判定ポリシー（ヒューリスティック／学習済みモデル）
+//! - ドライランモードと監査ログ
+//! - 障害予測と緩和フック
+
+pub mod executor;
+pub mod metrics;
+pub mod policy;
+pub mod prediction;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
 use tokio::sync::Mutex;
-use tracing::{info, warn};
+use tracing::info;
+use crate::config::AiSettings;
+use prediction::Mitigator;
+
+pub use executor::{ActionExecutor, ActionHandler, ActionOutcome, AuditEntry};
+pub use metrics::{MetricsCollector, SystemMetrics};
+pub use policy::{Decision, HeuristicPolicy, ModelPolicy, OptimizationPolicy};
+pub use prediction::{FailureKind, FailurePredictor, MitigationHook, Prediction, PredictorConfig, SnapshotHook};
+
+/// 最適化エンジンの設定
+#[derive(Debug, Clone)]
+pub struct AiConfig {
+    /// `heuristic` または `model`
+    pub policy: String,
+    /// `model` ポリシーのモデルファイル
+    pub model_path: Option<PathBuf>,
+    /// モデルの判定を採用する最小の確信度
+    pub min_confidence: f64,
+    pub scale_out_threshold: f64,
+    pub scale_in_threshold: f64,
+    pub max_error_rate: f64,
+    /// アクションを実行せずに記録のみ行う
+    pub dry_run: bool,
+    pub predictor: PredictorConfig,
+    /// 緩和フックを発動する確率
+    pub mitigation_threshold: f64,
+    /// 有効化する緩和フック
+    pub mitigations: Vec<String>,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self::from(&AiSettings::default())
+    }
+}
+
+impl From<&AiSettings> for AiConfig {
+    fn from(settings: &AiSettings) -> Self {
+        Self {
+            policy: settings.policy.clone(),
+            model_path: settings.model_path.clone(),
+            min_confidence: settings.min_confidence,
+            scale_out_threshold: settings.scale_out_threshold,
+            scale_in_threshold: settings.scale_in_threshold,
+            max_error_rate: settings.max_error_rate,
+            dry_run: settings.dry_run,
+            predictor: PredictorConfig {
+                horizon: settings.prediction_horizon,
+                smart_devices: settings.smart_devices.clone(),
+                max_peer_churn: settings.max_peer_churn,
+            },
+            mitigation_threshold: settings.mitigation_threshold,
+            mitigations: settings.mitigations.clone(),
+        }
+    }
+}
 
-#[derive(Debug)]
 pub struct AiOptimizer {
+    collector: MetricsCollector,
+    policy: Box<dyn OptimizationPolicy>,
     metrics: Arc<Mutex<NetworkMetrics>>,
-    executor: Arc<Mutex<OptimizationExecutor>>,
+    last_metrics: Option<SystemMetrics>,
+    executor: ActionExecutor,
+    predictor: FailurePredictor,
+    mitigator: Mitigator,
+    predictions: Vec<Prediction>,
 }
 
 impl AiOptimizer {
-    pub fn new() -> Self {
+    /// 設定からポリシーを選んで作成
+    pub fn new(config: AiConfig, data_dir: impl AsRef<Path>) -> Result<Self> {
+        let policy: Box<dyn OptimizationPolicy> = match config.policy.as_str() {
+            "model" => {
+                let path = config.model_path.as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("ai.model_path is required for the model policy"))?;
+                Box::new(ModelPolicy::load(path, config.min_confidence)?)
+            }
+            "heuristic" => Box::new(HeuristicPolicy {
+                scale_out_threshold: config.scale_out_threshold,
+                scale_in_threshold: config.scale_in_threshold,
+                max_error_rate: config.max_error_rate,
+            }),
+            other => return Err(anyhow::anyhow!("Unknown AI policy: {}", other)),
+        };
+        Ok(Self::with_policy(policy, config, data_dir))
+    }
+
+    /// ポリシーを指定して作成
+    pub fn with_policy(policy: Box<dyn OptimizationPolicy>, config: AiConfig, data_dir: impl AsRef<Path>) -> Self {
         Self {
+            collector: MetricsCollector::new(data_dir),
+            policy,
             metrics: Arc::new(Mutex::new(NetworkMetrics::default())),
-            executor: Arc::new(Mutex::new(OptimizationExecutor::default())),
+            last_metrics: None,
+            executor: ActionExecutor::new(config.dry_run),
+            predictor: FailurePredictor::new(config.predictor),
+            mitigator: Mitigator::new(config.mitigations, config.mitigation_threshold),
+            predictions: Vec::new(),
         }
     }
 
+    /// アクションハンドラーを登録
+    pub fn register_handler(&mut self, handler: Arc<dyn ActionHandler>) {
+        self.executor.register(handler);
+    }
+
+    /// 緩和フックを登録（`ai.mitigations` に含まれるもののみ発動する）
+    pub fn register_mitigation(&mut self, hook: Arc<dyn MitigationHook>) {
+        self.mitigator.register(hook);
+    }
+
+    /// 接続中のピアを記録
+    pub fn observe_peers(&mut self, peers: HashSet<String>) {
+        self.predictor.observe_peers(peers);
+    }
+
     pub async fn optimize_system(&mut self) -> Result<()> {
-        info!("Running AI optimization...");
+        info!("Running AI optimization with {} policy...", self.policy.name());
 
         // メトリクスの収集
-        let metrics = self.metrics.lock().await;
-        let current_state = metrics.get_current_state();
+        let system = self.collector.collect();
+        self.predict_failures(&system).await;
+        let state = SystemState {
+            system: system.clone(),
+            network: self.metrics.lock().await.clone(),
+        };
+        self.last_metrics = Some(system);
 
         // 最適化アクションの決定
-        let action = self.determine_action(&current_state);
-        info!("Determined optimization action: {:?}", action);
+        let decision = self.policy.decide(&state);
+        info!("Determined optimization action: {:?} ({})", decision.action, decision.reason);
 
         // アクションの実行
-        let mut executor = self.executor.lock().await;
-        executor.execute(action).await?;
+        if let ActionOutcome::Failed { error } = self.executor.execute(self.policy.name(), decision) {
+            return Err(anyhow::anyhow!("Optimization action failed: {}", error));
+        }
 
         Ok(())
     }
 
+    /// メトリクスを記録して障害を予測し、必要に応じて緩和フックを呼び出す
+    pub async fn predict_failures(&mut self, metrics: &SystemMetrics) -> Vec<Prediction> {
+        self.predictor.observe(metrics);
+        let predictions = self.predictor.predict(metrics).await;
+        self.mitigator.apply(&predictions);
+        self.predictions = predictions.clone();
+        predictions
+    }
+
+    /// 最後に計算した障害予測
+    pub fn predictions(&self) -> &[Prediction] {
+        &self.predictions
+    }
+
     pub async fn get_network_metrics(&self) -> NetworkMetrics {
         self.metrics.lock().await.clone()
     }
 
+    /// 最後に収集したシステムメトリクス
+    pub fn system_metrics(&self) -> Option<&SystemMetrics> {
+        self.last_metrics.as_ref()
+    }
+
+    pub fn executor(&self) -> &ActionExecutor {
+        &self.executor
+    }
+
+    pub fn executor_mut(&mut self) -> &mut ActionExecutor {
+        &mut self.executor
+    }
+
     pub async fn shutdown(&mut self) -> Result<()> {
         info!("Shutting down AI optimizer...");
         Ok(())
     }
-
-    fn determine_action(&self, state: &SystemState) -> OptimizationAction {
-        // 簡単な決定ロジック
-        if state.load > 0.8 {
-            OptimizationAction::ScaleOut
-        } else if state.load < 0.2 {
-            OptimizationAction::ScaleIn
-        } else {
-            OptimizationAction::Noop
-        }
-    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkMetrics {
     pub average_latency: f64,
     pub throughput: f64,
@@ -64,51 +204,23 @@ pub struct NetworkMetrics {
 }
 
 impl NetworkMetrics {
-    pub fn get_current_state(&self) -> SystemState {
-        SystemState {
-            load: self.throughput / 100000.0, // 100K TPSを基準
-            latency: self.average_latency,
-            errors: self.error_rate,
-        }
+    /// スループットによる負荷（100K TPSを基準）
+    pub fn load(&self) -> f64 {
+        self.throughput / 100000.0
     }
 }
 
-#[derive(Debug)]
+/// ポリシーへの入力
+#[derive(Debug, Clone)]
 pub struct SystemState {
-    pub load: f64,
-    pub latency: f64,
-    pub errors: f64,
+    pub system: SystemMetrics,
+    pub network: NetworkMetrics,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum OptimizationAction {
     ScaleOut,
     ScaleIn,
     Noop,
 }
-
-#[derive(Debug, Default)]
-pub struct OptimizationExecutor {
-    last_action: Option<OptimizationAction>,
-}
-
-impl OptimizationExecutor {
-    pub async fn execute(&mut self, action: OptimizationAction) -> Result<()> {
-        match action {
-            OptimizationAction::ScaleOut => {
-                info!("Scaling out resources...");
-                // TODO: 実際のスケールアウト処理
-            }
-            OptimizationAction::ScaleIn => {
-                info!("Scaling in resources...");
-                // TODO: 実際のスケールイン処理
-            }
-            OptimizationAction::Noop => {
-                info!("No optimization needed");
-            }
-        }
-
-        self.last_action = Some(action);
-        Ok(())
-    }
-}
@@ -0,0 +1,272 @@
+//! 最適化ポリシー
+//!
+//! 収集したメトリクスから実行するアクションを決定します。
+//! `OptimizationPolicy` を実装すれば判定ロジックを差し替えられます。
+//!
+//! 主な機能：
+//! - しきい値によるヒューリスティック判定
+//! - 学習済み線形モデル（JSON）による判定
+
+use std::collections::HashMap;
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use super::{OptimizationAction, SystemState};
+
+/// ポリシーの判定結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    pub action: OptimizationAction,
+    /// 判定の確信度（0.0〜1.0）
+    pub confidence: f64,
+    /// 判定理由
+    pub reason: String,
+}
+
+impl Decision {
+    fn noop(reason: impl Into<String>) -> Self {
+        Self {
+            action: OptimizationAction::Noop,
+            confidence: 1.0,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// 最適化ポリシー
+pub trait OptimizationPolicy: Send + Sync {
+    /// ポリシー名（監査ログに記録）
+    fn name(&self) -> &str;
+
+    /// 現在の状態から実行するアクションを決定
+    fn decide(&self, state: &SystemState) -> Decision;
+}
+
+/// しきい値によるポリシー
+#[derive(Debug, Clone)]
+pub struct HeuristicPolicy {
+    /// 負荷がこれを超えたらスケールアウト
+    pub scale_out_threshold: f64,
+    /// 負荷がこれを下回ったらスケールイン
+    pub scale_in_threshold: f64,
+    /// エラー率がこれを超えたら負荷に関わらずスケールアウト
+    pub max_error_rate: f64,
+}
+
+impl Default for HeuristicPolicy {
+    fn default() -> Self {
+        Self {
+            scale_out_threshold: 0.8,
+            scale_in_threshold: 0.2,
+            max_error_rate: 0.05,
+        }
+    }
+}
+
+impl OptimizationPolicy for HeuristicPolicy {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn decide(&self, state: &SystemState) -> Decision {
+        if state.network.error_rate > self.max_error_rate {
+            return Decision {
+                action: OptimizationAction::ScaleOut,
+                confidence: 1.0,
+                reason: format!("error rate {:.3} exceeds {:.3}", state.network.error_rate, self.max_error_rate),
+            };
+        }
+
+        // 最も逼迫しているリソースで判定する
+        let (resource, load) = [
+            ("cpu", state.system.cpu_usage),
+            ("load", state.system.load_per_cpu.min(1.0)),
+            ("memory", state.system.memory_usage()),
+            ("throughput", state.network.load()),
+        ]
+        .into_iter()
+        .fold(("cpu", 0.0), |max, current| if current.1 > max.1 { current } else { max });
+
+        if load > self.scale_out_threshold {
+            Decision {
+                action: OptimizationAction::ScaleOut,
+                confidence: 1.0,
+                reason: format!("{} at {:.2} exceeds {:.2}", resource, load, self.scale_out_threshold),
+            }
+        } else if load < self.scale_in_threshold {
+            Decision {
+                action: OptimizationAction::ScaleIn,
+                confidence: 1.0,
+                reason: format!("peak load {:.2} ({}) below {:.2}", load, resource, self.scale_in_threshold),
+            }
+        } else {
+            Decision::noop(format!("peak load {:.2} ({}) within bounds", load, resource))
+        }
+    }
+}
+
+/// アクションごとの重み
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActionWeights {
+    #[serde(default)]
+    pub bias: f64,
+    /// 特徴量名 → 重み
+    #[serde(default)]
+    pub weights: HashMap<String, f64>,
+}
+
+/// 学習済みの線形モデルによるポリシー
+///
+/// モデルファイルはアクションごとの重みを持つJSONです。
+/// ```json
+/// { "scale_out": { "bias": -3.0, "weights": { "cpu_usage": 4.0 } },
+///   "scale_in":  { "bias": 1.0,  "weights": { "cpu_usage": -4.0 } },
+///   "noop":      { "bias": 0.5 } }
+/// ```
+/// 各アクションのスコアをソフトマックスで確率に変換し、最も高いものを選びます。
+#[derive(Debug, Clone)]
+pub struct ModelPolicy {
+    actions: Vec<(OptimizationAction, ActionWeights)>,
+    /// 確信度がこれを下回る場合は何もしない
+    min_confidence: f64,
+}
+
+impl ModelPolicy {
+    /// 利用できる特徴量
+    pub const FEATURES: &'static [&'static str] = &[
+        "cpu_usage",
+        "load_per_cpu",
+        "memory_usage",
+        "disk_usage",
+        "network_rx_mbps",
+        "network_tx_mbps",
+        "latency_ms",
+        "throughput_load",
+        "error_rate",
+    ];
+
+    pub fn new(actions: HashMap<OptimizationAction, ActionWeights>, min_confidence: f64) -> Result<Self> {
+        if actions.is_empty() {
+            return Err(anyhow!("Model defines no actions"));
+        }
+        for (action, weights) in &actions {
+            if let Some(unknown) = weights.weights.keys().find(|f| !Self::FEATURES.contains(&f.as_str())) {
+                return Err(anyhow!("Unknown feature {:?} for action {:?}", unknown, action));
+            }
+        }
+        Ok(Self {
+            actions: actions.into_iter().collect(),
+            min_confidence,
+        })
+    }
+
+    /// モデルファイルを読み込む
+    pub fn load(path: &Path, min_confidence: f64) -> Result<Self> {
+        let actions = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| anyhow!("Invalid model file {}: {}", path.display(), e))?;
+        Self::new(actions, min_confidence)
+    }
+
+    fn features(state: &SystemState) -> HashMap<&'static str, f64> {
+        HashMap::from([
+            ("cpu_usage", state.system.cpu_usage),
+            ("load_per_cpu", state.system.load_per_cpu),
+            ("memory_usage", state.system.memory_usage()),
+            ("disk_usage", state.system.disk_usage()),
+            ("network_rx_mbps", state.system.network_rx_rate / 1_000_000.0),
+            ("network_tx_mbps", state.system.network_tx_rate / 1_000_000.0),
+            ("latency_ms", state.network.average_latency),
+            ("throughput_load", state.network.load()),
+            ("error_rate", state.network.error_rate),
+        ])
+    }
+}
+
+impl OptimizationPolicy for ModelPolicy {
+    fn name(&self) -> &str {
+        "model"
+    }
+
+    fn decide(&self, state: &SystemState) -> Decision {
+        let features = Self::features(state);
+        let scores: Vec<f64> = self.actions
+            .iter()
+            .map(|(_, w)| {
+                w.bias + w.weights.iter().map(|(f, weight)| weight * features[f.as_str()]).sum::<f64>()
+            })
+            .collect();
+
+        // ソフトマックス（オーバーフローを避けるため最大値を引く）
+        let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let exp: Vec<f64> = scores.iter().map(|s| (s - max).exp()).collect();
+        let total: f64 = exp.iter().sum();
+        let (best, probability) = exp
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (i, e / total))
+            .fold((0, 0.0), |max, current| if current.1 > max.1 { current } else { max });
+
+        let action = self.actions[best].0;
+        if probability < self.min_confidence {
+            return Decision::noop(format!(
+                "{:?} confidence {:.2} below {:.2}", action, probability, self.min_confidence
+            ));
+        }
+        Decision {
+            action,
+            confidence: probability,
+            reason: format!("model selected {:?} with p={:.2}", action, probability),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ai::{NetworkMetrics, SystemMetrics};
+
+    fn state(cpu_usage: f64) -> SystemState {
+        SystemState {
+            system: SystemMetrics {
+                cpu_usage,
+                memory_used: 1,
+                memory_total: 4,
+                ..Default::default()
+            },
+            network: NetworkMetrics::default(),
+        }
+    }
+
+    #[test]
+    fn test_heuristic_uses_peak_resource() {
+        let policy = HeuristicPolicy::default();
+        assert_eq!(policy.decide(&state(0.95)).action, OptimizationAction::ScaleOut);
+        assert_eq!(policy.decide(&state(0.5)).action, OptimizationAction::Noop);
+        // メモリ使用率が 0.25 のため CPU が低くてもスケールインしない
+        assert_eq!(policy.decide(&state(0.05)).action, OptimizationAction::Noop);
+    }
+
+    #[test]
+    fn test_model_policy() {
+        let model = serde_json::json!({
+            "scale_out": { "bias": -4.0, "weights": { "cpu_usage": 8.0 } },
+            "noop": { "bias": 0.0 },
+        });
+        let policy = ModelPolicy::new(serde_json::from_value(model).unwrap(), 0.6).unwrap();
+
+        let decision = policy.decide(&state(0.95));
+        assert_eq!(decision.action, OptimizationAction::ScaleOut);
+        assert!(decision.confidence > 0.9);
+        assert_eq!(policy.decide(&state(0.1)).action, OptimizationAction::Noop);
+        // 確信度が低い場合は何もしない
+        let uncertain = policy.decide(&state(0.5));
+        assert_eq!(uncertain.action, OptimizationAction::Noop);
+        assert!(uncertain.reason.contains("below"));
+    }
+
+    #[test]
+    fn test_model_rejects_unknown_feature() {
+        let model = serde_json::json!({ "noop": { "weights": { "moon_phase": 1.0 } } });
+        assert!(ModelPolicy::new(serde_json::from_value(model).unwrap(), 0.0).is_err());
+    }
+}
@@ -0,0 +1,392 @@
+//! 障害予測
+//!
+//! メトリクスの推移とOSのシグナルから、近い将来に起こりうる障害を確率付きで予測します。
+//!
+//! 主な機能：
+//! - ディスクのSMART情報（`smartctl` を使用）
+//! - ファイルディスクリプタの枯渇
+//! - メモリ・ディスク使用量の増加傾向（最小二乗法による傾き）
+//! - ピアの入れ替わり（チャーン）
+//! - しきい値を超えたときの緩和フック
+
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use super::SystemMetrics;
+use crate::core::storage::redb_storage::RedbStorage;
+
+/// 傾きの計算に使うサンプル数
+const HISTORY_LEN: usize = 60;
+/// 傾きを計算するのに必要な最小サンプル数
+const MIN_SAMPLES: usize = 5;
+
+/// 予測する障害の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// ディスクのハードウェア障害
+    DiskHardware,
+    /// ディスク容量の枯渇
+    DiskFull,
+    /// ファイルディスクリプタの枯渇
+    FdExhaustion,
+    /// メモリの枯渇
+    MemoryExhaustion,
+    /// ピアの入れ替わりによるネットワークからの孤立
+    PeerChurn,
+}
+
+/// 障害の予測
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Prediction {
+    pub kind: FailureKind,
+    /// 予測期間内に障害が起きる確率（0.0〜1.0）
+    pub probability: f64,
+    /// 障害までの推定時間（秒）
+    pub eta: Option<u64>,
+    /// 根拠
+    pub detail: String,
+}
+
+/// 障害予測の設定
+#[derive(Debug, Clone)]
+pub struct PredictorConfig {
+    /// 予測期間（秒）
+    pub horizon: u64,
+    /// SMART情報を確認するデバイス（例: `/dev/sda`）
+    pub smart_devices: Vec<String>,
+    /// この割合のピアが1回の観測で入れ替わったら確率を1とする
+    pub max_peer_churn: f64,
+}
+
+/// 予測がしきい値を超えたときに呼ばれる緩和フック
+pub trait MitigationHook: Send + Sync {
+    /// フック名（`ai.mitigations` で有効化する）
+    fn name(&self) -> &str;
+
+    /// しきい値を超える予測が現れたときに呼ばれる
+    fn trigger(&self, predictions: &[Prediction]) -> Result<()>;
+
+    /// しきい値を超える予測がなくなったときに呼ばれる
+    fn resolve(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 障害予測器
+pub struct FailurePredictor {
+    config: PredictorConfig,
+    /// （時刻, プロセスのメモリ, ディスク使用量）
+    history: VecDeque<(u64, u64, u64)>,
+    /// 観測したピア数と入れ替わり率
+    peers: Option<HashSet<String>>,
+    peer_churn: VecDeque<(usize, f64)>,
+}
+
+impl FailurePredictor {
+    pub fn new(config: PredictorConfig) -> Self {
+        Self {
+            config,
+            history: VecDeque::new(),
+            peers: None,
+            peer_churn: VecDeque::new(),
+        }
+    }
+
+    /// システムメトリクスを記録
+    pub fn observe(&mut self, metrics: &SystemMetrics) {
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back((metrics.timestamp, metrics.process_memory, metrics.disk_used));
+    }
+
+    /// 接続中のピアを記録
+    pub fn observe_peers(&mut self, peers: HashSet<String>) {
+        if let Some(previous) = &self.peers {
+            let changed = previous.symmetric_difference(&peers).count();
+            let churn = changed as f64 / previous.len().max(peers.len()).max(1) as f64;
+            if self.peer_churn.len() >= HISTORY_LEN {
+                self.peer_churn.pop_front();
+            }
+            self.peer_churn.push_back((peers.len(), churn));
+        }
+        self.peers = Some(peers);
+    }
+
+    /// 現在の予測を計算（確率の高い順）
+    pub async fn predict(&self, metrics: &SystemMetrics) -> Vec<Prediction> {
+        let mut predictions: Vec<Prediction> = [
+            self.predict_growth(FailureKind::MemoryExhaustion, |s| s.1, metrics.memory_total.saturating_sub(metrics.memory_used)),
+            self.predict_growth(FailureKind::DiskFull, |s| s.2, metrics.disk_total.saturating_sub(metrics.disk_used)),
+            predict_fd_exhaustion(),
+            self.predict_peer_churn(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        for device in &self.config.smart_devices {
+            match read_smart(device).await {
+                Ok(prediction) => predictions.extend(prediction),
+                Err(e) => debug!("Failed to read SMART data for {}: {}", device, e),
+            }
+        }
+
+        predictions.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        predictions
+    }
+
+    /// 使用量の増加傾向から残り容量を使い切るまでの時間を予測
+    fn predict_growth(&self, kind: FailureKind, value: impl Fn(&(u64, u64, u64)) -> u64, remaining: u64) -> Option<Prediction> {
+        if self.history.len() < MIN_SAMPLES {
+            return None;
+        }
+        let points: Vec<(f64, f64)> = self.history.iter().map(|s| (s.0 as f64, value(s) as f64)).collect();
+        let slope = slope(&points)?;
+        if slope <= 0.0 {
+            return None;
+        }
+
+        let eta = remaining as f64 / slope;
+        Some(Prediction {
+            kind,
+            probability: (1.0 - eta / self.config.horizon.max(1) as f64).clamp(0.0, 1.0),
+            eta: Some(eta as u64),
+            detail: format!("growing {:.0} bytes/s with {} bytes remaining", slope, remaining),
+        })
+    }
+
+    fn predict_peer_churn(&self) -> Option<Prediction> {
+        let (peers, _) = *self.peer_churn.back()?;
+        let had_peers = self.peer_churn.iter().any(|(count, _)| *count > 0);
+        if peers == 0 && had_peers {
+            return Some(Prediction {
+                kind: FailureKind::PeerChurn,
+                probability: 0.9,
+                eta: Some(0),
+                detail: "all peers disconnected".to_string(),
+            });
+        }
+
+        let average = self.peer_churn.iter().map(|(_, churn)| churn).sum::<f64>() / self.peer_churn.len() as f64;
+        Some(Prediction {
+            kind: FailureKind::PeerChurn,
+            probability: (average / self.config.max_peer_churn.max(f64::EPSILON)).clamp(0.0, 1.0),
+            eta: None,
+            detail: format!("average churn {:.2} across {} observations with {} peers", average, self.peer_churn.len(), peers),
+        })
+    }
+}
+
+/// 最小二乗法による傾き（値/秒）
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    Some(points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum::<f64>() / variance)
+}
+
+/// プロセスのファイルディスクリプタ使用率から予測（Linuxのみ）
+fn predict_fd_exhaustion() -> Option<Prediction> {
+    let open = std::fs::read_dir("/proc/self/fd").ok()?.count();
+    let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+    let limit: usize = limits
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+
+    let usage = open as f64 / limit.max(1) as f64;
+    // 使用率 50% から確率が上がり始める
+    Some(Prediction {
+        kind: FailureKind::FdExhaustion,
+        probability: ((usage - 0.5) / 0.5).clamp(0.0, 1.0),
+        eta: None,
+        detail: format!("{} of {} file descriptors open", open, limit),
+    })
+}
+
+/// `smartctl` でディスクの健全性を確認
+async fn read_smart(device: &str) -> Result<Option<Prediction>> {
+    let output = tokio::process::Command::new("smartctl")
+        .args(["--json", "-H", "-A", device])
+        .output()
+        .await?;
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+
+    if report["smart_status"]["passed"].as_bool() == Some(false) {
+        return Ok(Some(Prediction {
+            kind: FailureKind::DiskHardware,
+            probability: 0.95,
+            eta: None,
+            detail: format!("{} failed SMART overall health self-assessment", device),
+        }));
+    }
+
+    // 代替処理済み・保留中・回復不能セクタ（ATA）とメディアエラー（NVMe）
+    let sectors: u64 = report["ata_smart_attributes"]["table"]
+        .as_array()
+        .map(|table| {
+            table
+                .iter()
+                .filter(|attr| matches!(attr["id"].as_u64(), Some(5 | 197 | 198)))
+                .filter_map(|attr| attr["raw"]["value"].as_u64())
+                .sum()
+        })
+        .unwrap_or(0)
+        + report["nvme_smart_health_information_log"]["media_errors"].as_u64().unwrap_or(0);
+    let wear = report["nvme_smart_health_information_log"]["percentage_used"].as_u64().unwrap_or(0);
+
+    if sectors == 0 && wear < 90 {
+        return Ok(None);
+    }
+    Ok(Some(Prediction {
+        kind: FailureKind::DiskHardware,
+        probability: (0.3 + sectors as f64 / 100.0 + wear.saturating_sub(90) as f64 / 20.0).min(0.9),
+        eta: None,
+        detail: format!("{}: {} bad sectors or media errors, {}% endurance used", device, sectors, wear),
+    }))
+}
+
+/// しきい値を超えた予測に応じてフックを呼び出す
+pub(crate) struct Mitigator {
+    hooks: Vec<Arc<dyn MitigationHook>>,
+    /// 有効化されたフック名
+    enabled: Vec<String>,
+    threshold: f64,
+    /// 現在発動中か
+    active: bool,
+}
+
+impl Mitigator {
+    pub(crate) fn new(enabled: Vec<String>, threshold: f64) -> Self {
+        Self {
+            hooks: Vec::new(),
+            enabled,
+            threshold,
+            active: false,
+        }
+    }
+
+    pub(crate) fn register(&mut self, hook: Arc<dyn MitigationHook>) {
+        self.hooks.push(hook);
+    }
+
+    /// 予測に応じてフックを発動・解除
+    pub(crate) fn apply(&mut self, predictions: &[Prediction]) {
+        let alerts: Vec<Prediction> = predictions
+            .iter()
+            .filter(|p| p.probability >= self.threshold)
+            .cloned()
+            .collect();
+
+        // 状態が変わったときのみ呼び出す
+        if alerts.is_empty() == !self.active {
+            return;
+        }
+        self.active = !alerts.is_empty();
+
+        for hook in self.hooks.iter().filter(|h| self.enabled.iter().any(|name| name == h.name())) {
+            let result = if self.active {
+                warn!(target: "audit", "Triggering mitigation {} for {:?}", hook.name(), alerts.iter().map(|p| p.kind).collect::<Vec<_>>());
+                hook.trigger(&alerts)
+            } else {
+                warn!(target: "audit", "Resolving mitigation {}", hook.name());
+                hook.resolve()
+            };
+            if let Err(e) = result {
+                warn!(target: "audit", "Mitigation {} failed: {}", hook.name(), e);
+            }
+        }
+    }
+}
+
+/// ストレージのスナップショットを作成するフック
+pub struct SnapshotHook {
+    storage: Arc<RedbStorage>,
+    dir: PathBuf,
+}
+
+impl SnapshotHook {
+    pub fn new(storage: Arc<RedbStorage>, dir: PathBuf) -> Self {
+        Self { storage, dir }
+    }
+}
+
+impl MitigationHook for SnapshotHook {
+    fn name(&self) -> &str {
+        "snapshot"
+    }
+
+    fn trigger(&self, _predictions: &[Prediction]) -> Result<()> {
+        let storage = self.storage.clone();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = self.dir.join(format!("predicted-failure-{}.redb", timestamp));
+        tokio::spawn(async move {
+            if let Err(e) = storage.snapshot(&path).await {
+                warn!(target: "audit", "Failed to write snapshot {}: {}", path.display(), e);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predictor() -> FailurePredictor {
+        FailurePredictor::new(PredictorConfig {
+            horizon: 3600,
+            smart_devices: Vec::new(),
+            max_peer_churn: 0.5,
+        })
+    }
+
+    #[test]
+    fn test_memory_growth_slope() {
+        let mut predictor = predictor();
+        for i in 0..10u64 {
+            predictor.observe(&SystemMetrics {
+                timestamp: i * 60,
+                process_memory: 1_000_000 + i * 60_000,
+                ..Default::default()
+            });
+        }
+
+        // 1000 バイト/秒で増加し、残り 1.8MB なら 1800 秒で枯渇
+        let prediction = predictor
+            .predict_growth(FailureKind::MemoryExhaustion, |s| s.1, 1_800_000)
+            .unwrap();
+        assert_eq!(prediction.eta, Some(1800));
+        assert!((prediction.probability - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_peer_churn() {
+        let mut predictor = predictor();
+        let peers = |ids: &[&str]| ids.iter().map(|s| s.to_string()).collect::<HashSet<_>>();
+        predictor.observe_peers(peers(&["a", "b", "c", "d"]));
+        predictor.observe_peers(peers(&["a", "b", "c", "e"]));
+        // 4 ピア中 2 つの差分で入れ替わり率 0.5
+        assert!((predictor.predict_peer_churn().unwrap().probability - 1.0).abs() < 1e-9);
+
+        predictor.observe_peers(HashSet::new());
+        let prediction = predictor.predict_peer_churn().unwrap();
+        assert_eq!(prediction.detail, "all peers disconnected");
+    }
+}
@@ -0,0 +1,466 @@
+//! ブロブ（大きなデータのサイドカー）
+//!
+//! ロールアップのデータ可用性のため、大きなデータをトランザクションの本体ではなくサイドカーとして運びます。
+//! トランザクションはデータのコミットメント（SHA-256）とバイト数（`BlobRef`）だけを持ち、
+//! データはブロックに含めず、確定後 `blobs.retention_blocks` ブロックの間だけノードが保持します。
+//! 主な機能：
+//! - コミットメントの計算とサイドカーの検証
+//! - ガスとは別のバイト単価（ブロブ手数料）。親ブロックのブロブのバイト数が目標（上限の半分）を
+//!   超えれば上げ、下回れば下げます（基本手数料と同じく1ブロックで最大 `1/8`）
+//! - 受け付けたサイドカーの保持、確定時の保存と保持期間を過ぎたものの削除
+//!
+//! ブロブ手数料は `consensus.max_blob_bytes` が0の場合は無効（0）で、ブロブを持つトランザクションは
+//! ブロックに含められません。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use crate::config::BlobSettings;
+use crate::core::block::{Block, Chain, limits::ConsensusParams};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+
+/// 1ブロックで変わるブロブ手数料の割合の上限（`1/BLOB_FEE_CHANGE_DENOMINATOR`）
+pub const BLOB_FEE_CHANGE_DENOMINATOR: u64 = 8;
+/// 1ブロックの確定で削除する期限切れのサイドカーの最大数
+const PRUNE_BATCH: usize = 1024;
+
+/// トランザクションが参照するブロブ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BlobRef {
+    /// データの SHA-256（hex）
+    pub commitment: String,
+    /// データのバイト数
+    pub size: u64,
+    /// 支払うブロブ手数料の上限（1バイトあたり）
+    pub max_fee_per_byte: u64,
+}
+
+impl BlobRef {
+    pub fn new(data: &[u8], max_fee_per_byte: u64) -> Self {
+        Self { commitment: commitment(data), size: data.len() as u64, max_fee_per_byte }
+    }
+
+    /// データがこの参照と一致するか
+    pub fn verify(&self, data: &[u8]) -> Result<(), BlobError> {
+        if self.size != data.len() as u64 {
+            return Err(BlobError::SizeMismatch { declared: self.size, actual: data.len() as u64 });
+        }
+        let actual = commitment(data);
+        if self.commitment != actual {
+            return Err(BlobError::CommitmentMismatch { declared: self.commitment.clone(), actual });
+        }
+        Ok(())
+    }
+}
+
+/// ノードが保持しているサイドカー
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "blob/data", version = 1)]
+pub struct BlobSidecar {
+    #[state(key)]
+    pub commitment: String,
+    pub size: u64,
+    /// 取り込まれたブロックの高さ（未確定の場合は `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// 参照したトランザクション（未確定の場合は `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    /// データ（hex）
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub data: Vec<u8>,
+}
+
+/// 保持期限の索引（高さの順に削除する）
+#[derive(Debug, Clone, Serialize, Deserialize, StateObject)]
+#[state(cf = "blob/expiry", version = 1)]
+pub struct BlobExpiry {
+    #[state(key)]
+    pub height: u64,
+    #[state(key)]
+    pub commitment: String,
+}
+
+/// ブロブの検証エラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BlobError {
+    #[error("blob transactions are disabled on this network")]
+    Disabled,
+
+    #[error("blob is empty")]
+    Empty,
+
+    #[error("blob is {size} bytes, exceeding the limit of {limit} bytes")]
+    TooLarge { size: u64, limit: u64 },
+
+    #[error("blob is {actual} bytes but the transaction declares {declared}")]
+    SizeMismatch { declared: u64, actual: u64 },
+
+    #[error("blob commitment {declared} does not match the data ({actual})")]
+    CommitmentMismatch { declared: String, actual: String },
+
+    #[error("blob fee {declared} does not match the expected blob fee {expected}")]
+    FeeMismatch { declared: u64, expected: u64 },
+
+    #[error("transaction {tx_hash} pays at most {max_fee_per_byte} per blob byte, below the blob fee {blob_fee}")]
+    Underpriced { tx_hash: String, max_fee_per_byte: u64, blob_fee: u64 },
+
+    #[error("declared blob bytes {declared} do not match the transactions ({actual})")]
+    BytesMismatch { declared: u64, actual: u64 },
+
+    #[error("block carries {bytes} blob bytes, exceeding the limit of {limit}")]
+    BlockTooLarge { bytes: u64, limit: u64 },
+
+    #[error("blob of {size} bytes does not fit in the pending sidecar pool ({limit} bytes)")]
+    PendingFull { size: u64, limit: u64 },
+}
+
+/// データのコミットメント（SHA-256 の hex）
+pub fn commitment(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// 親ブロックから次のブロックのブロブ手数料を計算
+///
+/// 目標は `max_bytes` の半分です。`max_bytes` が0の場合はブロブを使わず0を返します。
+/// 親がない、またはブロブの導入前のブロック（`parent_fee` が0）の場合は `min_fee` から始め、
+/// `min_fee` を下回ることはありません。
+pub fn next_blob_fee(min_fee: u64, max_bytes: u64, parent_fee: u64, parent_bytes: u64) -> u64 {
+    if max_bytes == 0 {
+        return 0;
+    }
+    let min_fee = min_fee.max(1);
+    if parent_fee == 0 {
+        return min_fee;
+    }
+    let target = (max_bytes / 2).max(1) as u128;
+    let used = parent_bytes as u128;
+    let fee = parent_fee as u128;
+    let denominator = BLOB_FEE_CHANGE_DENOMINATOR as u128;
+    let next = if used > target {
+        fee + (fee * (used - target) / target / denominator).max(1)
+    } else {
+        fee - fee * (target - used) / target / denominator
+    };
+    next.clamp(min_fee as u128, u64::MAX as u128) as u64
+}
+
+/// ブロブ手数料を払えない、またはブロックのブロブの上限を超えるトランザクションを除く
+///
+/// `retain_payable` と同じく、ノンスの順序を保つため同じ送信者の後続のトランザクションも除きます。
+pub fn retain_blobs(txs: &mut Vec<PendingTransaction>, blob_fee: u64, max_blob_bytes: u64) {
+    let mut remaining = max_blob_bytes;
+    retain_with_senders(txs, |tx| match &tx.blob {
+        None => true,
+        Some(blob) if blob_fee > 0 && blob.max_fee_per_byte >= blob_fee && blob.size <= remaining => {
+            remaining -= blob.size;
+            true
+        }
+        Some(_) => false,
+    });
+}
+
+/// `keep` が偽のトランザクションと、同じ送信者の後続のトランザクションを除く
+fn retain_with_senders(txs: &mut Vec<PendingTransaction>, mut keep: impl FnMut(&PendingTransaction) -> bool) {
+    let mut dropped = HashSet::new();
+    txs.retain(|tx| {
+        if dropped.contains(&tx.from) || !keep(tx) {
+            dropped.insert(tx.from.clone());
+            return false;
+        }
+        true
+    });
+}
+
+impl ConsensusParams {
+    /// 親ブロックの（ブロブ手数料, ブロブのバイト数）から次のブロックのブロブ手数料
+    pub fn next_blob_fee(&self, parent: Option<(u64, u64)>) -> u64 {
+        let (fee, bytes) = parent.unwrap_or_default();
+        next_blob_fee(self.min_blob_fee, self.max_blob_bytes, fee, bytes)
+    }
+
+    /// トランザクションのブロブが上限を守っているか
+    pub fn check_blob(&self, blob: &BlobRef) -> Result<(), BlobError> {
+        if self.max_blob_bytes == 0 {
+            return Err(BlobError::Disabled);
+        }
+        if blob.size == 0 {
+            return Err(BlobError::Empty);
+        }
+        let limit = self.max_blob_size.min(self.max_blob_bytes);
+        if blob.size > limit {
+            return Err(BlobError::TooLarge { size: blob.size, limit });
+        }
+        Ok(())
+    }
+}
+
+impl Block {
+    /// トランザクションのブロブのバイト数の合計
+    pub fn total_blob_bytes(transactions: &[PendingTransaction]) -> u64 {
+        transactions.iter()
+            .filter_map(|tx| tx.blob.as_ref())
+            .fold(0u64, |sum, blob| sum.saturating_add(blob.size))
+    }
+
+    /// ブロブ手数料を設定してハッシュを計算し直す
+    pub fn with_blob_fee(mut self, blob_fee: u64) -> Self {
+        self.blob_fee = blob_fee;
+        self.hash = self.compute_hash();
+        self.signature = None;
+        self
+    }
+
+    /// ブロブ手数料とブロブのバイト数が上限と本体に一致するか検証
+    pub fn verify_blobs(&self, params: &ConsensusParams, expected_fee: u64) -> Result<(), BlobError> {
+        if self.blob_fee != expected_fee {
+            return Err(BlobError::FeeMismatch { declared: self.blob_fee, expected: expected_fee });
+        }
+        let actual = Self::total_blob_bytes(&self.transactions);
+        if self.blob_bytes != actual {
+            return Err(BlobError::BytesMismatch { declared: self.blob_bytes, actual });
+        }
+        if actual > params.max_blob_bytes {
+            return Err(BlobError::BlockTooLarge { bytes: actual, limit: params.max_blob_bytes });
+        }
+        for tx in &self.transactions {
+            let Some(blob) = &tx.blob else {
+                continue;
+            };
+            params.check_blob(blob)?;
+            if blob.max_fee_per_byte < self.blob_fee {
+                return Err(BlobError::Underpriced {
+                    tx_hash: tx.hash.clone(),
+                    max_fee_per_byte: blob.max_fee_per_byte,
+                    blob_fee: self.blob_fee,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 確定前のサイドカー（受け付けた順）
+#[derive(Default)]
+struct Pending {
+    blobs: HashMap<String, Vec<u8>>,
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl Pending {
+    fn take(&mut self, commitment: &str) -> Option<Vec<u8>> {
+        let data = self.blobs.remove(commitment)?;
+        self.order.retain(|c| c != commitment);
+        self.bytes -= data.len();
+        Some(data)
+    }
+}
+
+/// サイドカーの保持
+pub struct BlobStore {
+    storage: Arc<dyn StorageEngine>,
+    settings: BlobSettings,
+    pending: Mutex<Pending>,
+}
+
+impl BlobStore {
+    pub fn new(storage: Arc<dyn StorageEngine>, settings: BlobSettings) -> Self {
+        Self { storage, settings, pending: Mutex::new(Pending::default()) }
+    }
+
+    /// 受け付けたトランザクションのサイドカーを確定まで保持する
+    ///
+    /// 上限を超える場合は古いものから破棄します（破棄されたサイドカーのトランザクションはブロックに含めない）。
+    pub async fn add_pending(&self, data: Vec<u8>) -> Result<String, BlobError> {
+        let limit = self.settings.max_pending_bytes;
+        if data.len() > limit {
+            return Err(BlobError::PendingFull { size: data.len() as u64, limit: limit as u64 });
+        }
+        let commitment = commitment(&data);
+        let mut pending = self.pending.lock().await;
+        if pending.blobs.contains_key(&commitment) {
+            return Ok(commitment);
+        }
+        while pending.bytes + data.len() > limit {
+            let Some(oldest) = pending.order.front().cloned() else {
+                break;
+            };
+            pending.take(&oldest);
+            debug!("Dropped pending blob sidecar {} to make room", oldest);
+        }
+        pending.bytes += data.len();
+        pending.order.push_back(commitment.clone());
+        pending.blobs.insert(commitment.clone(), data);
+        Ok(commitment)
+    }
+
+    /// サイドカーを取得（確定前のものを含む）
+    pub async fn get(&self, commitment: &str) -> Result<Option<BlobSidecar>> {
+        let commitment = commitment.trim_start_matches("0x").to_lowercase();
+        if let Some(data) = self.pending.lock().await.blobs.get(&commitment) {
+            return Ok(Some(BlobSidecar {
+                commitment: commitment.clone(),
+                size: data.len() as u64,
+                height: None,
+                tx_hash: None,
+                data: data.clone(),
+            }));
+        }
+        BlobSidecar::load(self.storage.as_ref(), &commitment).await
+    }
+
+    /// サイドカーを保持していないブロブのトランザクション（と同じ送信者の後続のもの）を除く
+    pub async fn retain_available(&self, txs: &mut Vec<PendingTransaction>) {
+        let pending = self.pending.lock().await;
+        retain_with_senders(txs, |tx| tx.blob.as_ref().is_none_or(|blob| pending.blobs.contains_key(&blob.commitment)));
+    }
+
+    /// 確定したブロックのサイドカーを保存し、保持期間を過ぎたものを削除する
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let mut batch = Vec::new();
+        let mut pending = self.pending.lock().await;
+        for tx in &block.transactions {
+            let Some(blob) = &tx.blob else {
+                continue;
+            };
+            match pending.take(&blob.commitment) {
+                Some(data) => {
+                    let sidecar = BlobSidecar {
+                        commitment: blob.commitment.clone(),
+                        size: blob.size,
+                        height: Some(block.height),
+                        tx_hash: Some(tx.hash.clone()),
+                        data,
+                    };
+                    batch.push(sidecar.put_change()?);
+                    batch.push(BlobExpiry { height: block.height, commitment: blob.commitment.clone() }.put_change()?);
+                }
+                // 他のノードが受け付けたトランザクションのサイドカーはこのノードにない
+                None => debug!("Blob sidecar {} of transaction {} is not held by this node", blob.commitment, tx.hash),
+            }
+        }
+        drop(pending);
+
+        if let Some(cutoff) = block.height.checked_sub(self.settings.retention_blocks.max(1)) {
+            let expired = BlobExpiry::list(self.storage.as_ref(), None, PRUNE_BATCH).await?;
+            for expiry in expired.into_iter().take_while(|expiry| expiry.height <= cutoff) {
+                batch.push(BlobSidecar::delete_change(&expiry.commitment));
+                batch.push(BlobExpiry::delete_change(&expiry.key()));
+            }
+        }
+        if !batch.is_empty() {
+            self.storage.batch_write(batch).await?;
+        }
+        Ok(())
+    }
+
+    /// 以降の確定でサイドカーを保存・削除する
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match commits.recv().await {
+                    Ok(block) => {
+                        if let Err(e) = self.apply_block(&block).await {
+                            warn!("Failed to store blob sidecars of block {}: {}", block.height, e);
+                        }
+                    }
+                    // 取りこぼしたブロックのサイドカーは保持期限まで確定前のまま残る
+                    Err(broadcast::error::RecvError::Lagged(n)) => warn!("Blob store skipped {} blocks", n),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn params() -> ConsensusParams {
+        ConsensusParams {
+            max_blob_size: 1024,
+            max_blob_bytes: 2048,
+            min_blob_fee: 10,
+            ..ConsensusParams::default()
+        }
+    }
+
+    fn blob_tx(from: &str, nonce: u64, data: &[u8], max_fee_per_byte: u64) -> PendingTransaction {
+        PendingTransaction { blob: Some(BlobRef::new(data, max_fee_per_byte)), ..PendingTransaction::test_transfer(from, "rollup", 0, nonce) }.rehashed()
+    }
+
+    #[tokio::test]
+    async fn test_blob_fees_limits_and_retention() {
+        // 目標（上限の半分）を超えれば上がり、下回れば最低値まで下がる
+        assert_eq!(next_blob_fee(10, 0, 50, 2048), 0);
+        assert_eq!(next_blob_fee(10, 2048, 0, 0), 10);
+        assert_eq!(next_blob_fee(10, 2048, 80, 2048), 90);
+        assert_eq!(next_blob_fee(10, 2048, 80, 1024), 80);
+        assert_eq!(next_blob_fee(10, 2048, 10, 0), 10);
+
+        let params = params();
+        let fee = params.next_blob_fee(None);
+        let mut txs = vec![
+            blob_tx("alice", 0, &[1; 1000], 10),
+            blob_tx("bob", 0, &[2; 1000], 9),
+            blob_tx("bob", 1, &[3; 10], 20),
+            blob_tx("carol", 0, &[4; 1000], 10),
+            blob_tx("dave", 0, &[5; 100], 10),
+        ];
+        retain_blobs(&mut txs, fee, params.max_blob_bytes);
+        // bob は手数料の上限が足りず後続も除かれ、dave は残りのバイト数に収まらない
+        assert_eq!(txs.iter().map(|tx| tx.from.as_str()).collect::<Vec<_>>(), ["alice", "carol"]);
+
+        let block = Block::new(5, "p".to_string(), "v".to_string(), txs.clone()).with_blob_fee(fee);
+        assert_eq!(block.blob_bytes, 2000);
+        assert_eq!(block.verify_blobs(&params, fee), Ok(()));
+        assert_eq!(block.verify_blobs(&params, 11), Err(BlobError::FeeMismatch { declared: 10, expected: 11 }));
+        let mut forged = block.clone();
+        forged.blob_bytes = 1;
+        assert!(matches!(forged.verify_blobs(&params, fee), Err(BlobError::BytesMismatch { .. })));
+        assert_eq!(
+            block.verify_blobs(&ConsensusParams::default(), 0),
+            Err(BlobError::FeeMismatch { declared: 10, expected: 0 })
+        );
+        assert_eq!(
+            BlobRef { size: 1000, ..BlobRef::new(&[1; 999], 1) }.verify(&[1; 999]),
+            Err(BlobError::SizeMismatch { declared: 1000, actual: 999 })
+        );
+
+        let storage = RedbStorage::memory();
+        let store = BlobStore::new(storage, BlobSettings { retention_blocks: 10, max_pending_bytes: 2500 });
+        store.add_pending(vec![1; 1000]).await.unwrap();
+        store.add_pending(vec![4; 1000]).await.unwrap();
+        // 上限を超えると古いものから破棄される
+        let extra = store.add_pending(vec![9; 1000]).await.unwrap();
+        let mut selected = txs.clone();
+        store.retain_available(&mut selected).await;
+        assert_eq!(selected.len(), 1);
+        assert_eq!(store.get(&extra).await.unwrap().unwrap().height, None);
+
+        store.apply_block(&block).await.unwrap();
+        let carol = &txs[1].blob.as_ref().unwrap().commitment;
+        let stored = store.get(carol).await.unwrap().unwrap();
+        assert_eq!((stored.height, stored.data.len()), (Some(5), 1000));
+        assert_eq!(store.get(&txs[0].blob.as_ref().unwrap().commitment).await.unwrap(), None);
+
+        // 保持期間を過ぎたブロックのサイドカーは削除される
+        store.apply_block(&Block::new(14, "p".to_string(), "v".to_string(), vec![])).await.unwrap();
+        assert!(store.get(carol).await.unwrap().is_some());
+        store.apply_block(&Block::new(15, "p".to_string(), "v".to_string(), vec![])).await.unwrap();
+        assert_eq!(store.get(carol).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,413 @@
+//! ブロックごとの乱数ビーコン
+//!
+//! 各ブロックの乱数は、親ブロックの乱数と生成者の VRF 出力を混ぜたものです。
+//! VRF の入力はチェーンID・高さ・親の乱数だけなので、生成者がトランザクションの選択や
+//! 時刻を変えても出力は変わらず（選べるのはブロックを出さないことだけ）、
+//! 生成者の公開鍵があれば誰でも検証できます。ブロックハッシュを乱数に使う場合と違い、
+//! 生成者が都合のよい値になるまでブロックを作り直すことはできません。
+//!
+//! VRF は ECVRF-EDWARDS25519-SHA512-TAI（RFC 9381）で、ブロックの署名と同じ ed25519 鍵を使います。
+//! 証明はブロックハッシュの対象に含まれるため、署名済みのブロックから取り除けません。
+//! `header_activation_height` 以降のブロックは生成者の VRF 証明が必須で、確定時に検証します。
+//! 証明のない導入前のブロックはブロックハッシュを混ぜ、`source` で区別します。
+//! 導入後の高さについてブロックハッシュによる値を返すことはありません。
+//!
+//! 乱数はブロックの確定と同じバッチで高さごとに保存し、導入前のブロックの分は
+//! チェーンを開くときに追加します。生成者は自分のブロックの乱数を先に知っているため、
+//! アプリケーションは申し込みを受け付けた後のブロックの乱数を使ってください。
+
+use anyhow::{Result, anyhow};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256, Sha512};
+use thiserror::Error;
+use tracing::info;
+use utoipa::ToSchema;
+use super::{Block, Chain};
+use super::limits::ConsensusParams;
+
+/// 高さごとの乱数のキープレフィックス
+const BEACON_PREFIX: &str = "block/beacon/";
+/// 乱数を保存済みの高さのキー
+const BEACON_HEIGHT_KEY: &[u8] = b"block/beacon_height";
+/// VRF の入力と乱数の混合のドメイン
+const DOMAIN: &[u8] = b"rustorium-beacon";
+/// ECVRF-EDWARDS25519-SHA512-TAI のスイート
+const SUITE: u8 = 0x03;
+/// 証明のチャレンジのバイト数
+const CHALLENGE_BYTES: usize = 16;
+/// VRF 証明のバイト数（Gamma、チャレンジ、s）
+pub const PROOF_BYTES: usize = 32 + CHALLENGE_BYTES + 32;
+
+/// 乱数の元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RandomnessSource {
+    /// 生成者の VRF 出力
+    Vrf,
+    /// VRF 証明のないブロックのハッシュ（生成者が操作できる）
+    BlockHash,
+}
+
+/// ブロックの乱数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Randomness {
+    pub height: u64,
+    pub block_hash: String,
+    /// 乱数（hex、32バイト）
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub value: [u8; 32],
+    pub source: RandomnessSource,
+    /// VRF の公開鍵（生成者の署名の鍵、hex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// VRF 証明（hex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<String>,
+    /// VRF の出力（hex、64バイト）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vrf_output: Option<String>,
+}
+
+/// 乱数の検証エラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BeaconError {
+    #[error("randomness proof requires a proposer signature")]
+    Unsigned,
+
+    #[error("block {height} has no randomness proof (required from height {activation})")]
+    MissingProof { height: u64, activation: u64 },
+
+    #[error("malformed randomness proof: {0}")]
+    Malformed(String),
+
+    #[error("randomness proof does not match the proposer's key and the parent randomness")]
+    InvalidProof,
+}
+
+/// ブロックの VRF の入力
+pub fn vrf_input(chain_id: u64, height: u64, parent: &[u8; 32]) -> Vec<u8> {
+    let mut input = DOMAIN.to_vec();
+    input.extend_from_slice(&chain_id.to_be_bytes());
+    input.extend_from_slice(&height.to_be_bytes());
+    input.extend_from_slice(parent);
+    input
+}
+
+/// 親の乱数とブロックの寄与（VRF 出力またはブロックハッシュ）を混ぜる
+pub fn mix(parent: &[u8; 32], contribution: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update(parent);
+    hasher.update(contribution);
+    hasher.finalize().into()
+}
+
+/// VRF 証明を作成
+pub fn prove(key: &SigningKey, alpha: &[u8]) -> [u8; PROOF_BYTES] {
+    let expanded = Sha512::digest(key.to_bytes());
+    let mut secret = [0u8; 32];
+    secret.copy_from_slice(&expanded[..32]);
+    secret[0] &= 248;
+    secret[31] &= 127;
+    secret[31] |= 64;
+    let x = Scalar::from_bytes_mod_order(secret);
+    let public_key = key.verifying_key().to_bytes();
+
+    let h = encode_to_curve(&public_key, alpha);
+    let gamma = h * x;
+    // RFC 8032 と同じく、鍵の後半とハッシュから決定的にノンスを作る
+    let nonce = Sha512::new()
+        .chain_update(&expanded[32..])
+        .chain_update(h.compress().as_bytes())
+        .finalize();
+    let k = Scalar::from_bytes_mod_order_wide(&nonce.as_slice().try_into().expect("64 bytes"));
+    let c = challenge(&public_key, &h, &gamma, &EdwardsPoint::mul_base(&k), &(h * k));
+    let s = k + challenge_scalar(&c) * x;
+
+    let mut proof = [0u8; PROOF_BYTES];
+    proof[..32].copy_from_slice(gamma.compress().as_bytes());
+    proof[32..32 + CHALLENGE_BYTES].copy_from_slice(&c);
+    proof[32 + CHALLENGE_BYTES..].copy_from_slice(s.as_bytes());
+    proof
+}
+
+/// VRF 証明を検証して出力を返す
+pub fn verify(public_key: &VerifyingKey, alpha: &[u8], proof: &[u8]) -> Result<[u8; 64], BeaconError> {
+    let malformed = |reason: &str| BeaconError::Malformed(reason.to_string());
+    if proof.len() != PROOF_BYTES {
+        return Err(BeaconError::Malformed(format!("proof must be {} bytes", PROOF_BYTES)));
+    }
+    let public_bytes = public_key.to_bytes();
+    let y = CompressedEdwardsY(public_bytes).decompress()
+        .filter(|y| !y.is_small_order())
+        .ok_or_else(|| malformed("public key is not a valid VRF key"))?;
+    let gamma = CompressedEdwardsY(proof[..32].try_into().expect("32 bytes")).decompress()
+        .ok_or_else(|| malformed("gamma is not a curve point"))?;
+    let c: [u8; CHALLENGE_BYTES] = proof[32..32 + CHALLENGE_BYTES].try_into().expect("16 bytes");
+    let s: Option<Scalar> = Scalar::from_canonical_bytes(proof[32 + CHALLENGE_BYTES..].try_into().expect("32 bytes")).into();
+    let s = s.ok_or_else(|| malformed("s is not a canonical scalar"))?;
+
+    let h = encode_to_curve(&public_bytes, alpha);
+    let c_scalar = challenge_scalar(&c);
+    let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c_scalar, &y, &s);
+    let v = h * s - gamma * c_scalar;
+    if challenge(&public_bytes, &h, &gamma, &u, &v) != c {
+        return Err(BeaconError::InvalidProof);
+    }
+    Ok(proof_to_hash(&gamma))
+}
+
+/// 試行と増分による曲線上の点へのハッシュ
+fn encode_to_curve(public_key: &[u8; 32], alpha: &[u8]) -> EdwardsPoint {
+    (0..=u8::MAX)
+        .find_map(|counter| {
+            let hash = Sha512::new()
+                .chain_update([SUITE, 0x01])
+                .chain_update(public_key)
+                .chain_update(alpha)
+                .chain_update([counter, 0x00])
+                .finalize();
+            CompressedEdwardsY(hash[..32].try_into().expect("32 bytes")).decompress()
+        })
+        .expect("a curve point is found within 256 attempts")
+        .mul_by_cofactor()
+}
+
+fn challenge(
+    public_key: &[u8; 32],
+    h: &EdwardsPoint,
+    gamma: &EdwardsPoint,
+    u: &EdwardsPoint,
+    v: &EdwardsPoint,
+) -> [u8; CHALLENGE_BYTES] {
+    let mut hasher = Sha512::new();
+    hasher.update([SUITE, 0x02]);
+    hasher.update(public_key);
+    for point in [h, gamma, u, v] {
+        hasher.update(point.compress().as_bytes());
+    }
+    hasher.update([0x00]);
+    let hash = hasher.finalize();
+    hash[..CHALLENGE_BYTES].try_into().expect("16 bytes")
+}
+
+fn challenge_scalar(c: &[u8; CHALLENGE_BYTES]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[..CHALLENGE_BYTES].copy_from_slice(c);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+fn proof_to_hash(gamma: &EdwardsPoint) -> [u8; 64] {
+    let hash = Sha512::new()
+        .chain_update([SUITE, 0x03])
+        .chain_update(gamma.mul_by_cofactor().compress().as_bytes())
+        .chain_update([0x00])
+        .finalize();
+    hash.as_slice().try_into().expect("64 bytes")
+}
+
+fn beacon_key(height: u64) -> Vec<u8> {
+    format!("{}{:020}", BEACON_PREFIX, height).into_bytes()
+}
+
+/// ブロックの乱数の書き込み
+pub(super) fn randomness_writes(randomness: &Randomness) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+    Ok(vec![
+        (beacon_key(randomness.height), Some(serde_json::to_vec(randomness)?)),
+        (BEACON_HEIGHT_KEY.to_vec(), Some(randomness.height.to_be_bytes().to_vec())),
+    ])
+}
+
+impl Block {
+    /// 乱数の VRF 証明を設定してハッシュを計算し直す（署名はこの後に行う）
+    pub fn with_randomness_proof(mut self, key: &SigningKey, alpha: &[u8]) -> Self {
+        self.randomness_proof = Some(hex::encode(prove(key, alpha)));
+        self.hash = self.compute_hash();
+        self.signature = None;
+        self
+    }
+
+    /// 確定するブロックの乱数（`header_activation_height` 以降は VRF 証明のないブロックを拒否する）
+    pub fn verified_randomness(&self, params: &ConsensusParams, parent: &[u8; 32]) -> Result<Randomness, BeaconError> {
+        let activation = params.header_activation_height;
+        if self.randomness_proof.is_none() && self.height >= activation {
+            return Err(BeaconError::MissingProof { height: self.height, activation });
+        }
+        self.randomness(params.chain_id, parent)
+    }
+
+    /// 親の乱数からこのブロックの乱数を導出（VRF 証明は署名の鍵で検証する）
+    ///
+    /// 証明のないブロックはブロックハッシュを混ぜます。確定時の検証には `verified_randomness` を使います。
+    pub fn randomness(&self, chain_id: u64, parent: &[u8; 32]) -> Result<Randomness, BeaconError> {
+        let Some(proof) = &self.randomness_proof else {
+            return Ok(Randomness {
+                height: self.height,
+                block_hash: self.hash.clone(),
+                value: mix(parent, self.hash.as_bytes()),
+                source: RandomnessSource::BlockHash,
+                public_key: None,
+                proof: None,
+                vrf_output: None,
+            });
+        };
+        let signature = self.signature.as_ref().ok_or(BeaconError::Unsigned)?;
+        let public_key: [u8; 32] = hex::decode(&signature.public_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| BeaconError::Malformed("proposer public key must be 32 bytes".to_string()))?;
+        let public_key = VerifyingKey::from_bytes(&public_key).map_err(|e| BeaconError::Malformed(e.to_string()))?;
+        let proof_bytes = hex::decode(proof).map_err(|e| BeaconError::Malformed(e.to_string()))?;
+        let output = verify(&public_key, &vrf_input(chain_id, self.height, parent), &proof_bytes)?;
+        Ok(Randomness {
+            height: self.height,
+            block_hash: self.hash.clone(),
+            value: mix(parent, &output),
+            source: RandomnessSource::Vrf,
+            public_key: Some(signature.public_key.clone()),
+            proof: Some(proof.clone()),
+            vrf_output: Some(hex::encode(output)),
+        })
+    }
+}
+
+impl Chain {
+    /// 先頭に続くブロックに乱数の VRF 証明を付けて署名
+    pub async fn sign_block(&self, block: Block, key: &SigningKey) -> Block {
+        self.prove_randomness(block, key).await.sign(key)
+    }
+
+    /// 先頭に続くブロックに乱数の VRF 証明を付ける（署名の前にハッシュを確定する）
+    pub async fn prove_randomness(&self, block: Block, key: &SigningKey) -> Block {
+        let parent = self.head.read().await.as_ref().map_or([0; 32], |h| h.randomness);
+        let alpha = vrf_input(self.params.chain_id, block.height, &parent);
+        block.with_randomness_proof(key, &alpha)
+    }
+
+    /// 高さを指定してブロックの乱数を取得
+    ///
+    /// `header_activation_height` 以降の高さのブロックハッシュによる値（導入前の形式で保存されたもの）はエラーにします。
+    pub async fn randomness(&self, height: u64) -> Result<Option<Randomness>> {
+        let randomness = self.load_randomness(height).await?;
+        if let Some(randomness) = &randomness {
+            if randomness.source == RandomnessSource::BlockHash && height >= self.params.header_activation_height {
+                return Err(anyhow!(
+                    "Block {} has no verifiable randomness (VRF proofs are required from height {})",
+                    height, self.params.header_activation_height
+                ));
+            }
+        }
+        Ok(randomness)
+    }
+
+    async fn load_randomness(&self, height: u64) -> Result<Option<Randomness>> {
+        Ok(match self.storage.get(&beacon_key(height)).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        })
+    }
+
+    /// 乱数の導入前に確定したブロックの乱数を導出し、先頭の乱数を読み込む
+    pub(super) async fn backfill_beacon(&self) -> Result<()> {
+        let Some((head, _)) = self.head().await else {
+            return Ok(());
+        };
+        let start = match self.storage.get(BEACON_HEIGHT_KEY).await? {
+            Some(bytes) => u64::from_be_bytes(bytes.try_into().map_err(|_| anyhow!("Corrupted beacon height"))?) + 1,
+            None => 0,
+        };
+        let mut parent = match start.checked_sub(1) {
+            Some(height) => self.load_randomness(height).await?
+                .ok_or_else(|| anyhow!("Randomness of block {} is missing", height))?
+                .value,
+            None => [0; 32],
+        };
+        if start <= head {
+            info!("Deriving randomness of blocks {} to {}", start, head);
+        }
+        for height in start..=head {
+            let block = self.get_block(height).await?
+                .ok_or_else(|| anyhow!("Block {} is missing", height))?;
+            let randomness = block.randomness(self.params.chain_id, &parent)
+                .map_err(|e| anyhow!("Block {} has invalid randomness: {}", block.hash, e))?;
+            parent = randomness.value;
+            self.storage.batch_write(randomness_writes(&randomness)?).await?;
+        }
+        if let Some(head) = self.head.write().await.as_mut() {
+            head.randomness = parent;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::core::storage::{StorageEngine, redb_storage::RedbStorage};
+
+    #[test]
+    fn test_vrf_proof_is_unique_and_verifiable() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let alpha = vrf_input(1, 5, &[9; 32]);
+        let proof = prove(&key, &alpha);
+        assert_eq!(proof, prove(&key, &alpha));
+        let output = verify(&key.verifying_key(), &alpha, &proof).unwrap();
+        assert_eq!(output, proof_to_hash(&CompressedEdwardsY(proof[..32].try_into().unwrap()).decompress().unwrap()));
+
+        // 入力・鍵・証明のどれが違っても検証できない
+        assert_eq!(verify(&key.verifying_key(), &vrf_input(1, 6, &[9; 32]), &proof), Err(BeaconError::InvalidProof));
+        let other = SigningKey::from_bytes(&[8; 32]);
+        assert_eq!(verify(&other.verifying_key(), &alpha, &proof), Err(BeaconError::InvalidProof));
+        let mut tampered = proof;
+        tampered[40] ^= 1;
+        assert_eq!(verify(&key.verifying_key(), &alpha, &tampered), Err(BeaconError::InvalidProof));
+        assert!(matches!(verify(&key.verifying_key(), &alpha, &proof[..79]), Err(BeaconError::Malformed(_))));
+        assert_ne!(verify(&other.verifying_key(), &alpha, &prove(&other, &alpha)).unwrap(), output);
+    }
+
+    #[tokio::test]
+    async fn test_chain_derives_randomness() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let validator = crate::core::wallet::address_of(&key.verifying_key());
+        // 高さ0は署名の導入前のブロック
+        let params = ConsensusParams { header_activation_height: 1, ..ConsensusParams::default() };
+        let chain = Chain::open(storage.clone()).await.unwrap().with_params(params.clone());
+
+        chain.commit(chain.next_block("v".to_string(), vec![]).await).await.unwrap();
+        let block = chain.sign_block(chain.next_block(validator.clone(), vec![]).await, &key).await;
+        assert!(block.randomness_proof.is_some());
+        // 証明を取り除くとハッシュが変わる
+        let mut stripped = block.clone();
+        stripped.randomness_proof = None;
+        assert!(chain.commit(stripped).await.is_err());
+        // 導入後の高さでは、署名だけで VRF 証明のないブロックを確定できない
+        let unproven = chain.next_block(validator.clone(), vec![]).await.sign(&key);
+        assert_eq!(
+            unproven.verified_randomness(&params, &[0; 32]),
+            Err(BeaconError::MissingProof { height: 1, activation: 1 }),
+        );
+        assert!(chain.commit(unproven).await.is_err());
+        chain.commit(block).await.unwrap();
+
+        let first = chain.randomness(0).await.unwrap().unwrap();
+        let second = chain.randomness(1).await.unwrap().unwrap();
+        assert_eq!(first.source, RandomnessSource::BlockHash);
+        assert_eq!(second.source, RandomnessSource::Vrf);
+        assert_eq!(second.public_key.as_deref(), Some(validator.as_str()));
+        let output = hex::decode(second.vrf_output.as_deref().unwrap()).unwrap();
+        assert_eq!(second.value, mix(&first.value, &output));
+
+        // 開き直しても先頭の乱数から続けられる
+        drop(chain);
+        let chain = Chain::open(storage).await.unwrap().with_params(params);
+        let block = chain.sign_block(chain.next_block(validator, vec![]).await, &key).await;
+        chain.commit(block).await.unwrap();
+        assert_eq!(chain.randomness(2).await.unwrap().unwrap().source, RandomnessSource::Vrf);
+    }
+}
@@ -0,0 +1,169 @@
+//! 信頼するチェックポイントからの起動
+//!
+//! 空のチェーンを `<高さ>:<ハッシュ>` のブロックから始めます（`--trust-checkpoint`）。チェックポイントより前の
+//! ブロックは検証せず、チェックポイントのブロックとその乱数を上流から取得して先頭にします。
+//! 以降のブロックは通常どおり `Chain::commit` で検証します。
+//!
+//! チェックポイントより前の履歴は、後から新しい順に取得できます（[`Chain::backfill`]）。
+//! 取得したブロックはハッシュが子のブロックの親ハッシュと一致することだけを確認し、
+//! 高さとハッシュ、トランザクションの索引に追加します（乱数とビューには反映しない）。
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{Result, anyhow, bail};
+use tracing::info;
+use super::{Block, Chain, Head, HASH_PREFIX, HEAD_KEY, height_key};
+use super::beacon::{Randomness, randomness_writes};
+use super::explorer::{tx_index_writes, tx_location_writes};
+
+/// 保存している最も古いブロックの高さのキー（チェックポイントから起動した場合のみ）
+const BASE_KEY: &[u8] = b"block/base";
+
+/// 信頼するブロック（`<高さ>:<ハッシュ>`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedCheckpoint {
+    pub height: u64,
+    pub hash: String,
+}
+
+impl FromStr for TrustedCheckpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (height, hash) = s.split_once(':')
+            .ok_or_else(|| anyhow!("Checkpoint '{}' must be <height>:<hash>", s))?;
+        let height = height.trim().parse::<u64>()
+            .map_err(|_| anyhow!("Checkpoint height '{}' is not a number", height))?;
+        let hash = hash.trim().trim_start_matches("0x").to_lowercase();
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!("Checkpoint hash '{}' must be 32 bytes in hex", hash);
+        }
+        Ok(Self { height, hash })
+    }
+}
+
+impl fmt::Display for TrustedCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.height, self.hash)
+    }
+}
+
+impl TrustedCheckpoint {
+    /// ブロックがチェックポイントと一致するか
+    pub fn verify(&self, block: &Block) -> Result<()> {
+        if block.height != self.height || block.hash != self.hash {
+            bail!("Block {} at height {} does not match checkpoint {}", block.hash, block.height, self);
+        }
+        if block.hash != block.compute_hash() {
+            bail!("Checkpoint block {} has an invalid hash", block.hash);
+        }
+        Ok(())
+    }
+}
+
+impl Chain {
+    /// 空のチェーンをチェックポイントのブロックから始める
+    ///
+    /// 乱数はブロックのハッシュと一致することだけを確認します（親の乱数がないため導出できない）。
+    pub async fn anchor(&self, checkpoint: &TrustedCheckpoint, block: Block, randomness: Randomness) -> Result<()> {
+        checkpoint.verify(&block)?;
+        if randomness.height != block.height || randomness.block_hash != block.hash {
+            bail!("Randomness of block {} does not belong to checkpoint {}", randomness.block_hash, checkpoint);
+        }
+        let mut head = self.head.write().await;
+        if let Some(head) = head.as_ref() {
+            bail!("Chain already has blocks up to {}; checkpoints only apply to an empty chain", head.height);
+        }
+
+        let mut batch = vec![
+            (height_key(block.height), Some(serde_json::to_vec(&block)?)),
+            (format!("{}{}", HASH_PREFIX, block.hash).into_bytes(), Some(block.height.to_be_bytes().to_vec())),
+            (HEAD_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())),
+            (BASE_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())),
+        ];
+        batch.extend(tx_index_writes(&block));
+        batch.extend(randomness_writes(&randomness)?);
+        self.storage.batch_write(batch).await?;
+        *head = Some(Head::of(&block, randomness.value));
+        info!("Started the chain from trusted checkpoint {}", checkpoint);
+        Ok(())
+    }
+
+    /// 保存している最も古いブロックの高さ（ジェネシスから全て保存している場合は `None`）
+    pub async fn base(&self) -> Result<Option<u64>> {
+        self.storage.get(BASE_KEY).await?
+            .map(|bytes| bytes.try_into().map(u64::from_be_bytes).map_err(|_| anyhow!("Corrupted chain base")))
+            .transpose()
+    }
+
+    /// チェックポイントより前のブロックを、保存している最も古いブロックの直前に追加する
+    pub async fn backfill(&self, block: Block) -> Result<()> {
+        self.check_disk()?;
+        let base = self.base().await?
+            .filter(|base| *base > 0)
+            .ok_or_else(|| anyhow!("Chain history is complete; nothing to backfill"))?;
+        let child = self.get_block(base).await?
+            .ok_or_else(|| anyhow!("Block {} is missing", base))?;
+        if block.height + 1 != base || block.hash != child.parent_hash {
+            bail!("Block {} at height {} is not the parent of block {}", block.hash, block.height, child.hash);
+        }
+        if block.hash != block.compute_hash() {
+            bail!("Block {} has an invalid hash", block.hash);
+        }
+
+        let mut batch = vec![
+            (height_key(block.height), Some(serde_json::to_vec(&block)?)),
+            (format!("{}{}", HASH_PREFIX, block.hash).into_bytes(), Some(block.height.to_be_bytes().to_vec())),
+            // ジェネシスまで揃ったら通常のチェーンと同じにする
+            (BASE_KEY.to_vec(), (block.height > 0).then(|| block.height.to_be_bytes().to_vec())),
+        ];
+        batch.extend(tx_location_writes(&block));
+        self.storage.batch_write(batch).await?;
+        if block.height == 0 {
+            info!("Backfilled chain history down to genesis");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    async fn open() -> Chain {
+        Chain::open(RedbStorage::memory()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_anchor_at_checkpoint_and_backfill() {
+        let upstream = open().await;
+        for _ in 0..4 {
+            upstream.commit(upstream.next_signed_block(vec![]).await).await.unwrap();
+        }
+        let mut blocks = Vec::new();
+        for height in 0..4 {
+            blocks.push(upstream.get_block(height).await.unwrap().unwrap());
+        }
+        let block = |height: usize| blocks[height].clone();
+        let checkpoint: TrustedCheckpoint = format!("2:0x{}", block(2).hash.to_uppercase()).parse().unwrap();
+        let randomness = upstream.randomness(2).await.unwrap().unwrap();
+
+        let chain = open().await;
+        let wrong = TrustedCheckpoint { height: 2, hash: block(1).hash };
+        assert!(chain.anchor(&wrong, block(2), randomness.clone()).await.is_err());
+        chain.anchor(&checkpoint, block(2), randomness).await.unwrap();
+        assert_eq!(chain.base().await.unwrap(), Some(2));
+
+        // チェックポイントの後は通常どおり検証して続ける（乱数も上流と一致する）
+        chain.commit(block(3)).await.unwrap();
+        assert_eq!(chain.randomness(3).await.unwrap(), upstream.randomness(3).await.unwrap());
+
+        // 履歴は新しい順にしか追加できない
+        assert!(chain.backfill(block(0)).await.is_err());
+        chain.backfill(block(1)).await.unwrap();
+        chain.backfill(block(0)).await.unwrap();
+        assert_eq!(chain.base().await.unwrap(), None);
+        assert_eq!(chain.get_block_by_hash(&block(0).hash).await.unwrap().map(|b| b.height), Some(0));
+    }
+}
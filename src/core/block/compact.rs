@@ -0,0 +1,310 @@
+//! コンパクトブロックによるブロックの中継
+//!
+//! ブロックの全体ではなくヘッダーとトランザクションの短いIDだけを送り、受信側は
+//! 自身のメモリプールからブロックを復元します。メモリプールにないトランザクションだけを
+//! 送信元に要求するため、メモリプールが温まったノード間では転送量が大きく減ります。
+//!
+//! 短いIDはブロックハッシュとランダムなノンスから作る鍵でトランザクションハッシュを
+//! ハッシュした先頭6バイトです。鍵がブロックごとに変わるため、IDが衝突する
+//! トランザクションを事前に用意する攻撃はできません。衝突や復元の失敗は
+//! ブロックハッシュの検証で検出し、欠けているトランザクションとして要求し直します。
+
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use crate::core::mempool::PendingTransaction;
+use super::{Block, Event};
+use super::header::BlockSignature;
+
+/// 短いIDのバイト数
+pub const SHORT_ID_LEN: usize = 6;
+
+/// コンパクトブロック
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactBlock {
+    pub height: u64,
+    pub hash: String,
+    pub parent_hash: String,
+    pub timestamp: u64,
+    pub validator: String,
+    #[serde(default)]
+    pub gas_limit: u64,
+    #[serde(default)]
+    pub gas_used: u64,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub receipts_root: String,
+    #[serde(default)]
+    pub logs_bloom: String,
+    #[serde(default)]
+    pub base_fee: u64,
+    #[serde(default)]
+    pub blob_fee: u64,
+    #[serde(default)]
+    pub blob_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub randomness_proof: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<BlockSignature>,
+    /// 短いIDの鍵に使うノンス
+    pub nonce: u64,
+    /// トランザクションの短いID（`SHORT_ID_LEN` バイトずつ連結）
+    #[serde(with = "hex::serde")]
+    pub short_ids: Vec<u8>,
+    /// 受信側が持っていないと分かっているトランザクション（ブロック内の位置と本体）
+    #[serde(default)]
+    pub prefilled: Vec<(u32, PendingTransaction)>,
+}
+
+/// 欠けているトランザクションの要求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTxnRequest {
+    pub block_hash: String,
+    /// ブロック内の位置（昇順）
+    pub indexes: Vec<u32>,
+}
+
+/// 要求されたトランザクション（要求の位置の順）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTxn {
+    pub block_hash: String,
+    pub transactions: Vec<PendingTransaction>,
+}
+
+/// 復元の結果
+#[derive(Debug)]
+pub enum Reconstruction {
+    Complete(Block),
+    /// 欠けているトランザクションがある
+    Incomplete(PartialBlock),
+}
+
+/// 復元途中のブロック
+#[derive(Debug)]
+pub struct PartialBlock {
+    compact: CompactBlock,
+    slots: Vec<Option<PendingTransaction>>,
+}
+
+impl CompactBlock {
+    /// `prefill` の位置のトランザクションは本体を含める
+    pub fn new(block: &Block, prefill: &[usize]) -> Self {
+        let mut compact = Self {
+            height: block.height,
+            hash: block.hash.clone(),
+            parent_hash: block.parent_hash.clone(),
+            timestamp: block.timestamp,
+            validator: block.validator.clone(),
+            gas_limit: block.gas_limit,
+            gas_used: block.gas_used,
+            events: block.events.clone(),
+            receipts_root: block.receipts_root.clone(),
+            logs_bloom: block.logs_bloom.clone(),
+            base_fee: block.base_fee,
+            blob_fee: block.blob_fee,
+            blob_bytes: block.blob_bytes,
+            randomness_proof: block.randomness_proof.clone(),
+            signature: block.signature.clone(),
+            nonce: rand::random(),
+            short_ids: Vec::with_capacity(block.transactions.len() * SHORT_ID_LEN),
+            prefilled: Vec::new(),
+        };
+        for (index, tx) in block.transactions.iter().enumerate() {
+            if prefill.contains(&index) {
+                compact.prefilled.push((index as u32, tx.clone()));
+            }
+            compact.short_ids.extend_from_slice(&compact.short_id(&tx.hash));
+        }
+        compact
+    }
+
+    /// ブロック内のトランザクションの数
+    pub fn len(&self) -> usize {
+        self.short_ids.len() / SHORT_ID_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.short_ids.is_empty()
+    }
+
+    /// トランザクションハッシュの短いID
+    pub fn short_id(&self, tx_hash: &str) -> [u8; SHORT_ID_LEN] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.hash.as_bytes());
+        hasher.update(self.nonce.to_be_bytes());
+        hasher.update(tx_hash.as_bytes());
+        let digest = hasher.finalize();
+        let mut id = [0; SHORT_ID_LEN];
+        id.copy_from_slice(&digest[..SHORT_ID_LEN]);
+        id
+    }
+
+    fn short_id_at(&self, index: usize) -> &[u8] {
+        &self.short_ids[index * SHORT_ID_LEN..(index + 1) * SHORT_ID_LEN]
+    }
+
+    /// JSONでのバイト数
+    pub fn encoded_size(&self) -> usize {
+        serde_json::to_vec(self).map_or(usize::MAX, |bytes| bytes.len())
+    }
+
+    /// `pool` のトランザクションからブロックを復元
+    ///
+    /// 短いIDが `pool` の複数のトランザクションと一致する場合は欠けているものとして扱います。
+    pub fn reconstruct<'a>(self, pool: impl IntoIterator<Item = &'a PendingTransaction>) -> Result<Reconstruction> {
+        if self.short_ids.len() % SHORT_ID_LEN != 0 {
+            return Err(anyhow!("Compact block {} has truncated short ids", self.hash));
+        }
+        let mut slots: Vec<Option<PendingTransaction>> = vec![None; self.len()];
+        for (index, tx) in &self.prefilled {
+            let slot = slots.get_mut(*index as usize)
+                .ok_or_else(|| anyhow!("Compact block {} prefills out-of-range index {}", self.hash, index))?;
+            *slot = Some(tx.clone());
+        }
+
+        // 短いID → 一致したトランザクション（複数一致した場合は None）
+        let wanted: HashMap<&[u8], usize> = (0..self.len())
+            .filter(|index| slots[*index].is_none())
+            .map(|index| (self.short_id_at(index), index))
+            .collect();
+        let mut matches: HashMap<usize, Option<&PendingTransaction>> = HashMap::new();
+        for tx in pool {
+            let id = self.short_id(&tx.hash);
+            if let Some(index) = wanted.get(id.as_slice()) {
+                matches.entry(*index)
+                    .and_modify(|found| *found = None)
+                    .or_insert(Some(tx));
+            }
+        }
+        for (index, tx) in matches {
+            slots[index] = tx.cloned();
+        }
+
+        let partial = PartialBlock { compact: self, slots };
+        if partial.missing().is_empty() {
+            match partial.assemble() {
+                Ok(block) => return Ok(Reconstruction::Complete(block)),
+                // 短いIDの衝突で別のトランザクションを取り込んだ場合は本体をすべて要求する
+                Err(_) => return Ok(Reconstruction::Incomplete(partial.cleared())),
+            }
+        }
+        Ok(Reconstruction::Incomplete(partial))
+    }
+
+    /// 要求に応えるトランザクションを `block` から取り出す
+    pub fn respond(block: &Block, request: &BlockTxnRequest) -> Result<BlockTxn> {
+        let transactions = request.indexes.iter()
+            .map(|index| block.transactions.get(*index as usize).cloned()
+                .ok_or_else(|| anyhow!("Block {} has no transaction at index {}", block.hash, index)))
+            .collect::<Result<_>>()?;
+        Ok(BlockTxn { block_hash: block.hash.clone(), transactions })
+    }
+}
+
+impl PartialBlock {
+    pub fn hash(&self) -> &str {
+        &self.compact.hash
+    }
+
+    /// 欠けているトランザクションの位置
+    pub fn missing(&self) -> Vec<u32> {
+        self.slots.iter().enumerate()
+            .filter(|(_, slot)| slot.is_none())
+            .map(|(index, _)| index as u32)
+            .collect()
+    }
+
+    /// 欠けているトランザクションの要求
+    pub fn request(&self) -> BlockTxnRequest {
+        BlockTxnRequest { block_hash: self.compact.hash.clone(), indexes: self.missing() }
+    }
+
+    /// 受け取ったトランザクションで欠けている位置を埋めてブロックを完成させる
+    pub fn fill(mut self, response: BlockTxn) -> Result<Block> {
+        let missing = self.missing();
+        if response.block_hash != self.compact.hash || response.transactions.len() != missing.len() {
+            return Err(anyhow!("Response does not match the missing transactions of block {}", self.compact.hash));
+        }
+        for (index, tx) in missing.into_iter().zip(response.transactions) {
+            if self.compact.short_id(&tx.hash) != self.compact.short_id_at(index as usize) {
+                return Err(anyhow!("Transaction {} does not match block {} at index {}", tx.hash, self.compact.hash, index));
+            }
+            self.slots[index as usize] = Some(tx);
+        }
+        self.assemble()
+    }
+
+    /// すべての位置を欠けている状態に戻す（プレフィル済みのものを除く）
+    fn cleared(mut self) -> Self {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if !self.compact.prefilled.iter().any(|(i, _)| *i as usize == index) {
+                *slot = None;
+            }
+        }
+        self
+    }
+
+    fn assemble(&self) -> Result<Block> {
+        let compact = &self.compact;
+        let transactions = self.slots.iter().cloned()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| anyhow!("Block {} is missing transactions", compact.hash))?;
+        let block = Block {
+            height: compact.height,
+            hash: compact.hash.clone(),
+            parent_hash: compact.parent_hash.clone(),
+            timestamp: compact.timestamp,
+            validator: compact.validator.clone(),
+            transactions,
+            events: compact.events.clone(),
+            gas_limit: compact.gas_limit,
+            gas_used: compact.gas_used,
+            receipts_root: compact.receipts_root.clone(),
+            logs_bloom: compact.logs_bloom.clone(),
+            base_fee: compact.base_fee,
+            blob_fee: compact.blob_fee,
+            blob_bytes: compact.blob_bytes,
+            randomness_proof: compact.randomness_proof.clone(),
+            signature: compact.signature.clone(),
+        };
+        if block.compute_hash() != block.hash {
+            return Err(anyhow!("Reconstructed block {} does not match its hash", block.hash));
+        }
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(nonce: u64) -> PendingTransaction {
+        PendingTransaction::test_transfer("0xalice", "0xbob", 1, nonce).with_data(vec![0; 256])
+    }
+
+    #[test]
+    fn test_reconstruct_from_mempool_and_fetch_missing() {
+        let txs: Vec<PendingTransaction> = (0..20).map(tx).collect();
+        let block = Block::new(7, "parent".to_string(), "validator".to_string(), txs.clone());
+        let compact = CompactBlock::new(&block, &[0]);
+        assert!(compact.encoded_size() * 10 < block.encoded_size());
+
+        // 受信側のメモリプールには 3 と 11 がない
+        let pool: Vec<&PendingTransaction> = txs.iter().filter(|tx| ![3, 11].contains(&tx.nonce)).collect();
+        let Reconstruction::Incomplete(partial) = compact.clone().reconstruct(pool.iter().copied()).unwrap() else {
+            panic!("expected missing transactions");
+        };
+        let request = partial.request();
+        assert_eq!(request.indexes, [3, 11]);
+        let response = CompactBlock::respond(&block, &request).unwrap();
+        assert_eq!(partial.fill(response).unwrap().hash, block.hash);
+
+        // すべて揃っていればそのまま復元できる
+        let Reconstruction::Complete(restored) = compact.reconstruct(txs.iter()).unwrap() else {
+            panic!("expected a complete block");
+        };
+        assert_eq!(restored.compute_hash(), block.hash);
+    }
+}
@@ -0,0 +1,189 @@
+//! エクスプローラー向けのブロックとトランザクションの参照
+//!
+//! ブロックの一覧（新しい順、カーソルで取得）と、ハッシュからのトランザクションの検索を提供します。
+//! トランザクションの索引はブロックの確定と同じバッチで書き込み、索引の導入前のブロックは
+//! チェーンを開くときに追加します。
+
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use tracing::info;
+use utoipa::ToSchema;
+use crate::core::mempool::PendingTransaction;
+use super::header::Receipt;
+use super::{Block, Chain};
+
+/// トランザクションのハッシュから（高さ, ブロック内の位置）への索引のキープレフィックス
+const TX_PREFIX: &str = "block/tx/";
+/// トランザクションの索引に反映済みの高さのキー
+const TX_INDEX_HEIGHT_KEY: &[u8] = b"block/tx_index_height";
+/// ブロックの一覧の1ページの上限
+pub const MAX_BLOCK_PAGE: usize = 100;
+
+/// ブロックの概要
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BlockSummary {
+    pub height: u64,
+    pub hash: String,
+    pub parent_hash: String,
+    /// 生成時刻（UNIX秒）
+    pub timestamp: u64,
+    pub validator: String,
+    pub transaction_count: usize,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    pub base_fee: u64,
+}
+
+impl BlockSummary {
+    pub fn of(block: &Block) -> Self {
+        Self {
+            height: block.height,
+            hash: block.hash.clone(),
+            parent_hash: block.parent_hash.clone(),
+            timestamp: block.timestamp,
+            validator: block.validator.clone(),
+            transaction_count: block.transactions.len(),
+            gas_used: block.gas_used,
+            gas_limit: block.gas_limit,
+            base_fee: block.base_fee,
+        }
+    }
+}
+
+/// ブロックの一覧（新しい順）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BlockPage {
+    pub items: Vec<BlockSummary>,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    pub next_cursor: Option<String>,
+}
+
+/// 確定したトランザクションとレシート
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransactionDetail {
+    pub transaction: PendingTransaction,
+    pub block_height: u64,
+    pub block_hash: String,
+    /// ブロック内の位置
+    pub index: u32,
+    /// ブロックの生成時刻（UNIX秒）
+    pub timestamp: u64,
+    pub receipt: Receipt,
+}
+
+fn tx_key(hash: &str) -> Vec<u8> {
+    format!("{}{}", TX_PREFIX, hash.trim_start_matches("0x").to_lowercase()).into_bytes()
+}
+
+/// ブロックのトランザクションの索引の書き込み
+pub(super) fn tx_index_writes(block: &Block) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    let mut writes = tx_location_writes(block);
+    writes.push((TX_INDEX_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+    writes
+}
+
+/// トランザクションの位置の書き込み（索引済みの高さは更新しない）
+pub(super) fn tx_location_writes(block: &Block) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    block.transactions.iter().enumerate()
+        .map(|(index, tx)| {
+            let mut location = block.height.to_be_bytes().to_vec();
+            location.extend_from_slice(&(index as u32).to_be_bytes());
+            (tx_key(&tx.hash), Some(location))
+        })
+        .collect()
+}
+
+impl Chain {
+    /// 保存しているブロックのトランザクションを索引に追加し直す（`index rebuild`）
+    pub async fn reindex_transactions(&self, block: &Block) -> Result<()> {
+        self.storage.batch_write(tx_location_writes(block)).await
+    }
+
+    /// 索引の導入前に確定したブロックのトランザクションを索引に追加
+    pub(super) async fn backfill_tx_index(&self) -> Result<()> {
+        let Some((head, _)) = self.head().await else {
+            return Ok(());
+        };
+        let start = match self.storage.get(TX_INDEX_HEIGHT_KEY).await? {
+            Some(bytes) => u64::from_be_bytes(bytes.try_into().map_err(|_| anyhow!("Corrupted transaction index height"))?) + 1,
+            None => 0,
+        };
+        if start > head {
+            return Ok(());
+        }
+        info!("Indexing transactions of blocks {} to {}", start, head);
+        for height in start..=head {
+            if let Some(block) = self.get_block(height).await? {
+                self.storage.batch_write(tx_index_writes(&block)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// ブロックの概要を取得
+    ///
+    /// 既定は新しい順で、`from` を指定した場合はこの高さ以下から始めます。
+    /// `ascending` の場合は古い順で、`from` 以上から始めます。
+    pub async fn blocks(&self, from: Option<u64>, ascending: bool, limit: usize) -> Result<BlockPage> {
+        let limit = limit.clamp(1, MAX_BLOCK_PAGE);
+        let Some((head, _)) = self.head().await else {
+            return Ok(BlockPage { items: Vec::new(), next_cursor: None });
+        };
+        let (heights, next): (Vec<u64>, Option<u64>) = if ascending {
+            let start = from.unwrap_or(0);
+            let next = start.checked_add(limit as u64).filter(|next| *next <= head);
+            ((start..=head).take(limit).collect(), next)
+        } else {
+            let start = from.map_or(head, |from| from.min(head));
+            ((0..=start).rev().take(limit).collect(), start.checked_sub(limit as u64))
+        };
+        let mut items = Vec::with_capacity(heights.len());
+        for height in heights {
+            if let Some(block) = self.get_block(height).await? {
+                items.push(BlockSummary::of(&block));
+            }
+        }
+        Ok(BlockPage { items, next_cursor: next.map(|height| height.to_string()) })
+    }
+
+    /// ハッシュを指定して確定したトランザクションを取得
+    pub async fn find_transaction(&self, hash: &str) -> Result<Option<TransactionDetail>> {
+        let Some(location) = self.storage.get(&tx_key(hash)).await? else {
+            return Ok(None);
+        };
+        let location: [u8; 12] = location.try_into().map_err(|_| anyhow!("Corrupted transaction index"))?;
+        let height = u64::from_be_bytes(location[..8].try_into()?);
+        let index = u32::from_be_bytes(location[8..].try_into()?);
+        let Some(block) = self.get_block(height).await? else {
+            return Ok(None);
+        };
+        let Some(transaction) = block.transactions.get(index as usize).cloned() else {
+            return Ok(None);
+        };
+        let receipt = block.receipts().swap_remove(index as usize);
+        Ok(Some(TransactionDetail {
+            transaction,
+            block_height: block.height,
+            block_hash: block.hash.clone(),
+            index,
+            timestamp: block.timestamp,
+            receipt,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tx_index_writes() {
+        let tx = PendingTransaction::test_transfer("alice", "bob", 1, 0);
+        let block = Block::new(7, "parent".to_string(), "v".to_string(), vec![tx.clone(), tx]);
+        let writes = tx_index_writes(&block);
+        assert_eq!(writes.len(), 3);
+        assert_eq!(writes[1].0, b"block/tx/abcd".to_vec());
+        assert_eq!(writes[1].1.as_deref(), Some(&[0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 1][..]));
+        assert_eq!(writes[2], (TX_INDEX_HEIGHT_KEY.to_vec(), Some(7u64.to_be_bytes().to_vec())));
+    }
+}
@@ -0,0 +1,373 @@
+//! ブロックヘッダーの拡張フィールド
+//!
+//! 標準的なエクスプローラーと同じ情報を表示できるよう、レシートのルート、イベントの
+//! ブルームフィルター、基本手数料、生成したバリデーターの署名をブロックに持たせます。
+//! レシートのルートとブルームフィルターはブロックハッシュの対象に含まれ、
+//! 署名はブロックハッシュへの署名です。
+//! 主な機能：
+//! - トランザクションごとのレシートとそのマークルルート（SHA-256）
+//! - 2048ビットのブルームフィルター（Ethereum と同じ形式、ハッシュは SHA-256）
+//! - 親ブロックのガス使用量による基本手数料の調整（EIP-1559 と同じ式）
+//! - ブロックハッシュへの ed25519 署名と検証
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use utoipa::ToSchema;
+use crate::core::wallet;
+use super::{Block, Event};
+use super::limits::ConsensusParams;
+
+/// ブルームフィルターのバイト数
+pub const BLOOM_BYTES: usize = 256;
+/// 基本手数料を上げ下げする目安（ガス上限のこの分の1を使うと据え置き）
+pub const ELASTICITY: u64 = 2;
+/// 1ブロックで変わる基本手数料の割合の上限（`1/BASE_FEE_CHANGE_DENOMINATOR`）
+pub const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// トランザクションのレシート
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Receipt {
+    pub tx_hash: String,
+    /// 1 は成功（実行に失敗したトランザクションはブロックに含まれない）
+    pub status: u8,
+    pub gas_used: u64,
+    /// ブロック内でこのトランザクションまでのガス使用量の合計
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Event>,
+}
+
+impl Receipt {
+    fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.tx_hash.as_bytes());
+        hasher.update([self.status]);
+        hasher.update(self.gas_used.to_be_bytes());
+        hasher.update(self.cumulative_gas_used.to_be_bytes());
+        for log in &self.logs {
+            hasher.update(log.index.to_be_bytes());
+            hasher.update(normalize(&log.address).as_bytes());
+            for topic in &log.topics {
+                hasher.update(normalize(topic).as_bytes());
+            }
+            hasher.update((log.data.len() as u64).to_be_bytes());
+            hasher.update(&log.data);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// ブロックハッシュへの署名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BlockSignature {
+    /// 署名者の公開鍵（hex）
+    pub public_key: String,
+    /// 署名（hex）
+    pub signature: String,
+}
+
+/// ヘッダーの検証エラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum HeaderError {
+    #[error("receipts root {declared} does not match the transactions ({actual})")]
+    ReceiptsRootMismatch { declared: String, actual: String },
+
+    #[error("logs bloom does not match the block's events")]
+    LogsBloomMismatch,
+
+    #[error("base fee {declared} does not match the expected base fee {expected}")]
+    BaseFeeMismatch { declared: u64, expected: u64 },
+
+    #[error("transaction {tx_hash} pays gas price {gas_price}, below the base fee {base_fee}")]
+    Underpriced { tx_hash: String, gas_price: u64, base_fee: u64 },
+
+    #[error("invalid proposer signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("block {height} has no receipts root (required from height {activation})")]
+    MissingReceiptsRoot { height: u64, activation: u64 },
+
+    #[error("block {height} is not signed by its proposer (required from height {activation})")]
+    Unsigned { height: u64, activation: u64 },
+}
+
+/// 2048ビットのブルームフィルター
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogsBloom([u8; BLOOM_BYTES]);
+
+impl Default for LogsBloom {
+    fn default() -> Self {
+        Self([0; BLOOM_BYTES])
+    }
+}
+
+impl LogsBloom {
+    /// イベントのアドレスとトピックから作成
+    pub fn from_events(events: &[Event]) -> Self {
+        let mut bloom = Self::default();
+        for event in events {
+            bloom.accrue(&event.address);
+            for topic in &event.topics {
+                bloom.accrue(topic);
+            }
+        }
+        bloom
+    }
+
+    /// 値（アドレスまたはトピック）を追加
+    pub fn accrue(&mut self, value: &str) {
+        for (byte, bit) in Self::positions(value) {
+            self.0[byte] |= bit;
+        }
+    }
+
+    /// 値を含む可能性があるか（偽陽性あり）
+    pub fn contains(&self, value: &str) -> bool {
+        Self::positions(value).into_iter().all(|(byte, bit)| self.0[byte] & bit != 0)
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// ハッシュの先頭3組の2バイトの下位11ビットを立てるビットとする
+    fn positions(value: &str) -> [(usize, u8); 3] {
+        let hash = Sha256::digest(normalize(value).as_bytes());
+        std::array::from_fn(|i| {
+            let bit = (u16::from_be_bytes([hash[2 * i], hash[2 * i + 1]]) & 2047) as usize;
+            (BLOOM_BYTES - 1 - bit / 8, 1 << (bit % 8))
+        })
+    }
+}
+
+/// レシートのマークルルート（奇数個の段は最後の要素を複製する、空の場合は0）
+pub fn receipts_root(receipts: &[Receipt]) -> String {
+    let mut level: Vec<[u8; 32]> = receipts.iter().map(Receipt::hash).collect();
+    if level.is_empty() {
+        return hex::encode([0u8; 32]);
+    }
+    while level.len() > 1 {
+        level = level.chunks(2)
+            .map(|pair| {
+                let mut hasher = Sha256::new();
+                hasher.update(pair[0]);
+                hasher.update(pair.get(1).unwrap_or(&pair[0]));
+                hasher.finalize().into()
+            })
+            .collect();
+    }
+    hex::encode(level[0])
+}
+
+/// 親ブロックから次のブロックの基本手数料を計算
+///
+/// 親がガス上限の `1/ELASTICITY` より多く使っていれば上げ、少なければ下げます。
+/// `initial` が0の場合は基本手数料を使いません。親がない、または基本手数料の導入前のブロック
+/// （`parent_base_fee` が0）の場合は `initial` から始めます。
+pub fn next_base_fee(initial: u64, parent_base_fee: u64, parent_gas_used: u64, parent_gas_limit: u64) -> u64 {
+    if initial == 0 {
+        return 0;
+    }
+    if parent_base_fee == 0 || parent_gas_limit == 0 {
+        return initial;
+    }
+    let target = (parent_gas_limit / ELASTICITY).max(1) as u128;
+    let used = parent_gas_used as u128;
+    let base = parent_base_fee as u128;
+    let denominator = BASE_FEE_CHANGE_DENOMINATOR as u128;
+    let next = if used > target {
+        base + (base * (used - target) / target / denominator).max(1)
+    } else {
+        base - base * (target - used) / target / denominator
+    };
+    next.clamp(1, u64::MAX as u128) as u64
+}
+
+impl Block {
+    /// トランザクションのレシート（実行したガスはトランザクションのガス上限とする）
+    pub fn receipts(&self) -> Vec<Receipt> {
+        let mut cumulative = 0u64;
+        self.transactions.iter()
+            .map(|tx| {
+                cumulative = cumulative.saturating_add(tx.gas_limit);
+                Receipt {
+                    tx_hash: tx.hash.clone(),
+                    status: 1,
+                    gas_used: tx.gas_limit,
+                    cumulative_gas_used: cumulative,
+                    logs: self.events.iter().filter(|e| e.tx_hash == tx.hash).cloned().collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// レシートのルートとブルームフィルターを計算し直してハッシュを更新
+    pub fn seal(mut self) -> Self {
+        self.receipts_root = receipts_root(&self.receipts());
+        self.logs_bloom = LogsBloom::from_events(&self.events).to_hex();
+        self.hash = self.compute_hash();
+        self.signature = None;
+        self
+    }
+
+    /// 基本手数料を設定してハッシュを計算し直す
+    pub fn with_base_fee(mut self, base_fee: u64) -> Self {
+        self.base_fee = base_fee;
+        self.hash = self.compute_hash();
+        self.signature = None;
+        self
+    }
+
+    /// ブロックハッシュに署名
+    pub fn sign(mut self, key: &SigningKey) -> Self {
+        self.signature = Some(BlockSignature {
+            public_key: wallet::address_of(&key.verifying_key()),
+            signature: hex::encode(key.sign(self.hash.as_bytes()).to_bytes()),
+        });
+        self
+    }
+
+    /// ヘッダーの拡張フィールドが本体と一致し、生成者の署名が正しいか検証
+    ///
+    /// `header_activation_height` 以降のブロックはレシートのルートと、生成者のアドレスの鍵による署名が必須です。
+    /// それより前のブロック（導入前の形式）はどちらも省略でき、省略した項目は検証しません。
+    pub fn verify_header(&self, params: &ConsensusParams, expected_base_fee: u64) -> Result<(), HeaderError> {
+        let activation = params.header_activation_height;
+        let activated = self.height >= activation;
+        if !self.receipts_root.is_empty() {
+            let actual = receipts_root(&self.receipts());
+            if self.receipts_root != actual {
+                return Err(HeaderError::ReceiptsRootMismatch { declared: self.receipts_root.clone(), actual });
+            }
+            if self.logs_bloom != LogsBloom::from_events(&self.events).to_hex() {
+                return Err(HeaderError::LogsBloomMismatch);
+            }
+            if self.base_fee != expected_base_fee {
+                return Err(HeaderError::BaseFeeMismatch { declared: self.base_fee, expected: expected_base_fee });
+            }
+            if let Some(tx) = self.transactions.iter().find(|tx| tx.gas_price < self.base_fee) {
+                return Err(HeaderError::Underpriced {
+                    tx_hash: tx.hash.clone(),
+                    gas_price: tx.gas_price,
+                    base_fee: self.base_fee,
+                });
+            }
+        } else if activated {
+            return Err(HeaderError::MissingReceiptsRoot { height: self.height, activation });
+        }
+        match &self.signature {
+            Some(signature) => self.verify_signature(signature, activated),
+            None if activated => Err(HeaderError::Unsigned { height: self.height, activation }),
+            None => Ok(()),
+        }
+    }
+
+    /// 署名がブロックハッシュへのもので、生成者の鍵によるものか
+    ///
+    /// 導入前のブロックはアドレス形式のバリデーターの場合のみ鍵との一致を確認します。
+    fn verify_signature(&self, signed: &BlockSignature, activated: bool) -> Result<(), HeaderError> {
+        let invalid = |e: &dyn std::fmt::Display| HeaderError::InvalidSignature(e.to_string());
+        let public_key: [u8; 32] = hex::decode(&signed.public_key)
+            .map_err(|e| invalid(&e))?
+            .try_into()
+            .map_err(|_| invalid(&"public key must be 32 bytes"))?;
+        let public_key = VerifyingKey::from_bytes(&public_key).map_err(|e| invalid(&e))?;
+        let validator = normalize(&self.validator);
+        let is_address = validator.len() == 64 && validator.chars().all(|c| c.is_ascii_hexdigit());
+        if (activated || is_address) && wallet::address_of(&public_key) != validator {
+            return Err(invalid(&format!("signed by {} instead of validator {}", signed.public_key, self.validator)));
+        }
+        let signature = hex::decode(&signed.signature)
+            .map_err(|e| invalid(&e))
+            .and_then(|bytes| Signature::from_slice(&bytes).map_err(|e| invalid(&e)))?;
+        public_key.verify_strict(self.hash.as_bytes(), &signature)
+            .map_err(|_| invalid(&format!("signature does not match block {}", self.hash)))
+    }
+}
+
+/// アドレスとトピックの表記を揃える
+fn normalize(value: &str) -> String {
+    value.trim().trim_start_matches("0x").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::PendingTransaction;
+
+    fn tx(nonce: u64, gas_price: u64) -> PendingTransaction {
+        PendingTransaction { gas_price, ..PendingTransaction::test_transfer("alice", "bob", 1, nonce) }.rehashed()
+    }
+
+    #[test]
+    fn test_header_fields_are_verified() {
+        let params = ConsensusParams::default();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let validator = wallet::address_of(&key.verifying_key());
+        let mut block = Block::new(1, "p".to_string(), validator, vec![tx(0, 10), tx(1, 12)]);
+        block.events.push(Event {
+            tx_hash: block.transactions[1].hash.clone(),
+            index: 0,
+            address: "0xCCCC".to_string(),
+            topics: vec!["ddf252ad".to_string()],
+            data: vec![1, 2],
+        });
+        let block = block.seal().with_base_fee(10).sign(&key);
+        assert_eq!(block.verify_header(&params, 10), Ok(()));
+        assert_eq!(block.receipts()[1].cumulative_gas_used, 42_000);
+
+        let bloom = LogsBloom::from_events(&block.events);
+        assert!(bloom.contains("cccc") && bloom.contains("0xDDF252AD"));
+        assert!(!bloom.contains("eeee"));
+
+        assert_eq!(block.verify_header(&params, 11), Err(HeaderError::BaseFeeMismatch { declared: 10, expected: 11 }));
+        let underpriced = block.clone().with_base_fee(11).sign(&key);
+        assert!(matches!(underpriced.verify_header(&params, 11), Err(HeaderError::Underpriced { .. })));
+        let mut tampered = block.clone();
+        tampered.events.clear();
+        assert!(matches!(tampered.verify_header(&params, 10), Err(HeaderError::ReceiptsRootMismatch { .. })));
+
+        // 署名はブロックハッシュに対する生成者の鍵によるもの
+        let mut forged = block.clone();
+        forged.hash = "00".repeat(32);
+        assert!(matches!(forged.verify_header(&params, 10), Err(HeaderError::InvalidSignature(_))));
+        let other = block.clone().with_base_fee(10).sign(&SigningKey::from_bytes(&[8; 32]));
+        assert!(matches!(other.verify_header(&params, 10), Err(HeaderError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_legacy_blocks_before_activation() {
+        let params = ConsensusParams { header_activation_height: 5, ..ConsensusParams::default() };
+        let mut legacy = Block::new(4, "p".to_string(), "v".to_string(), vec![tx(0, 10)]);
+        legacy.receipts_root.clear();
+        legacy.hash = legacy.compute_hash();
+        assert_eq!(legacy.verify_header(&params, 0), Ok(()));
+        // 導入前はアドレス形式でない生成者の署名も受け入れる
+        assert_eq!(legacy.clone().sign(&SigningKey::from_bytes(&[7; 32])).verify_header(&params, 0), Ok(()));
+
+        // 導入後はレシートのルートと署名を省略できない
+        let mut stripped = Block::new(5, "p".to_string(), "v".to_string(), vec![tx(0, 10)]);
+        stripped.receipts_root.clear();
+        stripped.hash = stripped.compute_hash();
+        assert_eq!(
+            stripped.verify_header(&params, 0),
+            Err(HeaderError::MissingReceiptsRoot { height: 5, activation: 5 }),
+        );
+        let unsigned = Block::new(5, "p".to_string(), "v".to_string(), vec![tx(0, 10)]);
+        assert_eq!(unsigned.verify_header(&params, 0), Err(HeaderError::Unsigned { height: 5, activation: 5 }));
+        let unbound = unsigned.sign(&SigningKey::from_bytes(&[7; 32]));
+        assert!(matches!(unbound.verify_header(&params, 0), Err(HeaderError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_base_fee_follows_parent_usage() {
+        assert_eq!(next_base_fee(0, 800, 1_000_000, 1_000_000), 0);
+        assert_eq!(next_base_fee(1_000, 0, 0, 0), 1_000);
+        assert_eq!(next_base_fee(1_000, 800, 500_000, 1_000_000), 800);
+        assert_eq!(next_base_fee(1_000, 800, 1_000_000, 1_000_000), 900);
+        assert_eq!(next_base_fee(1_000, 800, 0, 1_000_000), 700);
+        // 下げる幅が0に丸められても1より下がらない
+        assert_eq!(next_base_fee(1_000, 1, 0, 1_000_000), 1);
+    }
+}
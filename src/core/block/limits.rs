@@ -0,0 +1,207 @@
+//! ブロックのガス・サイズ上限
+//!
+//! ブロックごとのガス上限とバイト数の上限を定め、ブロックの生成と検証の両方で適用します。
+//! 動的調整を有効にすると、各ブロックのガス上限は親ブロックから `1/adjustment_quotient` の範囲で
+//! 生成者が投票した目標値へ近づきます（Ethereum のガス上限の投票と同じ方式）。
+//!
+//! ブロックのガスは含まれるトランザクションのガス上限の合計、サイズは JSON でのバイト数です。
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::config::ConsensusSettings;
+use super::Block;
+
+/// 生成時にブロックのヘッダー等のために残しておくバイト数
+pub const HEADER_RESERVE: usize = 1024;
+
+/// ブロックに関するコンセンサスパラメーター
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusParams {
+    /// ブロックの最大バイト数
+    pub max_block_bytes: usize,
+    /// ガス上限の初期値（動的調整が無効の場合は固定値）
+    pub block_gas_limit: u64,
+    /// ガス上限を投票で調整する
+    pub dynamic_gas_limit: bool,
+    /// 動的調整で取りうるガス上限の範囲
+    pub min_gas_limit: u64,
+    pub max_gas_limit: u64,
+    /// 1ブロックで変更できるガス上限の割合（親の `1/adjustment_quotient`）
+    pub adjustment_quotient: u64,
+    /// このノードが生成するブロックで投票するガス上限
+    pub gas_target: Option<u64>,
+    /// ネットワークのチェーンID（署名済みトランザクションはこのIDを含む必要がある）
+    pub chain_id: u64,
+    /// 基本手数料の初期値（0 は無効）
+    pub initial_base_fee: u64,
+    /// トランザクション1件のブロブの最大バイト数
+    pub max_blob_size: u64,
+    /// ブロックのブロブの最大バイト数（0 はブロブを無効にする）
+    pub max_blob_bytes: u64,
+    /// ブロブ手数料の最低値
+    pub min_blob_fee: u64,
+    /// この高さ以降のブロックはレシートのルート、生成者の署名と乱数の VRF 証明を必須にする
+    pub header_activation_height: u64,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self::from(&ConsensusSettings::default())
+    }
+}
+
+impl From<&ConsensusSettings> for ConsensusParams {
+    fn from(settings: &ConsensusSettings) -> Self {
+        Self {
+            max_block_bytes: settings.max_block_bytes,
+            block_gas_limit: settings.block_gas_limit,
+            dynamic_gas_limit: settings.dynamic_gas_limit,
+            min_gas_limit: settings.min_gas_limit,
+            max_gas_limit: settings.max_gas_limit,
+            adjustment_quotient: settings.adjustment_quotient.max(1),
+            gas_target: settings.gas_target,
+            chain_id: settings.chain_id,
+            initial_base_fee: settings.initial_base_fee,
+            max_blob_size: settings.max_blob_size,
+            max_blob_bytes: settings.max_blob_bytes,
+            min_blob_fee: settings.min_blob_fee.max(1),
+            header_activation_height: settings.header_activation_height,
+        }
+    }
+}
+
+/// 上限に違反したブロック
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BlockLimitError {
+    #[error("gas limit {gas_limit} is outside the allowed range {min}..={max}")]
+    GasLimitOutOfBounds { gas_limit: u64, min: u64, max: u64 },
+
+    #[error("block uses {used} gas, exceeding its gas limit of {limit}")]
+    GasLimitExceeded { used: u64, limit: u64 },
+
+    #[error("declared gas used {declared} does not match the transactions ({actual})")]
+    GasUsedMismatch { declared: u64, actual: u64 },
+
+    #[error("block is {size} bytes, exceeding the limit of {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+}
+
+impl ConsensusParams {
+    /// 親ブロックのガス上限から許されるガス上限の範囲
+    ///
+    /// 親が制限の導入前のブロック（ガス上限が0）または存在しない場合は初期値のみを許します。
+    pub fn gas_limit_bounds(&self, parent_gas_limit: Option<u64>) -> (u64, u64) {
+        match parent_gas_limit.filter(|g| *g > 0) {
+            Some(parent) if self.dynamic_gas_limit => {
+                let step = parent / self.adjustment_quotient;
+                (
+                    parent.saturating_sub(step).max(self.min_gas_limit),
+                    parent.saturating_add(step).min(self.max_gas_limit),
+                )
+            }
+            _ => (self.block_gas_limit, self.block_gas_limit),
+        }
+    }
+
+    /// 次のブロックのガス上限（目標値へ許される範囲で近づける）
+    pub fn next_gas_limit(&self, parent_gas_limit: Option<u64>) -> u64 {
+        let (min, max) = self.gas_limit_bounds(parent_gas_limit);
+        let current = parent_gas_limit.filter(|g| *g > 0).unwrap_or(self.block_gas_limit);
+        self.gas_target.unwrap_or(current).clamp(min, max)
+    }
+
+    /// ブロックが上限を守っているか検証
+    pub fn validate(&self, block: &Block, parent_gas_limit: Option<u64>) -> Result<(), BlockLimitError> {
+        let (min, max) = self.gas_limit_bounds(parent_gas_limit);
+        if block.gas_limit < min || block.gas_limit > max {
+            return Err(BlockLimitError::GasLimitOutOfBounds { gas_limit: block.gas_limit, min, max });
+        }
+        let actual = Block::total_gas(&block.transactions);
+        if block.gas_used != actual {
+            return Err(BlockLimitError::GasUsedMismatch { declared: block.gas_used, actual });
+        }
+        if actual > block.gas_limit {
+            return Err(BlockLimitError::GasLimitExceeded { used: actual, limit: block.gas_limit });
+        }
+        let size = block.encoded_size();
+        if size > self.max_block_bytes {
+            return Err(BlockLimitError::TooLarge { size, limit: self.max_block_bytes });
+        }
+        Ok(())
+    }
+
+    /// 親ブロックの（基本手数料, ガス使用量, ガス上限）から次のブロックの基本手数料
+    pub fn next_base_fee(&self, parent: Option<(u64, u64, u64)>) -> u64 {
+        let (base_fee, gas_used, gas_limit) = parent.unwrap_or_default();
+        super::header::next_base_fee(self.initial_base_fee, base_fee, gas_used, gas_limit)
+    }
+
+    /// ブロック生成時にトランザクションへ割り当てられるバイト数
+    pub fn transaction_bytes(&self) -> usize {
+        self.max_block_bytes.saturating_sub(HEADER_RESERVE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::PendingTransaction;
+
+    fn params(dynamic: bool, target: Option<u64>) -> ConsensusParams {
+        ConsensusParams {
+            max_block_bytes: 4096,
+            block_gas_limit: 1_000_000,
+            dynamic_gas_limit: dynamic,
+            min_gas_limit: 500_000,
+            max_gas_limit: 2_000_000,
+            adjustment_quotient: 1024,
+            gas_target: target,
+            chain_id: crate::core::wallet::DEFAULT_CHAIN_ID,
+            initial_base_fee: 0,
+            max_blob_size: 0,
+            max_blob_bytes: 0,
+            min_blob_fee: 1,
+            header_activation_height: 0,
+        }
+    }
+
+    fn tx(gas_limit: u64, data: usize) -> PendingTransaction {
+        PendingTransaction { gas_limit, ..PendingTransaction::test_transfer("alice", "bob", 1, 0).with_data(vec![0; data]) }.rehashed()
+    }
+
+    #[test]
+    fn test_gas_limit_moves_towards_target() {
+        let fixed = params(false, Some(2_000_000));
+        assert_eq!(fixed.next_gas_limit(Some(1_000_000)), 1_000_000);
+
+        let rising = params(true, Some(2_000_000));
+        assert_eq!(rising.next_gas_limit(None), 1_000_000);
+        assert_eq!(rising.next_gas_limit(Some(1_000_000)), 1_000_000 + 1_000_000 / 1024);
+        assert_eq!(rising.next_gas_limit(Some(1_999_999)), 2_000_000);
+
+        // 目標がなければ親の値を維持する
+        assert_eq!(params(true, None).next_gas_limit(Some(1_200_000)), 1_200_000);
+        assert_eq!(params(true, Some(0)).next_gas_limit(Some(500_100)), 500_000);
+    }
+
+    #[test]
+    fn test_validate_block_limits() {
+        let params = params(true, None);
+        let block = Block::new(1, "p".to_string(), "v".to_string(), vec![tx(21_000, 0)]).with_gas_limit(1_000_000);
+        assert_eq!(params.validate(&block, Some(1_000_000)), Ok(()));
+        assert!(matches!(
+            params.validate(&block, Some(1_500_000)),
+            Err(BlockLimitError::GasLimitOutOfBounds { .. })
+        ));
+
+        let heavy = Block::new(1, "p".to_string(), "v".to_string(), vec![tx(600_000, 0), tx(600_000, 0)])
+            .with_gas_limit(1_000_000);
+        assert_eq!(
+            params.validate(&heavy, Some(1_000_000)),
+            Err(BlockLimitError::GasLimitExceeded { used: 1_200_000, limit: 1_000_000 })
+        );
+
+        let large = Block::new(1, "p".to_string(), "v".to_string(), vec![tx(21_000, 4096)]).with_gas_limit(1_000_000);
+        assert!(matches!(params.validate(&large, Some(1_000_000)), Err(BlockLimitError::TooLarge { .. })));
+    }
+}
@@ -0,0 +1,432 @@
+//! ブロックとチェーン
+//!
+//! 確定したブロックを保存し、購読者へ通知します。
+//! マテリアライズドビューやイベント配信はこの通知を起点に更新されます。
+//! 先頭に取り込めなかったブロックは孤立ブロックとして記録します（`orphans`）。
+//! トランザクションはハッシュから検索できるよう、確定時に索引へ追加します（`explorer`）。
+//! 各ブロックの乱数ビーコンは確定時に検証して保存します（`beacon`）。
+//! 空のチェーンは信頼するチェックポイントのブロックから始めることもできます（`checkpoint`）。
+//! ネイティブモジュールは自身の状態による検証を `BlockRule` として確定前の検証に追加します。
+
+pub mod beacon;
+pub mod checkpoint;
+pub mod compact;
+pub mod explorer;
+pub mod header;
+pub mod limits;
+pub mod orphans;
+pub mod relay;
+pub mod replica;
+
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use tokio::sync::{broadcast, RwLock};
+use tracing::info;
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::watchdog::DiskGuard;
+use crate::core::wallet;
+use header::BlockSignature;
+use limits::ConsensusParams;
+
+/// 高さごとのブロックのキープレフィックス
+const HEIGHT_PREFIX: &str = "block/height/";
+/// ハッシュから高さへの索引のキープレフィックス
+const HASH_PREFIX: &str = "block/hash/";
+/// 最新ブロックのキー
+const HEAD_KEY: &[u8] = b"block/head";
+/// 通知チャネルの容量
+const CHANNEL_CAPACITY: usize = 256;
+
+/// ブロック
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Block {
+    pub height: u64,
+    /// ブロックハッシュ（hex）
+    pub hash: String,
+    pub parent_hash: String,
+    /// 生成時刻（UNIX秒）
+    pub timestamp: u64,
+    /// 生成したバリデーター
+    pub validator: String,
+    pub transactions: Vec<PendingTransaction>,
+    /// 実行時に発行されたイベント
+    #[serde(default)]
+    pub events: Vec<Event>,
+    /// ガス上限（上限の導入前のブロックは0）
+    #[serde(default)]
+    pub gas_limit: u64,
+    /// トランザクションのガス上限の合計
+    #[serde(default)]
+    pub gas_used: u64,
+    /// レシートのマークルルート（hex、導入前のブロックは空）
+    #[serde(default)]
+    pub receipts_root: String,
+    /// イベントのアドレスとトピックのブルームフィルター（hex）
+    #[serde(default)]
+    pub logs_bloom: String,
+    /// 基本手数料（無効な場合は0）
+    #[serde(default)]
+    pub base_fee: u64,
+    /// ブロブ手数料（1バイトあたり、無効な場合は0）
+    #[serde(default)]
+    pub blob_fee: u64,
+    /// トランザクションのブロブのバイト数の合計
+    #[serde(default)]
+    pub blob_bytes: u64,
+    /// 乱数ビーコンの VRF 証明（hex、署名と同じ鍵、`header_activation_height` より前のブロックは `None` の場合がある）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub randomness_proof: Option<String>,
+    /// 生成したバリデーターのブロックハッシュへの署名（ハッシュの対象外）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<BlockSignature>,
+}
+
+/// トランザクションの実行時に発行されたイベント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Event {
+    pub tx_hash: String,
+    /// トランザクション内での順番
+    pub index: u32,
+    /// 発行したコントラクトのアドレス
+    pub address: String,
+    /// トピック（hex）
+    pub topics: Vec<String>,
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub data: Vec<u8>,
+}
+
+impl Block {
+    /// 新しいブロックを作成（レシートのルートとブルームフィルターも計算する）
+    pub fn new(height: u64, parent_hash: String, validator: String, transactions: Vec<PendingTransaction>) -> Self {
+        let block = Self {
+            height,
+            hash: String::new(),
+            parent_hash,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            validator,
+            gas_used: Self::total_gas(&transactions),
+            blob_bytes: Self::total_blob_bytes(&transactions),
+            transactions,
+            events: Vec::new(),
+            gas_limit: 0,
+            receipts_root: String::new(),
+            logs_bloom: String::new(),
+            base_fee: 0,
+            blob_fee: 0,
+            randomness_proof: None,
+            signature: None,
+        };
+        block.seal()
+    }
+
+    /// ガス上限を設定してハッシュを計算し直す
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self.hash = self.compute_hash();
+        self.signature = None;
+        self
+    }
+
+    /// トランザクションのガス上限の合計
+    pub fn total_gas(transactions: &[PendingTransaction]) -> u64 {
+        transactions.iter().fold(0u64, |sum, tx| sum.saturating_add(tx.gas_limit))
+    }
+
+    /// JSONでのバイト数
+    pub fn encoded_size(&self) -> usize {
+        serde_json::to_vec(self).map_or(usize::MAX, |bytes| bytes.len())
+    }
+
+    /// 内容からハッシュを計算
+    pub fn compute_hash(&self) -> String {
+        use sha2::{Sha256, Digest};
+        let mut hasher = Sha256::new();
+        hasher.update(self.height.to_be_bytes());
+        hasher.update(self.parent_hash.as_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        hasher.update(self.validator.as_bytes());
+        // 上限の導入前のブロックのハッシュを変えないよう、設定されている場合のみ含める
+        if self.gas_limit > 0 {
+            hasher.update(self.gas_limit.to_be_bytes());
+            hasher.update(self.gas_used.to_be_bytes());
+        }
+        // 同様に、拡張フィールドは導入後のブロックのみ含める
+        if !self.receipts_root.is_empty() {
+            hasher.update(self.receipts_root.as_bytes());
+            hasher.update(self.logs_bloom.as_bytes());
+            hasher.update(self.base_fee.to_be_bytes());
+        }
+        if self.blob_fee > 0 {
+            hasher.update(self.blob_fee.to_be_bytes());
+            hasher.update(self.blob_bytes.to_be_bytes());
+        }
+        if let Some(proof) = &self.randomness_proof {
+            hasher.update(proof.as_bytes());
+        }
+        for tx in &self.transactions {
+            hasher.update(tx.hash.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// 最新ブロック
+#[derive(Debug, Clone)]
+struct Head {
+    height: u64,
+    hash: String,
+    gas_limit: u64,
+    gas_used: u64,
+    base_fee: u64,
+    blob_fee: u64,
+    blob_bytes: u64,
+    /// 乱数ビーコンの値
+    randomness: [u8; 32],
+}
+
+impl Head {
+    fn of(block: &Block, randomness: [u8; 32]) -> Self {
+        Self {
+            height: block.height,
+            hash: block.hash.clone(),
+            gas_limit: block.gas_limit,
+            gas_used: block.gas_used,
+            base_fee: block.base_fee,
+            blob_fee: block.blob_fee,
+            blob_bytes: block.blob_bytes,
+            randomness,
+        }
+    }
+}
+
+/// ブロックの確定前に適用する、ネイティブモジュールの状態による検証
+///
+/// 確定中はチェーンの先頭が書き込みロックされているため、`check_block` から `Chain::head` など
+/// 先頭を読む関数は呼べません。親までのブロックは `Chain::get_block` で読めます。
+#[async_trait]
+pub trait BlockRule: Send + Sync {
+    /// ブロックを先頭に続けて確定できるか検証
+    async fn check_block(&self, chain: &Chain, block: &Block) -> Result<()>;
+}
+
+/// 確定したブロックの列
+pub struct Chain {
+    storage: Arc<dyn StorageEngine>,
+    head: RwLock<Option<Head>>,
+    commits: broadcast::Sender<Arc<Block>>,
+    params: ConsensusParams,
+    /// ディスクの空きが少ない間は確定を拒否する
+    disk: Option<DiskGuard>,
+    /// 署名のないトランザクションを含むブロックを確定する（開発用、`dev.allow_unsigned`）
+    allow_unsigned: bool,
+    /// 確定前に適用する検証（`add_rule`）
+    rules: std::sync::RwLock<Vec<Arc<dyn BlockRule>>>,
+}
+
+impl Chain {
+    /// ストレージから最新ブロックを読み込んでチェーンを開く
+    pub async fn open(storage: Arc<dyn StorageEngine>) -> Result<Self> {
+        let head = match storage.get(HEAD_KEY).await? {
+            Some(bytes) => {
+                let height = u64::from_be_bytes(bytes.try_into().map_err(|_| anyhow!("Corrupted chain head"))?);
+                let block = Self::load(storage.as_ref(), height).await?
+                    .ok_or_else(|| anyhow!("Head block {} is missing", height))?;
+                // 乱数は `backfill_beacon` で読み込む
+                Some(Head::of(&block, [0; 32]))
+            }
+            None => None,
+        };
+        let (commits, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let chain = Self {
+            storage,
+            head: RwLock::new(head),
+            commits,
+            params: ConsensusParams::default(),
+            disk: None,
+            allow_unsigned: false,
+            rules: std::sync::RwLock::new(Vec::new()),
+        };
+        chain.backfill_tx_index().await?;
+        chain.backfill_beacon().await?;
+        Ok(chain)
+    }
+
+    /// コンセンサスパラメーターを設定
+    pub fn with_params(mut self, params: ConsensusParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn params(&self) -> &ConsensusParams {
+        &self.params
+    }
+
+    /// ディスクの監視を設定（停止中の確定はエラーになる）
+    pub fn with_disk_guard(mut self, guard: DiskGuard) -> Self {
+        self.disk = Some(guard);
+        self
+    }
+
+    /// 署名のないトランザクションを確定するか設定（開発用）
+    pub fn with_allow_unsigned(mut self, allow: bool) -> Self {
+        self.allow_unsigned = allow;
+        self
+    }
+
+    /// 確定前に適用する検証を追加（以降に確定するブロックから適用する）
+    pub fn add_rule(&self, rule: Arc<dyn BlockRule>) {
+        self.rules.write().unwrap_or_else(|e| e.into_inner()).push(rule);
+    }
+
+    /// ブロックを確定できるか（ディスクの監視が停止していれば理由を返す）
+    pub fn check_disk(&self) -> Result<()> {
+        self.disk.as_ref().map_or(Ok(()), DiskGuard::check)
+    }
+
+    /// 最新ブロックの（高さ, ハッシュ）
+    pub async fn head(&self) -> Option<(u64, String)> {
+        self.head.read().await.as_ref().map(|h| (h.height, h.hash.clone()))
+    }
+
+    /// 次のブロックのガス上限
+    pub async fn next_gas_limit(&self) -> u64 {
+        let parent = self.head.read().await.as_ref().map(|h| h.gas_limit);
+        self.params.next_gas_limit(parent)
+    }
+
+    /// 次のブロックの基本手数料
+    pub async fn next_base_fee(&self) -> u64 {
+        self.params.next_base_fee(self.head.read().await.as_ref().map(|h| (h.base_fee, h.gas_used, h.gas_limit)))
+    }
+
+    /// 次のブロックのブロブ手数料
+    pub async fn next_blob_fee(&self) -> u64 {
+        self.params.next_blob_fee(self.head.read().await.as_ref().map(|h| (h.blob_fee, h.blob_bytes)))
+    }
+
+    /// 次のブロックを作成
+    pub async fn next_block(&self, validator: String, transactions: Vec<PendingTransaction>) -> Block {
+        let head = self.head.read().await.clone();
+        let gas_limit = self.params.next_gas_limit(head.as_ref().map(|h| h.gas_limit));
+        let base_fee = self.params.next_base_fee(head.as_ref().map(|h| (h.base_fee, h.gas_used, h.gas_limit)));
+        let blob_fee = self.params.next_blob_fee(head.as_ref().map(|h| (h.blob_fee, h.blob_bytes)));
+        let (height, parent_hash) = match head {
+            Some(head) => (head.height + 1, head.hash),
+            None => (0, String::new()),
+        };
+        Block::new(height, parent_hash, validator, transactions)
+            .with_gas_limit(gas_limit)
+            .with_base_fee(base_fee)
+            .with_blob_fee(blob_fee)
+    }
+
+    /// ブロックを確定して購読者へ通知
+    pub async fn commit(&self, block: Block) -> Result<()> {
+        self.check_disk()?;
+        let mut head = self.head.write().await;
+        let (expected_height, expected_parent) = match head.as_ref() {
+            Some(head) => (head.height + 1, head.hash.as_str()),
+            None => (0, ""),
+        };
+        if block.height != expected_height || block.parent_hash != expected_parent {
+            self.track_orphan(&block, head.as_ref()).await;
+            return Err(anyhow!(
+                "Block {} does not extend head (expected height {} with parent {:?})",
+                block.hash, expected_height, expected_parent
+            ));
+        }
+        if block.hash != block.compute_hash() {
+            return Err(anyhow!("Block {} has an invalid hash", block.hash));
+        }
+        self.params.validate(&block, head.as_ref().map(|h| h.gas_limit))
+            .map_err(|e| anyhow!("Block {} violates consensus limits: {}", block.hash, e))?;
+        let base_fee = self.params.next_base_fee(head.as_ref().map(|h| (h.base_fee, h.gas_used, h.gas_limit)));
+        block.verify_header(&self.params, base_fee)
+            .map_err(|e| anyhow!("Block {} has an invalid header: {}", block.hash, e))?;
+        let blob_fee = self.params.next_blob_fee(head.as_ref().map(|h| (h.blob_fee, h.blob_bytes)));
+        block.verify_blobs(&self.params, blob_fee)
+            .map_err(|e| anyhow!("Block {} has invalid blobs: {}", block.hash, e))?;
+        if let Some(tx) = block.transactions.iter().find(|tx| tx.is_expired(block.timestamp)) {
+            return Err(anyhow!("Block {} includes transaction {} that expired before the block", block.hash, tx.hash));
+        }
+        for tx in block.transactions.iter().filter(|tx| tx.signature.is_some() || !self.allow_unsigned) {
+            wallet::verify(tx, self.params.chain_id)
+                .map_err(|e| anyhow!("Block {} includes transaction {} with an invalid signature: {}", block.hash, tx.hash, e))?;
+        }
+        let randomness = block.verified_randomness(&self.params, &head.as_ref().map_or([0; 32], |h| h.randomness))
+            .map_err(|e| anyhow!("Block {} has invalid randomness: {}", block.hash, e))?;
+        let rules = self.rules.read().unwrap_or_else(|e| e.into_inner()).clone();
+        for rule in rules {
+            rule.check_block(self, &block).await
+                .map_err(|e| anyhow!("Block {} is rejected: {:#}", block.hash, e))?;
+        }
+
+        let mut batch = vec![
+            (height_key(block.height), Some(serde_json::to_vec(&block)?)),
+            (format!("{}{}", HASH_PREFIX, block.hash).into_bytes(), Some(block.height.to_be_bytes().to_vec())),
+            (HEAD_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())),
+        ];
+        batch.extend(explorer::tx_index_writes(&block));
+        batch.extend(beacon::randomness_writes(&randomness)?);
+        self.storage.batch_write(batch).await?;
+        *head = Some(Head::of(&block, randomness.value));
+        drop(head);
+
+        info!("Committed block {} ({} txs)", block.height, block.transactions.len());
+        // 購読者がいない場合の送信エラーは無視する
+        let _ = self.commits.send(Arc::new(block));
+        Ok(())
+    }
+
+    /// 高さを指定してブロックを取得
+    pub async fn get_block(&self, height: u64) -> Result<Option<Block>> {
+        Self::load(self.storage.as_ref(), height).await
+    }
+
+    /// ハッシュを指定してブロックを取得
+    pub async fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>> {
+        let key = format!("{}{}", HASH_PREFIX, hash.trim_start_matches("0x").to_lowercase());
+        match self.storage.get(key.as_bytes()).await? {
+            Some(bytes) => {
+                let height = u64::from_be_bytes(bytes.try_into().map_err(|_| anyhow!("Corrupted block index"))?);
+                self.get_block(height).await
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 確定したブロックを購読
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Block>> {
+        self.commits.subscribe()
+    }
+
+    async fn load(storage: &dyn StorageEngine, height: u64) -> Result<Option<Block>> {
+        Ok(match storage.get(&height_key(height)).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        })
+    }
+}
+
+#[cfg(test)]
+impl Chain {
+    /// テスト用の鍵のアドレスを生成者として、乱数の証明を付けて署名した次のブロック
+    pub async fn next_signed_block(&self, transactions: Vec<PendingTransaction>) -> Block {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[42; 32]);
+        let block = self.next_block(wallet::address_of(&key.verifying_key()), transactions).await;
+        self.sign_block(block, &key).await
+    }
+}
+
+fn height_key(height: u64) -> Vec<u8> {
+    // 辞書順と高さの順序を一致させる
+    format!("{}{:020}", HEIGHT_PREFIX, height).into_bytes()
+}
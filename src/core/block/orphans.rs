@@ -0,0 +1,212 @@
+//! 孤立ブロックの記録
+//!
+//! チェーンに取り込まれなかったブロックを理由とともに保存し、フォークの発生率を監視できるようにします。
+//! 確定済みの高さに届いた別のブロックは「フォーク選択で負けた」、確定済みの先頭の次の高さで
+//! 親が先頭と異なるブロックは「親が不正」として記録します。ハッシュが正しくないブロックと、
+//! 先頭から `ORPHAN_WINDOW` より古い高さのブロックは記録しません。
+
+use std::collections::BTreeMap;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use super::{Block, Chain, Head};
+
+/// 孤立ブロックのキープレフィックス（`<反転した高さ>/<ハッシュ>`、高い順に並ぶ）
+const ORPHAN_PREFIX: &str = "block/orphan/";
+/// 理由ごとの孤立ブロックの数のキープレフィックス
+const ORPHAN_COUNT_PREFIX: &str = "block/orphan_count/";
+/// 先頭からこの高さより古いブロックは記録しない
+pub const ORPHAN_WINDOW: u64 = 256;
+/// 1つの高さで記録する孤立ブロックの上限
+pub const MAX_ORPHANS_PER_HEIGHT: usize = 8;
+/// 1ページの上限
+pub const MAX_ORPHAN_PAGE: usize = 1000;
+
+/// 孤立した理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// 確定済みの高さの別のブロック
+    LostForkChoice,
+    /// 親が確定済みの先頭ではない
+    InvalidParent,
+}
+
+impl OrphanReason {
+    pub const ALL: [OrphanReason; 2] = [Self::LostForkChoice, Self::InvalidParent];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::LostForkChoice => "lost_fork_choice",
+            Self::InvalidParent => "invalid_parent",
+        }
+    }
+}
+
+/// 孤立ブロック
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OrphanBlock {
+    pub height: u64,
+    pub hash: String,
+    pub parent_hash: String,
+    pub validator: String,
+    /// 生成時刻（UNIX秒）
+    pub timestamp: u64,
+    pub transaction_count: usize,
+    pub reason: OrphanReason,
+    /// 記録した時刻（UNIX秒）
+    pub orphaned_at: u64,
+}
+
+/// 孤立ブロックの一覧（新しい高さの順）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrphanPage {
+    pub items: Vec<OrphanBlock>,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    pub next_cursor: Option<String>,
+    /// これまでに記録した孤立ブロックの数
+    pub total: u64,
+    /// 理由ごとの数
+    pub by_reason: BTreeMap<OrphanReason, u64>,
+}
+
+/// 先頭に対して孤立したブロックの理由（確定済みの高さは同じブロックかどうかを呼び出し元で確認する）
+pub fn classify(block: &Block, head: Option<(u64, &str)>) -> Option<OrphanReason> {
+    match head {
+        None => (block.height == 0 && !block.parent_hash.is_empty()).then_some(OrphanReason::InvalidParent),
+        Some((height, hash)) if block.height == height + 1 => {
+            (block.parent_hash != hash).then_some(OrphanReason::InvalidParent)
+        }
+        Some((height, _)) if block.height <= height && height - block.height <= ORPHAN_WINDOW => {
+            Some(OrphanReason::LostForkChoice)
+        }
+        _ => None,
+    }
+}
+
+fn orphan_key(height: u64, hash: &str) -> Vec<u8> {
+    format!("{}{}", ORPHAN_PREFIX, height_cursor(height, hash)).into_bytes()
+}
+
+fn height_cursor(height: u64, hash: &str) -> String {
+    format!("{:020}/{}", u64::MAX - height, hash)
+}
+
+fn count_key(reason: OrphanReason) -> Vec<u8> {
+    format!("{}{}", ORPHAN_COUNT_PREFIX, reason.as_str()).into_bytes()
+}
+
+impl Chain {
+    /// 先頭に取り込めなかったブロックが孤立ブロックであれば記録（失敗しても確定の処理は続ける）
+    pub(super) async fn track_orphan(&self, block: &Block, head: Option<&Head>) {
+        let Some(reason) = classify(block, head.map(|h| (h.height, h.hash.as_str()))) else {
+            return;
+        };
+        if let Err(e) = self.record_orphan(block, reason).await {
+            warn!("Failed to record orphaned block {}: {}", block.hash, e);
+        }
+    }
+
+    async fn record_orphan(&self, block: &Block, reason: OrphanReason) -> Result<()> {
+        if block.hash != block.compute_hash() {
+            return Ok(());
+        }
+        if reason == OrphanReason::LostForkChoice
+            && self.get_block(block.height).await?.is_some_and(|committed| committed.hash == block.hash)
+        {
+            return Ok(());
+        }
+        let key = orphan_key(block.height, &block.hash);
+        if self.storage.get(&key).await?.is_some() {
+            return Ok(());
+        }
+        let height_prefix = format!("{}{:020}/", ORPHAN_PREFIX, u64::MAX - block.height).into_bytes();
+        let at_height = self.storage.scan(&height_prefix, MAX_ORPHANS_PER_HEIGHT).await?;
+        if at_height.iter().filter(|(k, _)| k.starts_with(&height_prefix)).count() >= MAX_ORPHANS_PER_HEIGHT {
+            debug!("Not recording orphan {}: height {} already has {} orphans", block.hash, block.height, MAX_ORPHANS_PER_HEIGHT);
+            return Ok(());
+        }
+
+        let orphan = OrphanBlock {
+            height: block.height,
+            hash: block.hash.clone(),
+            parent_hash: block.parent_hash.clone(),
+            validator: block.validator.clone(),
+            timestamp: block.timestamp,
+            transaction_count: block.transactions.len(),
+            reason,
+            orphaned_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        // 確定と同じく先頭のロックを持った状態で呼ばれるため、数の読み書きは競合しない
+        let count = self.orphan_count(reason).await? + 1;
+        self.storage.batch_write(vec![
+            (key, Some(serde_json::to_vec(&orphan)?)),
+            (count_key(reason), Some(count.to_be_bytes().to_vec())),
+        ]).await?;
+        debug!("Recorded orphaned block {} at height {} ({})", block.hash, block.height, reason.as_str());
+        Ok(())
+    }
+
+    async fn orphan_count(&self, reason: OrphanReason) -> Result<u64> {
+        Ok(match self.storage.get(&count_key(reason)).await? {
+            Some(bytes) => u64::from_be_bytes(bytes.try_into().map_err(|_| anyhow!("Corrupted orphan count"))?),
+            None => 0,
+        })
+    }
+
+    /// 孤立ブロックを新しい高さの順に取得
+    pub async fn orphans(&self, cursor: Option<&str>, limit: usize) -> Result<OrphanPage> {
+        let limit = limit.clamp(1, MAX_ORPHAN_PAGE);
+        let start = format!("{}{}", ORPHAN_PREFIX, cursor.unwrap_or_default()).into_bytes();
+        let mut items = Vec::with_capacity(limit);
+        let mut next_cursor = None;
+        for (key, value) in self.storage.scan(&start, limit + 1).await? {
+            if !key.starts_with(ORPHAN_PREFIX.as_bytes()) {
+                break;
+            }
+            let orphan: OrphanBlock = serde_json::from_slice(&value)?;
+            if items.len() == limit {
+                next_cursor = Some(height_cursor(orphan.height, &orphan.hash));
+                break;
+            }
+            items.push(orphan);
+        }
+
+        let mut by_reason = BTreeMap::new();
+        for reason in OrphanReason::ALL {
+            by_reason.insert(reason, self.orphan_count(reason).await?);
+        }
+        Ok(OrphanPage { items, next_cursor, total: by_reason.values().sum(), by_reason })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_orphans() {
+        let block = |height: u64, parent: &str| Block::new(height, parent.to_string(), "v".to_string(), vec![]);
+
+        assert_eq!(classify(&block(0, ""), None), None);
+        assert_eq!(classify(&block(0, "x"), None), Some(OrphanReason::InvalidParent));
+
+        let head = Some((10, "head"));
+        assert_eq!(classify(&block(11, "head"), head), None);
+        assert_eq!(classify(&block(11, "other"), head), Some(OrphanReason::InvalidParent));
+        assert_eq!(classify(&block(10, "parent"), head), Some(OrphanReason::LostForkChoice));
+        // 先の高さは同期が遅れているだけの可能性がある
+        assert_eq!(classify(&block(12, "unknown"), head), None);
+
+        let deep = Some((ORPHAN_WINDOW + 10, "head"));
+        assert_eq!(classify(&block(9, "p"), deep), None);
+        assert_eq!(classify(&block(10, "p"), deep), Some(OrphanReason::LostForkChoice));
+
+        // 高さの高い順に並ぶ
+        assert!(orphan_key(11, "a") < orphan_key(10, "a"));
+    }
+}
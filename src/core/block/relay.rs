@@ -0,0 +1,299 @@
+//! ブロックのゴシップによる中継
+//!
+//! 確定したブロックをピアへゴシップし、受信したブロックをチェーンへ確定します。
+//! コンパクトブロックを有効にした場合は、ヘッダーと短いIDだけを送り、受信側は
+//! メモリプールから復元して、持っていないトランザクションだけを送信元に要求します。
+//! 受信側で確定したブロックも同じ経路でさらにゴシップされます。
+//!
+//! ビーコンチェーンを渡した場合は、ビーコンの提案・投票・確定したブロックも中継します。
+//! 受け付けたメッセージは他のピアへゴシップし、先の高さのメッセージを受信した場合は
+//! 足りないビーコンのブロックを送信元に要求してから処理し直します。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use anyhow::{Result, anyhow};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+use crate::config::RelaySettings;
+use crate::core::coordination::{BeaconChain, BeaconMessage, Received};
+use crate::core::mempool::Mempool;
+use crate::core::network::quic::{Message, MessageHandler, PeerId, QuicNetwork};
+use super::orphans::ORPHAN_WINDOW;
+use super::compact::{BlockTxn, BlockTxnRequest, CompactBlock, Reconstruction};
+use super::{Block, Chain};
+
+/// 1回の追いつきで送信元に要求するビーコンのブロックの上限
+const MAX_BEACON_FETCH: u64 = 256;
+
+/// ブロックの中継
+pub struct BlockRelay {
+    chain: Arc<Chain>,
+    mempool: Arc<RwLock<Mempool>>,
+    network: Arc<QuicNetwork>,
+    compact_blocks: bool,
+    prefill_age: u64,
+    beacon: Option<Arc<BeaconChain>>,
+    /// ビーコンのブロックを要求中か（同時に1つだけ追いつく）
+    beacon_syncing: AtomicBool,
+}
+
+impl BlockRelay {
+    pub fn new(
+        settings: &RelaySettings,
+        chain: Arc<Chain>,
+        mempool: Arc<RwLock<Mempool>>,
+        network: Arc<QuicNetwork>,
+    ) -> Self {
+        Self {
+            chain,
+            mempool,
+            network,
+            compact_blocks: settings.compact_blocks,
+            prefill_age: settings.prefill_age,
+            beacon: None,
+            beacon_syncing: AtomicBool::new(false),
+        }
+    }
+
+    /// ビーコンチェーンの合意形成のメッセージも中継する
+    pub fn with_beacon(mut self, beacon: Arc<BeaconChain>) -> Self {
+        self.beacon = Some(beacon);
+        self
+    }
+
+    /// 受信したメッセージの処理とブロックのゴシップを開始
+    pub async fn spawn(self: Arc<Self>) {
+        let relay = self.clone();
+        let handler: MessageHandler = Arc::new(move |peer, message| {
+            let relay = relay.clone();
+            Box::pin(async move { relay.handle(peer, message).await })
+        });
+        self.network.set_handler(handler).await;
+
+        if let Some(beacon) = &self.beacon {
+            let mut outbound = beacon.subscribe();
+            let network = self.network.clone();
+            tokio::spawn(async move {
+                loop {
+                    match outbound.recv().await {
+                        Ok(message) => match serde_json::to_vec(&message) {
+                            Ok(data) => {
+                                network.gossip(Message::Beacon(data)).await;
+                            }
+                            Err(e) => warn!("Failed to encode beacon message: {}", e),
+                        },
+                        // 取りこぼした投票は次のラウンドでやり直す
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            debug!("Skipped relaying {} beacon messages", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        let mut commits = self.chain.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match commits.recv().await {
+                    Ok(block) => {
+                        if let Err(e) = self.announce(&block).await {
+                            warn!("Failed to relay block {}: {}", block.height, e);
+                        }
+                    }
+                    // 古いブロックを中継しても受信側で捨てられるだけなので読み直さない
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!("Skipped relaying {} blocks", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// 確定したブロックをゴシップ
+    async fn announce(&self, block: &Block) -> Result<()> {
+        let message = if self.compact_blocks {
+            // 生成の直前に受信したトランザクションは他のノードにまだ届いていない可能性が高い
+            let since = block.timestamp.saturating_sub(self.prefill_age);
+            let prefill: Vec<usize> = block.transactions.iter().enumerate()
+                .filter(|(_, tx)| tx.received_at >= since)
+                .map(|(index, _)| index)
+                .collect();
+            let compact = CompactBlock::new(block, &prefill);
+            debug!(
+                "Relaying block {} as {} bytes instead of {}",
+                block.height, compact.encoded_size(), block.encoded_size(),
+            );
+            Message::CompactBlock(serde_json::to_vec(&compact)?)
+        } else {
+            Message::Block(serde_json::to_vec(block)?)
+        };
+        self.network.gossip(message).await;
+        Ok(())
+    }
+
+    /// 受信したメッセージを処理して応答を返す
+    async fn handle(self: Arc<Self>, peer: PeerId, message: Message) -> Vec<u8> {
+        match message {
+            // 足りないトランザクションの要求は送信元の応答を待つため、受信の応答は先に返す
+            Message::CompactBlock(data) => {
+                tokio::spawn(async move {
+                    if let Err(e) = self.receive_compact(&peer, &data).await {
+                        debug!("Dropped compact block from {}: {}", peer, e);
+                    }
+                });
+                vec![]
+            }
+            Message::Block(data) => {
+                match serde_json::from_slice::<Block>(&data) {
+                    Ok(block) => {
+                        if let Err(e) = self.accept(block).await {
+                            debug!("Dropped block from {}: {}", peer, e);
+                        }
+                    }
+                    Err(e) => debug!("Malformed block from {}: {}", peer, e),
+                }
+                vec![]
+            }
+            Message::GetBlockTxn(data) => match self.respond(&data).await {
+                Ok(response) => response,
+                Err(e) => {
+                    debug!("Failed to serve block transactions to {}: {}", peer, e);
+                    vec![]
+                }
+            },
+            Message::Beacon(data) => match self.receive_beacon(peer.clone(), &data).await {
+                Ok(response) => response,
+                Err(e) => {
+                    debug!("Dropped beacon message from {}: {}", peer, e);
+                    vec![]
+                }
+            },
+            _ => vec![],
+        }
+    }
+
+    /// ビーコンのメッセージを処理し、受け付けたものをゴシップする
+    ///
+    /// ブロックの要求には `BeaconMessage::Commit` で応答します（なければ空の応答）。
+    async fn receive_beacon(self: Arc<Self>, peer: PeerId, data: &[u8]) -> Result<Vec<u8>> {
+        let Some(beacon) = &self.beacon else {
+            return Ok(vec![]);
+        };
+        let message: BeaconMessage = serde_json::from_slice(data)?;
+        match beacon.receive(message.clone()).await? {
+            Received::Relay => {
+                self.network.gossip(Message::Beacon(data.to_vec())).await;
+            }
+            Received::Reply(Some(block)) => {
+                return Message::Beacon(serde_json::to_vec(&BeaconMessage::Commit { block })?).encode();
+            }
+            Received::Behind { next } => {
+                if !self.beacon_syncing.swap(true, Ordering::SeqCst) {
+                    let relay = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = relay.fetch_beacon(&peer, next, message).await {
+                            debug!("Failed to fetch beacon blocks from {}: {}", peer, e);
+                        }
+                        relay.beacon_syncing.store(false, Ordering::SeqCst);
+                    });
+                }
+            }
+            Received::Reply(None) | Received::Ignored => {}
+        }
+        Ok(vec![])
+    }
+
+    /// 確定したビーコンのブロックを送信元に順に要求し、追いついたら元のメッセージを処理し直す
+    async fn fetch_beacon(&self, peer: &PeerId, next: u64, pending: BeaconMessage) -> Result<()> {
+        let beacon = self.beacon.as_ref().ok_or_else(|| anyhow!("beacon relay is not enabled"))?;
+        for height in next..next + MAX_BEACON_FETCH {
+            let request = Message::Beacon(serde_json::to_vec(&BeaconMessage::GetBlock { height })?);
+            let response = self.network.request(peer, request).await?;
+            if response.is_empty() {
+                break;
+            }
+            let Message::Beacon(data) = Message::decode(&response)? else {
+                return Err(anyhow!("unexpected response to beacon block request"));
+            };
+            let block @ BeaconMessage::Commit { .. } = serde_json::from_slice::<BeaconMessage>(&data)? else {
+                return Err(anyhow!("unexpected response to beacon block request"));
+            };
+            if beacon.receive(block).await? != Received::Relay {
+                break;
+            }
+            debug!("Fetched beacon block {} from {}", height, peer);
+        }
+        if beacon.receive(pending.clone()).await? == Received::Relay {
+            self.network.gossip(Message::Beacon(serde_json::to_vec(&pending)?)).await;
+        }
+        Ok(())
+    }
+
+    /// コンパクトブロックを復元して確定
+    async fn receive_compact(&self, peer: &PeerId, data: &[u8]) -> Result<()> {
+        let compact: CompactBlock = serde_json::from_slice(data)?;
+        if self.is_known(compact.height, &compact.hash).await {
+            return Ok(());
+        }
+        let height = compact.height;
+        let reconstruction = {
+            let mempool = self.mempool.read().await;
+            compact.reconstruct(mempool.iter())?
+        };
+        let block = match reconstruction {
+            Reconstruction::Complete(block) => block,
+            Reconstruction::Incomplete(partial) => {
+                let request = partial.request();
+                debug!("Fetching {} transactions of block {} from {}", request.indexes.len(), height, peer);
+                let response = self.network
+                    .request(peer, Message::GetBlockTxn(serde_json::to_vec(&request)?))
+                    .await?;
+                let Message::BlockTxn(data) = Message::decode(&response)? else {
+                    return Err(anyhow!("unexpected response to block transaction request"));
+                };
+                partial.fill(serde_json::from_slice::<BlockTxn>(&data)?)?
+            }
+        };
+        self.accept(block).await
+    }
+
+    /// ブロックを確定し、取り込まれたトランザクションをメモリプールから取り除く
+    async fn accept(&self, block: Block) -> Result<()> {
+        if self.is_known(block.height, &block.hash).await {
+            return Ok(());
+        }
+        let height = block.height;
+        let hashes: Vec<String> = block.transactions.iter().map(|tx| tx.hash.clone()).collect();
+        self.chain.commit(block).await?;
+        let mut mempool = self.mempool.write().await;
+        for hash in &hashes {
+            mempool.remove(hash);
+        }
+        info!("Accepted block {} from the network", height);
+        Ok(())
+    }
+
+    /// 確定済みのブロックか
+    ///
+    /// 確定済みの高さの別のブロックは、孤立ブロックとして記録するためにチェーンへ渡します。
+    async fn is_known(&self, height: u64, hash: &str) -> bool {
+        match self.chain.head().await {
+            Some((head, _)) if height <= head => {
+                head - height > ORPHAN_WINDOW
+                    || self.chain.get_block_by_hash(hash).await.ok().flatten().is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// 足りないトランザクションの要求に応える
+    async fn respond(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let request: BlockTxnRequest = serde_json::from_slice(data)?;
+        let block = self.chain.get_block_by_hash(&request.block_hash).await?
+            .ok_or_else(|| anyhow!("unknown block {}", request.block_hash))?;
+        let response = CompactBlock::respond(&block, &request)?;
+        Message::BlockTxn(serde_json::to_vec(&response)?).encode()
+    }
+}
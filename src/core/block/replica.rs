@@ -0,0 +1,142 @@
+//! 読み取り専用レプリカのブロック同期
+//!
+//! 上流のシーケンサー／バリデーターから `GET /api/blocks/{height}` で確定済みのブロックを取得し、
+//! ローカルのチェーンへ順に確定します。レプリカはブロックを生成しないため、
+//! 親ハッシュとブロックハッシュの検証は `Chain::commit` に任せます。
+//!
+//! `replica.trust_checkpoint` を指定した場合、空のチェーンはジェネシスからではなくチェックポイントの
+//! ブロックから始めます（`checkpoint`）。`replica.backfill_history` を有効にすると、それより前の
+//! ブロックを新しいブロックの同期の合間に新しい順に取得します。
+
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use reqwest::StatusCode;
+use tracing::{debug, warn};
+use crate::config::RpcReplicaSettings;
+use super::{Block, Chain};
+use super::beacon::Randomness;
+use super::checkpoint::TrustedCheckpoint;
+
+/// 上流からブロックを取得してチェーンへ確定する
+pub struct BlockFollower {
+    client: reqwest::Client,
+    upstream: String,
+    poll_interval: Duration,
+    batch_size: u64,
+    checkpoint: Option<TrustedCheckpoint>,
+    backfill_history: bool,
+}
+
+impl BlockFollower {
+    pub fn new(settings: &RpcReplicaSettings) -> Result<Self> {
+        if settings.upstream.is_empty() {
+            return Err(anyhow!("replica.upstream is required for the rpc-replica role"));
+        }
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(settings.request_timeout))
+                .build()?,
+            upstream: settings.upstream.trim_end_matches('/').to_string(),
+            poll_interval: Duration::from_millis(settings.poll_interval.max(1)),
+            batch_size: settings.batch_size.max(1),
+            checkpoint: settings.trust_checkpoint.as_deref().map(str::parse).transpose()?,
+            backfill_history: settings.backfill_history,
+        })
+    }
+
+    /// 空のチェーンをチェックポイントから始める（既にブロックがある場合はチェックポイントと矛盾しないことを確認）
+    pub async fn bootstrap(&self, chain: &Chain) -> Result<()> {
+        let Some(checkpoint) = &self.checkpoint else {
+            return Ok(());
+        };
+        if chain.head().await.is_some() {
+            // チェックポイント以前の高さのブロックは、保存していればチェックポイントと一致する必要がある
+            return match chain.get_block(checkpoint.height).await? {
+                Some(block) => checkpoint.verify(&block),
+                None => Ok(()),
+            };
+        }
+        let block = self.fetch(checkpoint.height).await?
+            .ok_or_else(|| anyhow!("upstream has no block at checkpoint height {}", checkpoint.height))?;
+        let randomness = self.fetch_randomness(checkpoint.height).await?;
+        chain.anchor(checkpoint, block, randomness).await
+    }
+
+    /// 上流のブロックを取得（未確定の高さの場合は `None`）
+    async fn fetch(&self, height: u64) -> Result<Option<Block>> {
+        let response = self.client
+            .get(format!("{}/api/blocks/{}", self.upstream, height))
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.json().await?)),
+            status => Err(anyhow!("upstream returned {} for block {}", status, height)),
+        }
+    }
+
+    /// 上流のブロックの乱数
+    async fn fetch_randomness(&self, height: u64) -> Result<Randomness> {
+        let response = self.client
+            .get(format!("{}/api/blocks/{}/randomness", self.upstream, height))
+            .send()
+            .await?;
+        match response.status() {
+            status if status.is_success() => Ok(response.json().await?),
+            status => Err(anyhow!("upstream returned {} for the randomness of block {}", status, height)),
+        }
+    }
+
+    /// チェックポイントより前のブロックを最大 `batch_size` 個、新しい順に追加し、追加した数を返す
+    pub async fn backfill_once(&self, chain: &Chain) -> Result<u64> {
+        let mut added = 0;
+        while added < self.batch_size {
+            let Some(base) = chain.base().await?.filter(|base| *base > 0) else {
+                break;
+            };
+            let block = self.fetch(base - 1).await?
+                .ok_or_else(|| anyhow!("upstream has no block at height {}", base - 1))?;
+            chain.backfill(block).await?;
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// 上流に追いつくまで最大 `batch_size` 個のブロックを確定し、確定した数を返す
+    pub async fn sync_once(&self, chain: &Chain) -> Result<u64> {
+        let mut committed = 0;
+        while committed < self.batch_size {
+            let height = chain.head().await.map_or(0, |(height, _)| height + 1);
+            let Some(block) = self.fetch(height).await? else {
+                break;
+            };
+            chain.commit(block).await?;
+            committed += 1;
+        }
+        Ok(committed)
+    }
+
+    /// 定期的に同期するタスクを起動
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.poll_interval);
+            loop {
+                ticker.tick().await;
+                match self.sync_once(&chain).await {
+                    Ok(0) => {}
+                    Ok(count) => debug!("Synced {} blocks from {}", count, self.upstream),
+                    Err(e) => warn!("Block sync from {} failed: {}", self.upstream, e),
+                }
+                if !self.backfill_history {
+                    continue;
+                }
+                match self.backfill_once(&chain).await {
+                    Ok(0) => {}
+                    Ok(count) => debug!("Backfilled {} blocks from {}", count, self.upstream),
+                    Err(e) => warn!("History backfill from {} failed: {}", self.upstream, e),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,381 @@
+//! 地理的ルーティング
+//!
+//! 読み取りリクエストを、クライアントに最も近いレプリカ／キャッシュノードへ振り分けます。
+//! 主な機能：
+//! - 設定またはGeoIPテーブルによるクライアント位置の解決
+//! - 距離と実測レイテンシーに基づく候補ノードの順位付け
+//! - 連続して失敗したノードを一定時間除外するフェイルオーバー
+//! - リージョンごとのヒット数メトリクス
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::config::GeoSettings;
+use crate::core::transaction::GeoLocation;
+use super::{AccessPattern, NodeId, PlacementPlan};
+
+/// 地球の半径（km）
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// レイテンシーの指数移動平均の係数
+const LATENCY_ALPHA: f64 = 0.3;
+
+/// 地理的ルーティングの設定
+#[derive(Debug, Clone)]
+pub struct GeoConfig {
+    /// これを超える平均レイテンシー（ミリ秒）のノードは後回しにする
+    pub latency_threshold: u32,
+    /// 1リクエストで試行するノード数
+    pub replication_factor: u32,
+    /// ノードを除外するまでの連続失敗回数
+    pub failure_threshold: u32,
+    /// 除外したノードを再び候補にするまでの時間
+    pub cooldown: Duration,
+}
+
+impl Default for GeoConfig {
+    fn default() -> Self {
+        Self {
+            latency_threshold: 200,
+            replication_factor: 3,
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl From<&GeoSettings> for GeoConfig {
+    fn from(settings: &GeoSettings) -> Self {
+        Self {
+            latency_threshold: settings.latency_threshold,
+            replication_factor: settings.max_attempts,
+            failure_threshold: settings.failure_threshold,
+            cooldown: Duration::from_secs(settings.cooldown),
+        }
+    }
+}
+
+/// レプリカノード
+#[derive(Debug, Clone)]
+pub struct ReplicaNode {
+    pub id: NodeId,
+    pub location: GeoLocation,
+    /// 転送先のベースURL（`None` は自ノード）
+    pub endpoint: Option<String>,
+    /// レイテンシーの指数移動平均（ミリ秒）
+    latency_ms: Option<f64>,
+    consecutive_failures: u32,
+    down_until: Option<Instant>,
+}
+
+impl ReplicaNode {
+    fn is_down(&self, now: Instant) -> bool {
+        self.down_until.is_some_and(|until| now < until)
+    }
+}
+
+/// ノードの状態
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NodeStatus {
+    pub id: NodeId,
+    pub region: String,
+    pub endpoint: Option<String>,
+    pub latency_ms: Option<f64>,
+    pub consecutive_failures: u32,
+    pub available: bool,
+}
+
+/// リージョンごとのメトリクス
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RegionMetrics {
+    pub region: String,
+    /// リクエスト数
+    pub requests: u64,
+    /// 同じリージョンのノードで処理した数
+    pub local_hits: u64,
+    /// 他リージョンのノードで処理した数
+    pub remote_hits: u64,
+    /// フェイルオーバーの発生回数
+    pub failovers: u64,
+    /// 全ノードで失敗した数
+    pub errors: u64,
+}
+
+/// 地理的ルーティング
+#[derive(Debug)]
+pub struct GeoRouter {
+    nodes: HashMap<NodeId, ReplicaNode>,
+    metrics: HashMap<String, RegionMetrics>,
+    config: GeoConfig,
+}
+
+impl GeoRouter {
+    pub fn new(config: GeoConfig) -> Self {
+        Self {
+            nodes: HashMap::new(),
+            metrics: HashMap::new(),
+            config,
+        }
+    }
+
+    /// ノードを登録
+    pub fn add_node(&mut self, id: NodeId, location: GeoLocation, endpoint: Option<String>) {
+        self.nodes.insert(id.clone(), ReplicaNode {
+            id,
+            location,
+            endpoint,
+            latency_ms: None,
+            consecutive_failures: 0,
+            down_until: None,
+        });
+    }
+
+    /// ノードを削除
+    pub fn remove_node(&mut self, id: &NodeId) -> Option<ReplicaNode> {
+        self.nodes.remove(id)
+    }
+
+    pub fn node(&self, id: &NodeId) -> Option<&ReplicaNode> {
+        self.nodes.get(id)
+    }
+
+    /// 試行するノードを優先順に返す
+    ///
+    /// 除外中のノードを外し、平均レイテンシーが閾値以下のノードを優先して距離順に並べます。
+    /// 全ノードが除外中の場合は距離順に全ノードを返します。
+    pub fn route(&self, location: &GeoLocation) -> Vec<NodeId> {
+        let now = Instant::now();
+        let threshold = self.config.latency_threshold as f64;
+        let rank = |node: &ReplicaNode| {
+            let slow = node.latency_ms.is_some_and(|l| l > threshold);
+            (slow, distance_km(location, &node.location))
+        };
+
+        let mut candidates: Vec<&ReplicaNode> = self.nodes.values().filter(|n| !n.is_down(now)).collect();
+        if candidates.is_empty() {
+            candidates = self.nodes.values().collect();
+        }
+        candidates.sort_by(|a, b| {
+            let (a_slow, a_dist) = rank(a);
+            let (b_slow, b_dist) = rank(b);
+            a_slow.cmp(&b_slow).then(a_dist.total_cmp(&b_dist)).then(a.id.cmp(&b.id))
+        });
+
+        candidates
+            .into_iter()
+            .take(self.config.replication_factor.max(1) as usize)
+            .map(|n| n.id.clone())
+            .collect()
+    }
+
+    pub fn get_nearest_node(&self, location: &GeoLocation) -> Result<NodeId> {
+        self.route(location)
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No nodes available"))
+    }
+
+    /// 成功したリクエストのレイテンシーを記録
+    pub fn record_success(&mut self, id: &NodeId, latency: Duration) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            let sample = latency.as_secs_f64() * 1000.0;
+            node.latency_ms = Some(match node.latency_ms {
+                Some(avg) => avg + LATENCY_ALPHA * (sample - avg),
+                None => sample,
+            });
+            node.consecutive_failures = 0;
+            node.down_until = None;
+        }
+    }
+
+    /// 失敗を記録し、閾値に達したノードを一定時間除外
+    pub fn record_failure(&mut self, id: &NodeId) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.consecutive_failures += 1;
+            if node.consecutive_failures >= self.config.failure_threshold {
+                node.down_until = Some(Instant::now() + self.config.cooldown);
+            }
+        }
+    }
+
+    /// リクエストの結果をクライアントのリージョンごとに集計
+    pub fn record_request(&mut self, region: &str, served_by: Option<&NodeId>, failovers: u32) {
+        let served_region = served_by
+            .and_then(|id| self.nodes.get(id))
+            .map(|n| n.location.region.clone());
+        let metrics = self.metrics.entry(region.to_string()).or_insert_with(|| RegionMetrics {
+            region: region.to_string(),
+            ..Default::default()
+        });
+
+        metrics.requests += 1;
+        metrics.failovers += failovers as u64;
+        match served_region {
+            Some(r) if r == region => metrics.local_hits += 1,
+            Some(_) => metrics.remote_hits += 1,
+            None => metrics.errors += 1,
+        }
+    }
+
+    /// リージョンごとのメトリクス
+    pub fn metrics(&self) -> Vec<RegionMetrics> {
+        let mut metrics: Vec<_> = self.metrics.values().cloned().collect();
+        metrics.sort_by(|a, b| a.region.cmp(&b.region));
+        metrics
+    }
+
+    /// 全ノードの状態
+    pub fn nodes(&self) -> Vec<NodeStatus> {
+        let now = Instant::now();
+        let mut nodes: Vec<_> = self.nodes.values().map(|n| NodeStatus {
+            id: n.id.clone(),
+            region: n.location.region.clone(),
+            endpoint: n.endpoint.clone(),
+            latency_ms: n.latency_ms,
+            consecutive_failures: n.consecutive_failures,
+            available: !n.is_down(now),
+        }).collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes
+    }
+
+    pub async fn calculate_optimal_placement(&self, _pattern: &AccessPattern) -> Result<PlacementPlan> {
+        // TODO: 実際の最適配置計算
+        Ok(PlacementPlan::default())
+    }
+}
+
+/// 2地点間の大円距離（km）
+pub fn distance_km(a: &GeoLocation, b: &GeoLocation) -> f64 {
+    let (lat1, lat2) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.longitude - a.longitude).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().min(1.0).asin()
+}
+
+/// GeoIPテーブル
+///
+/// `CIDR,リージョン,緯度,経度` 形式の行からなるファイルを読み込み、
+/// 最長一致でクライアントの位置を解決します。
+#[derive(Debug, Default)]
+pub struct GeoIpTable {
+    entries: Vec<(IpAddr, u8, GeoLocation)>,
+}
+
+impl GeoIpTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (n, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [cidr, region, lat, lon] = fields[..] else {
+                return Err(anyhow!("line {}: expected cidr,region,latitude,longitude", n + 1));
+            };
+            let (addr, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+            let addr: IpAddr = addr.parse().map_err(|e| anyhow!("line {}: {}", n + 1, e))?;
+            let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = if prefix.is_empty() { max_prefix } else { prefix.parse()? };
+            if prefix > max_prefix {
+                return Err(anyhow!("line {}: invalid prefix length {}", n + 1, prefix));
+            }
+            entries.push((addr, prefix, GeoLocation::new(lat.parse()?, lon.parse()?, region.to_string())));
+        }
+        // 長いプレフィックスを先に照合する
+        entries.sort_by(|a, b| b.1.cmp(&a.1));
+        Ok(Self { entries })
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<&GeoLocation> {
+        self.entries
+            .iter()
+            .find(|(net, prefix, _)| contains(*net, *prefix, ip))
+            .map(|(_, _, location)| location)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// `net/prefix` が `ip` を含むか
+pub(crate) fn contains(net: IpAddr, prefix: u8, ip: IpAddr) -> bool {
+    let (net, ip, bits) = match (net, ip) {
+        (IpAddr::V4(n), IpAddr::V4(i)) => (u32::from(n) as u128, u32::from(i) as u128, 32),
+        (IpAddr::V6(n), IpAddr::V6(i)) => (u128::from(n), u128::from(i), 128),
+        _ => return false,
+    };
+    if prefix == 0 {
+        return true;
+    }
+    let shift = bits - prefix as u32;
+    (net >> shift) == (ip >> shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokyo() -> GeoLocation {
+        GeoLocation::new(35.68, 139.69, "ap-northeast".to_string())
+    }
+
+    fn router() -> GeoRouter {
+        let mut router = GeoRouter::new(GeoConfig { failure_threshold: 2, ..Default::default() });
+        router.add_node("tokyo".to_string(), tokyo(), None);
+        router.add_node("frankfurt".to_string(), GeoLocation::new(50.11, 8.68, "eu-central".to_string()), Some("http://fra:9071".to_string()));
+        router.add_node("virginia".to_string(), GeoLocation::new(38.03, -78.48, "us-east".to_string()), Some("http://iad:9071".to_string()));
+        router
+    }
+
+    #[test]
+    fn test_route_by_distance_and_failover() {
+        let mut router = router();
+        let paris = GeoLocation::new(48.85, 2.35, "eu-central".to_string());
+        assert_eq!(router.route(&paris), vec!["frankfurt", "virginia", "tokyo"]);
+
+        // 遅いノードは後回し
+        router.record_success(&"frankfurt".to_string(), Duration::from_millis(500));
+        assert_eq!(router.get_nearest_node(&paris).unwrap(), "virginia");
+
+        // 連続して失敗したノードは除外
+        router.record_failure(&"virginia".to_string());
+        router.record_failure(&"virginia".to_string());
+        assert_eq!(router.route(&paris), vec!["tokyo", "frankfurt"]);
+    }
+
+    #[test]
+    fn test_region_metrics() {
+        let mut router = router();
+        router.record_request("ap-northeast", Some(&"tokyo".to_string()), 0);
+        router.record_request("ap-northeast", Some(&"frankfurt".to_string()), 1);
+        router.record_request("ap-northeast", None, 3);
+
+        let metrics = &router.metrics()[0];
+        assert_eq!((metrics.requests, metrics.local_hits, metrics.remote_hits), (3, 1, 1));
+        assert_eq!((metrics.failovers, metrics.errors), (4, 1));
+    }
+
+    #[test]
+    fn test_geoip_longest_prefix() {
+        let table = GeoIpTable::parse(
+            "# cidr,region,lat,lon\n\
+             10.0.0.0/8,us-east,38.03,-78.48\n\
+             10.1.0.0/16,ap-northeast,35.68,139.69\n",
+        ).unwrap();
+        assert_eq!(table.lookup("10.1.2.3".parse().unwrap()).unwrap().region, "ap-northeast");
+        assert_eq!(table.lookup("10.2.0.1".parse().unwrap()).unwrap().region, "us-east");
+        assert!(table.lookup("192.168.0.1".parse().unwrap()).is_none());
+        assert!(GeoIpTable::parse("10.0.0.0/33,x,0,0").is_err());
+    }
+}
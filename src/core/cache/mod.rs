@@ -1,8 +1,15 @@
+pub mod geo;
+pub mod reindex;
+pub mod views;
+
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::collections::HashMap;
-use crate::core::transaction::{Transaction, ShardId, GeoLocation};
+use crate::core::storage::StorageEngine;
+use crate::core::transaction::GeoLocation;
+pub use views::{AddressTx, ArchivePage, ArchiveRange, BalancePoint, BlockReward, ExportedState, MaterializedViews, TokenHolder, TxDirection};
+pub use geo::{GeoConfig, GeoIpTable, GeoRouter, NodeStatus, RegionMetrics};
 
 /// Noriaベースのグローバルキャッシュ管理
 pub struct CacheManager {
@@ -111,7 +118,7 @@ impl CacheNode {
         Ok(())
     }
 
-    pub async fn apply_configuration(&mut self, config: NodeConfig) -> Result<()> {
+    pub async fn apply_configuration(&mut self, _config: NodeConfig) -> Result<()> {
         // TODO: 実際の設定適用
         Ok(())
     }
@@ -131,12 +138,12 @@ impl FlowManager {
         }
     }
 
-    pub async fn handle_cache_miss(&mut self, key: &[u8], node_id: &NodeId) -> Result<()> {
+    pub async fn handle_cache_miss(&mut self, _key: &[u8], _node_id: &NodeId) -> Result<()> {
         // TODO: キャッシュミス時のフロー更新
         Ok(())
     }
 
-    pub async fn create_update_plan(&self, key: &[u8], value: &[u8], location: &GeoLocation) -> Result<UpdatePlan> {
+    pub async fn create_update_plan(&self, _key: &[u8], _value: &[u8], _location: &GeoLocation) -> Result<UpdatePlan> {
         // TODO: 実際の更新計画作成
         Ok(UpdatePlan::default())
     }
@@ -147,58 +154,86 @@ impl FlowManager {
     }
 }
 
-/// 地理的ルーティング
-pub struct GeoRouter {
-    node_locations: HashMap<NodeId, GeoLocation>,
-    config: GeoConfig,
+/// Noriaストレージ
+///
+/// マテリアライズドビューの行を保持します。書き込みはバッファーに溜め、
+/// `take_pending` で取り出してストレージへ一括で反映します。
+/// 読み取りはバッファーを優先し、なければストレージを参照します。
+pub struct NoriaStorage {
+    prefix: String,
+    storage: Arc<dyn StorageEngine>,
+    /// 未反映の行（`None` は削除）
+    pending: HashMap<Vec<u8>, Option<Vec<u8>>>,
 }
 
-impl GeoRouter {
-    pub fn new(config: GeoConfig) -> Self {
+impl NoriaStorage {
+    pub fn new(prefix: impl Into<String>, storage: Arc<dyn StorageEngine>) -> Self {
         Self {
-            node_locations: HashMap::new(),
-            config,
+            prefix: prefix.into(),
+            storage,
+            pending: HashMap::new(),
         }
     }
 
-    pub fn get_nearest_node(&self, location: &GeoLocation) -> Result<NodeId> {
-        // TODO: 実際の最寄りノード計算
-        self.node_locations.keys().next()
-            .ok_or_else(|| anyhow::anyhow!("No nodes available"))
-            .map(|id| id.clone())
-    }
-
-    pub async fn calculate_optimal_placement(&self, pattern: &AccessPattern) -> Result<PlacementPlan> {
-        // TODO: 実際の最適配置計算
-        Ok(PlacementPlan::default())
-    }
-}
-
-// Noriaストレージ
-pub struct NoriaStorage {
-    // TODO: 実際のNoria実装
-}
-
-impl NoriaStorage {
     pub async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        // TODO: 実際のNoria get実装
-        Ok(None)
+        if let Some(value) = self.pending.get(key) {
+            return Ok(value.clone());
+        }
+        self.storage.get(&self.full_key(key)).await
     }
 
     pub async fn insert(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
-        // TODO: 実際のNoria insert実装
+        self.pending.insert(key.to_vec(), Some(value.to_vec()));
         Ok(())
     }
 
     pub async fn update(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
-        // TODO: 実際のNoria update実装
-        Ok(())
+        self.insert(key, value).await
     }
 
     pub async fn delete(&mut self, key: &[u8]) -> Result<()> {
-        // TODO: 実際のNoria delete実装
+        self.pending.insert(key.to_vec(), None);
         Ok(())
     }
+
+    /// `prefix` で始まる反映済みの行を取得
+    pub async fn scan_prefix(&self, prefix: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let start = self.full_key(prefix);
+        let offset = self.prefix.len();
+        Ok(self.storage.scan(&start, limit).await?
+            .into_iter()
+            .take_while(|(key, _)| key.starts_with(&start))
+            .map(|(key, value)| (key[offset..].to_vec(), value))
+            .collect())
+    }
+
+    /// `prefix` で始まる反映済みの行を `start` 以降から取得
+    pub async fn scan_range(&self, prefix: &[u8], start: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let bound = self.full_key(prefix);
+        let offset = self.prefix.len();
+        Ok(self.storage.scan(&self.full_key(start), limit).await?
+            .into_iter()
+            .take_while(|(key, _)| key.starts_with(&bound))
+            .map(|(key, value)| (key[offset..].to_vec(), value))
+            .collect())
+    }
+
+    /// 未反映の行をストレージのキーで取り出す
+    pub fn take_pending(&mut self) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        let pending = std::mem::take(&mut self.pending);
+        pending.into_iter().map(|(key, value)| (self.full_key(&key), value)).collect()
+    }
+
+    /// 未反映の行を破棄
+    pub fn discard_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    fn full_key(&self, key: &[u8]) -> Vec<u8> {
+        let mut full = self.prefix.as_bytes().to_vec();
+        full.extend_from_slice(key);
+        full
+    }
 }
 
 // 補助的な型定義
@@ -216,12 +251,6 @@ pub struct FlowConfig {
     pub flow_timeout: std::time::Duration,
 }
 
-#[derive(Debug)]
-pub struct GeoConfig {
-    pub latency_threshold: u32,
-    pub replication_factor: u32,
-}
-
 #[derive(Debug)]
 pub enum CacheOperation {
     Insert { key: Vec<u8>, value: Vec<u8> },
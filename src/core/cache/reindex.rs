@@ -0,0 +1,150 @@
+//! 保存しているブロックからの索引の再構築（`rustorium index rebuild`）
+//!
+//! 索引の形式の変更や破損の後に、ブロックから索引を導出し直します。ノードを停止した状態で実行します。
+//! - `from = 0`: 全てのビューを削除し、全ブロックを反映し直す（残高・ノンス・トークンの保有量を含む）
+//! - `from > 0`: その高さ以降のブロックの履歴の索引（アドレスの全トランザクション・メモ・報酬・
+//!   トランザクションハッシュの索引）を作り直す。前のブロックに依存する残高などは変更しない
+//!
+//! `rate` を指定すると1秒あたりのブロック数を制限します（同じディスクを使う他のプロセスへの影響を抑える）。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use tracing::info;
+use crate::core::block::Chain;
+use super::MaterializedViews;
+
+/// 進捗を報告する間隔
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 再構築の進捗
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    /// 最初の高さ
+    pub first: u64,
+    /// 最後の高さ
+    pub last: u64,
+    /// 処理したブロック数
+    pub done: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn total(&self) -> u64 {
+        self.last + 1 - self.first
+    }
+
+    /// 処理した割合（%）
+    pub fn percent(&self) -> f64 {
+        self.done as f64 * 100.0 / self.total() as f64
+    }
+
+    /// 1秒あたりのブロック数
+    pub fn rate(&self) -> f64 {
+        self.done as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// 残りの所要時間の見込み
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate();
+        (self.done > 0 && rate > 0.0).then(|| Duration::from_secs_f64((self.total() - self.done) as f64 / rate))
+    }
+}
+
+/// 索引の再構築
+pub struct Reindexer {
+    chain: Arc<Chain>,
+    views: Arc<MaterializedViews>,
+    /// 1秒あたりの最大ブロック数（`None` は無制限）
+    rate: Option<u32>,
+}
+
+impl Reindexer {
+    pub fn new(chain: Arc<Chain>, views: Arc<MaterializedViews>, rate: Option<u32>) -> Self {
+        Self { chain, views, rate: rate.filter(|rate| *rate > 0) }
+    }
+
+    /// `from` 以降のブロックから索引を作り直し、`REPORT_INTERVAL` ごとと最後に進捗を渡す
+    pub async fn run(&self, from: u64, mut report: impl FnMut(&Progress)) -> Result<Progress> {
+        let (head, _) = self.chain.head().await
+            .ok_or_else(|| anyhow!("Chain has no blocks to index"))?;
+        let full = from == 0;
+        let (first, last) = if full {
+            (self.views.reset(&self.chain).await?, head)
+        } else {
+            let applied = self.views.applied_height().await?
+                .ok_or_else(|| anyhow!("Views are empty; rebuild from 0 instead"))?;
+            if from > applied {
+                return Err(anyhow!("Views have only applied blocks up to {}", applied));
+            }
+            (from.max(self.chain.base().await?.unwrap_or(0)), applied.min(head))
+        };
+        info!("Rebuilding {} of blocks {} to {}", if full { "all views" } else { "history indexes" }, first, last);
+
+        let started = Instant::now();
+        let mut progress = Progress { first, last, done: 0, elapsed: Duration::ZERO };
+        let mut reported = started;
+        for height in first..=last {
+            let block = self.chain.get_block(height).await?
+                .ok_or_else(|| anyhow!("Block {} is missing", height))?;
+            if full {
+                self.views.apply_block(&block).await?;
+            } else {
+                self.views.reindex_block(&block).await?;
+            }
+            self.chain.reindex_transactions(&block).await?;
+            progress.done += 1;
+
+            if let Some(rate) = self.rate {
+                let due = Duration::from_secs_f64(progress.done as f64 / rate as f64);
+                if let Some(wait) = due.checked_sub(started.elapsed()) {
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            progress.elapsed = started.elapsed();
+            if reported.elapsed() >= REPORT_INTERVAL {
+                reported = Instant::now();
+                report(&progress);
+            }
+        }
+        progress.elapsed = started.elapsed();
+        report(&progress);
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::core::cache::ExportedState;
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::{StorageEngine, redb_storage::RedbStorage};
+
+    #[tokio::test]
+    async fn test_rebuild_restores_views() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let chain = Arc::new(Chain::open(storage.clone()).await.unwrap().with_allow_unsigned(true));
+        let genesis = ExportedState { balances: BTreeMap::from([("aa".to_string(), 30)]), ..Default::default() };
+        let views = Arc::new(MaterializedViews::new(storage.clone()).with_genesis(genesis));
+        for nonce in 0..3 {
+            let tx = PendingTransaction::test_transfer("aa", "bb", 10, nonce);
+            chain.commit(chain.next_signed_block(vec![tx]).await).await.unwrap();
+        }
+        views.catch_up(&chain).await.unwrap();
+        let history = views.transactions("bb", None, 10).await.unwrap().items.len();
+
+        // ビューを壊しても、全ブロックから作り直せば同じ値になる
+        storage.delete(b"view/balance/bb").await.unwrap();
+        let mut reports = 0;
+        let progress = Reindexer::new(chain.clone(), views.clone(), None).run(0, |_| reports += 1).await.unwrap();
+        assert_eq!((progress.done, progress.total(), reports), (3, 3, 1));
+        assert_eq!(views.balance("bb").await.unwrap(), 30);
+
+        // 途中からは履歴の索引のみ作り直す
+        let progress = Reindexer::new(chain.clone(), views.clone(), Some(1000)).run(2, |_| {}).await.unwrap();
+        assert_eq!((progress.first, progress.done), (2, 1));
+        assert_eq!(views.transactions("bb", None, 10).await.unwrap().items.len(), history);
+        assert!(Reindexer::new(chain, views, None).run(3, |_| {}).await.is_err());
+    }
+}
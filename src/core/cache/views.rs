@@ -0,0 +1,924 @@
+//! マテリアライズドビュー
+//!
+//! よく使われるクエリの結果をブロック確定ごとに差分で更新し、
+//! APIがリクエストのたびに集計し直さなくて済むようにします。
+//! 主な機能：
+//! - アドレスごとの残高（HTLC の受け取りと返金の払い出しを含む）
+//! - アドレスごとのトランザクションの索引（新しい順、カーソルで取得）
+//! - トークンごとの保有者と保有量（ERC-20 `transfer` 呼び出しから算出）
+//! - アーカイブ：アドレスごとの全トランザクションと残高の推移（時刻範囲とカーソルで取得）
+//! - 受信者とメモのタグごとのトランザクション（取引所の入金タグなど）
+//! - アーカイブ：ブロックを生成したバリデーターごとの手数料の報酬
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::htlc::{HtlcLock, HtlcOp, HTLC_ADDRESS};
+use crate::core::memo::Memo;
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use super::NoriaStorage;
+
+/// 全てのビューのキープレフィックス
+const VIEW_PREFIX: &[u8] = b"view/";
+/// ビューの削除で1回に削除する件数
+const RESET_BATCH: usize = 1000;
+/// 全ての行を読む1回の件数
+const SCAN_BATCH: usize = 1000;
+/// 反映済みの高さのキー
+const HEIGHT_KEY: &[u8] = b"view/height";
+/// トランザクションの索引に反映済みの高さのキー（索引の導入前のビューは追いつくまで遅れる）
+const INDEX_HEIGHT_KEY: &[u8] = b"view/index_height";
+/// ERC-20 `transfer(address,uint256)` のセレクター
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// 保有者一覧で読み込む最大件数
+const MAX_HOLDERS_SCAN: usize = 100_000;
+/// アーカイブとトランザクションの索引の1ページの最大件数
+pub const MAX_ARCHIVE_PAGE: usize = 1000;
+
+/// 送受信の方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TxDirection {
+    In,
+    Out,
+}
+
+/// アドレスのトランザクション履歴の要素
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AddressTx {
+    pub hash: String,
+    pub height: u64,
+    pub direction: TxDirection,
+    /// 相手のアドレス
+    pub counterparty: String,
+    pub value: u64,
+    pub timestamp: u64,
+    /// `data` のメモ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<Memo>,
+    /// 送信者が支払った手数料（送信したトランザクションのみ、導入前に反映したものは `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+}
+
+/// ブロックの生成で受け取った手数料
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BlockReward {
+    pub height: u64,
+    pub hash: String,
+    pub timestamp: u64,
+    /// ブロックのトランザクションの手数料の合計
+    pub fees: u64,
+    pub transactions: usize,
+}
+
+/// トークンの保有者
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TokenHolder {
+    pub address: String,
+    pub balance: u64,
+}
+
+/// ブロック確定後の残高
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BalancePoint {
+    pub height: u64,
+    pub timestamp: u64,
+    pub balance: u64,
+}
+
+/// アーカイブの時刻範囲（UNIX秒、両端を含む）
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct ArchiveRange {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+/// アーカイブ（古い順）とトランザクションの索引（新しい順）のページ
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ArchivePage<T> {
+    pub items: Vec<T>,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    pub next_cursor: Option<String>,
+}
+
+/// ビューから書き出したアカウントの状態
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportedState {
+    /// 残高（0 のアドレスは含まない）
+    pub balances: BTreeMap<String, u64>,
+    /// 送信者ごとの次のノンス
+    pub nonces: BTreeMap<String, u64>,
+    /// トークンごとの保有者と保有量
+    pub tokens: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+struct Tables {
+    balances: NoriaStorage,
+    /// アドレスごとのトランザクションの索引（`<address>/<index_key>`、新しい順に並ぶ）
+    tx_index: NoriaStorage,
+    holders: NoriaStorage,
+    /// アドレスごとの全トランザクション（`<address>/<archive_key>`）
+    archive_txs: NoriaStorage,
+    /// アドレスごとの残高の推移（`<address>/<archive_key>`）
+    balance_history: NoriaStorage,
+    /// 送信者ごとの次のノンス
+    nonces: NoriaStorage,
+    /// 受信者とタグごとのメモ付きトランザクション（`<address>/<tag>/<archive_key>`）
+    memos: NoriaStorage,
+    /// バリデーターごとのブロックの報酬（`<validator>/<archive_key>`）
+    rewards: NoriaStorage,
+    /// 決済されていない HTLC のロック（`<id>`）
+    htlc: NoriaStorage,
+}
+
+/// マテリアライズドビュー
+pub struct MaterializedViews {
+    storage: Arc<dyn StorageEngine>,
+    tables: Mutex<Tables>,
+    /// ブロック0の前の状態（`consensus.genesis`、ブロック0を反映する際に書き込む）
+    genesis: ExportedState,
+}
+
+impl MaterializedViews {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        Self {
+            tables: Mutex::new(Tables {
+                balances: NoriaStorage::new("view/balance/", storage.clone()),
+                tx_index: NoriaStorage::new("view/txindex/", storage.clone()),
+                holders: NoriaStorage::new("view/holders/", storage.clone()),
+                archive_txs: NoriaStorage::new("view/archive/txs/", storage.clone()),
+                balance_history: NoriaStorage::new("view/archive/balance/", storage.clone()),
+                nonces: NoriaStorage::new("view/nonce/", storage.clone()),
+                memos: NoriaStorage::new("view/memo/", storage.clone()),
+                rewards: NoriaStorage::new("view/archive/rewards/", storage.clone()),
+                htlc: NoriaStorage::new("view/htlc/", storage.clone()),
+            }),
+            storage,
+            genesis: ExportedState::default(),
+        }
+    }
+
+    /// ブロック0の前の残高・ノンス・トークンの保有量を設定
+    ///
+    /// 作り直す場合もブロック0から反映するため、ビューには書き込まず保持します。
+    pub fn with_genesis(mut self, genesis: ExportedState) -> Self {
+        self.genesis = genesis;
+        self
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// トランザクションの索引に反映済みのブロックの高さ
+    pub async fn indexed_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(INDEX_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 確定したブロックをビューに反映
+    ///
+    /// 反映済みのブロックは無視するため、同じブロックを複数回渡しても安全です。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let mut tables = self.tables.lock().await;
+        let expected = self.applied_height().await?.map_or(0, |h| h + 1);
+        if block.height < expected {
+            return Ok(());
+        }
+        if block.height > expected {
+            return Err(anyhow::anyhow!("views are at height {}, cannot apply block {}", expected, block.height));
+        }
+
+        if let Err(e) = self.apply_transactions(&mut tables, block).await {
+            tables.balances.discard_pending();
+            tables.tx_index.discard_pending();
+            tables.holders.discard_pending();
+            tables.archive_txs.discard_pending();
+            tables.balance_history.discard_pending();
+            tables.nonces.discard_pending();
+            tables.memos.discard_pending();
+            tables.rewards.discard_pending();
+            tables.htlc.discard_pending();
+            return Err(e);
+        }
+
+        // ビューの更新と高さの記録を1回のバッチで反映する
+        let mut batch = tables.balances.take_pending();
+        batch.extend(tables.tx_index.take_pending());
+        batch.extend(tables.holders.take_pending());
+        batch.extend(tables.archive_txs.take_pending());
+        batch.extend(tables.balance_history.take_pending());
+        batch.extend(tables.nonces.take_pending());
+        batch.extend(tables.memos.take_pending());
+        batch.extend(tables.rewards.take_pending());
+        batch.extend(tables.htlc.take_pending());
+        batch.push((HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        // 索引が遅れている場合は、追いつくまで索引の高さを進めない
+        if self.indexed_height().await?.map_or(0, |h| h + 1) == block.height {
+            batch.push((INDEX_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        }
+        self.storage.batch_write(batch).await
+    }
+
+    /// 索引の導入前に反映したブロックをトランザクションの索引に追加する
+    async fn backfill_index(&self, chain: &Chain) -> Result<()> {
+        let Some(applied) = self.applied_height().await? else {
+            return Ok(());
+        };
+        let start = self.indexed_height().await?.map_or(0, |h| h + 1);
+        if start > applied {
+            return Ok(());
+        }
+        info!("Indexing transactions of blocks {} to {}", start, applied);
+        let mut tables = self.tables.lock().await;
+        for height in start..=applied {
+            let Some(block) = chain.get_block(height).await? else {
+                continue;
+            };
+            for (index, tx) in block.transactions.iter().enumerate() {
+                if let Err(e) = index_transaction(&mut tables.tx_index, &block, index, tx).await {
+                    tables.tx_index.discard_pending();
+                    return Err(e);
+                }
+            }
+            let mut batch = tables.tx_index.take_pending();
+            batch.push((INDEX_HEIGHT_KEY.to_vec(), Some(height.to_be_bytes().to_vec())));
+            self.storage.batch_write(batch).await?;
+        }
+        Ok(())
+    }
+
+    async fn apply_transactions(&self, tables: &mut Tables, block: &Block) -> Result<()> {
+        let mut touched = BTreeSet::new();
+        if block.height == 0 {
+            self.apply_genesis(tables, &mut touched).await?;
+        }
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let from = normalize_address(&tx.from);
+            let to = normalize_address(&tx.to);
+
+            let next_nonce = tx.nonce.saturating_add(1);
+            if read_u64(&tables.nonces, from.as_bytes()).await? < next_nonce {
+                tables.nonces.update(from.as_bytes(), &next_nonce.to_be_bytes()).await?;
+            }
+
+            // 残高を超える送金は送金額を移さない（ブロックの生成と受付で除くため、通常は起きない）。
+            // HTLC とトークンの効果は他のモジュールと同じく送金額とは独立に反映する
+            if tx.value > 0 {
+                if transfer(tables, &from, &to, tx.value).await? {
+                    touched.insert(from.clone());
+                    touched.insert(to.clone());
+                } else {
+                    warn!("Transaction {} in block {} overdraws {}; its value was not transferred", tx.hash, block.height, from);
+                }
+            }
+
+            if let Some((payee, amount)) = settle_htlc(&mut tables.htlc, block, tx).await? {
+                if transfer(tables, HTLC_ADDRESS, &payee, amount).await? {
+                    touched.insert(HTLC_ADDRESS.to_string());
+                    touched.insert(payee);
+                }
+            }
+
+            if let Some((recipient, amount)) = decode_token_transfer(tx) {
+                if !transfer_tokens(tables, &to, &from, &recipient, amount).await? {
+                    warn!("Transaction {} in block {} overdraws {} tokens of {}; they were not transferred", tx.hash, block.height, from, to);
+                }
+            }
+        }
+
+        index_history(tables, block).await?;
+
+        for address in touched {
+            let point = BalancePoint {
+                height: block.height,
+                timestamp: block.timestamp,
+                balance: read_u64(&tables.balances, address.as_bytes()).await?,
+            };
+            let key = format!("{}/{}", address, archive_key(block.timestamp, block.height));
+            tables.balance_history.insert(key.as_bytes(), &serde_json::to_vec(&point)?).await?;
+        }
+        Ok(())
+    }
+
+    /// ブロック0の前の状態を書き込む
+    async fn apply_genesis(&self, tables: &mut Tables, touched: &mut BTreeSet<String>) -> Result<()> {
+        for (address, balance) in &self.genesis.balances {
+            let address = normalize_address(address);
+            tables.balances.update(address.as_bytes(), &balance.to_be_bytes()).await?;
+            touched.insert(address);
+        }
+        for (address, nonce) in &self.genesis.nonces {
+            tables.nonces.update(normalize_address(address).as_bytes(), &nonce.to_be_bytes()).await?;
+        }
+        for (token, holders) in &self.genesis.tokens {
+            for (holder, balance) in holders {
+                tables.holders.update(&holder_key(&normalize_address(token), &normalize_address(holder)), &balance.to_be_bytes()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 送金額の合計が確定済みの残高を超えるトランザクション（と同じ送信者の後続のもの）をブロックから除く
+    pub async fn retain_funded(&self, txs: &mut Vec<PendingTransaction>) {
+        let mut available: HashMap<String, u64> = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            let from = normalize_address(&tx.from);
+            if dropped.contains(&from) {
+                continue;
+            }
+            let balance = match available.get(&from) {
+                Some(balance) => Ok(*balance),
+                None => self.balance(&from).await,
+            };
+            match balance {
+                Ok(balance) if tx.value <= balance => {
+                    available.insert(from, balance - tx.value);
+                    kept.push(tx);
+                }
+                Ok(balance) => {
+                    debug!("Leaving {} out of the block: value {} exceeds balance {}", tx.hash, tx.value, balance);
+                    dropped.insert(from);
+                }
+                Err(e) => {
+                    warn!("Leaving {} out of the block: balance unavailable: {}", tx.hash, e);
+                    dropped.insert(from);
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// ビューを最新のブロックまで追いつかせる
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        self.backfill_index(chain).await?;
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        self.catch_up_to(chain, head).await
+    }
+
+    /// 高さ `height` までの確定したブロックをビューに反映する
+    ///
+    /// チェーンの先頭を読まないため、確定中の検証（`BlockRule`）からも呼べます。
+    pub async fn catch_up_to(&self, chain: &Chain, height: u64) -> Result<()> {
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => self.start_at_base(chain).await?,
+        };
+        for height in start..=height {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 空のビューを最初に反映するブロックの高さ
+    ///
+    /// チェックポイントから起動したチェーンは、チェックポイントのブロックから反映します。
+    async fn start_at_base(&self, chain: &Chain) -> Result<u64> {
+        match chain.base().await? {
+            Some(base) if base > 0 => {
+                let before = (base - 1).to_be_bytes().to_vec();
+                self.storage.batch_write(vec![
+                    (HEIGHT_KEY.to_vec(), Some(before.clone())),
+                    (INDEX_HEIGHT_KEY.to_vec(), Some(before)),
+                ]).await?;
+                Ok(base)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    /// 全てのビューを削除し、最初に反映するブロックの高さを返す（ノードを停止した状態で使う）
+    pub async fn reset(&self, chain: &Chain) -> Result<u64> {
+        let _tables = self.tables.lock().await;
+        loop {
+            let keys: Vec<_> = self.storage.scan(VIEW_PREFIX, RESET_BATCH).await?
+                .into_iter()
+                .map(|(key, _)| key)
+                .take_while(|key| key.starts_with(VIEW_PREFIX))
+                .collect();
+            if keys.is_empty() {
+                break;
+            }
+            self.storage.batch_write(keys.into_iter().map(|key| (key, None)).collect()).await?;
+        }
+        self.start_at_base(chain).await
+    }
+
+    /// 反映済みのブロックの履歴の索引を作り直す
+    ///
+    /// 残高・ノンス・トークンの保有量・HTLC のロックなど前のブロックに依存する値は変更しません。
+    pub async fn reindex_block(&self, block: &Block) -> Result<()> {
+        let mut tables = self.tables.lock().await;
+        if self.applied_height().await?.is_none_or(|applied| block.height > applied) {
+            return Err(anyhow!("views have not applied block {} yet", block.height));
+        }
+        if let Err(e) = index_history(&mut tables, block).await {
+            tables.archive_txs.discard_pending();
+            tables.memos.discard_pending();
+            tables.tx_index.discard_pending();
+            tables.rewards.discard_pending();
+            return Err(e);
+        }
+        let mut batch = tables.archive_txs.take_pending();
+        batch.extend(tables.memos.take_pending());
+        batch.extend(tables.tx_index.take_pending());
+        batch.extend(tables.rewards.take_pending());
+        self.storage.batch_write(batch).await
+    }
+
+    /// ブロックの確定を購読してビューを更新し続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up materialized views: {}", e);
+            }
+            info!("Materialized views are following the chain");
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    // 取りこぼした場合はストレージから読み直す
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to update materialized views: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up materialized views: {}", e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// 全てのアドレスの残高・ノンスとトークンの保有量（`system export-genesis`）
+    pub async fn export_state(&self) -> Result<ExportedState> {
+        let tables = self.tables.lock().await;
+        let mut state = ExportedState::default();
+        for (key, value) in scan_all(&tables.balances).await? {
+            let balance = decode_u64(&value)?;
+            if balance > 0 {
+                state.balances.insert(String::from_utf8(key)?, balance);
+            }
+        }
+        for (key, value) in scan_all(&tables.nonces).await? {
+            state.nonces.insert(String::from_utf8(key)?, decode_u64(&value)?);
+        }
+        for (key, value) in scan_all(&tables.holders).await? {
+            let key = String::from_utf8(key)?;
+            let (token, holder) = key.split_once('/').ok_or_else(|| anyhow!("Corrupted token holder key {}", key))?;
+            state.tokens.entry(token.to_string()).or_default().insert(holder.to_string(), decode_u64(&value)?);
+        }
+        Ok(state)
+    }
+
+    /// アドレスの残高
+    pub async fn balance(&self, address: &str) -> Result<u64> {
+        let tables = self.tables.lock().await;
+        read_u64(&tables.balances, normalize_address(address).as_bytes()).await
+    }
+
+    /// アドレスが次に使うノンス（確定済みのトランザクションのみで判断）
+    pub async fn next_nonce(&self, address: &str) -> Result<u64> {
+        let tables = self.tables.lock().await;
+        read_u64(&tables.nonces, normalize_address(address).as_bytes()).await
+    }
+
+    /// アドレスが送受信したトランザクション（新しい順）
+    ///
+    /// カーソルは前のページの `next_cursor` で、件数の上限なく全履歴を辿れます。
+    pub async fn transactions(&self, address: &str, cursor: Option<&str>, limit: usize) -> Result<ArchivePage<AddressTx>> {
+        let tables = self.tables.lock().await;
+        let prefix = format!("{}/", normalize_address(address));
+        let start = match cursor {
+            Some(cursor) => format!("{}{}\0", prefix, cursor),
+            None => prefix.clone(),
+        };
+        let limit = limit.clamp(1, MAX_ARCHIVE_PAGE);
+
+        let mut rows = tables.tx_index.scan_range(prefix.as_bytes(), start.as_bytes(), limit + 1).await?;
+        let more = rows.len() > limit;
+        rows.truncate(limit);
+        let next_cursor = match rows.last() {
+            Some((key, _)) if more => Some(String::from_utf8(key[prefix.len()..].to_vec())?),
+            _ => None,
+        };
+        let items = rows.iter()
+            .map(|(_, value)| serde_json::from_slice(value))
+            .collect::<serde_json::Result<Vec<AddressTx>>>()?;
+        Ok(ArchivePage { items, next_cursor })
+    }
+
+    /// トークンの保有者（保有量の多い順）
+    pub async fn token_holders(&self, token: &str, limit: usize) -> Result<Vec<TokenHolder>> {
+        let tables = self.tables.lock().await;
+        let prefix = holder_key(&normalize_address(token), "");
+        let mut holders: Vec<TokenHolder> = tables.holders.scan_prefix(&prefix, MAX_HOLDERS_SCAN).await?
+            .into_iter()
+            .filter_map(|(key, value)| Some(TokenHolder {
+                address: String::from_utf8(key[prefix.len()..].to_vec()).ok()?,
+                balance: u64::from_be_bytes(value.try_into().ok()?),
+            }))
+            .collect();
+        holders.sort_by(|a, b| b.balance.cmp(&a.balance).then_with(|| a.address.cmp(&b.address)));
+        holders.truncate(limit);
+        Ok(holders)
+    }
+
+    /// アドレスの全トランザクション（古い順）
+    ///
+    /// 履歴ビューと異なり件数の上限はなく、アーカイブ導入後に反映したブロックのみを含みます。
+    pub async fn archive_transactions(
+        &self,
+        address: &str,
+        range: ArchiveRange,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<ArchivePage<AddressTx>> {
+        let tables = self.tables.lock().await;
+        archive_page(&tables.archive_txs, &normalize_address(address), range, cursor, limit).await
+    }
+
+    /// アドレスが受信した、指定したタグのメモ付きトランザクション（古い順）
+    pub async fn memo_transactions(
+        &self,
+        address: &str,
+        tag: &str,
+        range: ArchiveRange,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<ArchivePage<AddressTx>> {
+        let tables = self.tables.lock().await;
+        let prefix = format!("{}/{}", normalize_address(address), tag);
+        archive_page(&tables.memos, &prefix, range, cursor, limit).await
+    }
+
+    /// アドレスの残高の推移（古い順、残高が変化したブロックごと）
+    pub async fn balance_history(
+        &self,
+        address: &str,
+        range: ArchiveRange,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<ArchivePage<BalancePoint>> {
+        let tables = self.tables.lock().await;
+        archive_page(&tables.balance_history, &normalize_address(address), range, cursor, limit).await
+    }
+
+    /// バリデーターがブロックの生成で受け取った手数料（古い順）
+    pub async fn rewards(
+        &self,
+        validator: &str,
+        range: ArchiveRange,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<ArchivePage<BlockReward>> {
+        let tables = self.tables.lock().await;
+        archive_page(&tables.rewards, &normalize_address(validator), range, cursor, limit).await
+    }
+}
+
+/// アーカイブのキー（時刻、高さの順に並ぶ）
+fn archive_key(timestamp: u64, height: u64) -> String {
+    format!("{:020}{:020}", timestamp, height)
+}
+
+/// トランザクションの索引のキー（新しいブロック、ブロック内の後ろのトランザクションほど前に並ぶ）
+fn index_key(height: u64, index: usize) -> String {
+    format!("{:020}{:010}", u64::MAX - height, u32::MAX - index as u32)
+}
+
+/// ブロックだけから決まる履歴の索引（アドレスの全トランザクション・メモ・トランザクションの索引・報酬）
+async fn index_history(tables: &mut Tables, block: &Block) -> Result<()> {
+    for (index, tx) in block.transactions.iter().enumerate() {
+        let from = normalize_address(&tx.from);
+        let to = normalize_address(&tx.to);
+        let memo = tx.memo();
+        for (address, direction, counterparty) in [(&from, TxDirection::Out, &to), (&to, TxDirection::In, &from)] {
+            let entry = AddressTx {
+                hash: tx.hash.clone(),
+                height: block.height,
+                direction,
+                counterparty: counterparty.clone(),
+                value: tx.value,
+                timestamp: block.timestamp,
+                memo: memo.clone(),
+                fee: (direction == TxDirection::Out).then(|| tx.fee()),
+            };
+            let key = format!("{}/{}{:06}", address, archive_key(block.timestamp, block.height), index);
+            tables.archive_txs.insert(key.as_bytes(), &serde_json::to_vec(&entry)?).await?;
+            if direction == TxDirection::In {
+                if let Some(memo) = &memo {
+                    let key = format!("{}/{}/{}{:06}", address, memo.tag, archive_key(block.timestamp, block.height), index);
+                    tables.memos.insert(key.as_bytes(), &serde_json::to_vec(&entry)?).await?;
+                }
+            }
+        }
+        index_transaction(&mut tables.tx_index, block, index, tx).await?;
+    }
+
+    let fees = block.transactions.iter().fold(0u64, |sum, tx| sum.saturating_add(tx.fee()));
+    if fees > 0 {
+        let reward = BlockReward {
+            height: block.height,
+            hash: block.hash.clone(),
+            timestamp: block.timestamp,
+            fees,
+            transactions: block.transactions.len(),
+        };
+        let key = format!("{}/{}", normalize_address(&block.validator), archive_key(block.timestamp, block.height));
+        tables.rewards.insert(key.as_bytes(), &serde_json::to_vec(&reward)?).await?;
+    }
+    Ok(())
+}
+
+/// トランザクションを送信者と受信者の索引に追加する（自分宛ての送金は1件）
+async fn index_transaction(table: &mut NoriaStorage, block: &Block, index: usize, tx: &PendingTransaction) -> Result<()> {
+    let from = normalize_address(&tx.from);
+    let to = normalize_address(&tx.to);
+    for (address, direction, counterparty) in [(&to, TxDirection::In, &from), (&from, TxDirection::Out, &to)] {
+        let entry = AddressTx {
+            hash: tx.hash.clone(),
+            height: block.height,
+            direction,
+            counterparty: counterparty.clone(),
+            value: tx.value,
+            timestamp: block.timestamp,
+            memo: tx.memo(),
+            fee: (direction == TxDirection::Out).then(|| tx.fee()),
+        };
+        let key = format!("{}/{}", address, index_key(block.height, index));
+        table.insert(key.as_bytes(), &serde_json::to_vec(&entry)?).await?;
+    }
+    Ok(())
+}
+
+/// アーカイブを時刻範囲とカーソルで1ページ分読み込む
+///
+/// カーソルは前のページの最後の行のキーで、その直後から読み込みます。
+async fn archive_page<T: DeserializeOwned>(
+    table: &NoriaStorage,
+    address: &str,
+    range: ArchiveRange,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<ArchivePage<T>> {
+    let prefix = format!("{}/", address);
+    let start = match cursor {
+        Some(cursor) => format!("{}{}\0", prefix, cursor),
+        None => format!("{}{:020}", prefix, range.from.unwrap_or(0)),
+    };
+    let limit = limit.clamp(1, MAX_ARCHIVE_PAGE);
+
+    let mut items = Vec::new();
+    let mut last = None;
+    let mut next_cursor = None;
+    for (key, value) in table.scan_range(prefix.as_bytes(), start.as_bytes(), limit + 1).await? {
+        let suffix = String::from_utf8(key[prefix.len()..].to_vec())?;
+        let timestamp: u64 = suffix.get(..20)
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| anyhow!("Corrupted archive key {}", suffix))?;
+        if range.to.is_some_and(|to| timestamp > to) {
+            break;
+        }
+        if items.len() == limit {
+            next_cursor = last;
+            break;
+        }
+        items.push(serde_json::from_slice(&value)?);
+        last = Some(suffix);
+    }
+    Ok(ArchivePage { items, next_cursor })
+}
+
+/// HTLC の作成を記録し、受け取りと返金の払い出し先と額を返す
+///
+/// 検証は `core::htlc` の台帳と同じで、検証に失敗した操作は無視します。
+async fn settle_htlc(table: &mut NoriaStorage, block: &Block, tx: &PendingTransaction) -> Result<Option<(String, u64)>> {
+    let Some(Ok(op)) = HtlcOp::of(tx) else {
+        return Ok(None);
+    };
+    if tx.to != HTLC_ADDRESS {
+        return Ok(None);
+    }
+    let id = match &op {
+        HtlcOp::Create { .. } => {
+            if let Ok(lock) = HtlcLock::create(tx, &op, block.timestamp, block.height) {
+                table.insert(lock.id.as_bytes(), &serde_json::to_vec(&lock)?).await?;
+            }
+            return Ok(None);
+        }
+        HtlcOp::Claim { id, .. } | HtlcOp::Refund { id } => id,
+    };
+    let Some(value) = table.get(id.as_bytes()).await? else {
+        return Ok(None);
+    };
+    let lock: HtlcLock = serde_json::from_slice(&value)?;
+    let Ok(settled) = lock.settle(tx, &op, block.timestamp) else {
+        return Ok(None);
+    };
+    table.delete(id.as_bytes()).await?;
+    Ok(settled.payee().map(|payee| (payee.to_string(), settled.amount)))
+}
+
+/// ERC-20 `transfer(address,uint256)` 呼び出しを解析
+fn decode_token_transfer(tx: &PendingTransaction) -> Option<(String, u64)> {
+    let data = &tx.data;
+    if data.len() < 68 || data[..4] != TRANSFER_SELECTOR {
+        return None;
+    }
+    let recipient = hex::encode(&data[16..36]);
+    let amount = &data[36..68];
+    // u64 に収まらない量は扱わない
+    if amount[..24].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some((recipient, u64::from_be_bytes(amount[24..].try_into().ok()?)))
+}
+
+/// 残高を移す（送信元の残高が足りない場合は何もせず `false`）
+async fn transfer(tables: &mut Tables, from: &str, to: &str, amount: u64) -> Result<bool> {
+    let Some(sender) = read_u64(&tables.balances, from.as_bytes()).await?.checked_sub(amount) else {
+        return Ok(false);
+    };
+    tables.balances.update(from.as_bytes(), &sender.to_be_bytes()).await?;
+    let receiver = read_u64(&tables.balances, to.as_bytes()).await?;
+    tables.balances.update(to.as_bytes(), &receiver.saturating_add(amount).to_be_bytes()).await?;
+    Ok(true)
+}
+
+/// トークンの保有量を移す（送信元の保有量が足りない場合は何もせず `false`）
+async fn transfer_tokens(tables: &mut Tables, token: &str, from: &str, to: &str, amount: u64) -> Result<bool> {
+    let sender = holder_key(token, from);
+    let Some(balance) = read_u64(&tables.holders, &sender).await?.checked_sub(amount) else {
+        return Ok(false);
+    };
+    if balance == 0 {
+        tables.holders.delete(&sender).await?;
+    } else {
+        tables.holders.update(&sender, &balance.to_be_bytes()).await?;
+    }
+    let receiver = holder_key(token, to);
+    let balance = read_u64(&tables.holders, &receiver).await?.saturating_add(amount);
+    tables.holders.update(&receiver, &balance.to_be_bytes()).await?;
+    Ok(true)
+}
+
+fn decode_u64(value: &[u8]) -> Result<u64> {
+    Ok(u64::from_be_bytes(value.try_into().map_err(|_| anyhow!("Corrupted view value"))?))
+}
+
+/// 反映済みの全ての行（キーの順）
+async fn scan_all(table: &NoriaStorage) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut rows: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    loop {
+        let start = rows.last().map_or_else(Vec::new, |(key, _)| [key.as_slice(), &[0]].concat());
+        let page = table.scan_range(b"", &start, SCAN_BATCH).await?;
+        let done = page.len() < SCAN_BATCH;
+        rows.extend(page);
+        if done {
+            return Ok(rows);
+        }
+    }
+}
+
+async fn read_u64(table: &NoriaStorage, key: &[u8]) -> Result<u64> {
+    Ok(table.get(key).await?
+        .and_then(|v| v.try_into().ok())
+        .map(u64::from_be_bytes)
+        .unwrap_or(0))
+}
+
+fn holder_key(token: &str, holder: &str) -> Vec<u8> {
+    format!("{}/{}", token, holder).into_bytes()
+}
+
+fn normalize_address(address: &str) -> String {
+    address.trim().trim_start_matches("0x").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn tx(from: &str, to: &str, value: u64, nonce: u64, data: Vec<u8>) -> PendingTransaction {
+        PendingTransaction::test_transfer(from, to, value, nonce).with_data(data)
+    }
+
+    fn transfer_call(recipient: &str, amount: u64) -> Vec<u8> {
+        let mut data = TRANSFER_SELECTOR.to_vec();
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&hex::decode(recipient).unwrap());
+        data.extend_from_slice(&[0; 24]);
+        data.extend_from_slice(&amount.to_be_bytes());
+        data
+    }
+
+    #[tokio::test]
+    async fn test_views_follow_committed_blocks() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let chain = Chain::open(storage.clone()).await.unwrap().with_allow_unsigned(true);
+        let views = MaterializedViews::new(storage.clone());
+        let alice = "aa".repeat(20);
+        let bob = "bb".repeat(20);
+        let token = "cc".repeat(20);
+        let views = views.with_genesis(ExportedState {
+            balances: BTreeMap::from([("00".to_string(), 100)]),
+            tokens: BTreeMap::from([(token.clone(), BTreeMap::from([(alice.clone(), 800)]))]),
+            ..Default::default()
+        });
+
+        let genesis = chain.next_signed_block(vec![tx("00", &alice, 100, 0, vec![])]).await;
+        chain.commit(genesis.clone()).await.unwrap();
+        let block = chain.next_signed_block(vec![
+            tx(&alice, &bob, 30, 0, Memo::new("deposit", "user-7").unwrap().encode()),
+            tx(&format!("0x{}", alice.to_uppercase()), &token, 0, 1, transfer_call(&bob, 500)),
+        ]).await;
+        chain.commit(block.clone()).await.unwrap();
+
+        views.catch_up(&chain).await.unwrap();
+        // 反映済みのブロックは無視される
+        views.apply_block(&genesis).await.unwrap();
+
+        assert_eq!(views.applied_height().await.unwrap(), Some(1));
+        assert_eq!(views.balance(&alice).await.unwrap(), 70);
+        assert_eq!(views.balance(&format!("0x{}", bob)).await.unwrap(), 30);
+        assert_eq!(views.balance("00").await.unwrap(), 0);
+
+        // 索引は新しい順にカーソルで辿れる
+        let page = views.transactions(&alice, None, 2).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[0].counterparty, token);
+        assert_eq!(page.items[1].direction, TxDirection::Out);
+        let cursor = page.next_cursor.expect("more transactions");
+        let page = views.transactions(&alice, Some(&cursor), 2).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].direction, TxDirection::In);
+        assert!(page.next_cursor.is_none());
+        assert_eq!(views.indexed_height().await.unwrap(), Some(1));
+
+        let holders = views.token_holders(&token, 10).await.unwrap();
+        let holders: Vec<(&str, u64)> = holders.iter().map(|h| (h.address.as_str(), h.balance)).collect();
+        assert_eq!(holders, [(bob.as_str(), 500), (alice.as_str(), 300)]);
+
+        // アーカイブは古い順にカーソルで辿れる
+        let page = views.archive_transactions(&alice, ArchiveRange::default(), None, 2).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[0].direction, TxDirection::In);
+        let cursor = page.next_cursor.expect("more transactions");
+        let page = views.archive_transactions(&alice, ArchiveRange::default(), Some(&cursor), 2).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].counterparty, token);
+        assert!(page.next_cursor.is_none());
+
+        let history = views.balance_history(&alice, ArchiveRange::default(), None, 10).await.unwrap();
+        let balances: Vec<u64> = history.items.iter().map(|p| p.balance).collect();
+        assert_eq!(balances, vec![100, 70]);
+        let range = ArchiveRange { from: Some(block.timestamp + 1), to: None };
+        assert!(views.balance_history(&alice, range, None, 10).await.unwrap().items.is_empty());
+
+        // メモは受信者とタグで引ける
+        let deposits = views.memo_transactions(&bob, "deposit", ArchiveRange::default(), None, 10).await.unwrap();
+        assert_eq!(deposits.items.len(), 1);
+        assert_eq!(deposits.items[0].memo.as_ref().map(|m| m.payload.as_str()), Some("user-7"));
+        assert!(views.memo_transactions(&alice, "deposit", ArchiveRange::default(), None, 10).await.unwrap().items.is_empty());
+        assert!(views.memo_transactions(&bob, "invoice", ArchiveRange::default(), None, 10).await.unwrap().items.is_empty());
+
+        // 残高を超える送金はノンスのみ進み、送金額は移らない（トークンの移転は独立に反映する）
+        let overdraw = chain.next_signed_block(vec![
+            tx(&bob, &token, 31, 0, transfer_call(&alice, 100)),
+            tx(&alice, &token, 0, 2, transfer_call(&bob, 401)),
+        ]).await;
+        chain.commit(overdraw.clone()).await.unwrap();
+        views.apply_block(&overdraw).await.unwrap();
+        assert_eq!((views.balance(&alice).await.unwrap(), views.balance(&bob).await.unwrap()), (70, 30));
+        assert_eq!(views.next_nonce(&bob).await.unwrap(), 1);
+        // 保有量を超えるトークンの移転は反映しない
+        let holders = views.token_holders(&token, 10).await.unwrap();
+        let holders: Vec<(&str, u64)> = holders.iter().map(|h| (h.address.as_str(), h.balance)).collect();
+        assert_eq!(holders, [(alice.as_str(), 400), (bob.as_str(), 400)]);
+
+        // ブロックの生成では、残高を超える送金と同じ送信者の後続を除く
+        let mut txs = vec![tx(&bob, &alice, 20, 1, vec![]), tx(&bob, &alice, 11, 2, vec![]), tx(&bob, &alice, 1, 3, vec![])];
+        views.retain_funded(&mut txs).await;
+        assert_eq!(txs.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![1]);
+    }
+}
@@ -0,0 +1,316 @@
+//! 索引したチェーンデータの列指向の保存（`sql`・`export` フィーチャー）
+//!
+//! 確定したブロック・トランザクション・イベントを表の行に変換します。表の列を変更する場合は
+//! `SCHEMA_VERSION` を上げます（書き出したファイルのメタデータと一括書き出しのパスに含まれる）。
+//!
+//! `ColumnarIndex` は `segment_blocks` 個のブロックごとに Parquet のセグメントとして書き出し、
+//! SQL（`sql`）から表として参照できるようにします。一括書き出しは `export` を参照してください。
+//! - `<dir>/<表>/<最初の高さ>-<最後の高さ>.parquet`（表は `blocks`・`transactions`・`events`）
+//! - セグメントに満たない直近のブロックはメモリに保持する
+//! - 書き出し済みの高さは `blocks` のファイル名から求め、再起動後は続きから書き出す
+//!   （`blocks` のセグメントは他の表の後に書くため、途中で停止しても欠けた範囲は書き直される）
+
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "sql")]
+pub mod sql;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Context, Result, anyhow};
+use datafusion::arrow::array::{ArrayRef, ListBuilder, StringArray, StringBuilder, UInt32Array, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::file::metadata::KeyValue;
+use datafusion::parquet::file::properties::WriterProperties;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, warn};
+use crate::core::block::{Block, Chain, Event};
+use crate::core::mempool::PendingTransaction;
+
+/// 書き出しに失敗した場合の再試行間隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// セグメントのファイルの拡張子
+const SEGMENT_EXTENSION: &str = "parquet";
+/// 表の列のバージョン
+pub const SCHEMA_VERSION: u32 = 1;
+/// Parquet のメタデータのスキーマのバージョンのキー
+const SCHEMA_VERSION_KEY: &str = "rustorium.schema_version";
+
+/// 表
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Table {
+    Blocks,
+    Transactions,
+    Events,
+}
+
+impl Table {
+    /// 全ての表（`blocks` は最後に書き出す）
+    pub const ALL: [Table; 3] = [Self::Transactions, Self::Events, Self::Blocks];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Blocks => "blocks",
+            Self::Transactions => "transactions",
+            Self::Events => "events",
+        }
+    }
+
+    pub fn schema(self) -> SchemaRef {
+        use DataType::{UInt32, UInt64, Utf8};
+        let fields = match self {
+            Self::Blocks => vec![
+                Field::new("height", UInt64, false),
+                Field::new("hash", Utf8, false),
+                Field::new("parent_hash", Utf8, false),
+                Field::new("timestamp", UInt64, false),
+                Field::new("validator", Utf8, false),
+                Field::new("transaction_count", UInt32, false),
+                Field::new("event_count", UInt32, false),
+                Field::new("gas_used", UInt64, false),
+                Field::new("gas_limit", UInt64, false),
+                Field::new("base_fee", UInt64, false),
+            ],
+            Self::Transactions => vec![
+                Field::new("hash", Utf8, false),
+                Field::new("block_height", UInt64, false),
+                Field::new("block_timestamp", UInt64, false),
+                Field::new("index", UInt32, false),
+                Field::new("from", Utf8, false),
+                Field::new("to", Utf8, false),
+                Field::new("value", UInt64, false),
+                Field::new("nonce", UInt64, false),
+                Field::new("gas_price", UInt64, false),
+                Field::new("gas_limit", UInt64, false),
+                // データ（hex）
+                Field::new("data", Utf8, false),
+            ],
+            Self::Events => vec![
+                Field::new("tx_hash", Utf8, false),
+                Field::new("block_height", UInt64, false),
+                Field::new("block_timestamp", UInt64, false),
+                Field::new("index", UInt32, false),
+                Field::new("address", Utf8, false),
+                Field::new("topics", DataType::List(Arc::new(Field::new("item", Utf8, true))), false),
+                // データ（hex）
+                Field::new("data", Utf8, false),
+            ],
+        };
+        Arc::new(Schema::new(fields))
+    }
+
+    /// ブロックの行
+    pub fn batch(self, blocks: &[Block]) -> Result<RecordBatch> {
+        let columns: Vec<ArrayRef> = match self {
+            Self::Blocks => vec![
+                Arc::new(UInt64Array::from_iter_values(blocks.iter().map(|b| b.height))),
+                Arc::new(StringArray::from_iter_values(blocks.iter().map(|b| &b.hash))),
+                Arc::new(StringArray::from_iter_values(blocks.iter().map(|b| &b.parent_hash))),
+                Arc::new(UInt64Array::from_iter_values(blocks.iter().map(|b| b.timestamp))),
+                Arc::new(StringArray::from_iter_values(blocks.iter().map(|b| &b.validator))),
+                Arc::new(UInt32Array::from_iter_values(blocks.iter().map(|b| b.transactions.len() as u32))),
+                Arc::new(UInt32Array::from_iter_values(blocks.iter().map(|b| b.events.len() as u32))),
+                Arc::new(UInt64Array::from_iter_values(blocks.iter().map(|b| b.gas_used))),
+                Arc::new(UInt64Array::from_iter_values(blocks.iter().map(|b| b.gas_limit))),
+                Arc::new(UInt64Array::from_iter_values(blocks.iter().map(|b| b.base_fee))),
+            ],
+            Self::Transactions => {
+                let txs: Vec<(&Block, usize, &PendingTransaction)> = blocks.iter()
+                    .flat_map(|b| b.transactions.iter().enumerate().map(move |(i, tx)| (b, i, tx)))
+                    .collect();
+                vec![
+                    Arc::new(StringArray::from_iter_values(txs.iter().map(|(_, _, tx)| &tx.hash))),
+                    Arc::new(UInt64Array::from_iter_values(txs.iter().map(|(b, _, _)| b.height))),
+                    Arc::new(UInt64Array::from_iter_values(txs.iter().map(|(b, _, _)| b.timestamp))),
+                    Arc::new(UInt32Array::from_iter_values(txs.iter().map(|(_, i, _)| *i as u32))),
+                    Arc::new(StringArray::from_iter_values(txs.iter().map(|(_, _, tx)| &tx.from))),
+                    Arc::new(StringArray::from_iter_values(txs.iter().map(|(_, _, tx)| &tx.to))),
+                    Arc::new(UInt64Array::from_iter_values(txs.iter().map(|(_, _, tx)| tx.value))),
+                    Arc::new(UInt64Array::from_iter_values(txs.iter().map(|(_, _, tx)| tx.nonce))),
+                    Arc::new(UInt64Array::from_iter_values(txs.iter().map(|(_, _, tx)| tx.gas_price))),
+                    Arc::new(UInt64Array::from_iter_values(txs.iter().map(|(_, _, tx)| tx.gas_limit))),
+                    Arc::new(StringArray::from_iter_values(txs.iter().map(|(_, _, tx)| hex::encode(&tx.data)))),
+                ]
+            }
+            Self::Events => {
+                let events: Vec<(&Block, &Event)> = blocks.iter()
+                    .flat_map(|b| b.events.iter().map(move |e| (b, e)))
+                    .collect();
+                let mut topics = ListBuilder::new(StringBuilder::new());
+                for (_, event) in &events {
+                    for topic in &event.topics {
+                        topics.values().append_value(topic);
+                    }
+                    topics.append(true);
+                }
+                vec![
+                    Arc::new(StringArray::from_iter_values(events.iter().map(|(_, e)| &e.tx_hash))),
+                    Arc::new(UInt64Array::from_iter_values(events.iter().map(|(b, _)| b.height))),
+                    Arc::new(UInt64Array::from_iter_values(events.iter().map(|(b, _)| b.timestamp))),
+                    Arc::new(UInt32Array::from_iter_values(events.iter().map(|(_, e)| e.index))),
+                    Arc::new(StringArray::from_iter_values(events.iter().map(|(_, e)| &e.address))),
+                    Arc::new(topics.finish()),
+                    Arc::new(StringArray::from_iter_values(events.iter().map(|(_, e)| hex::encode(&e.data)))),
+                ]
+            }
+        };
+        Ok(RecordBatch::try_new(self.schema(), columns)?)
+    }
+}
+
+/// ある時点の表の内容
+pub struct Snapshot {
+    /// 表ごとの書き出し済みのセグメント（高さの順）
+    pub segments: Vec<(Table, Vec<PathBuf>)>,
+    /// 表ごとのセグメントに満たない直近のブロックの行
+    pub recent: Vec<(Table, RecordBatch)>,
+}
+
+/// 列指向の保存
+pub struct ColumnarIndex {
+    dir: PathBuf,
+    segment_blocks: u64,
+    /// セグメントに満たない直近のブロック
+    recent: RwLock<Vec<Block>>,
+}
+
+impl ColumnarIndex {
+    pub fn new(dir: impl Into<PathBuf>, segment_blocks: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_blocks: segment_blocks.max(1),
+            recent: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 表の書き出し済みのセグメント（最初と最後の高さ、パス）を高さの順に取得
+    fn segments(&self, table: Table) -> Result<Vec<(u64, u64, PathBuf)>> {
+        let dir = self.dir.join(table.name());
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut segments = Vec::new();
+        for entry in std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            let range = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(&format!(".{}", SEGMENT_EXTENSION)))
+                .and_then(|range| range.split_once('-'))
+                .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)));
+            if let Some((first, last)) = range {
+                segments.push((first, last, path));
+            }
+        }
+        segments.sort_by_key(|(first, _, _)| *first);
+        Ok(segments)
+    }
+
+    /// 書き出し済みの最後の高さ
+    pub fn written_height(&self) -> Result<Option<u64>> {
+        Ok(self.segments(Table::Blocks)?.last().map(|(_, last, _)| *last))
+    }
+
+    /// 書き出し済みのセグメントと直近のブロックの行
+    pub async fn snapshot(&self) -> Result<Snapshot> {
+        let recent = self.recent.read().await;
+        let written = self.written_height()?;
+        let mut segments = Vec::new();
+        for table in Table::ALL {
+            let paths = self.segments(table)?.into_iter()
+                .filter(|(_, last, _)| written.is_some_and(|written| *last <= written))
+                .map(|(_, _, path)| path)
+                .collect();
+            segments.push((table, paths));
+        }
+        // 書き出した直後は直近のブロックにセグメントの分が残っている場合がある
+        let unwritten: Vec<Block> = recent.iter()
+            .filter(|block| written.map_or(true, |written| block.height > written))
+            .cloned()
+            .collect();
+        let recent = Table::ALL.into_iter()
+            .map(|table| Ok((table, table.batch(&unwritten)?)))
+            .collect::<Result<_>>()?;
+        Ok(Snapshot { segments, recent })
+    }
+
+    /// ブロックを1つのセグメントとして書き出す
+    async fn write_segment(&self, blocks: Vec<Block>) -> Result<()> {
+        let (Some(first), Some(last)) = (blocks.first().map(|b| b.height), blocks.last().map(|b| b.height)) else {
+            return Ok(());
+        };
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || {
+            for table in Table::ALL {
+                let path = dir.join(table.name()).join(format!("{:012}-{:012}.{}", first, last, SEGMENT_EXTENSION));
+                write_parquet(&path, &table.batch(&blocks)?)?;
+            }
+            Ok::<_, anyhow::Error>(())
+        }).await??;
+        info!("Wrote columnar segment for blocks {} to {}", first, last);
+        Ok(())
+    }
+
+    /// 書き出し済みの次の高さから最新ブロックまで反映
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        // チェックポイントから開始したノードは基点より前のブロックを持たない
+        let base = chain.base().await?.unwrap_or(0);
+        let mut first = self.written_height()?.map_or(0, |h| h + 1).max(base);
+        while first + self.segment_blocks - 1 <= head {
+            let mut blocks = Vec::with_capacity(self.segment_blocks as usize);
+            for height in first..first + self.segment_blocks {
+                blocks.push(chain.get_block(height).await?.ok_or_else(|| anyhow!("Block {} is missing", height))?);
+            }
+            self.write_segment(blocks).await?;
+            first += self.segment_blocks;
+        }
+
+        let mut recent = self.recent.write().await;
+        recent.retain(|block| block.height >= first);
+        for height in recent.last().map_or(first, |block| block.height + 1)..=head {
+            recent.push(chain.get_block(height).await?.ok_or_else(|| anyhow!("Block {} is missing", height))?);
+        }
+        Ok(())
+    }
+
+    /// ブロックの確定を購読して書き出し続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            loop {
+                // 未反映のブロックはストレージから読み直すため、通知は反映の契機としてのみ使う
+                match self.catch_up(&chain).await {
+                    Ok(()) => match commits.recv().await {
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    Err(e) => {
+                        warn!("Failed to write columnar chain data: {}", e);
+                        tokio::time::sleep(RETRY_INTERVAL).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// 一時ファイルに書いてから置き換える（読み取り中のクエリに書きかけのファイルを見せない）
+fn write_parquet(path: &Path, batch: &RecordBatch) -> Result<()> {
+    let dir = path.parent().ok_or_else(|| anyhow!("{} has no parent directory", path.display()))?;
+    std::fs::create_dir_all(dir)?;
+    let tmp = path.with_extension("tmp");
+    let file = std::fs::File::create(&tmp).with_context(|| format!("Failed to create {}", tmp.display()))?;
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(SCHEMA_VERSION_KEY.to_string(), SCHEMA_VERSION.to_string())]))
+        .build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+    writer.write(batch)?;
+    writer.close()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! 列指向のチェーンデータへの読み取り専用のSQL
+//!
+//! DataFusion で `blocks`・`transactions`・`events` の表を参照します。各クエリは新しいセッションで
+//! 実行し、書き込み・DDL・`SET` などの文は拒否します。資源は次の設定で制限します。
+//! - `max_concurrent`: 同時に実行するクエリ数（超えた場合は待たずに拒否する）
+//! - `timeout_secs`: 実行時間
+//! - `memory_limit_mb`: 並べ替えや集計などが使うメモリ
+//! - `max_rows`: 返す行数（超えた分は切り捨て、`truncated` を返す）
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use datafusion::arrow::json::ArrayWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::{SQLOptions, SessionConfig, SessionContext};
+use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
+use datafusion::prelude::ParquetReadOptions;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use utoipa::ToSchema;
+use crate::config::SqlSettings;
+use super::ColumnarIndex;
+
+/// クエリの失敗
+#[derive(Debug, Error)]
+pub enum SqlError {
+    /// 構文・表・列の誤りと、読み取り以外の文
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Query exceeded the {0}")]
+    LimitExceeded(String),
+    #[error("Too many queries are running (at most {0})")]
+    Busy(usize),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl From<DataFusionError> for SqlError {
+    fn from(e: DataFusionError) -> Self {
+        let root = e.find_root();
+        if let DataFusionError::ResourcesExhausted(message) = root {
+            return Self::LimitExceeded(format!("memory limit ({})", message));
+        }
+        if matches!(
+            root,
+            DataFusionError::SQL(..) | DataFusionError::Plan(_) | DataFusionError::SchemaError(..) | DataFusionError::NotImplemented(_)
+        ) {
+            return Self::Invalid(e.to_string());
+        }
+        Self::Internal(e.into())
+    }
+}
+
+/// クエリの結果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SqlResult {
+    /// 列の名前（結果の順）
+    pub columns: Vec<String>,
+    /// 列の名前をキーとする行（NULL の列は含まない）
+    #[schema(value_type = Vec<Object>)]
+    pub rows: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// `max_rows` を超えた分を切り捨てた
+    pub truncated: bool,
+    /// 実行時間（ミリ秒）
+    pub elapsed_ms: u64,
+}
+
+/// 読み取り専用のSQL
+pub struct SqlEngine {
+    index: Arc<ColumnarIndex>,
+    settings: SqlSettings,
+    permits: Arc<Semaphore>,
+}
+
+impl SqlEngine {
+    pub fn new(index: Arc<ColumnarIndex>, settings: SqlSettings) -> Self {
+        let permits = Arc::new(Semaphore::new(settings.max_concurrent.max(1)));
+        Self { index, settings, permits }
+    }
+
+    /// クエリを実行
+    pub async fn query(&self, sql: &str) -> Result<SqlResult, SqlError> {
+        let _permit = self.permits.clone().try_acquire_owned()
+            .map_err(|_| SqlError::Busy(self.settings.max_concurrent.max(1)))?;
+        let started = Instant::now();
+        let (columns, batches, truncated) = tokio::time::timeout(Duration::from_secs(self.settings.timeout_secs), self.execute(sql))
+            .await
+            .map_err(|_| SqlError::LimitExceeded(format!("time limit of {} seconds", self.settings.timeout_secs)))??;
+
+        let mut rows = Vec::new();
+        if batches.iter().any(|batch| batch.num_rows() > 0) {
+            let mut writer = ArrayWriter::new(Vec::new());
+            writer.write_batches(&batches.iter().collect::<Vec<_>>()).map_err(DataFusionError::from)?;
+            writer.finish().map_err(DataFusionError::from)?;
+            rows = serde_json::from_slice(&writer.into_inner()).map_err(anyhow::Error::from)?;
+        }
+        Ok(SqlResult {
+            columns,
+            rows,
+            truncated,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    async fn execute(&self, sql: &str) -> Result<(Vec<String>, Vec<RecordBatch>, bool), SqlError> {
+        let session = self.session().await?;
+        let options = SQLOptions::new()
+            .with_allow_ddl(false)
+            .with_allow_dml(false)
+            .with_allow_statements(false);
+        let frame = session.sql_with_options(sql, options).await?;
+        let columns = frame.schema().fields().iter().map(|field| field.name().to_string()).collect();
+
+        // 1行多く取得して切り捨てたかを判定する
+        let max_rows = self.settings.max_rows;
+        let mut batches = frame.limit(0, Some(max_rows + 1))?.collect().await?;
+        let total: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        let truncated = total > max_rows;
+        if truncated {
+            let mut remaining = max_rows;
+            for batch in &mut batches {
+                let keep = batch.num_rows().min(remaining);
+                *batch = batch.slice(0, keep);
+                remaining -= keep;
+            }
+        }
+        Ok((columns, batches, truncated))
+    }
+
+    /// 表を登録したセッション
+    async fn session(&self) -> Result<SessionContext, SqlError> {
+        let runtime = RuntimeEnv::new(
+            RuntimeConfig::new().with_memory_limit(self.settings.memory_limit_mb.saturating_mul(1024 * 1024), 1.0),
+        )?;
+        let session = SessionContext::new_with_config_rt(SessionConfig::new(), Arc::new(runtime));
+        let snapshot = self.index.snapshot().await?;
+        for ((table, segments), (_, recent)) in snapshot.segments.into_iter().zip(snapshot.recent) {
+            let schema = table.schema();
+            let mut frame = session.read_batch(recent)?;
+            if !segments.is_empty() {
+                let paths: Vec<String> = segments.iter().map(|path| path.to_string_lossy().to_string()).collect();
+                let written = session.read_parquet(paths, ParquetReadOptions::default().schema(&schema)).await?;
+                frame = written.union(frame)?;
+            }
+            session.register_table(table.name(), frame.into_view())?;
+        }
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::block::Chain;
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    #[tokio::test]
+    async fn test_query_segments_and_recent_blocks() {
+        let columnar = tempfile::tempdir().unwrap();
+        let chain = Chain::open(RedbStorage::memory()).await.unwrap().with_allow_unsigned(true);
+        for nonce in 0..5 {
+            let tx = PendingTransaction::test_transfer("aa", "bb", 10, nonce);
+            chain.commit(chain.next_signed_block(vec![tx]).await).await.unwrap();
+        }
+        // 2ブロックずつのセグメントが2つと、直近の1ブロック
+        let index = Arc::new(ColumnarIndex::new(columnar.path(), 2));
+        index.catch_up(&chain).await.unwrap();
+        assert_eq!(index.written_height().unwrap(), Some(3));
+
+        let engine = SqlEngine::new(index, SqlSettings { max_rows: 2, ..Default::default() });
+        let result = engine.query("SELECT \"to\", SUM(value) AS total FROM transactions GROUP BY \"to\"").await.unwrap();
+        assert_eq!(result.columns, ["to", "total"]);
+        assert_eq!(result.rows[0]["total"], 50);
+        let result = engine.query("SELECT height FROM blocks ORDER BY height").await.unwrap();
+        assert_eq!((result.rows.len(), result.truncated), (2, true));
+
+        assert!(matches!(engine.query("DROP TABLE blocks").await, Err(SqlError::Invalid(_))));
+        assert!(matches!(engine.query("SELECT * FROM accounts").await, Err(SqlError::Invalid(_))));
+    }
+}
@@ -0,0 +1,580 @@
+//! 機密トランザクション（`confidential-tx` フィーチャー、試験的）
+//!
+//! 送金額をPedersenコミットメントで隠し、Bulletproofsの範囲証明で負にならないことを示します。
+//! 性能への影響を評価するためのdevnet向けのプロトタイプで、ネットワークの全ノードで
+//! フィーチャーを有効にする必要があります。
+//!
+//! アカウントは公開の残高とは別に、機密残高のコミットメント `v·B + r·B̃` を持ちます（初期値は単位元）。
+//! 操作は `data` フィールドに入れます：
+//!
+//! | 位置 | 内容 |
+//! |------|------|
+//! | 0..4 | マジック `cnfd`（`636e6664`） |
+//! | 4    | バージョン（`1`） |
+//! | 5    | 種類（`0` は入金、`1` は送金） |
+//! | 6..38 | 送金額のコミットメント（送金のみ、圧縮したRistrettoの点） |
+//! | 38.. | 範囲証明（送金のみ） |
+//!
+//! - 入金：公開の `value` を [`CONFIDENTIAL_ADDRESS`] に送り、送信者の機密残高に加えます
+//!   （コミットメントは `value·B`、ブラインドは0）。預けた公開の残高は機密残高の合計の裏付けで、引き出せません
+//! - 送金：`value` は0で、送金額のコミットメント `C` を送信者の機密残高から引き、受信者に加えます。
+//!   範囲証明は `C` と送信後の送信者の残高 `残高 - C` の2つが64ビットに収まることを示す集約証明です
+//!
+//! 送金額とブラインドは受信者へ別の経路で伝えます。操作は確定したブロックの順に検証して適用し、
+//! 検証に失敗した操作は状態を変えません。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::Result;
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use curve25519_dalek_ng::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek_ng::scalar::Scalar;
+use curve25519_dalek_ng::traits::Identity;
+use merlin::Transcript;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+
+/// 反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"confidential/applied_height";
+/// 入金の宛先（入金した公開の `value` を預かる）
+pub const CONFIDENTIAL_ADDRESS: &str = "0000000000000000000000000000000000000043";
+/// 操作の先頭のマジック
+pub const CONFIDENTIAL_MAGIC: [u8; 4] = *b"cnfd";
+/// 形式のバージョン
+const CONFIDENTIAL_VERSION: u8 = 1;
+/// 範囲証明のビット数
+pub const RANGE_BITS: usize = 64;
+/// 送金に必要なガス上限の最低値（範囲証明の検証の分）
+pub const TRANSFER_GAS: u64 = 250_000;
+/// 範囲証明のトランスクリプトのラベル
+const TRANSCRIPT_LABEL: &[u8] = b"rustorium-confidential-transfer";
+
+const KIND_DEPOSIT: u8 = 0;
+const KIND_TRANSFER: u8 = 1;
+
+/// 機密トランザクションのエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ConfidentialError {
+    #[error("confidential operation is truncated")]
+    Truncated,
+
+    #[error("unsupported confidential operation version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("unknown confidential operation kind {0}")]
+    UnknownKind(u8),
+
+    #[error("confidential deposit must carry a public value")]
+    EmptyDeposit,
+
+    #[error("confidential deposit must be sent to {CONFIDENTIAL_ADDRESS}, not {0}")]
+    DepositRecipient(String),
+
+    #[error("confidential transfer must not carry a public value ({0})")]
+    PublicValue(u64),
+
+    #[error("confidential transfer requires a gas limit of at least {required}, got {gas_limit}")]
+    GasTooLow { gas_limit: u64, required: u64 },
+
+    #[error("invalid commitment")]
+    InvalidCommitment,
+
+    #[error("invalid range proof: {0}")]
+    InvalidProof(String),
+
+    #[error("insufficient confidential balance")]
+    InsufficientBalance,
+}
+
+/// `data` に入れる操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfidentialOp {
+    /// 公開の `value` を `CONFIDENTIAL_ADDRESS` に送り、送信者の機密残高に入金
+    Deposit,
+    /// 機密残高から送金
+    Transfer {
+        /// 送金額のコミットメント
+        commitment: CompressedRistretto,
+        /// `[送金額, 送信後の残高]` の集約範囲証明
+        proof: Vec<u8>,
+    },
+}
+
+impl ConfidentialOp {
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = CONFIDENTIAL_MAGIC.to_vec();
+        data.push(CONFIDENTIAL_VERSION);
+        match self {
+            Self::Deposit => data.push(KIND_DEPOSIT),
+            Self::Transfer { commitment, proof } => {
+                data.push(KIND_TRANSFER);
+                data.extend_from_slice(commitment.as_bytes());
+                data.extend_from_slice(proof);
+            }
+        }
+        data
+    }
+
+    /// `data` フィールドを解析（機密トランザクションでなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, ConfidentialError>> {
+        let body = data.strip_prefix(&CONFIDENTIAL_MAGIC)?;
+        Some(Self::decode_body(body))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, ConfidentialError> {
+        let [version, kind, rest @ ..] = body else {
+            return Err(ConfidentialError::Truncated);
+        };
+        if *version != CONFIDENTIAL_VERSION {
+            return Err(ConfidentialError::UnsupportedVersion(*version));
+        }
+        match *kind {
+            KIND_DEPOSIT if rest.is_empty() => Ok(Self::Deposit),
+            KIND_DEPOSIT => Err(ConfidentialError::Truncated),
+            KIND_TRANSFER => {
+                if rest.len() <= 32 {
+                    return Err(ConfidentialError::Truncated);
+                }
+                let (commitment, proof) = rest.split_at(32);
+                Ok(Self::Transfer {
+                    commitment: CompressedRistretto::from_slice(commitment),
+                    proof: proof.to_vec(),
+                })
+            }
+            kind => Err(ConfidentialError::UnknownKind(kind)),
+        }
+    }
+
+    /// トランザクションの操作（機密トランザクションでなければ `None`）
+    pub fn of(tx: &PendingTransaction) -> Option<Result<Self, ConfidentialError>> {
+        Self::decode(&tx.data)
+    }
+}
+
+/// 機密残高の開示値（ウォレットが保持する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opening {
+    pub value: u64,
+    pub blinding: Scalar,
+}
+
+impl Opening {
+    /// 入金額の開示値（ブラインドは0）
+    pub fn deposit(value: u64) -> Self {
+        Self { value, blinding: Scalar::zero() }
+    }
+
+    pub fn commitment(&self) -> RistrettoPoint {
+        PedersenGens::default().commit(Scalar::from(self.value), self.blinding)
+    }
+}
+
+/// 証明の生成元（64ビットの範囲証明2つ）
+fn generators() -> (BulletproofGens, PedersenGens) {
+    (BulletproofGens::new(RANGE_BITS, 2), PedersenGens::default())
+}
+
+/// 送信者・受信者・ノンスに結び付けたトランスクリプト
+fn transcript(from: &str, to: &str, nonce: u64) -> Transcript {
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+    transcript.append_message(b"from", from.as_bytes());
+    transcript.append_message(b"to", to.as_bytes());
+    transcript.append_u64(b"nonce", nonce);
+    transcript
+}
+
+/// 送金の操作を作成
+///
+/// `balance` は送信者の現在の機密残高の開示値です。（操作, 送金額の開示値, 送信後の残高の開示値）を返します。
+pub fn prove_transfer(
+    from: &str,
+    to: &str,
+    nonce: u64,
+    balance: Opening,
+    amount: u64,
+) -> Result<(ConfidentialOp, Opening, Opening), ConfidentialError> {
+    let remaining = balance.value.checked_sub(amount).ok_or(ConfidentialError::InsufficientBalance)?;
+    let sent = Opening { value: amount, blinding: Scalar::random(&mut rand::thread_rng()) };
+    let change = Opening { value: remaining, blinding: balance.blinding - sent.blinding };
+    let (bp_gens, pc_gens) = generators();
+    let (proof, commitments) = RangeProof::prove_multiple(
+        &bp_gens,
+        &pc_gens,
+        &mut transcript(from, to, nonce),
+        &[sent.value, change.value],
+        &[sent.blinding, change.blinding],
+        RANGE_BITS,
+    )
+    .map_err(|e| ConfidentialError::InvalidProof(e.to_string()))?;
+    let op = ConfidentialOp::Transfer { commitment: commitments[0], proof: proof.to_bytes() };
+    Ok((op, sent, change))
+}
+
+/// 送金を検証し、（送信後の送信者の残高, 送金額）のコミットメントを返す
+pub fn verify_transfer(
+    tx: &PendingTransaction,
+    balance: &RistrettoPoint,
+    commitment: &CompressedRistretto,
+    proof: &[u8],
+) -> Result<(RistrettoPoint, RistrettoPoint), ConfidentialError> {
+    if tx.value != 0 {
+        return Err(ConfidentialError::PublicValue(tx.value));
+    }
+    if tx.gas_limit < TRANSFER_GAS {
+        return Err(ConfidentialError::GasTooLow { gas_limit: tx.gas_limit, required: TRANSFER_GAS });
+    }
+    let amount = commitment.decompress().ok_or(ConfidentialError::InvalidCommitment)?;
+    let remaining = balance - amount;
+    let proof = RangeProof::from_bytes(proof).map_err(|e| ConfidentialError::InvalidProof(e.to_string()))?;
+    let (bp_gens, pc_gens) = generators();
+    proof
+        .verify_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(&tx.from, &tx.to, tx.nonce),
+            &[*commitment, remaining.compress()],
+            RANGE_BITS,
+        )
+        .map_err(|e| ConfidentialError::InvalidProof(e.to_string()))?;
+    Ok((remaining, amount))
+}
+
+/// アカウントの機密残高
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "confidential/balance", version = 1)]
+pub struct ConfidentialAccount {
+    #[state(key)]
+    pub address: String,
+    /// 残高のコミットメント（圧縮したRistrettoの点、hex）
+    pub commitment: String,
+    /// 最後に変更されたブロックの高さ
+    pub updated_at: u64,
+}
+
+/// 範囲証明の検証の統計（性能の評価用）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ConfidentialStats {
+    /// 適用した入金
+    pub deposits: u64,
+    /// 検証に成功した送金
+    pub transfers: u64,
+    /// 検証に失敗した送金
+    pub rejected: u64,
+    /// 範囲証明の検証にかかった時間の合計（マイクロ秒）
+    pub verify_micros_total: u64,
+    /// 範囲証明の検証にかかった時間の最大値（マイクロ秒）
+    pub verify_micros_max: u64,
+    /// 検証した範囲証明のバイト数の合計
+    pub proof_bytes_total: u64,
+}
+
+impl ConfidentialStats {
+    fn record_verification(&mut self, started: Instant, proof_bytes: usize, valid: bool) {
+        let micros = started.elapsed().as_micros().min(u64::MAX as u128) as u64;
+        self.verify_micros_total = self.verify_micros_total.saturating_add(micros);
+        self.verify_micros_max = self.verify_micros_max.max(micros);
+        self.proof_bytes_total = self.proof_bytes_total.saturating_add(proof_bytes as u64);
+        if valid {
+            self.transfers += 1;
+        } else {
+            self.rejected += 1;
+        }
+    }
+}
+
+/// 機密残高の台帳
+pub struct ConfidentialLedger {
+    storage: Arc<dyn StorageEngine>,
+    stats: Mutex<ConfidentialStats>,
+    /// ブロックの反映を直列にする（購読と追いつきが同時に反映しないように）
+    applying: Mutex<()>,
+}
+
+impl ConfidentialLedger {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        Self { storage, stats: Mutex::new(ConfidentialStats::default()), applying: Mutex::new(()) }
+    }
+
+    /// アカウントの機密残高のコミットメント（記録がなければ単位元）
+    pub async fn balance(&self, address: &str) -> Result<RistrettoPoint> {
+        Ok(match ConfidentialAccount::load(self.storage.as_ref(), &address.to_string()).await? {
+            Some(account) => decode_point(&account.commitment)?,
+            None => RistrettoPoint::identity(),
+        })
+    }
+
+    /// アカウントの機密残高の記録
+    pub async fn account(&self, address: &str) -> Result<Option<ConfidentialAccount>> {
+        ConfidentialAccount::load(self.storage.as_ref(), &address.to_string()).await
+    }
+
+    pub async fn stats(&self) -> ConfidentialStats {
+        self.stats.lock().await.clone()
+    }
+
+    /// 受付時の検証（送信者の確定した残高に対して範囲証明を検証する）
+    ///
+    /// 同じ送信者の確定前の送金は考慮しないため、続けて送る場合は前の送金の確定を待ちます。
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<(), ConfidentialError> {
+        match ConfidentialOp::of(tx) {
+            None => Ok(()),
+            Some(Err(e)) => Err(e),
+            Some(Ok(ConfidentialOp::Deposit)) => check_deposit(tx),
+            Some(Ok(ConfidentialOp::Transfer { commitment, proof })) => {
+                let balance = self.balance(&tx.from).await.map_err(|_| ConfidentialError::InvalidCommitment)?;
+                verify_transfer(tx, &balance, &commitment, &proof).map(|_| ())
+            }
+        }
+    }
+
+    /// ブロックに含められない機密トランザクション（と同じ送信者の後続のもの）を除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let mut balances = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            match self.apply(&mut balances, &tx).await {
+                Ok(()) => kept.push(tx),
+                Err(e) => {
+                    debug!("Leaving confidential transaction {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// 操作を検証して `balances` に反映する
+    async fn apply(
+        &self,
+        balances: &mut HashMap<String, RistrettoPoint>,
+        tx: &PendingTransaction,
+    ) -> Result<(), ConfidentialError> {
+        let op = match ConfidentialOp::of(tx) {
+            None => return Ok(()),
+            Some(op) => op?,
+        };
+        match op {
+            ConfidentialOp::Deposit => {
+                check_deposit(tx)?;
+                let from = self.current(balances, &tx.from).await? + Opening::deposit(tx.value).commitment();
+                balances.insert(tx.from.clone(), from);
+                self.stats.lock().await.deposits += 1;
+            }
+            ConfidentialOp::Transfer { commitment, proof } => {
+                let from = self.current(balances, &tx.from).await?;
+                let to = self.current(balances, &tx.to).await?;
+                let started = Instant::now();
+                let result = verify_transfer(tx, &from, &commitment, &proof);
+                self.stats.lock().await.record_verification(started, proof.len(), result.is_ok());
+                let (remaining, amount) = result?;
+                balances.insert(tx.from.clone(), remaining);
+                // 自分宛ての送金は送信後の残高に加える
+                let to = if tx.to == tx.from { remaining } else { to };
+                balances.insert(tx.to.clone(), to + amount);
+            }
+        }
+        Ok(())
+    }
+
+    /// 反映中の残高（なければ確定した残高）
+    async fn current(
+        &self,
+        balances: &HashMap<String, RistrettoPoint>,
+        address: &str,
+    ) -> Result<RistrettoPoint, ConfidentialError> {
+        match balances.get(address) {
+            Some(point) => Ok(*point),
+            None => self.balance(address).await.map_err(|_| ConfidentialError::InvalidCommitment),
+        }
+    }
+
+    /// 確定したブロックの操作を順に検証して適用する（失敗した操作は状態を変えない）
+    ///
+    /// 反映済みの高さ以下のブロックは無視します（入金を二重に反映しないため）。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        let mut balances = HashMap::new();
+        for tx in &block.transactions {
+            if let Err(e) = self.apply(&mut balances, tx).await {
+                warn!("Confidential transaction {} in block {} was not applied: {}", tx.hash, block.height, e);
+            }
+        }
+        let mut batch = balances.into_iter()
+            .map(|(address, point)| ConfidentialAccount {
+                address,
+                commitment: hex::encode(point.compress().as_bytes()),
+                updated_at: block.height,
+            }.put_change())
+            .collect::<Result<Vec<_>>>()?;
+        batch.push((APPLIED_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        self.storage.batch_write(batch).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 最新のブロックまでストレージから読み直して反映する
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=head {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 以降の確定で操作を適用する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up the confidential ledger: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply confidential transactions: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up the confidential ledger: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// 入金の宛先と額を確認
+fn check_deposit(tx: &PendingTransaction) -> Result<(), ConfidentialError> {
+    if tx.to != CONFIDENTIAL_ADDRESS {
+        return Err(ConfidentialError::DepositRecipient(tx.to.clone()));
+    }
+    if tx.value == 0 {
+        return Err(ConfidentialError::EmptyDeposit);
+    }
+    Ok(())
+}
+
+fn decode_point(commitment: &str) -> Result<RistrettoPoint> {
+    let bytes = hex::decode(commitment)?;
+    if bytes.len() != 32 {
+        anyhow::bail!("Corrupted confidential balance commitment");
+    }
+    CompressedRistretto::from_slice(&bytes)
+        .decompress()
+        .ok_or_else(|| anyhow::anyhow!("Corrupted confidential balance commitment"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::core::cache::{ExportedState, MaterializedViews};
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn tx(from: &str, to: &str, value: u64, nonce: u64, op: &ConfidentialOp) -> PendingTransaction {
+        PendingTransaction { gas_limit: TRANSFER_GAS, ..PendingTransaction::test_transfer(from, to, value, nonce).with_data(op.encode()) }.rehashed()
+    }
+
+    #[tokio::test]
+    async fn test_deposit_transfer_and_overspend() {
+        let storage = RedbStorage::memory();
+        let ledger = ConfidentialLedger::new(storage);
+
+        let deposit = tx("alice", CONFIDENTIAL_ADDRESS, 100, 0, &ConfidentialOp::Deposit);
+        let deposited = Block::new(1, "p".to_string(), "v".to_string(), vec![deposit]);
+        ledger.apply_block(&deposited).await.unwrap();
+        // 反映済みのブロックを再び渡しても（追いつきとの重複）入金を二重に反映しない
+        ledger.apply_block(&deposited).await.unwrap();
+        let alice = Opening::deposit(100);
+        assert_eq!(ledger.balance("alice").await.unwrap(), alice.commitment());
+
+        let (op, sent, change) = prove_transfer("alice", "bob", 1, alice, 30).unwrap();
+        let transfer = tx("alice", "bob", 0, 1, &op);
+        assert_eq!(ConfidentialOp::of(&transfer), Some(Ok(op.clone())));
+        assert_eq!(ledger.check(&transfer).await, Ok(()));
+
+        // 証明は送信者・受信者・ノンスに結び付いている
+        assert!(matches!(ledger.check(&tx("alice", "carol", 0, 1, &op)).await, Err(ConfidentialError::InvalidProof(_))));
+        let mut cheap = transfer.clone();
+        cheap.gas_limit = 21_000;
+        assert!(matches!(ledger.check(&cheap).await, Err(ConfidentialError::GasTooLow { .. })));
+
+        // 残高を超える送金は証明を作れず、他人の開示値で作った証明は検証に失敗する
+        assert_eq!(prove_transfer("alice", "bob", 1, alice, 101).unwrap_err(), ConfidentialError::InsufficientBalance);
+        let (forged, _, _) = prove_transfer("alice", "bob", 2, Opening::deposit(1_000), 500).unwrap();
+        let overspend = tx("alice", "bob", 0, 2, &forged);
+
+        let mut txs = vec![transfer.clone(), overspend.clone()];
+        ledger.retain_valid(&mut txs).await;
+        assert_eq!(txs.len(), 1);
+
+        ledger.apply_block(&Block::new(2, "p".to_string(), "v".to_string(), vec![transfer, overspend])).await.unwrap();
+        assert_eq!(ledger.balance("alice").await.unwrap(), change.commitment());
+        assert_eq!(ledger.balance("bob").await.unwrap(), sent.commitment());
+        assert_eq!(change.value, 70);
+
+        let stats = ledger.stats().await;
+        assert_eq!((stats.deposits, stats.transfers, stats.rejected), (1, 2, 2));
+        assert!(stats.proof_bytes_total > 0);
+
+        assert_eq!(ConfidentialOp::decode(b"cnfd\x01\x01\x00"), Some(Err(ConfidentialError::Truncated)));
+        assert_eq!(ConfidentialOp::decode(b"cnfd\x02\x00"), Some(Err(ConfidentialError::UnsupportedVersion(2))));
+        assert_eq!(ConfidentialOp::decode(b"memo"), None);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_conserves_supply() {
+        let storage = RedbStorage::memory();
+        let views = MaterializedViews::new(storage.clone())
+            .with_genesis(ExportedState { balances: BTreeMap::from([("alice".to_string(), 150)]), ..Default::default() });
+        let ledger = ConfidentialLedger::new(storage);
+
+        // 自分宛ての入金は拒否し、公開の残高を残したまま機密残高を作れない
+        let to_self = tx("alice", "alice", 100, 0, &ConfidentialOp::Deposit);
+        assert_eq!(ledger.check(&to_self).await, Err(ConfidentialError::DepositRecipient("alice".to_string())));
+        let deposit = tx("alice", CONFIDENTIAL_ADDRESS, 100, 1, &ConfidentialOp::Deposit);
+        assert_eq!(ledger.check(&deposit).await, Ok(()));
+
+        let block = Block::new(0, String::new(), "v".to_string(), vec![to_self, deposit]);
+        views.apply_block(&block).await.unwrap();
+        ledger.apply_block(&block).await.unwrap();
+
+        // 公開の残高と機密残高の合計は入金の前後で変わらず、預けた額は機密残高の合計と一致する
+        let public = views.balance("alice").await.unwrap();
+        assert_eq!(ledger.balance("alice").await.unwrap(), Opening::deposit(100).commitment());
+        assert_eq!(public + 100, 150);
+        assert_eq!(views.balance(CONFIDENTIAL_ADDRESS).await.unwrap(), 100);
+        assert!(ledger.account(CONFIDENTIAL_ADDRESS).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,224 @@
+//! 委任先の比較（`GET /staking/market`）
+//!
+//! 登録済みのバリデーターの候補ごとに、手数料率・自己ステークの比率・期間の稼働率と
+//! 報酬から計算した年率の利回り（APR）をまとめます。ウォレットが委任先を選ぶ画面を、
+//! 複数のAPIを組み合わせずに作れるようにするためのものです。
+//!
+//! [`RewardSimulation`] は同じ数値から、委任した額に対する報酬の見込み（`GET /staking/simulate`）を計算します。
+//!
+//! 報酬はブロックの生成で受け取った手数料（`MaterializedViews::rewards`）です。委任の台帳はまだないため、
+//! 総ステークは自己ステークと同じで、自己ステークの比率は常に1です。
+
+use std::collections::HashMap;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::core::cache::{ArchiveRange, MaterializedViews};
+use crate::core::cache::views::MAX_ARCHIVE_PAGE;
+use super::performance::{PerformanceTracker, ValidatorPerformance};
+use super::registry::{MAX_COMMISSION_BPS, ValidatorCandidate, ValidatorRegistry};
+
+/// 既定の集計期間（APRは短い期間ほど報酬のばらつきが大きいため、最も長い期間にする）
+pub const DEFAULT_WINDOW: &str = "7d";
+/// 1年の秒数（APRの換算に使う）
+const YEAR_SECS: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+/// 報酬の見込みを計算できる最長の日数
+pub const MAX_SIMULATION_DAYS: u32 = 3650;
+/// 候補を読む1回の件数
+const CANDIDATE_BATCH: usize = 1000;
+
+/// 委任先の候補の比較
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MarketEntry {
+    pub validator: String,
+    /// 手数料率（ベーシスポイント）
+    pub commission_bps: u16,
+    pub self_stake: u64,
+    /// 自己ステークと委任の合計
+    pub total_stake: u64,
+    /// `self_stake / total_stake`
+    pub self_stake_ratio: f64,
+    /// 期間の報酬の合計
+    pub rewards: u64,
+    /// 委任者の年率の利回り（手数料率を引いた報酬を総ステークで割り、1年に換算したもの）
+    pub apr: f64,
+    /// 担当スロットで提案した割合（期間に担当スロットがない場合は `None`）
+    pub uptime: Option<f64>,
+    /// 期間の提案数
+    pub proposed: u64,
+    /// 期間に担当スロットで提案しなかった数
+    pub missed: u64,
+}
+
+impl MarketEntry {
+    pub fn new(candidate: &ValidatorCandidate, performance: Option<&ValidatorPerformance>, rewards: u64, window_secs: u64) -> Self {
+        let total_stake = candidate.self_stake;
+        let (proposed, missed) = performance.map_or((0, 0), |p| (p.proposed, p.missed));
+        let delegators_share = 1.0 - candidate.commission_bps.min(MAX_COMMISSION_BPS) as f64 / MAX_COMMISSION_BPS as f64;
+        let apr = if total_stake == 0 || window_secs == 0 {
+            0.0
+        } else {
+            rewards as f64 * delegators_share / total_stake as f64 * (YEAR_SECS / window_secs as f64)
+        };
+        Self {
+            validator: candidate.address.clone(),
+            commission_bps: candidate.commission_bps,
+            self_stake: candidate.self_stake,
+            total_stake,
+            self_stake_ratio: if total_stake == 0 { 0.0 } else { candidate.self_stake as f64 / total_stake as f64 },
+            rewards,
+            apr,
+            uptime: (proposed + missed > 0).then(|| proposed as f64 / (proposed + missed) as f64),
+            proposed,
+            missed,
+        }
+    }
+}
+
+/// 全ての候補の比較（期間が不明な場合は `None`）
+pub async fn market(
+    registry: &ValidatorRegistry,
+    performance: &PerformanceTracker,
+    views: &MaterializedViews,
+    window: &str,
+    now: u64,
+) -> Result<Option<(u64, Vec<MarketEntry>)>> {
+    let Some(report) = performance.report(window, now).await else {
+        return Ok(None);
+    };
+    let by_validator: HashMap<&str, &ValidatorPerformance> = report.validators.iter()
+        .map(|p| (p.validator.as_str(), p))
+        .collect();
+    let range = ArchiveRange { from: Some(now.saturating_sub(report.window_secs)), to: Some(now) };
+
+    let mut entries = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let candidates = registry.list(after.as_deref(), CANDIDATE_BATCH).await?;
+        for candidate in &candidates {
+            let rewards = rewards(views, &candidate.address, range).await?;
+            entries.push(MarketEntry::new(candidate, by_validator.get(candidate.address.as_str()).copied(), rewards, report.window_secs));
+        }
+        if candidates.len() < CANDIDATE_BATCH {
+            return Ok(Some((report.window_secs, entries)));
+        }
+        after = candidates.last().map(|candidate| candidate.address.clone());
+    }
+}
+
+/// 1人の候補の比較（期間が不明な場合は `None`）
+pub async fn entry(
+    performance: &PerformanceTracker,
+    views: &MaterializedViews,
+    candidate: &ValidatorCandidate,
+    window: &str,
+    now: u64,
+) -> Result<Option<(u64, MarketEntry)>> {
+    let Some(report) = performance.report(window, now).await else {
+        return Ok(None);
+    };
+    let range = ArchiveRange { from: Some(now.saturating_sub(report.window_secs)), to: Some(now) };
+    let rewards = rewards(views, &candidate.address, range).await?;
+    let performance = report.validators.iter().find(|p| p.validator == candidate.address);
+    Ok(Some((report.window_secs, MarketEntry::new(candidate, performance, rewards, report.window_secs))))
+}
+
+/// 委任した額に対する報酬の見込み
+///
+/// 期間の報酬と手数料率が続くと仮定し、委任した額を加えた総ステークで報酬を分け合うとして計算します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RewardSimulation {
+    pub validator: String,
+    /// 委任する額
+    pub amount: u64,
+    /// 見込みの日数
+    pub days: u32,
+    pub commission_bps: u16,
+    /// 期間の実績のAPR（委任する前の総ステークに対する値）
+    pub historical_apr: f64,
+    /// 委任した後のAPR（委任した額を総ステークに加えた値）
+    pub projected_apr: f64,
+    /// 期間の稼働率
+    pub uptime: Option<f64>,
+    /// 1日あたりの報酬の見込み
+    pub daily_rewards: f64,
+    /// `days` 日間の報酬の見込み（切り捨て）
+    pub expected_rewards: u64,
+}
+
+impl RewardSimulation {
+    pub fn new(entry: &MarketEntry, amount: u64, days: u32) -> Self {
+        let total_stake = entry.total_stake.saturating_add(amount);
+        let projected_apr = if total_stake == 0 {
+            0.0
+        } else {
+            entry.apr * entry.total_stake as f64 / total_stake as f64
+        };
+        let daily_rewards = amount as f64 * projected_apr / 365.0;
+        Self {
+            validator: entry.validator.clone(),
+            amount,
+            days,
+            commission_bps: entry.commission_bps,
+            historical_apr: entry.apr,
+            projected_apr,
+            uptime: entry.uptime,
+            daily_rewards,
+            expected_rewards: (daily_rewards * days as f64).floor() as u64,
+        }
+    }
+}
+
+/// 期間の報酬の合計
+async fn rewards(views: &MaterializedViews, validator: &str, range: ArchiveRange) -> Result<u64> {
+    let mut total = 0u64;
+    let mut cursor = None;
+    loop {
+        let page = views.rewards(validator, range, cursor.as_deref(), MAX_ARCHIVE_PAGE).await?;
+        total = page.items.iter().fold(total, |sum, reward| sum.saturating_add(reward.fees));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_annualizes_delegator_rewards() {
+        let candidate = ValidatorCandidate {
+            address: "ab".repeat(32),
+            self_stake: 1_000_000,
+            commission_bps: 1_000,
+            registered_at: 0,
+        };
+        let performance = ValidatorPerformance {
+            validator: candidate.address.clone(),
+            proposed: 95,
+            missed: 5,
+            miss_rate: 0.05,
+            votes: 0,
+            avg_vote_latency_ms: None,
+            max_vote_latency_ms: None,
+            last_proposed_height: Some(100),
+        };
+
+        // 7日で 2000 の報酬、手数料率10% → (2000 * 0.9 / 1,000,000) * (365 / 7)
+        let entry = MarketEntry::new(&candidate, Some(&performance), 2_000, 604_800);
+        assert!((entry.apr - 0.0018 * 365.0 / 7.0).abs() < 1e-12);
+        assert_eq!(entry.uptime, Some(0.95));
+        assert_eq!((entry.total_stake, entry.self_stake_ratio), (1_000_000, 1.0));
+
+        // 期間に担当スロットのない候補は稼働率を出さない
+        let idle = MarketEntry::new(&candidate, None, 0, 604_800);
+        assert_eq!((idle.uptime, idle.apr), (None, 0.0));
+
+        // 同じ額を委任すると総ステークが倍になり、APRは半分になる
+        let simulation = RewardSimulation::new(&entry, 1_000_000, 365);
+        assert!((simulation.projected_apr - entry.apr / 2.0).abs() < 1e-12);
+        assert_eq!(simulation.expected_rewards, (1_000_000.0 * entry.apr / 2.0).floor() as u64);
+    }
+}
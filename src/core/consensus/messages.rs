@@ -0,0 +1,267 @@
+//! HotStuff の合意形成のメッセージ
+//!
+//! 提案・投票・クォーラム証明（QC）と、その正規のワイヤーフォーマットを定義します。
+//! 順序は（高さ, ラウンド）の辞書順です。
+//!
+//! ワイヤーフォーマット（`Message::Consensus` のペイロード）は、先頭1バイトのバージョンと、
+//! それに続く `ConsensusMessage` の bincode（固定長整数・リトルエンディアン、文字列と配列は
+//! u64 の長さプレフィックス）です。同じメッセージは常に同じバイト列になるよう、
+//! QCの投票者は昇順で重複なしとし、それ以外の並びはエンコードもデコードも拒否します。
+//!
+//! ローリングアップグレードの間は古いバージョンのノードとも通信できるよう、
+//! フォーマットを変える場合は `WIRE_VERSION` を上げ、`MIN_WIRE_VERSION` までの古い
+//! バージョンのデコードを残します。新しいノードは全ノードの更新が済むまで古いバージョンで
+//! 送ります。対応していないバージョンは `UnsupportedVersion` として区別できるため、
+//! 受信側は接続を切らずにそのメッセージだけを無視できます。
+//! `testdata/golden_v1.txt` の正解ベクトルは、バージョン1のフォーマットを変えていないことを確認します。
+
+use bincode::Options;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+/// 送信するワイヤーフォーマットのバージョン
+pub const WIRE_VERSION: u8 = 1;
+/// デコードできる最も古いバージョン
+pub const MIN_WIRE_VERSION: u8 = 1;
+/// 合意形成のメッセージの最大サイズ（バイト）
+pub const MAX_CONSENSUS_MESSAGE_SIZE: u64 = 256 * 1024;
+
+/// ブロックの提案
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Proposal {
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: String,
+    pub parent_hash: String,
+    pub proposer: String,
+    /// 親ブロックのQC（ジェネシスの直後は `None`）
+    pub justify: Option<QuorumCert>,
+}
+
+/// 投票
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Vote {
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: String,
+    pub voter: String,
+}
+
+/// クォーラム証明（2/3以上の投票を集めたブロック）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct QuorumCert {
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: String,
+    /// 投票したバリデーター（昇順・重複なし）
+    pub voters: Vec<String>,
+}
+
+/// 合意形成のメッセージ（バリアントの順番はワイヤーフォーマットの一部なので変えないこと）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum ConsensusMessage {
+    Proposal(Proposal),
+    Vote(Vote),
+    QuorumCert(QuorumCert),
+}
+
+#[derive(Error, Debug)]
+pub enum ConsensusDecodeError {
+    #[error("入力が空です")]
+    Empty,
+
+    #[error("入力サイズが上限を超えています: {size} > {limit}")]
+    TooLarge { size: usize, limit: u64 },
+
+    #[error("対応していないバージョンです: {version}（{min}〜{max} に対応）")]
+    UnsupportedVersion { version: u8, min: u8, max: u8 },
+
+    #[error("正規のエンコーディングではありません: {0}")]
+    NonCanonical(String),
+
+    #[error("不正なエンコーディング: {0}")]
+    Malformed(String),
+}
+
+impl Proposal {
+    pub fn rank(&self) -> (u64, u32) {
+        (self.height, self.round)
+    }
+}
+
+impl Vote {
+    pub fn rank(&self) -> (u64, u32) {
+        (self.height, self.round)
+    }
+}
+
+impl QuorumCert {
+    /// 投票者を昇順に並べ、重複を取り除いて作成
+    pub fn new(height: u64, round: u32, block_hash: String, mut voters: Vec<String>) -> Self {
+        voters.sort();
+        voters.dedup();
+        Self { height, round, block_hash, voters }
+    }
+
+    pub fn rank(&self) -> (u64, u32) {
+        (self.height, self.round)
+    }
+
+    fn check_canonical(&self) -> Result<(), ConsensusDecodeError> {
+        if self.voters.windows(2).all(|pair| pair[0] < pair[1]) {
+            Ok(())
+        } else {
+            Err(ConsensusDecodeError::NonCanonical("QC voters must be sorted and unique".to_string()))
+        }
+    }
+}
+
+/// 上限付きのbincode設定
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(MAX_CONSENSUS_MESSAGE_SIZE)
+        .reject_trailing_bytes()
+}
+
+impl ConsensusMessage {
+    fn check_canonical(&self) -> Result<(), ConsensusDecodeError> {
+        match self {
+            Self::Proposal(proposal) => proposal.justify.as_ref().map_or(Ok(()), QuorumCert::check_canonical),
+            Self::Vote(_) => Ok(()),
+            Self::QuorumCert(qc) => qc.check_canonical(),
+        }
+    }
+
+    /// 現在のバージョンでエンコード（正規でないメッセージはエラー）
+    pub fn encode(&self) -> Result<Vec<u8>, ConsensusDecodeError> {
+        self.check_canonical()?;
+        let mut bytes = vec![WIRE_VERSION];
+        options()
+            .serialize_into(&mut bytes, self)
+            .map_err(|e| ConsensusDecodeError::Malformed(e.to_string()))?;
+        if bytes.len() as u64 > MAX_CONSENSUS_MESSAGE_SIZE {
+            return Err(ConsensusDecodeError::TooLarge { size: bytes.len(), limit: MAX_CONSENSUS_MESSAGE_SIZE });
+        }
+        Ok(bytes)
+    }
+
+    /// ワイヤー上のバイト列からデコード
+    ///
+    /// 入力は信頼できないため、サイズ上限を超える入力・長さプレフィックス、末尾の余分なバイト、
+    /// 正規でない並びはすべてエラーとして扱います。
+    pub fn decode(bytes: &[u8]) -> Result<Self, ConsensusDecodeError> {
+        let (&version, body) = bytes.split_first().ok_or(ConsensusDecodeError::Empty)?;
+        if bytes.len() as u64 > MAX_CONSENSUS_MESSAGE_SIZE {
+            return Err(ConsensusDecodeError::TooLarge { size: bytes.len(), limit: MAX_CONSENSUS_MESSAGE_SIZE });
+        }
+        if !(MIN_WIRE_VERSION..=WIRE_VERSION).contains(&version) {
+            return Err(ConsensusDecodeError::UnsupportedVersion {
+                version,
+                min: MIN_WIRE_VERSION,
+                max: WIRE_VERSION,
+            });
+        }
+        let message: Self = options()
+            .deserialize(body)
+            .map_err(|e| ConsensusDecodeError::Malformed(e.to_string()))?;
+        message.check_canonical()?;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// バージョン1の正解ベクトル（名前と hex）
+    const GOLDEN_V1: &str = include_str!("testdata/golden_v1.txt");
+
+    fn qc(height: u64, round: u32, hash: &str, voters: &[&str]) -> QuorumCert {
+        QuorumCert::new(height, round, hash.to_string(), voters.iter().map(|v| v.to_string()).collect())
+    }
+
+    fn golden_messages() -> Vec<(&'static str, ConsensusMessage)> {
+        vec![
+            ("proposal", ConsensusMessage::Proposal(Proposal {
+                height: 7,
+                round: 1,
+                block_hash: "b7".to_string(),
+                parent_hash: "b6".to_string(),
+                proposer: "v1".to_string(),
+                justify: Some(qc(6, 0, "b6", &["v3", "v1", "v2"])),
+            })),
+            ("proposal_without_justify", ConsensusMessage::Proposal(Proposal {
+                height: 1,
+                round: 0,
+                block_hash: "b1".to_string(),
+                parent_hash: "b0".to_string(),
+                proposer: "v1".to_string(),
+                justify: None,
+            })),
+            ("vote", ConsensusMessage::Vote(Vote {
+                height: 7,
+                round: 1,
+                block_hash: "b7".to_string(),
+                voter: "v2".to_string(),
+            })),
+            ("quorum_cert", ConsensusMessage::QuorumCert(qc(7, 1, "b7", &["v2", "v1", "v2"]))),
+        ]
+    }
+
+    #[test]
+    fn test_golden_vectors_v1() {
+        let vectors: Vec<(&str, Vec<u8>)> = GOLDEN_V1.lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (name, hex) = line.split_once(' ').unwrap();
+                (name, hex::decode(hex.trim()).unwrap())
+            })
+            .collect();
+        let messages = golden_messages();
+        assert_eq!(vectors.len(), messages.len());
+
+        for ((name, bytes), (expected_name, message)) in vectors.iter().zip(&messages) {
+            assert_eq!(name, expected_name);
+            assert_eq!(&ConsensusMessage::decode(bytes).unwrap(), message, "{} decodes", name);
+            assert_eq!(&message.encode().unwrap(), bytes, "{} encodes", name);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_and_unknown_versions() {
+        let bytes = golden_messages()[0].1.encode().unwrap();
+
+        assert!(matches!(ConsensusMessage::decode(&[]), Err(ConsensusDecodeError::Empty)));
+        assert!(matches!(ConsensusMessage::decode(&bytes[..bytes.len() - 1]), Err(ConsensusDecodeError::Malformed(_))));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(ConsensusMessage::decode(&trailing), Err(ConsensusDecodeError::Malformed(_))));
+
+        // 新しいバージョンのノードからのメッセージは区別できるエラーにする
+        let mut future = bytes.clone();
+        future[0] = WIRE_VERSION + 1;
+        assert!(matches!(
+            ConsensusMessage::decode(&future),
+            Err(ConsensusDecodeError::UnsupportedVersion { version, .. }) if version == WIRE_VERSION + 1
+        ));
+
+        // 投票者の並びが正規でないQC
+        let unsorted = ConsensusMessage::QuorumCert(QuorumCert {
+            height: 7,
+            round: 1,
+            block_hash: "b7".to_string(),
+            voters: vec!["v2".to_string(), "v1".to_string()],
+        });
+        assert!(matches!(unsorted.encode(), Err(ConsensusDecodeError::NonCanonical(_))));
+
+        // 文字列の長さプレフィックスが上限を超える
+        let mut huge = vec![WIRE_VERSION, 1, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+        huge.extend_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(ConsensusMessage::decode(&huge), Err(ConsensusDecodeError::Malformed(_))));
+    }
+}
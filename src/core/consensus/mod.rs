@@ -1,3 +1,10 @@
+pub mod market;
+pub mod messages;
+pub mod performance;
+pub mod registry;
+pub mod safety;
+pub mod shadow;
+
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -0,0 +1,254 @@
+//! バリデーターのパフォーマンス
+//!
+//! バリデーターごとのブロック提案、担当スロットでの提案の失敗（ミス）、投票の遅延を
+//! 直近の期間（1時間・24時間・7日）で集計します。委任者がステーク先を選ぶための指標です。
+//!
+//! 集計は1分ごとのバケットで行い、最も長い期間より古いバケットは破棄します。
+//! 提案は確定したブロックから記録し、ミスと投票は合意形成の処理から `record_miss` /
+//! `record_vote` で記録します。起動時には保持期間内のブロックから提案を復元します。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use tokio::sync::{broadcast, RwLock};
+use tracing::warn;
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+
+/// 集計期間（名前と秒数）
+pub const WINDOWS: [(&str, u64); 3] = [("1h", 3_600), ("24h", 86_400), ("7d", 604_800)];
+/// 既定の集計期間
+pub const DEFAULT_WINDOW: &str = "24h";
+/// バケットの幅（秒）
+const BUCKET_SECS: u64 = 60;
+
+/// 期間の名前から秒数を取得
+pub fn window_secs(window: &str) -> Option<u64> {
+    WINDOWS.iter().find(|(name, _)| *name == window).map(|(_, secs)| *secs)
+}
+
+/// 1分間の集計
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    proposed: u64,
+    missed: u64,
+    votes: u64,
+    vote_latency_total_ms: u64,
+    vote_latency_max_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct ValidatorRecord {
+    /// バケットの開始時刻（UNIX秒）ごとの集計
+    buckets: BTreeMap<u64, Bucket>,
+    /// 最後に提案したブロックの高さ
+    last_proposed_height: Option<u64>,
+}
+
+/// 期間内のバリデーターのパフォーマンス
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ValidatorPerformance {
+    pub validator: String,
+    /// 確定したブロックの提案数
+    pub proposed: u64,
+    /// 担当スロットで提案しなかった数
+    pub missed: u64,
+    /// `missed / (proposed + missed)`（担当スロットがない場合は0）
+    pub miss_rate: f64,
+    /// 投票数
+    pub votes: u64,
+    /// ブロックの提案から投票までの平均時間（ミリ秒）
+    pub avg_vote_latency_ms: Option<f64>,
+    /// ブロックの提案から投票までの最大時間（ミリ秒）
+    pub max_vote_latency_ms: Option<u64>,
+    pub last_proposed_height: Option<u64>,
+}
+
+/// パフォーマンスの一覧
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PerformanceReport {
+    /// 集計期間（`1h`、`24h`、`7d`）
+    pub window: String,
+    pub window_secs: u64,
+    /// 集計時刻（UNIX秒）
+    pub generated_at: u64,
+    /// 提案数の多い順
+    pub validators: Vec<ValidatorPerformance>,
+}
+
+/// バリデーターのパフォーマンスの集計
+#[derive(Debug, Default)]
+pub struct PerformanceTracker {
+    validators: RwLock<HashMap<String, ValidatorRecord>>,
+}
+
+impl PerformanceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ブロックの提案を記録
+    pub async fn record_proposal(&self, validator: &str, height: u64, at: u64) {
+        let mut validators = self.validators.write().await;
+        let record = validators.entry(validator.to_string()).or_default();
+        record.last_proposed_height = record.last_proposed_height.max(Some(height));
+        Self::bucket(record, at).proposed += 1;
+    }
+
+    /// 担当スロットでの提案の失敗を記録
+    pub async fn record_miss(&self, validator: &str, at: u64) {
+        let mut validators = self.validators.write().await;
+        Self::bucket(validators.entry(validator.to_string()).or_default(), at).missed += 1;
+    }
+
+    /// 投票とその遅延を記録
+    pub async fn record_vote(&self, validator: &str, at: u64, latency_ms: u64) {
+        let mut validators = self.validators.write().await;
+        let bucket = Self::bucket(validators.entry(validator.to_string()).or_default(), at);
+        bucket.votes += 1;
+        bucket.vote_latency_total_ms = bucket.vote_latency_total_ms.saturating_add(latency_ms);
+        bucket.vote_latency_max_ms = bucket.vote_latency_max_ms.max(latency_ms);
+    }
+
+    fn bucket(record: &mut ValidatorRecord, at: u64) -> &mut Bucket {
+        // 最も長い期間より古いバケットを破棄する
+        let retention = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        let oldest = at.saturating_sub(retention) / BUCKET_SECS * BUCKET_SECS;
+        record.buckets = record.buckets.split_off(&oldest);
+        record.buckets.entry(at / BUCKET_SECS * BUCKET_SECS).or_default()
+    }
+
+    /// `now` までの `window_secs` 秒間のパフォーマンス（提案数の多い順）
+    pub async fn report(&self, window: &str, now: u64) -> Option<PerformanceReport> {
+        let window_secs = window_secs(window)?;
+        let since = now.saturating_sub(window_secs);
+        let validators = self.validators.read().await;
+        let mut performance: Vec<ValidatorPerformance> = validators.iter()
+            .filter_map(|(validator, record)| {
+                let total = record.buckets.range(since / BUCKET_SECS * BUCKET_SECS..)
+                    .fold(Bucket::default(), |mut total, (_, bucket)| {
+                        total.proposed += bucket.proposed;
+                        total.missed += bucket.missed;
+                        total.votes += bucket.votes;
+                        total.vote_latency_total_ms = total.vote_latency_total_ms.saturating_add(bucket.vote_latency_total_ms);
+                        total.vote_latency_max_ms = total.vote_latency_max_ms.max(bucket.vote_latency_max_ms);
+                        total
+                    });
+                if total.proposed + total.missed + total.votes == 0 {
+                    return None;
+                }
+                let slots = total.proposed + total.missed;
+                Some(ValidatorPerformance {
+                    validator: validator.clone(),
+                    proposed: total.proposed,
+                    missed: total.missed,
+                    miss_rate: if slots == 0 { 0.0 } else { total.missed as f64 / slots as f64 },
+                    votes: total.votes,
+                    avg_vote_latency_ms: (total.votes > 0)
+                        .then(|| total.vote_latency_total_ms as f64 / total.votes as f64),
+                    max_vote_latency_ms: (total.votes > 0).then_some(total.vote_latency_max_ms),
+                    last_proposed_height: record.last_proposed_height,
+                })
+            })
+            .collect();
+        performance.sort_by(|a, b| b.proposed.cmp(&a.proposed).then_with(|| a.validator.cmp(&b.validator)));
+        Some(PerformanceReport {
+            window: window.to_string(),
+            window_secs,
+            generated_at: now,
+            validators: performance,
+        })
+    }
+
+    async fn apply_block(&self, block: &Block) {
+        self.record_proposal(&block.validator, block.height, block.timestamp).await;
+    }
+
+    /// 保持期間内のブロックから提案を復元し、復元した最新の高さを返す
+    async fn backfill(&self, chain: &Chain) -> Option<u64> {
+        let (head, _) = chain.head().await?;
+        let retention = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        let since = unix_now().saturating_sub(retention);
+        for height in (0..=head).rev() {
+            match chain.get_block(height).await {
+                Ok(Some(block)) if block.timestamp >= since => self.apply_block(&block).await,
+                Ok(_) => break,
+                Err(e) => {
+                    warn!("Failed to read block {} for validator performance: {}", height, e);
+                    break;
+                }
+            }
+        }
+        Some(head)
+    }
+
+    /// ブロックの確定を購読して提案を記録し続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            let mut last = self.backfill(&chain).await;
+            loop {
+                match commits.recv().await {
+                    Ok(block) => {
+                        if last.map_or(true, |last| block.height > last) {
+                            self.apply_block(&block).await;
+                            last = Some(block.height);
+                        }
+                    }
+                    // 取りこぼした場合はストレージから読み直す
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let Some((head, _)) = chain.head().await else {
+                            continue;
+                        };
+                        for height in last.map_or(0, |last| last + 1)..=head {
+                            if let Ok(Some(block)) = chain.get_block(height).await {
+                                self.apply_block(&block).await;
+                            }
+                        }
+                        last = Some(head);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rolling_windows() {
+        let tracker = PerformanceTracker::new();
+        let now = 10 * 86_400;
+        // 2日前の提案は24時間の集計に含まれない
+        tracker.record_proposal("alice", 1, now - 2 * 86_400).await;
+        tracker.record_proposal("alice", 5, now - 60).await;
+        tracker.record_proposal("bob", 4, now - 120).await;
+        tracker.record_proposal("bob", 6, now - 30).await;
+        tracker.record_miss("alice", now - 90).await;
+        tracker.record_vote("alice", now - 60, 100).await;
+        tracker.record_vote("alice", now - 30, 300).await;
+
+        let day = tracker.report("24h", now).await.unwrap();
+        let names: Vec<&str> = day.validators.iter().map(|v| v.validator.as_str()).collect();
+        assert_eq!(names, ["bob", "alice"]);
+        let alice = &day.validators[1];
+        assert_eq!((alice.proposed, alice.missed, alice.votes), (1, 1, 2));
+        assert_eq!(alice.miss_rate, 0.5);
+        assert_eq!((alice.avg_vote_latency_ms, alice.max_vote_latency_ms), (Some(200.0), Some(300)));
+        assert_eq!(alice.last_proposed_height, Some(5));
+
+        let week = tracker.report("7d", now).await.unwrap();
+        assert_eq!(week.validators.iter().find(|v| v.validator == "alice").unwrap().proposed, 2);
+        assert!(tracker.report("30d", now).await.is_none());
+    }
+}
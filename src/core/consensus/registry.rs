@@ -0,0 +1,203 @@
+//! バリデーターの候補の登録
+//!
+//! 候補に加えるには、コンセンサス鍵の所有証明（proof-of-possession）と最低限の自己ステークが必要です。
+//! 所有証明は [`pop_message`] に対するコンセンサス鍵の ed25519 署名で、ドメインとチェーンIDを含むため
+//! トランザクションの署名や他のチェーンの登録には使い回せません。
+//!
+//! 自己ステークはコンセンサス鍵のアドレス（公開鍵のhex）の残高から出します。登録時に
+//! `validator.min_stake` 以上で、ベスティングでロック中の額を除いた残高を超えないことを確認します。
+
+use std::sync::Arc;
+use anyhow::Result;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tracing::info;
+use utoipa::ToSchema;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+use crate::core::vesting::VestingLedger;
+use crate::core::wallet;
+
+/// 所有証明の署名対象の先頭（トランザクションの署名と区別する）
+pub const POP_DOMAIN: &[u8] = b"rustorium/validator-pop/v1";
+/// 手数料率の上限（ベーシスポイント、100%）
+pub const MAX_COMMISSION_BPS: u16 = 10_000;
+
+/// 所有証明の署名対象
+pub fn pop_message(chain_id: u64, key: &VerifyingKey) -> Vec<u8> {
+    let mut message = POP_DOMAIN.to_vec();
+    message.extend_from_slice(&chain_id.to_be_bytes());
+    message.extend_from_slice(key.as_bytes());
+    message
+}
+
+/// コンセンサス鍵で所有証明を作成（hex）
+pub fn prove_possession(key: &SigningKey, chain_id: u64) -> String {
+    hex::encode(key.sign(&pop_message(chain_id, &key.verifying_key())).to_bytes())
+}
+
+/// 登録の拒否の理由
+#[derive(Debug, Error)]
+pub enum RegistrationError {
+    #[error("consensus key must be a 32-byte ed25519 public key in hex")]
+    InvalidKey,
+
+    #[error("proof of possession is not a valid signature by the consensus key for chain {0}")]
+    InvalidProof(u64),
+
+    #[error("self-stake {offered} is below the minimum {required}")]
+    BelowMinimum { required: u64, offered: u64 },
+
+    #[error("self-stake {offered} exceeds the unlocked balance {unlocked} of {address}")]
+    ExceedsBalance { address: String, offered: u64, unlocked: u64 },
+
+    #[error("commission must be at most {MAX_COMMISSION_BPS} basis points, got {0}")]
+    InvalidCommission(u16),
+
+    #[error("validator {0} is already registered")]
+    AlreadyRegistered(String),
+
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// 登録の申請（`POST /validators/candidates` の本文）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Registration {
+    /// コンセンサス鍵（ed25519 の公開鍵のhex、バリデーターのアドレスになる）
+    pub consensus_key: String,
+    /// 自己ステーク
+    pub self_stake: u64,
+    /// 手数料率（ベーシスポイント）
+    #[serde(default)]
+    pub commission_bps: u16,
+    /// 所有証明（[`pop_message`] への署名のhex）
+    pub proof: String,
+}
+
+/// 登録済みの候補
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "consensus/candidate", version = 1)]
+pub struct ValidatorCandidate {
+    /// バリデーターのアドレス（コンセンサス鍵のhex）
+    #[state(key)]
+    pub address: String,
+    pub self_stake: u64,
+    pub commission_bps: u16,
+    /// 登録した時刻（UNIX秒）
+    pub registered_at: u64,
+}
+
+/// バリデーターの候補の台帳
+pub struct ValidatorRegistry {
+    storage: Arc<dyn StorageEngine>,
+    vesting: Arc<VestingLedger>,
+    chain_id: u64,
+    min_stake: u64,
+}
+
+impl ValidatorRegistry {
+    pub fn new(storage: Arc<dyn StorageEngine>, vesting: Arc<VestingLedger>, chain_id: u64, min_stake: u64) -> Self {
+        Self { storage, vesting, chain_id, min_stake }
+    }
+
+    /// 所有証明と自己ステークを検証して候補に加える
+    pub async fn register(&self, registration: &Registration, now: u64) -> Result<ValidatorCandidate, RegistrationError> {
+        let key = parse_key(&registration.consensus_key)?;
+        let proof = hex::decode(&registration.proof).ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or(RegistrationError::InvalidProof(self.chain_id))?;
+        key.verify_strict(&pop_message(self.chain_id, &key), &proof)
+            .map_err(|_| RegistrationError::InvalidProof(self.chain_id))?;
+        if registration.commission_bps > MAX_COMMISSION_BPS {
+            return Err(RegistrationError::InvalidCommission(registration.commission_bps));
+        }
+
+        let address = wallet::address_of(&key);
+        if self.candidate(&address).await?.is_some() {
+            return Err(RegistrationError::AlreadyRegistered(address));
+        }
+        if registration.self_stake < self.min_stake {
+            return Err(RegistrationError::BelowMinimum { required: self.min_stake, offered: registration.self_stake });
+        }
+        let unlocked = self.vesting.report(&address, now).await?.unlocked;
+        if registration.self_stake > unlocked {
+            return Err(RegistrationError::ExceedsBalance { address, offered: registration.self_stake, unlocked });
+        }
+
+        let candidate = ValidatorCandidate {
+            address,
+            self_stake: registration.self_stake,
+            commission_bps: registration.commission_bps,
+            registered_at: now,
+        };
+        candidate.save(self.storage.as_ref()).await?;
+        info!("Registered validator candidate {} with self-stake {}", candidate.address, candidate.self_stake);
+        Ok(candidate)
+    }
+
+    /// 登録済みの候補
+    pub async fn candidate(&self, address: &str) -> Result<Option<ValidatorCandidate>> {
+        ValidatorCandidate::load(self.storage.as_ref(), &address.trim_start_matches("0x").to_lowercase()).await
+    }
+
+    /// アドレスの順に `after` の次から最大 `limit` 件
+    pub async fn list(&self, after: Option<&str>, limit: usize) -> Result<Vec<ValidatorCandidate>> {
+        let after = after.map(str::to_string);
+        ValidatorCandidate::list(self.storage.as_ref(), after.as_ref(), limit).await
+    }
+}
+
+fn parse_key(hex_key: &str) -> Result<VerifyingKey, RegistrationError> {
+    let bytes: [u8; 32] = hex::decode(hex_key.trim_start_matches("0x")).ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(RegistrationError::InvalidKey)?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| RegistrationError::InvalidKey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::block::Block;
+    use std::collections::BTreeMap;
+    use crate::core::cache::{ExportedState, MaterializedViews};
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    #[tokio::test]
+    async fn test_register_requires_proof_and_self_stake() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let genesis = ExportedState { balances: BTreeMap::from([("00".to_string(), 1500)]), ..Default::default() };
+        let views = Arc::new(MaterializedViews::new(storage.clone()).with_genesis(genesis));
+        let vesting = Arc::new(VestingLedger::new(storage.clone(), views.clone()));
+        let registry = ValidatorRegistry::new(storage, vesting, 7, 1000);
+
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let address = wallet::address_of(&key.verifying_key());
+        let funding = PendingTransaction::test_transfer("00", &address, 1500, 0);
+        views.apply_block(&Block::new(0, "p".to_string(), "v".to_string(), vec![funding])).await.unwrap();
+
+        let registration = |stake: u64, proof: String| Registration {
+            consensus_key: address.clone(),
+            self_stake: stake,
+            commission_bps: 500,
+            proof,
+        };
+        // 他のチェーン向けの証明と、他の鍵の証明は拒否する
+        let other = SigningKey::from_bytes(&[4; 32]);
+        for proof in [prove_possession(&key, 8), prove_possession(&other, 7)] {
+            assert!(matches!(registry.register(&registration(1200, proof), 1).await, Err(RegistrationError::InvalidProof(7))));
+        }
+        let proof = prove_possession(&key, 7);
+        assert!(matches!(registry.register(&registration(999, proof.clone()), 1).await,
+            Err(RegistrationError::BelowMinimum { required: 1000, offered: 999 })));
+        assert!(matches!(registry.register(&registration(1501, proof.clone()), 1).await,
+            Err(RegistrationError::ExceedsBalance { unlocked: 1500, .. })));
+
+        let candidate = registry.register(&registration(1200, proof.clone()), 1).await.unwrap();
+        assert_eq!(registry.candidate(&address).await.unwrap(), Some(candidate.clone()));
+        assert_eq!(registry.list(None, 10).await.unwrap(), vec![candidate]);
+        assert!(matches!(registry.register(&registration(1200, proof), 1).await, Err(RegistrationError::AlreadyRegistered(_))));
+    }
+}
@@ -0,0 +1,214 @@
+//! 合意形成の安全性の状態の永続化
+//!
+//! バリデーターが投票する前に、最後の投票とロックしたQCをストレージへ書き込みます。
+//! 投票を送った後にクラッシュしても、再起動時に状態を復元するため、同じ（高さ, ラウンド）で
+//! 別のブロックに投票する（二重投票）ことや、ロックに反する投票をすることはありません。
+//! 主な機能：
+//! - 投票済みの（高さ, ラウンド）以下への投票の拒否（同じ提案への再送は許可）
+//! - ロックしたQCより古いQCを根拠とする提案への投票の拒否
+//! - 状態を書き込めなかった場合は投票しない
+//!
+//! メインチェーンではブロックの生成者が [`SafetyRules::open`] の状態で、署名する前に
+//! ブロックを投票として記録します（高さごとにラウンド0から、作り直すたびにラウンドを進める）。
+//! ビーコンチェーンは [`SafetyRules::open_at`] で別のキーに保存します。
+
+use std::sync::Arc;
+use anyhow::anyhow;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::core::storage::StorageEngine;
+use super::messages::{Proposal, QuorumCert, Vote};
+
+/// 安全性の状態を保存するキー
+const SAFETY_KEY: &[u8] = b"consensus/safety";
+
+/// 投票を拒否した理由
+#[derive(Debug, Error)]
+pub enum SafetyError {
+    #[error("already voted for {voted} at height {height} round {round}")]
+    Equivocation { height: u64, round: u32, voted: String },
+    #[error("height {height} round {round} is not after the last vote at height {last_height} round {last_round}")]
+    Stale { height: u64, round: u32, last_height: u64, last_round: u32 },
+    #[error("proposal does not extend the locked block {locked} at height {height} round {round}")]
+    Locked { locked: String, height: u64, round: u32 },
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// 永続化する安全性の状態
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafetyState {
+    /// 最後に送った投票
+    pub last_vote: Option<Vote>,
+    /// ロックしたQC
+    pub locked_qc: Option<QuorumCert>,
+}
+
+/// 投票の安全性の判定
+#[derive(Debug)]
+pub struct SafetyRules {
+    /// 状態の保存先（`None` はメモリ上のみ）
+    storage: Option<Arc<dyn StorageEngine>>,
+    /// 状態を保存するキー
+    key: &'static [u8],
+    voter: String,
+    state: SafetyState,
+}
+
+impl SafetyRules {
+    /// 保存された状態を復元する（読めない場合は投票を再開しないようエラーにする）
+    pub async fn open(storage: Arc<dyn StorageEngine>, voter: impl Into<String>) -> anyhow::Result<Self> {
+        Self::open_at(storage, SAFETY_KEY, voter).await
+    }
+
+    /// 別の合意形成（ビーコンチェーンなど）の状態を `key` に保存する
+    pub async fn open_at(storage: Arc<dyn StorageEngine>, key: &'static [u8], voter: impl Into<String>) -> anyhow::Result<Self> {
+        let state = match storage.get(key).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| anyhow!("Corrupted consensus safety state: {}", e))?,
+            None => SafetyState::default(),
+        };
+        Ok(Self { storage: Some(storage), key, voter: voter.into(), state })
+    }
+
+    /// 状態をメモリ上だけで管理する（投票を送らないシャドーバリデーター用）
+    pub fn in_memory(voter: impl Into<String>) -> Self {
+        Self { storage: None, key: SAFETY_KEY, voter: voter.into(), state: SafetyState::default() }
+    }
+
+    pub fn state(&self) -> &SafetyState {
+        &self.state
+    }
+
+    /// 提案への投票を作成する
+    ///
+    /// 状態をストレージへ書き込んでから投票を返すため、返された投票は送ってかまいません。
+    /// 既に投票した提案には同じ投票を返します（再送用）。
+    pub async fn vote(&mut self, proposal: &Proposal) -> Result<Vote, SafetyError> {
+        if let Some(last) = &self.state.last_vote {
+            if last.rank() == proposal.rank() {
+                if last.block_hash == proposal.block_hash {
+                    return Ok(last.clone());
+                }
+                return Err(SafetyError::Equivocation {
+                    height: last.height,
+                    round: last.round,
+                    voted: last.block_hash.clone(),
+                });
+            }
+            if proposal.rank() < last.rank() {
+                return Err(SafetyError::Stale {
+                    height: proposal.height,
+                    round: proposal.round,
+                    last_height: last.height,
+                    last_round: last.round,
+                });
+            }
+        }
+        if let Some(locked) = &self.state.locked_qc {
+            if !extends_lock(proposal, locked) {
+                return Err(SafetyError::Locked {
+                    locked: locked.block_hash.clone(),
+                    height: locked.height,
+                    round: locked.round,
+                });
+            }
+        }
+
+        let mut next = self.state.clone();
+        // 親のQCでロックを更新する（2チェーンのロック）
+        if let Some(justify) = &proposal.justify {
+            if next.locked_qc.as_ref().map_or(true, |locked| justify.rank() > locked.rank()) {
+                next.locked_qc = Some(justify.clone());
+            }
+        }
+        let vote = Vote {
+            height: proposal.height,
+            round: proposal.round,
+            block_hash: proposal.block_hash.clone(),
+            voter: self.voter.clone(),
+        };
+        next.last_vote = Some(vote.clone());
+        self.persist(next).await?;
+        Ok(vote)
+    }
+
+    /// 観測したQCでロックを更新する（より新しい場合のみ）
+    pub async fn observe_qc(&mut self, qc: &QuorumCert) -> Result<(), SafetyError> {
+        if self.state.locked_qc.as_ref().is_some_and(|locked| qc.rank() <= locked.rank()) {
+            return Ok(());
+        }
+        let mut next = self.state.clone();
+        next.locked_qc = Some(qc.clone());
+        self.persist(next).await
+    }
+
+    /// 書き込みに成功した場合のみメモリ上の状態を更新する
+    async fn persist(&mut self, next: SafetyState) -> Result<(), SafetyError> {
+        if let Some(storage) = &self.storage {
+            let bytes = serde_json::to_vec(&next).map_err(anyhow::Error::from)?;
+            storage.put(self.key, &bytes).await?;
+        }
+        self.state = next;
+        Ok(())
+    }
+}
+
+/// 提案がロックしたブロックから続くか（HotStuff の safeNode）
+///
+/// 親のQCがロックしたQCと同じブロックか、それより新しいQCであれば安全です。
+fn extends_lock(proposal: &Proposal, locked: &QuorumCert) -> bool {
+    match &proposal.justify {
+        Some(justify) if justify.rank() == locked.rank() => justify.block_hash == locked.block_hash,
+        Some(justify) => justify.rank() > locked.rank(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn qc(height: u64, round: u32, hash: &str) -> QuorumCert {
+        QuorumCert { height, round, block_hash: hash.to_string(), voters: vec!["v1".to_string(), "v2".to_string()] }
+    }
+
+    fn proposal(height: u64, round: u32, hash: &str, justify: Option<QuorumCert>) -> Proposal {
+        Proposal {
+            height,
+            round,
+            block_hash: hash.to_string(),
+            parent_hash: justify.as_ref().map_or(String::new(), |qc| qc.block_hash.clone()),
+            proposer: "v2".to_string(),
+            justify,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restored_validator_cannot_equivocate() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+
+        let mut safety = SafetyRules::open(storage.clone(), "v1").await.unwrap();
+        let vote = safety.vote(&proposal(5, 0, "a5", Some(qc(4, 0, "a4")))).await.unwrap();
+        assert_eq!(vote.block_hash, "a5");
+
+        // 投票を送った直後にクラッシュし、再起動した
+        drop(safety);
+        let mut safety = SafetyRules::open(storage, "v1").await.unwrap();
+        assert_eq!(safety.state().locked_qc, Some(qc(4, 0, "a4")));
+        assert!(matches!(
+            safety.vote(&proposal(5, 0, "b5", Some(qc(4, 0, "a4")))).await,
+            Err(SafetyError::Equivocation { .. })
+        ));
+        assert_eq!(safety.vote(&proposal(5, 0, "a5", Some(qc(4, 0, "a4")))).await.unwrap(), vote);
+        assert!(matches!(safety.vote(&proposal(4, 3, "c4", None)).await, Err(SafetyError::Stale { .. })));
+
+        // ロックしたブロックと競合する古いQCを根拠とする提案には投票しない
+        assert!(matches!(
+            safety.vote(&proposal(5, 1, "d5", Some(qc(4, 0, "x4")))).await,
+            Err(SafetyError::Locked { .. })
+        ));
+        assert!(safety.vote(&proposal(6, 0, "a6", Some(qc(5, 0, "a5")))).await.is_ok());
+    }
+}
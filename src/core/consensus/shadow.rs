@@ -0,0 +1,295 @@
+//! シャドーバリデーター
+//!
+//! ステークを預ける前に、バリデーターの設定と性能を確認するためのモードです。
+//! 確定したブロックごとにバリデーターの処理をすべて行いますが、作成したブロックと投票は
+//! 送信も確定もせず、ログと計測値に残すだけです。
+//! 主な機能：
+//! - 担当スロットの判定（直近にブロックを生成したバリデーターと自分の、高さによるラウンドロビン）
+//! - 担当スロットでのブロックの作成・署名・検証と、その所要時間の計測
+//! - 確定したブロックへの投票（安全性の判定はメモリ上のみで行い、ノードの投票状態は変えない）
+//!
+//! オンチェーンのバリデーターセットがまだないため、担当スロットは実際の選出の近似です。
+
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::{Result, anyhow};
+use ed25519_dalek::SigningKey;
+use serde::{Serialize, Deserialize};
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+use crate::core::blob;
+use crate::core::block::{Block, Chain, limits::ConsensusParams};
+use crate::core::mempool::{self, Mempool};
+use super::messages::{ConsensusMessage, Proposal, QuorumCert};
+use super::safety::SafetyRules;
+
+/// 担当スロットの判定に使う直近のブロック数
+const SELECTION_WINDOW: usize = 100;
+
+/// シャドーモードの計測値
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ShadowReport {
+    /// このノードのバリデーターのアドレス（署名鍵がない場合はノード名）
+    pub validator: String,
+    /// 署名鍵を読み込んだか（`false` の場合、作成したブロックには署名しない）
+    pub signing: bool,
+    /// 観測した確定ブロック数
+    pub blocks_observed: u64,
+    /// 最後に観測したブロックの高さ
+    pub last_height: Option<u64>,
+    /// 担当だったスロット数
+    pub slots: u64,
+    /// 担当スロットで作成・検証できたブロック数
+    pub blocks_built: u64,
+    /// 担当スロットでブロックを作成・検証できなかった数
+    pub build_failures: u64,
+    /// 作成したブロックに含めたトランザクションの合計
+    pub built_txs: u64,
+    /// ブロックの作成から検証までの平均時間（ミリ秒）
+    pub avg_build_ms: Option<f64>,
+    pub max_build_ms: Option<u64>,
+    /// 投票数
+    pub votes: u64,
+    /// 安全性の判定で拒否した投票の数
+    pub vote_rejections: u64,
+    /// ブロックの生成から投票までの平均時間（ミリ秒）
+    pub avg_vote_latency_ms: Option<f64>,
+    pub max_vote_latency_ms: Option<u64>,
+    /// 最後のエラー
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    report: ShadowReport,
+    build_total_ms: u64,
+    vote_latency_total_ms: u64,
+}
+
+/// シャドーバリデーター
+pub struct ShadowValidator {
+    validator: String,
+    signing_key: Option<SigningKey>,
+    params: ConsensusParams,
+    mempool: Arc<RwLock<Mempool>>,
+    max_txs: usize,
+    safety: Mutex<SafetyRules>,
+    recent: Mutex<VecDeque<String>>,
+    stats: RwLock<Stats>,
+}
+
+impl ShadowValidator {
+    pub fn new(
+        validator: String,
+        signing_key: Option<SigningKey>,
+        params: ConsensusParams,
+        mempool: Arc<RwLock<Mempool>>,
+        max_txs: usize,
+    ) -> Self {
+        let stats = Stats {
+            report: ShadowReport {
+                validator: validator.clone(),
+                signing: signing_key.is_some(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Self {
+            safety: Mutex::new(SafetyRules::in_memory(validator.clone())),
+            validator,
+            signing_key,
+            params,
+            mempool,
+            max_txs,
+            recent: Mutex::new(VecDeque::with_capacity(SELECTION_WINDOW)),
+            stats: RwLock::new(stats),
+        }
+    }
+
+    /// 現在の計測値
+    pub async fn report(&self) -> ShadowReport {
+        self.stats.read().await.report.clone()
+    }
+
+    /// 確定したブロックに投票し、次の高さが担当であればブロックを作成する
+    pub async fn observe(&self, block: &Block) {
+        let leader = {
+            let mut recent = self.recent.lock().await;
+            if recent.len() == SELECTION_WINDOW {
+                recent.pop_front();
+            }
+            recent.push_back(block.validator.clone());
+            slot_leader(block.height + 1, recent.iter().chain(std::iter::once(&self.validator)))
+        };
+        {
+            let mut stats = self.stats.write().await;
+            stats.report.blocks_observed += 1;
+            stats.report.last_height = Some(block.height);
+        }
+
+        self.vote(block).await;
+        if leader == self.validator {
+            let started = Instant::now();
+            let result = self.build(block).await;
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            let mut stats = self.stats.write().await;
+            stats.report.slots += 1;
+            match result {
+                Ok(candidate) => {
+                    info!(target: "shadow",
+                        "Would propose block {} ({} txs, {} gas, {} bytes) built in {}ms; not broadcasting",
+                        candidate.height, candidate.transactions.len(), candidate.gas_used, candidate.encoded_size(), elapsed_ms);
+                    let report = &mut stats.report;
+                    report.blocks_built += 1;
+                    report.built_txs += candidate.transactions.len() as u64;
+                    report.max_build_ms = report.max_build_ms.max(Some(elapsed_ms));
+                    stats.build_total_ms = stats.build_total_ms.saturating_add(elapsed_ms);
+                    stats.report.avg_build_ms = Some(stats.build_total_ms as f64 / stats.report.blocks_built as f64);
+                }
+                Err(e) => {
+                    warn!(target: "shadow", "Failed to build block {} in our slot: {:#}", block.height + 1, e);
+                    stats.report.build_failures += 1;
+                    stats.report.last_error = Some(format!("{:#}", e));
+                }
+            }
+        }
+    }
+
+    /// 親ブロックに続くブロックを作成して署名し、確定時と同じ検証を行う
+    async fn build(&self, parent: &Block) -> Result<Block> {
+        let gas_limit = self.params.next_gas_limit(Some(parent.gas_limit));
+        let base_fee = self.params.next_base_fee(Some((parent.base_fee, parent.gas_used, parent.gas_limit)));
+        let blob_fee = self.params.next_blob_fee(Some((parent.blob_fee, parent.blob_bytes)));
+        // メモリプールからは取り出さない（実際に生成するノードのために残す）
+        let mut txs = self.mempool.write().await
+            .select_within(self.max_txs, gas_limit, self.params.transaction_bytes());
+        mempool::retain_payable(&mut txs, base_fee);
+        blob::retain_blobs(&mut txs, blob_fee, self.params.max_blob_bytes);
+
+        let mut block = Block::new(parent.height + 1, parent.hash.clone(), self.validator.clone(), txs)
+            .with_gas_limit(gas_limit)
+            .with_base_fee(base_fee)
+            .with_blob_fee(blob_fee);
+        if let Some(key) = &self.signing_key {
+            block = block.sign(key);
+        }
+        self.params.validate(&block, Some(parent.gas_limit))
+            .map_err(|e| anyhow!("Block violates consensus limits: {}", e))?;
+        block.verify_header(&self.params, base_fee)
+            .map_err(|e| anyhow!("Block has an invalid header: {}", e))?;
+        block.verify_blobs(&self.params, blob_fee)
+            .map_err(|e| anyhow!("Block has invalid blobs: {}", e))?;
+        Ok(block)
+    }
+
+    /// 確定したブロックへの投票を作成する（送信はしない）
+    async fn vote(&self, block: &Block) {
+        let proposal = Proposal {
+            height: block.height,
+            round: 0,
+            block_hash: block.hash.clone(),
+            parent_hash: block.parent_hash.clone(),
+            proposer: block.validator.clone(),
+            justify: block.height.checked_sub(1)
+                .map(|height| QuorumCert::new(height, 0, block.parent_hash.clone(), Vec::new())),
+        };
+        let result = self.safety.lock().await.vote(&proposal).await;
+        let latency_ms = unix_now_ms().saturating_sub(block.timestamp.saturating_mul(1000));
+
+        let mut stats = self.stats.write().await;
+        match result {
+            Ok(vote) => {
+                let size = ConsensusMessage::Vote(vote).encode().map_or(0, |bytes| bytes.len());
+                debug!(target: "shadow", "Would vote for block {} ({} bytes) {}ms after it was produced",
+                    block.height, size, latency_ms);
+                let report = &mut stats.report;
+                report.votes += 1;
+                report.max_vote_latency_ms = report.max_vote_latency_ms.max(Some(latency_ms));
+                stats.vote_latency_total_ms = stats.vote_latency_total_ms.saturating_add(latency_ms);
+                stats.report.avg_vote_latency_ms = Some(stats.vote_latency_total_ms as f64 / stats.report.votes as f64);
+            }
+            Err(e) => {
+                warn!(target: "shadow", "Would refuse to vote for block {}: {}", block.height, e);
+                stats.report.vote_rejections += 1;
+                stats.report.last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// ブロックの確定を購読して処理し続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            info!(target: "shadow", "Shadow validator {} started: blocks and votes are logged, not broadcast", self.validator);
+            loop {
+                match commits.recv().await {
+                    Ok(block) => self.observe(&block).await,
+                    // 過ぎたスロットの作成や投票は意味がないので読み直さない
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(target: "shadow", "Shadow validator fell behind by {} blocks", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+/// 高さ `height` の担当（候補をアドレス順に並べたラウンドロビン）
+fn slot_leader<'a>(height: u64, candidates: impl Iterator<Item = &'a String>) -> String {
+    let candidates: BTreeSet<&String> = candidates.collect();
+    candidates.iter()
+        .nth((height % candidates.len().max(1) as u64) as usize)
+        .map(|leader| leader.to_string())
+        .unwrap_or_default()
+}
+
+fn unix_now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::MempoolConfig;
+
+    #[tokio::test]
+    async fn test_builds_in_own_slots_and_votes_without_broadcasting() {
+        // 署名鍵のない候補は、署名を必須にする前の高さでのみ確定時の検証を通る
+        let params = ConsensusParams { header_activation_height: u64::MAX, ..ConsensusParams::default() };
+        let mempool = Arc::new(RwLock::new(Mempool::new(MempoolConfig::default())));
+        let shadow = ShadowValidator::new("v1".to_string(), None, params.clone(), mempool.clone(), 10);
+
+        // 候補は v1 と v2：高さ1は v2、高さ2は v1 の担当
+        let genesis = Block::new(0, String::new(), "v2".to_string(), vec![])
+            .with_gas_limit(params.next_gas_limit(None));
+        shadow.observe(&genesis).await;
+        let report = shadow.report().await;
+        assert_eq!((report.slots, report.votes), (0, 1));
+
+        let next = Block::new(1, genesis.hash.clone(), "v2".to_string(), vec![])
+            .with_gas_limit(params.next_gas_limit(Some(genesis.gas_limit)));
+        shadow.observe(&next).await;
+        let report = shadow.report().await;
+        assert_eq!(report.blocks_observed, 2);
+        assert_eq!((report.slots, report.blocks_built, report.build_failures), (1, 1, 0));
+        assert_eq!((report.votes, report.vote_rejections), (2, 0));
+
+        let unsigned = ShadowValidator::new("v1".to_string(), None, ConsensusParams::default(), mempool, 10);
+        unsigned.observe(&genesis).await;
+        unsigned.observe(&next).await;
+        let report = unsigned.report().await;
+        assert_eq!((report.slots, report.blocks_built, report.build_failures), (1, 0, 1));
+        assert!(report.last_error.unwrap().contains("not signed"));
+
+        // 同じ高さの別のブロックには投票しない
+        let conflicting = Block::new(1, genesis.hash.clone(), "v3".to_string(), vec![]);
+        shadow.vote(&conflicting).await;
+        assert_eq!(shadow.report().await.vote_rejections, 1);
+    }
+}
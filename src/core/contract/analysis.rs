@@ -0,0 +1,323 @@
+//! デプロイ時の静的解析
+//!
+//! デプロイされるバイトコードを検査し、サイズ上限の超過を拒否するとともに
+//! 既知の危険なパターンを検出します。
+//! 主な機能：
+//! - EVM: SELFDESTRUCT、呼び出しデータ由来のアドレスへの DELEGATECALL
+//! - WASM: 脱出条件のないループ
+//! - 検出結果を警告のみとするか拒否するかをネットワークごとに設定
+
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use wasmparser::{Operator, Parser, Payload};
+use crate::config::ContractSettings;
+use super::verification::strip_metadata;
+
+/// WASMモジュールのマジックナンバー
+const WASM_MAGIC: &[u8] = b"\0asm";
+/// DELEGATECALL の引数の由来を遡る命令数
+const DELEGATECALL_LOOKBACK: usize = 16;
+
+/// 検出結果の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalysisMode {
+    /// 解析しない
+    Off,
+    /// 検出しても警告のみ
+    Warn,
+    /// 検出した場合はデプロイを拒否
+    Enforce,
+}
+
+/// 解析の設定
+#[derive(Debug, Clone)]
+pub struct AnalysisConfig {
+    pub mode: AnalysisMode,
+    /// バイトコードの最大サイズ（バイト）
+    pub max_code_size: usize,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            mode: AnalysisMode::Warn,
+            max_code_size: 48 * 1024,
+        }
+    }
+}
+
+impl From<&ContractSettings> for AnalysisConfig {
+    fn from(settings: &ContractSettings) -> Self {
+        Self {
+            mode: settings.analysis_mode,
+            max_code_size: settings.max_code_size,
+        }
+    }
+}
+
+/// バイトコードの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeKind {
+    Evm,
+    Wasm,
+}
+
+/// 重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// 常に拒否
+    Error,
+    /// `enforce` モードで拒否
+    Warning,
+}
+
+/// 検出結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Finding {
+    /// ルール名
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    /// バイトコード内のオフセット（EVM）または関数インデックス（WASM）
+    pub location: Option<usize>,
+}
+
+/// 解析レポート
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnalysisReport {
+    pub kind: CodeKind,
+    pub code_size: usize,
+    pub findings: Vec<Finding>,
+    /// デプロイを拒否するか
+    pub rejected: bool,
+}
+
+/// バイトコードを解析
+pub fn analyze(code: &[u8], config: &AnalysisConfig) -> AnalysisReport {
+    let kind = if code.starts_with(WASM_MAGIC) { CodeKind::Wasm } else { CodeKind::Evm };
+    let mut findings = Vec::new();
+
+    if code.len() > config.max_code_size {
+        findings.push(Finding {
+            rule: "code-size".to_string(),
+            severity: Severity::Error,
+            message: format!("bytecode is {} bytes, limit is {}", code.len(), config.max_code_size),
+            location: None,
+        });
+    }
+
+    if config.mode != AnalysisMode::Off {
+        match kind {
+            CodeKind::Evm => analyze_evm(code, &mut findings),
+            CodeKind::Wasm => analyze_wasm(code, &mut findings),
+        }
+    }
+
+    let rejected = findings.iter().any(|f| {
+        f.severity == Severity::Error
+            || (config.mode == AnalysisMode::Enforce && f.severity == Severity::Warning)
+    });
+
+    AnalysisReport {
+        kind,
+        code_size: code.len(),
+        findings,
+        rejected,
+    }
+}
+
+/// EVMバイトコードの解析
+///
+/// 末尾のメタデータは命令ではないため、取り除いてから解析します。
+fn analyze_evm(code: &[u8], findings: &mut Vec<Finding>) {
+    const CALLDATALOAD: u8 = 0x35;
+    const CALLDATACOPY: u8 = 0x37;
+    const DELEGATECALL: u8 = 0xf4;
+    const SELFDESTRUCT: u8 = 0xff;
+    const PUSH1: u8 = 0x60;
+    const PUSH32: u8 = 0x7f;
+
+    let code = strip_metadata(code);
+    // PUSHの即値を読み飛ばしながら命令列を復元
+    let mut ops: Vec<(usize, u8)> = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        ops.push((pc, op));
+        pc += 1;
+        if (PUSH1..=PUSH32).contains(&op) {
+            pc += (op - PUSH1 + 1) as usize;
+        }
+    }
+
+    for (i, &(offset, op)) in ops.iter().enumerate() {
+        match op {
+            SELFDESTRUCT => findings.push(Finding {
+                rule: "selfdestruct".to_string(),
+                severity: Severity::Warning,
+                message: "contract can self-destruct".to_string(),
+                location: Some(offset),
+            }),
+            DELEGATECALL => {
+                let window = &ops[i.saturating_sub(DELEGATECALL_LOOKBACK)..i];
+                if window.iter().any(|&(_, op)| op == CALLDATALOAD || op == CALLDATACOPY) {
+                    findings.push(Finding {
+                        rule: "untrusted-delegatecall".to_string(),
+                        severity: Severity::Warning,
+                        message: "delegatecall target may be derived from call data".to_string(),
+                        location: Some(offset),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// WASMモジュールの解析
+fn analyze_wasm(code: &[u8], findings: &mut Vec<Finding>) {
+    if let Err(e) = scan_wasm_loops(code, findings) {
+        findings.push(Finding {
+            rule: "invalid-wasm".to_string(),
+            severity: Severity::Error,
+            message: format!("failed to parse module: {}", e),
+            location: None,
+        });
+    }
+}
+
+/// 制御構造のフレーム
+struct Frame {
+    is_loop: bool,
+    /// ループから抜ける分岐があるか
+    has_exit: bool,
+    /// ループ先頭への分岐があるか
+    has_backedge: bool,
+}
+
+/// 脱出条件のないループを検出
+///
+/// ループ先頭への分岐を持ちながら、条件分岐・return・外側への分岐を
+/// 一切含まないループを無限ループとみなします。
+fn scan_wasm_loops(code: &[u8], findings: &mut Vec<Finding>) -> wasmparser::Result<()> {
+    let mut function = 0;
+    for payload in Parser::new(0).parse_all(code) {
+        let Payload::CodeSectionEntry(body) = payload? else {
+            continue;
+        };
+
+        let mut frames = vec![Frame { is_loop: false, has_exit: false, has_backedge: false }];
+        let mut reader = body.get_operators_reader()?;
+        while !reader.eof() {
+            match reader.read()? {
+                Operator::Block { .. } | Operator::If { .. } => {
+                    frames.push(Frame { is_loop: false, has_exit: false, has_backedge: false });
+                }
+                Operator::Loop { .. } => {
+                    frames.push(Frame { is_loop: true, has_exit: false, has_backedge: false });
+                }
+                Operator::BrIf { .. } | Operator::BrTable { .. } | Operator::Return => {
+                    frames.iter_mut().for_each(|f| f.has_exit = true);
+                }
+                Operator::Br { relative_depth } => {
+                    let depth = relative_depth as usize;
+                    if depth < frames.len() {
+                        let target = frames.len() - 1 - depth;
+                        // 対象より内側のループからは抜ける
+                        frames[target + 1..].iter_mut().for_each(|f| f.has_exit = true);
+                        if frames[target].is_loop {
+                            frames[target].has_backedge = true;
+                        }
+                    }
+                }
+                Operator::End => {
+                    if let Some(frame) = frames.pop() {
+                        if frame.is_loop && frame.has_backedge && !frame.has_exit {
+                            findings.push(Finding {
+                                rule: "unbounded-loop".to_string(),
+                                severity: Severity::Warning,
+                                message: "loop has no exit condition".to_string(),
+                                location: Some(function),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        function += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evm_patterns() {
+        let config = AnalysisConfig::default();
+
+        // PUSH1 0xff は即値なので検出しない
+        let report = analyze(&[0x60, 0xff, 0x00], &config);
+        assert!(report.findings.is_empty());
+        // PUSH32 の即値の途中の 0xff も命令ではない
+        let mut push32 = vec![0x7f];
+        push32.extend([0xff; 32]);
+        assert!(analyze(&push32, &config).findings.is_empty());
+        // 末尾のCBORメタデータ（map の値の 0xff）は命令ではない
+        let report = analyze(&[0x00, 0xa1, 0x41, 0xff, 0x00, 0x03], &config);
+        assert!(report.findings.is_empty());
+
+        // CALLDATALOAD ... DELEGATECALL, SELFDESTRUCT
+        let report = analyze(&[0x60, 0x04, 0x35, 0x5a, 0xf4, 0x33, 0xff], &config);
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, vec!["untrusted-delegatecall", "selfdestruct"]);
+        assert!(!report.rejected);
+
+        let enforce = AnalysisConfig { mode: AnalysisMode::Enforce, ..Default::default() };
+        assert!(analyze(&[0x33, 0xff], &enforce).rejected);
+    }
+
+    #[test]
+    fn test_size_limit_rejects_in_warn_mode() {
+        let config = AnalysisConfig { mode: AnalysisMode::Warn, max_code_size: 4 };
+        let report = analyze(&[0x00; 5], &config);
+        assert!(report.rejected);
+        assert_eq!(report.findings[0].rule, "code-size");
+    }
+
+    #[test]
+    fn test_settings_reject_unknown_mode() {
+        let settings: ContractSettings = serde_json::from_str(r#"{"analysis_mode": "enforce"}"#).unwrap();
+        assert_eq!(AnalysisConfig::from(&settings).mode, AnalysisMode::Enforce);
+        assert!(serde_json::from_str::<ContractSettings>(r#"{"analysis_mode": "enforced"}"#).is_err());
+    }
+
+    #[test]
+    fn test_wasm_unbounded_loop() {
+        // (func (loop (br 0)))
+        let infinite = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+            0x03, 0x02, 0x01, 0x00,
+            0x0a, 0x09, 0x01, 0x07, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b,
+        ];
+        let report = analyze(&infinite, &AnalysisConfig::default());
+        assert_eq!(report.kind, CodeKind::Wasm);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, "unbounded-loop");
+
+        // (func (loop (br_if 0 (i32.const 0))))
+        let bounded = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+            0x03, 0x02, 0x01, 0x00,
+            0x0a, 0x0b, 0x01, 0x09, 0x00, 0x03, 0x40, 0x41, 0x00, 0x0d, 0x00, 0x0b, 0x0b,
+        ];
+        assert!(analyze(&bounded, &AnalysisConfig::default()).findings.is_empty());
+    }
+}
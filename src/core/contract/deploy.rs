@@ -0,0 +1,334 @@
+//! コントラクトのデプロイ
+//!
+//! デプロイは署名されたトランザクションで行います。デプロイ者はデプロイのアドレス [`DEPLOYER_ADDRESS`] 宛てに
+//! 作成バイトコードを `data` に入れて送ります（`value` は0）。操作は確定したブロックの順に
+//! 静的解析（[`super::analysis`]）を通して適用し、作成バイトコードはソース検証の照合の対象として記録されます。
+//!
+//! | 位置 | 内容 |
+//! |------|------|
+//! | 0..4 | マジック `dply`（`64706c79`） |
+//! | 4    | バージョン（`1`） |
+//! | 5..  | 作成バイトコード |
+//!
+//! コントラクトのアドレスはデプロイ者のアドレスとノンス（u64 BE）の SHA-256 の先頭20バイトで、
+//! 確定前でも [`contract_address`] で求められます。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+use super::analysis::{self, AnalysisConfig, Finding};
+use super::verification;
+
+/// 反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"contract/deployment_applied_height";
+
+/// 操作の先頭のマジック
+pub const DEPLOY_MAGIC: [u8; 4] = *b"dply";
+/// 形式のバージョン
+const DEPLOY_VERSION: u8 = 1;
+/// デプロイのアドレス（操作の宛先）
+pub const DEPLOYER_ADDRESS: &str = "0000000000000000000000000000000000000044";
+
+/// デプロイのエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum DeployError {
+    #[error("deploy operation is truncated")]
+    Truncated,
+
+    #[error("unsupported deploy operation version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("deployments must be sent to 0000000000000000000000000000000000000044")]
+    WrongRecipient,
+
+    #[error("deployments must not transfer value")]
+    UnexpectedValue,
+
+    #[error("creation bytecode must not be empty")]
+    EmptyCode,
+
+    #[error("rejected by static analysis: {0}")]
+    Rejected(String),
+
+    #[error("contract {0} is already deployed")]
+    AlreadyDeployed(String),
+}
+
+/// `data` に入れる操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployOp {
+    /// 作成バイトコード
+    pub code: Vec<u8>,
+}
+
+impl DeployOp {
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = DEPLOY_MAGIC.to_vec();
+        data.push(DEPLOY_VERSION);
+        data.extend_from_slice(&self.code);
+        data
+    }
+
+    /// `data` フィールドを解析（デプロイの操作でなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, DeployError>> {
+        let body = data.strip_prefix(&DEPLOY_MAGIC)?;
+        let Some((version, code)) = body.split_first() else {
+            return Some(Err(DeployError::Truncated));
+        };
+        if *version != DEPLOY_VERSION {
+            return Some(Err(DeployError::UnsupportedVersion(*version)));
+        }
+        if code.is_empty() {
+            return Some(Err(DeployError::EmptyCode));
+        }
+        Some(Ok(Self { code: code.to_vec() }))
+    }
+
+    /// トランザクションの操作
+    pub fn of(tx: &PendingTransaction) -> Option<Result<Self, DeployError>> {
+        Self::decode(&tx.data)
+    }
+}
+
+/// デプロイ者とノンスから決まるコントラクトのアドレス
+pub fn contract_address(deployer: &str, nonce: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(deployer.as_bytes());
+    hasher.update(nonce.to_be_bytes());
+    hex::encode(&hasher.finalize()[..20])
+}
+
+/// デプロイされたコントラクト
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "contract/deployment", version = 1)]
+pub struct Deployment {
+    #[state(key)]
+    pub address: String,
+    pub deployer: String,
+    /// デプロイしたトランザクションのハッシュ
+    pub tx_hash: String,
+    /// 作成バイトコードのバイト数
+    pub code_size: usize,
+    /// 静的解析で警告として検出された項目（`warn` モード）
+    pub findings: Vec<Finding>,
+    /// 確定したブロックの高さ
+    pub created_at: u64,
+}
+
+/// デプロイの台帳
+pub struct DeploymentLedger {
+    storage: Arc<dyn StorageEngine>,
+    analysis: AnalysisConfig,
+    /// ブロックの反映を直列にする（購読と追いつきが同時に反映しないように）
+    applying: Mutex<()>,
+}
+
+impl DeploymentLedger {
+    pub fn new(storage: Arc<dyn StorageEngine>, analysis: AnalysisConfig) -> Self {
+        Self { storage, analysis, applying: Mutex::new(()) }
+    }
+
+    /// デプロイされたコントラクト
+    pub async fn deployment(&self, address: &str) -> Result<Option<Deployment>> {
+        Deployment::load(self.storage.as_ref(), &address.to_string()).await
+    }
+
+    /// 受付時の検証（確定した状態に対して検証する）
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<(), DeployError> {
+        self.apply(&mut HashMap::new(), tx, 0).await
+    }
+
+    /// ブロックに含められない操作（と同じ送信者の後続のもの）を除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let mut deployments = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            match self.apply(&mut deployments, &tx, 0).await {
+                Ok(()) => kept.push(tx),
+                Err(e) => {
+                    debug!("Leaving deployment {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// 操作を検証して `deployments` に反映する
+    async fn apply(
+        &self,
+        deployments: &mut HashMap<String, (Deployment, Vec<u8>)>,
+        tx: &PendingTransaction,
+        height: u64,
+    ) -> Result<(), DeployError> {
+        let op = match DeployOp::of(tx) {
+            None => return Ok(()),
+            Some(op) => op?,
+        };
+        if tx.to != DEPLOYER_ADDRESS {
+            return Err(DeployError::WrongRecipient);
+        }
+        if tx.value != 0 {
+            return Err(DeployError::UnexpectedValue);
+        }
+
+        let report = analysis::analyze(&op.code, &self.analysis);
+        if report.rejected {
+            let reasons: Vec<String> = report.findings.iter()
+                .map(|f| format!("{}: {}", f.rule, f.message))
+                .collect();
+            return Err(DeployError::Rejected(reasons.join("; ")));
+        }
+
+        let address = contract_address(&tx.from, tx.nonce);
+        let deployed = deployments.contains_key(&address)
+            || self.deployment(&address).await.map_err(|_| DeployError::AlreadyDeployed(address.clone()))?.is_some();
+        if deployed {
+            return Err(DeployError::AlreadyDeployed(address));
+        }
+        let deployment = Deployment {
+            address: address.clone(),
+            deployer: tx.from.clone(),
+            tx_hash: tx.hash.clone(),
+            code_size: op.code.len(),
+            findings: report.findings,
+            created_at: height,
+        };
+        deployments.insert(address, (deployment, op.code));
+        Ok(())
+    }
+
+    /// 確定したブロックの操作を順に検証して適用する（失敗した操作は状態を変えない）
+    ///
+    /// 反映済みの高さ以下のブロックは無視します。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        let mut deployments = HashMap::new();
+        for tx in &block.transactions {
+            if let Err(e) = self.apply(&mut deployments, tx, block.height).await {
+                warn!("Deployment {} in block {} was not applied: {}", tx.hash, block.height, e);
+            }
+        }
+        let mut batch = Vec::with_capacity(deployments.len() * 2 + 1);
+        for (deployment, code) in deployments.values() {
+            for finding in &deployment.findings {
+                warn!("Contract {} deployed by {}: {} ({})", deployment.address, deployment.deployer, finding.message, finding.rule);
+            }
+            batch.push(deployment.put_change()?);
+            batch.push(verification::creation_code_change(&deployment.address, code));
+        }
+        batch.push((APPLIED_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        self.storage.batch_write(batch).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 最新のブロックまでストレージから読み直して反映する
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=head {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 以降の確定で操作を適用する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up the deployment ledger: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply deployments: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up the deployment ledger: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn tx(from: &str, nonce: u64, code: &[u8]) -> PendingTransaction {
+        PendingTransaction::test_transfer(from, DEPLOYER_ADDRESS, 0, nonce)
+            .with_data(DeployOp { code: code.to_vec() }.encode())
+    }
+
+    #[tokio::test]
+    async fn test_deploy_records_creation_code() {
+        let storage = RedbStorage::memory();
+        let analysis = AnalysisConfig { max_code_size: 4, ..Default::default() };
+        let ledger = DeploymentLedger::new(storage.clone(), analysis);
+
+        let code = [0x60, 0x00, 0x60, 0x00];
+        let deploy = tx("aa", 0, &code);
+        assert_eq!(ledger.check(&deploy).await, Ok(()));
+        assert_eq!(DeployOp::of(&deploy), Some(Ok(DeployOp { code: code.to_vec() })));
+
+        // 解析で拒否されたコード、値の送金、宛先の誤りは適用されない
+        let too_large = tx("aa", 1, &[0x60; 5]);
+        assert!(matches!(ledger.check(&too_large).await, Err(DeployError::Rejected(_))));
+        let mut with_value = tx("aa", 2, &code);
+        with_value.value = 1;
+        let mut misdirected = tx("aa", 3, &code);
+        misdirected.to = "bb".to_string();
+        assert_eq!(ledger.check(&misdirected).await, Err(DeployError::WrongRecipient));
+        assert_eq!(DeployOp::decode(&DEPLOY_MAGIC), Some(Err(DeployError::Truncated)));
+        assert_eq!(DeployOp::decode(b"memo"), None);
+
+        ledger.apply_block(&Block::new(1, "p".to_string(), "v".to_string(), vec![deploy, too_large, with_value, misdirected]))
+            .await.unwrap();
+        let address = contract_address("aa", 0);
+        let deployment = ledger.deployment(&address).await.unwrap().unwrap();
+        assert_eq!((deployment.deployer.as_str(), deployment.code_size, deployment.created_at), ("aa", 4, 1));
+        let codes = verification::creation_codes(storage.as_ref()).await.unwrap();
+        assert_eq!(codes, vec![(address.clone(), code.to_vec())]);
+
+        // 同じ送信者とノンスのデプロイは二重に記録しない
+        assert_eq!(ledger.check(&tx("aa", 0, &code)).await, Err(DeployError::AlreadyDeployed(address)));
+        assert_eq!(ledger.applied_height().await.unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,233 @@
+//! コントラクトごとの利用状況
+//!
+//! コントラクトの呼び出し（`data` を持つトランザクション）の件数、ガス使用量、失敗数を
+//! 宛先のアドレスごとに直近の期間（1時間・24時間・7日）で集計します。
+//! どの dApp が負荷を生んでいるかを把握し、容量の計画に使うための指標です。
+//!
+//! 集計は1分ごとのバケットで行い、最も長い期間より古いバケットは破棄します（バリデーターの
+//! パフォーマンスと同じ期間を使います）。起動時には保持期間内のブロックから復元します。
+//! 実行に失敗したトランザクションは現在ブロックに含まれないため、失敗数はレシートの
+//! `status` が成功以外のものを数えます。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use tokio::sync::{broadcast, RwLock};
+use tracing::warn;
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::consensus::performance::{window_secs, WINDOWS};
+
+/// バケットの幅（秒）
+const BUCKET_SECS: u64 = 60;
+
+/// 1分間の集計
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    calls: u64,
+    failures: u64,
+    gas_used: u64,
+}
+
+/// 期間内のコントラクトの利用状況
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContractUsage {
+    pub contract: String,
+    /// 確定したブロックに含まれた呼び出しの数
+    pub calls: u64,
+    /// 失敗した呼び出しの数
+    pub failures: u64,
+    /// `failures / calls`
+    pub failure_rate: f64,
+    /// ガス使用量の合計
+    pub gas_used: u64,
+    /// 1回の呼び出しあたりのガス使用量
+    pub avg_gas_per_call: f64,
+    /// 期間内の全コントラクトのガス使用量に占める割合
+    pub gas_share: f64,
+}
+
+/// コントラクトの利用状況の一覧
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContractUsageReport {
+    /// 集計期間（`1h`、`24h`、`7d`）
+    pub window: String,
+    pub window_secs: u64,
+    /// 集計時刻（UNIX秒）
+    pub generated_at: u64,
+    /// 期間内の全コントラクトのガス使用量の合計
+    pub total_gas_used: u64,
+    /// ガス使用量の多い順
+    pub contracts: Vec<ContractUsage>,
+}
+
+/// コントラクトごとの利用状況の集計
+#[derive(Debug, Default)]
+pub struct ContractMetrics {
+    /// コントラクトのアドレスごとの、バケットの開始時刻（UNIX秒）ごとの集計
+    contracts: RwLock<HashMap<String, BTreeMap<u64, Bucket>>>,
+}
+
+impl ContractMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// コントラクトの呼び出しを記録
+    pub async fn record_call(&self, contract: &str, at: u64, gas_used: u64, success: bool) {
+        let mut contracts = self.contracts.write().await;
+        let buckets = contracts.entry(contract.to_string()).or_default();
+        let bucket = buckets.entry(at / BUCKET_SECS * BUCKET_SECS).or_default();
+        bucket.calls += 1;
+        bucket.failures += u64::from(!success);
+        bucket.gas_used = bucket.gas_used.saturating_add(gas_used);
+    }
+
+    /// 最も長い期間より古いバケットと、呼び出しのなくなったコントラクトを破棄
+    async fn prune(&self, now: u64) {
+        let retention = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        let oldest = now.saturating_sub(retention) / BUCKET_SECS * BUCKET_SECS;
+        let mut contracts = self.contracts.write().await;
+        contracts.retain(|_, buckets| {
+            *buckets = buckets.split_off(&oldest);
+            !buckets.is_empty()
+        });
+    }
+
+    /// `now` までの `window` の利用状況（ガス使用量の多い順）
+    pub async fn report(&self, window: &str, now: u64) -> Option<ContractUsageReport> {
+        let window_secs = window_secs(window)?;
+        let since = now.saturating_sub(window_secs);
+        let contracts = self.contracts.read().await;
+        let mut usage: Vec<ContractUsage> = contracts.iter()
+            .filter_map(|(contract, buckets)| {
+                let total = buckets.range(since / BUCKET_SECS * BUCKET_SECS..)
+                    .fold(Bucket::default(), |mut total, (_, bucket)| {
+                        total.calls += bucket.calls;
+                        total.failures += bucket.failures;
+                        total.gas_used = total.gas_used.saturating_add(bucket.gas_used);
+                        total
+                    });
+                (total.calls > 0).then(|| ContractUsage {
+                    contract: contract.clone(),
+                    calls: total.calls,
+                    failures: total.failures,
+                    failure_rate: total.failures as f64 / total.calls as f64,
+                    gas_used: total.gas_used,
+                    avg_gas_per_call: total.gas_used as f64 / total.calls as f64,
+                    gas_share: 0.0,
+                })
+            })
+            .collect();
+        let total_gas_used = usage.iter().fold(0u64, |total, u| total.saturating_add(u.gas_used));
+        for u in &mut usage {
+            u.gas_share = if total_gas_used == 0 { 0.0 } else { u.gas_used as f64 / total_gas_used as f64 };
+        }
+        usage.sort_by(|a, b| b.gas_used.cmp(&a.gas_used).then_with(|| a.contract.cmp(&b.contract)));
+        Some(ContractUsageReport {
+            window: window.to_string(),
+            window_secs,
+            generated_at: now,
+            total_gas_used,
+            contracts: usage,
+        })
+    }
+
+    async fn apply_block(&self, block: &Block) {
+        for (tx, receipt) in block.transactions.iter().zip(block.receipts()) {
+            if tx.data.is_empty() || tx.to.is_empty() {
+                continue;
+            }
+            self.record_call(&tx.to, block.timestamp, receipt.gas_used, receipt.status == 1).await;
+        }
+        self.prune(block.timestamp).await;
+    }
+
+    /// 保持期間内のブロックから呼び出しを復元し、復元した最新の高さを返す
+    async fn backfill(&self, chain: &Chain) -> Option<u64> {
+        let (head, _) = chain.head().await?;
+        let retention = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        let since = unix_now().saturating_sub(retention);
+        for height in (0..=head).rev() {
+            match chain.get_block(height).await {
+                Ok(Some(block)) if block.timestamp >= since => self.apply_block(&block).await,
+                Ok(_) => break,
+                Err(e) => {
+                    warn!("Failed to read block {} for contract metrics: {}", height, e);
+                    break;
+                }
+            }
+        }
+        Some(head)
+    }
+
+    /// ブロックの確定を購読して呼び出しを記録し続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            let mut last = self.backfill(&chain).await;
+            loop {
+                match commits.recv().await {
+                    Ok(block) => {
+                        if last.map_or(true, |last| block.height > last) {
+                            self.apply_block(&block).await;
+                            last = Some(block.height);
+                        }
+                    }
+                    // 取りこぼした場合はストレージから読み直す
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let Some((head, _)) = chain.head().await else {
+                            continue;
+                        };
+                        for height in last.map_or(0, |last| last + 1)..=head {
+                            if let Ok(Some(block)) = chain.get_block(height).await {
+                                self.apply_block(&block).await;
+                            }
+                        }
+                        last = Some(head);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_top_contracts_by_gas() {
+        let metrics = ContractMetrics::new();
+        let now = 10 * 86_400;
+        // 2日前の呼び出しは24時間の集計に含まれない
+        metrics.record_call("dex", now - 2 * 86_400, 1_000_000, true).await;
+        metrics.record_call("dex", now - 60, 300_000, true).await;
+        metrics.record_call("dex", now - 30, 100_000, false).await;
+        metrics.record_call("nft", now - 90, 600_000, true).await;
+
+        let day = metrics.report("24h", now).await.unwrap();
+        let names: Vec<&str> = day.contracts.iter().map(|c| c.contract.as_str()).collect();
+        assert_eq!(names, ["nft", "dex"]);
+        assert_eq!(day.total_gas_used, 1_000_000);
+        let dex = &day.contracts[1];
+        assert_eq!((dex.calls, dex.failures, dex.gas_used), (2, 1, 400_000));
+        assert_eq!((dex.failure_rate, dex.avg_gas_per_call, dex.gas_share), (0.5, 200_000.0, 0.4));
+
+        let week = metrics.report("7d", now).await.unwrap();
+        assert_eq!(week.contracts[0].contract, "dex");
+        assert!(metrics.report("30d", now).await.is_none());
+
+        // 保持期間を過ぎると破棄される
+        metrics.prune(now + 8 * 86_400).await;
+        assert!(metrics.contracts.read().await.is_empty());
+    }
+}
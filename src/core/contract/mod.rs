@@ -0,0 +1,25 @@
+//! コントラクト
+//!
+//! 主な機能：
+//! - ソースコード検証（コンパイラマトリクス、バイトコード照合）
+//! - アップグレード可能なコントラクトのプロキシレジストリ（登録とアップグレードはトランザクション）
+//! - 署名されたトランザクションによるデプロイと静的解析
+//! - コントラクトごとの利用状況（呼び出し数・ガス使用量・失敗率）
+
+pub mod analysis;
+pub mod deploy;
+pub mod metrics;
+pub mod proxy;
+pub mod verification;
+
+pub use analysis::{AnalysisConfig, AnalysisMode, AnalysisReport, CodeKind, Finding, Severity};
+pub use deploy::{contract_address, DeployError, DeployOp, Deployment, DeploymentLedger, DEPLOYER_ADDRESS};
+pub use proxy::{
+    Approval, ProxyError, ProxyOp, ProxyRecord, ProxyRegistry, RegisterProxy,
+    UpgradeAuthority, UpgradeEvent, UpgradeTransaction, PROXY_ADDRESS,
+};
+
+pub use verification::{
+    CompilerKind, CompilerMatrix, CompilerSettings, ContractVerifier,
+    MatchStatus, VerificationError, VerificationRequest, VerifiedContract, VerifiedContractSummary,
+};
@@ -0,0 +1,699 @@
+//! アップグレード可能なコントラクトのプロキシレジストリ
+//!
+//! 論理コントラクトIDから実装アドレスへの対応をステートに保持し、
+//! オーナーまたはガバナンス（署名者の閾値）によるアップグレードのみを受け付けます。
+//! 登録とアップグレードはレジストリのアドレス [`PROXY_ADDRESS`] 宛てのトランザクションの `data` に入れ
+//! （`value` は0）、確定したブロックの順に検証して適用します。
+//!
+//! | 位置 | 内容 |
+//! |------|------|
+//! | 0..4 | マジック `prxy`（`70727879`） |
+//! | 4    | バージョン（`1`） |
+//! | 5    | 種類（`0` は登録、`1` はアップグレード） |
+//! | 6..  | 登録は [`RegisterProxy`]、アップグレードは [`UpgradeTransaction`] の JSON |
+//!
+//! - 登録：送信者はプロキシのアドレスのデプロイ者で、アップグレード権限（オーナーまたはメンバー）に
+//!   含まれていること。他のプロキシに属するアドレスは登録できません
+//! - アップグレード：送信者と署名付き承認の署名者が権限を満たすこと
+//!
+//! 主な機能：
+//! - プロキシの登録と実装アドレスの解決
+//! - 署名付きアップグレードトランザクションの検証
+//! - アドレスごとのアップグレード履歴
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::Result;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use super::deploy::DeploymentLedger;
+
+/// 操作の先頭のマジック
+pub const PROXY_MAGIC: [u8; 4] = *b"prxy";
+/// 形式のバージョン
+const PROXY_VERSION: u8 = 1;
+/// レジストリのアドレス（操作の宛先）
+pub const PROXY_ADDRESS: &str = "0000000000000000000000000000000000000050";
+
+const KIND_REGISTER: u8 = 0;
+const KIND_UPGRADE: u8 = 1;
+
+/// プロキシレコードのキープレフィックス
+const PROXY_PREFIX: &str = "proxy/id/";
+/// アドレスからプロキシIDへの索引のキープレフィックス
+const ADDRESS_INDEX_PREFIX: &str = "proxy/addr/";
+/// 反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"proxy/applied_height";
+
+/// アップグレード権限
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UpgradeAuthority {
+    /// 単一オーナー
+    Owner { address: String },
+    /// ガバナンス（メンバーのうち `threshold` 名以上の承認が必要）
+    Governance { members: Vec<String>, threshold: usize },
+}
+
+impl UpgradeAuthority {
+    /// オーナーまたはメンバーか
+    fn includes(&self, address: &str) -> bool {
+        match self {
+            Self::Owner { address: owner } => normalize_address(owner) == address,
+            Self::Governance { members, .. } => members.iter().any(|m| normalize_address(m) == address),
+        }
+    }
+}
+
+/// アップグレード履歴のエントリ
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpgradeEvent {
+    /// アップグレード後のバージョン
+    pub version: u64,
+    /// 以前の実装アドレス（登録時は `None`）
+    pub previous_implementation: Option<String>,
+    /// 新しい実装アドレス
+    pub implementation: String,
+    /// 実行者
+    pub sender: String,
+    /// 承認者（ガバナンスの場合）
+    pub approvals: Vec<String>,
+    /// 確定したブロックの時刻（UNIX秒）
+    pub timestamp: u64,
+}
+
+/// プロキシレコード
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProxyRecord {
+    /// 論理コントラクトID
+    pub id: String,
+    /// プロキシのアドレス
+    pub proxy_address: String,
+    /// 現在の実装アドレス
+    pub implementation: String,
+    /// アップグレード権限
+    pub authority: UpgradeAuthority,
+    /// 現在のバージョン
+    pub version: u64,
+    /// アップグレード履歴（古い順）
+    pub history: Vec<UpgradeEvent>,
+}
+
+/// 署名付き承認
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Approval {
+    /// 承認者アドレス（ed25519公開鍵のhex）
+    pub address: String,
+    /// 承認メッセージへの署名（hex）
+    pub signature: String,
+}
+
+/// プロキシ登録の操作（送信者がプロキシのデプロイ者であること）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RegisterProxy {
+    pub id: String,
+    pub proxy_address: String,
+    pub implementation: String,
+    pub authority: UpgradeAuthority,
+}
+
+/// アップグレードの操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UpgradeTransaction {
+    pub id: String,
+    pub new_implementation: String,
+    /// 現在のバージョン（リプレイ防止）
+    pub version: u64,
+    /// 送信者以外の署名付き承認（ガバナンスの場合）
+    #[serde(default)]
+    pub approvals: Vec<Approval>,
+}
+
+impl UpgradeTransaction {
+    /// 署名対象のメッセージ
+    pub fn signing_message(&self) -> String {
+        format!("upgrade:{}:{}:{}", self.id, self.new_implementation, self.version)
+    }
+}
+
+/// `data` に入れる操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyOp {
+    Register(RegisterProxy),
+    Upgrade(UpgradeTransaction),
+}
+
+impl ProxyOp {
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = PROXY_MAGIC.to_vec();
+        data.push(PROXY_VERSION);
+        let body = match self {
+            Self::Register(register) => {
+                data.push(KIND_REGISTER);
+                serde_json::to_vec(register)
+            }
+            Self::Upgrade(upgrade) => {
+                data.push(KIND_UPGRADE);
+                serde_json::to_vec(upgrade)
+            }
+        };
+        data.extend_from_slice(&body.expect("proxy operations serialize to JSON"));
+        data
+    }
+
+    /// `data` フィールドを解析（プロキシの操作でなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, ProxyError>> {
+        let body = data.strip_prefix(&PROXY_MAGIC)?;
+        Some(Self::decode_body(body))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, ProxyError> {
+        let [version, kind, rest @ ..] = body else {
+            return Err(ProxyError::Malformed("operation is truncated".to_string()));
+        };
+        if *version != PROXY_VERSION {
+            return Err(ProxyError::Malformed(format!("unsupported version {}", version)));
+        }
+        let malformed = |e: serde_json::Error| ProxyError::Malformed(e.to_string());
+        match *kind {
+            KIND_REGISTER => Ok(Self::Register(serde_json::from_slice(rest).map_err(malformed)?)),
+            KIND_UPGRADE => Ok(Self::Upgrade(serde_json::from_slice(rest).map_err(malformed)?)),
+            other => Err(ProxyError::Malformed(format!("unknown kind {}", other))),
+        }
+    }
+
+    /// トランザクションの操作
+    pub fn of(tx: &PendingTransaction) -> Option<Result<Self, ProxyError>> {
+        Self::decode(&tx.data)
+    }
+}
+
+/// プロキシレジストリのエラー
+#[derive(Debug, Error)]
+pub enum ProxyError {
+    #[error("Malformed proxy operation: {0}")]
+    Malformed(String),
+
+    #[error("Proxy operations must be sent to 0000000000000000000000000000000000000050 without value")]
+    WrongRecipient,
+
+    #[error("Proxy {0} is already registered")]
+    AlreadyRegistered(String),
+
+    #[error("Proxy {0} not found")]
+    NotFound(String),
+
+    #[error("{sender} did not deploy proxy contract {address}")]
+    NotDeployer { address: String, sender: String },
+
+    #[error("Address {address} already belongs to proxy {id}")]
+    AddressInUse { address: String, id: String },
+
+    #[error("Invalid signature from {0}")]
+    InvalidSignature(String),
+
+    #[error("Not authorized to upgrade proxy: {0}")]
+    Unauthorized(String),
+
+    #[error("Insufficient approvals: {got} of {threshold} required")]
+    InsufficientApprovals { got: usize, threshold: usize },
+
+    #[error("Version mismatch: expected {expected}, got {got}")]
+    VersionMismatch { expected: u64, got: u64 },
+
+    #[error("Proxy already points to implementation {0}")]
+    SameImplementation(String),
+
+    #[error("Invalid authority: {0}")]
+    InvalidAuthority(String),
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// ブロック内で反映中のレコードとアドレスの索引
+#[derive(Default)]
+struct Pending {
+    records: HashMap<String, ProxyRecord>,
+    addresses: HashMap<String, String>,
+}
+
+/// プロキシレジストリ
+pub struct ProxyRegistry {
+    storage: Arc<dyn StorageEngine>,
+    deployments: Arc<DeploymentLedger>,
+    /// ブロックの反映を直列にする（購読と追いつきが同時に反映しないように）
+    applying: Mutex<()>,
+}
+
+impl ProxyRegistry {
+    pub fn new(storage: Arc<dyn StorageEngine>, deployments: Arc<DeploymentLedger>) -> Self {
+        Self { storage, deployments, applying: Mutex::new(()) }
+    }
+
+    /// 論理IDから現在の実装アドレスを解決
+    pub async fn resolve(&self, id: &str) -> Result<Option<String>> {
+        Ok(self.get(id).await?.map(|r| r.implementation))
+    }
+
+    /// プロキシレコードを取得
+    pub async fn get(&self, id: &str) -> Result<Option<ProxyRecord>> {
+        match self.storage.get(&proxy_key(id)).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// アドレス（プロキシまたは過去・現在の実装）に関連するプロキシを取得
+    pub async fn find_by_address(&self, address: &str) -> Result<Option<ProxyRecord>> {
+        match self.owner_of(address).await? {
+            Some(id) => self.get(&id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// アドレスが属するプロキシのID
+    async fn owner_of(&self, address: &str) -> Result<Option<String>> {
+        match self.storage.get(&address_key(address)).await? {
+            Some(id) => Ok(Some(String::from_utf8(id)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 受付時の検証（確定した状態に対して検証する）
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<(), ProxyError> {
+        self.apply(&mut Pending::default(), tx, 0).await
+    }
+
+    /// ブロックに含められない操作（と同じ送信者の後続のもの）を除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let mut pending = Pending::default();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            match self.apply(&mut pending, &tx, 0).await {
+                Ok(()) => kept.push(tx),
+                Err(e) => {
+                    debug!("Leaving proxy operation {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// 操作を検証して `pending` に反映する
+    async fn apply(&self, pending: &mut Pending, tx: &PendingTransaction, timestamp: u64) -> Result<(), ProxyError> {
+        let op = match ProxyOp::of(tx) {
+            None => return Ok(()),
+            Some(op) => op?,
+        };
+        if tx.to != PROXY_ADDRESS || tx.value != 0 {
+            return Err(ProxyError::WrongRecipient);
+        }
+        let sender = normalize_address(&tx.from);
+        let record = match op {
+            ProxyOp::Register(register) => self.register(pending, register, sender, timestamp).await?,
+            ProxyOp::Upgrade(upgrade) => self.upgrade(pending, upgrade, sender, timestamp).await?,
+        };
+        pending.addresses.insert(record.proxy_address.clone(), record.id.clone());
+        pending.addresses.insert(record.implementation.clone(), record.id.clone());
+        pending.records.insert(record.id.clone(), record);
+        Ok(())
+    }
+
+    /// 登録を検証してレコードを作る
+    async fn register(
+        &self,
+        pending: &Pending,
+        register: RegisterProxy,
+        sender: String,
+        timestamp: u64,
+    ) -> Result<ProxyRecord, ProxyError> {
+        if self.current(pending, &register.id).await?.is_some() {
+            return Err(ProxyError::AlreadyRegistered(register.id));
+        }
+        if let UpgradeAuthority::Governance { members, threshold } = &register.authority {
+            if *threshold == 0 || *threshold > members.len() {
+                return Err(ProxyError::InvalidAuthority(format!(
+                    "threshold {} with {} members", threshold, members.len()
+                )));
+            }
+        }
+        if !register.authority.includes(&sender) {
+            return Err(ProxyError::Unauthorized(format!("{} is not part of the upgrade authority", sender)));
+        }
+        let proxy_address = normalize_address(&register.proxy_address);
+        let deployer = self.deployments.deployment(&proxy_address).await?.map(|d| normalize_address(&d.deployer));
+        if deployer.as_deref() != Some(sender.as_str()) {
+            return Err(ProxyError::NotDeployer { address: proxy_address, sender });
+        }
+        let implementation = normalize_address(&register.implementation);
+        for address in [&proxy_address, &implementation] {
+            self.check_unclaimed(pending, address, &register.id).await?;
+        }
+
+        Ok(ProxyRecord {
+            id: register.id,
+            proxy_address,
+            implementation: implementation.clone(),
+            authority: register.authority,
+            version: 1,
+            history: vec![UpgradeEvent {
+                version: 1,
+                previous_implementation: None,
+                implementation,
+                sender,
+                approvals: Vec::new(),
+                timestamp,
+            }],
+        })
+    }
+
+    /// アップグレードを検証して更新後のレコードを作る
+    async fn upgrade(
+        &self,
+        pending: &Pending,
+        upgrade: UpgradeTransaction,
+        sender: String,
+        timestamp: u64,
+    ) -> Result<ProxyRecord, ProxyError> {
+        let mut record = self.current(pending, &upgrade.id).await?
+            .ok_or_else(|| ProxyError::NotFound(upgrade.id.clone()))?;
+        if upgrade.version != record.version {
+            return Err(ProxyError::VersionMismatch { expected: record.version, got: upgrade.version });
+        }
+        let implementation = normalize_address(&upgrade.new_implementation);
+        if implementation == record.implementation {
+            return Err(ProxyError::SameImplementation(implementation));
+        }
+        self.check_unclaimed(pending, &implementation, &record.id).await?;
+
+        // 送信者はトランザクションの署名で承認している
+        let message = upgrade.signing_message();
+        let mut signers = HashSet::from([sender.clone()]);
+        for approval in &upgrade.approvals {
+            verify_approval(approval, &message)?;
+            signers.insert(normalize_address(&approval.address));
+        }
+        let approvals = authorize(&record.authority, &signers)?;
+
+        record.version += 1;
+        record.history.push(UpgradeEvent {
+            version: record.version,
+            previous_implementation: Some(record.implementation.clone()),
+            implementation: implementation.clone(),
+            sender,
+            approvals,
+            timestamp,
+        });
+        record.implementation = implementation;
+        Ok(record)
+    }
+
+    /// 反映中のレコード（なければ確定したレコード）
+    async fn current(&self, pending: &Pending, id: &str) -> Result<Option<ProxyRecord>> {
+        match pending.records.get(id) {
+            Some(record) => Ok(Some(record.clone())),
+            None => self.get(id).await,
+        }
+    }
+
+    /// アドレスが他のプロキシに属していないか
+    async fn check_unclaimed(&self, pending: &Pending, address: &str, id: &str) -> Result<(), ProxyError> {
+        let owner = match pending.addresses.get(address) {
+            Some(owner) => Some(owner.clone()),
+            None => self.owner_of(address).await?,
+        };
+        match owner {
+            Some(owner) if owner != id => Err(ProxyError::AddressInUse { address: address.to_string(), id: owner }),
+            _ => Ok(()),
+        }
+    }
+
+    /// 確定したブロックの操作を順に検証して適用する（失敗した操作は状態を変えない）
+    ///
+    /// 反映済みの高さ以下のブロックは無視します。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        let mut pending = Pending::default();
+        for tx in &block.transactions {
+            if let Err(e) = self.apply(&mut pending, tx, block.timestamp).await {
+                warn!("Proxy operation {} in block {} was not applied: {}", tx.hash, block.height, e);
+            }
+        }
+        let mut batch = Vec::new();
+        for record in pending.records.values() {
+            batch.push((proxy_key(&record.id), Some(serde_json::to_vec(record)?)));
+            info!("Proxy {} points to {} (v{})", record.id, record.implementation, record.version);
+        }
+        for (address, id) in pending.addresses {
+            batch.push((address_key(&address), Some(id.into_bytes())));
+        }
+        batch.push((APPLIED_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        self.storage.batch_write(batch).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 最新のブロックまでストレージから読み直して反映する
+    ///
+    /// 登録の検証はデプロイの台帳を読むため、先にデプロイの台帳を追いつかせます。
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        self.deployments.catch_up(chain).await?;
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=head {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 以降の確定で操作を適用する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up the proxy registry: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply proxy operations: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up the proxy registry: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// 承認者の集合がアップグレード権限を満たすか検証し、承認者一覧を返す
+fn authorize(authority: &UpgradeAuthority, signers: &HashSet<String>) -> Result<Vec<String>, ProxyError> {
+    match authority {
+        UpgradeAuthority::Owner { address } => {
+            let owner = normalize_address(address);
+            if !signers.contains(&owner) {
+                return Err(ProxyError::Unauthorized(format!("owner {} did not sign", owner)));
+            }
+            Ok(Vec::new())
+        }
+        UpgradeAuthority::Governance { members, threshold } => {
+            let mut approved: Vec<String> = members
+                .iter()
+                .map(|m| normalize_address(m))
+                .filter(|m| signers.contains(m))
+                .collect();
+            approved.sort();
+            approved.dedup();
+            if approved.len() < *threshold {
+                return Err(ProxyError::InsufficientApprovals { got: approved.len(), threshold: *threshold });
+            }
+            Ok(approved)
+        }
+    }
+}
+
+/// 承認の署名を検証
+fn verify_approval(approval: &Approval, message: &str) -> Result<(), ProxyError> {
+    let invalid = || ProxyError::InvalidSignature(approval.address.clone());
+    let key_bytes: [u8; 32] = hex::decode(normalize_address(&approval.address))
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(invalid)?;
+    let sig_bytes: [u8; 64] = hex::decode(approval.signature.trim_start_matches("0x"))
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(invalid)?;
+    let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| invalid())?;
+    key.verify(message.as_bytes(), &Signature::from_bytes(&sig_bytes))
+        .map_err(|_| invalid())
+}
+
+fn normalize_address(address: &str) -> String {
+    let lower = address.trim().to_ascii_lowercase();
+    lower.strip_prefix("0x").map(str::to_string).unwrap_or(lower)
+}
+
+fn proxy_key(id: &str) -> Vec<u8> {
+    format!("{}{}", PROXY_PREFIX, id).into_bytes()
+}
+
+fn address_key(address: &str) -> Vec<u8> {
+    format!("{}{}", ADDRESS_INDEX_PREFIX, normalize_address(address)).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use crate::core::contract::analysis::AnalysisConfig;
+    use crate::core::contract::deploy::{contract_address, DeployOp, DEPLOYER_ADDRESS};
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn address(key: &SigningKey) -> String {
+        hex::encode(key.verifying_key().as_bytes())
+    }
+
+    fn approve(key: &SigningKey, message: &str) -> Approval {
+        Approval {
+            address: address(key),
+            signature: hex::encode(key.sign(message.as_bytes()).to_bytes()),
+        }
+    }
+
+    fn tx(from: &SigningKey, nonce: u64, op: &ProxyOp) -> PendingTransaction {
+        PendingTransaction::test_transfer(&address(from), PROXY_ADDRESS, 0, nonce).with_data(op.encode())
+    }
+
+    fn block(height: u64, transactions: Vec<PendingTransaction>) -> Block {
+        Block::new(height, "p".to_string(), "v".to_string(), transactions)
+    }
+
+    /// `deployer` がノンス0でデプロイしたプロキシのアドレスとレジストリ
+    async fn registry(deployer: &SigningKey) -> (ProxyRegistry, String) {
+        let storage = RedbStorage::memory();
+        let deployments = Arc::new(DeploymentLedger::new(storage.clone(), AnalysisConfig::default()));
+        let deploy = PendingTransaction::test_transfer(&address(deployer), DEPLOYER_ADDRESS, 0, 0)
+            .with_data(DeployOp { code: vec![0x60, 0x00] }.encode());
+        deployments.apply_block(&block(1, vec![deploy])).await.unwrap();
+        (ProxyRegistry::new(storage, deployments), contract_address(&address(deployer), 0))
+    }
+
+    fn register(id: &str, proxy_address: &str, implementation: &str, authority: UpgradeAuthority) -> ProxyOp {
+        ProxyOp::Register(RegisterProxy {
+            id: id.to_string(),
+            proxy_address: proxy_address.to_string(),
+            implementation: implementation.to_string(),
+            authority,
+        })
+    }
+
+    fn upgrade(id: &str, implementation: &str, version: u64, approvers: &[&SigningKey]) -> ProxyOp {
+        let mut upgrade = UpgradeTransaction {
+            id: id.to_string(),
+            new_implementation: implementation.to_string(),
+            version,
+            approvals: Vec::new(),
+        };
+        let message = upgrade.signing_message();
+        upgrade.approvals = approvers.iter().map(|k| approve(k, &message)).collect();
+        ProxyOp::Upgrade(upgrade)
+    }
+
+    #[tokio::test]
+    async fn test_owner_gated_upgrade_and_history() {
+        let owner = key(1);
+        let (registry, proxy) = registry(&owner).await;
+        let authority = UpgradeAuthority::Owner { address: address(&owner) };
+        let op = register("token", &proxy, "0xaa", authority.clone());
+        assert_eq!(ProxyOp::decode(&op.encode()).map(Result::ok), Some(Some(op.clone())));
+
+        // デプロイ者以外は登録できず、デプロイ者も権限の外にいれば登録できない
+        let other = key(2);
+        assert!(matches!(registry.check(&tx(&other, 0, &register("token", &proxy, "aa", UpgradeAuthority::Owner {
+            address: address(&other),
+        }))).await, Err(ProxyError::NotDeployer { .. })));
+        assert!(matches!(registry.check(&tx(&owner, 1, &register("token", &proxy, "aa", UpgradeAuthority::Owner {
+            address: address(&other),
+        }))).await, Err(ProxyError::Unauthorized(_))));
+
+        registry.apply_block(&block(2, vec![
+            tx(&owner, 1, &op),
+            tx(&other, 0, &upgrade("token", "0xbb", 1, &[])),
+            tx(&owner, 2, &upgrade("token", "0xbb", 1, &[])),
+        ])).await.unwrap();
+        // 同じバージョンでの再送は拒否
+        assert!(matches!(
+            registry.check(&tx(&owner, 3, &upgrade("token", "0xcc", 1, &[]))).await,
+            Err(ProxyError::VersionMismatch { expected: 2, got: 1 })
+        ));
+
+        assert_eq!(registry.resolve("token").await.unwrap().as_deref(), Some("bb"));
+        let record = registry.find_by_address("0xAA").await.unwrap().unwrap();
+        assert_eq!(record.history.len(), 2);
+        assert_eq!(record.history[1].previous_implementation.as_deref(), Some("aa"));
+
+        // 登録済みのアドレスを別のIDで登録し直して乗っ取ることはできない
+        assert!(matches!(
+            registry.check(&tx(&owner, 3, &register("token2", &proxy, "dd", authority))).await,
+            Err(ProxyError::AddressInUse { id, .. }) if id == "token"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_governance_threshold() {
+        let members = [key(1), key(2), key(3)];
+        let (registry, proxy) = registry(&members[0]).await;
+        let authority = UpgradeAuthority::Governance {
+            members: members.iter().map(address).collect(),
+            threshold: 2,
+        };
+        registry.apply_block(&block(2, vec![tx(&members[0], 1, &register("dao", &proxy, "02", authority))]))
+            .await.unwrap();
+
+        let err = registry.check(&tx(&members[0], 2, &upgrade("dao", "03", 1, &[&key(9)]))).await.unwrap_err();
+        assert!(matches!(err, ProxyError::InsufficientApprovals { got: 1, threshold: 2 }));
+
+        registry.apply_block(&block(3, vec![tx(&members[0], 2, &upgrade("dao", "03", 1, &[&members[2]]))]))
+            .await.unwrap();
+        let record = registry.get("dao").await.unwrap().unwrap();
+        assert_eq!((record.version, record.history[1].approvals.len()), (2, 2));
+        assert_eq!(registry.applied_height().await.unwrap(), Some(3));
+    }
+}
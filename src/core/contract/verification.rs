@@ -0,0 +1,611 @@
+//! コントラクトのソースコード検証
+//!
+//! 提出されたソースコードを宣言されたコンパイラ・設定でコンパイルし、
+//! デプロイ時に記録した作成バイトコードと照合します。
+//! 主な機能：
+//! - バージョンごとのコンパイラバイナリの解決（コンパイラマトリクス）
+//! - 完全一致 / 部分一致（メタデータハッシュのみ相違）の判定
+//! - 検証済みソースとABIの保存
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+use crate::core::storage::migration::Change;
+
+/// 作成バイトコードのキープレフィックス
+const CREATION_CODE_PREFIX: &str = "contract/creation/";
+/// 同時に実行する検証数の既定の上限
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+/// コンパイラの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CompilerKind {
+    Solc,
+    Vyper,
+}
+
+impl CompilerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Solc => "solc",
+            Self::Vyper => "vyper",
+        }
+    }
+
+    fn language(&self) -> &'static str {
+        match self {
+            Self::Solc => "Solidity",
+            Self::Vyper => "Vyper",
+        }
+    }
+
+    fn source_file(&self, contract_name: &str) -> String {
+        match self {
+            Self::Solc => format!("{}.sol", contract_name),
+            Self::Vyper => format!("{}.vy", contract_name),
+        }
+    }
+}
+
+/// コンパイラ設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CompilerSettings {
+    /// コンパイラの種類
+    pub compiler: CompilerKind,
+    /// コンパイラのバージョン（例: 0.8.24）
+    pub version: String,
+    /// オプティマイザーの有効化
+    #[serde(default)]
+    pub optimizer: bool,
+    /// オプティマイザーの実行回数
+    #[serde(default = "default_optimizer_runs")]
+    pub optimizer_runs: u32,
+    /// EVMバージョン（未指定の場合はコンパイラのデフォルト）
+    #[serde(default)]
+    pub evm_version: Option<String>,
+}
+
+fn default_optimizer_runs() -> u32 {
+    200
+}
+
+/// 検証リクエスト
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerificationRequest {
+    /// コントラクト名
+    pub contract_name: String,
+    /// ソースコード
+    pub source: String,
+    /// コンパイラ設定
+    pub settings: CompilerSettings,
+    /// ABIエンコード済みコンストラクター引数（hex）
+    #[serde(default)]
+    pub constructor_args: String,
+}
+
+/// 照合結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchStatus {
+    /// メタデータを含めて完全に一致
+    Exact,
+    /// メタデータハッシュを除いて一致
+    Partial,
+}
+
+/// 検証済みコントラクト（`contract/source/{アドレス}`）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "contract/source", version = 1)]
+pub struct VerifiedContract {
+    #[state(key)]
+    pub address: String,
+    pub contract_name: String,
+    pub source: String,
+    pub settings: CompilerSettings,
+    #[schema(value_type = Object)]
+    pub abi: serde_json::Value,
+    pub constructor_args: String,
+    pub match_status: MatchStatus,
+    /// 検証時刻（UNIX秒）
+    pub verified_at: u64,
+}
+
+/// 一覧に表示する検証済みコントラクトの概要（ソースとABIを除く）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerifiedContractSummary {
+    pub address: String,
+    pub contract_name: String,
+    pub compiler: CompilerKind,
+    pub compiler_version: String,
+    pub match_status: MatchStatus,
+    /// 検証時刻（UNIX秒）
+    pub verified_at: u64,
+}
+
+impl VerifiedContractSummary {
+    pub fn of(contract: &VerifiedContract) -> Self {
+        Self {
+            address: contract.address.clone(),
+            contract_name: contract.contract_name.clone(),
+            compiler: contract.settings.compiler,
+            compiler_version: contract.settings.version.clone(),
+            match_status: contract.match_status,
+            verified_at: contract.verified_at,
+        }
+    }
+}
+
+/// コンパイル結果
+#[derive(Debug, Clone)]
+pub struct CompilerOutput {
+    pub creation_bytecode: Vec<u8>,
+    pub abi: serde_json::Value,
+}
+
+/// 検証エラー
+#[derive(Debug, Error)]
+pub enum VerificationError {
+    #[error("Compiler {compiler} {version} is not installed")]
+    CompilerNotFound { compiler: String, version: String },
+
+    #[error("No creation bytecode recorded for contract {0}")]
+    UnknownContract(String),
+
+    #[error("Compilation failed: {0}")]
+    CompilationFailed(String),
+
+    #[error("Contract {0} not found in compiler output")]
+    ContractNotInOutput(String),
+
+    #[error("Invalid constructor arguments: {0}")]
+    InvalidConstructorArgs(String),
+
+    #[error("Compiled bytecode does not match the deployed creation bytecode")]
+    Mismatch,
+
+    #[error("Too many verifications are running (at most {0})")]
+    Busy(usize),
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// コンパイラ
+#[async_trait]
+pub trait Compiler: Send + Sync {
+    async fn compile(
+        &self,
+        source: &str,
+        contract_name: &str,
+        settings: &CompilerSettings,
+    ) -> Result<CompilerOutput, VerificationError>;
+}
+
+/// コンパイラマトリクス
+///
+/// `compilers_dir` 以下に `solc-0.8.24` や `vyper-0.3.10` の形式で配置された
+/// バイナリをバージョンごとに解決し、standard JSON インターフェースで実行します。
+#[derive(Debug, Clone)]
+pub struct CompilerMatrix {
+    compilers_dir: PathBuf,
+    timeout: Duration,
+}
+
+impl CompilerMatrix {
+    pub fn new(compilers_dir: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            compilers_dir: compilers_dir.into(),
+            timeout,
+        }
+    }
+
+    /// コンパイラバイナリのパスを解決
+    pub fn resolve(&self, compiler: CompilerKind, version: &str) -> Option<PathBuf> {
+        let version = version.trim_start_matches('v');
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-') {
+            return None;
+        }
+        let path = self.compilers_dir.join(format!("{}-{}", compiler.name(), version));
+        path.is_file().then_some(path)
+    }
+
+    /// インストール済みのコンパイラ一覧
+    pub fn installed(&self) -> Vec<(CompilerKind, String)> {
+        let Ok(entries) = std::fs::read_dir(&self.compilers_dir) else {
+            return Vec::new();
+        };
+        let mut installed: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                [CompilerKind::Solc, CompilerKind::Vyper].into_iter().find_map(|kind| {
+                    name.strip_prefix(&format!("{}-", kind.name()))
+                        .map(|version| (kind, version.to_string()))
+                })
+            })
+            .collect();
+        installed.sort_by(|a, b| (a.0.name(), &a.1).cmp(&(b.0.name(), &b.1)));
+        installed
+    }
+
+    fn standard_json_input(source: &str, contract_name: &str, settings: &CompilerSettings) -> serde_json::Value {
+        let file = settings.compiler.source_file(contract_name);
+        let mut compiler_settings = json!({
+            "optimizer": {
+                "enabled": settings.optimizer,
+                "runs": settings.optimizer_runs,
+            },
+            "outputSelection": {
+                "*": { "*": ["abi", "evm.bytecode.object"] }
+            },
+        });
+        if settings.compiler == CompilerKind::Vyper {
+            if let Some(s) = compiler_settings.as_object_mut() {
+                s.remove("optimizer");
+                s.insert("optimize".to_string(), json!(if settings.optimizer { "gas" } else { "none" }));
+            }
+        }
+        if let Some(evm_version) = &settings.evm_version {
+            compiler_settings["evmVersion"] = json!(evm_version);
+        }
+
+        json!({
+            "language": settings.compiler.language(),
+            "sources": { file: { "content": source } },
+            "settings": compiler_settings,
+        })
+    }
+}
+
+#[async_trait]
+impl Compiler for CompilerMatrix {
+    async fn compile(
+        &self,
+        source: &str,
+        contract_name: &str,
+        settings: &CompilerSettings,
+    ) -> Result<CompilerOutput, VerificationError> {
+        let binary = self.resolve(settings.compiler, &settings.version).ok_or_else(|| {
+            VerificationError::CompilerNotFound {
+                compiler: settings.compiler.name().to_string(),
+                version: settings.version.clone(),
+            }
+        })?;
+
+        let input = Self::standard_json_input(source, contract_name, settings);
+        let mut child = tokio::process::Command::new(&binary)
+            .arg("--standard-json")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", binary.display(), e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.to_string().as_bytes()).await
+                .map_err(|e| anyhow::anyhow!("Failed to write compiler input: {}", e))?;
+        }
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| VerificationError::CompilationFailed(format!("timed out after {:?}", self.timeout)))?
+            .map_err(|e| anyhow::anyhow!("Compiler process failed: {}", e))?;
+        if !output.status.success() {
+            return Err(VerificationError::CompilationFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        let output: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .map_err(|e| anyhow::anyhow!("Invalid compiler output: {}", e))?;
+        parse_standard_json_output(&output, &settings.compiler.source_file(contract_name), contract_name)
+    }
+}
+
+/// standard JSON 出力からバイトコードとABIを取り出す
+fn parse_standard_json_output(
+    output: &serde_json::Value,
+    file: &str,
+    contract_name: &str,
+) -> Result<CompilerOutput, VerificationError> {
+    let errors: Vec<String> = output["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|e| e["severity"].as_str() == Some("error"))
+        .map(|e| e["formattedMessage"].as_str().or(e["message"].as_str()).unwrap_or("unknown error").to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(VerificationError::CompilationFailed(errors.join("\n")));
+    }
+
+    // Vyperはファイル名（拡張子なし）をコントラクト名として出力する
+    let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    let contract = output["contracts"][file]
+        .get(contract_name)
+        .or_else(|| output["contracts"][file].get(stem))
+        .ok_or_else(|| VerificationError::ContractNotInOutput(contract_name.to_string()))?;
+
+    let bytecode = contract["evm"]["bytecode"]["object"].as_str().unwrap_or_default();
+    let creation_bytecode = hex::decode(bytecode.trim_start_matches("0x"))
+        .map_err(|e| anyhow::anyhow!("Invalid bytecode in compiler output: {}", e))?;
+
+    Ok(CompilerOutput {
+        creation_bytecode,
+        abi: contract["abi"].clone(),
+    })
+}
+
+/// 末尾のCBORメタデータを取り除く
+///
+/// solc / vyper はバイトコード末尾にCBORエンコードされたメタデータと、
+/// その長さ（ビッグエンディアン2バイト）を付加します。
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    if code.len() < 2 {
+        return code;
+    }
+    let len = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+    if len == 0 || len + 2 > code.len() {
+        return code;
+    }
+    let start = code.len() - 2 - len;
+    // CBORのmap（メジャータイプ5）で始まる場合のみメタデータとみなす
+    if (0xa0..=0xbf).contains(&code[start]) {
+        &code[..start]
+    } else {
+        code
+    }
+}
+
+/// コンパイル結果と作成バイトコードを照合
+///
+/// デプロイ済みの作成バイトコードは、コンパイル結果の後ろにコンストラクター引数が連結されたものです。
+pub fn compare_bytecode(compiled: &[u8], deployed: &[u8], constructor_args: &[u8]) -> Option<MatchStatus> {
+    let body = deployed.strip_suffix(constructor_args)?;
+    if body == compiled {
+        return Some(MatchStatus::Exact);
+    }
+    let stripped_compiled = strip_metadata(compiled);
+    let stripped_body = strip_metadata(body);
+    (stripped_compiled.len() < compiled.len() && stripped_compiled == stripped_body)
+        .then_some(MatchStatus::Partial)
+}
+
+/// コントラクト検証サービス
+pub struct ContractVerifier {
+    compiler: Arc<dyn Compiler>,
+    storage: Arc<dyn StorageEngine>,
+    /// 同時に実行するコンパイルの上限
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+}
+
+impl std::fmt::Debug for ContractVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContractVerifier").field("storage", &self.storage).finish()
+    }
+}
+
+impl ContractVerifier {
+    pub fn new(compiler: Arc<dyn Compiler>, storage: Arc<dyn StorageEngine>) -> Self {
+        Self {
+            compiler,
+            storage,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT)),
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+        }
+    }
+
+    /// 同時に実行する検証数の上限を変更（上限に達している間の検証は `Busy` で拒否する）
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self.permits = Arc::new(Semaphore::new(self.max_concurrent));
+        self
+    }
+
+    /// デプロイ時の作成バイトコードを記録
+    pub async fn record_creation_code(&self, address: &str, code: &[u8]) -> anyhow::Result<()> {
+        self.storage.put(&creation_key(address), code).await
+    }
+
+    /// ソースコードを検証して保存
+    pub async fn verify(&self, address: &str, request: VerificationRequest) -> Result<VerifiedContract, VerificationError> {
+        let deployed = self.storage.get(&creation_key(address)).await?
+            .ok_or_else(|| VerificationError::UnknownContract(address.to_string()))?;
+        let constructor_args = hex::decode(request.constructor_args.trim_start_matches("0x"))
+            .map_err(|e| VerificationError::InvalidConstructorArgs(e.to_string()))?;
+
+        let _permit = self.permits.clone().try_acquire_owned()
+            .map_err(|_| VerificationError::Busy(self.max_concurrent))?;
+        let output = self.compiler
+            .compile(&request.source, &request.contract_name, &request.settings)
+            .await?;
+        let match_status = compare_bytecode(&output.creation_bytecode, &deployed, &constructor_args)
+            .ok_or(VerificationError::Mismatch)?;
+
+        let verified = VerifiedContract {
+            address: normalize_address(address),
+            contract_name: request.contract_name,
+            source: request.source,
+            settings: request.settings,
+            abi: output.abi,
+            constructor_args: hex::encode(&constructor_args),
+            match_status,
+            verified_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+
+        // 完全一致で検証済みのものを部分一致で上書きしない
+        if let Some(existing) = self.get_source(address).await? {
+            if existing.match_status == MatchStatus::Exact && match_status == MatchStatus::Partial {
+                warn!("Keeping exact match for {} over new partial match", verified.address);
+                return Ok(existing);
+            }
+        }
+
+        verified.save(self.storage.as_ref()).await?;
+        info!(
+            "Verified contract {} ({}, {} {}): {:?} match",
+            verified.address, verified.contract_name,
+            verified.settings.compiler.name(), verified.settings.version, match_status
+        );
+        Ok(verified)
+    }
+
+    /// 検証済みソースを取得
+    pub async fn get_source(&self, address: &str) -> anyhow::Result<Option<VerifiedContract>> {
+        VerifiedContract::load(self.storage.as_ref(), &normalize_address(address)).await
+    }
+
+    /// 検証済みのコントラクトをアドレスの順に取得（`after` を指定した場合はその次から）
+    pub async fn list_verified(&self, after: Option<&str>, limit: usize) -> anyhow::Result<Vec<VerifiedContract>> {
+        let after = after.map(normalize_address);
+        VerifiedContract::list(self.storage.as_ref(), after.as_ref(), limit).await
+    }
+}
+
+/// 作成バイトコードを記録した全てのコントラクト（アドレスの順）
+pub async fn creation_codes(storage: &dyn StorageEngine) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    const BATCH: usize = 1000;
+    let mut codes: Vec<(String, Vec<u8>)> = Vec::new();
+    loop {
+        let start = match codes.last() {
+            Some((address, _)) => [creation_key(address).as_slice(), &[0]].concat(),
+            None => CREATION_CODE_PREFIX.as_bytes().to_vec(),
+        };
+        let page = storage.scan(&start, BATCH).await?;
+        let done = page.len() < BATCH;
+        for (key, code) in page {
+            let Some(address) = key.strip_prefix(CREATION_CODE_PREFIX.as_bytes()) else {
+                return Ok(codes);
+            };
+            codes.push((String::from_utf8(address.to_vec())?, code));
+        }
+        if done {
+            return Ok(codes);
+        }
+    }
+}
+
+fn normalize_address(address: &str) -> String {
+    let lower = address.trim().to_ascii_lowercase();
+    lower.strip_prefix("0x").map(str::to_string).unwrap_or(lower)
+}
+
+/// 作成バイトコードを一括書き込みで記録するための変更
+pub(crate) fn creation_code_change(address: &str, code: &[u8]) -> Change {
+    (creation_key(address), Some(code.to_vec()))
+}
+
+fn creation_key(address: &str) -> Vec<u8> {
+    format!("{}{}", CREATION_CODE_PREFIX, normalize_address(address)).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    /// 固定の出力を返すコンパイラ
+    struct FixedCompiler(Vec<u8>);
+
+    #[async_trait]
+    impl Compiler for FixedCompiler {
+        async fn compile(&self, _: &str, _: &str, _: &CompilerSettings) -> Result<CompilerOutput, VerificationError> {
+            Ok(CompilerOutput {
+                creation_bytecode: self.0.clone(),
+                abi: json!([{ "type": "constructor", "inputs": [] }]),
+            })
+        }
+    }
+
+    fn with_metadata(code: &[u8], metadata: &[u8]) -> Vec<u8> {
+        let mut out = code.to_vec();
+        out.push(0xa2);
+        out.extend_from_slice(metadata);
+        out.extend_from_slice(&((metadata.len() + 1) as u16).to_be_bytes());
+        out
+    }
+
+    fn request() -> VerificationRequest {
+        VerificationRequest {
+            contract_name: "Counter".to_string(),
+            source: "contract Counter {}".to_string(),
+            settings: CompilerSettings {
+                compiler: CompilerKind::Solc,
+                version: "0.8.24".to_string(),
+                optimizer: true,
+                optimizer_runs: 200,
+                evm_version: None,
+            },
+            constructor_args: "0x2a".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_compare_bytecode() {
+        let compiled = with_metadata(&[0x60, 0x80, 0x60, 0x40], &[1, 2, 3]);
+        let mut deployed = compiled.clone();
+        deployed.push(0x2a);
+        assert_eq!(compare_bytecode(&compiled, &deployed, &[0x2a]), Some(MatchStatus::Exact));
+
+        let mut other_metadata = with_metadata(&[0x60, 0x80, 0x60, 0x40], &[9, 9, 9, 9]);
+        other_metadata.push(0x2a);
+        assert_eq!(compare_bytecode(&compiled, &other_metadata, &[0x2a]), Some(MatchStatus::Partial));
+
+        let mut different = with_metadata(&[0x60, 0x80, 0x60, 0x41], &[1, 2, 3]);
+        different.push(0x2a);
+        assert_eq!(compare_bytecode(&compiled, &different, &[0x2a]), None);
+    }
+
+    #[tokio::test]
+    async fn test_verify_stores_source_and_keeps_exact_match() {
+        let storage = RedbStorage::memory();
+
+        let compiled = with_metadata(&[0x60, 0x80], &[1, 2, 3]);
+        let mut deployed = compiled.clone();
+        deployed.push(0x2a);
+
+        let exact = ContractVerifier::new(Arc::new(FixedCompiler(compiled)), storage.clone());
+        assert!(matches!(exact.verify("0xABC", request()).await, Err(VerificationError::UnknownContract(_))));
+
+        exact.record_creation_code("0xABC", &deployed).await.unwrap();
+        let verified = exact.verify("0xABC", request()).await.unwrap();
+        assert_eq!(verified.match_status, MatchStatus::Exact);
+
+        let partial = ContractVerifier::new(Arc::new(FixedCompiler(with_metadata(&[0x60, 0x80], &[7, 7]))), storage);
+        let kept = partial.verify("abc", request()).await.unwrap();
+        assert_eq!(kept.match_status, MatchStatus::Exact);
+        assert_eq!(partial.get_source("0xabc").await.unwrap().unwrap().contract_name, "Counter");
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_beyond_max_concurrent() {
+        let storage = RedbStorage::memory();
+        let compiled = with_metadata(&[0x60, 0x80], &[1, 2, 3]);
+        let mut deployed = compiled.clone();
+        deployed.push(0x2a);
+        let verifier = ContractVerifier::new(Arc::new(FixedCompiler(compiled)), storage)
+            .with_max_concurrent(1);
+        verifier.record_creation_code("abc", &deployed).await.unwrap();
+
+        // 実行中の検証が上限を使い切っている
+        let running = verifier.permits.clone().try_acquire_owned().unwrap();
+        assert!(matches!(verifier.verify("abc", request()).await, Err(VerificationError::Busy(1))));
+        drop(running);
+        assert!(verifier.verify("abc", request()).await.is_ok());
+    }
+}
@@ -0,0 +1,985 @@
+//! ビーコンチェーン（シャードの調整用チェーン）
+//!
+//! シャードの構成、シャードごとのバリデーターの割り当て、各シャードのチェックポイント
+//! （状態の根とクロスシャードメッセージの根）を、ブロックチェーンとは別の軽量なチェーンで管理します。
+//! すべてのノードは確定したビーコンのブロックの操作だけを状態に適用するため、同じ高さでは
+//! 同じシャード構成と割り当てになり、各モジュールが「シャードは1つ」と仮定する必要がなくなります。
+//!
+//! ビーコンのブロックは独自の合意形成で確定します。提案者は高さとラウンドによる
+//! ビーコンのバリデーターのラウンドロビンで、投票は HotStuff の `Proposal`・`Vote` を使い、
+//! 安全性の状態はブロックチェーンとは別のキー（`beacon/safety`）に保存します。
+//! 2/3を超える投票のQCを集めたブロックだけを確定します。提案者がブロックを確定できないまま
+//! 1周期が過ぎた場合は、ラウンドを進めて次の提案者に交代します。
+//!
+//! バリデーターが複数の場合は、提案・投票・確定したブロックを `BeaconMessage` としてピアへ
+//! 中継します（`block::relay`）。バリデーター名は署名鍵のアドレスで、提案と投票には
+//! `ConsensusMessage` のエンコードへの ed25519 の署名を付け、確定したブロックには
+//! QCの投票者全員の署名を付けます。受信側は署名と提案者を確認してから投票・確定するため、
+//! バリデーターでないノードも同じブロックを確定できます。先の高さのメッセージを受信した場合は、
+//! 足りないブロックを送信元に要求して追いつきます。
+//!
+//! シャードの構成はビーコンチェーンが正で、`ShardManager` は確定した状態に合わせてシャードを
+//! 作成・削除します。負荷によるシャードの追加も `AddShard` の提案として合意してから反映します。
+//!
+//! バリデーターの割り当ては、シャードかビーコンのバリデーターが変わるたびに、バリデーターを
+//! シャードIDの順にラウンドロビンで割り振り直します。バリデーターがシャードより少ない場合は兼任します。
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, anyhow, ensure};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+use crate::core::consensus::messages::{ConsensusMessage, Proposal, QuorumCert, Vote};
+use crate::core::consensus::safety::SafetyRules;
+use crate::core::sharding::{ShardId, ShardManager};
+use crate::core::sharding::checkpoint::{self, ShardCheckpoint};
+use crate::core::storage::StorageEngine;
+
+/// 確定した最新のブロックのキー
+const HEAD_KEY: &[u8] = b"beacon/head";
+/// 最新のブロックを適用した状態のキー
+const STATE_KEY: &[u8] = b"beacon/state";
+/// 高さごとのブロックのキープレフィックス
+const BLOCK_PREFIX: &str = "beacon/block/";
+/// ビーコンの合意形成の安全性の状態のキー
+const SAFETY_KEY: &[u8] = b"beacon/safety";
+/// 1ブロックに含める操作の上限
+const MAX_OPS: usize = 256;
+/// 現在のラウンドより先の投票を受け付けるラウンド数
+const MAX_ROUNDS_AHEAD: u32 = 16;
+/// 中継待ちのメッセージと確定の通知の数
+const CHANNEL_CAPACITY: usize = 256;
+
+/// ビーコンのブロックの操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BeaconOp {
+    /// シャードを追加
+    AddShard { shard: ShardId },
+    /// シャードを削除
+    RemoveShard { shard: ShardId },
+    /// ビーコンのバリデーターを変更
+    SetValidators { validators: Vec<String> },
+    /// シャードのチェックポイントを記録
+    Checkpoint { checkpoint: ShardCheckpoint },
+}
+
+/// 操作を適用できない理由
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BeaconError {
+    #[error("shard {0} already exists")]
+    ShardExists(ShardId),
+
+    #[error("shard {0} does not exist")]
+    UnknownShard(ShardId),
+
+    #[error("the last shard cannot be removed")]
+    LastShard,
+
+    #[error("the beacon needs at least one validator")]
+    NoValidators,
+
+    #[error("checkpoint {sequence} of shard {shard} does not match its hash")]
+    InvalidCheckpoint { shard: ShardId, sequence: u64 },
+
+    #[error("checkpoint {sequence} of shard {shard} is not after the recorded checkpoint {recorded}")]
+    StaleCheckpoint { shard: ShardId, sequence: u64, recorded: u64 },
+}
+
+/// シャードの記録
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ShardEntry {
+    /// 割り当てられたバリデーター
+    pub validators: Vec<String>,
+    /// 最後に記録したチェックポイント
+    pub checkpoint: Option<ShardCheckpoint>,
+}
+
+/// ビーコンの状態
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BeaconState {
+    /// 最後に適用したブロックの高さ（ジェネシスは0）
+    pub height: u64,
+    /// ビーコンのバリデーター（昇順）
+    pub validators: Vec<String>,
+    pub shards: BTreeMap<ShardId, ShardEntry>,
+}
+
+impl BeaconState {
+    /// シャード0だけのジェネシス状態
+    pub fn genesis(validators: Vec<String>) -> Self {
+        let mut state = Self {
+            height: 0,
+            validators: normalize(validators),
+            shards: BTreeMap::from([(0, ShardEntry::default())]),
+        };
+        state.reassign();
+        state
+    }
+
+    /// 操作を適用（適用できない場合は状態を変えない）
+    pub fn apply(&mut self, op: &BeaconOp) -> Result<(), BeaconError> {
+        match op {
+            BeaconOp::AddShard { shard } => {
+                if self.shards.contains_key(shard) {
+                    return Err(BeaconError::ShardExists(*shard));
+                }
+                self.shards.insert(*shard, ShardEntry::default());
+                self.reassign();
+            }
+            BeaconOp::RemoveShard { shard } => {
+                if !self.shards.contains_key(shard) {
+                    return Err(BeaconError::UnknownShard(*shard));
+                }
+                if self.shards.len() == 1 {
+                    return Err(BeaconError::LastShard);
+                }
+                self.shards.remove(shard);
+                self.reassign();
+            }
+            BeaconOp::SetValidators { validators } => {
+                let validators = normalize(validators.clone());
+                if validators.is_empty() {
+                    return Err(BeaconError::NoValidators);
+                }
+                self.validators = validators;
+                self.reassign();
+            }
+            BeaconOp::Checkpoint { checkpoint } => {
+                let entry = self.shards.get_mut(&checkpoint.shard)
+                    .ok_or(BeaconError::UnknownShard(checkpoint.shard))?;
+                if checkpoint.compute_hash() != checkpoint.hash {
+                    return Err(BeaconError::InvalidCheckpoint { shard: checkpoint.shard, sequence: checkpoint.sequence });
+                }
+                if let Some(recorded) = entry.checkpoint.as_ref().filter(|c| checkpoint.sequence <= c.sequence) {
+                    return Err(BeaconError::StaleCheckpoint {
+                        shard: checkpoint.shard,
+                        sequence: checkpoint.sequence,
+                        recorded: recorded.sequence,
+                    });
+                }
+                entry.checkpoint = Some(checkpoint.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// シャードに割り当てられたバリデーター
+    pub fn validators_of(&self, shard: ShardId) -> &[String] {
+        self.shards.get(&shard).map_or(&[], |entry| &entry.validators)
+    }
+
+    /// シャードごとのバリデーターの割り当て
+    pub fn assignments(&self) -> BTreeMap<ShardId, Vec<String>> {
+        self.shards.iter().map(|(id, entry)| (*id, entry.validators.clone())).collect()
+    }
+
+    /// 合意に必要な投票数（2/3を超える数）
+    pub fn quorum(&self) -> usize {
+        self.validators.len() * 2 / 3 + 1
+    }
+
+    /// 高さとラウンドの提案者
+    pub fn leader(&self, height: u64, round: u32) -> Option<&str> {
+        let count = self.validators.len() as u64;
+        (count > 0).then(|| self.validators[((height + round as u64) % count) as usize].as_str())
+    }
+
+    /// バリデーターをシャードIDの順にラウンドロビンで割り振る
+    fn reassign(&mut self) {
+        for entry in self.shards.values_mut() {
+            entry.validators.clear();
+        }
+        if self.validators.is_empty() {
+            return;
+        }
+        let shards = self.shards.len();
+        let rounds = self.validators.len().max(shards);
+        let ids: Vec<ShardId> = self.shards.keys().copied().collect();
+        for i in 0..rounds {
+            let validator = &self.validators[i % self.validators.len()];
+            let entry = self.shards.get_mut(&ids[i % shards]).expect("shard exists");
+            if !entry.validators.contains(validator) {
+                entry.validators.push(validator.clone());
+            }
+        }
+    }
+}
+
+/// ビーコンのブロック
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BeaconBlock {
+    pub height: u64,
+    pub round: u32,
+    pub parent_hash: String,
+    pub proposer: String,
+    pub timestamp: u64,
+    pub ops: Vec<BeaconOp>,
+    /// 含めたチェックポイントのレシートの根のマークル根（クロスシャードメッセージの根、hex）
+    pub message_root: String,
+    pub hash: String,
+    /// ブロックを確定したQC
+    #[schema(value_type = Option<Object>)]
+    pub qc: Option<QuorumCert>,
+    /// QCの投票者ごとの投票の署名（hex、署名しないノードでは空）
+    #[serde(default)]
+    pub signatures: BTreeMap<String, String>,
+}
+
+impl BeaconBlock {
+    fn new(height: u64, round: u32, parent_hash: String, proposer: String, ops: Vec<BeaconOp>) -> Self {
+        let mut block = Self {
+            height,
+            round,
+            parent_hash,
+            proposer,
+            timestamp: now(),
+            message_root: hex::encode(message_root(&ops)),
+            ops,
+            hash: String::new(),
+            qc: None,
+            signatures: BTreeMap::new(),
+        };
+        block.hash = block.compute_hash();
+        block
+    }
+
+    /// ハッシュの対象はハッシュ・QC・署名以外の全フィールド
+    pub fn compute_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.height.to_be_bytes());
+        hasher.update(self.round.to_be_bytes());
+        hasher.update(self.parent_hash.as_bytes());
+        hasher.update(self.proposer.as_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        hasher.update(serde_json::to_vec(&self.ops).unwrap_or_default());
+        hasher.update(self.message_root.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    fn proposal(&self, justify: Option<QuorumCert>) -> Proposal {
+        Proposal {
+            height: self.height,
+            round: self.round,
+            block_hash: self.hash.clone(),
+            parent_hash: self.parent_hash.clone(),
+            proposer: self.proposer.clone(),
+            justify,
+        }
+    }
+}
+
+/// ブロックに含めたチェックポイントの `SHA-256(シャード || 連番 || receipt_root)` のマークル根
+pub fn message_root(ops: &[BeaconOp]) -> [u8; 32] {
+    let leaves: Vec<[u8; 32]> = ops.iter()
+        .filter_map(|op| match op {
+            BeaconOp::Checkpoint { checkpoint } => {
+                let mut hasher = Sha256::new();
+                hasher.update(checkpoint.shard.to_be_bytes());
+                hasher.update(checkpoint.sequence.to_be_bytes());
+                hasher.update(checkpoint.receipt_root.as_bytes());
+                Some(hasher.finalize().into())
+            }
+            _ => None,
+        })
+        .collect();
+    checkpoint::merkle_root(&leaves)
+}
+
+/// ノード間で中継するビーコンの合意形成のメッセージ（`Message::Beacon` のペイロード）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BeaconMessage {
+    /// 提案者が署名したブロックの提案
+    Proposal { block: BeaconBlock, justify: Option<QuorumCert>, signature: String },
+    /// 投票者が署名した投票
+    Vote { vote: Vote, signature: String },
+    /// QCと投票者の署名を付けた確定したブロック
+    Commit { block: BeaconBlock },
+    /// 確定したブロックの要求（`Commit` で応答する）
+    GetBlock { height: u64 },
+}
+
+/// 受信したメッセージの処理結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Received {
+    /// 新しく受け付けたメッセージ（他のピアへ中継する）
+    Relay,
+    /// 既知・古い・無効なメッセージ
+    Ignored,
+    /// 確定したブロックより先の高さのメッセージ（`next` からのブロックを送信元に要求する）
+    Behind { next: u64 },
+    /// ブロックの要求への応答
+    Reply(Option<BeaconBlock>),
+}
+
+/// 投票を集めた結果
+enum Tally {
+    Ignored,
+    Counted,
+    Committed(BeaconBlock),
+}
+
+/// 現在の高さの提案と集めた投票
+#[derive(Debug, Default)]
+struct RoundState {
+    height: u64,
+    round: u32,
+    /// 現在のラウンドで周期を1回以上待ったか（次の周期でラウンドを進める）
+    ticked: bool,
+    candidate: Option<BeaconBlock>,
+    /// （ラウンド, ブロックのハッシュ）ごとの投票者と署名（署名のないローカルの投票は `None`）
+    votes: BTreeMap<(u32, String), BTreeMap<String, Option<String>>>,
+}
+
+impl RoundState {
+    /// 高さが変わった場合はラウンド0からやり直す
+    fn at(&mut self, height: u64) {
+        if self.height != height {
+            *self = Self { height, ..Default::default() };
+        }
+    }
+
+    /// ラウンドを進め、それより前のラウンドの提案と投票を捨てる
+    fn enter(&mut self, round: u32) {
+        self.round = round;
+        self.ticked = false;
+        self.candidate = None;
+        self.votes.retain(|(vote_round, _), _| *vote_round >= round);
+    }
+
+    /// 提案が投票の定足数を集めていれば、QCと署名を付けたブロックを返す
+    fn certified(&mut self, quorum: usize) -> Option<BeaconBlock> {
+        let candidate = self.candidate.as_ref()?;
+        let votes = self.votes.get(&(candidate.round, candidate.hash.clone()))?;
+        if votes.len() < quorum {
+            return None;
+        }
+        let mut block = self.candidate.take()?;
+        block.qc = Some(QuorumCert::new(block.height, block.round, block.hash.clone(), votes.keys().cloned().collect()));
+        block.signatures = votes.iter()
+            .filter_map(|(voter, signature)| Some((voter.clone(), signature.clone()?)))
+            .collect();
+        self.votes.clear();
+        Some(block)
+    }
+}
+
+/// ビーコンチェーン
+#[derive(Debug)]
+pub struct BeaconChain {
+    storage: Arc<dyn StorageEngine>,
+    /// このノードのバリデーター名（署名する場合は署名鍵のアドレス）
+    validator: String,
+    /// 提案と投票に署名する鍵（なければ提案と投票をピアへ送らない）
+    signing_key: Option<SigningKey>,
+    state: RwLock<BeaconState>,
+    head: RwLock<Option<BeaconBlock>>,
+    safety: Mutex<SafetyRules>,
+    /// ブロックに含める前の操作
+    pending: Mutex<Vec<BeaconOp>>,
+    round: Mutex<RoundState>,
+    /// ピアへ送るメッセージ
+    outbound: broadcast::Sender<BeaconMessage>,
+    /// 確定したブロックの通知
+    committed: broadcast::Sender<BeaconBlock>,
+}
+
+impl BeaconChain {
+    /// 保存された状態を復元する（なければジェネシスから始める）
+    ///
+    /// 設定のバリデーターが記録と異なる場合は、変更を次のブロックで提案します。
+    pub async fn open(storage: Arc<dyn StorageEngine>, validator: &str, validators: Vec<String>) -> Result<Self> {
+        let mut validators = normalize(validators);
+        if validators.is_empty() {
+            validators.push(validator.to_string());
+        }
+        let state = match storage.get(STATE_KEY).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => BeaconState::genesis(validators.clone()),
+        };
+        let head: Option<BeaconBlock> = match storage.get(HEAD_KEY).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        };
+        let mut pending = Vec::new();
+        if state.validators != validators {
+            info!("Proposing beacon validator change to {:?}", validators);
+            pending.push(BeaconOp::SetValidators { validators });
+        }
+        let safety = SafetyRules::open_at(storage.clone(), SAFETY_KEY, validator).await?;
+        Ok(Self {
+            storage,
+            validator: validator.to_string(),
+            signing_key: None,
+            state: RwLock::new(state),
+            head: RwLock::new(head),
+            safety: Mutex::new(safety),
+            pending: Mutex::new(pending),
+            round: Mutex::new(RoundState::default()),
+            outbound: broadcast::channel(CHANNEL_CAPACITY).0,
+            committed: broadcast::channel(CHANNEL_CAPACITY).0,
+        })
+    }
+
+    /// 提案と投票に署名してピアへ送る（バリデーター名は鍵のアドレスにすること）
+    pub fn with_signing_key(mut self, key: SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// 現在の状態
+    pub async fn state(&self) -> BeaconState {
+        self.state.read().await.clone()
+    }
+
+    /// 確定した最新のブロック
+    pub async fn head(&self) -> Option<BeaconBlock> {
+        self.head.read().await.clone()
+    }
+
+    /// 高さのブロック
+    pub async fn block(&self, height: u64) -> Result<Option<BeaconBlock>> {
+        Ok(match self.storage.get(&block_key(height)).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        })
+    }
+
+    /// ピアへ送るメッセージを購読
+    pub fn subscribe(&self) -> broadcast::Receiver<BeaconMessage> {
+        self.outbound.subscribe()
+    }
+
+    /// 確定したブロックを購読
+    pub fn subscribe_committed(&self) -> broadcast::Receiver<BeaconBlock> {
+        self.committed.subscribe()
+    }
+
+    /// 次のブロックに含める操作を追加（同じ操作が待機中なら追加しない）
+    pub async fn submit(&self, op: BeaconOp) {
+        let mut pending = self.pending.lock().await;
+        if !pending.contains(&op) {
+            pending.push(op);
+        }
+    }
+
+    /// 担当であればブロックを提案する
+    ///
+    /// 前回の周期から確定しないままの高さでは、ラウンドを進めて次の提案者に交代します。
+    /// ピアの提案で現在のラウンドに入った直後は、1周期待ってから進めます。
+    pub async fn propose(&self) -> Result<Option<Proposal>> {
+        let state = self.state.read().await.clone();
+        let head = self.head.read().await.clone();
+        let height = state.height + 1;
+
+        let mut round = self.round.lock().await;
+        if round.height != height {
+            round.at(height);
+        } else if round.ticked {
+            let next = round.round + 1;
+            round.enter(next);
+        }
+        round.ticked = true;
+        if state.leader(height, round.round) != Some(self.validator.as_str()) || round.candidate.is_some() {
+            return Ok(None);
+        }
+
+        // 適用できない操作は捨てる
+        let mut next = state.clone();
+        let mut ops = Vec::new();
+        self.pending.lock().await.retain(|op| {
+            if ops.len() >= MAX_OPS {
+                return true;
+            }
+            match next.apply(op) {
+                Ok(()) => {
+                    ops.push(op.clone());
+                    true
+                }
+                Err(e) => {
+                    warn!("Dropping beacon operation: {}", e);
+                    false
+                }
+            }
+        });
+        let parent_hash = head.as_ref().map_or_else(String::new, |block| block.hash.clone());
+        let block = BeaconBlock::new(height, round.round, parent_hash, self.validator.clone(), ops);
+        let proposal = block.proposal(head.and_then(|block| block.qc));
+        round.candidate = Some(block);
+        Ok(Some(proposal))
+    }
+
+    /// 提案に投票する（安全性の状態を保存してから投票を返す）
+    pub async fn vote(&self, proposal: &Proposal) -> Result<Vote> {
+        Ok(self.safety.lock().await.vote(proposal).await?)
+    }
+
+    /// ローカルの投票を集め、2/3を超えたら提案したブロックを確定する
+    ///
+    /// ビーコンのバリデーター以外の投票は無視します。ピアの投票は署名を確認する `receive` で渡します。
+    pub async fn on_vote(&self, vote: &Vote) -> Result<Option<BeaconBlock>> {
+        Ok(match self.add_vote(vote, None).await? {
+            Tally::Committed(block) => Some(block),
+            Tally::Ignored | Tally::Counted => None,
+        })
+    }
+
+    /// 担当であれば提案し、自分の投票を加える（署名鍵があれば提案と投票をピアへ送る）
+    pub async fn step(&self) -> Result<Option<BeaconBlock>> {
+        let Some(proposal) = self.propose().await? else {
+            return Ok(None);
+        };
+        let vote = self.vote(&proposal).await?;
+        let Some(key) = &self.signing_key else {
+            return self.on_vote(&vote).await;
+        };
+        if let Some(block) = self.round.lock().await.candidate.clone() {
+            let signature = sign(key, &ConsensusMessage::Proposal(proposal.clone()))?;
+            self.send(BeaconMessage::Proposal { block, justify: proposal.justify, signature });
+        }
+        self.sign_vote(key, vote).await
+    }
+
+    /// ピアから受信したメッセージを処理する
+    pub async fn receive(&self, message: BeaconMessage) -> Result<Received> {
+        match message {
+            BeaconMessage::Proposal { block, justify, signature } => self.receive_proposal(block, justify, &signature).await,
+            BeaconMessage::Vote { vote, signature } => self.receive_vote(vote, signature).await,
+            BeaconMessage::Commit { block } => self.receive_commit(block).await,
+            BeaconMessage::GetBlock { height } => Ok(Received::Reply(self.block(height).await?)),
+        }
+    }
+
+    /// 提案者の署名と内容を確認し、受け付けたら投票する
+    async fn receive_proposal(&self, block: BeaconBlock, justify: Option<QuorumCert>, signature: &str) -> Result<Received> {
+        let state = self.state.read().await.clone();
+        let head = self.head.read().await.clone();
+        if let Some(received) = position(&state, block.height) {
+            return Ok(received);
+        }
+        if let Err(e) = check_block(&state, head.as_ref(), &block) {
+            debug!("Ignoring beacon proposal {} round {}: {}", block.height, block.round, e);
+            return Ok(Received::Ignored);
+        }
+        // 親のQCは確定した先頭のブロックのものであること（投票者はノードごとに異なってよい）
+        let certified = match (&head, &justify) {
+            (None, None) => true,
+            (Some(head), Some(qc)) => (qc.height, qc.round, qc.block_hash.as_str()) == (head.height, head.round, head.hash.as_str()),
+            _ => false,
+        };
+        let proposal = block.proposal(justify);
+        if !certified || !verify(&block.proposer, &ConsensusMessage::Proposal(proposal.clone()), signature) {
+            debug!("Ignoring beacon proposal {} round {} with an invalid justification or signature", block.height, block.round);
+            return Ok(Received::Ignored);
+        }
+
+        {
+            let mut round = self.round.lock().await;
+            round.at(block.height);
+            let duplicate = round.candidate.as_ref().is_some_and(|candidate| candidate.round == block.round);
+            if block.round < round.round || duplicate {
+                return Ok(Received::Ignored);
+            }
+            round.enter(block.round);
+            round.candidate = Some(block);
+        }
+
+        match (&self.signing_key, state.validators.contains(&self.validator)) {
+            (Some(key), true) => match self.vote(&proposal).await {
+                Ok(vote) => {
+                    self.sign_vote(key, vote).await?;
+                }
+                Err(e) => warn!("Not voting for beacon proposal {} round {}: {}", proposal.height, proposal.round, e),
+            },
+            // 提案より先に届いた投票で定足数に達していれば確定する
+            _ => {
+                self.tally(state.quorum()).await?;
+            }
+        }
+        Ok(Received::Relay)
+    }
+
+    /// 投票者の署名を確認して投票を集める
+    async fn receive_vote(&self, vote: Vote, signature: String) -> Result<Received> {
+        if let Some(received) = position(&*self.state.read().await, vote.height) {
+            return Ok(received);
+        }
+        if !verify(&vote.voter, &ConsensusMessage::Vote(vote.clone()), &signature) {
+            debug!("Ignoring beacon vote from {} with an invalid signature", vote.voter);
+            return Ok(Received::Ignored);
+        }
+        Ok(match self.add_vote(&vote, Some(signature)).await? {
+            Tally::Ignored => Received::Ignored,
+            Tally::Counted | Tally::Committed(_) => Received::Relay,
+        })
+    }
+
+    /// QCの投票者全員の署名を確認して確定する
+    async fn receive_commit(&self, block: BeaconBlock) -> Result<Received> {
+        let state = self.state.read().await.clone();
+        let head = self.head.read().await.clone();
+        if let Some(received) = position(&state, block.height) {
+            return Ok(received);
+        }
+        if let Err(e) = check_block(&state, head.as_ref(), &block).and_then(|()| check_certificate(&state, &block)) {
+            debug!("Ignoring beacon block {}: {}", block.height, e);
+            return Ok(Received::Ignored);
+        }
+        Ok(match self.commit(block).await? {
+            Some(_) => Received::Relay,
+            None => Received::Ignored,
+        })
+    }
+
+    /// 自分の投票に署名してピアへ送り、集計に加える
+    async fn sign_vote(&self, key: &SigningKey, vote: Vote) -> Result<Option<BeaconBlock>> {
+        let signature = sign(key, &ConsensusMessage::Vote(vote.clone()))?;
+        self.send(BeaconMessage::Vote { vote: vote.clone(), signature: signature.clone() });
+        Ok(match self.add_vote(&vote, Some(signature)).await? {
+            Tally::Committed(block) => Some(block),
+            Tally::Ignored | Tally::Counted => None,
+        })
+    }
+
+    /// 投票を集め、2/3を超えたら提案されたブロックを確定してピアへ送る
+    ///
+    /// 提案より先に届いた投票は、先のラウンドの分まで保持します。
+    async fn add_vote(&self, vote: &Vote, signature: Option<String>) -> Result<Tally> {
+        let quorum = {
+            let state = self.state.read().await;
+            if !state.validators.contains(&vote.voter) {
+                debug!("Ignoring beacon vote from non-validator {}", vote.voter);
+                return Ok(Tally::Ignored);
+            }
+            if vote.height != state.height + 1 {
+                return Ok(Tally::Ignored);
+            }
+            state.quorum()
+        };
+        {
+            let mut round = self.round.lock().await;
+            round.at(vote.height);
+            if vote.round < round.round || vote.round > round.round + MAX_ROUNDS_AHEAD {
+                return Ok(Tally::Ignored);
+            }
+            let votes = round.votes.entry((vote.round, vote.block_hash.clone())).or_default();
+            if votes.contains_key(&vote.voter) {
+                return Ok(Tally::Ignored);
+            }
+            votes.insert(vote.voter.clone(), signature);
+        }
+        Ok(match self.tally(quorum).await? {
+            Some(block) => Tally::Committed(block),
+            None => Tally::Counted,
+        })
+    }
+
+    /// 現在の提案が定足数に達していれば確定し、署名付きのブロックをピアへ送る
+    async fn tally(&self, quorum: usize) -> Result<Option<BeaconBlock>> {
+        let Some(block) = self.round.lock().await.certified(quorum) else {
+            return Ok(None);
+        };
+        let committed = self.commit(block).await?;
+        if let Some(block) = &committed {
+            if self.signing_key.is_some() {
+                self.send(BeaconMessage::Commit { block: block.clone() });
+            }
+        }
+        Ok(committed)
+    }
+
+    /// QCを持つブロックの操作を適用し、ブロック・状態・先頭を1回のバッチで保存
+    ///
+    /// 別の経路で同じ高さを確定済みの場合は何もしません。
+    async fn commit(&self, block: BeaconBlock) -> Result<Option<BeaconBlock>> {
+        let mut state = self.state.write().await;
+        if block.height != state.height + 1 {
+            return Ok(None);
+        }
+        let mut next = state.clone();
+        for op in &block.ops {
+            next.apply(op)?;
+        }
+        next.height = block.height;
+
+        let encoded = serde_json::to_vec(&block)?;
+        self.storage.batch_write(vec![
+            (block_key(block.height), Some(encoded.clone())),
+            (HEAD_KEY.to_vec(), Some(encoded)),
+            (STATE_KEY.to_vec(), Some(serde_json::to_vec(&next)?)),
+        ]).await?;
+        *state = next;
+        *self.head.write().await = Some(block.clone());
+        drop(state);
+        self.pending.lock().await.retain(|op| !block.ops.contains(op));
+        info!("Beacon block {} committed with {} operations", block.height, block.ops.len());
+        let _ = self.committed.send(block.clone());
+        Ok(Some(block))
+    }
+
+    fn send(&self, message: BeaconMessage) {
+        // 購読者（中継）がいない場合は送らない
+        let _ = self.outbound.send(message);
+    }
+
+    /// ラウンドを定期的に進め、確定した構成とバリデーターの割り当てをシャードへ反映する
+    ///
+    /// 起動時と確定のたびに、このノードのシャードを確定した状態に合わせます。
+    pub fn spawn(self: Arc<Self>, shards: Arc<RwLock<ShardManager>>, interval: Duration) {
+        let beacon = self.clone();
+        let mut committed = self.subscribe_committed();
+        tokio::spawn(async move {
+            loop {
+                let assignments = beacon.state.read().await.assignments();
+                if let Err(e) = shards.write().await.apply_membership(&assignments).await {
+                    error!("Failed to apply beacon shard membership: {}", e);
+                }
+                match committed.recv().await {
+                    // 取りこぼした分も現在の状態に含まれる
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.step().await {
+                    error!("Beacon round failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// メッセージの高さが次に確定する高さでなければ、その扱いを返す
+fn position(state: &BeaconState, height: u64) -> Option<Received> {
+    let next = state.height + 1;
+    match height.cmp(&next) {
+        std::cmp::Ordering::Less => Some(Received::Ignored),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(Received::Behind { next }),
+    }
+}
+
+/// 受信したブロックが確定した先頭に続き、高さとラウンドの提案者が作成したものか確認する
+fn check_block(state: &BeaconState, head: Option<&BeaconBlock>, block: &BeaconBlock) -> Result<()> {
+    ensure!(block.compute_hash() == block.hash, "hash does not match the contents");
+    ensure!(block.message_root == hex::encode(message_root(&block.ops)), "message root does not match the operations");
+    let parent_hash = head.map_or("", |head| head.hash.as_str());
+    ensure!(block.parent_hash == parent_hash, "parent {} is not the committed head", block.parent_hash);
+    ensure!(
+        state.leader(block.height, block.round) == Some(block.proposer.as_str()),
+        "{} is not the proposer of round {}", block.proposer, block.round
+    );
+    ensure!(block.ops.len() <= MAX_OPS, "{} operations exceed the limit of {}", block.ops.len(), MAX_OPS);
+    let mut next = state.clone();
+    for op in &block.ops {
+        next.apply(op)?;
+    }
+    Ok(())
+}
+
+/// QCが定足数のバリデーターの署名付きの投票でブロックを証明しているか確認する
+fn check_certificate(state: &BeaconState, block: &BeaconBlock) -> Result<()> {
+    let qc = block.qc.as_ref().ok_or_else(|| anyhow!("block has no quorum certificate"))?;
+    ensure!(
+        (qc.height, qc.round, qc.block_hash.as_str()) == (block.height, block.round, block.hash.as_str()),
+        "quorum certificate is for another block"
+    );
+    ensure!(qc.voters.windows(2).all(|pair| pair[0] < pair[1]), "quorum certificate voters are not sorted and unique");
+    ensure!(qc.voters.len() >= state.quorum(), "{} voters are below the quorum of {}", qc.voters.len(), state.quorum());
+    for voter in &qc.voters {
+        ensure!(state.validators.contains(voter), "{} is not a beacon validator", voter);
+        let vote = Vote { height: qc.height, round: qc.round, block_hash: qc.block_hash.clone(), voter: voter.clone() };
+        let signature = block.signatures.get(voter).map_or("", String::as_str);
+        ensure!(verify(voter, &ConsensusMessage::Vote(vote), signature), "signature of {} does not match", voter);
+    }
+    Ok(())
+}
+
+/// 合意形成のメッセージのエンコードに署名する（hex）
+fn sign(key: &SigningKey, message: &ConsensusMessage) -> Result<String> {
+    Ok(hex::encode(key.sign(&message.encode()?).to_bytes()))
+}
+
+/// 署名者のアドレス（公開鍵のhex）の鍵による署名か確認する
+fn verify(signer: &str, message: &ConsensusMessage, signature: &str) -> bool {
+    let key = hex::decode(signer).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok());
+    let signature = hex::decode(signature).ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .map(|bytes| Signature::from_bytes(&bytes));
+    match (key, signature, message.encode()) {
+        (Some(key), Some(signature), Ok(bytes)) => key.verify_strict(&bytes, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+/// バリデーターを昇順に並べ、重複を取り除く
+fn normalize(mut validators: Vec<String>) -> Vec<String> {
+    validators.sort();
+    validators.dedup();
+    validators
+}
+
+fn block_key(height: u64) -> Vec<u8> {
+    format!("{}{:016x}", BLOCK_PREFIX, height).into_bytes()
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn checkpoint(shard: ShardId, parent: Option<&ShardCheckpoint>) -> ShardCheckpoint {
+        ShardCheckpoint::new(shard, parent, [shard as u8; 32], &[], 0)
+    }
+
+    #[tokio::test]
+    async fn test_beacon_commits_with_quorum_and_assigns_validators() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let validators = vec!["v3".to_string(), "v1".to_string(), "v2".to_string()];
+        let beacon = BeaconChain::open(storage.clone(), "v1", validators.clone()).await.unwrap();
+        assert_eq!(beacon.state().await.validators_of(0), ["v1", "v2", "v3"]);
+
+        let first = checkpoint(1, None);
+        beacon.submit(BeaconOp::AddShard { shard: 1 }).await;
+        beacon.submit(BeaconOp::Checkpoint { checkpoint: first.clone() }).await;
+        beacon.submit(BeaconOp::RemoveShard { shard: 7 }).await;
+
+        // 高さ1の提案者はラウンド0が v2、ラウンド1が v3、ラウンド2が v1
+        assert!(beacon.step().await.unwrap().is_none());
+        assert!(beacon.step().await.unwrap().is_none());
+        let proposal = beacon.propose().await.unwrap().unwrap();
+        assert_eq!((proposal.height, proposal.round), (1, 2));
+        let own = beacon.vote(&proposal).await.unwrap();
+        assert!(beacon.on_vote(&own).await.unwrap().is_none());
+        let vote = |voter: &str| Vote { voter: voter.to_string(), ..own.clone() };
+        assert!(beacon.on_vote(&vote("mallory")).await.unwrap().is_none());
+        assert!(beacon.on_vote(&vote("v2")).await.unwrap().is_none());
+        let block = beacon.on_vote(&vote("v3")).await.unwrap().unwrap();
+
+        // 適用できない操作は含めない
+        assert_eq!(block.ops.len(), 2);
+        assert_eq!(block.qc.as_ref().unwrap().voters, ["v1", "v2", "v3"]);
+        assert_eq!(block.message_root, hex::encode(message_root(&block.ops)));
+        let state = beacon.state().await;
+        assert_eq!(state.height, 1);
+        assert_eq!(state.validators_of(0), ["v1", "v3"]);
+        assert_eq!(state.validators_of(1), ["v2"]);
+        assert_eq!(state.shards[&1].checkpoint.as_ref(), Some(&first));
+
+        // 古いチェックポイントや改ざんしたチェックポイントは記録しない
+        let mut next = state.clone();
+        assert!(matches!(next.apply(&BeaconOp::Checkpoint { checkpoint: first.clone() }), Err(BeaconError::StaleCheckpoint { .. })));
+        let mut forged = checkpoint(1, Some(&first));
+        forged.receipt_root = hex::encode([9u8; 32]);
+        assert!(matches!(next.apply(&BeaconOp::Checkpoint { checkpoint: forged }), Err(BeaconError::InvalidCheckpoint { .. })));
+        assert_eq!(next, state);
+
+        // 再起動しても確定した状態を引き継ぎ、バリデーターの変更は次のブロックで合意する
+        drop(beacon);
+        let beacon = BeaconChain::open(storage, "v1", vec!["v1".to_string()]).await.unwrap();
+        assert_eq!(beacon.state().await, state);
+        assert_eq!(beacon.head().await, Some(block.clone()));
+        // 高さ2の提案者はラウンド0が v3、ラウンド1が v1
+        assert!(beacon.step().await.unwrap().is_none());
+        let proposal = beacon.propose().await.unwrap().unwrap();
+        assert_eq!(proposal.justify, block.qc);
+        let own = beacon.vote(&proposal).await.unwrap();
+        beacon.on_vote(&own).await.unwrap();
+        beacon.on_vote(&Vote { voter: "v2".to_string(), ..own.clone() }).await.unwrap();
+        beacon.on_vote(&Vote { voter: "v3".to_string(), ..own }).await.unwrap().unwrap();
+        let state = beacon.state().await;
+        assert_eq!(state.validators, ["v1"]);
+        assert_eq!(state.validators_of(1), ["v1"]);
+        assert_eq!(beacon.block(1).await.unwrap(), Some(block));
+    }
+
+    /// 各ノードが送ったメッセージを他のすべてのノードへ、なくなるまで届ける
+    async fn deliver(nodes: &[&BeaconChain], outbound: &mut [broadcast::Receiver<BeaconMessage>]) {
+        loop {
+            let mut delivered = false;
+            for (i, receiver) in outbound.iter_mut().enumerate() {
+                while let Ok(message) = receiver.try_recv() {
+                    delivered = true;
+                    for (j, node) in nodes.iter().enumerate() {
+                        if i != j {
+                            node.receive(message.clone()).await.unwrap();
+                        }
+                    }
+                }
+            }
+            if !delivered {
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_messages_commit_on_every_node() {
+        let keys: Vec<SigningKey> = (1..=3u8).map(|i| SigningKey::from_bytes(&[i; 32])).collect();
+        let addresses: Vec<String> = keys.iter().map(|key| hex::encode(key.verifying_key().as_bytes())).collect();
+        let mut validators = Vec::new();
+        for (key, address) in keys.iter().zip(&addresses) {
+            let beacon = BeaconChain::open(RedbStorage::memory(), address, addresses.clone()).await.unwrap();
+            validators.push(beacon.with_signing_key(key.clone()));
+        }
+        // 署名鍵のないノードは投票せずに確定したブロックを受け取る
+        let observer = BeaconChain::open(RedbStorage::memory(), "observer", addresses.clone()).await.unwrap();
+        let nodes: Vec<&BeaconChain> = validators.iter().chain([&observer]).collect();
+        let mut outbound: Vec<_> = nodes.iter().map(|node| node.subscribe()).collect();
+
+        let genesis = observer.state().await;
+        let leader = validators.iter()
+            .find(|node| genesis.leader(1, 0) == Some(node.validator.as_str()))
+            .unwrap();
+        leader.submit(BeaconOp::AddShard { shard: 1 }).await;
+        assert!(leader.step().await.unwrap().is_none());
+        deliver(&nodes, &mut outbound).await;
+
+        let block = leader.head().await.unwrap();
+        assert_eq!(block.ops, [BeaconOp::AddShard { shard: 1 }]);
+        assert_eq!(block.signatures.len(), 3);
+        assert!(check_certificate(&genesis, &block).is_ok());
+        for node in &nodes {
+            assert_eq!(node.head().await.as_ref(), Some(&block));
+            assert_eq!(node.state().await.shards.len(), 2);
+        }
+
+        // 署名のない確定や偽の署名の投票は受け付けない
+        let fresh = BeaconChain::open(RedbStorage::memory(), "fresh", addresses.clone()).await.unwrap();
+        let unsigned = BeaconBlock { signatures: BTreeMap::new(), ..block.clone() };
+        assert_eq!(fresh.receive(BeaconMessage::Commit { block: unsigned }).await.unwrap(), Received::Ignored);
+        let vote = Vote { height: 1, round: 0, block_hash: block.hash.clone(), voter: addresses[0].clone() };
+        let forged = sign(&keys[1], &ConsensusMessage::Vote(vote.clone())).unwrap();
+        assert_eq!(fresh.receive(BeaconMessage::Vote { vote, signature: forged }).await.unwrap(), Received::Ignored);
+
+        // 先の高さのメッセージで遅れに気付き、要求したブロックで追いつく
+        let later = BeaconBlock { height: 2, ..block.clone() };
+        assert_eq!(fresh.receive(BeaconMessage::Commit { block: later }).await.unwrap(), Received::Behind { next: 1 });
+        let Received::Reply(Some(fetched)) = leader.receive(BeaconMessage::GetBlock { height: 1 }).await.unwrap() else {
+            panic!("committed block was not served");
+        };
+        assert_eq!(fresh.receive(BeaconMessage::Commit { block: fetched.clone() }).await.unwrap(), Received::Relay);
+        assert_eq!(fresh.receive(BeaconMessage::Commit { block: fetched }).await.unwrap(), Received::Ignored);
+        assert_eq!(fresh.state().await, leader.state().await);
+    }
+}
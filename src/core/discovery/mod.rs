@@ -4,7 +4,7 @@
 //! 主な機能：
 //! - ブートストラップノードへの接続
 //! - 新規ノードの検出
-//! - ノードリストの管理
+//! - ノードリストの管理（役割と機能による絞り込み）
 //! - 初期ノードとしての起動
 
 use std::collections::HashMap;
@@ -14,6 +14,7 @@ use anyhow::{Result, anyhow};
 use serde::{Serialize, Deserialize};
 use tracing::{info, warn};
 use libp2p::{PeerId, Multiaddr};
+use crate::core::network::roles::{Capability, NodeRole};
 
 /// ノード検出の設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,19 +29,21 @@ pub struct DiscoveryConfig {
     pub max_peers: usize,
     /// ノード検出の間隔（秒）
     pub discovery_interval: u64,
+    /// 自身の役割
+    #[serde(default)]
+    pub role: NodeRole,
 }
 
 impl Default for DiscoveryConfig {
     fn default() -> Self {
         Self {
-            bootstrap_nodes: vec![
-                "/ip4/104.131.131.82/tcp/4001/p2p/QmaCpDMGvV2BGHeYERUEnRQAwe3N8SzbUtfsmvsqQLuvuJ".to_string(),
-                "/dnsaddr/bootstrap.libp2p.io/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN".to_string(),
-            ],
+            // IPFSのブートストラップノードは使えないため、ネットワークごとに設定する
+            bootstrap_nodes: Vec::new(),
             is_bootstrap: false,
             min_peers: 3,
             max_peers: 25,
             discovery_interval: 60,
+            role: NodeRole::default(),
         }
     }
 }
@@ -50,20 +53,15 @@ impl Default for DiscoveryConfig {
 pub struct NodeInfo {
     pub peer_id: PeerId,
     pub addresses: Vec<Multiaddr>,
-    pub roles: Vec<NodeRole>,
+    pub role: NodeRole,
+    /// 提供できる機能
+    pub capabilities: Vec<Capability>,
+    /// ブートストラップノードとして動作しているか
+    pub bootstrap: bool,
     pub version: String,
     pub last_seen: chrono::DateTime<chrono::Utc>,
 }
 
-/// ノードの役割
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub enum NodeRole {
-    Bootstrap,
-    Validator,
-    FullNode,
-    LightNode,
-}
-
 /// ノード検出マネージャー
 #[derive(Debug)]
 pub struct DiscoveryManager {
@@ -80,6 +78,19 @@ impl DiscoveryManager {
         }
     }
 
+    /// 検出したノードの情報を記録
+    pub async fn record(&self, info: NodeInfo) {
+        self.node_info.write().await.insert(info.peer_id, info);
+    }
+
+    /// 指定した機能を持つノード（例: ライトクライアントは履歴を持つノードを探す）
+    pub async fn find(&self, capability: Capability) -> Vec<NodeInfo> {
+        self.node_info.read().await.values()
+            .filter(|info| info.capabilities.contains(&capability))
+            .cloned()
+            .collect()
+    }
+
     /// ノードの起動処理
     pub async fn start(&self) -> Result<()> {
         if self.config.is_bootstrap {
@@ -147,7 +158,9 @@ impl DiscoveryManager {
         node_info.insert(self.local_peer_id(), NodeInfo {
             peer_id: self.local_peer_id(),
             addresses: vec![],  // TODO: 自身のアドレスを設定
-            roles: vec![NodeRole::Bootstrap, NodeRole::Validator],
+            role: self.config.role,
+            capabilities: self.config.role.capabilities(),
+            bootstrap: true,
             version: env!("CARGO_PKG_VERSION").to_string(),
             last_seen: chrono::Utc::now(),
         });
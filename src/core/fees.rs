@@ -0,0 +1,364 @@
+//! 手数料の推定
+//!
+//! 直近のブロックに取り込まれたトランザクションのガス価格と、メモリプールの深さから、
+//! 取り込まれるまでの速さごと（slow・standard・fast）のガス価格を提案します。
+//!
+//! 各段階の価格は次の最大値です。
+//! - 手数料フロアと次のブロックの基本手数料（これを下回ると受け付けられない・取り込まれない）
+//! - 直近のブロックのうち、ガス上限の半分以上を使ったブロックで取り込まれた最低価格の百分位数
+//!   （空きのあるブロックの最低価格は競争の結果ではないため使わない）
+//! - メモリプールを価格の高い順に並べ、目標のブロック数に収まる位置の価格より1高い価格
+//!
+//! 直近のブロックは確定のたびに記録し、起動時にはストレージから読み直します。
+//!
+//! 指定したガス価格とガスの取り込みの見込み（`quote`）も計算します。メモリプールで同じ価格以上の
+//! トランザクションが先に取り込まれるとして必要なブロック数を求め、直近のブロックのうち
+//! その価格を取り込めたブロックの割合を各ブロックで取り込める確率とします。
+//! 後から届く高い価格のトランザクションは考慮しません。
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use tokio::sync::{broadcast, RwLock};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::mempool::Mempool;
+
+/// 記録する直近のブロック数
+pub const HISTORY_BLOCKS: usize = 20;
+/// 見込みを計算するブロック数の既定値
+pub const DEFAULT_QUOTE_BLOCKS: u64 = 10;
+/// 見込みを計算するブロック数の上限
+pub const MAX_QUOTE_BLOCKS: u64 = 100;
+
+/// 段階ごとの（百分位数, 目標のブロック数）
+const SLOW: (usize, u64) = (25, 10);
+const STANDARD: (usize, u64) = (50, 3);
+const FAST: (usize, u64) = (90, 1);
+
+/// ブロックの手数料の記録
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockFees {
+    pub height: u64,
+    /// ブロックの時刻（UNIX秒）
+    pub timestamp: u64,
+    /// 取り込まれた最低のガス価格（トランザクションがない場合は `None`）
+    pub min_gas_price: Option<u64>,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+}
+
+impl BlockFees {
+    pub fn of(block: &Block) -> Self {
+        Self {
+            height: block.height,
+            timestamp: block.timestamp,
+            min_gas_price: block.transactions.iter().map(|tx| tx.gas_price).min(),
+            gas_used: block.gas_used,
+            gas_limit: block.gas_limit,
+        }
+    }
+
+    /// 取り込みの競争があったか（ガス上限の半分以上を使った）
+    fn is_busy(&self) -> bool {
+        self.gas_limit > 0 && self.gas_used >= self.gas_limit / 2
+    }
+
+    /// `gas_price` の `gas` のトランザクションを取り込めたか
+    fn admits(&self, gas_price: u64, gas: u64) -> bool {
+        !self.is_busy()
+            || self.gas_limit.saturating_sub(self.gas_used) >= gas
+            || self.min_gas_price.is_some_and(|min| gas_price >= min)
+    }
+}
+
+/// 段階ごとの提案
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FeeEstimate {
+    pub gas_price: u64,
+    /// 取り込まれるまでの目標のブロック数
+    pub target_blocks: u64,
+}
+
+/// 手数料の提案
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FeeSuggestion {
+    pub slow: FeeEstimate,
+    pub standard: FeeEstimate,
+    pub fast: FeeEstimate,
+    /// 次のブロックの基本手数料（無効な場合は0）
+    pub base_fee: u64,
+    /// 次のブロックのブロブ手数料（1バイトあたり、無効な場合は0）
+    pub blob_fee: u64,
+    /// メモリプールの現在の手数料フロア
+    pub fee_floor: u64,
+    /// 参照した直近のブロック数（取り込みの競争があったもの）
+    pub sampled_blocks: usize,
+    /// メモリプールのトランザクション数
+    pub pending: usize,
+}
+
+/// 取り込みの見込み
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InclusionQuote {
+    pub gas_price: u64,
+    pub gas: u64,
+    /// 見込みを計算したブロック数
+    pub blocks: u64,
+    /// `blocks` 個のブロック以内に取り込まれる確率
+    pub probability: f64,
+    /// 次のブロックから数えて1個目、2個目、…までに取り込まれる確率
+    pub cumulative: Vec<f64>,
+    /// 取り込まれるまでの見込みのブロック数（取り込まれない場合は `None`）
+    pub expected_blocks: Option<u64>,
+    /// 取り込まれるまでの見込みの時間（秒）
+    pub eta_secs: Option<u64>,
+    /// 取り込みに必要なブロック数（メモリプールで先に取り込まれる分を含む）
+    pub blocks_needed: u64,
+    /// メモリプールで同じ価格以上のトランザクション数
+    pub pending_ahead: usize,
+    /// メモリプールで同じ価格以上のトランザクションのガスの合計
+    pub gas_ahead: u64,
+    /// 直近のブロックのうちこの価格を取り込めた割合
+    pub inclusion_rate: f64,
+    /// 受け付けられる最低のガス価格（手数料フロアと基本手数料の大きい方）
+    pub minimum_gas_price: u64,
+    /// 参照した直近のブロック数
+    pub sampled_blocks: usize,
+}
+
+/// 直近のブロックとメモリプールの（ガス価格, ガス）から取り込みの見込みを計算
+///
+/// `block_time_ms` は直近のブロックから間隔を求められない場合のブロック生成間隔です。
+#[allow(clippy::too_many_arguments)]
+pub fn quote(
+    history: &[BlockFees],
+    pending: &[(u64, u64)],
+    gas_limit: u64,
+    minimum: u64,
+    gas_price: u64,
+    gas: u64,
+    blocks: u64,
+    block_time_ms: u64,
+) -> InclusionQuote {
+    // 同じ価格のトランザクションは先に届いたものが優先される
+    let (pending_ahead, gas_ahead) = pending.iter()
+        .filter(|(price, _)| *price >= gas_price)
+        .fold((0, 0u64), |(count, total), (_, gas)| (count + 1, total.saturating_add(*gas)));
+    let blocks_needed = if gas_limit == 0 { u64::MAX } else { gas_ahead.saturating_add(gas).div_ceil(gas_limit).max(1) };
+    let inclusion_rate = if gas_price < minimum || gas > gas_limit {
+        0.0
+    } else if history.is_empty() {
+        1.0
+    } else {
+        history.iter().filter(|fees| fees.admits(gas_price, gas)).count() as f64 / history.len() as f64
+    };
+
+    // 各ブロックで独立に `inclusion_rate` の確率で進むとして、`blocks_needed` 個に達する確率
+    let mut cumulative = Vec::with_capacity(blocks as usize);
+    if blocks_needed > blocks {
+        cumulative.resize(blocks as usize, 0.0);
+    } else {
+        let needed = blocks_needed as usize;
+        let mut progress = vec![0.0; needed + 1];
+        progress[0] = 1.0;
+        for _ in 0..blocks {
+            for done in (0..needed).rev() {
+                let advanced = progress[done] * inclusion_rate;
+                progress[done] -= advanced;
+                progress[done + 1] += advanced;
+            }
+            cumulative.push(progress[needed]);
+        }
+    }
+
+    let expected_blocks = (inclusion_rate > 0.0 && blocks_needed != u64::MAX)
+        .then(|| (blocks_needed as f64 / inclusion_rate).ceil() as u64);
+    let block_time_ms = match (history.first(), history.last()) {
+        (Some(first), Some(last)) if history.len() > 1 && last.timestamp > first.timestamp =>
+            (last.timestamp - first.timestamp) * 1000 / (history.len() as u64 - 1),
+        _ => block_time_ms,
+    };
+    InclusionQuote {
+        gas_price,
+        gas,
+        blocks,
+        probability: cumulative.last().copied().unwrap_or(0.0),
+        cumulative,
+        expected_blocks,
+        eta_secs: expected_blocks.map(|blocks| blocks.saturating_mul(block_time_ms).div_ceil(1000)),
+        blocks_needed,
+        pending_ahead,
+        gas_ahead,
+        inclusion_rate,
+        minimum_gas_price: minimum,
+        sampled_blocks: history.len(),
+    }
+}
+
+/// 直近のブロックとメモリプールの（ガス価格, ガス）から提案を計算
+///
+/// `minimum` は手数料フロアと基本手数料の大きい方、`gas_limit` は次のブロックのガス上限です。
+pub fn suggest(history: &[BlockFees], pending: &[(u64, u64)], gas_limit: u64, minimum: u64) -> [FeeEstimate; 3] {
+    let mut included: Vec<u64> = history.iter()
+        .filter(|fees| fees.is_busy())
+        .filter_map(|fees| fees.min_gas_price)
+        .collect();
+    included.sort_unstable();
+    let mut pending = pending.to_vec();
+    pending.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+    let mut floor = minimum;
+    [SLOW, STANDARD, FAST].map(|(percentile, target_blocks)| {
+        let historical = (!included.is_empty())
+            .then(|| included[(included.len() - 1) * percentile / 100]);
+        let depth = depth_price(&pending, gas_limit.saturating_mul(target_blocks));
+        // 速い段階が遅い段階より安くならないようにする
+        floor = floor.max(historical.unwrap_or(0)).max(depth.unwrap_or(0));
+        FeeEstimate { gas_price: floor, target_blocks }
+    })
+}
+
+/// 価格の高い順に並べたメモリプールで `capacity` のガスに収まらない最初の価格より1高い価格
+fn depth_price(pending: &[(u64, u64)], capacity: u64) -> Option<u64> {
+    let mut used = 0u64;
+    for (gas_price, gas) in pending {
+        used = used.saturating_add(*gas);
+        if used > capacity {
+            return Some(gas_price.saturating_add(1));
+        }
+    }
+    None
+}
+
+/// 手数料の推定
+#[derive(Debug, Default)]
+pub struct FeeOracle {
+    history: RwLock<VecDeque<BlockFees>>,
+}
+
+impl FeeOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 確定したブロックを記録
+    pub async fn record(&self, block: &Block) {
+        let mut history = self.history.write().await;
+        if history.back().is_some_and(|last| block.height <= last.height) {
+            return;
+        }
+        history.push_back(BlockFees::of(block));
+        while history.len() > HISTORY_BLOCKS {
+            history.pop_front();
+        }
+    }
+
+    /// 現在のメモリプールと直近のブロックから提案を計算
+    pub async fn suggest(&self, chain: &Chain, mempool: &Mempool) -> FeeSuggestion {
+        let gas_limit = chain.next_gas_limit().await;
+        let base_fee = chain.next_base_fee().await;
+        let fee_floor = mempool.current_fee_floor();
+        let pending: Vec<(u64, u64)> = mempool.iter().map(|tx| (tx.gas_price, tx.gas_limit)).collect();
+        let history: Vec<BlockFees> = self.history.read().await.iter().copied().collect();
+        let [slow, standard, fast] = suggest(&history, &pending, gas_limit, fee_floor.max(base_fee));
+        FeeSuggestion {
+            slow,
+            standard,
+            fast,
+            base_fee,
+            blob_fee: chain.next_blob_fee().await,
+            fee_floor,
+            sampled_blocks: history.iter().filter(|fees| fees.is_busy() && fees.min_gas_price.is_some()).count(),
+            pending: pending.len(),
+        }
+    }
+
+    /// `gas_price` の `gas` のトランザクションが `blocks` 個のブロック以内に取り込まれる見込み
+    pub async fn quote(
+        &self,
+        chain: &Chain,
+        mempool: &Mempool,
+        gas_price: u64,
+        gas: u64,
+        blocks: u64,
+        block_time_ms: u64,
+    ) -> InclusionQuote {
+        let gas_limit = chain.next_gas_limit().await;
+        let minimum = mempool.current_fee_floor().max(chain.next_base_fee().await);
+        let pending: Vec<(u64, u64)> = mempool.iter().map(|tx| (tx.gas_price, tx.gas_limit)).collect();
+        let history: Vec<BlockFees> = self.history.read().await.iter().copied().collect();
+        quote(&history, &pending, gas_limit, minimum, gas_price, gas, blocks, block_time_ms)
+    }
+
+    /// 直近のブロックを読み込み、以降の確定を記録する
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            self.reload(&chain).await;
+            loop {
+                match commits.recv().await {
+                    Ok(block) => self.record(&block).await,
+                    // 取りこぼした場合はストレージから読み直す
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.reload(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn reload(&self, chain: &Chain) {
+        let Some((head, _)) = chain.head().await else {
+            return;
+        };
+        for height in head.saturating_sub(HISTORY_BLOCKS as u64 - 1)..=head {
+            if let Ok(Some(block)) = chain.get_block(height).await {
+                self.record(&block).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(height: u64, min_gas_price: u64, gas_used: u64) -> BlockFees {
+        BlockFees { height, timestamp: height * 2, min_gas_price: Some(min_gas_price), gas_used, gas_limit: 100_000 }
+    }
+
+    #[test]
+    fn test_suggestions_follow_history_and_depth() {
+        // 空のネットワークでは最低価格
+        let idle = suggest(&[], &[], 100_000, 3);
+        assert!(idle.iter().all(|estimate| estimate.gas_price == 3));
+
+        // 空きのあるブロック（高さ4）の価格は使わない
+        let history = [fees(1, 10, 90_000), fees(2, 20, 60_000), fees(3, 30, 100_000), fees(4, 500, 1_000)];
+        let [slow, standard, fast] = suggest(&history, &[], 100_000, 1);
+        assert_eq!((slow.gas_price, standard.gas_price, fast.gas_price), (10, 20, 20));
+
+        // 次のブロックに収まらない分があれば、その価格より高くする
+        let pending = [(50, 60_000), (40, 60_000), (5, 60_000)];
+        let [slow, standard, fast] = suggest(&history, &pending, 100_000, 1);
+        assert_eq!((slow.gas_price, standard.gas_price, fast.gas_price), (10, 20, 41));
+        assert_eq!((slow.target_blocks, standard.target_blocks, fast.target_blocks), (10, 3, 1));
+    }
+
+    #[test]
+    fn test_inclusion_quote() {
+        let history = [fees(1, 10, 90_000), fees(2, 20, 60_000), fees(3, 30, 100_000), fees(4, 500, 1_000)];
+        // 価格20を取り込めなかったのは空きがなく最低価格が30の高さ3のみ
+        let pending = [(50, 60_000), (40, 60_000), (5, 60_000)];
+        let quoted = quote(&history, &pending, 100_000, 1, 20, 21_000, 3, 1000);
+        assert_eq!((quoted.pending_ahead, quoted.gas_ahead, quoted.blocks_needed), (2, 120_000, 2));
+        assert_eq!(quoted.inclusion_rate, 0.75);
+        assert_eq!(quoted.cumulative, vec![0.0, 0.5625, 0.84375]);
+        // 2秒間隔のブロックで3個
+        assert_eq!((quoted.expected_blocks, quoted.eta_secs), (Some(3), Some(6)));
+
+        // 最低価格を下回る場合は取り込まれない
+        let below = quote(&history, &[], 100_000, 25, 20, 21_000, 3, 1000);
+        assert_eq!((below.probability, below.expected_blocks), (0.0, None));
+    }
+}
@@ -0,0 +1,240 @@
+//! ジェネシスの形式での状態の書き出し（`rustorium system export-genesis`）
+//!
+//! 指定した高さまでのブロックを一時的なストレージのビューに反映し直し、その時点の状態を `genesis.json` に
+//! 書き出します。ネットワークを揃って再起動する場合や、状態を引き継いだ別のネットワークを始める場合に使います。
+//! - 残高とノンス（同じチェーンIDで再開しても、古いトランザクションを再送できないようにする）
+//! - コントラクト：記録した作成バイトコードと、トークンの保有者ごとの保有量
+//! - バリデーター：その時点までに登録された候補（自己ステークと手数料率）
+//!
+//! 決済されていない HTLC のロックは書き出しません（ロック中の資金は HTLC のアドレスの残高に含まれる）。
+//!
+//! 書き出したファイルを `consensus.genesis` に指定すると、残高・ノンス・トークンの保有量を
+//! ブロック0の前の状態として読み込みます。新しいネットワークの初期の配分にも同じ形式を使います。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Serialize, Deserialize};
+use tracing::info;
+use crate::core::block::Chain;
+use crate::config::ConsensusSettings;
+use crate::core::cache::{ExportedState, MaterializedViews};
+use crate::core::consensus::registry::ValidatorCandidate;
+use crate::core::contract::verification;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+
+/// 形式のバージョン
+pub const GENESIS_VERSION: u32 = 1;
+/// 候補を読む1回の件数
+const CANDIDATE_BATCH: usize = 1000;
+
+/// 書き出したジェネシス
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Genesis {
+    pub version: u32,
+    pub chain_id: u64,
+    /// 書き出した時点のブロックの時刻（UNIX秒、新しいジェネシスの時刻になる）
+    pub genesis_time: u64,
+    /// 書き出し元のブロック
+    pub exported_from: ExportedBlock,
+    /// アドレスごとの残高（0 のアドレスは含まない）
+    pub balances: BTreeMap<String, u64>,
+    /// 送信者ごとの次のノンス
+    pub nonces: BTreeMap<String, u64>,
+    pub contracts: Vec<GenesisContract>,
+    pub validators: Vec<GenesisValidator>,
+}
+
+/// 書き出し元のブロック
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedBlock {
+    pub height: u64,
+    pub hash: String,
+}
+
+/// コントラクト
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisContract {
+    pub address: String,
+    /// 作成バイトコード（hex、記録していない場合は `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// トークンの保有者ごとの保有量
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub token_balances: BTreeMap<String, u64>,
+}
+
+/// バリデーター
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisValidator {
+    pub address: String,
+    pub self_stake: u64,
+    pub commission_bps: u16,
+}
+
+impl Genesis {
+    /// ファイルに書き出す
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// ファイルから読み込む（`chain_id` のネットワーク向けのものに限る）
+    pub fn read(path: &Path, chain_id: u64) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let genesis: Self = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if genesis.version != GENESIS_VERSION {
+            bail!("{} has genesis version {}, expected {}", path.display(), genesis.version, GENESIS_VERSION);
+        }
+        if genesis.chain_id != chain_id {
+            bail!("{} is for chain {}, but this network is chain {}", path.display(), genesis.chain_id, chain_id);
+        }
+        Ok(genesis)
+    }
+
+    /// ブロック0の前の状態
+    pub fn initial_state(&self) -> ExportedState {
+        ExportedState {
+            balances: self.balances.clone(),
+            nonces: self.nonces.clone(),
+            tokens: self.contracts.iter()
+                .filter(|contract| !contract.token_balances.is_empty())
+                .map(|contract| (contract.address.clone(), contract.token_balances.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// `consensus.genesis` のブロック0の前の状態（指定していなければ空）
+pub fn initial_state(settings: &ConsensusSettings) -> Result<ExportedState> {
+    match &settings.genesis {
+        Some(path) => Ok(Genesis::read(path, settings.chain_id)?.initial_state()),
+        None => Ok(ExportedState::default()),
+    }
+}
+
+/// `height`（省略時は最新のブロック）の状態をジェネシスの形式で書き出す
+///
+/// `views` は空のストレージのビューで、ブロック0からの状態を反映し直すために使います
+/// （ノードと同じブロック0の前の状態を設定しておく）。
+pub async fn export(
+    chain: &Chain,
+    storage: &dyn StorageEngine,
+    views: MaterializedViews,
+    height: Option<u64>,
+    chain_id: u64,
+) -> Result<Genesis> {
+    let (head, _) = chain.head().await.ok_or_else(|| anyhow!("Chain has no blocks to export"))?;
+    let height = height.unwrap_or(head);
+    if height > head {
+        bail!("Block {} is not committed yet (head is {})", height, head);
+    }
+    if let Some(base) = chain.base().await?.filter(|base| *base > 0) {
+        bail!("History before block {} is missing (started from a checkpoint); backfill it first", base);
+    }
+
+    info!("Replaying blocks 0 to {} to export their state", height);
+    let mut at = None;
+    for h in 0..=height {
+        let block = chain.get_block(h).await?.ok_or_else(|| anyhow!("Block {} is missing", h))?;
+        views.apply_block(&block).await?;
+        at = Some(block);
+    }
+    let at = at.ok_or_else(|| anyhow!("Block {} is missing", height))?;
+    let state = views.export_state().await?;
+
+    let mut contracts: BTreeMap<String, GenesisContract> = BTreeMap::new();
+    for (address, code) in verification::creation_codes(storage).await? {
+        contracts.entry(address.clone())
+            .or_insert_with(|| GenesisContract { address, code: None, token_balances: BTreeMap::new() })
+            .code = Some(hex::encode(code));
+    }
+    for (token, holders) in state.tokens {
+        contracts.entry(token.clone())
+            .or_insert_with(|| GenesisContract { address: token, code: None, token_balances: BTreeMap::new() })
+            .token_balances = holders;
+    }
+
+    let mut validators = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let candidates = ValidatorCandidate::list(storage, after.as_ref(), CANDIDATE_BATCH).await?;
+        validators.extend(candidates.iter()
+            .filter(|candidate| candidate.registered_at <= at.timestamp)
+            .map(|candidate| GenesisValidator {
+                address: candidate.address.clone(),
+                self_stake: candidate.self_stake,
+                commission_bps: candidate.commission_bps,
+            }));
+        if candidates.len() < CANDIDATE_BATCH {
+            break;
+        }
+        after = candidates.last().map(|candidate| candidate.address.clone());
+    }
+
+    Ok(Genesis {
+        version: GENESIS_VERSION,
+        chain_id,
+        genesis_time: at.timestamp,
+        exported_from: ExportedBlock { height: at.height, hash: at.hash.clone() },
+        balances: state.balances,
+        nonces: state.nonces,
+        contracts: contracts.into_values().collect(),
+        validators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn replay(initial: &ExportedState) -> MaterializedViews {
+        MaterializedViews::new(RedbStorage::memory()).with_genesis(initial.clone())
+    }
+
+    #[tokio::test]
+    async fn test_export_state_at_height() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let chain = Chain::open(storage.clone()).await.unwrap().with_allow_unsigned(true);
+        let initial = ExportedState { balances: BTreeMap::from([("aa".to_string(), 20)]), ..Default::default() };
+        let token = "cc".repeat(20);
+        // ERC-20 transfer(address,uint256) で 0x00…bb に 7 を送る
+        let mut transfer = vec![0xa9, 0x05, 0x9c, 0xbb];
+        transfer.extend_from_slice(&[0; 31]);
+        transfer.push(0xbb);
+        transfer.extend_from_slice(&[0; 31]);
+        transfer.push(7);
+        for (nonce, (to, value, data)) in [("bb", 10, vec![]), (token.as_str(), 0, transfer), ("bb", 5, vec![])].into_iter().enumerate() {
+            let tx = PendingTransaction { gas_limit: 50_000, ..PendingTransaction::test_transfer("aa", to, value, nonce as u64).with_data(data) }.rehashed();
+            chain.commit(chain.next_signed_block(vec![tx]).await).await.unwrap();
+        }
+        ValidatorCandidate { address: "dd".repeat(32), self_stake: 1000, commission_bps: 500, registered_at: 0 }
+            .save(storage.as_ref()).await.unwrap();
+
+        // 3番目の送金の前の状態
+        let genesis = export(&chain, storage.as_ref(), replay(&initial), Some(1), 7).await.unwrap();
+        assert_eq!(genesis.exported_from, ExportedBlock { height: 1, hash: chain.get_block(1).await.unwrap().unwrap().hash });
+        assert_eq!((genesis.balances.get("aa"), genesis.balances.get("bb")), (Some(&10), Some(&10)));
+        assert_eq!(genesis.nonces.get("aa"), Some(&2));
+        assert_eq!(genesis.contracts.len(), 1);
+        assert_eq!(genesis.contracts[0].token_balances, BTreeMap::from([(format!("{}bb", "00".repeat(19)), 7)]));
+        assert_eq!(genesis.validators, vec![GenesisValidator { address: "dd".repeat(32), self_stake: 1000, commission_bps: 500 }]);
+        assert!(export(&chain, storage.as_ref(), replay(&initial), Some(3), 7).await.is_err());
+
+        // 書き出したファイルは同じチェーンIDのノードのみ読み込める
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("genesis.json");
+        genesis.write(&path).unwrap();
+        assert_eq!(Genesis::read(&path, 7).unwrap().initial_state(), ExportedState {
+            balances: genesis.balances.clone(),
+            nonces: genesis.nonces.clone(),
+            tokens: BTreeMap::from([(token, genesis.contracts[0].token_balances.clone())]),
+        });
+        assert!(Genesis::read(&path, 8).is_err());
+    }
+}
@@ -0,0 +1,504 @@
+//! ハッシュタイムロック（HTLC）
+//!
+//! 異なるチェーンの間のアトミックスワップに使うネイティブのエスクローです。
+//! 作成者は HTLC のアドレス [`HTLC_ADDRESS`] に `value` を送ってロックし、受取人はハッシュロックの
+//! 原像を公開して期限までに受け取ります。期限を過ぎても受け取られなかったロックは作成者に返金できます。
+//! 受け取りと返金は誰が送信してもよく（`value` は0）、払い出し先はロックで決まります。
+//!
+//! | 位置   | 内容 |
+//! |--------|------|
+//! | 0..4   | マジック `htlc`（`68746c63`） |
+//! | 4      | バージョン（`1`） |
+//! | 5      | 種類（`0` は作成、`1` は受け取り、`2` は返金） |
+//! | 6..    | 作成：ハッシュロック（32バイト）、期限（UNIX秒、u64 BE）、受取人のアドレス（hex） |
+//! |        | 受け取り：ロックID（32バイト）、原像（1〜64バイト） |
+//! |        | 返金：ロックID（32バイト） |
+//!
+//! ロックIDは作成したトランザクションのハッシュ、ハッシュロックは原像の SHA-256 です。
+//! 受け取りで公開された原像はロックに記録され、相手のチェーンでの受け取りに使えます。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+
+/// 反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"htlc/applied_height";
+
+/// 操作の先頭のマジック
+pub const HTLC_MAGIC: [u8; 4] = *b"htlc";
+/// 形式のバージョン
+const HTLC_VERSION: u8 = 1;
+/// HTLC のアドレス（操作の宛先、ロック中の資金の保管先）
+pub const HTLC_ADDRESS: &str = "0000000000000000000000000000000000000048";
+/// 作成時に指定できる最も遠い期限（秒）
+pub const MAX_TIMEOUT_SECS: u64 = 30 * 24 * 60 * 60;
+/// 原像の最大バイト数
+pub const MAX_PREIMAGE_BYTES: usize = 64;
+
+const KIND_CREATE: u8 = 0;
+const KIND_CLAIM: u8 = 1;
+const KIND_REFUND: u8 = 2;
+
+/// HTLC のエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum HtlcError {
+    #[error("HTLC operation is truncated")]
+    Truncated,
+
+    #[error("unsupported HTLC operation version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("unknown HTLC operation kind {0}")]
+    UnknownKind(u8),
+
+    #[error("HTLC operations must be sent to 0000000000000000000000000000000000000048")]
+    WrongRecipient,
+
+    #[error("recipient is not a valid address")]
+    InvalidRecipient,
+
+    #[error("preimage must be 1 to 64 bytes")]
+    InvalidPreimage,
+
+    #[error("locked value must be greater than zero")]
+    EmptyLock,
+
+    #[error("claims and refunds must not transfer value")]
+    UnexpectedValue,
+
+    #[error("timeout must be in the future and at most 30 days away")]
+    InvalidTimeout,
+
+    #[error("HTLC {0} does not exist")]
+    NotFound(String),
+
+    #[error("HTLC {0} is already settled")]
+    Settled(String),
+
+    #[error("preimage does not match the hashlock of {0}")]
+    HashMismatch(String),
+
+    #[error("HTLC {0} has expired and can only be refunded")]
+    Expired(String),
+
+    #[error("HTLC {id} cannot be refunded before {timeout}")]
+    NotExpired { id: String, timeout: u64 },
+}
+
+/// `data` に入れる操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtlcOp {
+    Create { hashlock: [u8; 32], timeout: u64, recipient: String },
+    Claim { id: String, preimage: Vec<u8> },
+    Refund { id: String },
+}
+
+impl HtlcOp {
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = HTLC_MAGIC.to_vec();
+        data.push(HTLC_VERSION);
+        match self {
+            Self::Create { hashlock, timeout, recipient } => {
+                data.push(KIND_CREATE);
+                data.extend_from_slice(hashlock);
+                data.extend_from_slice(&timeout.to_be_bytes());
+                data.extend_from_slice(recipient.as_bytes());
+            }
+            Self::Claim { id, preimage } => {
+                data.push(KIND_CLAIM);
+                data.extend_from_slice(&id_bytes(id));
+                data.extend_from_slice(preimage);
+            }
+            Self::Refund { id } => {
+                data.push(KIND_REFUND);
+                data.extend_from_slice(&id_bytes(id));
+            }
+        }
+        data
+    }
+
+    /// `data` フィールドを解析（HTLC の操作でなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, HtlcError>> {
+        let body = data.strip_prefix(&HTLC_MAGIC)?;
+        Some(Self::decode_body(body))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, HtlcError> {
+        let [version, kind, rest @ ..] = body else {
+            return Err(HtlcError::Truncated);
+        };
+        if *version != HTLC_VERSION {
+            return Err(HtlcError::UnsupportedVersion(*version));
+        }
+        match *kind {
+            KIND_CREATE => {
+                if rest.len() < 40 {
+                    return Err(HtlcError::Truncated);
+                }
+                let recipient = std::str::from_utf8(&rest[40..]).map_err(|_| HtlcError::InvalidRecipient)?;
+                if !matches!(recipient.len(), 40 | 64) || !recipient.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+                    return Err(HtlcError::InvalidRecipient);
+                }
+                Ok(Self::Create {
+                    hashlock: rest[..32].try_into().expect("32 bytes"),
+                    timeout: u64::from_be_bytes(rest[32..40].try_into().expect("8 bytes")),
+                    recipient: recipient.to_string(),
+                })
+            }
+            KIND_CLAIM => {
+                if rest.len() < 32 {
+                    return Err(HtlcError::Truncated);
+                }
+                let preimage = rest[32..].to_vec();
+                if preimage.is_empty() || preimage.len() > MAX_PREIMAGE_BYTES {
+                    return Err(HtlcError::InvalidPreimage);
+                }
+                Ok(Self::Claim { id: hex::encode(&rest[..32]), preimage })
+            }
+            KIND_REFUND if rest.len() == 32 => Ok(Self::Refund { id: hex::encode(rest) }),
+            KIND_REFUND => Err(HtlcError::Truncated),
+            other => Err(HtlcError::UnknownKind(other)),
+        }
+    }
+
+    /// トランザクションの操作
+    pub fn of(tx: &PendingTransaction) -> Option<Result<Self, HtlcError>> {
+        Self::decode(&tx.data)
+    }
+}
+
+/// 32バイトのロックID（hex 以外は0で埋める）
+fn id_bytes(id: &str) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    if let Ok(decoded) = hex::decode(id) {
+        if decoded.len() == 32 {
+            bytes.copy_from_slice(&decoded);
+        }
+    }
+    bytes
+}
+
+/// 原像のハッシュロック
+pub fn hashlock(preimage: &[u8]) -> [u8; 32] {
+    Sha256::digest(preimage).into()
+}
+
+/// ロックの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcStatus {
+    Open,
+    Claimed,
+    Refunded,
+}
+
+/// ハッシュタイムロック
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "htlc/lock", version = 1)]
+pub struct HtlcLock {
+    /// 作成したトランザクションのハッシュ
+    #[state(key)]
+    pub id: String,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: u64,
+    /// 原像の SHA-256（hex）
+    pub hashlock: String,
+    /// 期限（UNIX秒）。これ以降は受け取れず、返金のみできる
+    pub timeout: u64,
+    pub status: HtlcStatus,
+    /// 受け取りで公開された原像（hex）
+    pub preimage: Option<String>,
+    /// 作成されたブロックの高さ
+    pub created_at: u64,
+    /// 受け取りまたは返金のトランザクション
+    pub settled_by: Option<String>,
+}
+
+impl HtlcLock {
+    /// 作成の操作を検証してロックにする
+    pub fn create(tx: &PendingTransaction, op: &HtlcOp, now: u64, height: u64) -> Result<Self, HtlcError> {
+        let HtlcOp::Create { hashlock, timeout, recipient } = op else {
+            return Err(HtlcError::UnknownKind(KIND_CREATE));
+        };
+        if tx.value == 0 {
+            return Err(HtlcError::EmptyLock);
+        }
+        if *timeout <= now || *timeout > now.saturating_add(MAX_TIMEOUT_SECS) {
+            return Err(HtlcError::InvalidTimeout);
+        }
+        Ok(Self {
+            id: tx.hash.clone(),
+            sender: tx.from.clone(),
+            recipient: recipient.clone(),
+            amount: tx.value,
+            hashlock: hex::encode(hashlock),
+            timeout: *timeout,
+            status: HtlcStatus::Open,
+            preimage: None,
+            created_at: height,
+            settled_by: None,
+        })
+    }
+
+    /// 受け取りまたは返金を検証して、決済後のロックを返す
+    pub fn settle(&self, tx: &PendingTransaction, op: &HtlcOp, now: u64) -> Result<Self, HtlcError> {
+        if tx.value != 0 {
+            return Err(HtlcError::UnexpectedValue);
+        }
+        if self.status != HtlcStatus::Open {
+            return Err(HtlcError::Settled(self.id.clone()));
+        }
+        match op {
+            HtlcOp::Claim { preimage, .. } => {
+                if now >= self.timeout {
+                    return Err(HtlcError::Expired(self.id.clone()));
+                }
+                if hex::encode(hashlock(preimage)) != self.hashlock {
+                    return Err(HtlcError::HashMismatch(self.id.clone()));
+                }
+                Ok(Self {
+                    status: HtlcStatus::Claimed,
+                    preimage: Some(hex::encode(preimage)),
+                    settled_by: Some(tx.hash.clone()),
+                    ..self.clone()
+                })
+            }
+            HtlcOp::Refund { .. } => {
+                if now < self.timeout {
+                    return Err(HtlcError::NotExpired { id: self.id.clone(), timeout: self.timeout });
+                }
+                Ok(Self { status: HtlcStatus::Refunded, settled_by: Some(tx.hash.clone()), ..self.clone() })
+            }
+            HtlcOp::Create { .. } => Err(HtlcError::Settled(self.id.clone())),
+        }
+    }
+
+    /// 決済済みのロックの払い出し先
+    pub fn payee(&self) -> Option<&str> {
+        match self.status {
+            HtlcStatus::Open => None,
+            HtlcStatus::Claimed => Some(&self.recipient),
+            HtlcStatus::Refunded => Some(&self.sender),
+        }
+    }
+}
+
+/// ハッシュタイムロックの台帳
+pub struct HtlcLedger {
+    storage: Arc<dyn StorageEngine>,
+    /// ブロックの反映を直列にする（購読と追いつきが同時に反映しないように）
+    applying: Mutex<()>,
+}
+
+impl HtlcLedger {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        Self { storage, applying: Mutex::new(()) }
+    }
+
+    /// ロック（決済済みのものも返す）
+    pub async fn lock(&self, id: &str) -> Result<Option<HtlcLock>> {
+        HtlcLock::load(self.storage.as_ref(), &id.to_string()).await
+    }
+
+    /// 受付時の検証（確定した状態と現在時刻に対して検証する）
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<(), HtlcError> {
+        self.apply(&mut HashMap::new(), tx, unix_now(), 0).await
+    }
+
+    /// ブロックに含められない操作（と同じ送信者の後続のもの）を除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let now = unix_now();
+        let mut locks = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            match self.apply(&mut locks, &tx, now, 0).await {
+                Ok(()) => kept.push(tx),
+                Err(e) => {
+                    debug!("Leaving HTLC operation {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// 操作を検証して `locks` に反映する
+    async fn apply(
+        &self,
+        locks: &mut HashMap<String, HtlcLock>,
+        tx: &PendingTransaction,
+        now: u64,
+        height: u64,
+    ) -> Result<(), HtlcError> {
+        let op = match HtlcOp::of(tx) {
+            None => return Ok(()),
+            Some(op) => op?,
+        };
+        if tx.to != HTLC_ADDRESS {
+            return Err(HtlcError::WrongRecipient);
+        }
+        let lock = match &op {
+            HtlcOp::Create { .. } => HtlcLock::create(tx, &op, now, height)?,
+            HtlcOp::Claim { id, .. } | HtlcOp::Refund { id } => {
+                let current = match locks.get(id) {
+                    Some(lock) => Some(lock.clone()),
+                    None => self.lock(id).await.map_err(|_| HtlcError::NotFound(id.clone()))?,
+                };
+                current.ok_or_else(|| HtlcError::NotFound(id.clone()))?.settle(tx, &op, now)?
+            }
+        };
+        locks.insert(lock.id.clone(), lock);
+        Ok(())
+    }
+
+    /// 確定したブロックの操作を順に検証して適用する（失敗した操作は状態を変えない）
+    ///
+    /// 反映済みの高さ以下のブロックは無視します。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        let mut locks = HashMap::new();
+        for tx in &block.transactions {
+            if let Err(e) = self.apply(&mut locks, tx, block.timestamp, block.height).await {
+                warn!("HTLC operation {} in block {} was not applied: {}", tx.hash, block.height, e);
+            }
+        }
+        let mut batch = locks.values()
+            .map(HtlcLock::put_change)
+            .collect::<Result<Vec<_>>>()?;
+        batch.push((APPLIED_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        self.storage.batch_write(batch).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 最新のブロックまでストレージから読み直して反映する
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=head {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 以降の確定で操作を適用する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up the HTLC ledger: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply HTLC operations: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up the HTLC ledger: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn tx(from: &str, value: u64, nonce: u64, op: &HtlcOp) -> PendingTransaction {
+        PendingTransaction::test_transfer(from, HTLC_ADDRESS, value, nonce).with_data(op.encode())
+    }
+
+    fn block(height: u64, timestamp: u64, transactions: Vec<PendingTransaction>) -> Block {
+        let mut block = Block::new(height, "p".to_string(), "v".to_string(), transactions);
+        block.timestamp = timestamp;
+        block
+    }
+
+    #[tokio::test]
+    async fn test_claim_with_preimage_and_refund_after_timeout() {
+        let storage = RedbStorage::memory();
+        let ledger = HtlcLedger::new(storage);
+        let (alice, bob) = ("a".repeat(40), "b".repeat(40));
+        let secret = b"swap secret".to_vec();
+        let start = unix_now();
+
+        let create = HtlcOp::Create { hashlock: hashlock(&secret), timeout: start + 3600, recipient: bob.clone() };
+        assert_eq!(HtlcOp::decode(&create.encode()), Some(Ok(create.clone())));
+        let created = tx(&alice, 500, 0, &create);
+        let id = created.hash.clone();
+        let refundable = tx(&alice, 200, 1, &create);
+        let first = block(1, start, vec![created, refundable.clone()]);
+        ledger.apply_block(&first).await.unwrap();
+
+        // 誤った原像では受け取れず、期限前は返金できない
+        let wrong = HtlcOp::Claim { id: id.clone(), preimage: b"guess".to_vec() };
+        assert_eq!(ledger.check(&tx(&bob, 0, 0, &wrong)).await, Err(HtlcError::HashMismatch(id.clone())));
+        let refund = HtlcOp::Refund { id: id.clone() };
+        assert!(matches!(ledger.check(&tx(&alice, 0, 2, &refund)).await, Err(HtlcError::NotExpired { .. })));
+
+        // 受け取りで原像が公開され、2回目の受け取りは適用されない
+        let claim = HtlcOp::Claim { id: id.clone(), preimage: secret.clone() };
+        ledger.apply_block(&block(2, start + 10, vec![tx(&bob, 0, 0, &claim), tx(&bob, 0, 1, &claim)])).await.unwrap();
+        let lock = ledger.lock(&id).await.unwrap().unwrap();
+        assert_eq!((lock.status, lock.payee()), (HtlcStatus::Claimed, Some(bob.as_str())));
+        assert_eq!(lock.preimage, Some(hex::encode(&secret)));
+        // 反映済みのブロックを再び渡しても（追いつきとの重複）ロックを作り直さない
+        ledger.apply_block(&first).await.unwrap();
+        assert_eq!(ledger.lock(&id).await.unwrap().unwrap().status, HtlcStatus::Claimed);
+        assert_eq!(ledger.applied_height().await.unwrap(), Some(2));
+
+        // 期限を過ぎたロックは受け取れず、作成者に返金される
+        let late = HtlcOp::Claim { id: refundable.hash.clone(), preimage: secret };
+        let refund = HtlcOp::Refund { id: refundable.hash.clone() };
+        ledger.apply_block(&block(3, start + 3600, vec![tx(&bob, 0, 2, &late), tx(&bob, 0, 3, &refund)])).await.unwrap();
+        let lock = ledger.lock(&refundable.hash).await.unwrap().unwrap();
+        assert_eq!((lock.status, lock.payee()), (HtlcStatus::Refunded, Some(alice.as_str())));
+
+        let far = HtlcOp::Create { hashlock: [0; 32], timeout: start + MAX_TIMEOUT_SECS + 3600, recipient: bob };
+        assert_eq!(ledger.check(&tx(&alice, 1, 2, &far)).await, Err(HtlcError::InvalidTimeout));
+    }
+}
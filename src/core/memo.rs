@@ -0,0 +1,131 @@
+//! トランザクションのメモ
+//!
+//! `data` フィールドにメモを入れるための共通の形式です。取引所の入金タグのように、
+//! 受信者がトランザクションを利用者に対応付けるために使います。
+//!
+//! 形式（バイト列）：
+//!
+//! | 位置 | 内容 |
+//! |------|------|
+//! | 0..4 | マジック `memo`（`6d656d6f`） |
+//! | 4    | バージョン（`1`） |
+//! | 5    | タグの長さ（1〜32） |
+//! | 6..  | タグ（`a-z` `0-9` `.` `_` `:` `-`）、続けてペイロード（UTF-8、最大256バイト） |
+//!
+//! マジックで始まる `data` は必ずこの形式として検証されます。
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// メモの先頭のマジック
+pub const MEMO_MAGIC: [u8; 4] = *b"memo";
+/// 形式のバージョン
+const MEMO_VERSION: u8 = 1;
+/// タグの最大バイト数
+pub const MAX_TAG_LENGTH: usize = 32;
+/// ペイロードの最大バイト数
+pub const MAX_PAYLOAD_LENGTH: usize = 256;
+
+/// メモの形式のエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum MemoError {
+    #[error("memo tag {0:?} must be 1-32 characters of a-z, 0-9, '.', '_', ':' or '-'")]
+    InvalidTag(String),
+
+    #[error("memo payload is {size} bytes, exceeding the limit of {limit} bytes")]
+    PayloadTooLarge { size: usize, limit: usize },
+
+    #[error("memo payload is not valid UTF-8")]
+    NotUtf8,
+
+    #[error("memo is truncated")]
+    Truncated,
+
+    #[error("unsupported memo version {0}")]
+    UnsupportedVersion(u8),
+}
+
+/// メモ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Memo {
+    /// 種類（例: `deposit`）
+    pub tag: String,
+    /// 内容（例: 入金を識別するID）
+    pub payload: String,
+}
+
+impl Memo {
+    pub fn new(tag: &str, payload: &str) -> Result<Self, MemoError> {
+        let memo = Self { tag: tag.to_string(), payload: payload.to_string() };
+        memo.validate()?;
+        Ok(memo)
+    }
+
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(6 + self.tag.len() + self.payload.len());
+        data.extend_from_slice(&MEMO_MAGIC);
+        data.push(MEMO_VERSION);
+        data.push(self.tag.len() as u8);
+        data.extend_from_slice(self.tag.as_bytes());
+        data.extend_from_slice(self.payload.as_bytes());
+        data
+    }
+
+    /// `data` フィールドを解析（メモでなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, MemoError>> {
+        let body = data.strip_prefix(&MEMO_MAGIC)?;
+        Some(Self::decode_body(body))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, MemoError> {
+        let (&version, rest) = body.split_first().ok_or(MemoError::Truncated)?;
+        if version != MEMO_VERSION {
+            return Err(MemoError::UnsupportedVersion(version));
+        }
+        let (&tag_len, rest) = rest.split_first().ok_or(MemoError::Truncated)?;
+        if rest.len() < tag_len as usize {
+            return Err(MemoError::Truncated);
+        }
+        let (tag, payload) = rest.split_at(tag_len as usize);
+        let memo = Self {
+            tag: String::from_utf8_lossy(tag).into_owned(),
+            payload: String::from_utf8(payload.to_vec()).map_err(|_| MemoError::NotUtf8)?,
+        };
+        memo.validate()?;
+        Ok(memo)
+    }
+
+    fn validate(&self) -> Result<(), MemoError> {
+        let valid_tag = !self.tag.is_empty()
+            && self.tag.len() <= MAX_TAG_LENGTH
+            && self.tag.bytes().all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b':' | b'-'));
+        if !valid_tag {
+            return Err(MemoError::InvalidTag(self.tag.clone()));
+        }
+        if self.payload.len() > MAX_PAYLOAD_LENGTH {
+            return Err(MemoError::PayloadTooLarge { size: self.payload.len(), limit: MAX_PAYLOAD_LENGTH });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memo_roundtrip() {
+        let memo = Memo::new("deposit", "user-1042").unwrap();
+        let data = memo.encode();
+        assert_eq!(hex::encode(&data[..6]), "6d656d6f0107");
+        assert_eq!(Memo::decode(&data), Some(Ok(memo)));
+
+        assert_eq!(Memo::decode(&[0xa9, 0x05, 0x9c, 0xbb]), None);
+        assert_eq!(Memo::decode(b"memo\x01\x09dep"), Some(Err(MemoError::Truncated)));
+        assert_eq!(Memo::decode(b"memo\x02\x01a"), Some(Err(MemoError::UnsupportedVersion(2))));
+        assert!(matches!(Memo::new("Deposit", ""), Err(MemoError::InvalidTag(_))));
+        assert!(matches!(Memo::new("a/b", ""), Err(MemoError::InvalidTag(_))));
+    }
+}
@@ -0,0 +1,219 @@
+//! アカウント単位のアクセスポリシー
+//!
+//! オペレーターが設定した拒否リスト（OFACリスト等）に含まれるアドレスの
+//! トランザクションを、ローカルのメモリプールおよびローカルで生成するブロックから除外します。
+//! すべての判定と変更は監査ログに記録されます。
+//! ファイルから読み込んだリストへの変更は [`AccessPolicy::save`] で同じファイルに書き戻します。
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// 監査ログの保持件数
+const AUDIT_LOG_CAPACITY: usize = 10_000;
+
+/// ポリシーのモード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessMode {
+    /// 拒否リストに含まれるアドレスのみ拒否
+    Denylist,
+    /// 許可リストに含まれるアドレスのみ受け付け
+    Allowlist,
+}
+
+/// 監査ログのイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// トランザクションを拒否
+    Rejected { tx_hash: String, address: String, stage: String },
+    /// リストにアドレスを追加
+    Added { address: String, actor: String },
+    /// リストからアドレスを削除
+    Removed { address: String, actor: String },
+    /// ファイルからリストを読み込み
+    Loaded { source: String, count: usize },
+}
+
+/// 監査ログのエントリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// アクセスポリシー
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    mode: AccessMode,
+    addresses: HashSet<String>,
+    audit_log: VecDeque<AuditEntry>,
+    /// 読み込んだファイル（変更の保存先）
+    path: Option<PathBuf>,
+}
+
+impl Default for AccessPolicy {
+    fn default() -> Self {
+        Self::new(AccessMode::Denylist)
+    }
+}
+
+impl AccessPolicy {
+    /// 新しいポリシーを作成
+    pub fn new(mode: AccessMode) -> Self {
+        Self {
+            mode,
+            addresses: HashSet::new(),
+            audit_log: VecDeque::new(),
+            path: None,
+        }
+    }
+
+    /// ファイルからアドレスリストを読み込む
+    ///
+    /// 1行に1アドレス。空行と `#` で始まる行は無視します。
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let addresses: HashSet<String> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(normalize)
+            .collect();
+
+        let count = addresses.len();
+        self.addresses = addresses;
+        self.path = Some(path.to_path_buf());
+        info!("Loaded {} addresses into access policy from {}", count, path.display());
+        self.audit(AuditEvent::Loaded { source: path.display().to_string(), count });
+        Ok(count)
+    }
+
+    /// 読み込んだファイルへ現在のリストを書き戻す（ファイルから読み込んでいなければ何もしない）
+    ///
+    /// 一時ファイルに書いてから置き換えるため、途中で失敗しても元のファイルは壊れません。
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut contents = String::new();
+        for address in self.addresses() {
+            contents.push_str(&address);
+            contents.push('\n');
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, contents)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// モードを取得
+    pub fn mode(&self) -> AccessMode {
+        self.mode
+    }
+
+    /// 登録済みアドレスの一覧（ソート済み）
+    pub fn addresses(&self) -> Vec<String> {
+        let mut list: Vec<String> = self.addresses.iter().cloned().collect();
+        list.sort();
+        list
+    }
+
+    /// アドレスを追加
+    pub fn add(&mut self, address: &str, actor: &str) -> bool {
+        let address = normalize(address);
+        let added = self.addresses.insert(address.clone());
+        if added {
+            info!(target: "audit", "Access list: {} added by {}", address, actor);
+            self.audit(AuditEvent::Added { address, actor: actor.to_string() });
+        }
+        added
+    }
+
+    /// アドレスを削除
+    pub fn remove(&mut self, address: &str, actor: &str) -> bool {
+        let address = normalize(address);
+        let removed = self.addresses.remove(&address);
+        if removed {
+            info!(target: "audit", "Access list: {} removed by {}", address, actor);
+            self.audit(AuditEvent::Removed { address, actor: actor.to_string() });
+        }
+        removed
+    }
+
+    /// アドレスが許可されているか
+    pub fn is_allowed(&self, address: &str) -> bool {
+        let listed = self.addresses.contains(&normalize(address));
+        match self.mode {
+            AccessMode::Denylist => !listed,
+            AccessMode::Allowlist => listed,
+        }
+    }
+
+    /// トランザクションの送信者・受信者を検査し、拒否した場合は監査ログに記録
+    ///
+    /// 拒否されたアドレスを返します。
+    pub fn check(&mut self, tx_hash: &str, from: &str, to: &str, stage: &str) -> Option<String> {
+        let denied = [from, to].into_iter().find(|a| !self.is_allowed(a))?.to_string();
+        warn!(target: "audit", "Rejected transaction {} at {}: address {} is not permitted", tx_hash, stage, denied);
+        self.audit(AuditEvent::Rejected {
+            tx_hash: tx_hash.to_string(),
+            address: denied.clone(),
+            stage: stage.to_string(),
+        });
+        Some(denied)
+    }
+
+    /// 監査ログを新しい順に取得
+    pub fn audit_log(&self, limit: usize) -> Vec<AuditEntry> {
+        self.audit_log.iter().rev().take(limit).cloned().collect()
+    }
+
+    fn audit(&mut self, event: AuditEvent) {
+        if self.audit_log.len() >= AUDIT_LOG_CAPACITY {
+            self.audit_log.pop_front();
+        }
+        self.audit_log.push_back(AuditEntry {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            event,
+        });
+    }
+}
+
+/// アドレス表記の正規化（小文字化・0xプレフィックス除去）
+fn normalize(address: &str) -> String {
+    let lower = address.trim().to_ascii_lowercase();
+    lower.strip_prefix("0x").map(str::to_string).unwrap_or(lower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_writes_back_to_loaded_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("denylist.txt");
+        std::fs::write(&path, "# OFAC\n0xAA\n").unwrap();
+
+        let mut policy = AccessPolicy::new(AccessMode::Denylist);
+        assert_eq!(policy.load_file(&path).unwrap(), 1);
+        policy.add("bb", "admin");
+        policy.remove("aa", "admin");
+        policy.save().unwrap();
+
+        let mut reloaded = AccessPolicy::new(AccessMode::Denylist);
+        reloaded.load_file(&path).unwrap();
+        assert_eq!(reloaded.addresses(), vec!["bb".to_string()]);
+        assert!(AccessPolicy::default().save().is_ok());
+    }
+}
@@ -0,0 +1,153 @@
+//! メモリプールの内容と集計
+//!
+//! 手数料の推定などの外部サービス向けに、保留中のトランザクションの数、ガス価格と
+//! 滞留時間の分布、条件に一致するトランザクションの一覧を返します。
+
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use super::{Mempool, PendingTransaction};
+
+/// ガス価格の分布の区切り（1-2-5 の系列、最後の区間は上限なし）
+const FEE_BOUNDS: [u64; 13] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000];
+/// 滞留時間の分布の区切り（秒、最後の区間は上限なし）
+const AGE_BOUNDS: [u64; 6] = [10, 30, 60, 300, 900, 3600];
+/// 一覧の上限
+pub const MAX_CONTENT_LIMIT: usize = 5000;
+
+/// 区間ごとの数（`max` は区間の上限で、この値を含まない。最後の区間は `None`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Bucket {
+    pub min: u64,
+    pub max: Option<u64>,
+    pub count: usize,
+    /// 区間のトランザクションのガス上限の合計
+    pub gas: u64,
+}
+
+/// メモリプールの集計
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MempoolSummary {
+    pub count: usize,
+    /// 送信者の数
+    pub senders: usize,
+    /// ガス上限の合計
+    pub total_gas: u64,
+    /// JSONでのバイト数の合計
+    pub total_bytes: usize,
+    /// 使用率（0.0〜1.0）
+    pub utilization: f64,
+    /// 現在の手数料フロア
+    pub fee_floor: u64,
+    /// ガス価格の最小・中央・最大（空の場合は `None`）
+    pub min_gas_price: Option<u64>,
+    pub median_gas_price: Option<u64>,
+    pub max_gas_price: Option<u64>,
+    /// ガス価格の分布
+    pub fee_histogram: Vec<Bucket>,
+    /// 受信からの経過時間（秒）の分布
+    pub age_histogram: Vec<Bucket>,
+}
+
+/// 一覧の条件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentFilter {
+    /// 送信者（`0x` の有無と大文字小文字は区別しない）
+    pub from: Option<String>,
+    pub min_gas_price: Option<u64>,
+    pub max_gas_price: Option<u64>,
+}
+
+impl ContentFilter {
+    pub fn matches(&self, tx: &PendingTransaction) -> bool {
+        self.from.as_deref().map_or(true, |from| normalize(from) == normalize(&tx.from))
+            && self.min_gas_price.map_or(true, |min| tx.gas_price >= min)
+            && self.max_gas_price.map_or(true, |max| tx.gas_price <= max)
+    }
+}
+
+fn normalize(address: &str) -> String {
+    address.trim_start_matches("0x").to_lowercase()
+}
+
+/// `bounds` で区切った区間ごとに数える
+fn histogram<'a>(bounds: &[u64], values: impl Iterator<Item = (u64, &'a PendingTransaction)>) -> Vec<Bucket> {
+    let mut buckets: Vec<Bucket> = std::iter::once(0).chain(bounds.iter().copied())
+        .zip(bounds.iter().copied().map(Some).chain(std::iter::once(None)))
+        .map(|(min, max)| Bucket { min, max, count: 0, gas: 0 })
+        .collect();
+    for (value, tx) in values {
+        let index = bounds.partition_point(|bound| *bound <= value);
+        buckets[index].count += 1;
+        buckets[index].gas = buckets[index].gas.saturating_add(tx.gas_limit);
+    }
+    buckets
+}
+
+impl Mempool {
+    /// 集計（`now` は滞留時間の基準のUNIX秒）
+    pub fn summary(&self, now: u64) -> MempoolSummary {
+        let mut prices: Vec<u64> = self.txs.values().map(|tx| tx.gas_price).collect();
+        prices.sort_unstable();
+        MempoolSummary {
+            count: self.txs.len(),
+            senders: self.by_sender.len(),
+            total_gas: self.txs.values().fold(0u64, |sum, tx| sum.saturating_add(tx.gas_limit)),
+            total_bytes: self.txs.values().map(PendingTransaction::encoded_size).sum(),
+            utilization: self.utilization(),
+            fee_floor: self.current_fee_floor(),
+            min_gas_price: prices.first().copied(),
+            median_gas_price: prices.get(prices.len() / 2).copied(),
+            max_gas_price: prices.last().copied(),
+            fee_histogram: histogram(&FEE_BOUNDS, self.txs.values().map(|tx| (tx.gas_price, tx))),
+            age_histogram: histogram(&AGE_BOUNDS, self.txs.values().map(|tx| (now.saturating_sub(tx.received_at), tx))),
+        }
+    }
+
+    /// 条件に一致するトランザクションをガス価格の高い順に最大 `limit` 件取得
+    ///
+    /// 同じガス価格では送信者とノンスの順に並べます。
+    pub fn content(&self, filter: &ContentFilter, limit: usize) -> Vec<PendingTransaction> {
+        let mut txs: Vec<&PendingTransaction> = self.txs.values().filter(|tx| filter.matches(tx)).collect();
+        txs.sort_by(|a, b| b.gas_price.cmp(&a.gas_price)
+            .then_with(|| a.from.cmp(&b.from))
+            .then_with(|| a.nonce.cmp(&b.nonce)));
+        txs.into_iter().take(limit.min(MAX_CONTENT_LIMIT)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::MempoolConfig;
+
+    fn tx(from: &str, nonce: u64, gas_price: u64, received_at: u64) -> PendingTransaction {
+        PendingTransaction { gas_price, received_at, ..PendingTransaction::test_transfer(from, "bob", 1, nonce) }.rehashed()
+    }
+
+    #[test]
+    fn test_summary_and_filtered_content() {
+        let mut mempool = Mempool::new(MempoolConfig::default()).with_allow_unsigned(true);
+        for tx in [tx("aa", 0, 3, 995), tx("aa", 1, 40, 900), tx("bb", 0, 40, 0), tx("cc", 0, 20_000, 999)] {
+            mempool.add(tx).unwrap();
+        }
+
+        let summary = mempool.summary(1000);
+        assert_eq!((summary.count, summary.senders, summary.total_gas), (4, 3, 84_000));
+        assert_eq!((summary.min_gas_price, summary.median_gas_price, summary.max_gas_price), (Some(3), Some(40), Some(20_000)));
+        let fees: Vec<_> = summary.fee_histogram.iter().filter(|b| b.count > 0).map(|b| (b.min, b.max, b.count)).collect();
+        assert_eq!(fees, vec![(2, Some(5), 1), (20, Some(50), 2), (10_000, None, 1)]);
+        let ages: Vec<_> = summary.age_histogram.iter().map(|b| b.count).collect();
+        assert_eq!(ages, vec![2, 0, 0, 1, 0, 1, 0]);
+
+        let all = mempool.content(&ContentFilter::default(), 10);
+        assert_eq!(all.iter().map(|tx| (tx.from.as_str(), tx.gas_price)).collect::<Vec<_>>(),
+            vec![("cc", 20_000), ("aa", 40), ("bb", 40), ("aa", 3)]);
+        let filtered = mempool.content(&ContentFilter {
+            from: Some("0xAA".to_string()),
+            min_gas_price: Some(10),
+            ..Default::default()
+        }, 10);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].nonce, 1);
+    }
+}
@@ -0,0 +1,627 @@
+//! メモリプール
+//!
+//! ブロックに取り込まれる前のトランザクションを保持します。
+//! 主な機能：
+//! - 受付ポリシー（最低ガス価格、アカウントごとの保留上限、データサイズ上限）
+//! - 負荷に応じた動的手数料フロア
+//! - 拒否リストによるアカウント単位のアクセス制御
+//! - ガス価格順の取り出し
+//! - 受け付けたトランザクションの通知
+//! - 手数料と滞留時間の分布の集計（`content`）
+
+pub mod access;
+pub mod content;
+pub mod policy;
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Serialize, Deserialize};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+use tracing::debug;
+
+use crate::core::blob::BlobRef;
+use crate::core::memo::Memo;
+use crate::core::types::canonical_hash;
+use crate::core::wallet::{self, TxSignature, DEFAULT_CHAIN_ID};
+
+pub use access::{AccessMode, AccessPolicy};
+pub use policy::{AdmissionError, MempoolConfig};
+
+/// 受付通知のチャネル容量
+const ARRIVALS_CAPACITY: usize = 1024;
+
+/// メモリプール内のトランザクション
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PendingTransaction {
+    /// トランザクションハッシュ（hex）
+    pub hash: String,
+    /// 送信者アドレス
+    pub from: String,
+    /// 受信者アドレス
+    pub to: String,
+    /// 送金額
+    pub value: u64,
+    /// ノンス
+    pub nonce: u64,
+    /// ガス価格
+    pub gas_price: u64,
+    /// ガス上限
+    pub gas_limit: u64,
+    /// データ
+    pub data: Vec<u8>,
+    /// 受信時刻（UNIX秒）
+    pub received_at: u64,
+    /// 有効期限（UNIX秒）。これより後のブロックには取り込まれず、メモリプールからも取り除かれる
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+    /// 署名の対象のネットワークのチェーンID（`dev.allow_unsigned` で許可した署名のないものを除き必須）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// ブロブの参照（データはサイドカーとして `core::blob` が保持する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<BlobRef>,
+    /// 送信者の署名（ハッシュの対象外）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<TxSignature>,
+}
+
+impl PendingTransaction {
+    /// ハッシュの対象となる本体（`hash`・`received_at`・`signature` を除く）
+    pub fn canonical_body(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "from": self.from,
+            "to": self.to,
+            "value": self.value,
+            "nonce": self.nonce,
+            "gas_price": self.gas_price,
+            "gas_limit": self.gas_limit,
+            "data": hex::encode(&self.data),
+        });
+        if let Some(valid_until) = self.valid_until {
+            body["valid_until"] = valid_until.into();
+        }
+        if let Some(chain_id) = self.chain_id {
+            body["chain_id"] = chain_id.into();
+        }
+        if let Some(blob) = &self.blob {
+            body["blob"] = serde_json::json!({
+                "commitment": blob.commitment,
+                "size": blob.size,
+                "max_fee_per_byte": blob.max_fee_per_byte,
+            });
+        }
+        body
+    }
+
+    /// 本体の正規化JSONハッシュを計算（手順は `core::types` を参照）
+    pub fn compute_hash(&self) -> String {
+        canonical_hash(&self.canonical_body())
+    }
+
+    /// `now`（UNIX秒）の時点で期限切れか
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.valid_until.is_some_and(|valid_until| valid_until < now)
+    }
+
+    /// `data` のメモ（メモの形式でない場合や不正な場合は `None`）
+    pub fn memo(&self) -> Option<Memo> {
+        Memo::decode(&self.data).and_then(Result::ok)
+    }
+
+    /// JSONでのバイト数
+    pub fn encoded_size(&self) -> usize {
+        serde_json::to_vec(self).map_or(usize::MAX, |bytes| bytes.len())
+    }
+
+    /// 送信者が支払う手数料（レシートのガス使用量 × ガス価格）
+    pub fn fee(&self) -> u64 {
+        self.gas_limit.saturating_mul(self.gas_price)
+    }
+
+    /// ブロブ手数料（ブロブのバイト数 × ブロックのブロブ手数料、ガスの手数料とは別に支払う）
+    pub fn blob_fee(&self, blob_fee: u64) -> u64 {
+        self.blob.as_ref().map_or(0, |blob| blob.size.saturating_mul(blob_fee))
+    }
+}
+
+#[cfg(test)]
+impl PendingTransaction {
+    /// テスト用の署名のない送金（ガス価格1、ガス上限21000）
+    pub fn test_transfer(from: &str, to: &str, value: u64, nonce: u64) -> Self {
+        Self {
+            hash: String::new(),
+            from: from.to_string(),
+            to: to.to_string(),
+            value,
+            nonce,
+            gas_price: 1,
+            gas_limit: 21_000,
+            data: Vec::new(),
+            received_at: 0,
+            valid_until: None,
+            chain_id: None,
+            blob: None,
+            signature: None,
+        }.rehashed()
+    }
+
+    /// `data` を設定（ハッシュは計算し直す）
+    pub fn with_data(self, data: Vec<u8>) -> Self {
+        Self { data, ..self }.rehashed()
+    }
+
+    /// 変更した本体に合わせてハッシュを計算し直す
+    pub fn rehashed(mut self) -> Self {
+        self.hash = self.compute_hash();
+        self
+    }
+}
+
+/// メモリプール
+#[derive(Debug)]
+pub struct Mempool {
+    config: MempoolConfig,
+    txs: HashMap<String, PendingTransaction>,
+    /// 送信者ごとの（ノンス → ハッシュ）
+    by_sender: HashMap<String, BTreeMap<u64, String>>,
+    access: AccessPolicy,
+    /// 署名を受け付けるチェーンID
+    chain_id: u64,
+    /// 署名のないトランザクションを受け付ける（開発用、`dev.allow_unsigned`）
+    allow_unsigned: bool,
+    /// 受け付けたトランザクションのハッシュ
+    arrivals: broadcast::Sender<String>,
+}
+
+impl Mempool {
+    /// 新しいメモリプールを作成
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            txs: HashMap::new(),
+            by_sender: HashMap::new(),
+            access: AccessPolicy::default(),
+            chain_id: DEFAULT_CHAIN_ID,
+            allow_unsigned: false,
+            arrivals: broadcast::channel(ARRIVALS_CAPACITY).0,
+        }
+    }
+
+    /// アクセスポリシーを設定
+    pub fn with_access_policy(mut self, access: AccessPolicy) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// 署名を受け付けるチェーンIDを設定
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// 署名のないトランザクションを受け付けるか設定（開発用）
+    pub fn with_allow_unsigned(mut self, allow: bool) -> Self {
+        self.allow_unsigned = allow;
+        self
+    }
+
+    /// アクセスポリシーを取得
+    pub fn access(&self) -> &AccessPolicy {
+        &self.access
+    }
+
+    /// アクセスポリシーを変更
+    pub fn access_mut(&mut self) -> &mut AccessPolicy {
+        &mut self.access
+    }
+
+    /// 設定を取得
+    pub fn config(&self) -> &MempoolConfig {
+        &self.config
+    }
+
+    /// 保持しているトランザクション数
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// 使用率（0.0〜1.0）
+    pub fn utilization(&self) -> f64 {
+        self.txs.len() as f64 / self.config.max_size.max(1) as f64
+    }
+
+    /// 現在の手数料フロア
+    pub fn current_fee_floor(&self) -> u64 {
+        self.config.fee_floor(self.utilization())
+    }
+
+    /// トランザクションを受け付ける
+    ///
+    /// 同じ送信者・ノンスのトランザクションが既にある場合は置き換え（replace-by-fee）として扱い、
+    /// ガス価格が既存の `replacement_bump` % 以上高い場合のみ既存のものと入れ替えます。
+    pub fn add(&mut self, tx: PendingTransaction) -> Result<String, AdmissionError> {
+        if let Some(address) = self.access.check(&tx.hash, &tx.from, &tx.to, "mempool") {
+            return Err(AdmissionError::Denied(address));
+        }
+        let replaced = self.check_replacement(&tx)?;
+        self.check_admission(&tx, replaced.is_some())?;
+
+        if let Some(hash) = replaced {
+            debug!("Replacing {} with {} (gas price {})", hash, tx.hash, tx.gas_price);
+            self.remove(&hash);
+        } else if self.txs.len() >= self.config.max_size {
+            // 満杯の場合は最も安いトランザクションを追い出す
+            let cheapest = self.txs.values()
+                .min_by_key(|t| t.gas_price)
+                .map(|t| (t.hash.clone(), t.gas_price));
+            match cheapest {
+                Some((hash, price)) if price < tx.gas_price => {
+                    debug!("Evicting {} (gas price {}) for {}", hash, price, tx.hash);
+                    self.remove(&hash);
+                }
+                _ => return Err(AdmissionError::PoolFull(self.txs.len())),
+            }
+        }
+
+        let hash = tx.hash.clone();
+        self.by_sender
+            .entry(tx.from.clone())
+            .or_default()
+            .insert(tx.nonce, hash.clone());
+        self.txs.insert(hash.clone(), tx);
+        // 購読者がいなくてもエラーにしない
+        let _ = self.arrivals.send(hash.clone());
+        Ok(hash)
+    }
+
+    /// 受け付けたトランザクションのハッシュを購読
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.arrivals.subscribe()
+    }
+
+    /// 同じ送信者・ノンスのトランザクションがあれば、置き換えできるか検証してそのハッシュを返す
+    fn check_replacement(&self, tx: &PendingTransaction) -> Result<Option<String>, AdmissionError> {
+        if self.txs.contains_key(&tx.hash) {
+            return Err(AdmissionError::Duplicate(tx.hash.clone()));
+        }
+        let Some(existing) = self.by_sender
+            .get(&tx.from)
+            .and_then(|nonces| nonces.get(&tx.nonce))
+            .and_then(|hash| self.txs.get(hash))
+        else {
+            return Ok(None);
+        };
+        let required = self.config.replacement_price(existing.gas_price);
+        if tx.gas_price < required {
+            return Err(AdmissionError::ReplacementUnderpriced {
+                existing: existing.hash.clone(),
+                gas_price: tx.gas_price,
+                required,
+            });
+        }
+        Ok(Some(existing.hash.clone()))
+    }
+
+    /// 受付ポリシーを検証
+    fn check_admission(&self, tx: &PendingTransaction, replacing: bool) -> Result<(), AdmissionError> {
+        // 署名とチェーンIDは必須（開発用に許可した場合のみ署名のないものを受け付ける）
+        if tx.signature.is_some() || !self.allow_unsigned {
+            wallet::verify(tx, self.chain_id).map_err(|e| AdmissionError::InvalidSignature(e.to_string()))?;
+        }
+
+        let now = unix_now();
+        if tx.is_expired(now) {
+            return Err(AdmissionError::Expired { valid_until: tx.valid_until.unwrap_or_default(), now });
+        }
+
+        if tx.data.len() > self.config.max_data_size {
+            return Err(AdmissionError::DataTooLarge {
+                size: tx.data.len(),
+                limit: self.config.max_data_size,
+            });
+        }
+
+        if let Some(Err(e)) = Memo::decode(&tx.data) {
+            return Err(AdmissionError::InvalidMemo(e.to_string()));
+        }
+
+        let floor = self.current_fee_floor();
+        if tx.gas_price < floor {
+            return Err(AdmissionError::FeeTooLow {
+                gas_price: tx.gas_price,
+                floor,
+                minimum: self.config.min_gas_price,
+            });
+        }
+
+        let pending = self.by_sender.get(&tx.from).map_or(0, |m| m.len());
+        if !replacing && pending >= self.config.max_pending_per_account {
+            return Err(AdmissionError::TooManyPending {
+                account: tx.from.clone(),
+                pending,
+                limit: self.config.max_pending_per_account,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// トランザクションを削除
+    pub fn remove(&mut self, hash: &str) -> Option<PendingTransaction> {
+        let tx = self.txs.remove(hash)?;
+        if let Some(nonces) = self.by_sender.get_mut(&tx.from) {
+            nonces.remove(&tx.nonce);
+            if nonces.is_empty() {
+                self.by_sender.remove(&tx.from);
+            }
+        }
+        Some(tx)
+    }
+
+    /// トランザクションを取得
+    pub fn get(&self, hash: &str) -> Option<&PendingTransaction> {
+        self.txs.get(hash)
+    }
+
+    /// 送信者の保留トランザクションに続くノンス（保留がなければ `None`）
+    pub fn next_nonce(&self, sender: &str) -> Option<u64> {
+        self.by_sender.get(sender)
+            .and_then(|nonces| nonces.keys().next_back())
+            .map(|nonce| nonce + 1)
+    }
+
+    /// 送信者の `nonce` より前の保留トランザクションの送金額の合計
+    pub fn pending_value_before(&self, sender: &str, nonce: u64) -> u64 {
+        self.by_sender.get(sender).map_or(0, |nonces| {
+            nonces.range(..nonce)
+                .filter_map(|(_, hash)| self.txs.get(hash))
+                .fold(0u64, |sum, tx| sum.saturating_add(tx.value))
+        })
+    }
+
+    /// 送信者の保留トランザクション数
+    pub fn pending_count(&self, sender: &str) -> usize {
+        self.by_sender.get(sender).map_or(0, |m| m.len())
+    }
+
+    /// 期限切れのトランザクションを取り除き、その数を返す
+    pub fn evict_expired(&mut self, now: u64) -> usize {
+        let expired: Vec<String> = self.txs.values()
+            .filter(|t| t.is_expired(now))
+            .map(|t| t.hash.clone())
+            .collect();
+        for hash in &expired {
+            debug!("Evicting expired transaction {}", hash);
+            self.remove(hash);
+        }
+        expired.len()
+    }
+
+    /// 全トランザクションのイテレーター
+    pub fn iter(&self) -> impl Iterator<Item = &PendingTransaction> {
+        self.txs.values()
+    }
+
+    /// ブロック生成用にガス価格の高い順で最大 `max` 件取得
+    ///
+    /// 同一送信者のトランザクションはノンス順を保ちます。
+    /// アクセスポリシーで拒否されたトランザクションはメモリプールから取り除かれます。
+    pub fn select_for_block(&mut self, max: usize) -> Vec<PendingTransaction> {
+        self.select_within(max, u64::MAX, usize::MAX)
+    }
+
+    /// ブロックのガス上限とバイト数に収まる範囲で `select_for_block` と同様に取得
+    ///
+    /// 収まらないトランザクションの送信者は、ノンス順を保つためそのブロックでは以降を選びません。
+    /// 期限切れのトランザクションは選ばずに取り除きます。
+    pub fn select_within(&mut self, max: usize, max_gas: u64, max_bytes: usize) -> Vec<PendingTransaction> {
+        self.evict_expired(unix_now());
+        let denied: Vec<String> = self.txs.values()
+            .filter(|t| !self.access.is_allowed(&t.from) || !self.access.is_allowed(&t.to))
+            .map(|t| t.hash.clone())
+            .collect();
+        for hash in denied {
+            if let Some(tx) = self.remove(&hash) {
+                self.access.check(&tx.hash, &tx.from, &tx.to, "block");
+            }
+        }
+
+        // 送信者ごとの先頭（最小ノンス）から貪欲に選択
+        let mut cursors: HashMap<&str, std::collections::btree_map::Values<'_, u64, String>> = self
+            .by_sender
+            .iter()
+            .map(|(sender, nonces)| (sender.as_str(), nonces.values()))
+            .collect();
+        let mut heads: Vec<&PendingTransaction> = cursors
+            .values_mut()
+            .filter_map(|it| it.next().and_then(|h| self.txs.get(h)))
+            .collect();
+
+        let mut selected = Vec::with_capacity(max.min(self.txs.len()));
+        let (mut gas, mut bytes) = (0u64, 0usize);
+        while selected.len() < max {
+            let Some((idx, _)) = heads.iter().enumerate().max_by_key(|(_, t)| t.gas_price) else {
+                break;
+            };
+            let tx = heads.swap_remove(idx);
+            // 区切りの分として1バイトを加える
+            let size = tx.encoded_size().saturating_add(1);
+            if gas.saturating_add(tx.gas_limit) > max_gas || bytes.saturating_add(size) > max_bytes {
+                continue;
+            }
+            gas += tx.gas_limit;
+            bytes += size;
+            if let Some(next) = cursors
+                .get_mut(tx.from.as_str())
+                .and_then(|it| it.next())
+                .and_then(|h| self.txs.get(h))
+            {
+                heads.push(next);
+            }
+            selected.push(tx.clone());
+        }
+        selected
+    }
+}
+
+/// ガス価格が基本手数料を下回るトランザクションを除く
+///
+/// ノンスの順序を保つため、同じ送信者の後続のトランザクションも除きます。
+pub fn retain_payable(txs: &mut Vec<PendingTransaction>, base_fee: u64) {
+    let mut underpriced = std::collections::HashSet::new();
+    txs.retain(|tx| {
+        if tx.gas_price < base_fee || underpriced.contains(&tx.from) {
+            underpriced.insert(tx.from.clone());
+            return false;
+        }
+        true
+    });
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(from: &str, nonce: u64, gas_price: u64) -> PendingTransaction {
+        PendingTransaction { gas_price, ..PendingTransaction::test_transfer(from, "bob", 1, nonce) }.rehashed()
+    }
+
+    #[test]
+    fn test_rejects_below_minimum_fee() {
+        let mut pool = Mempool::new(MempoolConfig { min_gas_price: 10, ..Default::default() }).with_allow_unsigned(true);
+        let err = pool.add(tx("alice", 0, 5)).unwrap_err();
+        assert!(matches!(err, AdmissionError::FeeTooLow { floor: 10, .. }));
+        assert!(pool.add(tx("alice", 0, 10)).is_ok());
+    }
+
+    #[test]
+    fn test_per_account_cap_and_data_size() {
+        let mut pool = Mempool::new(MempoolConfig {
+            max_pending_per_account: 2,
+            max_data_size: 4,
+            ..Default::default()
+        }).with_allow_unsigned(true);
+        pool.add(tx("alice", 0, 1)).unwrap();
+        pool.add(tx("alice", 1, 1)).unwrap();
+        assert!(matches!(pool.add(tx("alice", 2, 1)), Err(AdmissionError::TooManyPending { .. })));
+        assert!(pool.add(tx("bob", 0, 1)).is_ok());
+
+        let mut big = tx("carol", 0, 1);
+        big.data = vec![0; 5];
+        assert!(matches!(pool.add(big), Err(AdmissionError::DataTooLarge { size: 5, limit: 4 })));
+    }
+
+    #[test]
+    fn test_adaptive_floor_rises_under_load() {
+        let config = MempoolConfig {
+            max_size: 10,
+            min_gas_price: 10,
+            floor_utilization_threshold: 0.5,
+            floor_max_multiplier: 3.0,
+            ..Default::default()
+        };
+        assert_eq!(config.fee_floor(0.2), 10);
+        assert_eq!(config.fee_floor(0.75), 20);
+        assert_eq!(config.fee_floor(1.0), 30);
+
+        let mut pool = Mempool::new(config).with_allow_unsigned(true);
+        for i in 0..8 {
+            pool.add(tx(&format!("acct{}", i), 0, 100)).unwrap();
+        }
+        // 使用率80%ではフロアが22に上昇
+        assert_eq!(pool.current_fee_floor(), 22);
+        assert!(matches!(pool.add(tx("late", 0, 15)), Err(AdmissionError::FeeTooLow { floor: 22, .. })));
+    }
+
+    #[test]
+    fn test_denylist_blocks_admission_and_block_building() {
+        let mut pool = Mempool::new(MempoolConfig::default()).with_allow_unsigned(true);
+        pool.add(tx("alice", 0, 1)).unwrap();
+
+        pool.access_mut().add("0xALICE", "operator");
+        assert!(matches!(pool.add(tx("alice", 1, 1)), Err(AdmissionError::Denied(_))));
+
+        // リスト追加前に受け付けたトランザクションもブロックには含めない
+        assert!(pool.select_for_block(10).is_empty());
+        assert!(pool.is_empty());
+
+        let log = pool.access().audit_log(10);
+        assert_eq!(log.len(), 3);
+        assert!(matches!(log[0].event, access::AuditEvent::Rejected { ref stage, .. } if stage == "block"));
+    }
+
+    #[test]
+    fn test_select_for_block_respects_nonce_order() {
+        let mut pool = Mempool::new(MempoolConfig::default()).with_allow_unsigned(true);
+        pool.add(tx("alice", 0, 1)).unwrap();
+        pool.add(tx("alice", 1, 100)).unwrap();
+        pool.add(tx("bob", 0, 50)).unwrap();
+
+        let selected = pool.select_for_block(3);
+        let order: Vec<_> = selected.iter().map(|t| (t.from.as_str(), t.nonce)).collect();
+        assert_eq!(order, vec![("bob", 0), ("alice", 0), ("alice", 1)]);
+    }
+
+    #[test]
+    fn test_replace_by_fee_and_expiry() {
+        let mut pool = Mempool::new(MempoolConfig { max_pending_per_account: 1, ..Default::default() }).with_allow_unsigned(true);
+        let original = pool.add(tx("alice", 0, 100)).unwrap();
+
+        // 上乗せが10%未満の置き換えは拒否し、元のトランザクションを残す
+        let mut cheap = tx("alice", 0, 109);
+        cheap.value = 2;
+        cheap.hash = cheap.compute_hash();
+        assert!(matches!(pool.add(cheap), Err(AdmissionError::ReplacementUnderpriced { required: 110, .. })));
+
+        let replacement = pool.add(tx("alice", 0, 110)).unwrap();
+        assert!(pool.get(&original).is_none());
+        assert_eq!(pool.pending_count("alice"), 1);
+
+        let mut expiring = tx("bob", 0, 1);
+        expiring.valid_until = Some(1000);
+        expiring.hash = expiring.compute_hash();
+        assert!(matches!(pool.add(expiring.clone()), Err(AdmissionError::Expired { valid_until: 1000, .. })));
+
+        pool.txs.insert(expiring.hash.clone(), expiring);
+        assert_eq!(pool.evict_expired(1001), 1);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.get(&replacement).is_some());
+    }
+
+    #[test]
+    fn test_select_within_gas_budget() {
+        let mut pool = Mempool::new(MempoolConfig::default()).with_allow_unsigned(true);
+        pool.add(tx("alice", 0, 100)).unwrap();
+        pool.add(tx("alice", 1, 100)).unwrap();
+        pool.add(tx("bob", 0, 1)).unwrap();
+
+        // 2件分のガスしかない場合は価格の高い順に2件
+        let selected = pool.select_within(10, 42_000, usize::MAX);
+        let order: Vec<_> = selected.iter().map(|t| (t.from.as_str(), t.nonce)).collect();
+        assert_eq!(order, vec![("alice", 0), ("alice", 1)]);
+        assert!(pool.select_within(10, u64::MAX, 10).is_empty());
+    }
+
+    #[test]
+    fn test_requires_signature_unless_allowed() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        assert!(matches!(pool.add(tx("alice", 0, 1)), Err(AdmissionError::InvalidSignature(_))));
+
+        let key = ed25519_dalek::SigningKey::from_bytes(&[1; 32]);
+        let from = wallet::address_of(&key.verifying_key());
+        let unsigned = PendingTransaction { chain_id: Some(DEFAULT_CHAIN_ID), ..tx(&from, 0, 1) }.rehashed();
+        let signed = PendingTransaction { signature: Some(wallet::sign(&key, &unsigned)), ..unsigned };
+        assert!(pool.add(signed).is_ok());
+    }
+}
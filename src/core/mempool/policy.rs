@@ -0,0 +1,116 @@
+//! メモリプールの受付ポリシー
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::config::MempoolSettings;
+
+/// メモリプールの設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    /// 保持するトランザクションの最大数
+    pub max_size: usize,
+    /// 最低ガス価格
+    pub min_gas_price: u64,
+    /// アカウントごとの保留トランザクション上限
+    pub max_pending_per_account: usize,
+    /// データフィールドの最大サイズ（バイト）
+    pub max_data_size: usize,
+    /// 負荷に応じた動的手数料フロアを有効化
+    pub adaptive_fee_floor: bool,
+    /// 動的フロアが上昇し始める使用率（0.0〜1.0）
+    pub floor_utilization_threshold: f64,
+    /// 使用率100%時のフロア倍率
+    pub floor_max_multiplier: f64,
+    /// 置き換えに必要なガス価格の上乗せ（%）
+    pub replacement_bump: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10_000,
+            min_gas_price: 1,
+            max_pending_per_account: 64,
+            max_data_size: 128 * 1024, // 128KB
+            adaptive_fee_floor: true,
+            floor_utilization_threshold: 0.5,
+            floor_max_multiplier: 10.0,
+            replacement_bump: 10,
+        }
+    }
+}
+
+impl From<&MempoolSettings> for MempoolConfig {
+    fn from(settings: &MempoolSettings) -> Self {
+        Self {
+            max_size: settings.max_size,
+            min_gas_price: settings.min_gas_price,
+            max_pending_per_account: settings.max_pending_per_account,
+            max_data_size: settings.max_data_size,
+            adaptive_fee_floor: settings.adaptive_fee_floor,
+            floor_utilization_threshold: settings.floor_utilization_threshold,
+            floor_max_multiplier: settings.floor_max_multiplier,
+            replacement_bump: settings.replacement_bump,
+        }
+    }
+}
+
+impl MempoolConfig {
+    /// 既存のトランザクションを置き換えるのに必要な最低ガス価格
+    ///
+    /// 上乗せが0%でも同額での置き換えは認めず、少なくとも1高い価格を求めます。
+    pub fn replacement_price(&self, existing: u64) -> u64 {
+        let bumped = existing.saturating_mul(100 + self.replacement_bump).div_ceil(100);
+        bumped.max(existing.saturating_add(1))
+    }
+
+    /// 現在の使用率における手数料フロアを計算
+    ///
+    /// 使用率がしきい値以下なら最低ガス価格、それ以上では
+    /// 使用率100%で `floor_max_multiplier` 倍になるよう線形に上昇します。
+    pub fn fee_floor(&self, utilization: f64) -> u64 {
+        let utilization = utilization.clamp(0.0, 1.0);
+        if !self.adaptive_fee_floor || utilization <= self.floor_utilization_threshold {
+            return self.min_gas_price;
+        }
+
+        let span = (1.0 - self.floor_utilization_threshold).max(f64::EPSILON);
+        let pressure = (utilization - self.floor_utilization_threshold) / span;
+        let multiplier = 1.0 + pressure * (self.floor_max_multiplier - 1.0).max(0.0);
+        (self.min_gas_price.max(1) as f64 * multiplier).ceil() as u64
+    }
+}
+
+/// 受付拒否の理由
+#[derive(Debug, Clone, Error, PartialEq, Eq, Serialize)]
+pub enum AdmissionError {
+    #[error("gas price {gas_price} is below the current fee floor {floor} (minimum {minimum})")]
+    FeeTooLow { gas_price: u64, floor: u64, minimum: u64 },
+
+    #[error("account {account} already has {pending} pending transactions (limit {limit})")]
+    TooManyPending { account: String, pending: usize, limit: usize },
+
+    #[error("transaction data is {size} bytes, exceeding the limit of {limit} bytes")]
+    DataTooLarge { size: usize, limit: usize },
+
+    #[error("transaction {0} is already in the mempool")]
+    Duplicate(String),
+
+    #[error("mempool is full ({0} transactions) and the gas price does not outbid the cheapest entry")]
+    PoolFull(usize),
+
+    #[error("address {0} is not permitted by the node's access policy")]
+    Denied(String),
+
+    #[error("transaction expired at {valid_until} (now {now})")]
+    Expired { valid_until: u64, now: u64 },
+
+    #[error("replacing {existing} requires a gas price of at least {required}, got {gas_price}")]
+    ReplacementUnderpriced { existing: String, gas_price: u64, required: u64 },
+
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("invalid memo: {0}")]
+    InvalidMemo(String),
+}
@@ -1,7 +1,31 @@
+pub mod ai;
+pub mod block;
+pub mod consensus;
+pub mod coordination;
 pub mod dag;
 pub mod sharding;
 pub mod storage;
 pub mod token;
 pub mod network;
 pub mod time_sync;
-pub mod discovery;
\ No newline at end of file
+pub mod discovery;
+pub mod mempool;
+pub mod fees;
+pub mod accounting;
+pub mod contract;
+pub mod transaction;
+pub mod cache;
+#[cfg(any(feature = "sql", feature = "export"))]
+pub mod columnar;
+pub mod watchlist;
+pub mod types;
+pub mod memo;
+pub mod names;
+pub mod htlc;
+pub mod vesting;
+pub mod genesis;
+pub mod blob;
+#[cfg(feature = "confidential-tx")]
+pub mod confidential;
+pub mod telemetry;
+pub mod wallet;
@@ -0,0 +1,502 @@
+//! ネームサービス
+//!
+//! 人が読める名前（例: `alice`）をアドレスに対応付けるネイティブのレジストリです。
+//! 操作はレジストリのアドレス [`NAME_REGISTRY_ADDRESS`] 宛てのトランザクションの `data` に入れ、
+//! 登録と更新の手数料は `value` でレジストリに支払います。手数料はプロトコルで決まり
+//! （[`annual_fee`]）、レジストリから引き出す方法はないため焼却と同じです。
+//!
+//! | 位置 | 内容 |
+//! |------|------|
+//! | 0..4 | マジック `name`（`6e616d65`） |
+//! | 4    | バージョン（`1`） |
+//! | 5    | 種類（`0` は登録、`1` は更新、`2` は譲渡） |
+//! | 6    | 名前の長さ |
+//! | 7..  | 名前、続けて登録と更新は年数（1バイト）、譲渡は新しい所有者のアドレス（hex） |
+//!
+//! - 登録：空いている名前を送信者のものにします。名前は所有者のアドレスに解決されます
+//! - 更新：誰でも期限を延ばせます（期限切れ後の猶予期間も含む）
+//! - 譲渡：所有者のみ、期限内のみ
+//!
+//! 期限が切れた名前は解決されず、猶予期間の間は更新のみでき、その後は誰でも登録できます。
+//! 操作は確定したブロックの順に検証して適用し、検証に失敗した操作は状態を変えません。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+
+/// 反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"names/applied_height";
+
+/// 操作の先頭のマジック
+pub const NAME_MAGIC: [u8; 4] = *b"name";
+/// 形式のバージョン
+const NAME_VERSION: u8 = 1;
+/// レジストリのアドレス（操作の宛先、手数料の支払先）
+pub const NAME_REGISTRY_ADDRESS: &str = "000000000000000000000000000000000000004e";
+/// 名前の長さの範囲
+pub const MIN_NAME_LENGTH: usize = 3;
+pub const MAX_NAME_LENGTH: usize = 32;
+/// 1回の登録・更新で指定できる最大年数
+pub const MAX_YEARS: u8 = 10;
+/// 1年の秒数
+pub const YEAR_SECS: u64 = 365 * 24 * 60 * 60;
+/// 期限切れ後に更新のみできる期間（秒）
+pub const GRACE_PERIOD_SECS: u64 = 90 * 24 * 60 * 60;
+
+const KIND_REGISTER: u8 = 0;
+const KIND_RENEW: u8 = 1;
+const KIND_TRANSFER: u8 = 2;
+
+/// 名前の1年あたりの手数料（短い名前ほど高い）
+pub fn annual_fee(name: &str) -> u64 {
+    match name.len() {
+        3 => 1_000_000,
+        4 => 250_000,
+        _ => 10_000,
+    }
+}
+
+/// ネームサービスのエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum NameError {
+    #[error("name operation is truncated")]
+    Truncated,
+
+    #[error("unsupported name operation version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("unknown name operation kind {0}")]
+    UnknownKind(u8),
+
+    #[error("name {0:?} must be 3-32 characters of a-z, 0-9 and '-', not starting or ending with '-'")]
+    InvalidName(String),
+
+    #[error("registration period must be 1-10 years, got {0}")]
+    InvalidYears(u8),
+
+    #[error("name operations must be sent to the registry 000000000000000000000000000000000000004e")]
+    WrongRecipient,
+
+    #[error("name operation must pay {required}, got {paid}")]
+    FeeMismatch { required: u64, paid: u64 },
+
+    #[error("name {0} is already registered")]
+    Taken(String),
+
+    #[error("name {0} is not registered")]
+    NotRegistered(String),
+
+    #[error("name {0} has expired")]
+    Expired(String),
+
+    #[error("only the owner {owner} can transfer {name}")]
+    NotOwner { name: String, owner: String },
+
+    #[error("new owner is not a valid address")]
+    InvalidOwner,
+}
+
+/// `data` に入れる操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameOp {
+    Register { name: String, years: u8 },
+    Renew { name: String, years: u8 },
+    Transfer { name: String, owner: String },
+}
+
+impl NameOp {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Register { name, .. } | Self::Renew { name, .. } | Self::Transfer { name, .. } => name,
+        }
+    }
+
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = NAME_MAGIC.to_vec();
+        data.push(NAME_VERSION);
+        let (kind, tail) = match self {
+            Self::Register { years, .. } => (KIND_REGISTER, vec![*years]),
+            Self::Renew { years, .. } => (KIND_RENEW, vec![*years]),
+            Self::Transfer { owner, .. } => (KIND_TRANSFER, owner.as_bytes().to_vec()),
+        };
+        data.push(kind);
+        data.push(self.name().len() as u8);
+        data.extend_from_slice(self.name().as_bytes());
+        data.extend_from_slice(&tail);
+        data
+    }
+
+    /// `data` フィールドを解析（名前の操作でなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, NameError>> {
+        let body = data.strip_prefix(&NAME_MAGIC)?;
+        Some(Self::decode_body(body))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, NameError> {
+        let [version, kind, length, rest @ ..] = body else {
+            return Err(NameError::Truncated);
+        };
+        if *version != NAME_VERSION {
+            return Err(NameError::UnsupportedVersion(*version));
+        }
+        if rest.len() < *length as usize {
+            return Err(NameError::Truncated);
+        }
+        let (name, tail) = rest.split_at(*length as usize);
+        let name = validate_name(&String::from_utf8_lossy(name))?;
+        match (*kind, tail) {
+            (KIND_REGISTER, [years]) => Ok(Self::Register { name, years: validate_years(*years)? }),
+            (KIND_RENEW, [years]) => Ok(Self::Renew { name, years: validate_years(*years)? }),
+            (KIND_REGISTER | KIND_RENEW, _) => Err(NameError::Truncated),
+            (KIND_TRANSFER, owner) => {
+                let owner = std::str::from_utf8(owner).map_err(|_| NameError::InvalidOwner)?;
+                if !matches!(owner.len(), 40 | 64) || !owner.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+                    return Err(NameError::InvalidOwner);
+                }
+                Ok(Self::Transfer { name, owner: owner.to_string() })
+            }
+            (kind, _) => Err(NameError::UnknownKind(kind)),
+        }
+    }
+
+    /// トランザクションの操作（名前の操作でなければ `None`）
+    pub fn of(tx: &PendingTransaction) -> Option<Result<Self, NameError>> {
+        Self::decode(&tx.data)
+    }
+}
+
+/// 名前の形式を検証
+pub fn validate_name(name: &str) -> Result<String, NameError> {
+    let valid = (MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&name.len())
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(NameError::InvalidName(name.to_string()))
+    }
+}
+
+fn validate_years(years: u8) -> Result<u8, NameError> {
+    if (1..=MAX_YEARS).contains(&years) {
+        Ok(years)
+    } else {
+        Err(NameError::InvalidYears(years))
+    }
+}
+
+/// 名前の登録
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "names/record", version = 1)]
+pub struct NameRecord {
+    #[state(key)]
+    pub name: String,
+    /// 所有者（名前の解決先）
+    pub owner: String,
+    /// 登録した時刻（UNIX秒）
+    pub registered_at: u64,
+    /// 期限（UNIX秒）
+    pub expires_at: u64,
+    /// 最後に変更されたブロックの高さ
+    pub updated_at: u64,
+}
+
+/// 名前の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NameStatus {
+    /// 登録されていない、または猶予期間を過ぎた
+    Available,
+    Active,
+    /// 期限切れで、猶予期間の間は更新のみできる
+    Grace,
+}
+
+impl NameRecord {
+    pub fn status(&self, now: u64) -> NameStatus {
+        if now < self.expires_at {
+            NameStatus::Active
+        } else if now < self.expires_at.saturating_add(GRACE_PERIOD_SECS) {
+            NameStatus::Grace
+        } else {
+            NameStatus::Available
+        }
+    }
+}
+
+/// 名前のレジストリ
+pub struct NameRegistry {
+    storage: Arc<dyn StorageEngine>,
+    /// ブロックの反映を直列にする（購読と追いつきが同時に反映しないように）
+    applying: Mutex<()>,
+}
+
+impl NameRegistry {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        Self { storage, applying: Mutex::new(()) }
+    }
+
+    /// 名前の登録（期限切れのものも返す）
+    pub async fn record(&self, name: &str) -> Result<Option<NameRecord>> {
+        NameRecord::load(self.storage.as_ref(), &name.to_string()).await
+    }
+
+    /// 名前を `now` の時点で解決（期限切れの名前は `None`）
+    pub async fn resolve(&self, name: &str, now: u64) -> Result<Option<String>> {
+        Ok(self.record(name).await?
+            .filter(|record| record.status(now) == NameStatus::Active)
+            .map(|record| record.owner))
+    }
+
+    /// 受付時の検証（確定した状態と現在時刻に対して検証する）
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<(), NameError> {
+        self.apply(&mut HashMap::new(), tx, unix_now(), 0).await
+    }
+
+    /// ブロックに含められない名前の操作（と同じ送信者の後続のもの）を除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let now = unix_now();
+        let mut records = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            match self.apply(&mut records, &tx, now, 0).await {
+                Ok(()) => kept.push(tx),
+                Err(e) => {
+                    debug!("Leaving name operation {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// 操作を検証して `records` に反映する
+    async fn apply(
+        &self,
+        records: &mut HashMap<String, NameRecord>,
+        tx: &PendingTransaction,
+        now: u64,
+        height: u64,
+    ) -> Result<(), NameError> {
+        let op = match NameOp::of(tx) {
+            None => return Ok(()),
+            Some(op) => op?,
+        };
+        if tx.to != NAME_REGISTRY_ADDRESS {
+            return Err(NameError::WrongRecipient);
+        }
+        let name = op.name().to_string();
+        let current = match records.get(&name) {
+            Some(record) => Some(record.clone()),
+            None => self.record(&name).await.map_err(|_| NameError::NotRegistered(name.clone()))?,
+        };
+        let status = current.as_ref().map_or(NameStatus::Available, |r| r.status(now));
+        let record = match op {
+            NameOp::Register { years, .. } => {
+                check_fee(tx, &name, years)?;
+                if status != NameStatus::Available {
+                    return Err(NameError::Taken(name));
+                }
+                NameRecord {
+                    name: name.clone(),
+                    owner: tx.from.clone(),
+                    registered_at: now,
+                    expires_at: now.saturating_add(years as u64 * YEAR_SECS),
+                    updated_at: height,
+                }
+            }
+            NameOp::Renew { years, .. } => {
+                check_fee(tx, &name, years)?;
+                match current {
+                    Some(record) if status != NameStatus::Available => NameRecord {
+                        expires_at: record.expires_at.saturating_add(years as u64 * YEAR_SECS),
+                        updated_at: height,
+                        ..record
+                    },
+                    _ => return Err(NameError::NotRegistered(name)),
+                }
+            }
+            NameOp::Transfer { owner, .. } => {
+                check_fee(tx, &name, 0)?;
+                let record = current.ok_or_else(|| NameError::NotRegistered(name.clone()))?;
+                if status != NameStatus::Active {
+                    return Err(NameError::Expired(name));
+                }
+                if record.owner != tx.from {
+                    return Err(NameError::NotOwner { name, owner: record.owner });
+                }
+                NameRecord { owner, updated_at: height, ..record }
+            }
+        };
+        records.insert(name, record);
+        Ok(())
+    }
+
+    /// 確定したブロックの操作を順に検証して適用する（失敗した操作は状態を変えない）
+    ///
+    /// 反映済みの高さ以下のブロックは無視します。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        let mut records = HashMap::new();
+        for tx in &block.transactions {
+            if let Err(e) = self.apply(&mut records, tx, block.timestamp, block.height).await {
+                warn!("Name operation {} in block {} was not applied: {}", tx.hash, block.height, e);
+            }
+        }
+        let mut batch = records.values()
+            .map(NameRecord::put_change)
+            .collect::<Result<Vec<_>>>()?;
+        batch.push((APPLIED_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        self.storage.batch_write(batch).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 最新のブロックまでストレージから読み直して反映する
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=head {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 以降の確定で操作を適用する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up the name registry: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply name operations: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up the name registry: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// 登録・更新は年数分の手数料ちょうど、譲渡は0
+fn check_fee(tx: &PendingTransaction, name: &str, years: u8) -> Result<(), NameError> {
+    let required = annual_fee(name).saturating_mul(years as u64);
+    if tx.value != required {
+        return Err(NameError::FeeMismatch { required, paid: tx.value });
+    }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn tx(from: &str, value: u64, nonce: u64, op: &NameOp) -> PendingTransaction {
+        PendingTransaction::test_transfer(from, NAME_REGISTRY_ADDRESS, value, nonce).with_data(op.encode())
+    }
+
+    fn block(height: u64, timestamp: u64, transactions: Vec<PendingTransaction>) -> Block {
+        let mut block = Block::new(height, "p".to_string(), "v".to_string(), transactions);
+        block.timestamp = timestamp;
+        block
+    }
+
+    #[tokio::test]
+    async fn test_register_renew_transfer_and_expiry() {
+        let storage = RedbStorage::memory();
+        let registry = NameRegistry::new(storage);
+        let (alice, bob) = ("a".repeat(40), "b".repeat(40));
+        let register = NameOp::Register { name: "alice".to_string(), years: 1 };
+        assert_eq!(NameOp::decode(&register.encode()), Some(Ok(register.clone())));
+
+        // 手数料が足りない登録と、既に登録された名前の登録は適用されない
+        let start = unix_now();
+        registry.apply_block(&block(1, start, vec![
+            tx(&alice, 1, 0, &register),
+            tx(&alice, 10_000, 1, &register),
+            tx(&bob, 10_000, 0, &register),
+        ])).await.unwrap();
+        let record = registry.record("alice").await.unwrap().unwrap();
+        assert_eq!((record.owner.as_str(), record.expires_at), (alice.as_str(), start + YEAR_SECS));
+        assert_eq!(registry.resolve("alice", start).await.unwrap(), Some(alice.clone()));
+
+        // 所有者以外は譲渡できない
+        let transfer = NameOp::Transfer { name: "alice".to_string(), owner: bob.clone() };
+        assert!(matches!(registry.check(&tx(&bob, 0, 1, &transfer)).await, Err(NameError::NotOwner { .. })));
+        registry.apply_block(&block(2, start + 10, vec![tx(&alice, 0, 2, &transfer)])).await.unwrap();
+        assert_eq!(registry.resolve("alice", start + 10).await.unwrap(), Some(bob.clone()));
+
+        // 期限切れの名前は解決されず、猶予期間の間は更新のみできる
+        let expired = start + YEAR_SECS;
+        assert_eq!(registry.resolve("alice", expired).await.unwrap(), None);
+        let renew = NameOp::Renew { name: "alice".to_string(), years: 2 };
+        let renewal = block(3, expired, vec![
+            tx(&alice, 10_000, 3, &register),
+            tx(&alice, 20_000, 4, &renew),
+        ]);
+        registry.apply_block(&renewal).await.unwrap();
+        // 反映済みのブロックを再び渡しても（追いつきとの重複）二重に更新しない
+        registry.apply_block(&renewal).await.unwrap();
+        let record = registry.record("alice").await.unwrap().unwrap();
+        assert_eq!((record.owner.as_str(), record.expires_at), (bob.as_str(), start + 3 * YEAR_SECS));
+        assert_eq!(registry.applied_height().await.unwrap(), Some(3));
+
+        // 猶予期間を過ぎると誰でも登録できる
+        let released = start + 3 * YEAR_SECS + GRACE_PERIOD_SECS;
+        registry.apply_block(&block(4, released, vec![tx(&alice, 10_000, 5, &register)])).await.unwrap();
+        assert_eq!(registry.resolve("alice", released).await.unwrap(), Some(alice));
+
+        assert_eq!(annual_fee("abc"), 1_000_000);
+        assert!(matches!(validate_name("-ab"), Err(NameError::InvalidName(_))));
+        assert!(matches!(validate_name("Alice"), Err(NameError::InvalidName(_))));
+        let mut wrong = tx(&bob, 10_000, 0, &register);
+        wrong.to = bob;
+        assert_eq!(registry.check(&wrong).await, Err(NameError::WrongRecipient));
+    }
+}
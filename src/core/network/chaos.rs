@@ -0,0 +1,276 @@
+//! 開発用のネットワーク障害の注入
+//!
+//! ローカルのdevnetで、WANのような遅延・揺らぎ・パケットロス・帯域制限と、予定した
+//! ネットワーク分断を再現します。送信するメッセージごとに、リンクの条件に応じて待つか捨て、
+//! 分断中のピアとの間のメッセージは送受信とも捨てます。
+//! 主な機能：
+//! - 全リンク共通の条件と、ピアごとの上書き
+//! - 起動からの経過時間で始まり終わるネットワーク分断
+//! - コマンドラインの `--chaos-link`・`--chaos-partition` の解析
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use rand::Rng;
+use crate::config::{ChaosSettings, LinkChaosSettings, PartitionSettings};
+use super::quic::PeerId;
+
+/// 障害を注入するピアの指定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PeerMatch {
+    /// `ip:port`
+    Addr(SocketAddr),
+    /// `ip`（全ポート）
+    Ip(IpAddr),
+}
+
+impl PeerMatch {
+    fn parse(peer: &str) -> Result<Self> {
+        peer.parse().map(Self::Addr)
+            .or_else(|_| peer.parse().map(Self::Ip))
+            .map_err(|_| anyhow!("Invalid chaos peer {}: expected ip or ip:port", peer))
+    }
+
+    fn matches(&self, peer: &PeerId) -> bool {
+        match self {
+            Self::Addr(addr) => peer.addr() == Some(*addr),
+            Self::Ip(ip) => peer.ip() == Some(*ip),
+        }
+    }
+}
+
+/// リンクの条件
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkConditions {
+    pub latency: Duration,
+    pub jitter: Duration,
+    /// メッセージを捨てる割合
+    pub loss: f64,
+    /// 帯域の上限（バイト/秒、0 は無制限）
+    pub bandwidth: u64,
+}
+
+impl LinkConditions {
+    fn apply(&self, settings: &LinkChaosSettings) -> Result<Self> {
+        let loss = settings.loss.unwrap_or(self.loss);
+        check_loss(loss)?;
+        Ok(Self {
+            latency: settings.latency_ms.map_or(self.latency, Duration::from_millis),
+            jitter: settings.jitter_ms.map_or(self.jitter, Duration::from_millis),
+            loss,
+            bandwidth: settings.bandwidth_kbps.map_or(self.bandwidth, kbps_to_bytes),
+        })
+    }
+
+    fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn check_loss(loss: f64) -> Result<()> {
+    if (0.0..=1.0).contains(&loss) {
+        Ok(())
+    } else {
+        Err(anyhow!("Chaos loss must be between 0.0 and 1.0: {}", loss))
+    }
+}
+
+fn kbps_to_bytes(kbps: u64) -> u64 {
+    kbps * 1000 / 8
+}
+
+/// ネットワーク分断
+#[derive(Debug, Clone)]
+struct Partition {
+    start: Duration,
+    end: Duration,
+    peers: Vec<PeerMatch>,
+}
+
+/// 障害の注入の設定
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    default: LinkConditions,
+    links: Vec<(PeerMatch, LinkConditions)>,
+    partitions: Vec<Partition>,
+}
+
+impl ChaosConfig {
+    pub fn from_settings(settings: &ChaosSettings) -> Result<Self> {
+        check_loss(settings.loss)?;
+        let default = LinkConditions {
+            latency: Duration::from_millis(settings.latency_ms),
+            jitter: Duration::from_millis(settings.jitter_ms),
+            loss: settings.loss,
+            bandwidth: kbps_to_bytes(settings.bandwidth_kbps),
+        };
+        let links = settings.links.iter()
+            .map(|link| Ok((PeerMatch::parse(&link.peer)?, default.apply(link)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let partitions = settings.partitions.iter()
+            .map(|partition| Ok(Partition {
+                start: Duration::from_secs(partition.start),
+                end: Duration::from_secs(partition.start + partition.duration),
+                peers: partition.peers.iter().map(|peer| PeerMatch::parse(peer)).collect::<Result<_>>()?,
+            }))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { default, links, partitions })
+    }
+
+    /// 障害を注入するか
+    pub fn is_enabled(&self) -> bool {
+        !self.default.is_clean()
+            || self.links.iter().any(|(_, link)| !link.is_clean())
+            || !self.partitions.is_empty()
+    }
+
+    /// ピアとのリンクの条件（ピアごとの設定は最初に一致したものを使う）
+    pub fn conditions(&self, peer: &PeerId) -> LinkConditions {
+        self.links.iter()
+            .find(|(target, _)| target.matches(peer))
+            .map_or(self.default, |(_, link)| *link)
+    }
+
+    /// 起動から `elapsed` の時点でピアと分断されているか
+    pub fn partitioned(&self, peer: &PeerId, elapsed: Duration) -> bool {
+        self.partitions.iter().any(|partition| {
+            (partition.start..partition.end).contains(&elapsed)
+                && partition.peers.iter().any(|target| target.matches(peer))
+        })
+    }
+}
+
+/// 障害の注入
+#[derive(Debug)]
+pub struct Chaos {
+    config: ChaosConfig,
+    started: Instant,
+    /// リンクごとの、送信中のデータを送り終える時刻（帯域制限）
+    busy_until: Mutex<HashMap<PeerId, Instant>>,
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig) -> Self {
+        Self { config, started: Instant::now(), busy_until: Mutex::default() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_enabled()
+    }
+
+    /// 現在ピアと分断されているか
+    pub fn partitioned(&self, peer: &PeerId) -> bool {
+        self.config.partitioned(peer, self.started.elapsed())
+    }
+
+    /// 送信の前に呼び、リンクの条件の分だけ待つ（捨てる場合はエラー）
+    pub async fn before_send(&self, peer: &PeerId, size: usize) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        if self.partitioned(peer) {
+            return Err(anyhow!("Dropped by chaos: partitioned from {}", peer));
+        }
+        let link = self.config.conditions(peer);
+        let (lost, jitter) = {
+            let mut rng = rand::thread_rng();
+            let jitter = if link.jitter.is_zero() { Duration::ZERO } else { link.jitter.mul_f64(rng.gen()) };
+            (link.loss > 0.0 && rng.gen_bool(link.loss), jitter)
+        };
+        if lost {
+            return Err(anyhow!("Dropped by chaos: lost on the link to {}", peer));
+        }
+        let mut delay = link.latency + jitter;
+        if link.bandwidth > 0 {
+            // 同じリンクの前のメッセージを送り終えてから送る
+            let now = Instant::now();
+            let transfer = Duration::from_secs_f64(size as f64 / link.bandwidth as f64);
+            let mut busy_until = self.busy_until.lock().unwrap();
+            let until = busy_until.get(peer).copied().filter(|until| *until > now).unwrap_or(now) + transfer;
+            busy_until.insert(peer.clone(), until);
+            delay += until - now;
+        }
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Self::new(ChaosConfig::default())
+    }
+}
+
+/// `--chaos-link` の値を解析（`PEER=latency:100,jitter:20,loss:0.05,bandwidth:1000`）
+pub fn parse_link(value: &str) -> Result<LinkChaosSettings> {
+    let (peer, spec) = value.split_once('=')
+        .ok_or_else(|| anyhow!("Invalid chaos link {}: expected PEER=KEY:VALUE,...", value))?;
+    let mut link = LinkChaosSettings { peer: peer.to_string(), ..Default::default() };
+    for item in spec.split(',').filter(|item| !item.is_empty()) {
+        let (key, val) = item.split_once(':')
+            .ok_or_else(|| anyhow!("Invalid chaos link setting {}: expected KEY:VALUE", item))?;
+        let invalid = |e: &dyn std::fmt::Display| anyhow!("Invalid chaos link {} {}: {}", key, val, e);
+        match key {
+            "latency" => link.latency_ms = Some(val.parse().map_err(|e| invalid(&e))?),
+            "jitter" => link.jitter_ms = Some(val.parse().map_err(|e| invalid(&e))?),
+            "loss" => link.loss = Some(val.parse().map_err(|e| invalid(&e))?),
+            "bandwidth" => link.bandwidth_kbps = Some(val.parse().map_err(|e| invalid(&e))?),
+            other => return Err(anyhow!("Unknown chaos link setting {}", other)),
+        }
+    }
+    PeerMatch::parse(&link.peer)?;
+    Ok(link)
+}
+
+/// `--chaos-partition` の値を解析（`START+DURATION=PEER,PEER`、秒）
+pub fn parse_partition(value: &str) -> Result<PartitionSettings> {
+    let invalid = || anyhow!("Invalid chaos partition {}: expected START+DURATION=PEER,...", value);
+    let (window, peers) = value.split_once('=').ok_or_else(invalid)?;
+    let (start, duration) = window.split_once('+').ok_or_else(invalid)?;
+    let peers: Vec<String> = peers.split(',').filter(|peer| !peer.is_empty()).map(str::to_string).collect();
+    for peer in &peers {
+        PeerMatch::parse(peer)?;
+    }
+    Ok(PartitionSettings {
+        start: start.parse().map_err(|_| invalid())?,
+        duration: duration.parse().map_err(|_| invalid())?,
+        peers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(addr: &str) -> PeerId {
+        PeerId::from_addr(&addr.parse().unwrap())
+    }
+
+    #[test]
+    fn test_links_and_partition_schedule() {
+        let settings = ChaosSettings {
+            latency_ms: 50,
+            links: vec![parse_link("127.0.0.1:4002=latency:200,loss:0.1").unwrap()],
+            partitions: vec![parse_partition("30+60=127.0.0.2").unwrap()],
+            ..Default::default()
+        };
+        let config = ChaosConfig::from_settings(&settings).unwrap();
+        assert!(config.is_enabled());
+
+        let slow = config.conditions(&peer("127.0.0.1:4002"));
+        assert_eq!((slow.latency, slow.loss), (Duration::from_millis(200), 0.1));
+        assert_eq!(config.conditions(&peer("127.0.0.1:4003")).latency, Duration::from_millis(50));
+
+        // 127.0.0.2 の全ポートと、起動の30秒後から90秒後まで分断する
+        assert!(!config.partitioned(&peer("127.0.0.2:4004"), Duration::from_secs(29)));
+        assert!(config.partitioned(&peer("127.0.0.2:4004"), Duration::from_secs(30)));
+        assert!(!config.partitioned(&peer("127.0.0.2:4004"), Duration::from_secs(90)));
+        assert!(!config.partitioned(&peer("127.0.0.1:4002"), Duration::from_secs(45)));
+
+        assert!(parse_link("127.0.0.1:4002=delay:5").is_err());
+        assert!(parse_partition("30=127.0.0.2").is_err());
+        assert!(!ChaosConfig::default().is_enabled());
+    }
+}
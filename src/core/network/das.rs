@@ -0,0 +1,372 @@
+//! データ可用性サンプリング（`das` フィーチャー）
+//!
+//! ブロックの本体を消失訂正符号（Reed-Solomon、GF(2^8)）で2倍に拡張し、シェアに分けて提供します。
+//! ライトノードはブロック全体をダウンロードせず、ランダムに選んだ少数のシェアとマークル証明を取得して、
+//! ブロックが入手可能であることを確率的に確認します。
+//! 主な機能：
+//! - ブロック本体（JSON）の符号化とシェアのマークルルート（`data_root`）
+//! - シェアのマークル証明の作成と検証
+//! - 任意の半分のシェアからのブロックの復元（誤った符号化の検出を含む）
+//! - 複数のピアへのサンプリング（HTTP、`/api/das`）と信頼度の計算
+//!
+//! 元のデータを復元できなくするには半分より多くのシェアを隠す必要があるため、ランダムなサンプル1件が
+//! それに当たる確率は1/2以上です。`s` 件すべてが検証できれば、入手可能である信頼度は `1 - 2^-s` です。
+//!
+//! `data_root` はまだブロックヘッダーに含まれないため、ライトノードは複数のピアから取得した
+//! コミットメントが一致することを確認します。
+
+use std::time::Duration;
+use rand::seq::index::sample as sample_indexes;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use utoipa::ToSchema;
+use crate::core::block::Block;
+
+/// データシェアの最大数（GF(2^8) のシェアは合計256まで）
+pub const MAX_DATA_SHARDS: usize = 128;
+/// シェアの最小バイト数（小さいブロックはシェアの数を減らす）
+pub const MIN_SHARD_SIZE: usize = 256;
+/// サンプリングの要求のタイムアウト
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ブロックの拡張データのコミットメント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DasCommitment {
+    pub height: u64,
+    pub block_hash: String,
+    /// 全シェアのマークルルート（hex）
+    pub data_root: String,
+    pub data_shards: usize,
+    pub parity_shards: usize,
+    /// シェアのバイト数
+    pub shard_size: usize,
+    /// 符号化したブロック本体のバイト数（末尾の埋め草を除く）
+    pub body_len: usize,
+}
+
+impl DasCommitment {
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+}
+
+/// シェアとマークル証明
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Sample {
+    pub index: usize,
+    /// シェア（hex）
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub share: Vec<u8>,
+    /// 葉から根までの兄弟のハッシュ（hex）
+    pub proof: Vec<String>,
+}
+
+/// データ可用性サンプリングのエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum DasError {
+    #[error("erasure coding failed: {0}")]
+    Coding(String),
+
+    #[error("share {index} is out of range (block has {total} shares)")]
+    OutOfRange { index: usize, total: usize },
+
+    #[error("share {index} does not match the data root")]
+    InvalidProof { index: usize },
+
+    #[error("need {need} distinct shares to reconstruct, have {have}")]
+    NotEnoughShares { have: usize, need: usize },
+
+    #[error("reconstructed shares do not match the data root (incorrectly encoded block)")]
+    BadEncoding,
+
+    #[error("reconstructed body is not block {0}")]
+    BlockMismatch(String),
+
+    #[error("peers disagree on the commitment of block {0}")]
+    CommitmentMismatch(u64),
+
+    #[error("share {index} is unavailable from {peer}: {reason}")]
+    Unavailable { index: usize, peer: String, reason: String },
+}
+
+/// 符号化したブロック
+pub struct ExtendedBlock {
+    commitment: DasCommitment,
+    shards: Vec<Vec<u8>>,
+    /// 葉から根までのマークル木の段
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl ExtendedBlock {
+    /// ブロック本体を符号化する
+    pub fn encode(block: &Block) -> Result<Self, DasError> {
+        let body = serde_json::to_vec(block).map_err(|e| DasError::Coding(e.to_string()))?;
+        let data_shards = body.len().div_ceil(MIN_SHARD_SIZE).clamp(1, MAX_DATA_SHARDS);
+        let shard_size = body.len().div_ceil(data_shards).max(1);
+        let mut shards: Vec<Vec<u8>> = (0..data_shards)
+            .map(|i| {
+                let mut shard = body.get(i * shard_size..).unwrap_or_default().to_vec();
+                shard.resize(shard_size, 0);
+                shard
+            })
+            .collect();
+        shards.resize(2 * data_shards, vec![0; shard_size]);
+        codec(data_shards)?.encode(&mut shards).map_err(|e| DasError::Coding(format!("{:?}", e)))?;
+
+        let levels = merkle_levels(&shards);
+        let commitment = DasCommitment {
+            height: block.height,
+            block_hash: block.hash.clone(),
+            data_root: hex::encode(levels.last().expect("at least one level")[0]),
+            data_shards,
+            parity_shards: data_shards,
+            shard_size,
+            body_len: body.len(),
+        };
+        Ok(Self { commitment, shards, levels })
+    }
+
+    pub fn commitment(&self) -> &DasCommitment {
+        &self.commitment
+    }
+
+    /// シェアとその証明
+    pub fn sample(&self, index: usize) -> Result<Sample, DasError> {
+        let share = self.shards.get(index)
+            .ok_or(DasError::OutOfRange { index, total: self.shards.len() })?;
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = level.get(position ^ 1).unwrap_or(&level[position]);
+            proof.push(hex::encode(sibling));
+            position /= 2;
+        }
+        Ok(Sample { index, share: share.clone(), proof })
+    }
+}
+
+/// シェアがコミットメントの `data_root` に含まれるか検証
+pub fn verify_sample(commitment: &DasCommitment, sample: &Sample) -> Result<(), DasError> {
+    let invalid = DasError::InvalidProof { index: sample.index };
+    if sample.index >= commitment.total_shards() {
+        return Err(DasError::OutOfRange { index: sample.index, total: commitment.total_shards() });
+    }
+    if sample.share.len() != commitment.shard_size || sample.proof.len() != tree_depth(commitment.total_shards()) {
+        return Err(invalid);
+    }
+    let mut hash = leaf_hash(&sample.share);
+    let mut position = sample.index;
+    for sibling in &sample.proof {
+        let sibling: [u8; 32] = hex::decode(sibling).ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid.clone())?;
+        hash = if position.is_multiple_of(2) { node_hash(&hash, &sibling) } else { node_hash(&sibling, &hash) };
+        position /= 2;
+    }
+    if hex::encode(hash) != commitment.data_root {
+        return Err(invalid);
+    }
+    Ok(())
+}
+
+/// 半分以上のシェアからブロックを復元する
+///
+/// 復元した全シェアから `data_root` を計算し直し、誤って符号化されたブロックを検出します。
+pub fn reconstruct(commitment: &DasCommitment, samples: &[Sample]) -> Result<Block, DasError> {
+    let mut shards: Vec<Option<Vec<u8>>> = vec![None; commitment.total_shards()];
+    for sample in samples {
+        verify_sample(commitment, sample)?;
+        shards[sample.index] = Some(sample.share.clone());
+    }
+    let have = shards.iter().filter(|shard| shard.is_some()).count();
+    if have < commitment.data_shards {
+        return Err(DasError::NotEnoughShares { have, need: commitment.data_shards });
+    }
+    codec(commitment.data_shards)?.reconstruct(&mut shards).map_err(|e| DasError::Coding(format!("{:?}", e)))?;
+    let shards: Vec<Vec<u8>> = shards.into_iter().map(|shard| shard.expect("reconstructed")).collect();
+
+    // パリティがデータから正しく計算されていなければ、根が一致しない
+    let mut reencoded = shards.clone();
+    codec(commitment.data_shards)?.encode(&mut reencoded).map_err(|e| DasError::Coding(format!("{:?}", e)))?;
+    if hex::encode(merkle_levels(&reencoded).last().expect("at least one level")[0]) != commitment.data_root {
+        return Err(DasError::BadEncoding);
+    }
+
+    let mut body: Vec<u8> = shards[..commitment.data_shards].concat();
+    body.truncate(commitment.body_len);
+    let block: Block = serde_json::from_slice(&body)
+        .map_err(|_| DasError::BlockMismatch(commitment.block_hash.clone()))?;
+    if block.hash != commitment.block_hash || block.compute_hash() != block.hash {
+        return Err(DasError::BlockMismatch(commitment.block_hash.clone()));
+    }
+    Ok(block)
+}
+
+/// `samples` 件のサンプルがすべて検証できた場合の、入手可能である信頼度
+pub fn confidence(samples: usize) -> f64 {
+    1.0 - 0.5f64.powi(samples.min(i32::MAX as usize) as i32)
+}
+
+/// 信頼度 `target`（0〜1未満）に必要なサンプル数
+pub fn samples_for_confidence(target: f64) -> usize {
+    let target = target.clamp(0.0, 1.0 - f64::EPSILON);
+    (-(1.0 - target).log2()).ceil().max(1.0) as usize
+}
+
+/// サンプリングの結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingReport {
+    pub commitment: DasCommitment,
+    /// 検証したシェアの位置
+    pub sampled: Vec<usize>,
+    pub confidence: f64,
+}
+
+/// ピアへのサンプリング
+///
+/// ピアはフルノードのAPIのベースURL（例: `http://node:9071/api`）です。
+pub struct Sampler {
+    client: reqwest::Client,
+    peers: Vec<String>,
+}
+
+impl Sampler {
+    pub fn new(peers: Vec<String>) -> anyhow::Result<Self> {
+        anyhow::ensure!(!peers.is_empty(), "at least one peer is required for sampling");
+        let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        let peers = peers.into_iter().map(|peer| peer.trim_end_matches('/').to_string()).collect();
+        Ok(Self { client, peers })
+    }
+
+    /// ブロックのコミットメントを全ピアから取得し、ランダムなシェアを `samples` 件検証する
+    ///
+    /// 応答しない、または検証できないシェアが1件でもあれば入手不能としてエラーを返します。
+    pub async fn check(&self, height: u64, samples: usize) -> anyhow::Result<SamplingReport> {
+        let mut commitment: Option<DasCommitment> = None;
+        for peer in &self.peers {
+            let received: DasCommitment = self.client
+                .get(format!("{}/das/blocks/{}", peer, height))
+                .send().await?
+                .error_for_status()?
+                .json().await?;
+            match &commitment {
+                Some(first) if *first != received => return Err(DasError::CommitmentMismatch(height).into()),
+                Some(_) => {}
+                None => commitment = Some(received),
+            }
+        }
+        let commitment = commitment.expect("at least one peer");
+
+        let total = commitment.total_shards();
+        let indexes = sample_indexes(&mut rand::thread_rng(), total, samples.min(total)).into_vec();
+        for (i, &index) in indexes.iter().enumerate() {
+            let peer = &self.peers[i % self.peers.len()];
+            let unavailable = |reason: String| DasError::Unavailable { index, peer: peer.clone(), reason };
+            let sample: Sample = self.fetch(peer, height, index).await.map_err(|e| unavailable(e.to_string()))?;
+            if sample.index != index {
+                return Err(unavailable(format!("returned share {}", sample.index)).into());
+            }
+            verify_sample(&commitment, &sample)?;
+        }
+        Ok(SamplingReport { confidence: confidence(indexes.len()), sampled: indexes, commitment })
+    }
+
+    async fn fetch(&self, peer: &str, height: u64, index: usize) -> reqwest::Result<Sample> {
+        self.client
+            .get(format!("{}/das/blocks/{}/samples/{}", peer, height, index))
+            .send().await?
+            .error_for_status()?
+            .json().await
+    }
+}
+
+fn codec(data_shards: usize) -> Result<ReedSolomon, DasError> {
+    ReedSolomon::new(data_shards, data_shards).map_err(|e| DasError::Coding(format!("{:?}", e)))
+}
+
+/// 葉と内部ノードのハッシュは先頭のバイトで区別する（第二原像攻撃の防止）
+fn leaf_hash(share: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0]);
+    hasher.update(share);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// `leaves` 枚の葉のマークル木の深さ（証明のハッシュの数）
+fn tree_depth(leaves: usize) -> usize {
+    let mut width = leaves;
+    let mut depth = 0;
+    while width > 1 {
+        width = width.div_ceil(2);
+        depth += 1;
+    }
+    depth
+}
+
+/// マークル木の段（奇数個の段は最後の要素を複製する）
+fn merkle_levels(shards: &[Vec<u8>]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![shards.iter().map(|shard| leaf_hash(shard)).collect::<Vec<_>>()];
+    while levels.last().expect("at least one level").len() > 1 {
+        let next = levels.last().expect("at least one level")
+            .chunks(2)
+            .map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::PendingTransaction;
+
+    fn block() -> Block {
+        let transactions = (0..40)
+            .map(|nonce| PendingTransaction::test_transfer("alice", "bob", nonce, nonce).with_data(vec![nonce as u8; 64]))
+            .collect();
+        Block::new(7, "p".to_string(), "v".to_string(), transactions)
+    }
+
+    #[test]
+    fn test_samples_verify_and_half_the_shares_reconstruct() {
+        let block = block();
+        let extended = ExtendedBlock::encode(&block).unwrap();
+        let commitment = extended.commitment().clone();
+        assert!(commitment.data_shards > 1 && commitment.data_shards <= MAX_DATA_SHARDS);
+        assert_eq!(commitment.parity_shards, commitment.data_shards);
+
+        let samples: Vec<Sample> = (0..commitment.total_shards()).map(|i| extended.sample(i).unwrap()).collect();
+        for sample in &samples {
+            assert_eq!(verify_sample(&commitment, sample), Ok(()));
+        }
+        let mut forged = samples[3].clone();
+        forged.share[0] ^= 1;
+        assert_eq!(verify_sample(&commitment, &forged), Err(DasError::InvalidProof { index: 3 }));
+        let mut moved = samples[3].clone();
+        moved.index = 2;
+        assert_eq!(verify_sample(&commitment, &moved), Err(DasError::InvalidProof { index: 2 }));
+
+        // 奇数番目（データとパリティが混ざる半分）だけから復元できる
+        let odd: Vec<Sample> = samples.iter().filter(|s| s.index % 2 == 1).cloned().collect();
+        assert_eq!(reconstruct(&commitment, &odd).unwrap().hash, block.hash);
+        assert!(matches!(
+            reconstruct(&commitment, &odd[1..]),
+            Err(DasError::NotEnoughShares { .. })
+        ));
+
+        assert_eq!(samples_for_confidence(0.99), 7);
+        assert!(confidence(7) >= 0.99 && confidence(6) < 0.99);
+    }
+}
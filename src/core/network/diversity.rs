@@ -0,0 +1,194 @@
+//! 発信するピアのIPアドレスの分散
+//!
+//! 同じサブネット・同じASNのピアへの発信の接続数を制限し、少数のネットワークを
+//! 支配する攻撃者がノードを孤立させる（エクリプス攻撃）リスクを下げます。
+//! ループバック・プライベート・リンクローカルのアドレスは制限の対象外です
+//! （開発用のネットワークやデータセンター内の接続を妨げないため）。
+
+use std::net::IpAddr;
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use crate::config::DiversitySettings;
+use crate::core::cache::geo::contains;
+
+/// ASNテーブル
+#[derive(Debug, Clone, Default)]
+pub struct AsnTable {
+    entries: Vec<(IpAddr, u8, u32)>,
+}
+
+impl AsnTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// 1行に `CIDR,ASN` の形式（ASN は `AS13335` または `13335`）
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (n, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((cidr, asn)) = line.split_once(',') else {
+                return Err(anyhow!("line {}: expected cidr,asn", n + 1));
+            };
+            let (addr, prefix) = cidr.trim().split_once('/').unwrap_or((cidr.trim(), ""));
+            let addr: IpAddr = addr.parse().map_err(|e| anyhow!("line {}: {}", n + 1, e))?;
+            let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = if prefix.is_empty() { max_prefix } else { prefix.parse()? };
+            if prefix > max_prefix {
+                return Err(anyhow!("line {}: invalid prefix length {}", n + 1, prefix));
+            }
+            let asn = asn.trim();
+            let asn = asn.strip_prefix("AS").unwrap_or(asn).parse()
+                .map_err(|e| anyhow!("line {}: invalid ASN: {}", n + 1, e))?;
+            entries.push((addr, prefix, asn));
+        }
+        // 長いプレフィックスを先に照合する
+        entries.sort_by(|a, b| b.1.cmp(&a.1));
+        Ok(Self { entries })
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<u32> {
+        self.entries.iter()
+            .find(|(net, prefix, _)| contains(*net, *prefix, ip))
+            .map(|(_, _, asn)| *asn)
+    }
+}
+
+/// 分散の制約に違反した理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiversityViolation {
+    /// 同じサブネットの接続が上限に達している
+    Subnet(IpAddr),
+    /// 同じASNの接続が上限に達している
+    Asn(u32),
+}
+
+impl std::fmt::Display for DiversityViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Subnet(subnet) => write!(f, "too many outbound peers in subnet {}", subnet),
+            Self::Asn(asn) => write!(f, "too many outbound peers in AS{}", asn),
+        }
+    }
+}
+
+/// 発信するピアの分散の制約
+#[derive(Debug, Clone)]
+pub struct DiversityPolicy {
+    max_per_subnet: usize,
+    max_per_asn: usize,
+    asn: Option<AsnTable>,
+}
+
+impl Default for DiversityPolicy {
+    fn default() -> Self {
+        let settings = DiversitySettings::default();
+        Self { max_per_subnet: settings.max_per_subnet, max_per_asn: settings.max_per_asn, asn: None }
+    }
+}
+
+impl DiversityPolicy {
+    pub fn from_settings(settings: &DiversitySettings) -> Result<Self> {
+        Ok(Self {
+            max_per_subnet: settings.max_per_subnet,
+            max_per_asn: settings.max_per_asn,
+            asn: settings.asn_path.as_ref().map(AsnTable::load).transpose()?,
+        })
+    }
+
+    pub fn with_asn_table(mut self, asn: AsnTable) -> Self {
+        self.asn = Some(asn);
+        self
+    }
+
+    /// 接続中の発信のピアに `candidate` を加えても制約を満たすか
+    pub fn check(&self, candidate: IpAddr, existing: &[IpAddr]) -> Result<(), DiversityViolation> {
+        if !is_public(candidate) {
+            return Ok(());
+        }
+        let subnet = subnet(candidate);
+        if self.max_per_subnet > 0
+            && existing.iter().filter(|ip| subnet_of_public(**ip) == Some(subnet)).count() >= self.max_per_subnet
+        {
+            return Err(DiversityViolation::Subnet(subnet));
+        }
+        if let (Some(table), true) = (&self.asn, self.max_per_asn > 0) {
+            if let Some(asn) = table.lookup(candidate) {
+                let same_asn = existing.iter()
+                    .filter(|ip| is_public(**ip) && table.lookup(**ip) == Some(asn))
+                    .count();
+                if same_asn >= self.max_per_asn {
+                    return Err(DiversityViolation::Asn(asn));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// サブネット（IPv4 は /24、IPv6 は /48）
+fn subnet(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            IpAddr::from([a, b, c, 0])
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => subnet(IpAddr::V4(v4)),
+            None => {
+                let [a, b, c, ..] = v6.segments();
+                IpAddr::from([a, b, c, 0, 0, 0, 0, 0])
+            }
+        },
+    }
+}
+
+fn subnet_of_public(ip: IpAddr) -> Option<IpAddr> {
+    is_public(ip).then(|| subnet(ip))
+}
+
+/// 制限の対象となるアドレスか（ループバック・プライベート・リンクローカルは対象外）
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !(v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified()),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            // fc00::/7（ユニークローカル）と fe80::/10（リンクローカル）
+            None => !(v6.is_loopback() || v6.is_unspecified()
+                || (v6.segments()[0] & 0xfe00) == 0xfc00
+                || (v6.segments()[0] & 0xffc0) == 0xfe80),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_limits_peers_per_subnet_and_asn() {
+        let asn = AsnTable::parse("# cidr,asn\n198.51.100.0/24,AS64500\n203.0.113.0/24,64500\n").unwrap();
+        let policy = DiversityPolicy { max_per_subnet: 1, max_per_asn: 2, asn: None }.with_asn_table(asn);
+
+        let existing = [ip("198.51.100.7")];
+        assert_eq!(policy.check(ip("198.51.100.8"), &existing), Err(DiversityViolation::Subnet(ip("198.51.100.0"))));
+        assert_eq!(policy.check(ip("203.0.113.1"), &existing), Ok(()));
+        // 2つのサブネットは同じASN
+        let existing = [ip("198.51.100.7"), ip("203.0.113.1")];
+        assert_eq!(policy.check(ip("192.0.2.1"), &existing), Ok(()));
+        let more = AsnTable::parse("198.51.100.0/24,64500\n203.0.113.0/24,64500\n192.0.2.0/24,64500").unwrap();
+        let policy = policy.with_asn_table(more);
+        assert_eq!(policy.check(ip("192.0.2.1"), &existing), Err(DiversityViolation::Asn(64500)));
+
+        // プライベート・ループバックのアドレスは制限しない
+        assert_eq!(policy.check(ip("127.0.0.1"), &[ip("127.0.0.2"), ip("127.0.0.3")]), Ok(()));
+        assert_eq!(policy.check(ip("10.0.0.1"), &[ip("10.0.0.2")]), Ok(()));
+    }
+}
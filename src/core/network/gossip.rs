@@ -0,0 +1,300 @@
+//! ゴシップの優先制御
+//!
+//! 投票などの合意形成のメッセージがブロックの一括転送の後ろで待たされないようにします。
+//! 主な機能：
+//! - メッセージの種類ごとの送信キュー（合意形成 > 制御 > トランザクション・ブロック）
+//! - トランザクションとブロックの重み付きラウンドロビン（ブロックを飢餓させない）
+//! - 送信先のピアの選択（合意形成のメッセージはすべてのバリデーターへステーク量の順に送り、
+//!   それ以外はステーク量で重み付けしたピアに送る）
+//! - QUICストリームの優先度（同じ接続の中でも合意形成のメッセージを先に送る）
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use tokio::sync::{Mutex, Notify};
+use tracing::warn;
+use crate::config::GossipSettings;
+use super::quic::{Message, PeerId};
+
+/// メッセージの種類（優先度の高い順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageClass {
+    /// 投票・提案などの合意形成のメッセージ
+    Consensus,
+    /// ハートビート・コンパクトブロックなどの制御メッセージ（小さく、遅れるとブロックの伝播が遅れる）
+    Control,
+    Transaction,
+    /// ブロックの転送（一括ダウンロードを含む）
+    Block,
+}
+
+impl MessageClass {
+    pub const ALL: [MessageClass; 4] = [Self::Consensus, Self::Control, Self::Transaction, Self::Block];
+
+    pub fn of(message: &Message) -> Self {
+        match message {
+            Message::Consensus(_) | Message::Beacon(_) => Self::Consensus,
+            Message::Heartbeat
+            | Message::CompactBlock(_)
+            | Message::GetBlockTxn(_)
+            | Message::BlockTxn(_)
+            | Message::Hello(_) => Self::Control,
+            Message::Transaction(_) => Self::Transaction,
+            Message::Block(_) => Self::Block,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// QUICストリームの優先度（大きいほど先に送られる）
+    pub fn stream_priority(self) -> i32 {
+        match self {
+            Self::Consensus => 3,
+            Self::Control => 2,
+            Self::Transaction => 1,
+            Self::Block => 0,
+        }
+    }
+}
+
+/// ゴシップの優先制御の設定
+#[derive(Debug, Clone)]
+pub struct GossipConfig {
+    pub fanout: usize,
+    pub max_in_flight: usize,
+    /// 種類ごとのキューの容量（`MessageClass` の順）
+    pub capacities: [usize; 4],
+    pub transaction_weight: u32,
+    pub block_weight: u32,
+    /// バリデーターのピアのステーク量
+    pub stakes: HashMap<PeerId, u64>,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self::from(&GossipSettings::default())
+    }
+}
+
+impl From<&GossipSettings> for GossipConfig {
+    fn from(settings: &GossipSettings) -> Self {
+        let stakes = settings.validator_stakes.iter()
+            .filter_map(|(addr, stake)| match addr.parse::<SocketAddr>() {
+                Ok(addr) => Some((PeerId::from_addr(&addr), *stake)),
+                Err(e) => {
+                    warn!("Ignoring validator stake for invalid address {}: {}", addr, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            fanout: settings.fanout.max(1),
+            max_in_flight: settings.max_in_flight.max(1),
+            // 制御メッセージは小さく少ないので合意形成のキューと同じ容量にする
+            capacities: [settings.consensus_queue, settings.consensus_queue, settings.transaction_queue, settings.block_queue],
+            transaction_weight: settings.transaction_weight.max(1),
+            block_weight: settings.block_weight.max(1),
+            stakes,
+        }
+    }
+}
+
+/// ピアのステーク量
+#[derive(Debug, Clone, Default)]
+pub struct StakeTable {
+    stakes: HashMap<PeerId, u64>,
+}
+
+impl StakeTable {
+    pub fn new(stakes: HashMap<PeerId, u64>) -> Self {
+        Self { stakes }
+    }
+
+    /// ステーク量を置き換える（ステーク量0のピアはバリデーターとして扱わない）
+    pub fn replace(&mut self, stakes: HashMap<PeerId, u64>) {
+        self.stakes = stakes;
+    }
+
+    pub fn stake(&self, peer: &PeerId) -> u64 {
+        self.stakes.get(peer).copied().unwrap_or(0)
+    }
+
+    /// メッセージの送信先を選ぶ
+    ///
+    /// 合意形成のメッセージは接続中のすべてのバリデーター（ステーク量の多い順）と、
+    /// バリデーター以外から `fanout` 個に送ります。それ以外のメッセージは
+    /// ステーク量 + 1 で重み付けして `fanout` 個を選びます。
+    pub fn select(&self, class: MessageClass, peers: &[PeerId], fanout: usize) -> Vec<PeerId> {
+        if class == MessageClass::Consensus {
+            let (mut validators, others): (Vec<&PeerId>, Vec<&PeerId>) = peers.iter()
+                .partition(|peer| self.stake(peer) > 0);
+            validators.sort_by(|a, b| self.stake(b).cmp(&self.stake(a)).then_with(|| a.to_string().cmp(&b.to_string())));
+            let mut selected: Vec<PeerId> = validators.into_iter().cloned().collect();
+            selected.extend(weighted_sample(others, fanout, |_| 1));
+            return selected;
+        }
+        weighted_sample(peers.iter().collect(), fanout, |peer| self.stake(peer).saturating_add(1))
+    }
+}
+
+/// 重み付きの非復元抽出（Efraimidis-Spirakis 法）
+fn weighted_sample(peers: Vec<&PeerId>, count: usize, weight: impl Fn(&PeerId) -> u64) -> Vec<PeerId> {
+    if peers.len() <= count {
+        return peers.into_iter().cloned().collect();
+    }
+    let mut keyed: Vec<(f64, &PeerId)> = peers.into_iter()
+        .map(|peer| (rand::random::<f64>().powf(1.0 / weight(peer) as f64), peer))
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().take(count).map(|(_, peer)| peer.clone()).collect()
+}
+
+/// 送信待ちのメッセージ
+#[derive(Debug, Clone)]
+pub struct Outbound {
+    pub peer: PeerId,
+    pub class: MessageClass,
+    pub message: Message,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    queues: [VecDeque<Outbound>; 4],
+    /// 種類ごとに捨てたメッセージの数
+    dropped: [u64; 4],
+    /// トランザクションとブロックのラウンドロビンの位置
+    turn: u32,
+}
+
+/// 種類ごとの送信キュー
+#[derive(Debug)]
+pub struct OutboundQueues {
+    state: Mutex<QueueState>,
+    ready: Notify,
+    capacities: [usize; 4],
+    transaction_weight: u32,
+    block_weight: u32,
+}
+
+impl OutboundQueues {
+    pub fn new(config: &GossipConfig) -> Self {
+        Self {
+            state: Mutex::new(QueueState::default()),
+            ready: Notify::new(),
+            capacities: config.capacities,
+            transaction_weight: config.transaction_weight,
+            block_weight: config.block_weight,
+        }
+    }
+
+    /// メッセージをキューに追加（追加せずに捨てた場合は `false`）
+    ///
+    /// 合意形成・制御のキューが溢れた場合は古いものを捨てます（新しい投票ほど価値がある）。
+    /// トランザクション・ブロックのキューが溢れた場合は追加しようとしたものを捨てます。
+    pub async fn push(&self, outbound: Outbound) -> bool {
+        let class = outbound.class.index();
+        let mut state = self.state.lock().await;
+        if state.queues[class].len() >= self.capacities[class] {
+            state.dropped[class] += 1;
+            if !matches!(outbound.class, MessageClass::Consensus | MessageClass::Control) {
+                return false;
+            }
+            state.queues[class].pop_front();
+        }
+        state.queues[class].push_back(outbound);
+        drop(state);
+        self.ready.notify_one();
+        true
+    }
+
+    /// 次に送るメッセージを取り出す（空の場合は追加されるまで待つ）
+    pub async fn pop(&self) -> Outbound {
+        loop {
+            if let Some(outbound) = self.try_pop().await {
+                return outbound;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    async fn try_pop(&self) -> Option<Outbound> {
+        let mut state = self.state.lock().await;
+        for class in [MessageClass::Consensus, MessageClass::Control] {
+            if let Some(outbound) = state.queues[class.index()].pop_front() {
+                return Some(outbound);
+            }
+        }
+        // トランザクションとブロックは比重に応じて交互に送る
+        let cycle = self.transaction_weight + self.block_weight;
+        let (first, second) = if state.turn % cycle < self.transaction_weight {
+            (MessageClass::Transaction, MessageClass::Block)
+        } else {
+            (MessageClass::Block, MessageClass::Transaction)
+        };
+        let outbound = state.queues[first.index()].pop_front()
+            .or_else(|| state.queues[second.index()].pop_front())?;
+        state.turn = state.turn.wrapping_add(1);
+        Some(outbound)
+    }
+
+    /// 種類ごとのキューの長さ
+    pub async fn depth(&self, class: MessageClass) -> usize {
+        self.state.lock().await.queues[class.index()].len()
+    }
+
+    /// 種類ごとに捨てたメッセージの数
+    pub async fn dropped(&self, class: MessageClass) -> u64 {
+        self.state.lock().await.dropped[class.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> PeerId {
+        PeerId::from_addr(&SocketAddr::from(([10, 0, 0, 1], port)))
+    }
+
+    #[tokio::test]
+    async fn test_votes_are_not_delayed_behind_blocks() {
+        let config = GossipConfig {
+            capacities: [2, 2, 8, 8],
+            transaction_weight: 2,
+            block_weight: 1,
+            ..Default::default()
+        };
+        let queues = OutboundQueues::new(&config);
+        let outbound = |message: Message| Outbound { peer: peer(1), class: MessageClass::of(&message), message };
+        for i in 0..3u8 {
+            queues.push(outbound(Message::Block(vec![i]))).await;
+            queues.push(outbound(Message::Transaction(vec![i]))).await;
+        }
+        for i in 0..3u8 {
+            queues.push(outbound(Message::Consensus(vec![i]))).await;
+        }
+
+        // 溢れた投票は古いものから捨て、ブロックより先に送る
+        assert_eq!(queues.dropped(MessageClass::Consensus).await, 1);
+        let mut order = Vec::new();
+        for _ in 0..8 {
+            order.push(queues.pop().await.message);
+        }
+        assert!(matches!(order[..2], [Message::Consensus(ref a), Message::Consensus(ref b)] if a == &[1] && b == &[2]));
+        let classes: Vec<MessageClass> = order[2..].iter().map(MessageClass::of).collect();
+        use MessageClass::{Block, Transaction};
+        assert_eq!(classes, [Transaction, Transaction, Block, Transaction, Block, Block]);
+    }
+
+    #[test]
+    fn test_consensus_goes_to_every_validator_by_stake() {
+        let stakes = StakeTable::new(HashMap::from([(peer(1), 10), (peer(2), 500), (peer(3), 0)]));
+        let peers: Vec<PeerId> = (1..=6).map(peer).collect();
+
+        let selected = stakes.select(MessageClass::Consensus, &peers, 2);
+        assert_eq!(selected[..2], [peer(2), peer(1)]);
+        assert_eq!(selected.len(), 4);
+        assert_eq!(stakes.select(MessageClass::Block, &peers, 3).len(), 3);
+    }
+}
@@ -5,6 +5,25 @@
 //! - ピアツーピア通信
 //! - メッセージングプロトコル
 //! - ネットワークイベント処理
+//! - ステーク量とメッセージの種類に応じたゴシップの優先制御
+//! - ピアの接続数の上限と追い出し
+//! - 発信するピアのサブネット・ASNの分散（エクリプス攻撃対策）
+//! - バリデーターのセントリーノード構成
+//! - ノードの役割と機能の通知
+//! - DNSシードと固定ピアへの再接続
+//! - 開発用のネットワーク障害の注入
+//! - ライトノードのデータ可用性サンプリング（`das` フィーチャー）
+
+pub mod chaos;
+#[cfg(feature = "das")]
+pub mod das;
+pub mod diversity;
+pub mod gossip;
+pub mod peers;
+pub mod quic;
+pub mod roles;
+pub mod seeds;
+pub mod sentry;
 
 use std::{
     collections::HashSet,
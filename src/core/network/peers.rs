@@ -0,0 +1,368 @@
+//! ピアの接続数の管理
+//!
+//! 受信・発信それぞれの接続数に上限を設け、上限に達したら評価の低いピアを追い出します。
+//! 主な機能：
+//! - 受信・発信の接続数の上限（ファイルディスクリプタの枯渇を防ぐ）
+//! - 保護するピアの固定（常に接続し、上限に数えず、追い出さない）
+//! - レイテンシー・有用性・重複メッセージの割合による評価と追い出し
+//! - 発信するピアのサブネット・ASNの分散
+//! - ハンドシェイクで通知された役割と機能の記録
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tracing::warn;
+use utoipa::ToSchema;
+use crate::config::PeerLimitSettings;
+use crate::core::types::sha256_hex;
+use super::diversity::{DiversityPolicy, DiversityViolation};
+use super::quic::PeerId;
+use super::roles::{Capability, Handshake, NodeRole};
+
+/// 重複の判定に使う最近のメッセージの数
+const SEEN_CAPACITY: usize = 16_384;
+/// これ以上のレイテンシーは評価0とする（ミリ秒）
+const MAX_SCORED_LATENCY_MS: f64 = 1_000.0;
+
+/// 接続の方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// 接続数の上限と評価の重み
+#[derive(Debug, Clone)]
+pub struct PeerLimits {
+    pub max_inbound: usize,
+    pub max_outbound: usize,
+    pub protected: HashSet<PeerId>,
+    pub eviction_grace: Duration,
+    pub latency_weight: f64,
+    pub usefulness_weight: f64,
+    pub duplicate_weight: f64,
+}
+
+impl Default for PeerLimits {
+    fn default() -> Self {
+        Self::from(&PeerLimitSettings::default())
+    }
+}
+
+impl From<&PeerLimitSettings> for PeerLimits {
+    fn from(settings: &PeerLimitSettings) -> Self {
+        let protected = settings.protected.iter()
+            .filter_map(|addr| match addr.parse::<SocketAddr>() {
+                Ok(addr) => Some(PeerId::from_addr(&addr)),
+                Err(e) => {
+                    warn!("Ignoring invalid protected peer {}: {}", addr, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            max_inbound: settings.max_inbound,
+            max_outbound: settings.max_outbound,
+            protected,
+            eviction_grace: Duration::from_secs(settings.eviction_grace),
+            latency_weight: settings.latency_weight,
+            usefulness_weight: settings.usefulness_weight,
+            duplicate_weight: settings.duplicate_weight,
+        }
+    }
+}
+
+/// 接続の受け入れの判定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    Accept,
+    /// 指定したピアを切断してから受け入れる
+    Evict(PeerId),
+    /// 接続数の上限に達している
+    Reject,
+    /// 発信するピアの分散の制約に違反する
+    Violates(DiversityViolation),
+}
+
+/// 接続中のピアの統計
+#[derive(Debug, Clone)]
+struct PeerStats {
+    direction: Direction,
+    connected_at: Instant,
+    /// 往復時間（ミリ秒）
+    latency_ms: Option<f64>,
+    /// 初めて届けたメッセージの数
+    useful: u64,
+    /// 既に受信していたメッセージの数
+    duplicates: u64,
+    /// ハンドシェイクで通知された情報（未完了や古いバージョンのピアは `None`）
+    handshake: Option<Handshake>,
+}
+
+/// 接続中のピアの概要
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PeerSummary {
+    pub address: String,
+    pub direction: Direction,
+    /// 役割（ハンドシェイクが済んでいない場合は `null`）
+    pub role: Option<NodeRole>,
+    pub capabilities: Vec<Capability>,
+    pub node_version: Option<String>,
+    /// 往復時間（ミリ秒）
+    pub latency_ms: Option<f64>,
+    pub protected: bool,
+}
+
+/// 接続中のピアの管理
+#[derive(Debug)]
+pub struct PeerManager {
+    limits: PeerLimits,
+    diversity: DiversityPolicy,
+    /// セントリーノードの背後のバリデーター（保護するが接続はしない）
+    private: HashSet<IpAddr>,
+    peers: HashMap<PeerId, PeerStats>,
+    seen: HashSet<String>,
+    seen_order: VecDeque<String>,
+}
+
+impl PeerManager {
+    pub fn new(limits: PeerLimits) -> Self {
+        Self {
+            limits,
+            diversity: DiversityPolicy::default(),
+            private: HashSet::new(),
+            peers: HashMap::new(),
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+        }
+    }
+
+    pub fn with_diversity(mut self, diversity: DiversityPolicy) -> Self {
+        self.diversity = diversity;
+        self
+    }
+
+    pub fn with_private(mut self, private: HashSet<IpAddr>) -> Self {
+        self.private = private;
+        self
+    }
+
+    /// 常に接続するピア
+    pub fn protected(&self) -> impl Iterator<Item = &PeerId> {
+        self.limits.protected.iter()
+    }
+
+    /// 保護するピアか（受信した接続は送信元のポートが変わるためIPアドレスで判定する）
+    pub fn is_protected(&self, peer: &PeerId) -> bool {
+        self.limits.protected.contains(peer)
+            || peer.ip().is_some_and(|ip| {
+                self.private.contains(&ip) || self.limits.protected.iter().any(|p| p.ip() == Some(ip))
+            })
+    }
+
+    /// 上限に数える接続の数
+    pub fn count(&self, direction: Direction) -> usize {
+        self.peers.iter()
+            .filter(|(peer, stats)| stats.direction == direction && !self.is_protected(peer))
+            .count()
+    }
+
+    /// 接続を受け入れるか判定し、受け入れる場合は登録する
+    ///
+    /// 保護するピアは常に受け入れます。受信の上限に達している場合は、接続してから
+    /// `eviction_grace` 以上経った保護されていない受信のピアのうち、評価の最も低いものを
+    /// 追い出します。発信の上限に達している場合や、発信のピアのサブネット・ASNが
+    /// 偏る場合は接続しません。
+    pub fn admit(&mut self, peer: &PeerId, direction: Direction, now: Instant) -> Admission {
+        if self.peers.contains_key(peer) {
+            return Admission::Accept;
+        }
+        let admission = if self.is_protected(peer) {
+            Admission::Accept
+        } else {
+            let limit = match direction {
+                Direction::Inbound => self.limits.max_inbound,
+                Direction::Outbound => self.limits.max_outbound,
+            };
+            if self.count(direction) < limit {
+                match (direction, self.diversity_violation(peer)) {
+                    (Direction::Outbound, Some(violation)) => Admission::Violates(violation),
+                    _ => Admission::Accept,
+                }
+            } else if direction == Direction::Inbound {
+                self.eviction_candidate(now).map_or(Admission::Reject, Admission::Evict)
+            } else {
+                Admission::Reject
+            }
+        };
+        if let Admission::Evict(evicted) = &admission {
+            self.peers.remove(evicted);
+        }
+        if matches!(admission, Admission::Accept | Admission::Evict(_)) {
+            self.peers.insert(peer.clone(), PeerStats {
+                direction,
+                connected_at: now,
+                latency_ms: None,
+                useful: 0,
+                duplicates: 0,
+                handshake: None,
+            });
+        }
+        admission
+    }
+
+    /// 発信の接続に `peer` を加えた場合に違反する分散の制約
+    fn diversity_violation(&self, peer: &PeerId) -> Option<DiversityViolation> {
+        let ip = peer.ip()?;
+        let outbound: Vec<_> = self.peers.iter()
+            .filter(|(peer, stats)| stats.direction == Direction::Outbound && !self.is_protected(peer))
+            .filter_map(|(peer, _)| peer.ip())
+            .collect();
+        self.diversity.check(ip, &outbound).err()
+    }
+
+    /// 切断したピアを削除
+    pub fn remove(&mut self, peer: &PeerId) {
+        self.peers.remove(peer);
+    }
+
+    /// 往復時間を記録
+    pub fn record_latency(&mut self, peer: &PeerId, rtt: Duration) {
+        if let Some(stats) = self.peers.get_mut(peer) {
+            stats.latency_ms = Some(rtt.as_secs_f64() * 1000.0);
+        }
+    }
+
+    /// ハンドシェイクで通知された役割と機能を記録
+    pub fn record_handshake(&mut self, peer: &PeerId, handshake: Handshake) {
+        if let Some(stats) = self.peers.get_mut(peer) {
+            stats.handshake = Some(handshake);
+        }
+    }
+
+    /// 指定した機能を持つピア
+    pub fn with_capability(&self, capability: Capability) -> Vec<PeerId> {
+        self.peers.iter()
+            .filter(|(_, stats)| stats.handshake.as_ref().is_some_and(|h| h.supports(capability)))
+            .map(|(peer, _)| peer.clone())
+            .collect()
+    }
+
+    /// 接続中のピアの概要（セントリーノードの背後のバリデーターは含めない）
+    pub fn summaries(&self) -> Vec<PeerSummary> {
+        let mut summaries: Vec<PeerSummary> = self.peers.iter()
+            .filter(|(peer, _)| !peer.ip().is_some_and(|ip| self.private.contains(&ip)))
+            .map(|(peer, stats)| PeerSummary {
+                address: peer.to_string(),
+                direction: stats.direction,
+                role: stats.handshake.as_ref().map(|h| h.role),
+                capabilities: stats.handshake.as_ref().map_or_else(Vec::new, |h| h.capabilities.clone()),
+                node_version: stats.handshake.as_ref().map(|h| h.node_version.clone()),
+                latency_ms: stats.latency_ms,
+                protected: self.is_protected(peer),
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.address.cmp(&b.address));
+        summaries
+    }
+
+    /// 受信したメッセージを記録し、初めて受信したものか返す
+    pub fn record_message(&mut self, peer: &PeerId, data: &[u8]) -> bool {
+        let hash = sha256_hex(data);
+        let new = self.seen.insert(hash.clone());
+        if new {
+            self.seen_order.push_back(hash);
+            if self.seen_order.len() > SEEN_CAPACITY {
+                if let Some(oldest) = self.seen_order.pop_front() {
+                    self.seen.remove(&oldest);
+                }
+            }
+        }
+        if let Some(stats) = self.peers.get_mut(peer) {
+            if new {
+                stats.useful += 1;
+            } else {
+                stats.duplicates += 1;
+            }
+        }
+        new
+    }
+
+    /// 追い出す受信のピア（評価の最も低いもの）
+    fn eviction_candidate(&self, now: Instant) -> Option<PeerId> {
+        let candidates: Vec<(&PeerId, &PeerStats)> = self.peers.iter()
+            .filter(|(peer, stats)| {
+                stats.direction == Direction::Inbound
+                    && !self.is_protected(peer)
+                    && now.saturating_duration_since(stats.connected_at) >= self.limits.eviction_grace
+            })
+            .collect();
+        // 有用性は候補の中で最も多く届けたピアを1とした相対値
+        let max_useful_rate = candidates.iter()
+            .map(|(_, stats)| useful_rate(stats, now))
+            .fold(0.0, f64::max);
+        candidates.into_iter()
+            .map(|(peer, stats)| (self.score(stats, now, max_useful_rate), peer))
+            .min_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.to_string().cmp(&b.1.to_string())))
+            .map(|(_, peer)| peer.clone())
+    }
+
+    /// ピアの評価（高いほど残す）
+    fn score(&self, stats: &PeerStats, now: Instant, max_useful_rate: f64) -> f64 {
+        let latency = stats.latency_ms
+            .map_or(0.5, |ms| 1.0 - (ms / MAX_SCORED_LATENCY_MS).min(1.0));
+        let usefulness = if max_useful_rate > 0.0 { useful_rate(stats, now) / max_useful_rate } else { 0.0 };
+        let received = stats.useful + stats.duplicates;
+        let duplicate_rate = if received == 0 { 0.0 } else { stats.duplicates as f64 / received as f64 };
+        self.limits.latency_weight * latency
+            + self.limits.usefulness_weight * usefulness
+            - self.limits.duplicate_weight * duplicate_rate
+    }
+}
+
+/// 接続していた1分あたりに初めて届けたメッセージの数
+fn useful_rate(stats: &PeerStats, now: Instant) -> f64 {
+    let minutes = now.saturating_duration_since(stats.connected_at).as_secs_f64() / 60.0;
+    stats.useful as f64 / minutes.max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(host: u8) -> PeerId {
+        PeerId::from_addr(&SocketAddr::from(([10, 0, 0, host], 4001)))
+    }
+
+    #[test]
+    fn test_evicts_the_worst_inbound_peer() {
+        let limits = PeerLimits {
+            max_inbound: 2,
+            max_outbound: 1,
+            protected: HashSet::from([peer(9)]),
+            ..Default::default()
+        };
+        let mut peers = PeerManager::new(limits);
+        let start = Instant::now();
+        assert_eq!(peers.admit(&peer(1), Direction::Inbound, start), Admission::Accept);
+        assert_eq!(peers.admit(&peer(2), Direction::Inbound, start), Admission::Accept);
+        // 接続して間もないピアは追い出さない
+        assert_eq!(peers.admit(&peer(3), Direction::Inbound, start), Admission::Reject);
+
+        // peer(1) は新しいメッセージを届け、peer(2) は重複ばかり送ってくる
+        peers.record_latency(&peer(1), Duration::from_millis(50));
+        peers.record_latency(&peer(2), Duration::from_millis(50));
+        assert!(peers.record_message(&peer(1), b"block 1"));
+        assert!(!peers.record_message(&peer(2), b"block 1"));
+        let later = start + Duration::from_secs(120);
+        assert_eq!(peers.admit(&peer(3), Direction::Inbound, later), Admission::Evict(peer(2)));
+        assert_eq!(peers.count(Direction::Inbound), 2);
+
+        // 保護するピアは上限に関係なく受け入れる。発信は上限を超えない
+        assert_eq!(peers.admit(&peer(9), Direction::Inbound, later), Admission::Accept);
+        assert_eq!(peers.admit(&peer(4), Direction::Outbound, later), Admission::Accept);
+        assert_eq!(peers.admit(&peer(5), Direction::Outbound, later), Admission::Reject);
+    }
+}
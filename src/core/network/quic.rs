@@ -1,12 +1,77 @@
 use anyhow::Result;
-use quinn::{Endpoint, ServerConfig, ClientConfig, Connection, TransportConfig};
+use quinn::{Endpoint, ServerConfig, ClientConfig, Connection, TransportConfig, VarInt};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex, RwLock, Semaphore};
 use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::time::Duration;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
-use tracing::{info, warn, error};
+use tracing::{debug, info, warn, error};
+use super::chaos::{Chaos, ChaosConfig};
+use super::gossip::{GossipConfig, MessageClass, Outbound, OutboundQueues, StakeTable};
+use super::diversity::DiversityPolicy;
+use super::peers::{Admission, Direction, PeerLimits, PeerManager, PeerSummary};
+use super::roles::{Capability, Handshake, NodeRole};
+use super::seeds::{PeeringConfig, RedialSchedule, SeedResolver, resolve_peer};
+use super::sentry::SentryConfig;
+
+/// 接続数の上限による切断を示すQUICのエラーコード
+const PEER_LIMIT_CLOSE_CODE: u32 = 0x10;
+/// 保護するピアへの再接続を確認する間隔
+const PIN_INTERVAL: Duration = Duration::from_secs(30);
+/// 中継待ちの合意形成のメッセージの数
+const RELAY_CAPACITY: usize = 1024;
+/// 固定ピアの再接続の予定を確認する間隔
+const REDIAL_TICK: Duration = Duration::from_secs(1);
+
+/// 受信したメッセージのハンドラー（応答のバイト列を返す）
+pub type MessageHandler = Arc<dyn Fn(PeerId, Message) -> Pin<Box<dyn Future<Output = Vec<u8>> + Send>> + Send + Sync>;
+
+/// 受信したメッセージの振り分け
+///
+/// 登録されたハンドラーに渡し（未登録の場合は空の応答を返す）、セントリーノードでは
+/// 初めて受信した合意形成のメッセージ（ビーコンチェーンのものを含む）を中継のキューにも追加します。
+#[derive(Clone)]
+struct Dispatcher {
+    handler: Arc<RwLock<Option<MessageHandler>>>,
+    relay: Option<mpsc::Sender<(PeerId, Message)>>,
+    /// ハンドシェイクで通知する自身の情報
+    local: Handshake,
+    /// 開発用のネットワーク障害の注入
+    chaos: Arc<Chaos>,
+}
+
+impl std::fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("relay", &self.relay.is_some())
+            .field("local", &self.local)
+            .field("chaos", &self.chaos.is_enabled())
+            .finish()
+    }
+}
+
+impl Dispatcher {
+    /// 自身のハンドシェイクのメッセージ
+    fn hello(&self) -> Result<Message> {
+        Ok(Message::Hello(serde_json::to_vec(&self.local)?))
+    }
+
+    async fn dispatch(&self, peer_id: PeerId, message: Message, new: bool) -> Vec<u8> {
+        if let (Some(relay), true, Message::Consensus(_) | Message::Beacon(_)) = (&self.relay, new, &message) {
+            if relay.try_send((peer_id.clone(), message.clone())).is_err() {
+                debug!("Dropped consensus message from {}: relay queue is full", peer_id);
+            }
+        }
+        let handler = self.handler.read().await.clone();
+        match handler {
+            Some(handler) => handler(peer_id, message).await,
+            None => vec![],
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
@@ -16,6 +81,26 @@ pub struct NetworkConfig {
     pub keep_alive_interval: Duration,
     pub handshake_timeout: Duration,
     pub idle_timeout: Duration,
+    /// ゴシップの優先制御
+    #[serde(skip)]
+    pub gossip: GossipConfig,
+    /// ピアの接続数の上限と追い出し
+    #[serde(skip)]
+    pub peers: PeerLimits,
+    /// 発信するピアのIPアドレスの分散
+    #[serde(skip)]
+    pub diversity: DiversityPolicy,
+    /// セントリーノード構成
+    #[serde(skip)]
+    pub sentry: SentryConfig,
+    /// DNSシードと固定ピア
+    #[serde(skip)]
+    pub peering: PeeringConfig,
+    /// 開発用のネットワーク障害の注入
+    #[serde(skip)]
+    pub chaos: ChaosConfig,
+    /// ハンドシェイクで通知する役割
+    pub role: NodeRole,
 }
 
 impl Default for NetworkConfig {
@@ -27,6 +112,13 @@ impl Default for NetworkConfig {
             keep_alive_interval: Duration::from_secs(10),
             handshake_timeout: Duration::from_secs(10),
             idle_timeout: Duration::from_secs(30),
+            gossip: GossipConfig::default(),
+            peers: PeerLimits::default(),
+            diversity: DiversityPolicy::default(),
+            sentry: SentryConfig::default(),
+            peering: PeeringConfig::default(),
+            chaos: ChaosConfig::default(),
+            role: NodeRole::default(),
         }
     }
 }
@@ -36,51 +128,73 @@ pub struct QuicNetwork {
     endpoint: Endpoint,
     connections: Arc<Mutex<HashMap<PeerId, Connection>>>,
     config: NetworkConfig,
+    /// バリデーターのピアのステーク量
+    stakes: Arc<RwLock<StakeTable>>,
+    /// 種類ごとの送信キュー
+    outbound: Arc<OutboundQueues>,
+    /// 接続数の上限と追い出し
+    peers: Arc<Mutex<PeerManager>>,
+    /// 受信したメッセージの振り分け
+    dispatcher: Dispatcher,
 }
 
 impl QuicNetwork {
     pub async fn new(config: NetworkConfig) -> Result<Self> {
         // QUICエンドポイントの設定
         let (endpoint, _server_cert) = Self::configure_endpoint(&config).await?;
+        let mut limits = config.peers.clone();
+        if !config.sentry.listens() {
+            // セントリーの背後のバリデーターはセントリーノードに常に接続する
+            limits.protected.extend(config.sentry.sentry_nodes.iter().map(PeerId::from_addr));
+        }
+        let (relay, relayed) = if config.sentry.relays_consensus() {
+            let (tx, rx) = mpsc::channel(RELAY_CAPACITY);
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
         
         let network = Self {
             endpoint,
             connections: Arc::new(Mutex::new(HashMap::new())),
+            stakes: Arc::new(RwLock::new(StakeTable::new(config.gossip.stakes.clone()))),
+            outbound: Arc::new(OutboundQueues::new(&config.gossip)),
+            peers: Arc::new(Mutex::new(
+                PeerManager::new(limits)
+                    .with_diversity(config.diversity.clone())
+                    .with_private(config.sentry.private_peers.clone()),
+            )),
+            dispatcher: Dispatcher {
+                handler: Arc::default(),
+                relay,
+                local: Handshake::local(config.role),
+                chaos: Arc::new(Chaos::new(config.chaos.clone())),
+            },
             config,
         };
         
-        // 受信ハンドラーの開始
+        if network.dispatcher.chaos.is_enabled() {
+            warn!("Injecting network chaos: {:?}", network.config.chaos);
+        }
+
+        // 受信ハンドラーと送信キューの処理、保護するピアへの接続の開始
         network.start_receiving().await?;
+        network.start_sending();
+        network.start_pinning();
+        if let Some(relayed) = relayed {
+            network.start_relaying(relayed);
+        }
         
         // ブートストラップノードへの接続
         network.connect_to_bootstrap_nodes().await?;
+        network.start_peering()?;
         
         Ok(network)
     }
 
-    /// ピアへの接続
+    /// ピアへの接続（発信の上限に達している場合はエラー）
     pub async fn connect(&self, peer_id: PeerId, addr: SocketAddr) -> Result<Connection> {
-        // 既存の接続をチェック
-        {
-            let connections = self.connections.lock().await;
-            if let Some(conn) = connections.get(&peer_id) {
-                if !conn.close_reason().is_some() {
-                    return Ok(conn.clone());
-                }
-            }
-        }
-
-        // 新しい接続を確立
-        let new_conn = self.endpoint.connect(addr, "rustorium")?
-            .await?;
-
-        // 接続を保存
-        {
-            let mut connections = self.connections.lock().await;
-            connections.insert(peer_id.clone(), new_conn.clone());
-        }
-
-        Ok(new_conn)
+        dial(&self.endpoint, &self.connections, &self.peers, &self.dispatcher, peer_id, addr).await
     }
 
     /// メッセージの送信
@@ -92,58 +206,277 @@ impl QuicNetwork {
                 .clone()
         };
 
-        // メッセージのシリアライズ
-        let data = bincode::serialize(&message)?;
+        send_on(&conn, &self.dispatcher.chaos, peer_id, &message).await.map(|_| ())
+    }
 
-        // 双方向ストリームを開く
-        let (mut send, mut recv) = conn.open_bi().await?;
+    /// メッセージを送信して応答を待つ（キューを経由しない）
+    pub async fn request(&self, peer_id: &PeerId, message: Message) -> Result<Vec<u8>> {
+        let conn = {
+            let connections = self.connections.lock().await;
+            connections.get(peer_id)
+                .ok_or_else(|| anyhow::anyhow!("No connection to peer"))?
+                .clone()
+        };
 
-        // データを送信
-        send.write_all(&data).await?;
-        send.finish().await?;
+        send_on(&conn, &self.dispatcher.chaos, peer_id, &message).await
+    }
 
-        // レスポンスを待機
-        let response = recv.read_to_end(1024 * 1024).await?;
+    /// 受信したメッセージのハンドラーを登録
+    pub async fn set_handler(&self, handler: MessageHandler) {
+        *self.dispatcher.handler.write().await = Some(handler);
+    }
 
-        Ok(())
+    /// メッセージをゴシップする（送信先を選んでキューに追加し、追加したピアの数を返す）
+    ///
+    /// 合意形成のメッセージは接続中のすべてのバリデーターへステーク量の順に送ります。
+    /// 送信は種類ごとのキューを経由するため、投票がブロックの転送の後ろで待たされません。
+    pub async fn gossip(&self, message: Message) -> usize {
+        let class = MessageClass::of(&message);
+        let peers = self.connected_peers().await;
+        let targets = self.stakes.read().await.select(class, &peers, self.config.gossip.fanout);
+        let mut queued = 0;
+        for peer in targets {
+            if self.outbound.push(Outbound { peer, class, message: message.clone() }).await {
+                queued += 1;
+            }
+        }
+        if queued == 0 && !peers.is_empty() {
+            debug!("Dropped {:?} gossip: queue is full", class);
+        }
+        queued
+    }
+
+    /// バリデーターのピアのステーク量を更新
+    pub async fn set_validator_stakes(&self, stakes: HashMap<PeerId, u64>) {
+        self.stakes.write().await.replace(stakes);
+    }
+
+    /// 送信キューの処理を開始
+    ///
+    /// 同時に送信中にできる数を制限し、空きができたら優先度の高いキューから取り出します。
+    fn start_sending(&self) {
+        let connections = self.connections.clone();
+        let outbound = self.outbound.clone();
+        let chaos = self.dispatcher.chaos.clone();
+        let in_flight = Arc::new(Semaphore::new(self.config.gossip.max_in_flight));
+
+        tokio::spawn(async move {
+            loop {
+                let Ok(permit) = in_flight.clone().acquire_owned().await else {
+                    break;
+                };
+                let next = outbound.pop().await;
+                let conn = connections.lock().await.get(&next.peer).cloned();
+                let Some(conn) = conn else {
+                    debug!("Dropped {:?} message for disconnected peer {}", next.class, next.peer);
+                    continue;
+                };
+                let chaos = chaos.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = send_on(&conn, &chaos, &next.peer, &next.message).await {
+                        warn!("Failed to send {:?} message to {}: {}", next.class, next.peer, e);
+                    }
+                    drop(permit);
+                });
+            }
+        });
+    }
+
+    /// 受信した合意形成のメッセージを送信元以外のピアへ中継する（セントリーノード）
+    ///
+    /// 背後のバリデーターには必ず送り、それ以外のピアは通常のゴシップと同じく選びます。
+    fn start_relaying(&self, mut relayed: mpsc::Receiver<(PeerId, Message)>) {
+        let connections = self.connections.clone();
+        let stakes = self.stakes.clone();
+        let outbound = self.outbound.clone();
+        let sentry = self.config.sentry.clone();
+        let fanout = self.config.gossip.fanout;
+
+        tokio::spawn(async move {
+            while let Some((source, message)) = relayed.recv().await {
+                let class = MessageClass::of(&message);
+                let peers: Vec<PeerId> = connections.lock().await.keys()
+                    .filter(|peer| **peer != source)
+                    .cloned()
+                    .collect();
+                let (private, public): (Vec<PeerId>, Vec<PeerId>) = peers.into_iter()
+                    .partition(|peer| sentry.is_private(peer));
+                let mut targets = stakes.read().await.select(class, &public, fanout);
+                targets.extend(private);
+                for peer in targets {
+                    outbound.push(Outbound { peer, class, message: message.clone() }).await;
+                }
+            }
+        });
     }
 
     /// メッセージの受信ハンドラーを開始
+    ///
+    /// 受信の上限に達している場合は評価の最も低いピアを追い出すか、新しい接続を切断します。
     pub async fn start_receiving(&self) -> Result<()> {
         let endpoint = self.endpoint.clone();
         let connections = self.connections.clone();
+        let peers = self.peers.clone();
+        let dispatcher = self.dispatcher.clone();
 
         tokio::spawn(async move {
-            while let Some(conn) = endpoint.accept().await {
-                let conn = conn.await.expect("Connection failed");
+            while let Some(connecting) = endpoint.accept().await {
+                let conn = match connecting.await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        debug!("Inbound handshake failed: {}", e);
+                        continue;
+                    }
+                };
                 let peer_id = PeerId::from_connection(&conn);
-                
-                // 接続を保存
-                {
+
+                let admission = {
                     let mut conns = connections.lock().await;
-                    conns.insert(peer_id.clone(), conn.clone());
+                    let mut peers = peers.lock().await;
+                    // 追い出すピアの評価に最新の往復時間を使う
+                    for (id, c) in conns.iter() {
+                        peers.record_latency(id, c.rtt());
+                    }
+                    let admission = peers.admit(&peer_id, Direction::Inbound, Instant::now());
+                    if matches!(admission, Admission::Accept | Admission::Evict(_)) {
+                        // 接続を保存
+                        conns.insert(peer_id.clone(), conn.clone());
+                    }
+                    if let Admission::Evict(evicted) = &admission {
+                        if let Some(old) = conns.remove(evicted) {
+                            old.close(VarInt::from_u32(PEER_LIMIT_CLOSE_CODE), b"evicted");
+                        }
+                    }
+                    admission
+                };
+                match admission {
+                    Admission::Reject | Admission::Violates(_) => {
+                        debug!("Rejected inbound peer {}: peer limit reached", peer_id);
+                        conn.close(VarInt::from_u32(PEER_LIMIT_CLOSE_CODE), b"too many peers");
+                        continue;
+                    }
+                    Admission::Evict(evicted) => info!("Evicted peer {} to accept {}", evicted, peer_id),
+                    Admission::Accept => {}
                 }
 
                 // 接続ごとのハンドラーを起動
-                tokio::spawn(handle_connection(conn, peer_id));
+                tokio::spawn(handle_connection(conn, peer_id, connections.clone(), peers.clone(), dispatcher.clone()));
             }
         });
 
         Ok(())
     }
 
-    /// ブートストラップノードへの接続
+    /// 保護するピアに接続し、切断されたら再接続し続ける
+    fn start_pinning(&self) {
+        let endpoint = self.endpoint.clone();
+        let connections = self.connections.clone();
+        let peers = self.peers.clone();
+        let dispatcher = self.dispatcher.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(PIN_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let protected: Vec<PeerId> = peers.lock().await.protected().cloned().collect();
+                for peer_id in protected {
+                    let Some(addr) = peer_id.addr() else {
+                        continue;
+                    };
+                    if let Err(e) = dial(&endpoint, &connections, &peers, &dispatcher, peer_id.clone(), addr).await {
+                        debug!("Failed to connect to protected peer {}: {}", peer_id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// ブートストラップノード（セントリーの背後のバリデーターはセントリーノード）への接続
     async fn connect_to_bootstrap_nodes(&self) -> Result<()> {
-        for node in &self.config.bootstrap_nodes {
-            let addr = node.parse()?;
+        for addr in self.config.sentry.dial_targets(&self.config.bootstrap_nodes)? {
             let peer_id = PeerId::from_addr(&addr);
             if let Err(e) = self.connect(peer_id, addr).await {
-                warn!("Failed to connect to bootstrap node {}: {}", node, e);
+                warn!("Failed to connect to {}: {}", addr, e);
             }
         }
         Ok(())
     }
 
+    /// DNSシードから見つけたピアと固定ピアに接続し、固定ピアが切断されたら間隔を空けて再接続する
+    ///
+    /// セントリーの背後のバリデーターはセントリーノードにだけ接続するため何もしません。
+    fn start_peering(&self) -> Result<()> {
+        let peering = self.config.peering.clone();
+        if peering.is_empty() || !self.config.sentry.listens() {
+            return Ok(());
+        }
+        let seeds = if peering.dns_seeds.is_empty() {
+            None
+        } else {
+            Some(SeedResolver::from_system_conf(peering.default_port)?)
+        };
+        let endpoint = self.endpoint.clone();
+        let connections = self.connections.clone();
+        let peers = self.peers.clone();
+        let dispatcher = self.dispatcher.clone();
+
+        tokio::spawn(async move {
+            let mut schedule = RedialSchedule::new(&peering.persistent_peers, peering.backoff, Instant::now());
+            let mut seeded_at: Option<Instant> = None;
+            let mut ticker = tokio::time::interval(REDIAL_TICK);
+            loop {
+                ticker.tick().await;
+
+                // DNSシードは起動時と、ピアがいなくなった場合に引き直す
+                if let Some(seeds) = &seeds {
+                    let refresh = match seeded_at {
+                        None => true,
+                        Some(at) => at.elapsed() >= peering.seed_refresh && connections.lock().await.is_empty(),
+                    };
+                    if refresh {
+                        seeded_at = Some(Instant::now());
+                        for addr in seeds.resolve_all(&peering.dns_seeds).await {
+                            let peer_id = PeerId::from_addr(&addr);
+                            if let Err(e) = dial(&endpoint, &connections, &peers, &dispatcher, peer_id, addr).await {
+                                debug!("Failed to connect to seed peer {}: {}", addr, e);
+                            }
+                        }
+                    }
+                }
+
+                let now = Instant::now();
+                for (entry, last) in schedule.due(now) {
+                    if let Some(peer_id) = last {
+                        let alive = connections.lock().await.get(&peer_id)
+                            .is_some_and(|conn| conn.close_reason().is_none());
+                        if alive {
+                            schedule.connected(&entry, peer_id, now);
+                            continue;
+                        }
+                    }
+                    let dialed = async {
+                        // ホスト名は再接続のたびに解決し直す
+                        let addr = resolve_peer(&entry).await?[0];
+                        let peer_id = PeerId::from_addr(&addr);
+                        dial(&endpoint, &connections, &peers, &dispatcher, peer_id.clone(), addr).await?;
+                        Ok::<_, anyhow::Error>(peer_id)
+                    };
+                    match dialed.await {
+                        Ok(peer_id) => {
+                            info!("Connected to persistent peer {} ({})", entry, peer_id);
+                            schedule.connected(&entry, peer_id, Instant::now());
+                        }
+                        Err(e) => {
+                            let delay = schedule.failed(&entry, Instant::now());
+                            debug!("Failed to connect to persistent peer {}: {} (retrying in {:?})", entry, e, delay);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
     /// エンドポイントの設定
     async fn configure_endpoint(config: &NetworkConfig) -> Result<(Endpoint, Vec<u8>)> {
         // 証明書の生成
@@ -173,7 +506,12 @@ impl QuicNetwork {
         ));
 
         // エンドポイントの作成（動的ポート割り当て）
-        let mut endpoint = Endpoint::server(server_config, "0.0.0.0:0".parse()?)?;
+        // セントリーの背後のバリデーターは接続を待ち受けない
+        let mut endpoint = if config.sentry.listens() {
+            Endpoint::server(server_config, "0.0.0.0:0".parse()?)?
+        } else {
+            Endpoint::client("0.0.0.0:0".parse()?)?
+        };
         endpoint.set_default_client_config(client_config);
 
         Ok((endpoint, cert_der))
@@ -189,6 +527,16 @@ impl QuicNetwork {
         self.connections.lock().await.keys().cloned().collect()
     }
 
+    /// 指定した機能を持つ接続中のピア（例: ライトクライアントが履歴を問い合わせるピア）
+    pub async fn peers_with(&self, capability: Capability) -> Vec<PeerId> {
+        self.peers.lock().await.with_capability(capability)
+    }
+
+    /// 接続中のピアの役割と状態
+    pub async fn peer_summaries(&self) -> Vec<PeerSummary> {
+        self.peers.lock().await.summaries()
+    }
+
     /// ネットワーク統計を取得
     pub async fn get_stats(&self) -> NetworkStats {
         let connections = self.connections.lock().await;
@@ -201,40 +549,157 @@ impl QuicNetwork {
     }
 }
 
+/// ピアに接続して保存する（接続済みの場合はその接続を返す）
+async fn dial(
+    endpoint: &Endpoint,
+    connections: &Arc<Mutex<HashMap<PeerId, Connection>>>,
+    peers: &Arc<Mutex<PeerManager>>,
+    dispatcher: &Dispatcher,
+    peer_id: PeerId,
+    addr: SocketAddr,
+) -> Result<Connection> {
+    // 既存の接続をチェック
+    {
+        let connections = connections.lock().await;
+        if let Some(conn) = connections.get(&peer_id) {
+            if conn.close_reason().is_none() {
+                return Ok(conn.clone());
+            }
+        }
+    }
+
+    match peers.lock().await.admit(&peer_id, Direction::Outbound, Instant::now()) {
+        Admission::Reject => anyhow::bail!("Outbound peer limit reached"),
+        Admission::Violates(violation) => anyhow::bail!("Skipping {}: {}", peer_id, violation),
+        Admission::Accept | Admission::Evict(_) => {}
+    }
+
+    // 新しい接続を確立
+    let new_conn = match endpoint.connect(addr, "rustorium")?.await {
+        Ok(conn) => conn,
+        Err(e) => {
+            peers.lock().await.remove(&peer_id);
+            return Err(e.into());
+        }
+    };
+
+    // 接続を保存
+    {
+        let mut connections = connections.lock().await;
+        connections.insert(peer_id.clone(), new_conn.clone());
+    }
+    tokio::spawn(handle_connection(new_conn.clone(), peer_id.clone(), connections.clone(), peers.clone(), dispatcher.clone()));
+
+    // 役割と機能を交換する（応答しない古いバージョンのピアとも接続は続ける）
+    let hello = async {
+        let response = send_on(&new_conn, &dispatcher.chaos, &peer_id, &dispatcher.hello()?).await?;
+        match Message::decode(&response)? {
+            Message::Hello(payload) => Ok(serde_json::from_slice::<Handshake>(&payload)?),
+            _ => Err(anyhow::anyhow!("unexpected handshake response")),
+        }
+    };
+    match hello.await {
+        Ok(handshake) => peers.lock().await.record_handshake(&peer_id, handshake),
+        Err(e) => debug!("Handshake with {} failed: {}", peer_id, e),
+    }
+
+    Ok(new_conn)
+}
+
+/// メッセージを新しいストリームで送信して応答を返す（種類に応じたストリームの優先度を付ける）
+async fn send_on(conn: &Connection, chaos: &Chaos, peer_id: &PeerId, message: &Message) -> Result<Vec<u8>> {
+    // メッセージのシリアライズ
+    let data = message.encode()?;
+    chaos.before_send(peer_id, data.len()).await?;
+
+    // 双方向ストリームを開く
+    let (mut send, mut recv) = conn.open_bi().await?;
+    send.set_priority(MessageClass::of(message).stream_priority())?;
+
+    // データを送信
+    send.write_all(&data).await?;
+    send.finish().await?;
+
+    // レスポンスを待機
+    let response = recv.read_to_end(MAX_MESSAGE_SIZE).await?;
+
+    Ok(response)
+}
+
 /// 接続ハンドラー
-async fn handle_connection(conn: Connection, peer_id: PeerId) {
-    while let Ok((mut send, mut recv)) = conn.accept_bi().await {
-        // データの受信
-        let mut data = Vec::new();
-        if let Err(e) = recv.read_to_end(1024 * 1024).await.map(|d| data = d) {
+///
+/// ストリームごとに並行して処理し、大きなブロックの受信中でも投票を受け取れるようにします。
+async fn handle_connection(
+    conn: Connection,
+    peer_id: PeerId,
+    connections: Arc<Mutex<HashMap<PeerId, Connection>>>,
+    peers: Arc<Mutex<PeerManager>>,
+    dispatcher: Dispatcher,
+) {
+    while let Ok((send, recv)) = conn.accept_bi().await {
+        tokio::spawn(handle_stream(send, recv, peer_id.clone(), peers.clone(), dispatcher.clone()));
+    }
+
+    // 同じピアの新しい接続に置き換わっていなければ登録を削除する
+    let mut conns = connections.lock().await;
+    if conns.get(&peer_id).is_some_and(|c| c.stable_id() == conn.stable_id()) {
+        conns.remove(&peer_id);
+        peers.lock().await.remove(&peer_id);
+    }
+    debug!("Connection to {} closed", peer_id);
+}
+
+async fn handle_stream(
+    mut send: quinn::SendStream,
+    mut recv: quinn::RecvStream,
+    peer_id: PeerId,
+    peers: Arc<Mutex<PeerManager>>,
+    dispatcher: Dispatcher,
+) {
+    // データの受信
+    let data = match recv.read_to_end(MAX_MESSAGE_SIZE).await {
+        Ok(data) => data,
+        Err(e) => {
             error!("Failed to read from stream: {}", e);
-            continue;
+            return;
         }
+    };
+    if dispatcher.chaos.partitioned(&peer_id) {
+        debug!("Dropped message from {}: partitioned by chaos", peer_id);
+        return;
+    }
 
-        // メッセージの処理
-        match bincode::deserialize::<Message>(&data) {
-            Ok(message) => {
-                // TODO: メッセージの実際の処理
-                let response = handle_message(message).await;
-                
-                // レスポンスの送信
-                if let Err(e) = send.write_all(&response).await {
-                    error!("Failed to send response: {}", e);
+    // メッセージの処理
+    match Message::decode(&data) {
+        Ok(message) => {
+            // 重複の割合をピアの評価に使う
+            let new = peers.lock().await.record_message(&peer_id, &data);
+            // 応答も受信したメッセージと同じ優先度で返す
+            let _ = send.set_priority(MessageClass::of(&message).stream_priority());
+            let response = match message {
+                // 受信した接続のハンドシェイクには自身の情報で応答する
+                Message::Hello(payload) => {
+                    match serde_json::from_slice::<Handshake>(&payload) {
+                        Ok(handshake) => peers.lock().await.record_handshake(&peer_id, handshake),
+                        Err(e) => debug!("Malformed handshake from {}: {}", peer_id, e),
+                    }
+                    dispatcher.hello().and_then(|hello| hello.encode()).unwrap_or_default()
                 }
+                message => dispatcher.dispatch(peer_id, message, new).await,
+            };
+
+            // レスポンスの送信
+            if let Err(e) = send.write_all(&response).await {
+                error!("Failed to send response: {}", e);
             }
-            Err(e) => {
-                error!("Failed to deserialize message: {}", e);
-            }
+            let _ = send.finish().await;
+        }
+        Err(e) => {
+            error!("Failed to deserialize message: {}", e);
         }
     }
 }
 
-/// メッセージ処理
-async fn handle_message(message: Message) -> Vec<u8> {
-    // TODO: 実際のメッセージ処理ロジック
-    vec![]
-}
-
 // 証明書検証をスキップするための実装
 struct SkipServerVerification;
 
@@ -256,22 +721,83 @@ impl rustls::client::ServerCertVerifier for SkipServerVerification {
 pub struct PeerId(String);
 
 impl PeerId {
-    pub fn from_connection(_conn: &Connection) -> Self {
-        // TODO: 実際のピアID生成ロジック
-        Self("dummy".into())
+    pub fn from_connection(conn: &Connection) -> Self {
+        Self::from_addr(&conn.remote_address())
     }
 
     pub fn from_addr(addr: &SocketAddr) -> Self {
         Self(addr.to_string())
     }
+
+    /// ピアのアドレス
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.0.parse().ok()
+    }
+
+    /// ピアのIPアドレス
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.addr().map(|addr| addr.ip())
+    }
+}
+
+impl std::fmt::Display for PeerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
+/// 受信メッセージの最大サイズ（バイト）
+pub const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum Message {
     Transaction(Vec<u8>),
     Block(Vec<u8>),
     Consensus(Vec<u8>),
     Heartbeat,
+    /// ヘッダーとトランザクションの短いID（`block::compact::CompactBlock`）
+    CompactBlock(Vec<u8>),
+    /// コンパクトブロックの復元に足りないトランザクションの要求（`BlockTxnRequest`）
+    GetBlockTxn(Vec<u8>),
+    /// 要求されたトランザクション（`BlockTxn`）
+    BlockTxn(Vec<u8>),
+    /// 接続の直後に交換する役割と機能（`roles::Handshake`）
+    Hello(Vec<u8>),
+    /// ビーコンチェーンの合意形成のメッセージ（`coordination::BeaconMessage`、JSON）
+    Beacon(Vec<u8>),
+}
+
+impl Message {
+    /// ワイヤー上のバイト列からメッセージをデコード
+    ///
+    /// 入力は信頼できないため、サイズ上限を超える長さプレフィックスや
+    /// 末尾の余分なバイトはすべてエラーとして扱います。
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        use bincode::Options;
+
+        if bytes.len() > MAX_MESSAGE_SIZE {
+            anyhow::bail!("Message too large: {} bytes", bytes.len());
+        }
+
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(MAX_MESSAGE_SIZE as u64)
+            .reject_trailing_bytes()
+            .deserialize(bytes)
+            .map_err(|e| anyhow::anyhow!("Malformed message: {}", e))
+    }
+
+    /// メッセージをワイヤーフォーマットにエンコード
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        use bincode::Options;
+
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(MAX_MESSAGE_SIZE as u64)
+            .serialize(self)
+            .map_err(|e| anyhow::anyhow!("Failed to encode message: {}", e))
+    }
 }
 
 #[derive(Debug)]
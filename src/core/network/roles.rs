@@ -0,0 +1,134 @@
+//! ノードの役割と機能の通知
+//!
+//! 接続の直後にハンドシェイクで自身の役割と提供できる機能を交換し、ピアごとに記録します。
+//! ライトクライアントが履歴を持つピアだけに問い合わせる場合など、必要な機能でピアを選べます。
+
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+
+/// ハンドシェイクのプロトコルのバージョン
+pub const HANDSHAKE_VERSION: u32 = 1;
+
+/// ノードの役割
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    /// ブロックを提案・投票する
+    Validator,
+    /// 全ブロックを検証し、直近の状態を持つ
+    #[default]
+    Full,
+    /// ヘッダーだけを検証する
+    Light,
+    /// 全履歴の状態を持つ
+    Archive,
+    /// 読み取り専用のRPCを提供する（`rpc-replica`）
+    Rpc,
+}
+
+/// ピアが提供できる機能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// 合意形成に参加する
+    Consensus,
+    /// 直近のブロックとトランザクションを提供する
+    RecentBlocks,
+    /// ジェネシスからの全履歴を提供する
+    FullHistory,
+    /// 読み取りのRPCを提供する
+    Rpc,
+}
+
+impl NodeRole {
+    pub const ALL: [NodeRole; 5] = [Self::Validator, Self::Full, Self::Light, Self::Archive, Self::Rpc];
+
+    /// 設定の `node.role` から判定（未知の値と `auto` はフルノード）
+    pub fn from_config(role: &str) -> Self {
+        match role {
+            "validator" => Self::Validator,
+            "light" => Self::Light,
+            "archive" => Self::Archive,
+            "rpc" | crate::config::ROLE_RPC_REPLICA => Self::Rpc,
+            _ => Self::Full,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Validator => "validator",
+            Self::Full => "full",
+            Self::Light => "light",
+            Self::Archive => "archive",
+            Self::Rpc => "rpc",
+        }
+    }
+
+    /// 役割が提供する機能
+    pub fn capabilities(self) -> Vec<Capability> {
+        match self {
+            Self::Validator => vec![Capability::Consensus, Capability::RecentBlocks],
+            Self::Full => vec![Capability::RecentBlocks],
+            Self::Light => vec![],
+            Self::Archive => vec![Capability::RecentBlocks, Capability::FullHistory],
+            Self::Rpc => vec![Capability::RecentBlocks, Capability::Rpc],
+        }
+    }
+}
+
+impl std::fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 接続の直後に交換する情報（`Message::Hello` のペイロード、JSON）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Handshake {
+    pub protocol_version: u32,
+    /// ノードのソフトウェアのバージョン
+    pub node_version: String,
+    pub role: NodeRole,
+    /// 提供できる機能（新しいバージョンの未知の機能は読み飛ばす）
+    #[serde(default, deserialize_with = "known_capabilities")]
+    pub capabilities: Vec<Capability>,
+}
+
+impl Handshake {
+    pub fn local(role: NodeRole) -> Self {
+        Self {
+            protocol_version: HANDSHAKE_VERSION,
+            node_version: env!("CARGO_PKG_VERSION").to_string(),
+            role,
+            capabilities: role.capabilities(),
+        }
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+fn known_capabilities<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Capability>, D::Error> {
+    let values: Vec<serde_json::Value> = Vec::deserialize(deserializer)?;
+    Ok(values.into_iter().filter_map(|value| serde_json::from_value(value).ok()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_ignores_unknown_capabilities() {
+        let handshake: Handshake = serde_json::from_str(
+            r#"{"protocol_version":2,"node_version":"9.9.9","role":"archive","capabilities":["full_history","state_sync"]}"#,
+        ).unwrap();
+        assert_eq!(handshake.role, NodeRole::Archive);
+        assert!(handshake.supports(Capability::FullHistory));
+        assert!(!handshake.supports(Capability::RecentBlocks));
+
+        assert_eq!(NodeRole::from_config("rpc-replica"), NodeRole::Rpc);
+        assert_eq!(NodeRole::from_config("auto"), NodeRole::Full);
+        assert!(Handshake::local(NodeRole::Validator).supports(Capability::Consensus));
+    }
+}
@@ -0,0 +1,309 @@
+//! DNSシードと固定ピア
+//!
+//! プライベートネットワークでも最初のピアを見つけられるよう、DNSのSRV・TXTレコードから
+//! ピアのアドレスを取得します。固定ピア（`persistent_peers`）には常に接続し、接続に失敗したり
+//! 切断されたりした場合は、指数的に間隔を空けて再接続します。
+//! 主な機能：
+//! - `_service._proto.domain` 形式の名前はSRVレコード、それ以外はTXTレコード（なければA・AAAAレコード）で解決
+//! - ホスト名の固定ピアは再接続のたびに解決し直す（IPアドレスの変更に追従）
+//! - 再接続の間隔の指数バックオフ（上限とジッターあり）
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use hickory_resolver::TokioAsyncResolver;
+use rand::Rng;
+use tracing::debug;
+use crate::config::PeeringSettings;
+use super::quic::PeerId;
+
+/// 接続中の固定ピアの切断を確認する間隔
+const CONNECTED_CHECK: Duration = Duration::from_secs(5);
+
+/// 再接続の間隔
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    /// `failures` 回続けて失敗した後の待ち時間（ジッターなし）
+    pub fn delay(&self, failures: u32) -> Duration {
+        let factor = 1u32.checked_shl(failures.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.min.saturating_mul(factor).min(self.max)
+    }
+
+    /// 同時に再起動したノードが一斉に接続しないよう、最大20%のジッターを加える
+    fn jittered(&self, failures: u32) -> Duration {
+        let delay = self.delay(failures);
+        delay + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.2))
+    }
+}
+
+/// DNSシードと固定ピアの設定
+#[derive(Debug, Clone)]
+pub struct PeeringConfig {
+    pub dns_seeds: Vec<String>,
+    pub persistent_peers: Vec<String>,
+    /// DNSシードのレコードにポートがない場合のポート
+    pub default_port: u16,
+    pub backoff: Backoff,
+    /// 接続中のピアがいない場合にDNSシードを引き直す間隔
+    pub seed_refresh: Duration,
+}
+
+impl Default for PeeringConfig {
+    fn default() -> Self {
+        Self::from_settings(&PeeringSettings::default(), 9070).unwrap()
+    }
+}
+
+impl PeeringConfig {
+    /// 設定から作成（`default_port` は自身のP2Pのポート）
+    pub fn from_settings(settings: &PeeringSettings, default_port: u16) -> Result<Self> {
+        for peer in &settings.persistent_peers {
+            if !has_port(peer) {
+                return Err(anyhow!("Invalid persistent peer {}: expected host:port", peer));
+            }
+        }
+        if settings.redial_min_backoff == 0 || settings.redial_min_backoff > settings.redial_max_backoff {
+            return Err(anyhow!(
+                "network.peering.redial_min_backoff must be between 1 and redial_max_backoff ({})",
+                settings.redial_max_backoff
+            ));
+        }
+        Ok(Self {
+            dns_seeds: settings.dns_seeds.clone(),
+            persistent_peers: settings.persistent_peers.clone(),
+            default_port,
+            backoff: Backoff {
+                min: Duration::from_secs(settings.redial_min_backoff),
+                max: Duration::from_secs(settings.redial_max_backoff),
+            },
+            seed_refresh: Duration::from_secs(settings.seed_refresh_interval),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dns_seeds.is_empty() && self.persistent_peers.is_empty()
+    }
+}
+
+/// `host:port` の形か（IPv6は `[addr]:port`）
+fn has_port(entry: &str) -> bool {
+    if entry.parse::<SocketAddr>().is_ok() {
+        return true;
+    }
+    match entry.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && !host.contains(':') && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+/// ポートのないアドレスに既定のポートを補う
+fn with_port(entry: &str, default_port: u16) -> String {
+    if has_port(entry) {
+        return entry.to_string();
+    }
+    match entry.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, default_port).to_string(),
+        Err(_) => format!("{}:{}", entry, default_port),
+    }
+}
+
+/// TXTレコードの値を `host:port` に分割（空白・カンマ区切り、ポートがなければ既定のポート）
+fn parse_txt(data: &str, default_port: u16) -> Vec<String> {
+    data.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| with_port(entry, default_port))
+        .collect()
+}
+
+/// `host:port` を解決（ホスト名はシステムのリゾルバーで引く）
+pub async fn resolve_peer(entry: &str) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host(entry).await?.collect();
+    if addrs.is_empty() {
+        return Err(anyhow!("{} resolved to no addresses", entry));
+    }
+    Ok(addrs)
+}
+
+/// DNSシードの解決
+pub struct SeedResolver {
+    resolver: TokioAsyncResolver,
+    default_port: u16,
+}
+
+impl std::fmt::Debug for SeedResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SeedResolver")
+            .field("default_port", &self.default_port)
+            .finish()
+    }
+}
+
+impl SeedResolver {
+    /// システムのDNS設定（`/etc/resolv.conf`）を使う
+    pub fn from_system_conf(default_port: u16) -> Result<Self> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| anyhow!("Failed to read the system DNS configuration: {}", e))?;
+        Ok(Self { resolver, default_port })
+    }
+
+    /// すべてのDNSシードを解決（解決できないシードは読み飛ばす）
+    pub async fn resolve_all(&self, seeds: &[String]) -> Vec<SocketAddr> {
+        let mut addrs = Vec::new();
+        for seed in seeds {
+            match self.resolve(seed).await {
+                Ok(found) => {
+                    debug!("DNS seed {} returned {} peers", seed, found.len());
+                    addrs.extend(found);
+                }
+                Err(e) => debug!("Failed to resolve DNS seed {}: {}", seed, e),
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        addrs.retain(|addr| seen.insert(*addr));
+        addrs
+    }
+
+    /// DNSシードを解決
+    pub async fn resolve(&self, seed: &str) -> Result<Vec<SocketAddr>> {
+        if seed.starts_with('_') {
+            return self.resolve_srv(seed).await;
+        }
+        match self.resolve_txt(seed).await {
+            Ok(addrs) if !addrs.is_empty() => Ok(addrs),
+            _ => {
+                let ips = self.resolver.lookup_ip(seed).await?;
+                Ok(ips.iter().map(|ip| SocketAddr::new(ip, self.default_port)).collect())
+            }
+        }
+    }
+
+    /// SRVレコード（優先度の高い順、同じ優先度では重みの大きい順）
+    async fn resolve_srv(&self, seed: &str) -> Result<Vec<SocketAddr>> {
+        let lookup = self.resolver.srv_lookup(seed).await?;
+        let mut records: Vec<_> = lookup.iter().collect();
+        records.sort_by_key(|srv| (srv.priority(), std::cmp::Reverse(srv.weight())));
+
+        let mut addrs = Vec::new();
+        for srv in records {
+            match self.resolver.lookup_ip(srv.target().clone()).await {
+                Ok(ips) => addrs.extend(ips.iter().map(|ip| SocketAddr::new(ip, srv.port()))),
+                Err(e) => debug!("Failed to resolve SRV target {}: {}", srv.target(), e),
+            }
+        }
+        Ok(addrs)
+    }
+
+    /// TXTレコード（値は `host:port` の空白・カンマ区切り）
+    async fn resolve_txt(&self, seed: &str) -> Result<Vec<SocketAddr>> {
+        let lookup = self.resolver.txt_lookup(seed).await?;
+        let mut addrs = Vec::new();
+        for record in lookup.iter() {
+            for data in record.txt_data() {
+                for entry in parse_txt(&String::from_utf8_lossy(data), self.default_port) {
+                    match resolve_peer(&entry).await {
+                        Ok(found) => addrs.extend(found),
+                        Err(e) => debug!("Skipping TXT entry {} of {}: {}", entry, seed, e),
+                    }
+                }
+            }
+        }
+        Ok(addrs)
+    }
+}
+
+/// 固定ピアの接続の状態
+#[derive(Debug)]
+struct PersistentPeer {
+    entry: String,
+    /// 最後に接続したアドレス
+    peer: Option<PeerId>,
+    /// 続けて失敗した回数
+    failures: u32,
+    next_attempt: Instant,
+}
+
+/// 固定ピアへの再接続の予定
+#[derive(Debug)]
+pub struct RedialSchedule {
+    peers: Vec<PersistentPeer>,
+    backoff: Backoff,
+}
+
+impl RedialSchedule {
+    /// すべての固定ピアにすぐ接続する予定を作成
+    pub fn new(entries: &[String], backoff: Backoff, now: Instant) -> Self {
+        let peers = entries.iter()
+            .map(|entry| PersistentPeer { entry: entry.clone(), peer: None, failures: 0, next_attempt: now })
+            .collect();
+        Self { peers, backoff }
+    }
+
+    /// 確認する時刻になった固定ピアと、最後に接続したアドレス
+    pub fn due(&self, now: Instant) -> Vec<(String, Option<PeerId>)> {
+        self.peers.iter()
+            .filter(|peer| peer.next_attempt <= now)
+            .map(|peer| (peer.entry.clone(), peer.peer.clone()))
+            .collect()
+    }
+
+    /// 接続できた（失敗の回数を戻し、しばらくしてから切断を確認する）
+    pub fn connected(&mut self, entry: &str, peer_id: PeerId, now: Instant) {
+        if let Some(peer) = self.peers.iter_mut().find(|peer| peer.entry == entry) {
+            peer.peer = Some(peer_id);
+            peer.failures = 0;
+            peer.next_attempt = now + CONNECTED_CHECK;
+        }
+    }
+
+    /// 接続に失敗した（次に試すまでの待ち時間を返す）
+    pub fn failed(&mut self, entry: &str, now: Instant) -> Duration {
+        let Some(peer) = self.peers.iter_mut().find(|peer| peer.entry == entry) else {
+            return Duration::ZERO;
+        };
+        peer.peer = None;
+        peer.failures = peer.failures.saturating_add(1);
+        let delay = self.backoff.jittered(peer.failures);
+        peer.next_attempt = now + delay;
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_txt_and_backoff() {
+        assert_eq!(
+            parse_txt("10.0.0.1:4001, seed.example.org  10.0.0.2 [2001:db8::1]:4002 2001:db8::2", 9070),
+            vec!["10.0.0.1:4001", "seed.example.org:9070", "10.0.0.2:9070", "[2001:db8::1]:4002", "[2001:db8::2]:9070"]
+        );
+        assert!(PeeringConfig::from_settings(&PeeringSettings {
+            persistent_peers: vec!["node1.internal".to_string()],
+            ..Default::default()
+        }, 9070).is_err());
+
+        let backoff = Backoff { min: Duration::from_secs(1), max: Duration::from_secs(60) };
+        assert_eq!(backoff.delay(1), Duration::from_secs(1));
+        assert_eq!(backoff.delay(4), Duration::from_secs(8));
+        assert_eq!(backoff.delay(40), Duration::from_secs(60));
+
+        let now = Instant::now();
+        let mut schedule = RedialSchedule::new(&["node1.internal:4001".to_string()], backoff, now);
+        assert_eq!(schedule.due(now).len(), 1);
+        let delay = schedule.failed("node1.internal:4001", now);
+        assert!(delay >= Duration::from_secs(1) && delay < Duration::from_millis(1200));
+        assert!(schedule.due(now).is_empty());
+        assert!(schedule.due(now + delay).len() == 1);
+
+        let peer_id = PeerId::from_addr(&"10.0.0.1:4001".parse().unwrap());
+        schedule.connected("node1.internal:4001", peer_id.clone(), now);
+        assert!(schedule.due(now).is_empty());
+        assert_eq!(schedule.due(now + CONNECTED_CHECK), vec![("node1.internal:4001".to_string(), Some(peer_id))]);
+    }
+}
@@ -0,0 +1,113 @@
+//! バリデーターのセントリーノード構成
+//!
+//! バリデーターは設定したセントリーノードにだけ接続し、接続を待ち受けないため、
+//! バリデーターのIPアドレスは公開ネットワークに出ません。セントリーノードは背後の
+//! バリデーターからの接続を上限に関係なく受け入れ、合意形成のメッセージを
+//! 公開ネットワークとバリデーターの間で中継します。
+
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use anyhow::{Result, anyhow};
+use crate::config::SentrySettings;
+use super::quic::PeerId;
+
+/// ノードの役割
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SentryMode {
+    /// セントリー構成を使わない
+    #[default]
+    Off,
+    /// セントリーの背後のバリデーター
+    Validator,
+    /// セントリーノード
+    Sentry,
+}
+
+/// セントリーノード構成
+#[derive(Debug, Clone, Default)]
+pub struct SentryConfig {
+    pub mode: SentryMode,
+    /// バリデーターが接続するセントリーノード
+    pub sentry_nodes: Vec<SocketAddr>,
+    /// 背後のバリデーターのIPアドレス
+    pub private_peers: HashSet<IpAddr>,
+}
+
+impl SentryConfig {
+    pub fn from_settings(settings: &SentrySettings) -> Result<Self> {
+        let mode = match settings.mode.as_str() {
+            "off" | "" => SentryMode::Off,
+            "validator" => SentryMode::Validator,
+            "sentry" => SentryMode::Sentry,
+            other => return Err(anyhow!("Unknown network.sentry.mode: {}", other)),
+        };
+        let sentry_nodes = settings.sentry_nodes.iter()
+            .map(|addr| addr.parse().map_err(|e| anyhow!("Invalid sentry node {}: {}", addr, e)))
+            .collect::<Result<Vec<SocketAddr>>>()?;
+        let private_peers = settings.private_peers.iter()
+            .map(|addr| addr.parse::<SocketAddr>().map(|addr| addr.ip())
+                .or_else(|_| addr.parse::<IpAddr>())
+                .map_err(|e| anyhow!("Invalid private peer {}: {}", addr, e)))
+            .collect::<Result<HashSet<IpAddr>>>()?;
+        if mode == SentryMode::Validator && sentry_nodes.is_empty() {
+            return Err(anyhow!("network.sentry.sentry_nodes is required in validator mode"));
+        }
+        Ok(Self { mode, sentry_nodes, private_peers })
+    }
+
+    /// 接続を待ち受けるか（セントリーの背後のバリデーターは待ち受けない）
+    pub fn listens(&self) -> bool {
+        self.mode != SentryMode::Validator
+    }
+
+    /// 受信した合意形成のメッセージを他のピアへ中継するか
+    pub fn relays_consensus(&self) -> bool {
+        self.mode == SentryMode::Sentry
+    }
+
+    /// 起動時に接続するノード（バリデーターはブートストラップノードの代わりにセントリーノードに接続する）
+    pub fn dial_targets(&self, bootstrap_nodes: &[String]) -> Result<Vec<SocketAddr>> {
+        if self.mode == SentryMode::Validator {
+            return Ok(self.sentry_nodes.clone());
+        }
+        bootstrap_nodes.iter()
+            .map(|node| node.parse().map_err(|e| anyhow!("Invalid bootstrap node {}: {}", node, e)))
+            .collect()
+    }
+
+    /// 背後のバリデーターか
+    pub fn is_private(&self, peer: &PeerId) -> bool {
+        peer.ip().is_some_and(|ip| self.private_peers.contains(&ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validator_dials_only_sentries() {
+        let validator = SentryConfig::from_settings(&SentrySettings {
+            mode: "validator".to_string(),
+            sentry_nodes: vec!["10.0.1.1:4001".to_string()],
+            private_peers: Vec::new(),
+        }).unwrap();
+        assert!(!validator.listens());
+        assert_eq!(
+            validator.dial_targets(&["198.51.100.1:4001".to_string()]).unwrap(),
+            vec!["10.0.1.1:4001".parse::<SocketAddr>().unwrap()]
+        );
+
+        let sentry = SentryConfig::from_settings(&SentrySettings {
+            mode: "sentry".to_string(),
+            sentry_nodes: Vec::new(),
+            private_peers: vec!["10.0.0.5".to_string(), "10.0.0.6:4001".to_string()],
+        }).unwrap();
+        assert!(sentry.listens() && sentry.relays_consensus());
+        assert!(sentry.is_private(&PeerId::from_addr(&"10.0.0.5:53211".parse().unwrap())));
+        assert!(!sentry.is_private(&PeerId::from_addr(&"198.51.100.1:4001".parse().unwrap())));
+
+        // セントリーノードのないバリデーターは孤立するため起動しない
+        assert!(SentryConfig::from_settings(&SentrySettings { mode: "validator".to_string(), ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,394 @@
+//! シャードのチェックポイントとレシート証明
+//!
+//! 各シャードは定期的に、アカウント状態のマークル根（`state_root`）と前回のチェックポイント
+//! 以降に発行したレシートのマークル根（`receipt_root`）を、全シャードが共有する調整用の記録
+//! （`beacon/checkpoint/`）にコミットします。チェックポイントは前のチェックポイントのハッシュを
+//! 含むため、シャードごとに改ざんできない列になります。
+//!
+//! 別のシャードのコントラクトは、レシートとマークル証明をチェックポイントの `receipt_root` と
+//! 照合するだけで、送信元シャードの状態を読まずにイベントの発生を検証できます。
+//! 葉は `SHA-256(0x00 || レシート)`、内部ノードは `SHA-256(0x01 || 左 || 右)` で、
+//! 奇数個の段の最後のノードは自身と組にします。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use thiserror::Error;
+use utoipa::ToSchema;
+use crate::core::storage::StorageEngine;
+use super::{Account, AccountId, ShardId, Timestamp};
+
+/// チェックポイントのキープレフィックス
+pub const CHECKPOINT_PREFIX: &str = "beacon/checkpoint/";
+/// チェックポイントに含めたレシートのキープレフィックス
+pub const RECEIPTS_PREFIX: &str = "beacon/receipts/";
+/// 取引IDからレシートの位置を引くキープレフィックス
+pub const RECEIPT_INDEX_PREFIX: &str = "beacon/receipt/";
+
+/// シャードが発行したイベントのレシート
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ShardReceipt {
+    pub shard: ShardId,
+    pub tx_id: String,
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub account: Vec<u8>,
+    /// イベントの種類（例: `token.credit`）
+    pub topic: String,
+    /// イベントの内容（hex）
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub data: Vec<u8>,
+}
+
+impl ShardReceipt {
+    /// マークル木の葉（可変長のフィールドは長さを前置する）
+    pub fn leaf(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update([0]);
+        hasher.update(self.shard.to_be_bytes());
+        for field in [self.tx_id.as_bytes(), &self.account, self.topic.as_bytes(), &self.data] {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// シャードのチェックポイント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ShardCheckpoint {
+    pub shard: ShardId,
+    /// シャードごとの連番（1から）
+    pub sequence: u64,
+    /// アカウント状態のマークル根（hex）
+    pub state_root: String,
+    /// 前回のチェックポイント以降のレシートのマークル根（hex）
+    pub receipt_root: String,
+    pub receipt_count: usize,
+    /// 前のチェックポイントのハッシュ
+    pub parent: Option<String>,
+    pub timestamp: Timestamp,
+    pub hash: String,
+}
+
+impl ShardCheckpoint {
+    pub fn new(
+        shard: ShardId,
+        parent: Option<&ShardCheckpoint>,
+        state_root: [u8; 32],
+        receipts: &[ShardReceipt],
+        timestamp: Timestamp,
+    ) -> Self {
+        let leaves: Vec<[u8; 32]> = receipts.iter().map(ShardReceipt::leaf).collect();
+        let mut checkpoint = Self {
+            shard,
+            sequence: parent.map_or(1, |p| p.sequence + 1),
+            state_root: hex::encode(state_root),
+            receipt_root: hex::encode(merkle_root(&leaves)),
+            receipt_count: receipts.len(),
+            parent: parent.map(|p| p.hash.clone()),
+            timestamp,
+            hash: String::new(),
+        };
+        checkpoint.hash = checkpoint.compute_hash();
+        checkpoint
+    }
+
+    /// ハッシュの対象はハッシュ以外の全フィールド
+    pub fn compute_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.shard.to_be_bytes());
+        hasher.update(self.sequence.to_be_bytes());
+        hasher.update(self.state_root.as_bytes());
+        hasher.update(self.receipt_root.as_bytes());
+        hasher.update((self.receipt_count as u64).to_be_bytes());
+        hasher.update(self.parent.as_deref().unwrap_or_default().as_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// レシートがチェックポイントに含まれる証明
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ReceiptProof {
+    pub receipt: ShardReceipt,
+    /// レシートを含むチェックポイントの連番
+    pub sequence: u64,
+    /// チェックポイント内のレシートの位置
+    pub index: usize,
+    /// 葉から根までの兄弟のハッシュ（hex）
+    pub proof: Vec<String>,
+}
+
+/// レシート証明の検証エラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ProofError {
+    #[error("checkpoint hash does not match its contents")]
+    TamperedCheckpoint,
+
+    #[error("receipt was issued by shard {receipt}, but the checkpoint belongs to shard {checkpoint}")]
+    WrongShard { receipt: ShardId, checkpoint: ShardId },
+
+    #[error("proof refers to checkpoint {proof}, not {checkpoint}")]
+    WrongCheckpoint { proof: u64, checkpoint: u64 },
+
+    #[error("receipt is not included in the checkpoint's receipt root")]
+    InvalidProof,
+}
+
+/// レシートがチェックポイントの `receipt_root` に含まれるか検証
+pub fn verify_receipt(checkpoint: &ShardCheckpoint, proof: &ReceiptProof) -> Result<(), ProofError> {
+    if checkpoint.compute_hash() != checkpoint.hash {
+        return Err(ProofError::TamperedCheckpoint);
+    }
+    if proof.receipt.shard != checkpoint.shard {
+        return Err(ProofError::WrongShard { receipt: proof.receipt.shard, checkpoint: checkpoint.shard });
+    }
+    if proof.sequence != checkpoint.sequence {
+        return Err(ProofError::WrongCheckpoint { proof: proof.sequence, checkpoint: checkpoint.sequence });
+    }
+    if proof.index >= checkpoint.receipt_count || proof.proof.len() != tree_depth(checkpoint.receipt_count) {
+        return Err(ProofError::InvalidProof);
+    }
+    let mut hash = proof.receipt.leaf();
+    let mut position = proof.index;
+    for sibling in &proof.proof {
+        let sibling: [u8; 32] = hex::decode(sibling).ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(ProofError::InvalidProof)?;
+        hash = if position.is_multiple_of(2) { node_hash(&hash, &sibling) } else { node_hash(&sibling, &hash) };
+        position /= 2;
+    }
+    if hex::encode(hash) != checkpoint.receipt_root {
+        return Err(ProofError::InvalidProof);
+    }
+    Ok(())
+}
+
+/// アカウント状態のマークル根（アカウントIDの順）
+pub fn state_root(accounts: &HashMap<AccountId, Account>) -> [u8; 32] {
+    let mut ids: Vec<&AccountId> = accounts.keys().collect();
+    ids.sort_unstable();
+    let leaves: Vec<[u8; 32]> = ids.into_iter().map(|id| account_leaf(id, &accounts[id])).collect();
+    merkle_root(&leaves)
+}
+
+fn account_leaf(id: &AccountId, account: &Account) -> [u8; 32] {
+    let mut entries: Vec<_> = account.storage.iter().collect();
+    entries.sort_unstable();
+    let mut hasher = Sha256::new();
+    hasher.update([0]);
+    hasher.update(id);
+    hasher.update(account.balance.to_be_bytes());
+    hasher.update(account.nonce.to_be_bytes());
+    match &account.code {
+        Some(code) => hasher.update(Sha256::digest(code)),
+        None => hasher.update([0u8; 32]),
+    }
+    for (key, value) in entries {
+        hasher.update((key.len() as u64).to_be_bytes());
+        hasher.update(key);
+        hasher.update((value.len() as u64).to_be_bytes());
+        hasher.update(value);
+    }
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// マークル木の段（葉がなければ空）
+fn merkle_levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    if leaves.is_empty() {
+        return Vec::new();
+    }
+    let mut levels = vec![leaves.to_vec()];
+    while levels.last().expect("at least one level").len() > 1 {
+        let next = levels.last().expect("at least one level")
+            .chunks(2)
+            .map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// マークル根（葉がなければゼロ）
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    merkle_levels(leaves).last().map_or([0; 32], |root| root[0])
+}
+
+/// `leaves` 枚の葉のマークル木の深さ（証明のハッシュの数）
+fn tree_depth(leaves: usize) -> usize {
+    let mut width = leaves;
+    let mut depth = 0;
+    while width > 1 {
+        width = width.div_ceil(2);
+        depth += 1;
+    }
+    depth
+}
+
+/// 取引IDから引くレシートの位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReceiptLocation {
+    shard: ShardId,
+    sequence: u64,
+    index: usize,
+}
+
+/// 全シャードのチェックポイントの記録
+#[derive(Debug)]
+pub struct CheckpointRecord {
+    storage: Arc<dyn StorageEngine>,
+}
+
+impl CheckpointRecord {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        Self { storage }
+    }
+
+    /// シャードの最新のチェックポイント
+    pub async fn latest(&self, shard: ShardId) -> Result<Option<ShardCheckpoint>> {
+        match self.storage.get(&latest_key(shard)).await? {
+            Some(bytes) => self.get(shard, u64::from_be_bytes(bytes.as_slice().try_into()?)).await,
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get(&self, shard: ShardId, sequence: u64) -> Result<Option<ShardCheckpoint>> {
+        Ok(match self.storage.get(&checkpoint_key(shard, sequence)).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        })
+    }
+
+    /// 状態の根とレシートをコミットし、新しいチェックポイントを返す
+    ///
+    /// チェックポイント・レシート・取引IDの索引は1回のバッチで書き込みます。
+    pub async fn commit(&self, shard: ShardId, state_root: [u8; 32], receipts: &[ShardReceipt], timestamp: Timestamp) -> Result<ShardCheckpoint> {
+        let parent = self.latest(shard).await?;
+        let checkpoint = ShardCheckpoint::new(shard, parent.as_ref(), state_root, receipts, timestamp);
+
+        let mut batch = vec![
+            (checkpoint_key(shard, checkpoint.sequence), Some(serde_json::to_vec(&checkpoint)?)),
+            (latest_key(shard), Some(checkpoint.sequence.to_be_bytes().to_vec())),
+        ];
+        if !receipts.is_empty() {
+            batch.push((receipts_key(shard, checkpoint.sequence), Some(serde_json::to_vec(receipts)?)));
+        }
+        for (index, receipt) in receipts.iter().enumerate() {
+            let location = ReceiptLocation { shard, sequence: checkpoint.sequence, index };
+            batch.push((receipt_index_key(&receipt.tx_id), Some(serde_json::to_vec(&location)?)));
+        }
+        self.storage.batch_write(batch).await?;
+        Ok(checkpoint)
+    }
+
+    /// 取引のレシートの証明（まだチェックポイントに含まれていなければ `None`）
+    pub async fn proof(&self, tx_id: &str) -> Result<Option<ReceiptProof>> {
+        let Some(bytes) = self.storage.get(&receipt_index_key(tx_id)).await? else {
+            return Ok(None);
+        };
+        let location: ReceiptLocation = serde_json::from_slice(&bytes)?;
+        let Some(bytes) = self.storage.get(&receipts_key(location.shard, location.sequence)).await? else {
+            return Ok(None);
+        };
+        let receipts: Vec<ShardReceipt> = serde_json::from_slice(&bytes)?;
+        let Some(receipt) = receipts.get(location.index).cloned() else {
+            return Ok(None);
+        };
+
+        let leaves: Vec<[u8; 32]> = receipts.iter().map(ShardReceipt::leaf).collect();
+        let levels = merkle_levels(&leaves);
+        let mut proof = Vec::with_capacity(levels.len().saturating_sub(1));
+        let mut position = location.index;
+        for level in &levels[..levels.len() - 1] {
+            let sibling = level.get(position ^ 1).unwrap_or(&level[position]);
+            proof.push(hex::encode(sibling));
+            position /= 2;
+        }
+        Ok(Some(ReceiptProof { receipt, sequence: location.sequence, index: location.index, proof }))
+    }
+
+    /// 記録済みのチェックポイントに対してレシート証明を検証
+    pub async fn verify(&self, proof: &ReceiptProof) -> Result<ShardCheckpoint> {
+        let checkpoint = self.get(proof.receipt.shard, proof.sequence).await?
+            .ok_or_else(|| anyhow::anyhow!("shard {} has no checkpoint {}", proof.receipt.shard, proof.sequence))?;
+        verify_receipt(&checkpoint, proof)?;
+        Ok(checkpoint)
+    }
+}
+
+fn checkpoint_key(shard: ShardId, sequence: u64) -> Vec<u8> {
+    format!("{}{:08x}/{:016x}", CHECKPOINT_PREFIX, shard, sequence).into_bytes()
+}
+
+fn latest_key(shard: ShardId) -> Vec<u8> {
+    format!("{}{:08x}/latest", CHECKPOINT_PREFIX, shard).into_bytes()
+}
+
+fn receipts_key(shard: ShardId, sequence: u64) -> Vec<u8> {
+    format!("{}{:08x}/{:016x}", RECEIPTS_PREFIX, shard, sequence).into_bytes()
+}
+
+fn receipt_index_key(tx_id: &str) -> Vec<u8> {
+    format!("{}{}", RECEIPT_INDEX_PREFIX, tx_id).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::sharding::ShardManager;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    #[tokio::test]
+    async fn test_receipt_from_another_shard_verifies() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let mut manager = ShardManager::new(storage);
+        manager.create_shard(1).await.unwrap();
+        {
+            let shard = manager.get_shard(1).await.unwrap();
+            let mut shard = shard.write().await;
+            for i in 0..5u8 {
+                shard.emit_receipt(format!("tx{}", i), vec![i; 32], "token.credit", vec![i]);
+            }
+        }
+
+        let checkpoints = manager.checkpoint_shards().await.unwrap();
+        let checkpoint = checkpoints.iter().find(|c| c.shard == 1).unwrap();
+        assert_eq!((checkpoint.sequence, checkpoint.receipt_count), (1, 5));
+
+        // シャード0のコントラクトがシャード1のイベントを検証する
+        let record = manager.checkpoints();
+        let proof = record.proof("tx4").await.unwrap().unwrap();
+        assert_eq!(proof.proof.len(), 3);
+        assert_eq!(record.verify(&proof).await.unwrap(), *checkpoint);
+
+        // 内容を変えたレシートや別のシャードのチェックポイントでは検証できない
+        let mut forged = proof.clone();
+        forged.receipt.data = vec![99];
+        assert_eq!(verify_receipt(checkpoint, &forged), Err(ProofError::InvalidProof));
+        let mut tampered = checkpoint.clone();
+        tampered.receipt_root = hex::encode([0u8; 32]);
+        assert_eq!(verify_receipt(&tampered, &proof), Err(ProofError::TamperedCheckpoint));
+        let other = checkpoints.iter().find(|c| c.shard == 0).unwrap();
+        assert_eq!(verify_receipt(other, &proof), Err(ProofError::WrongShard { receipt: 1, checkpoint: 0 }));
+
+        // 変化のないシャードはコミットせず、次のチェックポイントは前のものに連なる
+        manager.get_shard(1).await.unwrap().write().await.emit_receipt("tx5".to_string(), vec![5; 32], "token.credit", vec![5]);
+        let next = manager.checkpoint_shards().await.unwrap();
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].parent.as_deref(), Some(checkpoint.hash.as_str()));
+        assert_eq!(next[0].state_root, checkpoint.state_root);
+        assert!(record.proof("tx5").await.unwrap().unwrap().proof.is_empty());
+    }
+}
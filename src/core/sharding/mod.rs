@@ -7,15 +7,34 @@
 //! - 負荷分散
 //! - パフォーマンスモニタリング
 
-use std::collections::HashMap;
+pub mod checkpoint;
+pub mod rebalance;
+pub mod replay;
+pub mod scaling;
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Mutex, RwLock};
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
-use crate::{
-    core::storage::StorageEngine,
-    core::network::P2PNetwork,
-};
+use tracing::{debug, info, warn};
+use crate::core::block::{Block, BlockRule, Chain};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use checkpoint::{CheckpointRecord, ShardCheckpoint, ShardReceipt};
+use rebalance::{AccountLoad, RebalanceConfig, RebalancePlan, RebalanceState, RebalanceStatus, ShardLoad};
+use replay::{ReplayError, ReplayRegistry};
+use scaling::{ScalingReason, ScalingRecommendation, ScalingTrigger};
+
+/// アカウントの所属シャードのキープレフィックス
+const ASSIGNMENT_PREFIX: &str = "shard/assignment/";
+/// シャードに反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"shard/applied_height";
+/// スケーリング推奨の通知チャネルの容量
+const SCALING_CHANNEL_CAPACITY: usize = 64;
+/// メトリクスを集計する期間（秒）
+const METRICS_WINDOW_SECS: u64 = 10;
 
 // 基本的な型定義
 pub type ShardId = u32;
@@ -30,6 +49,19 @@ pub struct ShardAddress {
     pub checksum: [u8; 4],
 }
 
+/// チェーンのアドレス（hex）をアカウントIDに変換
+///
+/// 32バイトに満たないアドレスは先頭を0で埋めます。
+pub fn account_id(address: &str) -> Option<AccountId> {
+    let bytes = hex::decode(address.trim_start_matches("0x")).ok()?;
+    if bytes.len() > 32 {
+        return None;
+    }
+    let mut id = [0u8; 32];
+    id[32 - bytes.len()..].copy_from_slice(&bytes);
+    Some(id)
+}
+
 impl ShardAddress {
     /// 新しいシャードアドレスを作成
     pub fn new(shard_id: ShardId, account_id: AccountId) -> Self {
@@ -130,10 +162,28 @@ pub struct Shard {
     pub metrics: Arc<RwLock<ShardMetrics>>,
     pub validators: Vec<String>,
     pub accounts: HashMap<AccountId, Account>,
+    /// アカウントごとの処理トランザクション数（負荷の按分に使用）
+    pub activity: HashMap<AccountId, u64>,
+    /// 次のチェックポイントに含めるレシート
+    pub outbox: Vec<ShardReceipt>,
     pub storage: Arc<dyn StorageEngine>,
+    /// 集計期間内のアカウントごとの確定
+    recent: HashMap<AccountId, VecDeque<Throughput>>,
+    /// メトリクスを最後に集計した時刻（UNIX秒）
+    metrics_at: Timestamp,
 }
 
-#[derive(Debug, Clone)]
+/// 同じ時刻のブロックで確定した取引の集計
+#[derive(Debug, Clone, Copy)]
+struct Throughput {
+    timestamp: Timestamp,
+    txs: u64,
+    cross_shard: u64,
+    /// 受信から確定までの秒数の合計
+    wait_secs: u64,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Account {
     pub balance: u128,
     pub nonce: u64,
@@ -141,6 +191,15 @@ pub struct Account {
     pub storage: HashMap<Vec<u8>, Vec<u8>>,
 }
 
+impl Account {
+    /// アカウントが占めるストレージ容量（バイト）
+    pub fn storage_size(&self) -> u64 {
+        let code = self.code.as_ref().map_or(0, |c| c.len());
+        let entries: usize = self.storage.iter().map(|(k, v)| k.len() + v.len()).sum();
+        (code + entries) as u64
+    }
+}
+
 impl Shard {
     /// 新しいシャードを作成
     pub fn new(id: ShardId, config: ShardConfig, storage: Arc<dyn StorageEngine>) -> Self {
@@ -162,30 +221,125 @@ impl Shard {
             })),
             validators: Vec::new(),
             accounts: HashMap::new(),
+            activity: HashMap::new(),
+            outbox: Vec::new(),
             storage,
+            recent: HashMap::new(),
+            metrics_at: 0,
         }
     }
 
-    /// シャードのスケーリングが必要かどうかを判断
-    pub async fn needs_scaling(&self) -> bool {
+    /// アカウントのトランザクション処理を記録
+    pub fn record_tx(&mut self, account: &AccountId) {
+        *self.activity.entry(*account).or_default() += 1;
+    }
+
+    /// 確定した取引を記録（メトリクスへの反映は `refresh_metrics` で行う）
+    pub fn record_committed(&mut self, account: &AccountId, timestamp: Timestamp, cross_shard: bool, wait_secs: u64) {
+        self.accounts.entry(*account).or_default();
+        self.record_tx(account);
+        let entries = self.recent.entry(*account).or_default();
+        let entry = match entries.back_mut() {
+            Some(last) if last.timestamp == timestamp => last,
+            _ => {
+                entries.push_back(Throughput { timestamp, txs: 0, cross_shard: 0, wait_secs: 0 });
+                entries.back_mut().expect("just pushed")
+            }
+        };
+        entry.txs += 1;
+        entry.cross_shard += cross_shard as u64;
+        entry.wait_secs += wait_secs;
+    }
+
+    /// 直近 `METRICS_WINDOW_SECS` 秒の確定からメトリクスを更新
+    pub async fn refresh_metrics(&mut self, now: Timestamp) -> Result<()> {
+        self.metrics_at = self.metrics_at.max(now);
+        let since = self.metrics_at.saturating_sub(METRICS_WINDOW_SECS);
+        self.recent.retain(|_, entries| {
+            entries.retain(|t| t.timestamp > since);
+            !entries.is_empty()
+        });
+        let (txs, cross_shard, wait_secs) = self.recent.values().flatten()
+            .fold((0u64, 0u64, 0u64), |(txs, cross, wait), t| (txs + t.txs, cross + t.cross_shard, wait + t.wait_secs));
+
+        let mut metrics = self.metrics.read().await.clone();
+        metrics.current_tps = u32::try_from(txs.div_ceil(METRICS_WINDOW_SECS)).unwrap_or(u32::MAX);
+        metrics.cross_shard_tx_ratio = if txs == 0 { 0.0 } else { cross_shard as f64 / txs as f64 };
+        metrics.latency = std::time::Duration::from_secs(if txs == 0 { 0 } else { wait_secs / txs });
+        metrics.storage_usage = self.accounts.values().map(Account::storage_size).sum();
+        self.update_metrics(metrics).await
+    }
+
+    /// 他のシャードから検証できるイベントを発行
+    pub fn emit_receipt(&mut self, tx_id: String, account: Vec<u8>, topic: &str, data: Vec<u8>) {
+        self.outbox.push(ShardReceipt { shard: self.id, tx_id, account, topic: topic.to_string(), data });
+    }
+
+    /// アカウント状態のマークル根
+    pub fn state_root(&self) -> [u8; 32] {
+        checkpoint::state_root(&self.accounts)
+    }
+
+    /// 現在の負荷
+    pub async fn load(&self, tps_weight: f64) -> ShardLoad {
         let metrics = self.metrics.read().await;
-        
-        // TPSベースのチェック
-        if metrics.current_tps as f64 > self.config.max_tps as f64 * self.config.scaling_threshold {
-            return true;
+        ShardLoad {
+            shard_id: self.id,
+            tps: metrics.current_tps as f64,
+            storage_usage: metrics.storage_usage,
+            accounts: self.accounts.len(),
+            score: rebalance::score(
+                metrics.current_tps as f64,
+                metrics.storage_usage,
+                self.config.max_tps,
+                self.config.max_storage,
+                tps_weight,
+            ),
         }
+    }
 
-        // ストレージ使用量のチェック
-        if metrics.storage_usage as f64 > self.config.max_storage as f64 * self.config.scaling_threshold {
-            return true;
-        }
+    /// アカウントごとの負荷
+    ///
+    /// シャードのTPSを処理トランザクション数で按分します。
+    pub async fn account_loads(&self) -> Vec<AccountLoad> {
+        let tps = self.metrics.read().await.current_tps as f64;
+        let total: u64 = self.accounts.keys().map(|a| self.activity.get(a).copied().unwrap_or(0)).sum();
+        self.accounts
+            .iter()
+            .map(|(id, account)| AccountLoad {
+                account: *id,
+                tps: if total == 0 { 0.0 } else { tps * self.activity.get(id).copied().unwrap_or(0) as f64 / total as f64 },
+                storage: account.storage_size(),
+            })
+            .collect()
+    }
 
-        // アカウント数のチェック
-        if self.accounts.len() as f64 > self.config.max_accounts as f64 * self.config.scaling_threshold {
-            return true;
-        }
+    /// シャードのスケーリングが必要かどうかを判断
+    pub async fn needs_scaling(&self) -> bool {
+        !self.scaling_reasons().await.is_empty()
+    }
 
-        false
+    /// しきい値を超えている指標
+    pub async fn scaling_reasons(&self) -> Vec<ScalingReason> {
+        let metrics = self.metrics.read().await;
+        let threshold = self.config.scaling_threshold;
+        [
+            // TPSベースのチェック
+            (ScalingTrigger::Tps, metrics.current_tps as f64, self.config.max_tps as f64),
+            // ストレージ使用量のチェック
+            (ScalingTrigger::Storage, metrics.storage_usage as f64, self.config.max_storage as f64),
+            // アカウント数のチェック
+            (ScalingTrigger::Accounts, self.accounts.len() as f64, self.config.max_accounts as f64),
+        ]
+        .into_iter()
+        .filter(|(_, value, max)| *value > max * threshold)
+        .map(|(trigger, value, max)| ScalingReason {
+            shard_id: Some(self.id),
+            trigger,
+            value,
+            threshold: max * threshold,
+        })
+        .collect()
     }
 
     /// メトリクスを更新
@@ -220,18 +374,35 @@ pub struct ShardManager {
     shards: HashMap<ShardId, Arc<RwLock<Shard>>>,
     config: ShardConfig,
     storage: Arc<dyn StorageEngine>,
-    _network: Arc<P2PNetwork>,
+    rebalance_config: RebalanceConfig,
+    rebalance: Arc<RwLock<RebalanceStatus>>,
+    /// 外部オートスケーラー向けの推奨の通知
+    scaling_events: broadcast::Sender<ScalingRecommendation>,
+    /// 最後に通知した推奨
+    last_recommendation: Option<ScalingRecommendation>,
+    /// シャードをまたいだノンスの管理
+    replay: ReplayRegistry,
+    /// シャードのチェックポイントの記録
+    checkpoints: CheckpointRecord,
+    /// ブロックの反映を直列化する（購読と確定前の追いつきが同時に反映しないようにする）
+    applying: Mutex<()>,
 }
 
 impl ShardManager {
     /// 新しいシャードマネージャーを作成
-    pub fn new(storage: Arc<dyn StorageEngine>, network: Arc<P2PNetwork>) -> Self {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
         let config = ShardConfig::default();
         let mut manager = Self {
             shards: HashMap::new(),
             config: config.clone(),
+            replay: ReplayRegistry::new(storage.clone()),
+            checkpoints: CheckpointRecord::new(storage.clone()),
             storage,
-            _network: network,
+            rebalance_config: RebalanceConfig::default(),
+            rebalance: Arc::new(RwLock::new(RebalanceStatus::default())),
+            scaling_events: broadcast::channel(SCALING_CHANNEL_CAPACITY).0,
+            last_recommendation: None,
+            applying: Mutex::new(()),
         };
         
         // 初期シャードを作成
@@ -241,6 +412,12 @@ impl ShardManager {
         manager
     }
 
+    /// 再分配の設定を変更
+    pub fn with_rebalance_config(mut self, config: RebalanceConfig) -> Self {
+        self.rebalance_config = config;
+        self
+    }
+
     /// シャードを作成
     pub async fn create_shard(&mut self, id: ShardId) -> Result<()> {
         if self.shards.contains_key(&id) {
@@ -252,6 +429,218 @@ impl ShardManager {
         Ok(())
     }
 
+    /// 全シャードのID（昇順）
+    pub fn shard_ids(&self) -> Vec<ShardId> {
+        let mut ids: Vec<ShardId> = self.shards.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// ビーコンチェーンで確定したシャードの構成とバリデーターの割り当てに合わせる
+    ///
+    /// シャードの構成はビーコンチェーンが正です。このノードにまだないシャードは作成し、構成から
+    /// 外れたシャードはアカウントがなければ削除します（残っている場合は割り当てを外して残します）。
+    /// シャードを作成した場合はアカウントの再分配を計画します。
+    pub async fn apply_membership(&mut self, assignments: &BTreeMap<ShardId, Vec<String>>) -> Result<()> {
+        let mut added = false;
+        for (id, validators) in assignments {
+            if !self.shards.contains_key(id) {
+                self.create_shard(*id).await?;
+                info!("Created shard {} agreed on the beacon chain", id);
+                added = true;
+            }
+            self.get_shard(*id).await?.write().await.validators = validators.clone();
+        }
+        for id in self.shard_ids() {
+            if assignments.contains_key(&id) {
+                continue;
+            }
+            let shard = self.get_shard(id).await?;
+            let mut shard = shard.write().await;
+            if shard.accounts.is_empty() {
+                drop(shard);
+                self.shards.remove(&id);
+                info!("Removed shard {} as agreed on the beacon chain", id);
+            } else {
+                shard.validators.clear();
+                warn!("Shard {} was removed on the beacon chain but still holds {} accounts", id, shard.accounts.len());
+            }
+        }
+        if added {
+            self.schedule_rebalance().await;
+        }
+        Ok(())
+    }
+
+    /// 確定したブロックの取引をシャードに反映
+    ///
+    /// 取引は送信者のシャードで数えて確定し（`finalize_tx`）、受信者が別のシャードに所属するものは
+    /// クロスシャードとして数えます。反映済みの高さ以下のブロックは無視します。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        for tx in &block.transactions {
+            let Some(from) = account_id(&tx.from) else {
+                continue;
+            };
+            let from_shard = self.route(&from).await?;
+            let to = match account_id(&tx.to) {
+                Some(to) => Some((to, self.route(&to).await?)),
+                None => None,
+            };
+            let cross_shard = to.is_some_and(|(_, shard)| shard != from_shard);
+            let wait_secs = if tx.received_at == 0 { 0 } else { block.timestamp.saturating_sub(tx.received_at) };
+            let registered = self.replay.domain(&from).await?.is_some();
+            {
+                let shard = self.get_shard(from_shard).await?;
+                let mut shard = shard.write().await;
+                let account = shard.accounts.entry(from).or_default();
+                // 登録簿にまだないアカウントの以前の取引はチェーンで確定済みのため、このノンスから数える
+                if !registered {
+                    account.nonce = tx.nonce;
+                }
+                shard.record_committed(&from, block.timestamp, cross_shard, wait_secs);
+            }
+            // 確定前に `check_block` で検証済みのため、失敗するのは検証の導入前のブロックのみ
+            if let Err(e) = self.finalize_tx(from_shard, &from, tx.nonce).await {
+                warn!("Transaction {} was not finalized in shard {}: {}", tx.hash, from_shard, e);
+            }
+            if let Some((to, to_shard)) = to {
+                self.get_shard(to_shard).await?.write().await.accounts.entry(to).or_default();
+            }
+        }
+        for shard in self.shards.values() {
+            shard.write().await.refresh_metrics(block.timestamp).await?;
+        }
+        self.storage.put(APPLIED_HEIGHT_KEY, &block.height.to_be_bytes()).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 高さ `height` までの確定したブロックをストレージから読み直して反映する
+    ///
+    /// チェーンの先頭を読まないため、確定中の検証（`BlockRule`）からも呼べます。
+    pub async fn catch_up_to(&self, chain: &Chain, height: u64) -> Result<()> {
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=height {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 最新のブロックまで追いつかせる
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        match chain.head().await {
+            Some((head, _)) => self.catch_up_to(chain, head).await,
+            None => Ok(()),
+        }
+    }
+
+    /// ブロックの取引が送信者のシャードのノンスドメインで確定できるか検証（状態は変更しない）
+    ///
+    /// 確定済みのノンスの再送、ノンスの飛び、移動中や所属しないシャードでの確定を拒否します。
+    /// 登録簿にまだないアカウントは、ブロックで最初の取引のノンスから数えます。
+    pub async fn check_block(&self, block: &Block) -> Result<()> {
+        let mut next: HashMap<AccountId, u64> = HashMap::new();
+        for tx in &block.transactions {
+            let Some(from) = account_id(&tx.from) else {
+                continue;
+            };
+            let result: Result<()> = match next.get(&from) {
+                Some(&expected) if tx.nonce < expected => Err(ReplayError::AlreadyFinalized { nonce: tx.nonce, expected }.into()),
+                Some(&expected) if tx.nonce > expected => Err(ReplayError::NonceGap { nonce: tx.nonce, expected }.into()),
+                Some(_) => Ok(()),
+                None => self.replay.check(&from, self.route(&from).await?, tx.nonce).await,
+            };
+            result.map_err(|e| anyhow!("Transaction {} cannot be finalized: {}", tx.hash, e))?;
+            next.insert(from, tx.nonce + 1);
+        }
+        Ok(())
+    }
+
+    /// 受付時の検証（確定済みのノンスの再送を拒否する）
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<()> {
+        let Some(from) = account_id(&tx.from) else {
+            return Ok(());
+        };
+        match self.replay.domain(&from).await? {
+            Some(domain) if tx.nonce < domain.next_nonce => {
+                Err(ReplayError::AlreadyFinalized { nonce: tx.nonce, expected: domain.next_nonce }.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// ブロックで確定できない取引（と同じ送信者の後続のもの）を除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let mut next: HashMap<AccountId, u64> = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            let Some(from) = account_id(&tx.from) else {
+                kept.push(tx);
+                continue;
+            };
+            let result: Result<()> = match next.get(&from) {
+                Some(&expected) if tx.nonce != expected => Err(anyhow!("nonce {} does not follow {}", tx.nonce, expected)),
+                Some(_) => Ok(()),
+                None => match self.route(&from).await {
+                    Ok(shard) => self.replay.check(&from, shard, tx.nonce).await,
+                    Err(e) => Err(e),
+                },
+            };
+            match result {
+                Ok(()) => {
+                    next.insert(from, tx.nonce + 1);
+                    kept.push(tx);
+                }
+                Err(e) => {
+                    debug!("Leaving transaction {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// 以降の確定をシャードに反映する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(manager: Arc<RwLock<Self>>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = manager.read().await.catch_up(&chain).await {
+                warn!("Failed to catch up shards: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => manager.read().await.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => manager.read().await.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply blocks to shards: {}", e);
+                    if let Err(e) = manager.read().await.catch_up(&chain).await {
+                        warn!("Failed to catch up shards: {}", e);
+                    }
+                }
+            }
+        })
+    }
+
     /// シャードを取得
     pub async fn get_shard(&self, id: ShardId) -> Result<Arc<RwLock<Shard>>> {
         self.shards
@@ -260,21 +649,317 @@ impl ShardManager {
             .ok_or_else(|| anyhow!("Shard not found"))
     }
 
-    /// シャードの状態をチェックし、必要に応じてスケーリング
-    pub async fn check_and_scale(&mut self) -> Result<()> {
-        for shard_id in self.shards.keys().copied().collect::<Vec<_>>() {
+    /// シャードの状態をチェックし、追加が必要なシャードのIDを返す
+    ///
+    /// シャードの構成はビーコンチェーンで合意するため、ここでは作成しません。呼び出し側が
+    /// `BeaconOp::AddShard` として提案し、確定後の `apply_membership` で作成されます。
+    /// 負荷の偏りが許容範囲を超えた場合は、アカウントの再分配計画を登録します。
+    /// 計画の実行は `run_pending_rebalance` で行います。
+    pub async fn check_and_scale(&mut self) -> Result<Vec<ShardId>> {
+        self.publish_recommendation().await;
+
+        let mut proposed = Vec::new();
+        for shard_id in self.shard_ids() {
             let shard = self.get_shard(shard_id).await?;
             if shard.read().await.needs_scaling().await {
                 // 新しいシャードIDを生成
                 let new_shard_id = shard_id * 2 + 1;
-                // 新しいシャードを作成
-                self.create_shard(new_shard_id).await?;
-                // TODO: アカウントの再分配
+                if self.shards.contains_key(&new_shard_id) || proposed.contains(&new_shard_id) {
+                    continue;
+                }
+                info!("Proposing shard {} to relieve shard {}", new_shard_id, shard_id);
+                proposed.push(new_shard_id);
             }
         }
+
+        if rebalance::imbalance(&self.shard_loads().await) > self.rebalance_config.imbalance_tolerance {
+            self.schedule_rebalance().await;
+        }
+        Ok(proposed)
+    }
+
+    /// アカウントの再分配を計画する（実行中の計画があれば何もしない）
+    async fn schedule_rebalance(&self) {
+        let in_progress = matches!(
+            self.rebalance.read().await.state,
+            RebalanceState::WaitingForWindow | RebalanceState::Migrating
+        );
+        if in_progress {
+            return;
+        }
+        let plan = self.plan_rebalance().await;
+        info!("Scheduled shard rebalance with {} account moves", plan.moves.len());
+        self.rebalance.write().await.schedule(plan);
+    }
+
+    /// 現在の負荷からスケーリングの推奨を作成
+    pub async fn recommend_scaling(&self) -> ScalingRecommendation {
+        let mut reasons = Vec::new();
+        let mut validators = std::collections::HashSet::new();
+        for shard in self.shards.values() {
+            let shard = shard.read().await;
+            reasons.extend(shard.scaling_reasons().await);
+            validators.extend(shard.validators.iter().cloned());
+        }
+        let mut recommendation = scaling::recommend(
+            &self.shard_loads().await,
+            reasons,
+            validators.len(),
+            self.config.min_validators as usize,
+            self.config.scaling_threshold,
+        );
+        recommendation.sequence = self.last_recommendation.as_ref().map_or(0, |r| r.sequence);
+        recommendation
+    }
+
+    /// 最後に通知したスケーリングの推奨
+    pub fn last_recommendation(&self) -> Option<&ScalingRecommendation> {
+        self.last_recommendation.as_ref()
+    }
+
+    /// スケーリングの推奨を購読
+    pub fn subscribe_scaling(&self) -> broadcast::Receiver<ScalingRecommendation> {
+        self.scaling_events.subscribe()
+    }
+
+    /// 推奨が変化していれば通知
+    async fn publish_recommendation(&mut self) {
+        let mut recommendation = self.recommend_scaling().await;
+        if self.last_recommendation.as_ref().is_some_and(|last| !recommendation.differs_from(last)) {
+            return;
+        }
+        recommendation.sequence += 1;
+        info!(
+            "Scaling recommendation #{}: {:?} to {} shards / {} nodes",
+            recommendation.sequence, recommendation.action, recommendation.desired_shards, recommendation.desired_nodes
+        );
+        // 購読者がいない場合の送信エラーは無視する
+        let _ = self.scaling_events.send(recommendation.clone());
+        self.last_recommendation = Some(recommendation);
+    }
+
+    /// 全シャードの負荷
+    pub async fn shard_loads(&self) -> Vec<ShardLoad> {
+        let mut loads = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.values() {
+            loads.push(shard.read().await.load(self.rebalance_config.tps_weight).await);
+        }
+        loads.sort_by_key(|l| l.shard_id);
+        loads
+    }
+
+    /// 現在のメトリクスから再分配計画を作成
+    pub async fn plan_rebalance(&self) -> RebalancePlan {
+        let mut accounts = Vec::new();
+        for (id, shard) in &self.shards {
+            let shard = shard.read().await;
+            accounts.extend(shard.account_loads().await.into_iter().map(|a| (*id, a)));
+        }
+        rebalance::plan(
+            self.shard_loads().await,
+            accounts,
+            self.config.max_tps,
+            self.config.max_storage,
+            &self.rebalance_config,
+        )
+    }
+
+    /// 全シャードのトポロジー
+    pub async fn topology(&self) -> Vec<ShardTopology> {
+        let mut topology = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.values() {
+            let shard = shard.read().await;
+            let metrics = shard.metrics.read().await;
+            topology.push(ShardTopology {
+                id: shard.id,
+                validators: shard.validators.clone(),
+                account_count: shard.accounts.len(),
+                storage_usage: metrics.storage_usage,
+                tps: metrics.current_tps,
+                cross_shard_ratio: metrics.cross_shard_tx_ratio,
+                latency_ms: metrics.latency.as_millis() as u64,
+                needs_scaling: shard.needs_scaling().await,
+            });
+        }
+        topology.sort_by_key(|t| t.id);
+        topology
+    }
+
+    /// 再分配の進捗を取得
+    pub async fn rebalance_status(&self) -> RebalanceStatus {
+        self.rebalance.read().await.clone()
+    }
+
+    /// 登録済みの再分配計画を実行
+    ///
+    /// 全シャード合計のTPSが `low_traffic_tps` を超えている間は移動を中断し、
+    /// 次回の呼び出しで再開します。
+    pub async fn run_pending_rebalance(&self) -> Result<()> {
+        let remaining = {
+            let status = self.rebalance.read().await;
+            if !matches!(status.state, RebalanceState::WaitingForWindow | RebalanceState::Migrating) {
+                return Ok(());
+            }
+            status.remaining().to_vec()
+        };
+
+        for mv in remaining {
+            let total_tps: f64 = self.shard_loads().await.iter().map(|l| l.tps).sum();
+            if total_tps > self.rebalance_config.low_traffic_tps as f64 {
+                self.rebalance.write().await.set_state(RebalanceState::WaitingForWindow);
+                return Ok(());
+            }
+            self.rebalance.write().await.set_state(RebalanceState::Migrating);
+
+            if let Err(e) = self.migrate_account(&mv.account, mv.from, mv.to).await {
+                warn!("Failed to migrate account {}: {}", hex::encode(mv.account), e);
+                let mut status = self.rebalance.write().await;
+                status.last_error = Some(e.to_string());
+                status.set_state(RebalanceState::Failed);
+                return Err(e);
+            }
+            self.rebalance.write().await.completed_moves += 1;
+        }
+
+        self.rebalance.write().await.set_state(RebalanceState::Completed);
+        info!("Shard rebalance completed");
+        Ok(())
+    }
+
+    /// アカウントを別のシャードへ移動
+    ///
+    /// 移動中はアカウントの取引を確定せず、確定済みのノンスを移動先へ引き継ぎます。
+    /// アカウントの負荷（集計期間内の確定とストレージ）も移動先のメトリクスへ移します。
+    pub async fn migrate_account(&self, account: &AccountId, from: ShardId, to: ShardId) -> Result<()> {
+        let source = self.get_shard(from).await?;
+        let target = self.get_shard(to).await?;
+
+        let nonce = source.read().await.accounts.get(account).map(|state| state.nonce)
+            .ok_or_else(|| anyhow!("Account not found in shard {}", from))?;
+        self.replay.begin_migration(account, from, to, nonce).await?;
+
+        let moved = {
+            let mut source = source.write().await;
+            let moved = source.accounts.remove(account)
+                .map(|state| (state, source.activity.remove(account), source.recent.remove(account)));
+            let now = source.metrics_at;
+            source.refresh_metrics(now).await?;
+            moved.map(|moved| (moved, now))
+        };
+        let Some(((mut state, activity, recent), now)) = moved else {
+            self.replay.abort_migration(account).await?;
+            return Err(anyhow!("Account not found in shard {}", from));
+        };
+        let domain = self.replay.complete_migration(account, to, state.nonce).await?;
+        state.nonce = domain.next_nonce;
+        {
+            let mut target = target.write().await;
+            target.accounts.insert(*account, state);
+            if let Some(count) = activity {
+                target.activity.insert(*account, count);
+            }
+            if let Some(recent) = recent {
+                target.recent.insert(*account, recent);
+            }
+            target.refresh_metrics(now).await?;
+        }
+
+        let key = format!("{}{}", ASSIGNMENT_PREFIX, hex::encode(account));
+        self.storage.put(key.as_bytes(), &to.to_be_bytes()).await?;
+        Ok(())
+    }
+
+    /// シャードでアカウントの取引を確定
+    ///
+    /// 確定済みのノンスや、アカウントが所属しないシャードでの確定は `ReplayError` で拒否します。
+    pub async fn finalize_tx(&self, shard: ShardId, account: &AccountId, nonce: u64) -> Result<()> {
+        let handle = self.get_shard(shard).await?;
+        let account_nonce = handle.read().await.accounts.get(account).map_or(0, |state| state.nonce);
+        let domain = self.replay.finalize(account, shard, nonce, account_nonce).await?;
+        if let Some(state) = handle.write().await.accounts.get_mut(account) {
+            state.nonce = domain.next_nonce;
+        }
         Ok(())
     }
 
+    /// シャードをまたいだノンスの登録簿
+    pub fn replay(&self) -> &ReplayRegistry {
+        &self.replay
+    }
+
+    /// 各シャードの状態の根と未コミットのレシートをチェックポイントとして記録
+    ///
+    /// 前回から状態が変わらずレシートもないシャードはコミットしません。
+    pub async fn checkpoint_shards(&self) -> Result<Vec<ShardCheckpoint>> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut committed = Vec::new();
+        for id in self.shard_ids() {
+            let shard = self.get_shard(id).await?;
+            let (state_root, receipts) = {
+                let mut shard = shard.write().await;
+                (shard.state_root(), std::mem::take(&mut shard.outbox))
+            };
+            let latest = self.checkpoints.latest(id).await?;
+            if receipts.is_empty() && latest.is_some_and(|c| c.state_root == hex::encode(state_root)) {
+                continue;
+            }
+            match self.checkpoints.commit(id, state_root, &receipts, timestamp).await {
+                Ok(checkpoint) => committed.push(checkpoint),
+                Err(e) => {
+                    // 次回のチェックポイントに含めるよう戻す
+                    shard.write().await.outbox.splice(0..0, receipts);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(committed)
+    }
+
+    /// シャードのチェックポイントの記録
+    pub fn checkpoints(&self) -> &CheckpointRecord {
+        &self.checkpoints
+    }
+
+    /// アカウントを処理するシャードを決定
+    ///
+    /// 所属が記録されていないアカウントは、アドレスのハッシュで既存シャードに割り当てます。
+    pub async fn route(&self, address: &[u8]) -> Result<ShardId> {
+        use sha2::{Sha256, Digest};
+
+        if let Ok(account) = AccountId::try_from(address) {
+            if let Some(shard) = self.shard_of(&account).await? {
+                return Ok(shard);
+            }
+        }
+        let mut ids: Vec<ShardId> = self.shards.keys().copied().collect();
+        ids.sort_unstable();
+        let hash = Sha256::digest(address);
+        let index = u64::from_be_bytes(hash[..8].try_into()?) % ids.len().max(1) as u64;
+        ids.get(index as usize).copied().ok_or_else(|| anyhow!("No shards available"))
+    }
+
+    /// アカウントの所属シャードを取得
+    ///
+    /// このノードのシャードにない場合は、ノンスドメインと移動の記録から求めます。
+    pub async fn shard_of(&self, account: &AccountId) -> Result<Option<ShardId>> {
+        for (id, shard) in &self.shards {
+            if shard.read().await.accounts.contains_key(account) {
+                return Ok(Some(*id));
+            }
+        }
+        if let Some(domain) = self.replay.domain(account).await? {
+            return Ok(Some(domain.shard));
+        }
+        let key = format!("{}{}", ASSIGNMENT_PREFIX, hex::encode(account));
+        Ok(self.storage.get(key.as_bytes()).await?
+            .and_then(|v| v.try_into().ok())
+            .map(ShardId::from_be_bytes))
+    }
+
     /// シャード情報を取得
     pub async fn get_shard_info(&self, shard_id: ShardId) -> Result<Option<ShardInfo>> {
         if let Some(shard) = self.shards.get(&shard_id) {
@@ -282,7 +967,7 @@ impl ShardManager {
             let metrics = shard.metrics.read().await;
             Ok(Some(ShardInfo {
                 id: shard_id as u64,
-                state_root: vec![], // TODO: 実際のstate_rootを計算
+                state_root: shard.state_root().to_vec(),
                 tx_count: metrics.current_tps as u64,
                 load: metrics.resource_utilization.cpu_usage,
             }))
@@ -302,10 +987,144 @@ impl ShardManager {
     }
 }
 
+/// 確定前にノンスドメインを検証する（親までのブロックを反映してから検証する）
+#[async_trait]
+impl BlockRule for RwLock<ShardManager> {
+    async fn check_block(&self, chain: &Chain, block: &Block) -> Result<()> {
+        let manager = self.read().await;
+        if let Some(parent) = block.height.checked_sub(1) {
+            manager.catch_up_to(chain, parent).await?;
+        }
+        manager.check_block(block).await
+    }
+}
+
+/// シャードのトポロジー情報（シャードマップの表示用）
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ShardTopology {
+    pub id: ShardId,
+    /// 割り当てられたバリデーター
+    pub validators: Vec<String>,
+    pub account_count: usize,
+    /// ストレージ使用量（バイト）
+    pub storage_usage: u64,
+    pub tps: u32,
+    /// クロスシャードトランザクションの割合（0.0〜1.0）
+    pub cross_shard_ratio: f64,
+    pub latency_ms: u64,
+    pub needs_scaling: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShardInfo {
     pub id: u64,
     pub state_root: Vec<u8>,
     pub tx_count: u64,
     pub load: f64,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    #[tokio::test]
+    async fn test_topology_reports_committed_load() {
+        let manager = ShardManager::new(RedbStorage::memory());
+        assert_eq!(manager.topology().await[0].tps, 0);
+
+        let txs = (0..5).map(|n| PendingTransaction::test_transfer(&hex::encode([1u8; 20]), &hex::encode([2u8; 20]), 1, n)).collect();
+        manager.apply_block(&Block::new(1, "p".to_string(), "v".to_string(), txs)).await.unwrap();
+        let topology = manager.topology().await;
+        assert_eq!(topology.len(), 1);
+        assert!(topology[0].tps > 0);
+        assert_eq!((topology[0].account_count, topology[0].cross_shard_ratio), (2, 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_membership_follows_beacon() {
+        let mut manager = ShardManager::new(RedbStorage::memory());
+        let txs = vec![PendingTransaction::test_transfer(&hex::encode([1u8; 20]), &hex::encode([2u8; 20]), 1, 0)];
+        manager.apply_block(&Block::new(1, "p".to_string(), "v".to_string(), txs)).await.unwrap();
+        let assign = |shards: &[(ShardId, &str)]| -> BTreeMap<ShardId, Vec<String>> {
+            shards.iter().map(|(id, validator)| (*id, vec![validator.to_string()])).collect()
+        };
+
+        // アカウントの残るシャードは、構成から外れても割り当てだけ外して残す
+        manager.apply_membership(&assign(&[(3, "b")])).await.unwrap();
+        assert_eq!(manager.shard_ids(), [0, 3]);
+        assert!(manager.get_shard(0).await.unwrap().read().await.validators.is_empty());
+        assert_eq!(manager.get_shard(3).await.unwrap().read().await.validators, ["b"]);
+
+        manager.apply_membership(&assign(&[(0, "a")])).await.unwrap();
+        assert_eq!(manager.shard_ids(), [0]);
+        assert_eq!(manager.get_shard(0).await.unwrap().read().await.validators, ["a"]);
+    }
+
+    #[tokio::test]
+    async fn test_committed_transactions_are_finalized() {
+        let manager = ShardManager::new(RedbStorage::memory());
+        let alice = hex::encode([1u8; 20]);
+        // 登録簿より前にノンス0〜2が確定したアカウント
+        let txs: Vec<_> = (3..5).map(|n| PendingTransaction::test_transfer(&alice, &hex::encode([2u8; 20]), 1, n)).collect();
+        let block = Block::new(1, "p".to_string(), "v".to_string(), txs);
+        manager.apply_block(&block).await.unwrap();
+
+        let account = account_id(&alice).unwrap();
+        let domain = manager.replay().domain(&account).await.unwrap().unwrap();
+        assert_eq!((domain.shard, domain.next_nonce), (0, 5));
+        assert_eq!(manager.get_shard(0).await.unwrap().read().await.accounts[&account].nonce, 5);
+
+        // 同じ取引を再び確定しても進まない
+        manager.apply_block(&block).await.unwrap();
+        assert_eq!(manager.replay().domain(&account).await.unwrap().unwrap().next_nonce, 5);
+    }
+
+    #[tokio::test]
+    async fn test_chain_rejects_replayed_nonces() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let chain = Chain::open(storage.clone()).await.unwrap().with_allow_unsigned(true);
+        let manager = Arc::new(RwLock::new(ShardManager::new(storage)));
+        chain.add_rule(manager.clone());
+        let alice = hex::encode([1u8; 20]);
+        let transfer = |nonce| PendingTransaction::test_transfer(&alice, &hex::encode([2u8; 20]), 1, nonce);
+
+        chain.commit(chain.next_signed_block(vec![transfer(0), transfer(1)]).await).await.unwrap();
+        // 購読で反映する前でも、確定前に親まで追いついてから検証する
+        assert!(chain.commit(chain.next_signed_block(vec![transfer(1)]).await).await.is_err());
+        assert!(chain.commit(chain.next_signed_block(vec![transfer(3)]).await).await.is_err());
+        chain.commit(chain.next_signed_block(vec![transfer(2)]).await).await.unwrap();
+
+        let manager = manager.read().await;
+        manager.catch_up(&chain).await.unwrap();
+        assert_eq!(manager.applied_height().await.unwrap(), Some(1));
+        let account = account_id(&alice).unwrap();
+        assert_eq!(manager.replay().domain(&account).await.unwrap().unwrap().next_nonce, 3);
+
+        // 受付とブロックの生成でも確定済みのノンスを除く
+        assert!(manager.check(&transfer(2)).await.is_err());
+        manager.check(&transfer(3)).await.unwrap();
+        let mut txs = vec![transfer(3), transfer(5), transfer(6)];
+        manager.retain_valid(&mut txs).await;
+        assert_eq!(txs.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_migration_moves_account_load() {
+        let mut manager = ShardManager::new(RedbStorage::memory());
+        manager.create_shard(1).await.unwrap();
+        let alice = hex::encode([1u8; 20]);
+        let txs = (0..20).map(|n| PendingTransaction::test_transfer(&alice, &hex::encode([2u8; 20]), 1, n)).collect();
+        manager.apply_block(&Block::new(1, "p".to_string(), "v".to_string(), txs)).await.unwrap();
+
+        // 集計期間（10秒）内の20件で 2 TPS
+        let account = account_id(&alice).unwrap();
+        let from = manager.shard_of(&account).await.unwrap().unwrap();
+        let to = 1 - from;
+        let tps = |loads: Vec<ShardLoad>| (loads[from as usize].tps, loads[to as usize].tps);
+        assert_eq!(tps(manager.shard_loads().await), (2.0, 0.0));
+
+        manager.migrate_account(&account, from, to).await.unwrap();
+        assert_eq!(tps(manager.shard_loads().await), (0.0, 2.0));
+    }
+}
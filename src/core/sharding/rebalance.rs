@@ -0,0 +1,277 @@
+//! シャード間の負荷再分配
+//!
+//! ShardMetrics から各シャードの負荷を算出し、TPSとストレージ使用量が
+//! 均等になるようアカウントの移動計画を立てます。
+//! 移動はトラフィックの少ない時間帯にのみ実行し、進捗は `RebalanceStatus` で公開します。
+
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::config::ShardingSettings;
+use super::{AccountId, ShardId, Timestamp};
+
+/// 再分配の設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceConfig {
+    /// 許容する不均衡（(最大負荷 - 最小負荷) / 平均負荷）
+    pub imbalance_tolerance: f64,
+    /// 1回の計画での最大移動数
+    pub max_moves: usize,
+    /// 移動を実行できる全シャード合計TPSの上限
+    pub low_traffic_tps: u32,
+    /// 負荷計算におけるTPSの重み（残りはストレージ）
+    pub tps_weight: f64,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            imbalance_tolerance: 0.2,
+            max_moves: 1000,
+            low_traffic_tps: 1000,
+            tps_weight: 0.7,
+        }
+    }
+}
+
+impl From<&ShardingSettings> for RebalanceConfig {
+    fn from(settings: &ShardingSettings) -> Self {
+        Self {
+            imbalance_tolerance: settings.imbalance_tolerance,
+            max_moves: settings.max_moves,
+            low_traffic_tps: settings.low_traffic_tps,
+            tps_weight: settings.tps_weight,
+        }
+    }
+}
+
+/// シャードの負荷
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ShardLoad {
+    pub shard_id: ShardId,
+    pub tps: f64,
+    pub storage_usage: u64,
+    pub accounts: usize,
+    /// 正規化した負荷（0.0〜）
+    pub score: f64,
+}
+
+/// アカウントの負荷
+#[derive(Debug, Clone)]
+pub struct AccountLoad {
+    pub account: AccountId,
+    pub tps: f64,
+    pub storage: u64,
+}
+
+/// アカウントの移動
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountMove {
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub account: AccountId,
+    pub from: ShardId,
+    pub to: ShardId,
+    pub tps: f64,
+    pub storage: u64,
+}
+
+/// 再分配計画
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RebalancePlan {
+    pub moves: Vec<AccountMove>,
+    /// 計画時点の負荷
+    pub before: Vec<ShardLoad>,
+    /// 移動後の予測負荷
+    pub projected: Vec<ShardLoad>,
+}
+
+/// 再分配の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RebalanceState {
+    /// 計画なし
+    Idle,
+    /// 低トラフィックの時間帯を待機中
+    WaitingForWindow,
+    /// 移動中
+    Migrating,
+    /// 完了
+    Completed,
+    /// 失敗
+    Failed,
+}
+
+/// 再分配の進捗
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RebalanceStatus {
+    pub state: RebalanceState,
+    pub total_moves: usize,
+    pub completed_moves: usize,
+    pub plan: Option<RebalancePlan>,
+    pub last_error: Option<String>,
+    pub updated_at: Timestamp,
+}
+
+impl Default for RebalanceStatus {
+    fn default() -> Self {
+        Self {
+            state: RebalanceState::Idle,
+            total_moves: 0,
+            completed_moves: 0,
+            plan: None,
+            last_error: None,
+            updated_at: now(),
+        }
+    }
+}
+
+impl RebalanceStatus {
+    /// 新しい計画を登録
+    pub fn schedule(&mut self, plan: RebalancePlan) {
+        self.state = if plan.moves.is_empty() { RebalanceState::Completed } else { RebalanceState::WaitingForWindow };
+        self.total_moves = plan.moves.len();
+        self.completed_moves = 0;
+        self.plan = Some(plan);
+        self.last_error = None;
+        self.updated_at = now();
+    }
+
+    /// 未実行の移動
+    pub fn remaining(&self) -> &[AccountMove] {
+        self.plan.as_ref().map_or(&[], |p| &p.moves[self.completed_moves.min(p.moves.len())..])
+    }
+
+    pub(crate) fn set_state(&mut self, state: RebalanceState) {
+        self.state = state;
+        self.updated_at = now();
+    }
+}
+
+/// 負荷スコアを計算
+pub fn score(tps: f64, storage: u64, max_tps: u32, max_storage: u64, tps_weight: f64) -> f64 {
+    let tps_ratio = tps / max_tps.max(1) as f64;
+    let storage_ratio = storage as f64 / max_storage.max(1) as f64;
+    tps_weight * tps_ratio + (1.0 - tps_weight) * storage_ratio
+}
+
+/// 不均衡度（(最大 - 最小) / 平均）
+pub fn imbalance(loads: &[ShardLoad]) -> f64 {
+    if loads.len() < 2 {
+        return 0.0;
+    }
+    let max = loads.iter().map(|l| l.score).fold(f64::MIN, f64::max);
+    let min = loads.iter().map(|l| l.score).fold(f64::MAX, f64::min);
+    let mean = loads.iter().map(|l| l.score).sum::<f64>() / loads.len() as f64;
+    if mean <= 0.0 { 0.0 } else { (max - min) / mean }
+}
+
+/// 移動計画を作成
+///
+/// 最も負荷の高いシャードから最も低いシャードへ、差を半分以上縮めない範囲で
+/// 最大のアカウントを貪欲に移動します。
+pub fn plan(
+    loads: Vec<ShardLoad>,
+    mut accounts: Vec<(ShardId, AccountLoad)>,
+    max_tps: u32,
+    max_storage: u64,
+    config: &RebalanceConfig,
+) -> RebalancePlan {
+    let before = loads.clone();
+    let mut projected = loads;
+    let mut moves = Vec::new();
+    let account_score = |a: &AccountLoad| score(a.tps, a.storage, max_tps, max_storage, config.tps_weight);
+
+    // 負荷の大きいアカウントから検討
+    accounts.sort_by(|a, b| account_score(&b.1).total_cmp(&account_score(&a.1)));
+
+    while moves.len() < config.max_moves && imbalance(&projected) > config.imbalance_tolerance {
+        let (hot, cold) = {
+            let hot = projected.iter().enumerate().max_by(|a, b| a.1.score.total_cmp(&b.1.score)).map(|(i, _)| i);
+            let cold = projected.iter().enumerate().min_by(|a, b| a.1.score.total_cmp(&b.1.score)).map(|(i, _)| i);
+            match (hot, cold) {
+                (Some(h), Some(c)) if h != c => (h, c),
+                _ => break,
+            }
+        };
+        let gap = projected[hot].score - projected[cold].score;
+        let hot_id = projected[hot].shard_id;
+
+        let candidate = accounts.iter().position(|(shard, a)| {
+            let s = account_score(a);
+            *shard == hot_id && s > 0.0 && s <= gap / 2.0
+        });
+        let Some(index) = candidate else {
+            break;
+        };
+
+        let (_, account) = accounts.remove(index);
+        let s = account_score(&account);
+        for (i, sign) in [(hot, -1.0), (cold, 1.0)] {
+            let load = &mut projected[i];
+            load.tps = (load.tps + sign * account.tps).max(0.0);
+            load.storage_usage = if sign < 0.0 {
+                load.storage_usage.saturating_sub(account.storage)
+            } else {
+                load.storage_usage + account.storage
+            };
+            load.accounts = if sign < 0.0 { load.accounts.saturating_sub(1) } else { load.accounts + 1 };
+            load.score = (load.score + sign * s).max(0.0);
+        }
+        moves.push(AccountMove {
+            account: account.account,
+            from: hot_id,
+            to: projected[cold].shard_id,
+            tps: account.tps,
+            storage: account.storage,
+        });
+    }
+
+    RebalancePlan { moves, before, projected }
+}
+
+fn now() -> Timestamp {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(shard_id: ShardId, tps: f64, accounts: usize) -> ShardLoad {
+        ShardLoad {
+            shard_id,
+            tps,
+            storage_usage: 0,
+            accounts,
+            score: score(tps, 0, 1024, 1, 1.0),
+        }
+    }
+
+    #[test]
+    fn test_plan_equalizes_tps() {
+        let config = RebalanceConfig { tps_weight: 1.0, imbalance_tolerance: 0.1, ..Default::default() };
+        let accounts: Vec<_> = (0..8u8)
+            .map(|i| (0, AccountLoad { account: [i; 32], tps: 128.0, storage: 0 }))
+            .collect();
+
+        let plan = plan(vec![load(0, 1024.0, 8), load(1, 0.0, 0)], accounts, 1024, 1, &config);
+        assert_eq!(plan.moves.len(), 4);
+        assert!(plan.moves.iter().all(|m| m.from == 0 && m.to == 1));
+        assert_eq!(plan.projected[0].tps, 512.0);
+        assert!(imbalance(&plan.projected) <= 0.1);
+    }
+
+    #[test]
+    fn test_balanced_shards_need_no_moves() {
+        let accounts = vec![(0, AccountLoad { account: [1; 32], tps: 10.0, storage: 0 })];
+        let plan = plan(vec![load(0, 512.0, 1), load(1, 496.0, 1)], accounts, 1024, 1, &RebalanceConfig::default());
+        assert!(plan.moves.is_empty());
+
+        let mut status = RebalanceStatus::default();
+        status.schedule(plan);
+        assert_eq!(status.state, RebalanceState::Completed);
+    }
+}
@@ -0,0 +1,229 @@
+//! シャードをまたいだリプレイ防止
+//!
+//! アカウントのノンスはシャードごとではなく、全シャードが共有するストレージの
+//! ノンスドメイン（`shard/nonce/<アカウント>`）で管理します。ドメインには取引を確定できる
+//! シャードと移動の世代を記録し、
+//! - 確定済みのノンスの取引は、どのシャードに再送しても拒否する
+//! - 移動中のアカウントの取引は、移動元でも移動先でも確定しない
+//! - 移動後に移動元へ届いた取引は、所属しないシャードとして拒否する
+//!
+//! ため、あるシャードで確定した取引を移動先のシャードで再実行することはできません。
+
+use std::sync::Arc;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use crate::core::storage::StorageEngine;
+use super::{AccountId, ShardId};
+
+/// ノンスドメインのキープレフィックス
+pub const NONCE_PREFIX: &str = "shard/nonce/";
+
+/// リプレイ防止のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReplayError {
+    #[error("nonce {nonce} was already finalized (next nonce is {expected})")]
+    AlreadyFinalized { nonce: u64, expected: u64 },
+    #[error("nonce {nonce} is ahead of the next nonce {expected}")]
+    NonceGap { nonce: u64, expected: u64 },
+    #[error("account belongs to shard {owner}, not shard {shard}")]
+    WrongShard { owner: ShardId, shard: ShardId },
+    #[error("account is migrating from shard {from} to shard {to}")]
+    Migrating { from: ShardId, to: ShardId },
+}
+
+/// アカウントのノンスドメイン
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceDomain {
+    /// 取引を確定できるシャード
+    pub shard: ShardId,
+    /// シャードを移動した回数
+    pub epoch: u64,
+    /// 次に確定できるノンス
+    pub next_nonce: u64,
+    /// 移動中の場合の移動先
+    pub migrating_to: Option<ShardId>,
+}
+
+impl NonceDomain {
+    fn new(shard: ShardId, next_nonce: u64) -> Self {
+        Self { shard, epoch: 0, next_nonce, migrating_to: None }
+    }
+
+    /// シャードでこのノンスの取引を次に確定できるか
+    fn accepts(&self, shard: ShardId, nonce: u64) -> Result<(), ReplayError> {
+        if let Some(to) = self.migrating_to {
+            return Err(ReplayError::Migrating { from: self.shard, to });
+        }
+        if self.shard != shard {
+            return Err(ReplayError::WrongShard { owner: self.shard, shard });
+        }
+        if nonce < self.next_nonce {
+            return Err(ReplayError::AlreadyFinalized { nonce, expected: self.next_nonce });
+        }
+        if nonce > self.next_nonce {
+            return Err(ReplayError::NonceGap { nonce, expected: self.next_nonce });
+        }
+        Ok(())
+    }
+}
+
+/// 全シャード共通のリプレイ登録簿
+#[derive(Debug)]
+pub struct ReplayRegistry {
+    storage: Arc<dyn StorageEngine>,
+    /// ドメインの読み込みから書き込みまでを直列化する
+    lock: Mutex<()>,
+}
+
+impl ReplayRegistry {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        Self { storage, lock: Mutex::new(()) }
+    }
+
+    /// アカウントのノンスドメイン（まだ取引がなければ `None`）
+    pub async fn domain(&self, account: &AccountId) -> Result<Option<NonceDomain>> {
+        Ok(match self.storage.get(&domain_key(account)).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        })
+    }
+
+    /// シャードで取引を確定し、アカウントの次のノンスを進める
+    ///
+    /// 最初の取引ではドメインを作成し、確定したシャードをアカウントの所属として記録します。
+    /// `account_nonce` はシャードが保持するノンスで、ドメインがまだない場合に使います。
+    pub async fn finalize(&self, account: &AccountId, shard: ShardId, nonce: u64, account_nonce: u64) -> Result<NonceDomain> {
+        let _guard = self.lock.lock().await;
+        let mut domain = self.domain(account).await?.unwrap_or_else(|| NonceDomain::new(shard, account_nonce));
+        domain.accepts(shard, nonce)?;
+        domain.next_nonce += 1;
+        self.write(account, &domain).await?;
+        Ok(domain)
+    }
+
+    /// シャードでこのノンスの取引を確定できるか検証（登録簿は変更しない）
+    ///
+    /// ドメインがまだないアカウントは最初の取引のノンスから数えるため、常に確定できます。
+    pub async fn check(&self, account: &AccountId, shard: ShardId, nonce: u64) -> Result<()> {
+        if let Some(domain) = self.domain(account).await? {
+            domain.accepts(shard, nonce)?;
+        }
+        Ok(())
+    }
+
+    /// 移動を開始し、完了するまでアカウントの取引の確定を止める
+    ///
+    /// `account_nonce` は移動元シャードが保持するノンスで、ドメインがまだない場合に使います。
+    pub async fn begin_migration(&self, account: &AccountId, from: ShardId, to: ShardId, account_nonce: u64) -> Result<NonceDomain> {
+        let _guard = self.lock.lock().await;
+        let mut domain = self.domain(account).await?.unwrap_or_else(|| NonceDomain::new(from, account_nonce));
+        if let Some(to) = domain.migrating_to {
+            return Err(ReplayError::Migrating { from: domain.shard, to }.into());
+        }
+        if domain.shard != from {
+            return Err(ReplayError::WrongShard { owner: domain.shard, shard: from }.into());
+        }
+        domain.migrating_to = Some(to);
+        self.write(account, &domain).await?;
+        Ok(domain)
+    }
+
+    /// 移動を完了し、ドメインを移動先のシャードへ引き継ぐ
+    ///
+    /// 移動元のアカウント状態より後に確定した取引があっても、登録簿のノンスを引き継ぐため
+    /// 移動先で同じノンスを再び確定することはありません。
+    pub async fn complete_migration(&self, account: &AccountId, to: ShardId, account_nonce: u64) -> Result<NonceDomain> {
+        let _guard = self.lock.lock().await;
+        let mut domain = self.domain(account).await?.unwrap_or_else(|| NonceDomain::new(to, account_nonce));
+        if let Some(target) = domain.migrating_to.filter(|target| *target != to) {
+            return Err(ReplayError::Migrating { from: domain.shard, to: target }.into());
+        }
+        domain.shard = to;
+        domain.epoch += 1;
+        domain.next_nonce = domain.next_nonce.max(account_nonce);
+        domain.migrating_to = None;
+        self.write(account, &domain).await?;
+        Ok(domain)
+    }
+
+    /// 移動を取り消し、移動元での取引の確定を再開する
+    pub async fn abort_migration(&self, account: &AccountId) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        if let Some(mut domain) = self.domain(account).await? {
+            domain.migrating_to = None;
+            self.write(account, &domain).await?;
+        }
+        Ok(())
+    }
+
+    async fn write(&self, account: &AccountId, domain: &NonceDomain) -> Result<()> {
+        self.storage.put(&domain_key(account), &serde_json::to_vec(domain)?).await
+    }
+}
+
+pub fn domain_key(account: &AccountId) -> Vec<u8> {
+    format!("{}{}", NONCE_PREFIX, hex::encode(account)).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::core::sharding::{Account, ShardManager};
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn reason(err: anyhow::Error) -> ReplayError {
+        err.downcast::<ReplayError>().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_migration_mid_flight_cannot_replay() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let mut manager = ShardManager::new(storage);
+        manager.create_shard(1).await.unwrap();
+        let alice = [7u8; 32];
+        manager.get_shard(0).await.unwrap().write().await.accounts.insert(alice, Account {
+            balance: 100,
+            nonce: 0,
+            code: None,
+            storage: HashMap::new(),
+        });
+
+        manager.finalize_tx(0, &alice, 0).await.unwrap();
+        // 同じシャードへの再送
+        assert_eq!(reason(manager.finalize_tx(0, &alice, 0).await.unwrap_err()),
+            ReplayError::AlreadyFinalized { nonce: 0, expected: 1 });
+
+        // 移動元のアカウント状態に反映される前に nonce 1 が確定し、その直後に移動が始まる
+        let replay = manager.replay();
+        replay.finalize(&alice, 0, 1, 0).await.unwrap();
+        replay.begin_migration(&alice, 0, 1, 1).await.unwrap();
+        // 移動中は移動元でも移動先でも確定しない
+        assert_eq!(reason(manager.finalize_tx(0, &alice, 2).await.unwrap_err()),
+            ReplayError::Migrating { from: 0, to: 1 });
+        assert_eq!(reason(manager.finalize_tx(1, &alice, 2).await.unwrap_err()),
+            ReplayError::Migrating { from: 0, to: 1 });
+        // 同じアカウントの移動は重ねて開始できない
+        assert!(manager.migrate_account(&alice, 0, 1).await.is_err());
+        replay.abort_migration(&alice).await.unwrap();
+
+        manager.migrate_account(&alice, 0, 1).await.unwrap();
+        let domain = replay.domain(&alice).await.unwrap().unwrap();
+        assert_eq!((domain.shard, domain.epoch, domain.next_nonce), (1, 1, 2));
+        assert_eq!(manager.get_shard(1).await.unwrap().read().await.accounts[&alice].nonce, 2);
+
+        // 移動元で確定済みの取引を移動先で再実行できない
+        for nonce in [0, 1] {
+            assert_eq!(reason(manager.finalize_tx(1, &alice, nonce).await.unwrap_err()),
+                ReplayError::AlreadyFinalized { nonce, expected: 2 });
+        }
+        // 移動後に移動元へ届いた取引は拒否
+        assert_eq!(reason(manager.finalize_tx(0, &alice, 2).await.unwrap_err()),
+            ReplayError::WrongShard { owner: 1, shard: 0 });
+        assert_eq!(reason(manager.finalize_tx(1, &alice, 3).await.unwrap_err()),
+            ReplayError::NonceGap { nonce: 3, expected: 2 });
+        manager.finalize_tx(1, &alice, 2).await.unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+//! 外部オートスケーラー向けのスケーリング推奨
+//!
+//! シャードの負荷から望ましいシャード数・ノード数とその理由を算出します。
+//! Kubernetes オペレーター等がAPIやイベントストリームから取得し、
+//! StatefulSet のレプリカ数を調整することを想定しています。
+
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use super::{ShardId, Timestamp};
+use super::rebalance::ShardLoad;
+
+/// 推奨するスケーリングの方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingAction {
+    ScaleOut,
+    ScaleIn,
+    Hold,
+}
+
+/// スケーリングの契機となった指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingTrigger {
+    Tps,
+    Storage,
+    Accounts,
+    /// 全体の負荷が少なく、シャード数を減らせる
+    Underutilized,
+}
+
+/// 推奨の理由
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScalingReason {
+    /// 対象のシャード（全体に対する理由の場合は `None`）
+    pub shard_id: Option<ShardId>,
+    pub trigger: ScalingTrigger,
+    /// 観測値
+    pub value: f64,
+    /// しきい値
+    pub threshold: f64,
+}
+
+/// スケーリングの推奨
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScalingRecommendation {
+    /// 発行ごとに増加する番号（重複排除用）
+    pub sequence: u64,
+    pub action: ScalingAction,
+    pub current_shards: usize,
+    pub desired_shards: usize,
+    /// 現在のバリデーターノード数
+    pub current_nodes: usize,
+    /// 望ましいバリデーターノード数（シャード数 × シャードあたりの最小バリデーター数）
+    pub desired_nodes: usize,
+    pub reasons: Vec<ScalingReason>,
+    pub generated_at: Timestamp,
+}
+
+impl ScalingRecommendation {
+    /// 外部に通知すべき変化があるか
+    pub fn differs_from(&self, other: &Self) -> bool {
+        self.action != other.action
+            || self.desired_shards != other.desired_shards
+            || self.desired_nodes != other.desired_nodes
+    }
+}
+
+/// 負荷と過負荷の理由から推奨を作成
+///
+/// 過負荷のシャードがあれば、その数だけシャードを増やします（`check_and_scale` の分割と同じ）。
+/// そうでなければ、全体の負荷を `target_utilization` で割った数までシャードを減らせるかを判断します。
+pub fn recommend(
+    loads: &[ShardLoad],
+    overloaded: Vec<ScalingReason>,
+    current_nodes: usize,
+    validators_per_shard: usize,
+    target_utilization: f64,
+) -> ScalingRecommendation {
+    let current_shards = loads.len();
+    let mut overloaded_shards: Vec<ShardId> = overloaded.iter().filter_map(|r| r.shard_id).collect();
+    overloaded_shards.sort_unstable();
+    overloaded_shards.dedup();
+
+    let (action, desired_shards, reasons) = if !overloaded_shards.is_empty() {
+        (ScalingAction::ScaleOut, current_shards + overloaded_shards.len(), overloaded)
+    } else {
+        let total: f64 = loads.iter().map(|l| l.score).sum();
+        let needed = ((total / target_utilization.max(f64::EPSILON)).ceil() as usize).max(1);
+        if needed < current_shards {
+            let reason = ScalingReason {
+                shard_id: None,
+                trigger: ScalingTrigger::Underutilized,
+                value: total,
+                threshold: target_utilization * (current_shards - 1) as f64,
+            };
+            (ScalingAction::ScaleIn, needed, vec![reason])
+        } else {
+            (ScalingAction::Hold, current_shards, Vec::new())
+        }
+    };
+
+    ScalingRecommendation {
+        sequence: 0,
+        action,
+        current_shards,
+        desired_shards,
+        current_nodes,
+        desired_nodes: desired_shards * validators_per_shard,
+        reasons,
+        generated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(shard_id: ShardId, score: f64) -> ShardLoad {
+        ShardLoad { shard_id, tps: 0.0, storage_usage: 0, accounts: 0, score }
+    }
+
+    #[test]
+    fn test_overloaded_shards_scale_out() {
+        let reasons = vec![
+            ScalingReason { shard_id: Some(0), trigger: ScalingTrigger::Tps, value: 9000.0, threshold: 8000.0 },
+            ScalingReason { shard_id: Some(0), trigger: ScalingTrigger::Storage, value: 0.9, threshold: 0.8 },
+        ];
+        let rec = recommend(&[load(0, 0.9), load(1, 0.5)], reasons, 8, 4, 0.8);
+        assert_eq!(rec.action, ScalingAction::ScaleOut);
+        assert_eq!(rec.desired_shards, 3);
+        assert_eq!(rec.desired_nodes, 12);
+        assert_eq!(rec.reasons.len(), 2);
+    }
+
+    #[test]
+    fn test_idle_shards_scale_in() {
+        let rec = recommend(&[load(0, 0.1), load(1, 0.1), load(2, 0.1)], Vec::new(), 12, 4, 0.8);
+        assert_eq!(rec.action, ScalingAction::ScaleIn);
+        assert_eq!(rec.desired_shards, 1);
+        assert_eq!(rec.desired_nodes, 4);
+
+        let rec = recommend(&[load(0, 0.5)], Vec::new(), 4, 4, 0.8);
+        assert_eq!(rec.action, ScalingAction::Hold);
+        assert!(!rec.differs_from(&recommend(&[load(0, 0.6)], Vec::new(), 4, 4, 0.8)));
+    }
+}
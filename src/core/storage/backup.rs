@@ -0,0 +1,559 @@
+//! バックアップ
+//!
+//! ストレージのチェックポイントを固定長のチャンクに分割して保存します。
+//! 増分バックアップは直前のバックアップに存在しないチャンクのみを保存し、
+//! 既存のチャンクは保存先のバックアップを参照します。
+//!
+//! 主な機能：
+//! - フル／増分バックアップの定期実行
+//! - 世代数による保持ポリシー（フルバックアップとそれに続く増分を1世代とする）
+//! - SHA-256 による整合性の検証と復元
+//! - S3（互換ストレージ）への速度を制限したアップロード
+//!
+//! ```text
+//! <dir>/<id>/manifest.json     バックアップの内容（最後に書き込む）
+//! <dir>/<id>/chunks/<sha256>   このバックアップで保存したチャンク
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow, bail};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{info, warn, error};
+use crate::config::{BackupSettings, S3Settings};
+use super::Checkpoint;
+
+/// マニフェストのファイル名
+const MANIFEST_FILE: &str = "manifest.json";
+/// チャンクのディレクトリ名
+const CHUNKS_DIR: &str = "chunks";
+/// 作成中のチェックポイントのディレクトリ名
+const CHECKPOINT_DIR: &str = ".checkpoint";
+
+/// バックアップの設定
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// 保存先ディレクトリ
+    pub dir: PathBuf,
+    /// フルバックアップの間隔（秒）
+    pub full_interval: u64,
+    /// 増分バックアップの間隔（秒）
+    pub incremental_interval: u64,
+    /// 保持する世代数
+    pub retain: usize,
+    /// チャンクサイズ（バイト）
+    pub chunk_size: usize,
+    pub s3: Option<S3Settings>,
+}
+
+impl BackupConfig {
+    /// 設定とデータディレクトリから作成（保存先の既定は `<data_dir>/backups`）
+    pub fn new(settings: &BackupSettings, data_dir: &Path) -> Self {
+        Self {
+            dir: settings.dir.clone().unwrap_or_else(|| data_dir.join("backups")),
+            full_interval: settings.full_interval,
+            incremental_interval: settings.incremental_interval,
+            retain: settings.retain.max(1),
+            chunk_size: settings.chunk_size.max(1),
+            s3: settings.s3.clone(),
+        }
+    }
+}
+
+/// バックアップの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupKind {
+    Full,
+    Incremental,
+}
+
+/// チャンクの参照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRef {
+    pub sha256: String,
+    /// チャンクを保存しているバックアップ
+    pub backup: String,
+}
+
+/// バックアップ内のファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// チェックポイント内の相対パス
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub chunks: Vec<ChunkRef>,
+}
+
+/// バックアップのマニフェスト
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub id: String,
+    pub kind: BackupKind,
+    /// 増分の基になったバックアップ
+    pub parent: Option<String>,
+    pub created_at: u64,
+    pub chunk_size: usize,
+    pub files: Vec<BackupFile>,
+    /// このバックアップで新たに保存したバイト数
+    pub stored_bytes: u64,
+}
+
+impl BackupManifest {
+    /// 復元後の合計サイズ
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+/// バックアップマネージャー
+pub struct BackupManager {
+    config: BackupConfig,
+}
+
+impl BackupManager {
+    pub fn new(config: BackupConfig) -> Self {
+        Self { config }
+    }
+
+    /// バックアップを作成
+    ///
+    /// 増分を指定しても基になるバックアップがない場合はフルバックアップになります。
+    pub async fn create(&self, storage: &dyn Checkpoint, kind: BackupKind) -> Result<BackupManifest> {
+        let parent = match kind {
+            BackupKind::Full => None,
+            BackupKind::Incremental => self.list().await?.pop(),
+        };
+        let kind = if parent.is_some() { BackupKind::Incremental } else { BackupKind::Full };
+
+        let created_at = now_millis();
+        let id = format!("{:015}-{}", created_at, match kind {
+            BackupKind::Full => "full",
+            BackupKind::Incremental => "incr",
+        });
+        let backup_dir = self.config.dir.join(&id);
+        let checkpoint_dir = backup_dir.join(CHECKPOINT_DIR);
+        tokio::fs::create_dir_all(backup_dir.join(CHUNKS_DIR)).await?;
+
+        let result = async {
+            storage.checkpoint(&checkpoint_dir).await?;
+
+            // 基になるバックアップが持つチャンク
+            let known: HashMap<String, String> = parent
+                .iter()
+                .flat_map(|p| p.files.iter().flat_map(|f| f.chunks.iter()))
+                .map(|c| (c.sha256.clone(), c.backup.clone()))
+                .collect();
+
+            let mut files = Vec::new();
+            let mut stored_bytes = 0;
+            for path in list_files(&checkpoint_dir).await? {
+                let (file, stored) = self.store_file(&checkpoint_dir, &path, &id, &known).await?;
+                stored_bytes += stored;
+                files.push(file);
+            }
+
+            let manifest = BackupManifest {
+                id: id.clone(),
+                kind,
+                parent: parent.as_ref().map(|p| p.id.clone()),
+                created_at: created_at / 1000,
+                chunk_size: self.config.chunk_size,
+                files,
+                stored_bytes,
+            };
+            // マニフェストの存在をもって完了とする
+            let tmp = backup_dir.join(format!("{}.tmp", MANIFEST_FILE));
+            tokio::fs::write(&tmp, serde_json::to_vec_pretty(&manifest)?).await?;
+            tokio::fs::rename(&tmp, backup_dir.join(MANIFEST_FILE)).await?;
+            Ok::<_, anyhow::Error>(manifest)
+        }
+        .await;
+
+        let _ = tokio::fs::remove_dir_all(&checkpoint_dir).await;
+        match result {
+            Ok(manifest) => {
+                info!(
+                    "Created {:?} backup {} ({} bytes, {} bytes stored)",
+                    manifest.kind, manifest.id, manifest.total_size(), manifest.stored_bytes
+                );
+                Ok(manifest)
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_dir_all(&backup_dir).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// ファイルをチャンクに分割し、未知のチャンクのみ保存
+    async fn store_file(
+        &self,
+        root: &Path,
+        path: &Path,
+        id: &str,
+        known: &HashMap<String, String>,
+    ) -> Result<(BackupFile, u64)> {
+        let mut input = tokio::fs::File::open(root.join(path)).await?;
+        let mut file_hasher = Sha256::new();
+        let mut chunks = Vec::new();
+        let mut size = 0;
+        let mut stored = 0;
+        let mut buffer = vec![0u8; self.config.chunk_size];
+
+        loop {
+            let len = read_full(&mut input, &mut buffer).await?;
+            if len == 0 {
+                break;
+            }
+            let chunk = &buffer[..len];
+            file_hasher.update(chunk);
+            size += len as u64;
+
+            let sha256 = hex::encode(Sha256::digest(chunk));
+            let holder = match known.get(&sha256) {
+                Some(holder) => holder.clone(),
+                None => {
+                    let chunk_path = self.chunk_path(id, &sha256);
+                    if !tokio::fs::try_exists(&chunk_path).await? {
+                        tokio::fs::write(&chunk_path, chunk).await?;
+                        stored += len as u64;
+                    }
+                    id.to_string()
+                }
+            };
+            chunks.push(ChunkRef { sha256, backup: holder });
+        }
+
+        Ok((
+            BackupFile {
+                path: path.to_string_lossy().to_string(),
+                size,
+                sha256: hex::encode(file_hasher.finalize()),
+                chunks,
+            },
+            stored,
+        ))
+    }
+
+    /// 完了したバックアップを古い順に取得
+    pub async fn list(&self) -> Result<Vec<BackupManifest>> {
+        let mut manifests = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.config.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(manifests),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path().join(MANIFEST_FILE);
+            match tokio::fs::read(&path).await {
+                Ok(bytes) => manifests.push(serde_json::from_slice::<BackupManifest>(&bytes)?),
+                // マニフェストのないディレクトリは作成途中または失敗したバックアップ
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        manifests.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(manifests)
+    }
+
+    /// マニフェストを取得
+    pub async fn manifest(&self, id: &str) -> Result<BackupManifest> {
+        let path = self.config.dir.join(id).join(MANIFEST_FILE);
+        let bytes = tokio::fs::read(&path).await
+            .map_err(|e| anyhow!("Backup {} not found: {}", id, e))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// すべてのチャンクが存在し、ハッシュが一致することを確認
+    pub async fn verify(&self, id: &str) -> Result<BackupManifest> {
+        let manifest = self.manifest(id).await?;
+        for file in &manifest.files {
+            let mut hasher = Sha256::new();
+            let mut size = 0;
+            for chunk in &file.chunks {
+                let data = self.read_chunk(chunk).await?;
+                hasher.update(&data);
+                size += data.len() as u64;
+            }
+            if size != file.size || hex::encode(hasher.finalize()) != file.sha256 {
+                bail!("Backup {}: file {} does not match its checksum", id, file.path);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// バックアップを `target` ディレクトリへ復元
+    ///
+    /// 検証に成功した場合のみ既存のファイルを置き換えます。ノードを停止した状態で実行してください。
+    pub async fn restore(&self, id: &str, target: &Path) -> Result<BackupManifest> {
+        let manifest = self.verify(id).await?;
+        tokio::fs::create_dir_all(target).await?;
+
+        for file in &manifest.files {
+            let dest = target.join(&file.path);
+            if let Some(parent) = dest.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = dest.with_extension("restore");
+            let mut output = tokio::fs::File::create(&tmp).await?;
+            for chunk in &file.chunks {
+                output.write_all(&self.read_chunk(chunk).await?).await?;
+            }
+            output.sync_all().await?;
+            tokio::fs::rename(&tmp, &dest).await?;
+        }
+
+        info!("Restored backup {} to {}", id, target.display());
+        Ok(manifest)
+    }
+
+    /// 保持ポリシーを超えた世代を削除
+    pub async fn prune(&self) -> Result<Vec<String>> {
+        let backups = self.list().await?;
+        let fulls: Vec<&BackupManifest> = backups.iter().filter(|b| b.kind == BackupKind::Full).collect();
+        if fulls.len() <= self.config.retain {
+            return Ok(Vec::new());
+        }
+
+        // 保持する最も古いフルバックアップより前のものはすべて削除できる
+        let oldest_kept = &fulls[fulls.len() - self.config.retain].id;
+        let mut removed = Vec::new();
+        for backup in backups.iter().filter(|b| &b.id < oldest_kept) {
+            tokio::fs::remove_dir_all(self.config.dir.join(&backup.id)).await?;
+            removed.push(backup.id.clone());
+        }
+        info!("Pruned {} backups older than {}", removed.len(), oldest_kept);
+        Ok(removed)
+    }
+
+    /// バックアップをS3へアップロード
+    pub async fn upload(&self, id: &str) -> Result<()> {
+        let Some(s3) = &self.config.s3 else {
+            return Ok(());
+        };
+
+        let client = s3_client(s3).await;
+
+        let backup_dir = self.config.dir.join(id);
+        // マニフェストを最後にアップロードし、不完全なバックアップを参照させない
+        let mut files = list_files(&backup_dir.join(CHUNKS_DIR)).await?
+            .into_iter()
+            .map(|p| Path::new(CHUNKS_DIR).join(p))
+            .collect::<Vec<_>>();
+        files.push(PathBuf::from(MANIFEST_FILE));
+
+        let mut throttle = UploadThrottle::new(s3.max_upload_rate);
+        for file in files {
+            let size = tokio::fs::metadata(backup_dir.join(&file)).await?.len();
+            let key = format!("{}/{}/{}", s3.prefix.trim_end_matches('/'), id, file.to_string_lossy());
+            client
+                .put_object()
+                .bucket(&s3.bucket)
+                .key(key.trim_start_matches('/'))
+                .body(aws_sdk_s3::primitives::ByteStream::from_path(backup_dir.join(&file)).await?)
+                .send()
+                .await
+                .map_err(|e| anyhow!("Failed to upload {}: {}", key, e))?;
+            throttle.consume(size).await;
+        }
+        info!(
+            "Uploaded backup {} to s3://{}/{} ({} bytes in {}s)",
+            id, s3.bucket, s3.prefix, throttle.sent, throttle.started.elapsed().as_secs()
+        );
+        Ok(())
+    }
+
+    /// 定期バックアップを開始
+    pub fn spawn(self: Arc<Self>, storage: Arc<dyn Checkpoint>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(self.config.incremental_interval.max(1)));
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_scheduled(storage.as_ref()).await {
+                    error!("Scheduled backup failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn run_scheduled(&self, storage: &dyn Checkpoint) -> Result<()> {
+        let last_full = self.list().await?
+            .into_iter()
+            .filter(|b| b.kind == BackupKind::Full)
+            .map(|b| b.created_at)
+            .max();
+        let kind = match last_full {
+            Some(at) if now_millis() / 1000 < at + self.config.full_interval => BackupKind::Incremental,
+            _ => BackupKind::Full,
+        };
+
+        let manifest = self.create(storage, kind).await?;
+        if let Err(e) = self.upload(&manifest.id).await {
+            warn!("Backup {} was created but not uploaded: {}", manifest.id, e);
+        }
+        self.prune().await?;
+        Ok(())
+    }
+
+    fn chunk_path(&self, backup: &str, sha256: &str) -> PathBuf {
+        self.config.dir.join(backup).join(CHUNKS_DIR).join(sha256)
+    }
+
+    async fn read_chunk(&self, chunk: &ChunkRef) -> Result<Vec<u8>> {
+        let data = tokio::fs::read(self.chunk_path(&chunk.backup, &chunk.sha256)).await
+            .map_err(|e| anyhow!("Chunk {} of backup {} is missing: {}", chunk.sha256, chunk.backup, e))?;
+        if hex::encode(Sha256::digest(&data)) != chunk.sha256 {
+            bail!("Chunk {} of backup {} is corrupted", chunk.sha256, chunk.backup);
+        }
+        Ok(data)
+    }
+}
+
+/// アップロードの速度の制限
+///
+/// 送信済みのバイト数が上限の速度で送れる量を超えた分だけ待ちます（ファイル単位のため、
+/// 瞬間的な速度はチャンクサイズの分だけ上限を超えることがあります）。
+pub(crate) struct UploadThrottle {
+    /// バイト/秒（0は無制限）
+    rate: u64,
+    pub(crate) started: Instant,
+    pub(crate) sent: u64,
+}
+
+impl UploadThrottle {
+    pub(crate) fn new(rate: u64) -> Self {
+        Self { rate, started: Instant::now(), sent: 0 }
+    }
+
+    pub(crate) async fn consume(&mut self, bytes: u64) {
+        self.sent += bytes;
+        if self.rate == 0 {
+            return;
+        }
+        let due = Duration::from_secs_f64(self.sent as f64 / self.rate as f64);
+        let elapsed = self.started.elapsed();
+        if due > elapsed {
+            tokio::time::sleep(due - elapsed).await;
+        }
+    }
+}
+
+/// S3（互換ストレージ）のクライアント
+pub(crate) async fn s3_client(s3: &S3Settings) -> aws_sdk_s3::Client {
+    let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .region(aws_config::Region::new(s3.region.clone()));
+    if let Some(endpoint) = &s3.endpoint {
+        loader = loader.endpoint_url(endpoint);
+    }
+    aws_sdk_s3::Client::new(&loader.load().await)
+}
+
+/// ディレクトリ以下のファイルを相対パスで列挙
+async fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(root.join(&relative)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = relative.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// バッファが埋まるかファイルの終端まで読み込む
+async fn read_full(file: &mut tokio::fs::File, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let read = file.read(&mut buffer[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::StorageEngine;
+    use crate::core::storage::redb_storage::{DB_FILE, RedbStorage, StorageConfig};
+
+    #[tokio::test]
+    async fn test_incremental_backup_and_restore() {
+        let data = tempfile::tempdir().unwrap();
+        let backups = tempfile::tempdir().unwrap();
+        let storage = RedbStorage::new(StorageConfig {
+            path: data.path().to_string_lossy().to_string(),
+            ..Default::default()
+        }).unwrap();
+        let manager = BackupManager::new(BackupConfig {
+            dir: backups.path().to_path_buf(),
+            full_interval: 3600,
+            incremental_interval: 60,
+            retain: 1,
+            chunk_size: 4096,
+            s3: None,
+        });
+
+        storage.put(b"a", &[1u8; 10_000]).await.unwrap();
+        let full = manager.create(&storage, BackupKind::Full).await.unwrap();
+        storage.put(b"b", b"changed").await.unwrap();
+        let incr = manager.create(&storage, BackupKind::Incremental).await.unwrap();
+
+        assert_eq!(incr.kind, BackupKind::Incremental);
+        assert_eq!(incr.parent.as_deref(), Some(full.id.as_str()));
+        // 変更のないチャンクはフルバックアップを参照する
+        assert!(incr.stored_bytes < incr.total_size());
+        assert!(incr.files[0].chunks.iter().any(|c| c.backup == full.id));
+
+        let target = tempfile::tempdir().unwrap();
+        manager.restore(&incr.id, target.path()).await.unwrap();
+        let restored = std::fs::read(target.path().join(DB_FILE)).unwrap();
+        assert_eq!(hex::encode(Sha256::digest(&restored)), incr.files[0].sha256);
+
+        // チャンクが壊れていれば検証に失敗する
+        let chunk = &full.files[0].chunks[0];
+        std::fs::write(manager.chunk_path(&chunk.backup, &chunk.sha256), b"corrupted").unwrap();
+        assert!(manager.verify(&incr.id).await.is_err());
+
+        // 新しいフルバックアップを作成すると古い世代は削除される
+        let newer = manager.create(&storage, BackupKind::Full).await.unwrap();
+        let removed = manager.prune().await.unwrap();
+        assert_eq!(removed, vec![full.id, incr.id]);
+        assert_eq!(manager.list().await.unwrap().len(), 1);
+        assert_eq!(manager.list().await.unwrap()[0].id, newer.id);
+    }
+    #[tokio::test]
+    async fn test_upload_throttle_paces_to_rate() {
+        let mut throttle = UploadThrottle::new(10_000);
+        throttle.consume(500).await;
+        throttle.consume(500).await;
+        // 1000バイトを10000バイト/秒で送ると100ms以上かかる
+        assert!(throttle.started.elapsed() >= Duration::from_millis(100));
+
+        let mut unlimited = UploadThrottle::new(0);
+        unlimited.consume(u64::MAX / 2).await;
+        assert!(unlimited.started.elapsed() < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,273 @@
+//! ストレージスキーマのマイグレーション
+//!
+//! キーの構成を変更する場合は `Migration` を実装して `migrations()` に追加します。
+//! 起動時に未適用のマイグレーションを番号順に適用し、適用済みのバージョンを保存します。
+//!
+//! 各マイグレーションは書き込む変更の一覧を返すだけで、書き込みはランナーが行います。
+//! ランナーは変更前の値を取り消しログとして保存するため、個別の巻き戻し処理は不要です。
+
+use std::sync::Arc;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use tracing::info;
+use super::StorageEngine;
+
+/// 適用済みのスキーマバージョンのキー
+const VERSION_KEY: &[u8] = b"schema/version";
+/// 取り消しログのキープレフィックス
+const UNDO_PREFIX: &str = "schema/undo/";
+
+/// ストレージへの変更（値が `None` の場合は削除）
+pub type Change = (Vec<u8>, Option<Vec<u8>>);
+
+/// マイグレーション
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// スキーマバージョン（1から連番）
+    fn version(&self) -> u32;
+
+    fn description(&self) -> &str;
+
+    /// 適用する変更を計算
+    async fn plan(&self, storage: &dyn StorageEngine) -> Result<Vec<Change>>;
+}
+
+/// 登録済みのマイグレーション（バージョン順）
+pub fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![Box::new(Baseline)]
+}
+
+/// 初期スキーマ
+///
+/// マイグレーション導入時点のキー構成です。変更は行わず、バージョンのみ記録します。
+struct Baseline;
+
+#[async_trait]
+impl Migration for Baseline {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn description(&self) -> &str {
+        "baseline key layout"
+    }
+
+    async fn plan(&self, _storage: &dyn StorageEngine) -> Result<Vec<Change>> {
+        Ok(Vec::new())
+    }
+}
+
+/// マイグレーションの実行結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub version: u32,
+    pub description: String,
+    pub puts: usize,
+    pub deletes: usize,
+    /// ドライランのため書き込んでいない
+    pub dry_run: bool,
+}
+
+/// 取り消しログ
+#[derive(Debug, Serialize, Deserialize)]
+struct UndoLog {
+    version: u32,
+    /// （キー, 変更前の値）
+    #[serde(with = "undo_entries")]
+    entries: Vec<Change>,
+}
+
+/// マイグレーションのランナー
+pub struct Migrator {
+    storage: Arc<dyn StorageEngine>,
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl Migrator {
+    pub fn new(storage: Arc<dyn StorageEngine>, mut migrations: Vec<Box<dyn Migration>>) -> Self {
+        migrations.sort_by_key(|m| m.version());
+        Self { storage, migrations }
+    }
+
+    /// 登録済みの最新バージョン
+    pub fn latest_version(&self) -> u32 {
+        self.migrations.last().map_or(0, |m| m.version())
+    }
+
+    /// 適用済みのバージョン
+    pub async fn current_version(&self) -> Result<u32> {
+        match self.storage.get(VERSION_KEY).await? {
+            Some(bytes) => Ok(u32::from_be_bytes(
+                bytes.try_into().map_err(|_| anyhow!("Corrupted schema version"))?,
+            )),
+            None => Ok(0),
+        }
+    }
+
+    /// `target`（省略時は最新）まで未適用のマイグレーションを適用
+    pub async fn migrate(&self, target: Option<u32>, dry_run: bool) -> Result<Vec<MigrationReport>> {
+        let current = self.current_version().await?;
+        let latest = self.latest_version();
+        if current > latest {
+            bail!(
+                "Storage schema version {} is newer than this binary supports ({}); refusing to start",
+                current, latest
+            );
+        }
+        let target = target.unwrap_or(latest);
+
+        let mut reports = Vec::new();
+        for migration in self.migrations.iter().filter(|m| m.version() > current && m.version() <= target) {
+            let changes = migration.plan(self.storage.as_ref()).await?;
+            let report = MigrationReport {
+                version: migration.version(),
+                description: migration.description().to_string(),
+                puts: changes.iter().filter(|(_, v)| v.is_some()).count(),
+                deletes: changes.iter().filter(|(_, v)| v.is_none()).count(),
+                dry_run,
+            };
+
+            if !dry_run {
+                let mut undo = Vec::with_capacity(changes.len());
+                for (key, _) in &changes {
+                    undo.push((key.clone(), self.storage.get(key).await?));
+                }
+                let log = UndoLog { version: migration.version(), entries: undo };
+
+                let mut batch = changes;
+                batch.push((undo_key(migration.version()), Some(serde_json::to_vec(&log)?)));
+                batch.push((VERSION_KEY.to_vec(), Some(migration.version().to_be_bytes().to_vec())));
+                self.storage.batch_write(batch).await?;
+                info!(
+                    "Applied storage migration {} ({}): {} puts, {} deletes",
+                    report.version, report.description, report.puts, report.deletes
+                );
+            }
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    /// `target` のバージョンまで巻き戻す
+    pub async fn rollback(&self, target: u32, dry_run: bool) -> Result<Vec<MigrationReport>> {
+        let current = self.current_version().await?;
+        let mut reports = Vec::new();
+
+        for version in (target + 1..=current).rev() {
+            let bytes = self.storage.get(&undo_key(version)).await?
+                .ok_or_else(|| anyhow!("No undo log for schema version {}", version))?;
+            let log: UndoLog = serde_json::from_slice(&bytes)?;
+            let description = self.migrations
+                .iter()
+                .find(|m| m.version() == version)
+                .map_or_else(|| "unknown".to_string(), |m| m.description().to_string());
+            let report = MigrationReport {
+                version,
+                description,
+                puts: log.entries.iter().filter(|(_, v)| v.is_some()).count(),
+                deletes: log.entries.iter().filter(|(_, v)| v.is_none()).count(),
+                dry_run,
+            };
+
+            if !dry_run {
+                let mut batch = log.entries;
+                batch.push((undo_key(version), None));
+                batch.push((VERSION_KEY.to_vec(), Some((version - 1).to_be_bytes().to_vec())));
+                self.storage.batch_write(batch).await?;
+                info!("Rolled back storage migration {} ({})", version, report.description);
+            }
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+}
+
+fn undo_key(version: u32) -> Vec<u8> {
+    format!("{}{:010}", UNDO_PREFIX, version).into_bytes()
+}
+
+/// 取り消しログのエントリを hex でシリアライズ
+mod undo_entries {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::Change;
+
+    pub fn serialize<S: Serializer>(entries: &[Change], serializer: S) -> Result<S::Ok, S::Error> {
+        entries
+            .iter()
+            .map(|(k, v)| (hex::encode(k), v.as_ref().map(hex::encode)))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Change>, D::Error> {
+        Vec::<(String, Option<String>)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(k, v)| {
+                Ok((
+                    hex::decode(k).map_err(serde::de::Error::custom)?,
+                    v.map(hex::decode).transpose().map_err(serde::de::Error::custom)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    /// `old/` のキーを `new/` へ移すマイグレーション
+    struct RenamePrefix;
+
+    #[async_trait]
+    impl Migration for RenamePrefix {
+        fn version(&self) -> u32 {
+            2
+        }
+
+        fn description(&self) -> &str {
+            "rename old/ to new/"
+        }
+
+        async fn plan(&self, storage: &dyn StorageEngine) -> Result<Vec<Change>> {
+            let mut changes = Vec::new();
+            for (key, value) in storage.scan(b"old/", 1000).await?.into_iter().take_while(|(k, _)| k.starts_with(b"old/")) {
+                let mut renamed = b"new/".to_vec();
+                renamed.extend_from_slice(&key[4..]);
+                changes.push((renamed, Some(value)));
+                changes.push((key, None));
+            }
+            Ok(changes)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_and_rollback() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        storage.put(b"old/a", b"1").await.unwrap();
+        let migrator = Migrator::new(storage.clone(), vec![Box::new(RenamePrefix), Box::new(Baseline)]);
+
+        // ドライランでは何も書き込まない
+        let reports = migrator.migrate(None, true).await.unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!((reports[1].puts, reports[1].deletes), (1, 1));
+        assert_eq!(migrator.current_version().await.unwrap(), 0);
+
+        migrator.migrate(None, false).await.unwrap();
+        assert_eq!(migrator.current_version().await.unwrap(), 2);
+        assert_eq!(storage.get(b"new/a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(storage.get(b"old/a").await.unwrap(), None);
+        assert!(migrator.migrate(None, false).await.unwrap().is_empty());
+
+        migrator.rollback(1, false).await.unwrap();
+        assert_eq!(migrator.current_version().await.unwrap(), 1);
+        assert_eq!(storage.get(b"old/a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(storage.get(b"new/a").await.unwrap(), None);
+
+        // 新しいバージョンのストレージは拒否する
+        let older = Migrator::new(storage.clone(), Vec::new());
+        assert!(older.migrate(None, false).await.is_err());
+    }
+}
@@ -1,6 +1,15 @@
-use std::path::Path;
+pub mod backup;
+pub mod migration;
+pub mod redb_storage;
+pub mod tikv;
+pub mod typed;
+pub mod watchdog;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use anyhow::Result;
 use async_trait::async_trait;
+use tracing::info;
 
 #[async_trait]
 pub trait StorageEngine: Send + Sync + std::fmt::Debug {
@@ -8,17 +17,38 @@ pub trait StorageEngine: Send + Sync + std::fmt::Debug {
     async fn put(&self, key: &[u8], value: &[u8]) -> Result<()>;
     async fn delete(&self, key: &[u8]) -> Result<()>;
     async fn batch_write(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()>;
+    /// `start` 以降のキーを昇順に最大 `limit` 件取得
+    async fn scan(&self, start: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+}
+
+/// 整合性のある時点のデータをディレクトリへ書き出せるストレージ
+#[async_trait]
+pub trait Checkpoint: Send + Sync {
+    /// `dir`（存在しないパス）にチェックポイントを作成
+    async fn checkpoint(&self, dir: &Path) -> Result<()>;
 }
 
 #[derive(Debug)]
 pub struct RocksDBStorage {
-    db: rocksdb::DB,
+    db: Arc<rocksdb::DB>,
+    path: PathBuf,
 }
 
 impl RocksDBStorage {
     pub fn new(path: impl AsRef<Path>) -> Result<Self> {
-        let db = rocksdb::DB::open_default(path)?;
-        Ok(Self { db })
+        let path = path.as_ref().to_path_buf();
+        let db = rocksdb::DB::open_default(&path)?;
+        Ok(Self { db: Arc::new(db), path })
+    }
+
+    /// チェックポイントを作成する一時ディレクトリ（ハードリンクできるようデータベースと同じ場所に置く）
+    fn staging_dir(&self) -> PathBuf {
+        let name = self.path.file_name().map_or_else(|| "db".into(), |n| n.to_string_lossy());
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        self.path.with_file_name(format!(".{}.checkpoint-{}", name, nanos))
     }
 }
 
@@ -51,4 +81,68 @@ impl StorageEngine for RocksDBStorage {
         self.db.write(wb)?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    async fn scan(&self, start: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mode = rocksdb::IteratorMode::From(start, rocksdb::Direction::Forward);
+        let mut entries = Vec::with_capacity(limit);
+        for item in self.db.iterator(mode).take(limit) {
+            let (key, value) = item?;
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+}
+/// チェックポイントの作成中も書き込みを止めない
+///
+/// RocksDBのチェックポイントは、変更されないSSTファイルをハードリンクし、MANIFESTなどの小さなファイルのみを
+/// コピーします。ハードリンクは同じファイルシステム内でしか作れないため、データベースの隣に作成してから
+/// `dir` へ移動します。別のファイルシステムの場合はコピーしますが、コピーするのはチェックポイントの
+/// ファイルのため書き込みとは競合しません。作成はブロッキングスレッドで行い、非同期ランタイムも止めません。
+#[async_trait]
+impl Checkpoint for RocksDBStorage {
+    async fn checkpoint(&self, dir: &Path) -> Result<()> {
+        let started = std::time::Instant::now();
+        let staging = self.staging_dir();
+        let db = self.db.clone();
+        let target = staging.clone();
+        tokio::task::spawn_blocking(move || {
+            rocksdb::checkpoint::Checkpoint::new(&db)?.create_checkpoint(&target)?;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await??;
+        let linked = started.elapsed();
+
+        let result = move_dir(&staging, dir).await;
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+        result?;
+        info!(
+            "RocksDB checkpoint written to {} (linked in {}ms, {}ms in total)",
+            dir.display(), linked.as_millis(), started.elapsed().as_millis()
+        );
+        Ok(())
+    }
+}
+
+/// ディレクトリを移動（別のファイルシステムの場合はコピー）
+async fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    if tokio::fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        tokio::fs::create_dir_all(to.join(&relative)).await?;
+        let mut entries = tokio::fs::read_dir(from.join(&relative)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = relative.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                pending.push(path);
+            } else {
+                tokio::fs::copy(from.join(&path), to.join(&path)).await?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -6,12 +6,16 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use serde::{Serialize, Deserialize};
 use tracing::{info, warn, error};
+use super::{Checkpoint, StorageEngine};
 
 // テーブル定義
 const TX_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("transactions");
 const STATE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("states");
 const MERKLE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("merkle_tree");
 
+/// データベースファイル名
+pub const DB_FILE: &str = "data.redb";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub path: String,
@@ -38,6 +42,8 @@ pub struct RedbStorage {
     db: Arc<Mutex<Database>>,
     merkle_tree: Arc<Mutex<PoseidonMerkleTree>>,
     config: StorageConfig,
+    /// メモリ上のデータベース（デモモード、ファイルがないためスナップショットを作れない）
+    in_memory: bool,
 }
 
 impl RedbStorage {
@@ -46,9 +52,27 @@ impl RedbStorage {
         std::fs::create_dir_all(&config.path)?;
         
         // データベースの初期化
-        let db_path = Path::new(&config.path).join("data.redb");
+        let db_path = Path::new(&config.path).join(DB_FILE);
         let db = Database::create(db_path)?;
-        
+        info!("Storage initialized at: {}", config.path);
+        Self::open(db, config, false)
+    }
+
+    /// メモリ上のデータベースを作成（プロセスの終了でデータは消える）
+    #[cfg(any(test, feature = "demo"))]
+    pub fn in_memory(config: StorageConfig) -> Result<Self> {
+        let db = Database::builder().create_with_backend(redb::backends::InMemoryBackend::new())?;
+        info!("Storage initialized in memory");
+        Self::open(db, config, true)
+    }
+
+    /// テスト用の空のメモリ上のデータベース
+    #[cfg(test)]
+    pub fn memory() -> Arc<Self> {
+        Arc::new(Self::in_memory(StorageConfig::default()).expect("in-memory database"))
+    }
+
+    fn open(db: Database, config: StorageConfig, in_memory: bool) -> Result<Self> {
         // テーブルの初期化
         let write_txn = db.begin_write()?;
         {
@@ -61,19 +85,25 @@ impl RedbStorage {
         // マークルツリーの初期化
         let merkle_tree = PoseidonMerkleTree::new();
         
-        info!("Storage initialized at: {}", config.path);
-        
         Ok(Self {
             db: Arc::new(Mutex::new(db)),
             merkle_tree: Arc::new(Mutex::new(merkle_tree)),
             config,
+            in_memory,
         })
     }
     
     pub async fn write_with_proof(&self, key: &[u8], value: &[u8]) -> Result<WriteResult> {
-        let db = self.db.lock().await;
-        let write_txn = db.begin_write()?;
+        let merkle_proof = self.commit_staged(|write_txn, tree| Self::insert_in(write_txn, tree, key, value)).await?;
         
+        Ok(WriteResult {
+            merkle_proof,
+            timestamp: std::time::SystemTime::now(),
+        })
+    }
+
+    /// 書き込みトランザクション内でキーを書き込む
+    fn insert_in(write_txn: &redb::WriteTransaction, tree: &mut PoseidonMerkleTree, key: &[u8], value: &[u8]) -> Result<MerkleProof> {
         // データの書き込み
         {
             let mut table = write_txn.open_table(TX_TABLE)?;
@@ -92,10 +122,7 @@ impl RedbStorage {
         }
         
         // マークルツリーの更新
-        let merkle_proof = {
-            let mut tree = self.merkle_tree.lock().await;
-            tree.insert(key, value)?
-        };
+        let merkle_proof = tree.insert(key, value)?;
         
         // マークルツリーの保存
         {
@@ -103,12 +130,7 @@ impl RedbStorage {
             table.insert(key, bincode::serialize(&merkle_proof)?.as_slice())?;
         }
         
-        write_txn.commit()?;
-        
-        Ok(WriteResult {
-            merkle_proof,
-            timestamp: std::time::SystemTime::now(),
-        })
+        Ok(merkle_proof)
     }
     
     pub async fn read(&self, key: &[u8]) -> Result<Option<ReadResult>> {
@@ -157,9 +179,29 @@ impl RedbStorage {
     }
     
     pub async fn delete(&self, key: &[u8]) -> Result<()> {
+        self.commit_staged(|write_txn, tree| Self::remove_in(write_txn, tree, key)).await
+    }
+
+    /// 書き込みトランザクションを確定する
+    ///
+    /// マークルツリーの変更は写しに適用し、トランザクションを確定できた場合だけ反映します
+    /// （失敗した場合にツリーがデータベースとずれないようにする）。
+    async fn commit_staged<T>(
+        &self,
+        apply: impl FnOnce(&redb::WriteTransaction, &mut PoseidonMerkleTree) -> Result<T>,
+    ) -> Result<T> {
         let db = self.db.lock().await;
         let write_txn = db.begin_write()?;
-        
+        let mut tree = self.merkle_tree.lock().await;
+        let mut staged = tree.clone();
+        let result = apply(&write_txn, &mut staged)?;
+        write_txn.commit()?;
+        *tree = staged;
+        Ok(result)
+    }
+
+    /// 書き込みトランザクション内でキーを削除
+    fn remove_in(write_txn: &redb::WriteTransaction, tree: &mut PoseidonMerkleTree, key: &[u8]) -> Result<()> {
         // データの削除
         {
             let mut table = write_txn.open_table(TX_TABLE)?;
@@ -173,12 +215,7 @@ impl RedbStorage {
         }
         
         // マークルツリーの更新
-        {
-            let mut tree = self.merkle_tree.lock().await;
-            tree.delete(key)?;
-        }
-        
-        write_txn.commit()?;
+        tree.delete(key)?;
         
         Ok(())
     }
@@ -199,6 +236,22 @@ impl RedbStorage {
         Ok(())
     }
     
+    /// データベースファイルを指定したパスへ複製
+    ///
+    /// 複製中は書き込みをロックするため、整合性のある状態が保存されます。
+    pub async fn snapshot(&self, dest: &Path) -> Result<u64> {
+        if self.in_memory {
+            anyhow::bail!("In-memory storage has no database file to snapshot");
+        }
+        let _db = self.db.lock().await;
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let copied = std::fs::copy(Path::new(&self.config.path).join(DB_FILE), dest)?;
+        info!("Storage snapshot written to {} ({} bytes)", dest.display(), copied);
+        Ok(copied)
+    }
+
     pub async fn shutdown(&self) -> Result<()> {
         info!("Shutting down storage...");
         self.compact().await?;
@@ -222,12 +275,66 @@ impl RedbStorage {
         Ok(StorageStats {
             transaction_count: tx_count,
             state_count,
-            total_size: std::fs::metadata(&self.config.path)?.len(),
+            total_size: if self.in_memory { 0 } else { std::fs::metadata(&self.config.path)?.len() },
             merkle_root: self.get_merkle_root().await?,
         })
     }
 }
 
+#[async_trait]
+impl StorageEngine for RedbStorage {
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.read(key).await?.map(|r| r.value))
+    }
+
+    async fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.write_with_proof(key, value).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &[u8]) -> Result<()> {
+        RedbStorage::delete(self, key).await
+    }
+
+    /// 一つの書き込みトランザクションで適用する（途中で失敗した場合はマークルツリーを含めてどの変更も残らない）
+    async fn batch_write(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        self.commit_staged(|write_txn, tree| {
+            for (key, value) in batch {
+                match value {
+                    Some(value) => {
+                        Self::insert_in(write_txn, tree, &key, &value)?;
+                    }
+                    None => {
+                        Self::remove_in(write_txn, tree, &key)?;
+                    }
+                }
+            }
+            Ok(())
+        }).await
+    }
+
+    async fn scan(&self, start: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let db = self.db.lock().await;
+        let read_txn = db.begin_read()?;
+        let table = read_txn.open_table(TX_TABLE)?;
+
+        let mut entries = Vec::with_capacity(limit);
+        for item in table.range(start..)?.take(limit) {
+            let (key, value) = item?;
+            entries.push((key.value().to_vec(), value.value().to_vec()));
+        }
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl Checkpoint for RedbStorage {
+    async fn checkpoint(&self, dir: &Path) -> Result<()> {
+        self.snapshot(&dir.join(DB_FILE)).await?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
     pub value: Vec<u8>,
@@ -257,13 +364,13 @@ pub struct StorageStats {
 }
 
 // PoseidonMerkleTreeの実装
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PoseidonMerkleTree {
     root: [u8; 32],
     nodes: std::collections::HashMap<Vec<u8>, Node>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Node {
     hash: [u8; 32],
     left: Option<Box<Node>>,
@@ -306,3 +413,45 @@ pub struct MerkleProof {
     pub path: Vec<[u8; 32]>,
     pub indices: Vec<bool>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_batch_write_is_atomic() {
+        let storage = RedbStorage::memory();
+        storage.batch_write(vec![
+            (b"a".to_vec(), Some(b"0".to_vec())),
+            (b"b".to_vec(), Some(b"0".to_vec())),
+            (b"gone".to_vec(), Some(b"x".to_vec())),
+            (b"gone".to_vec(), None),
+        ]).await.unwrap();
+        assert_eq!(storage.get(b"a").await.unwrap(), Some(b"0".to_vec()));
+        assert_eq!(storage.get(b"gone").await.unwrap(), None);
+
+        // 並行して読み取っても一括書き込みの途中の状態は見えない
+        let writer = {
+            let storage = storage.clone();
+            tokio::spawn(async move {
+                for n in 1..=200u32 {
+                    let value = n.to_string().into_bytes();
+                    storage.batch_write(vec![
+                        (b"a".to_vec(), Some(value.clone())),
+                        (b"b".to_vec(), Some(value)),
+                    ]).await.unwrap();
+                }
+            })
+        };
+        while !writer.is_finished() {
+            let db = storage.db.lock().await;
+            let read_txn = db.begin_read().unwrap();
+            let table = read_txn.open_table(TX_TABLE).unwrap();
+            let a = table.get(b"a".as_slice()).unwrap().map(|v| v.value().to_vec());
+            let b = table.get(b"b".as_slice()).unwrap().map(|v| v.value().to_vec());
+            assert_eq!(a, b);
+        }
+        writer.await.unwrap();
+        assert_eq!(storage.get(b"b").await.unwrap(), Some(b"200".to_vec()));
+    }
+}
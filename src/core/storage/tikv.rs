@@ -0,0 +1,65 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use tikv_client::{BoundRange, Key, KvPair, RawClient};
+use super::StorageEngine;
+
+/// TiKV（Raw API）をバックエンドとするストレージ
+pub struct TikvStorage {
+    client: RawClient,
+}
+
+impl std::fmt::Debug for TikvStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TikvStorage").finish()
+    }
+}
+
+impl TikvStorage {
+    /// PDエンドポイントに接続
+    pub async fn connect(pd_endpoints: Vec<String>) -> Result<Self> {
+        let client = RawClient::new(pd_endpoints).await?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl StorageEngine for TikvStorage {
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.client.get(key.to_vec()).await?)
+    }
+
+    async fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        Ok(self.client.put(key.to_vec(), value.to_vec()).await?)
+    }
+
+    async fn delete(&self, key: &[u8]) -> Result<()> {
+        Ok(self.client.delete(key.to_vec()).await?)
+    }
+
+    async fn batch_write(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        let mut puts = Vec::new();
+        let mut deletes = Vec::new();
+        for (key, value) in batch {
+            match value {
+                Some(value) => puts.push(KvPair::new(key, value)),
+                None => deletes.push(key),
+            }
+        }
+        if !puts.is_empty() {
+            self.client.batch_put(puts).await?;
+        }
+        if !deletes.is_empty() {
+            self.client.batch_delete(deletes).await?;
+        }
+        Ok(())
+    }
+
+    async fn scan(&self, start: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let range: BoundRange = (Key::from(start.to_vec())..).into();
+        let pairs = self.client.scan(range, limit as u32).await?;
+        Ok(pairs
+            .into_iter()
+            .map(|pair| (Vec::<u8>::from(pair.key().clone()), pair.value().clone()))
+            .collect())
+    }
+}
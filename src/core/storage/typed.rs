@@ -0,0 +1,231 @@
+//! 型付きの状態オブジェクト
+//!
+//! キーの組み立てと値の直列化を型ごとに一度だけ定義し、`TypedStorage` を通して読み書きします。
+//! 通常は `#[derive(StateObject)]` で実装します（`crates/storage-macros`）。
+//!
+//! キーは `{CF}/{キー}` です。`StateKey` の文字列はそのまま、整数は辞書順が数値順と一致するよう
+//! 20桁にゼロ埋めし、タプルは `/` で連結します。
+//! 値は先頭1バイトのバージョンとJSONです。バージョンのない旧形式のJSONはバージョン0として読み込み、
+//! 既定では現在の型としてそのまま解釈するため、既存のキーを導出に移行してもマイグレーションは不要です。
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use serde::{Serialize, de::DeserializeOwned};
+use super::StorageEngine;
+use super::migration::Change;
+
+pub use rustorium_storage_macros::StateObject;
+
+/// キーの要素
+pub trait StateKey: Send + Sync {
+    fn encode_key(&self, out: &mut String);
+}
+
+impl StateKey for String {
+    fn encode_key(&self, out: &mut String) {
+        out.push_str(self);
+    }
+}
+
+impl StateKey for u64 {
+    fn encode_key(&self, out: &mut String) {
+        out.push_str(&format!("{:020}", self));
+    }
+}
+
+impl StateKey for u32 {
+    fn encode_key(&self, out: &mut String) {
+        (*self as u64).encode_key(out);
+    }
+}
+
+impl<A: StateKey, B: StateKey> StateKey for (A, B) {
+    fn encode_key(&self, out: &mut String) {
+        self.0.encode_key(out);
+        out.push('/');
+        self.1.encode_key(out);
+    }
+}
+
+impl<A: StateKey, B: StateKey, C: StateKey> StateKey for (A, B, C) {
+    fn encode_key(&self, out: &mut String) {
+        self.0.encode_key(out);
+        out.push('/');
+        self.1.encode_key(out);
+        out.push('/');
+        self.2.encode_key(out);
+    }
+}
+
+/// ストレージに保存する状態オブジェクト
+pub trait StateObject: Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// キーのプレフィックス（末尾の `/` を除く）
+    const CF: &'static str;
+    /// 値のエンコードのバージョン（1〜100）
+    const VERSION: u8;
+    type Key: StateKey;
+
+    fn key(&self) -> Self::Key;
+
+    /// 古いバージョンの値を現在の型に変換する（`version` が0の場合はバージョンのない旧形式）
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self> {
+        if version == 0 {
+            return Ok(serde_json::from_slice(data)?);
+        }
+        bail!("No upgrade from version {} of {} (current version is {})", version, Self::CF, Self::VERSION)
+    }
+
+    /// このCFのキーのプレフィックス
+    fn prefix() -> String {
+        format!("{}/", Self::CF)
+    }
+
+    /// キーのバイト列
+    fn storage_key(key: &Self::Key) -> Vec<u8> {
+        let mut out = Self::prefix();
+        key.encode_key(&mut out);
+        out.into_bytes()
+    }
+
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![Self::VERSION];
+        serde_json::to_writer(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            None => bail!("Empty value for {}", Self::CF),
+            // バージョンのない旧形式（JSONのオブジェクト）
+            Some(b'{') => Self::upgrade(0, bytes),
+            Some(&version) if version == Self::VERSION => Ok(serde_json::from_slice(&bytes[1..])?),
+            Some(&version) if version < Self::VERSION => Self::upgrade(version, &bytes[1..]),
+            Some(&version) => bail!(
+                "{} was written with version {}, newer than this binary supports ({})",
+                Self::CF, version, Self::VERSION
+            ),
+        }
+    }
+
+    /// 一括書き込み（`StorageEngine::batch_write`）で保存するための変更
+    fn put_change(&self) -> Result<Change> {
+        Ok((Self::storage_key(&self.key()), Some(self.encode()?)))
+    }
+
+    /// 一括書き込みで削除するための変更
+    fn delete_change(key: &Self::Key) -> Change {
+        (Self::storage_key(key), None)
+    }
+}
+
+/// 状態オブジェクトの読み書き
+#[async_trait]
+pub trait TypedStorage {
+    async fn load<T: StateObject>(&self, key: &T::Key) -> Result<Option<T>>;
+
+    async fn save<T: StateObject>(&self, object: &T) -> Result<()>;
+
+    async fn remove<T: StateObject>(&self, key: &T::Key) -> Result<()>;
+
+    /// キーの順に `after` の次から最大 `limit` 件読み込む
+    async fn list<T: StateObject>(&self, after: Option<&T::Key>, limit: usize) -> Result<Vec<T>>;
+}
+
+#[async_trait]
+impl<S: StorageEngine + ?Sized> TypedStorage for S {
+    async fn load<T: StateObject>(&self, key: &T::Key) -> Result<Option<T>> {
+        match self.get(&T::storage_key(key)).await? {
+            Some(bytes) => Ok(Some(T::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save<T: StateObject>(&self, object: &T) -> Result<()> {
+        self.put(&T::storage_key(&object.key()), &object.encode()?).await
+    }
+
+    async fn remove<T: StateObject>(&self, key: &T::Key) -> Result<()> {
+        self.delete(&T::storage_key(key)).await
+    }
+
+    async fn list<T: StateObject>(&self, after: Option<&T::Key>, limit: usize) -> Result<Vec<T>> {
+        let prefix = T::prefix().into_bytes();
+        let start = match after {
+            Some(key) => {
+                let mut start = T::storage_key(key);
+                start.push(0);
+                start
+            }
+            None => prefix.clone(),
+        };
+        self.scan(&start, limit).await?
+            .into_iter()
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, value)| T::decode(&value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use crate::core::storage::redb_storage::{RedbStorage, StorageConfig};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StateObject)]
+    #[state(cf = "test/stake", version = 2, upgrade = "Stake::from_v1")]
+    struct Stake {
+        #[state(key)]
+        validator: String,
+        #[state(key)]
+        epoch: u64,
+        amount: u64,
+    }
+
+    impl Stake {
+        fn from_v1(version: u8, data: &[u8]) -> Result<Self> {
+            #[derive(Deserialize)]
+            struct V1 {
+                validator: String,
+                epoch: u64,
+                stake: u64,
+            }
+            match version {
+                0 | 1 => {
+                    let v1: V1 = serde_json::from_slice(data)?;
+                    Ok(Self { validator: v1.validator, epoch: v1.epoch, amount: v1.stake })
+                }
+                _ => bail!("unknown version {}", version),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_derived_layout_and_versioned_encoding() {
+        let storage = RedbStorage::in_memory(StorageConfig::default()).unwrap();
+
+        let stakes: Vec<Stake> = (1..=3)
+            .map(|epoch| Stake { validator: "v1".to_string(), epoch, amount: epoch * 100 })
+            .collect();
+        for stake in &stakes {
+            stake.save(&storage).await.unwrap();
+        }
+        let key = ("v1".to_string(), 2);
+        assert_eq!(Stake::storage_key(&key), b"test/stake/v1/00000000000000000002");
+        assert_eq!(Stake::load(&storage, &key).await.unwrap(), Some(stakes[1].clone()));
+        assert_eq!(Stake::list(&storage, Some(&("v1".to_string(), 1)), 10).await.unwrap(), stakes[1..]);
+
+        // 旧形式（バージョンなし）と古いバージョンは upgrade で読み込み、新しいバージョンは拒否する
+        let legacy = br#"{"validator":"v2","epoch":1,"stake":5}"#;
+        storage.put(&Stake::storage_key(&("v2".to_string(), 1)), legacy).await.unwrap();
+        assert_eq!(Stake::load(&storage, &("v2".to_string(), 1)).await.unwrap().unwrap().amount, 5);
+        let mut v1 = vec![1];
+        v1.extend_from_slice(legacy);
+        assert_eq!(Stake::decode(&v1).unwrap().amount, 5);
+        v1[0] = 3;
+        assert!(Stake::decode(&v1).is_err());
+
+        Stake::delete(&storage, &key).await.unwrap();
+        assert_eq!(Stake::load(&storage, &key).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,308 @@
+//! ディスク容量の監視
+//!
+//! データディレクトリを含むディスクの使用率を定期的に確認します。
+//! - `cleanup_percent` 以上: 古い障害予測のスナップショットを削除し、データベースを圧縮する
+//! - `halt_percent` 以上、または空きが `min_free_mb` 未満: ブロックの確定を停止する
+//!
+//! ディスクが満杯になってからの書き込みはデータベースを壊すことがあるため、その前に
+//! `Chain::commit` が `DiskGuard` を確認して確定を拒否します。状態は `/api/health/disk` で確認できます。
+//! 確定は使用率が `halt_percent` より `RESUME_MARGIN` ポイント下がるまで再開しません（しきい値付近で
+//! 停止と再開を繰り返さないため）。バックアップとログは削除しません。
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use sysinfo::Disks;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use super::redb_storage::RedbStorage;
+use crate::config::DiskSettings;
+use crate::core::ai::metrics::disk_usage;
+
+/// 確定を再開するのに必要な `halt_percent` からの差（ポイント）
+const RESUME_MARGIN: f64 = 2.0;
+/// 削除と圧縮を繰り返す最短の間隔
+const CLEANUP_COOLDOWN: Duration = Duration::from_secs(600);
+/// 障害予測のスナップショットの名前の接頭辞（`SnapshotHook`）
+const SNAPSHOT_PREFIX: &str = "predicted-failure-";
+const MB: u64 = 1024 * 1024;
+
+/// ディスクの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskState {
+    Ok,
+    /// 削除と圧縮を行う使用率
+    Cleanup,
+    /// ブロックの確定を停止している
+    Halted,
+}
+
+/// 削除と圧縮の結果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CleanupReport {
+    /// 実行した時刻（UNIX秒）
+    pub at: u64,
+    /// 削除したスナップショット
+    pub removed_snapshots: Vec<String>,
+    /// 削除したファイルの合計サイズ
+    pub removed_bytes: u64,
+    /// データベースを圧縮したか
+    pub compacted: bool,
+    pub errors: Vec<String>,
+}
+
+/// 最後に確認したディスクの状態
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DiskStatus {
+    pub state: DiskState,
+    pub accepting_blocks: bool,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub usage_percent: f64,
+    /// 確認した時刻（UNIX秒）
+    pub checked_at: u64,
+    pub last_cleanup: Option<CleanupReport>,
+}
+
+/// ブロックの確定を止めるかの共有の状態
+#[derive(Debug, Clone, Default)]
+pub struct DiskGuard(Arc<RwLock<Option<DiskStatus>>>);
+
+impl DiskGuard {
+    /// 最後に確認した状態（まだ確認していなければ `None`）
+    pub fn status(&self) -> Option<DiskStatus> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// 確定を停止している場合はその理由を返す
+    pub fn check(&self) -> Result<()> {
+        match self.0.read().unwrap().as_ref() {
+            Some(status) if status.state == DiskState::Halted => Err(anyhow!(
+                "Not accepting blocks: the data directory's disk is {:.1}% full with {} MB free",
+                status.usage_percent,
+                status.free_bytes / MB,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn set(&self, status: DiskStatus) {
+        *self.0.write().unwrap() = Some(status);
+    }
+}
+
+/// ディスク容量の監視
+pub struct DiskWatchdog {
+    settings: DiskSettings,
+    data_dir: PathBuf,
+    snapshot_dir: PathBuf,
+    storage: Option<Arc<RedbStorage>>,
+    guard: DiskGuard,
+}
+
+impl DiskWatchdog {
+    pub fn new(settings: &DiskSettings, data_dir: &Path, storage: Option<Arc<RedbStorage>>) -> Self {
+        Self {
+            settings: settings.clone(),
+            data_dir: data_dir.canonicalize().unwrap_or_else(|_| data_dir.to_path_buf()),
+            snapshot_dir: data_dir.join("snapshots"),
+            storage,
+            guard: DiskGuard::default(),
+        }
+    }
+
+    pub fn guard(&self) -> DiskGuard {
+        self.guard.clone()
+    }
+
+    /// 使用量と容量から次の状態を決める
+    fn next_state(&self, previous: DiskState, used: u64, total: u64) -> DiskState {
+        let usage = percent(used, total);
+        let free = total.saturating_sub(used);
+        if usage >= self.settings.halt_percent || free < self.settings.min_free_mb * MB {
+            DiskState::Halted
+        } else if previous == DiskState::Halted && usage >= self.settings.halt_percent - RESUME_MARGIN {
+            DiskState::Halted
+        } else if usage >= self.settings.cleanup_percent {
+            DiskState::Cleanup
+        } else {
+            DiskState::Ok
+        }
+    }
+
+    /// データディレクトリのディスクの（使用量, 容量）
+    fn measure(&self) -> Option<(u64, u64)> {
+        disk_usage(&Disks::new_with_refreshed_list(), &self.data_dir).filter(|&(_, total)| total > 0)
+    }
+
+    /// 古いスナップショットを削除し、データベースを圧縮
+    async fn cleanup(&self) -> CleanupReport {
+        let mut report = CleanupReport {
+            at: unix_now(),
+            removed_snapshots: Vec::new(),
+            removed_bytes: 0,
+            compacted: false,
+            errors: Vec::new(),
+        };
+        match old_snapshots(&self.snapshot_dir, self.settings.keep_snapshots) {
+            Ok(snapshots) => {
+                for path in snapshots {
+                    let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+                    match std::fs::remove_file(&path) {
+                        Ok(()) => {
+                            report.removed_bytes += size;
+                            report.removed_snapshots.push(path.display().to_string());
+                        }
+                        Err(e) => report.errors.push(format!("Failed to remove {}: {}", path.display(), e)),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => report.errors.push(format!("Failed to list {}: {}", self.snapshot_dir.display(), e)),
+        }
+        if let Some(storage) = &self.storage {
+            match storage.compact().await {
+                Ok(()) => report.compacted = true,
+                Err(e) => report.errors.push(format!("Failed to compact the database: {}", e)),
+            }
+        }
+        report
+    }
+
+    /// 定期的に確認し、状態が変わったときに記録する
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let interval = Duration::from_secs(self.settings.interval.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut state = DiskState::Ok;
+            let mut last_cleanup: Option<(Instant, CleanupReport)> = None;
+            let mut unmeasured = false;
+            loop {
+                ticker.tick().await;
+                let Some((mut used, total)) = self.measure() else {
+                    if !unmeasured {
+                        warn!("Cannot find the disk of {}; disk usage is not monitored", self.data_dir.display());
+                        unmeasured = true;
+                    }
+                    continue;
+                };
+                let mut next = self.next_state(state, used, total);
+
+                let cooled_down = last_cleanup.as_ref().is_none_or(|(at, _)| at.elapsed() >= CLEANUP_COOLDOWN);
+                if next != DiskState::Ok && cooled_down {
+                    warn!("Disk of {} is {:.1}% full; removing old snapshots and compacting the database", self.data_dir.display(), percent(used, total));
+                    let report = self.cleanup().await;
+                    for error in &report.errors {
+                        warn!("Disk cleanup: {}", error);
+                    }
+                    info!("Disk cleanup removed {} snapshots ({} MB)", report.removed_snapshots.len(), report.removed_bytes / MB);
+                    last_cleanup = Some((Instant::now(), report));
+                    if let Some(measured) = self.measure() {
+                        used = measured.0;
+                        next = self.next_state(state, used, total);
+                    }
+                }
+
+                match (state == DiskState::Halted, next == DiskState::Halted) {
+                    (false, true) => warn!(target: "audit", "Stopped accepting blocks: the disk of {} is {:.1}% full with {} MB free", self.data_dir.display(), percent(used, total), total.saturating_sub(used) / MB),
+                    (true, false) => info!(target: "audit", "Accepting blocks again: the disk of {} is {:.1}% full", self.data_dir.display(), percent(used, total)),
+                    _ => {}
+                }
+                state = next;
+                self.guard.set(DiskStatus {
+                    state,
+                    accepting_blocks: state != DiskState::Halted,
+                    used_bytes: used,
+                    total_bytes: total,
+                    free_bytes: total.saturating_sub(used),
+                    usage_percent: percent(used, total),
+                    checked_at: unix_now(),
+                    last_cleanup: last_cleanup.as_ref().map(|(_, report)| report.clone()),
+                });
+            }
+        })
+    }
+}
+
+/// 新しいものを `keep` 個残した、削除するスナップショット
+fn old_snapshots(dir: &Path, keep: usize) -> std::io::Result<Vec<PathBuf>> {
+    let mut snapshots: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let timestamp = name.strip_prefix(SNAPSHOT_PREFIX)?.strip_suffix(".redb")?.parse().ok()?;
+            Some((timestamp, entry.path()))
+        })
+        .collect();
+    snapshots.sort();
+    let remove = snapshots.len().saturating_sub(keep);
+    Ok(snapshots.into_iter().take(remove).map(|(_, path)| path).collect())
+}
+
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 * 100.0 / total as f64
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_halts_before_full_and_resumes_with_margin() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = DiskSettings { min_free_mb: 10, keep_snapshots: 1, ..Default::default() };
+        let watchdog = DiskWatchdog::new(&settings, dir.path(), None);
+        let total = 1000 * MB;
+        assert_eq!(watchdog.next_state(DiskState::Ok, 500 * MB, total), DiskState::Ok);
+        assert_eq!(watchdog.next_state(DiskState::Ok, 870 * MB, total), DiskState::Cleanup);
+        assert_eq!(watchdog.next_state(DiskState::Cleanup, 950 * MB, total), DiskState::Halted);
+        // 再開は停止の使用率より2ポイント下がってから
+        assert_eq!(watchdog.next_state(DiskState::Halted, 935 * MB, total), DiskState::Halted);
+        assert_eq!(watchdog.next_state(DiskState::Halted, 925 * MB, total), DiskState::Cleanup);
+        // 空き容量が少なければ使用率に関係なく停止する
+        assert_eq!(watchdog.next_state(DiskState::Ok, 95 * MB, 104 * MB), DiskState::Halted);
+
+        let guard = watchdog.guard();
+        assert!(guard.check().is_ok());
+        guard.set(DiskStatus {
+            state: DiskState::Halted,
+            accepting_blocks: false,
+            used_bytes: 960 * MB,
+            total_bytes: total,
+            free_bytes: 40 * MB,
+            usage_percent: 96.0,
+            checked_at: 0,
+            last_cleanup: None,
+        });
+        assert!(guard.check().unwrap_err().to_string().contains("96.0% full"));
+
+        // 新しいスナップショットを残して削除する（名前の時刻は桁数が揃っていない）
+        let snapshots = dir.path().join("snapshots");
+        std::fs::create_dir_all(&snapshots).unwrap();
+        for name in ["predicted-failure-900.redb", "predicted-failure-1000.redb", "predicted-failure-950.redb", "other.redb"] {
+            std::fs::write(snapshots.join(name), b"snapshot").unwrap();
+        }
+        let report = watchdog.cleanup().await;
+        assert_eq!(report.removed_snapshots.len(), 2);
+        assert_eq!(report.removed_bytes, 16);
+        assert!(!report.compacted);
+        assert!(snapshots.join("predicted-failure-1000.redb").exists());
+        assert!(snapshots.join("other.redb").exists());
+    }
+}
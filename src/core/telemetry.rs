@@ -0,0 +1,154 @@
+//! テレメトリー（オプトイン）
+//!
+//! 設定で有効にしたノードのみ、匿名化した統計を定期的に送信します。
+//! ハードフォーク前にネットワーク全体のバージョンの分布を把握するためのものです。
+//!
+//! 送信する内容は `TelemetryReport` のフィールドがすべてです。ノードIDは初回に生成して
+//! データディレクトリに保存する乱数で、鍵・アドレス・ノード名・IPアドレスとは無関係です。
+//! ファイルを削除すると新しいIDになります。
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use tracing::{debug, info, warn};
+use crate::config::TelemetrySettings;
+use crate::core::block::Chain;
+use crate::core::network::quic::QuicNetwork;
+
+/// 匿名のノードIDを保存するファイル名
+const NODE_ID_FILE: &str = "telemetry_id";
+
+/// 送信する統計
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    /// 匿名のノードID（同じノードの重複を数えないためのもの）
+    pub node_id: String,
+    /// ノードのバージョン
+    pub version: String,
+    pub chain_id: u64,
+    /// 設定上の役割（validator, full など）
+    pub role: String,
+    pub peer_count: usize,
+    /// 確定済みの最新のブロックの高さ
+    pub block_height: Option<u64>,
+    pub os: String,
+    pub arch: String,
+    /// 送信時刻（UNIX秒）
+    pub reported_at: u64,
+}
+
+/// 統計を定期的に送信する
+pub struct TelemetryReporter {
+    client: reqwest::Client,
+    endpoint: String,
+    interval: Duration,
+    node_id: String,
+    chain_id: u64,
+    role: String,
+}
+
+impl TelemetryReporter {
+    pub async fn new(settings: &TelemetrySettings, data_dir: &Path, chain_id: u64, role: &str) -> Result<Self> {
+        if settings.endpoint.is_empty() {
+            return Err(anyhow!("telemetry.endpoint is required when telemetry is enabled"));
+        }
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(settings.request_timeout))
+                .build()?,
+            endpoint: settings.endpoint.clone(),
+            interval: Duration::from_secs(settings.interval.max(60)),
+            node_id: load_or_create_node_id(&data_dir.join(NODE_ID_FILE)).await?,
+            chain_id,
+            role: role.to_string(),
+        })
+    }
+
+    /// 現在の統計
+    pub fn report(&self, peer_count: usize, block_height: Option<u64>) -> TelemetryReport {
+        TelemetryReport {
+            node_id: self.node_id.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            chain_id: self.chain_id,
+            role: self.role.clone(),
+            peer_count,
+            block_height,
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            reported_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+
+    /// 統計を送信
+    pub async fn send(&self, report: &TelemetryReport) -> Result<()> {
+        self.client.post(&self.endpoint)
+            .json(report)
+            .send().await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// 定期的に送信するタスクを起動
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>, network: Arc<QuicNetwork>) {
+        info!("Telemetry enabled: reporting anonymized node stats to {} every {:?}", self.endpoint, self.interval);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.interval);
+            loop {
+                ticker.tick().await;
+                let height = chain.head().await.map(|(height, _)| height);
+                let report = self.report(network.connected_peers().await.len(), height);
+                debug!("Sending telemetry report: {:?}", report);
+                if let Err(e) = self.send(&report).await {
+                    // 送信できなくてもノードの動作には影響させない
+                    warn!("Failed to send telemetry to {}: {}", self.endpoint, e);
+                }
+            }
+        });
+    }
+}
+
+/// 匿名のノードIDを読み込む（なければ乱数で生成して保存）
+async fn load_or_create_node_id(path: &Path) -> Result<String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(id) if !id.trim().is_empty() => return Ok(id.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let id = hex::encode(rand::random::<[u8; 16]>());
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, &id).await?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_uses_a_stable_anonymous_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = TelemetrySettings { enabled: true, ..Default::default() };
+        let first = TelemetryReporter::new(&settings, dir.path(), 1337, "full").await.unwrap();
+        let second = TelemetryReporter::new(&settings, dir.path(), 1337, "full").await.unwrap();
+
+        let report = first.report(4, Some(120));
+        assert_eq!(report.node_id.len(), 32);
+        assert_eq!(report.node_id, second.report(0, None).node_id);
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!((report.peer_count, report.block_height), (4, Some(120)));
+
+        // 送信する項目はこれだけ
+        let mut fields: Vec<String> = serde_json::to_value(&report).unwrap()
+            .as_object().unwrap().keys().cloned().collect();
+        fields.sort();
+        assert_eq!(fields, ["arch", "block_height", "chain_id", "node_id", "os", "peer_count", "reported_at", "role", "version"]);
+    }
+}
@@ -1,14 +1,43 @@
+pub mod transfer;
+
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use anyhow::Result;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+use crate::core::sharding::{AccountId, ShardId, ShardManager};
 use crate::core::storage::StorageEngine;
+use transfer::{
+    balance_key, pending_key, receipt_key, transfer_id, tx_id,
+    PendingTransfer, ReceiptKind, TransferError, TransferOutcome, TransferPhase, TransferReceipt,
+    PENDING_PREFIX,
+};
+
+/// 復旧時に一度に読み込む処理中送金の件数
+const RECOVERY_BATCH: usize = 256;
 
 pub struct TokenManager {
-    _storage: Arc<dyn StorageEngine>,
+    storage: Arc<dyn StorageEngine>,
+    shards: Option<Arc<RwLock<ShardManager>>>,
+    /// 残高更新を直列化し、同じ残高からの二重引き落としを防ぐ
+    lock: Mutex<()>,
+    nonce: AtomicU64,
 }
 
 impl TokenManager {
     pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
-        Self { _storage: storage }
+        Self {
+            storage,
+            shards: None,
+            lock: Mutex::new(()),
+            nonce: AtomicU64::new(now_nanos()),
+        }
+    }
+
+    /// シャードマネージャーを設定し、クロスシャード送金を有効にする
+    pub fn with_shards(mut self, shards: Arc<RwLock<ShardManager>>) -> Self {
+        self.shards = Some(shards);
+        self
     }
 
     pub async fn create_token(&self, _token: Token) -> Result<()> {
@@ -21,14 +50,233 @@ impl TokenManager {
         Ok(None)
     }
 
-    pub async fn transfer(&self, _from: &[u8], _to: &[u8], _amount: u64) -> Result<()> {
-        // TODO: トークンを送金
-        Ok(())
+    /// トークンを送金
+    ///
+    /// 送金元と送金先が別シャードの場合は2段階（prepare/commit）で処理し、
+    /// 入金に失敗した場合は送金元に返金します。
+    pub async fn transfer(&self, from: &[u8], to: &[u8], amount: u64) -> Result<TransferOutcome> {
+        if amount == 0 {
+            return Err(TransferError::ZeroAmount.into());
+        }
+        if from == to {
+            return Err(TransferError::SelfTransfer.into());
+        }
+
+        let (from_shard, to_shard) = self.route(from, to).await?;
+        let _guard = self.lock.lock().await;
+
+        let available = self.read_balance(from).await?;
+        if available < amount {
+            return Err(TransferError::InsufficientBalance { available, required: amount }.into());
+        }
+
+        let id = transfer_id(from, to, amount, self.nonce.fetch_add(1, Ordering::Relaxed));
+        let pending = PendingTransfer {
+            debit_tx: tx_id(&id, ReceiptKind::Debit),
+            credit_tx: tx_id(&id, ReceiptKind::Credit),
+            id,
+            from: from.to_vec(),
+            to: to.to_vec(),
+            amount,
+            from_shard,
+            to_shard,
+            phase: TransferPhase::Prepared,
+            created_at: now(),
+        };
+
+        if from_shard == to_shard {
+            self.transfer_local(&pending, available).await?;
+        } else {
+            self.prepare(&pending, available).await?;
+            if let Err(e) = self.commit(&pending).await {
+                warn!("Cross-shard transfer {} failed to commit: {}", pending.id, e);
+                self.abort(&pending).await?;
+                return Err(e.context(format!("cross-shard transfer {} aborted", pending.id)));
+            }
+            info!("Cross-shard transfer {} committed ({} -> {})", pending.id, from_shard, to_shard);
+        }
+
+        self.record_activity(&pending).await;
+        Ok(TransferOutcome::from(&pending))
+    }
+
+    pub async fn get_balance(&self, address: &[u8]) -> Result<u64> {
+        self.read_balance(address).await
+    }
+
+    /// 残高を発行
+    pub async fn mint(&self, address: &[u8], amount: u64) -> Result<u64> {
+        let _guard = self.lock.lock().await;
+        let balance = self.read_balance(address).await?
+            .checked_add(amount)
+            .ok_or(TransferError::Overflow)?;
+        self.storage.put(&balance_key(address), &balance.to_be_bytes()).await?;
+        Ok(balance)
+    }
+
+    /// 取引レシートを取得
+    pub async fn get_receipt(&self, tx_id: &str) -> Result<Option<TransferReceipt>> {
+        Ok(match self.storage.get(&receipt_key(tx_id)).await? {
+            Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+            None => None,
+        })
+    }
+
+    /// 処理中のクロスシャード送金を再開
+    ///
+    /// 起動時に呼び出し、prepare 済みで commit されていない送金を完了させます。
+    /// commit できない送金は取り消して返金します。
+    pub async fn recover_pending(&self) -> Result<Vec<TransferOutcome>> {
+        let _guard = self.lock.lock().await;
+        let mut recovered = Vec::new();
+
+        loop {
+            // 処理済みの記録は削除されるため、常に先頭から読み直す
+            let entries = self.storage.scan(PENDING_PREFIX.as_bytes(), RECOVERY_BATCH).await?;
+            let pending: Vec<PendingTransfer> = entries
+                .into_iter()
+                .take_while(|(key, _)| key.starts_with(PENDING_PREFIX.as_bytes()))
+                .map(|(_, value)| serde_json::from_slice(&value))
+                .collect::<Result<_, _>>()?;
+            if pending.is_empty() {
+                break;
+            }
+
+            for transfer in pending {
+                match self.commit(&transfer).await {
+                    Ok(()) => info!("Recovered cross-shard transfer {}", transfer.id),
+                    Err(e) => {
+                        warn!("Aborting cross-shard transfer {}: {}", transfer.id, e);
+                        self.abort(&transfer).await?;
+                    }
+                }
+                recovered.push(TransferOutcome::from(&transfer));
+            }
+        }
+
+        Ok(recovered)
     }
 
-    pub async fn get_balance(&self, _address: &[u8]) -> Result<u64> {
-        // TODO: トークンの残高を取得
-        Ok(0)
+    /// 同一シャード内の送金（1回のバッチで完結）
+    async fn transfer_local(&self, pending: &PendingTransfer, available: u64) -> Result<()> {
+        let credited = self.read_balance(&pending.to).await?
+            .checked_add(pending.amount)
+            .ok_or(TransferError::Overflow)?;
+
+        let debit = self.receipt(pending, ReceiptKind::Debit, TransferPhase::Committed);
+        let credit = self.receipt(pending, ReceiptKind::Credit, TransferPhase::Committed);
+        self.storage.batch_write(vec![
+            (balance_key(&pending.from), Some((available - pending.amount).to_be_bytes().to_vec())),
+            (balance_key(&pending.to), Some(credited.to_be_bytes().to_vec())),
+            (receipt_key(&debit.tx_id), Some(serde_json::to_vec(&debit)?)),
+            (receipt_key(&credit.tx_id), Some(serde_json::to_vec(&credit)?)),
+        ]).await
+    }
+
+    /// 送金元シャードで引き落とし、処理中の記録を残す
+    async fn prepare(&self, pending: &PendingTransfer, available: u64) -> Result<()> {
+        let debit = self.receipt(pending, ReceiptKind::Debit, TransferPhase::Prepared);
+        self.storage.batch_write(vec![
+            (balance_key(&pending.from), Some((available - pending.amount).to_be_bytes().to_vec())),
+            (pending_key(&pending.id), Some(serde_json::to_vec(pending)?)),
+            (receipt_key(&debit.tx_id), Some(serde_json::to_vec(&debit)?)),
+        ]).await
+    }
+
+    /// 送金先シャードで入金し、両シャードのレシートを確定する
+    async fn commit(&self, pending: &PendingTransfer) -> Result<()> {
+        let credited = self.read_balance(&pending.to).await?
+            .checked_add(pending.amount)
+            .ok_or(TransferError::Overflow)?;
+
+        let debit = self.receipt(pending, ReceiptKind::Debit, TransferPhase::Committed);
+        let credit = self.receipt(pending, ReceiptKind::Credit, TransferPhase::Committed);
+        self.storage.batch_write(vec![
+            (balance_key(&pending.to), Some(credited.to_be_bytes().to_vec())),
+            (receipt_key(&debit.tx_id), Some(serde_json::to_vec(&debit)?)),
+            (receipt_key(&credit.tx_id), Some(serde_json::to_vec(&credit)?)),
+            (pending_key(&pending.id), None),
+        ]).await
+    }
+
+    /// 送金を取り消して送金元に返金する
+    async fn abort(&self, pending: &PendingTransfer) -> Result<()> {
+        let refunded = self.read_balance(&pending.from).await?
+            .checked_add(pending.amount)
+            .ok_or(TransferError::Overflow)?;
+
+        let debit = TransferReceipt {
+            linked_tx: Some(tx_id(&pending.id, ReceiptKind::Refund)),
+            ..self.receipt(pending, ReceiptKind::Debit, TransferPhase::Aborted)
+        };
+        let refund = self.receipt(pending, ReceiptKind::Refund, TransferPhase::Aborted);
+        self.storage.batch_write(vec![
+            (balance_key(&pending.from), Some(refunded.to_be_bytes().to_vec())),
+            (receipt_key(&debit.tx_id), Some(serde_json::to_vec(&debit)?)),
+            (receipt_key(&refund.tx_id), Some(serde_json::to_vec(&refund)?)),
+            (pending_key(&pending.id), None),
+        ]).await
+    }
+
+    /// レシートを作成（引き落としと入金は互いを参照する）
+    fn receipt(&self, pending: &PendingTransfer, kind: ReceiptKind, phase: TransferPhase) -> TransferReceipt {
+        let (shard, account, linked_tx) = match kind {
+            ReceiptKind::Debit => (pending.from_shard, &pending.from, Some(pending.credit_tx.clone())),
+            ReceiptKind::Credit => (pending.to_shard, &pending.to, Some(pending.debit_tx.clone())),
+            ReceiptKind::Refund => (pending.from_shard, &pending.from, Some(pending.debit_tx.clone())),
+        };
+        TransferReceipt {
+            tx_id: tx_id(&pending.id, kind),
+            transfer_id: pending.id.clone(),
+            kind,
+            shard,
+            account: account.clone(),
+            amount: pending.amount,
+            linked_tx,
+            phase,
+            timestamp: now(),
+        }
+    }
+
+    async fn read_balance(&self, address: &[u8]) -> Result<u64> {
+        Ok(self.storage.get(&balance_key(address)).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0))
+    }
+
+    /// 送金元・送金先のシャードを決定
+    async fn route(&self, from: &[u8], to: &[u8]) -> Result<(ShardId, ShardId)> {
+        match &self.shards {
+            Some(shards) => {
+                let shards = shards.read().await;
+                Ok((shards.route(from).await?, shards.route(to).await?))
+            }
+            None => Ok((0, 0)),
+        }
+    }
+
+    /// シャードの負荷計測に取引を記録し、他のシャードから検証できるレシートを発行
+    async fn record_activity(&self, pending: &PendingTransfer) {
+        let Some(shards) = &self.shards else {
+            return;
+        };
+        let shards = shards.read().await;
+        let legs = [
+            (pending.from_shard, &pending.from, &pending.debit_tx, "token.debit"),
+            (pending.to_shard, &pending.to, &pending.credit_tx, "token.credit"),
+        ];
+        for (shard_id, address, tx_id, topic) in legs {
+            let Ok(shard) = shards.get_shard(shard_id).await else {
+                continue;
+            };
+            let mut shard = shard.write().await;
+            if let Ok(account) = AccountId::try_from(address.as_slice()) {
+                shard.record_tx(&account);
+            }
+            let event = serde_json::json!({ "transfer_id": pending.id, "amount": pending.amount });
+            shard.emit_receipt(tx_id.clone(), address.clone(), topic, event.to_string().into_bytes());
+        }
     }
 }
 
@@ -40,4 +288,105 @@ pub struct Token {
     pub decimals: u8,
     pub total_supply: u64,
     pub owner: Vec<u8>,
-}
\ No newline at end of file
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn now_nanos() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    async fn setup() -> (Arc<dyn StorageEngine>, TokenManager) {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let mut shards = ShardManager::new(storage.clone());
+        shards.create_shard(1).await.unwrap();
+        let manager = TokenManager::new(storage.clone()).with_shards(Arc::new(RwLock::new(shards)));
+        (storage, manager)
+    }
+
+    /// 指定シャードに振り分けられるアドレスを探す
+    async fn address_on(manager: &TokenManager, shard: ShardId, seed: u8) -> Vec<u8> {
+        let shards = manager.shards.as_ref().unwrap().read().await;
+        for i in 0..=u8::MAX {
+            let address = vec![seed, i];
+            if shards.route(&address).await.unwrap() == shard {
+                return address;
+            }
+        }
+        panic!("no address routes to shard {}", shard);
+    }
+
+    #[tokio::test]
+    async fn test_cross_shard_transfer_links_receipts() {
+        let (_storage, manager) = setup().await;
+        let alice = address_on(&manager, 0, 1).await;
+        let bob = address_on(&manager, 1, 2).await;
+        manager.mint(&alice, 100).await.unwrap();
+
+        let outcome = manager.transfer(&alice, &bob, 40).await.unwrap();
+        assert!(outcome.cross_shard);
+        assert_eq!(manager.get_balance(&alice).await.unwrap(), 60);
+        assert_eq!(manager.get_balance(&bob).await.unwrap(), 40);
+
+        let debit = manager.get_receipt(&outcome.debit_tx).await.unwrap().unwrap();
+        let credit = manager.get_receipt(&outcome.credit_tx).await.unwrap().unwrap();
+        assert_eq!(debit.shard, 0);
+        assert_eq!(credit.shard, 1);
+        assert_eq!(debit.linked_tx.as_deref(), Some(outcome.credit_tx.as_str()));
+        assert_eq!(credit.linked_tx.as_deref(), Some(outcome.debit_tx.as_str()));
+        assert_eq!(debit.phase, TransferPhase::Committed);
+
+        // 残高を超える送金は拒否
+        let err = manager.transfer(&alice, &bob, 61).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(TransferError::InsufficientBalance { available: 60, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_recover_prepared_transfer() {
+        let (storage, manager) = setup().await;
+        let alice = address_on(&manager, 0, 1).await;
+        let bob = address_on(&manager, 1, 2).await;
+        manager.mint(&alice, 100).await.unwrap();
+
+        // prepare 直後に停止した状態を再現
+        let id = transfer_id(&alice, &bob, 30, 0);
+        let pending = PendingTransfer {
+            debit_tx: tx_id(&id, ReceiptKind::Debit),
+            credit_tx: tx_id(&id, ReceiptKind::Credit),
+            id,
+            from: alice.clone(),
+            to: bob.clone(),
+            amount: 30,
+            from_shard: 0,
+            to_shard: 1,
+            phase: TransferPhase::Prepared,
+            created_at: now(),
+        };
+        manager.prepare(&pending, 100).await.unwrap();
+        assert_eq!(manager.get_balance(&alice).await.unwrap(), 70);
+        assert_eq!(manager.get_balance(&bob).await.unwrap(), 0);
+
+        let recovered = manager.recover_pending().await.unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(manager.get_balance(&alice).await.unwrap(), 70);
+        assert_eq!(manager.get_balance(&bob).await.unwrap(), 30);
+        assert!(storage.get(&pending_key(&pending.id)).await.unwrap().is_none());
+
+        // 2回目は何もしない
+        assert!(manager.recover_pending().await.unwrap().is_empty());
+        assert_eq!(manager.get_balance(&bob).await.unwrap(), 30);
+    }
+}
@@ -0,0 +1,147 @@
+//! クロスシャード送金
+//!
+//! 送金元と送金先が異なるシャードに属する場合、送金元シャードでの引き落とし（prepare）と
+//! 送金先シャードでの入金（commit）の2段階で処理します。
+//! 各段階はストレージへの1回のバッチ書き込みで完結し、途中で停止しても
+//! `token/pending/` の記録から再開または取り消しができます。
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use thiserror::Error;
+use utoipa::ToSchema;
+use crate::core::sharding::{ShardId, Timestamp};
+
+/// 残高のキープレフィックス
+pub const BALANCE_PREFIX: &str = "token/balance/";
+/// 処理中のクロスシャード送金のキープレフィックス
+pub const PENDING_PREFIX: &str = "token/pending/";
+/// 送金レシートのキープレフィックス
+pub const RECEIPT_PREFIX: &str = "token/receipt/";
+
+/// 送金エラー
+#[derive(Debug, Error)]
+pub enum TransferError {
+    #[error("amount must be greater than zero")]
+    ZeroAmount,
+    #[error("sender and receiver are the same account")]
+    SelfTransfer,
+    #[error("insufficient balance: available {available}, required {required}")]
+    InsufficientBalance { available: u64, required: u64 },
+    #[error("balance overflow")]
+    Overflow,
+}
+
+/// クロスシャード送金の段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferPhase {
+    /// 送金元シャードで引き落とし済み
+    Prepared,
+    /// 送金先シャードで入金済み
+    Committed,
+    /// 取り消して送金元に返金済み
+    Aborted,
+}
+
+/// 処理中のクロスシャード送金
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTransfer {
+    pub id: String,
+    #[serde(with = "hex::serde")]
+    pub from: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub to: Vec<u8>,
+    pub amount: u64,
+    pub from_shard: ShardId,
+    pub to_shard: ShardId,
+    pub debit_tx: String,
+    pub credit_tx: String,
+    pub phase: TransferPhase,
+    pub created_at: Timestamp,
+}
+
+/// レシートの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptKind {
+    Debit,
+    Credit,
+    Refund,
+}
+
+/// シャード内の取引レシート
+///
+/// クロスシャード送金では、引き落としと入金のレシートが `linked_tx` で互いを参照します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransferReceipt {
+    pub tx_id: String,
+    pub transfer_id: String,
+    pub kind: ReceiptKind,
+    pub shard: ShardId,
+    #[serde(with = "hex::serde")]
+    #[schema(value_type = String)]
+    pub account: Vec<u8>,
+    pub amount: u64,
+    /// 対になる他シャードの取引
+    pub linked_tx: Option<String>,
+    pub phase: TransferPhase,
+    pub timestamp: Timestamp,
+}
+
+/// 送金結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransferOutcome {
+    pub transfer_id: String,
+    pub from_shard: ShardId,
+    pub to_shard: ShardId,
+    pub debit_tx: String,
+    pub credit_tx: String,
+    pub cross_shard: bool,
+}
+
+impl From<&PendingTransfer> for TransferOutcome {
+    fn from(pending: &PendingTransfer) -> Self {
+        Self {
+            transfer_id: pending.id.clone(),
+            from_shard: pending.from_shard,
+            to_shard: pending.to_shard,
+            debit_tx: pending.debit_tx.clone(),
+            credit_tx: pending.credit_tx.clone(),
+            cross_shard: pending.from_shard != pending.to_shard,
+        }
+    }
+}
+
+pub fn balance_key(address: &[u8]) -> Vec<u8> {
+    format!("{}{}", BALANCE_PREFIX, hex::encode(address)).into_bytes()
+}
+
+pub fn pending_key(id: &str) -> Vec<u8> {
+    format!("{}{}", PENDING_PREFIX, id).into_bytes()
+}
+
+pub fn receipt_key(tx_id: &str) -> Vec<u8> {
+    format!("{}{}", RECEIPT_PREFIX, tx_id).into_bytes()
+}
+
+/// 送金IDを生成
+pub fn transfer_id(from: &[u8], to: &[u8], amount: u64, nonce: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(from);
+    hasher.update(to);
+    hasher.update(amount.to_be_bytes());
+    hasher.update(nonce.to_be_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// 送金IDと種類からシャード内の取引IDを導出
+pub fn tx_id(transfer_id: &str, kind: ReceiptKind) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(transfer_id.as_bytes());
+    hasher.update(match kind {
+        ReceiptKind::Debit => b"debit".as_slice(),
+        ReceiptKind::Credit => b"credit".as_slice(),
+        ReceiptKind::Refund => b"refund".as_slice(),
+    });
+    hex::encode(hasher.finalize())
+}
@@ -1,8 +1,12 @@
+pub mod redpanda;
+
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::collections::HashMap;
 
+pub use redpanda::{ChainSink, RedpandaClient, RedpandaConfig};
+
 /// Redpandaベースのトランザクション受付レイヤー
 pub struct TransactionManager {
     shards: HashMap<ShardId, Arc<Mutex<TransactionShard>>>,
@@ -49,7 +53,7 @@ impl TransactionShard {
     pub async fn submit(&mut self, tx: Transaction) -> Result<TxReceipt> {
         // Redpandaへのトランザクション投入
         let topic = self.get_topic_for_tx(&tx);
-        self.redpanda.produce(topic, tx.serialize()?).await?;
+        self.redpanda.produce(&topic, &tx.id(), &tx.serialize()?).await?;
 
         Ok(TxReceipt {
             tx_id: tx.id(),
@@ -63,19 +67,6 @@ impl TransactionShard {
     }
 }
 
-/// Redpandaクライアント
-pub struct RedpandaClient {
-    brokers: Vec<String>,
-    client_config: HashMap<String, String>,
-}
-
-impl RedpandaClient {
-    pub async fn produce(&self, topic: String, data: Vec<u8>) -> Result<()> {
-        // TODO: 実際のRedpanda実装
-        Ok(())
-    }
-}
-
 // 補助的な型定義
 pub type ShardId = String;
 
@@ -111,12 +102,6 @@ impl TransactionConfig {
     }
 }
 
-#[derive(Debug)]
-pub struct RedpandaConfig {
-    pub brokers: Vec<String>,
-    pub client_settings: HashMap<String, String>,
-}
-
 #[derive(Debug)]
 pub struct Transaction {
     id: String,
@@ -0,0 +1,453 @@
+//! Redpanda/Kafkaへのチェーンデータ配信
+//!
+//! 確定したブロック、トランザクション、イベントを設定されたトピックへ発行し、
+//! 下流のデータ基盤がAPIをポーリングせずにチェーンデータを取り込めるようにします。
+//! 主な機能：
+//! - ブロック単位のKafkaトランザクションによる発行（冪等プロデューサー）
+//! - 内容から決まるメッセージキー（再送されても同じキー）
+//! - Schema Registry 互換のペイロード（JSON Schema を登録し、ワイヤーフォーマットで送信）
+//! - 発行済みの高さを記録し、再起動後に続きから再開
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use crate::config::StreamingSettings;
+use crate::core::block::{Block, Chain, Event};
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+
+/// 発行済みの高さのキー
+const PUBLISHED_HEIGHT_KEY: &[u8] = b"stream/height";
+/// Confluent ワイヤーフォーマットのマジックバイト
+const WIRE_MAGIC: u8 = 0;
+/// 発行に失敗した場合の再試行間隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Redpandaクライアントの設定
+#[derive(Debug, Clone)]
+pub struct RedpandaConfig {
+    pub brokers: Vec<String>,
+    /// librdkafka にそのまま渡す設定
+    pub client_settings: HashMap<String, String>,
+    /// 設定するとトランザクショナルプロデューサーとして動作する
+    pub transactional_id: Option<String>,
+    pub timeout: Duration,
+}
+
+/// Redpandaクライアント
+#[derive(Clone)]
+pub struct RedpandaClient {
+    producer: FutureProducer,
+    transactional: bool,
+    timeout: Duration,
+}
+
+impl RedpandaClient {
+    pub fn new(config: &RedpandaConfig) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", config.brokers.join(","))
+            .set("enable.idempotence", "true")
+            .set("acks", "all");
+        if let Some(id) = &config.transactional_id {
+            client_config.set("transactional.id", id);
+        }
+        for (key, value) in &config.client_settings {
+            client_config.set(key, value);
+        }
+
+        let producer: FutureProducer = client_config.create()?;
+        if config.transactional_id.is_some() {
+            producer.init_transactions(Timeout::After(config.timeout))?;
+        }
+        Ok(Self {
+            producer,
+            transactional: config.transactional_id.is_some(),
+            timeout: config.timeout,
+        })
+    }
+
+    /// メッセージを発行
+    pub async fn produce(&self, topic: &str, key: &str, data: &[u8]) -> Result<()> {
+        self.producer
+            .send(FutureRecord::to(topic).key(key).payload(data), Timeout::After(self.timeout))
+            .await
+            .map_err(|(e, _)| anyhow!("failed to produce to {}: {}", topic, e))?;
+        Ok(())
+    }
+
+    /// 複数のメッセージをまとめて発行
+    ///
+    /// トランザクショナルプロデューサーの場合は全件が1つのトランザクションで確定し、
+    /// 失敗した場合はすべて取り消されます。
+    pub async fn produce_all(&self, messages: Vec<Message>) -> Result<()> {
+        if self.transactional {
+            self.producer.begin_transaction()?;
+        }
+        for message in &messages {
+            if let Err(e) = self.produce(&message.topic, &message.key, &message.payload).await {
+                if self.transactional {
+                    self.run_blocking(|producer, timeout| producer.abort_transaction(timeout)).await?;
+                }
+                return Err(e);
+            }
+        }
+        if self.transactional {
+            self.run_blocking(|producer, timeout| producer.commit_transaction(timeout)).await?;
+        } else {
+            self.run_blocking(|producer, timeout| producer.flush(timeout)).await?;
+        }
+        Ok(())
+    }
+
+    /// librdkafka のブロッキング呼び出しを専用スレッドで実行
+    async fn run_blocking<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&FutureProducer, Timeout) -> rdkafka::error::KafkaResult<()> + Send + 'static,
+    {
+        let producer = self.producer.clone();
+        let timeout = Timeout::After(self.timeout);
+        tokio::task::spawn_blocking(move || f(&producer, timeout)).await??;
+        Ok(())
+    }
+}
+
+/// 発行するメッセージ
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub topic: String,
+    pub key: String,
+    pub payload: Vec<u8>,
+}
+
+/// ブロックのメッセージ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRecord {
+    pub height: u64,
+    pub hash: String,
+    pub parent_hash: String,
+    pub timestamp: u64,
+    pub validator: String,
+    pub transaction_count: u32,
+    pub event_count: u32,
+}
+
+/// トランザクションのメッセージ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub hash: String,
+    pub block_height: u64,
+    pub block_hash: String,
+    pub index: u32,
+    pub from: String,
+    pub to: String,
+    pub value: u64,
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    /// データ（hex）
+    pub data: String,
+}
+
+/// イベントのメッセージ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub tx_hash: String,
+    pub block_height: u64,
+    pub block_hash: String,
+    pub index: u32,
+    pub address: String,
+    pub topics: Vec<String>,
+    /// データ（hex）
+    pub data: String,
+}
+
+impl BlockRecord {
+    fn from_block(block: &Block) -> Self {
+        Self {
+            height: block.height,
+            hash: block.hash.clone(),
+            parent_hash: block.parent_hash.clone(),
+            timestamp: block.timestamp,
+            validator: block.validator.clone(),
+            transaction_count: block.transactions.len() as u32,
+            event_count: block.events.len() as u32,
+        }
+    }
+}
+
+impl TransactionRecord {
+    fn from_tx(block: &Block, index: usize, tx: &PendingTransaction) -> Self {
+        Self {
+            hash: tx.hash.clone(),
+            block_height: block.height,
+            block_hash: block.hash.clone(),
+            index: index as u32,
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            value: tx.value,
+            nonce: tx.nonce,
+            gas_price: tx.gas_price,
+            gas_limit: tx.gas_limit,
+            data: hex::encode(&tx.data),
+        }
+    }
+}
+
+impl EventRecord {
+    fn from_event(block: &Block, event: &Event) -> Self {
+        Self {
+            tx_hash: event.tx_hash.clone(),
+            block_height: block.height,
+            block_hash: block.hash.clone(),
+            index: event.index,
+            address: event.address.clone(),
+            topics: event.topics.clone(),
+            data: hex::encode(&event.data),
+        }
+    }
+}
+
+/// 配信するレコードの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RecordKind {
+    Block,
+    Transaction,
+    Event,
+}
+
+impl RecordKind {
+    /// レコードの JSON Schema
+    fn schema(self) -> serde_json::Value {
+        let (title, properties, required): (&str, serde_json::Value, Vec<&str>) = match self {
+            Self::Block => ("Block", json!({
+                "height": { "type": "integer" },
+                "hash": { "type": "string" },
+                "parent_hash": { "type": "string" },
+                "timestamp": { "type": "integer" },
+                "validator": { "type": "string" },
+                "transaction_count": { "type": "integer" },
+                "event_count": { "type": "integer" }
+            }), vec!["height", "hash", "parent_hash", "timestamp"]),
+            Self::Transaction => ("Transaction", json!({
+                "hash": { "type": "string" },
+                "block_height": { "type": "integer" },
+                "block_hash": { "type": "string" },
+                "index": { "type": "integer" },
+                "from": { "type": "string" },
+                "to": { "type": "string" },
+                "value": { "type": "integer" },
+                "nonce": { "type": "integer" },
+                "gas_price": { "type": "integer" },
+                "gas_limit": { "type": "integer" },
+                "data": { "type": "string" }
+            }), vec!["hash", "block_height", "block_hash", "index", "from", "to"]),
+            Self::Event => ("Event", json!({
+                "tx_hash": { "type": "string" },
+                "block_height": { "type": "integer" },
+                "block_hash": { "type": "string" },
+                "index": { "type": "integer" },
+                "address": { "type": "string" },
+                "topics": { "type": "array", "items": { "type": "string" } },
+                "data": { "type": "string" }
+            }), vec!["tx_hash", "block_height", "index", "address"]),
+        };
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": title,
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+}
+
+/// Schema Registry クライアント
+struct SchemaRegistry {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl SchemaRegistry {
+    /// `{topic}-value` サブジェクトにスキーマを登録し、スキーマIDを返す
+    async fn register(&self, topic: &str, schema: &serde_json::Value) -> Result<u32> {
+        #[derive(Deserialize)]
+        struct Registered {
+            id: u32,
+        }
+
+        let url = format!("{}/subjects/{}-value/versions", self.url.trim_end_matches('/'), topic);
+        let response = self.client
+            .post(url)
+            .header("Content-Type", "application/vnd.schemaregistry.v1+json")
+            .json(&json!({ "schemaType": "JSON", "schema": schema.to_string() }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json::<Registered>().await?.id)
+    }
+}
+
+/// チェーンデータの配信先
+pub struct ChainSink {
+    client: RedpandaClient,
+    storage: Arc<dyn StorageEngine>,
+    topics: HashMap<RecordKind, String>,
+    /// トピックごとのスキーマID（Schema Registry 未使用の場合は空）
+    schema_ids: HashMap<RecordKind, u32>,
+}
+
+impl ChainSink {
+    pub async fn new(settings: &StreamingSettings, storage: Arc<dyn StorageEngine>) -> Result<Self> {
+        let client = RedpandaClient::new(&RedpandaConfig::from(settings))?;
+        let topics = HashMap::from([
+            (RecordKind::Block, settings.blocks_topic.clone()),
+            (RecordKind::Transaction, settings.transactions_topic.clone()),
+            (RecordKind::Event, settings.events_topic.clone()),
+        ]);
+
+        let mut schema_ids = HashMap::new();
+        if let Some(url) = &settings.schema_registry_url {
+            let registry = SchemaRegistry { url: url.clone(), client: reqwest::Client::new() };
+            for (kind, topic) in &topics {
+                let id = registry.register(topic, &kind.schema()).await?;
+                info!("Registered schema {} for topic {}", id, topic);
+                schema_ids.insert(*kind, id);
+            }
+        }
+
+        Ok(Self { client, storage, topics, schema_ids })
+    }
+
+    /// 発行済みのブロックの高さ
+    pub async fn published_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(PUBLISHED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// ブロックとその中のトランザクション・イベントを発行
+    ///
+    /// 発行済みのブロックは無視します。メッセージキーは内容から決まるため、
+    /// 記録前に停止して再送した場合もコンパクション対象のトピックでは重複しません。
+    pub async fn publish_block(&self, block: &Block) -> Result<()> {
+        if self.published_height().await?.is_some_and(|h| block.height <= h) {
+            return Ok(());
+        }
+
+        let mut messages = vec![self.message(RecordKind::Block, block.hash.clone(), &BlockRecord::from_block(block))?];
+        for (index, tx) in block.transactions.iter().enumerate() {
+            messages.push(self.message(RecordKind::Transaction, tx.hash.clone(), &TransactionRecord::from_tx(block, index, tx))?);
+        }
+        for event in &block.events {
+            let key = format!("{}:{}", event.tx_hash, event.index);
+            messages.push(self.message(RecordKind::Event, key, &EventRecord::from_event(block, event))?);
+        }
+
+        self.client.produce_all(messages).await?;
+        self.storage.put(PUBLISHED_HEIGHT_KEY, &block.height.to_be_bytes()).await
+    }
+
+    fn message<T: Serialize>(&self, kind: RecordKind, key: String, record: &T) -> Result<Message> {
+        Ok(Message {
+            topic: self.topics[&kind].clone(),
+            key,
+            payload: encode(self.schema_ids.get(&kind).copied(), record)?,
+        })
+    }
+
+    /// 発行済みの次の高さから最新ブロックまで発行
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        let Some((head, _)) = chain.head().await else {
+            return Ok(());
+        };
+        let start = self.published_height().await?.map_or(0, |h| h + 1);
+        for height in start..=head {
+            if let Some(block) = chain.get_block(height).await? {
+                self.publish_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// ブロックの確定を購読して発行し続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            loop {
+                // 未発行のブロックはストレージから読み直すため、通知は発行の契機としてのみ使う
+                match self.catch_up(&chain).await {
+                    Ok(()) => match commits.recv().await {
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    Err(e) => {
+                        warn!("Failed to publish chain data: {}", e);
+                        tokio::time::sleep(RETRY_INTERVAL).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl From<&StreamingSettings> for RedpandaConfig {
+    fn from(settings: &StreamingSettings) -> Self {
+        Self {
+            brokers: settings.brokers.clone(),
+            client_settings: settings.client_settings.clone(),
+            transactional_id: settings.transactional_id.clone(),
+            timeout: Duration::from_millis(settings.timeout),
+        }
+    }
+}
+
+/// レコードをエンコード
+///
+/// スキーマIDがある場合は Confluent ワイヤーフォーマット（マジックバイト、スキーマID、JSON）にします。
+fn encode<T: Serialize>(schema_id: Option<u32>, record: &T) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(record)?;
+    Ok(match schema_id {
+        Some(id) => {
+            let mut payload = Vec::with_capacity(5 + json.len());
+            payload.push(WIRE_MAGIC);
+            payload.extend_from_slice(&id.to_be_bytes());
+            payload.extend_from_slice(&json);
+            payload
+        }
+        None => json,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_format() {
+        let record = json!({ "height": 1 });
+        assert_eq!(encode(None, &record).unwrap(), br#"{"height":1}"#.to_vec());
+
+        let payload = encode(Some(7), &record).unwrap();
+        assert_eq!(&payload[..5], &[0, 0, 0, 0, 7]);
+        assert_eq!(&payload[5..], br#"{"height":1}"#);
+    }
+
+    #[test]
+    fn test_schemas_cover_record_fields() {
+        let block = Block::new(0, String::new(), "v".to_string(), vec![]);
+        let record = serde_json::to_value(BlockRecord::from_block(&block)).unwrap();
+        let schema = RecordKind::Block.schema();
+        for field in record.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{} is missing from the schema", field);
+        }
+    }
+}
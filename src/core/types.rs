@@ -0,0 +1,84 @@
+//! 共通の型とハッシュ
+//!
+//! トランザクションのハッシュ（ID）は次の正規化JSONハッシュで計算します。
+//! 外部のシステムも同じ手順で送信前にIDを計算できます。
+//!
+//! 1. 署名を含まない本体をJSONオブジェクトで表す（バイト列は小文字のhex文字列、省略可能な項目は未指定なら含めない）
+//! 2. オブジェクトのキーをUTF-8のバイト順で並べ、空白を入れずに出力する
+//!    （文字列のエスケープは `"`・`\`・制御文字のみ、数値は整数の10進表記）
+//! 3. 出力したバイト列のSHA-256を小文字のhexで表したものをハッシュとする
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// 正規化したJSON文字列
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+/// 正規化したJSONのSHA-256（hex）
+pub fn canonical_hash(value: &Value) -> String {
+    sha256_hex(canonical_json(value).as_bytes())
+}
+
+/// SHA-256（hex）
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            // serde_json の設定（preserve_order）に依存しないよう明示的に並べる
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_json_is_order_independent() {
+        let a: Value = serde_json::from_str(r#"{ "b": [1, {"z": null, "a": "x\ny"}], "a": true }"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a":true,"b":[1,{"a":"x\ny","z":null}]}"#).unwrap();
+        assert_eq!(canonical_json(&a), r#"{"a":true,"b":[1,{"a":"x\ny","z":null}]}"#);
+        assert_eq!(canonical_hash(&a), canonical_hash(&b));
+    }
+
+    #[test]
+    fn test_canonical_hash_vector() {
+        // 外部実装との照合用の固定ベクター
+        assert_eq!(canonical_json(&json!({})), "{}");
+        assert_eq!(
+            canonical_hash(&json!({})),
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! トークンのベスティング（ロックアップ）
+//!
+//! 送金に付与するスケジュールで、受取人が送金額を使えるようになる時期を制限します。
+//! 付与はトランザクションの `data` にスケジュールを入れた受取人宛ての送金で、`value` が付与額です。
+//!
+//! | 位置   | 内容 |
+//! |--------|------|
+//! | 0..4   | マジック `vest`（`76657374`） |
+//! | 4      | バージョン（`1`） |
+//! | 5..13  | 開始時刻（UNIX秒、u64 BE） |
+//! | 13..21 | クリフ（開始からの秒数、u64 BE） |
+//! | 21..29 | 期間（開始からの秒数、u64 BE） |
+//!
+//! クリフまでは何も使えず、クリフ以降は開始からの経過時間に比例して解放され、期間の終わりに
+//! 全額が解放されます（クリフ時点でそれまでの比例分がまとめて解放される）。スケジュールを持つ
+//! アカウントの送金は、残高からロック中の額を引いた額までに制限されます。制限は受付時と
+//! ブロックの生成時に検証し、確定前にもブロックのタイムスタンプの時点で検証するため（`BlockRule`）、
+//! 制限を破るブロックは確定しません。スケジュールを持たないアカウントには影響しません。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, BlockRule, Chain};
+use crate::core::cache::MaterializedViews;
+use crate::core::mempool::PendingTransaction;
+use crate::core::storage::StorageEngine;
+use crate::core::storage::typed::StateObject;
+
+/// 反映済みのブロックの高さのキー
+const APPLIED_HEIGHT_KEY: &[u8] = b"vesting/applied_height";
+
+/// 付与の先頭のマジック
+pub const VESTING_MAGIC: [u8; 4] = *b"vest";
+/// 形式のバージョン
+const VESTING_VERSION: u8 = 1;
+/// 1アカウントが持てるスケジュールの上限
+pub const MAX_GRANTS: usize = 64;
+/// スケジュールの期間の上限（秒）
+pub const MAX_DURATION_SECS: u64 = 20 * 365 * 24 * 60 * 60;
+
+/// ベスティングのエラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum VestingError {
+    #[error("vesting grant is truncated")]
+    Truncated,
+
+    #[error("unsupported vesting grant version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("vesting grant must transfer a non-zero value")]
+    EmptyGrant,
+
+    #[error("vesting grant cannot be sent to the sender")]
+    SelfGrant,
+
+    #[error("vesting duration must be between 1 second and 20 years, with the cliff no later than the end")]
+    InvalidSchedule,
+
+    #[error("account {0} already has the maximum of 64 vesting grants")]
+    TooManyGrants(String),
+
+    #[error("{locked} of the balance is still vesting; {spendable} can be spent, {required} is required")]
+    Locked { locked: u64, spendable: u64, required: u64 },
+
+    #[error("vesting state is unavailable")]
+    Unavailable,
+}
+
+/// `data` に入れるスケジュール
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub start: u64,
+    pub cliff_secs: u64,
+    pub duration_secs: u64,
+}
+
+impl Schedule {
+    /// `data` フィールドの内容に変換
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = VESTING_MAGIC.to_vec();
+        data.push(VESTING_VERSION);
+        data.extend_from_slice(&self.start.to_be_bytes());
+        data.extend_from_slice(&self.cliff_secs.to_be_bytes());
+        data.extend_from_slice(&self.duration_secs.to_be_bytes());
+        data
+    }
+
+    /// `data` フィールドを解析（付与でなければ `None`）
+    pub fn decode(data: &[u8]) -> Option<Result<Self, VestingError>> {
+        let body = data.strip_prefix(&VESTING_MAGIC)?;
+        Some(Self::decode_body(body))
+    }
+
+    fn decode_body(body: &[u8]) -> Result<Self, VestingError> {
+        let [version, rest @ ..] = body else {
+            return Err(VestingError::Truncated);
+        };
+        if *version != VESTING_VERSION {
+            return Err(VestingError::UnsupportedVersion(*version));
+        }
+        if rest.len() != 24 {
+            return Err(VestingError::Truncated);
+        }
+        let field = |i: usize| u64::from_be_bytes(rest[i * 8..(i + 1) * 8].try_into().expect("8 bytes"));
+        let schedule = Self { start: field(0), cliff_secs: field(1), duration_secs: field(2) };
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    pub fn validate(&self) -> Result<(), VestingError> {
+        if self.duration_secs == 0 || self.duration_secs > MAX_DURATION_SECS || self.cliff_secs > self.duration_secs {
+            return Err(VestingError::InvalidSchedule);
+        }
+        Ok(())
+    }
+
+    /// トランザクションのスケジュール
+    pub fn of(tx: &PendingTransaction) -> Option<Result<Self, VestingError>> {
+        Self::decode(&tx.data)
+    }
+}
+
+/// 付与されたスケジュール
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct VestingGrant {
+    /// 付与のトランザクション
+    pub tx_hash: String,
+    pub grantor: String,
+    pub amount: u64,
+    /// 開始時刻（UNIX秒）
+    pub start: u64,
+    pub cliff_secs: u64,
+    pub duration_secs: u64,
+}
+
+impl VestingGrant {
+    /// `now` の時点で解放済みの額
+    pub fn unlocked(&self, now: u64) -> u64 {
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff_secs {
+            0
+        } else if elapsed >= self.duration_secs {
+            self.amount
+        } else {
+            (self.amount as u128 * elapsed as u128 / self.duration_secs as u128) as u64
+        }
+    }
+
+    /// `now` の時点でロック中の額
+    pub fn locked(&self, now: u64) -> u64 {
+        self.amount - self.unlocked(now)
+    }
+}
+
+/// アカウントのスケジュール
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, StateObject)]
+#[state(cf = "vesting/account", version = 1)]
+pub struct VestingAccount {
+    #[state(key)]
+    pub address: String,
+    pub grants: Vec<VestingGrant>,
+    /// 最後に付与されたブロックの高さ
+    pub updated_at: u64,
+}
+
+impl VestingAccount {
+    /// `now` の時点でロック中の額
+    pub fn locked(&self, now: u64) -> u64 {
+        self.grants.iter().fold(0u64, |sum, grant| sum.saturating_add(grant.locked(now)))
+    }
+}
+
+/// アカウントの解放済みとロック中の残高
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VestingReport {
+    pub address: String,
+    /// 残高（ビューに反映済みのブロックまで）
+    pub balance: u64,
+    /// ロック中の額
+    pub locked: u64,
+    /// 使える額（残高からロック中の額を引いたもの）
+    pub unlocked: u64,
+    /// 計算に使った時刻（UNIX秒）
+    pub as_of: u64,
+    pub grants: Vec<VestingGrantStatus>,
+}
+
+/// スケジュールの現在の状態
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VestingGrantStatus {
+    #[serde(flatten)]
+    pub grant: VestingGrant,
+    pub unlocked: u64,
+    pub locked: u64,
+}
+
+/// ベスティングの台帳
+pub struct VestingLedger {
+    storage: Arc<dyn StorageEngine>,
+    views: Arc<MaterializedViews>,
+    /// ブロックの反映を直列にする（購読と確定前の追いつきが同時に反映しないように）
+    applying: Mutex<()>,
+}
+
+impl VestingLedger {
+    pub fn new(storage: Arc<dyn StorageEngine>, views: Arc<MaterializedViews>) -> Self {
+        Self { storage, views, applying: Mutex::new(()) }
+    }
+
+    /// アカウントのスケジュール
+    pub async fn account(&self, address: &str) -> Result<Option<VestingAccount>> {
+        VestingAccount::load(self.storage.as_ref(), &address.to_string()).await
+    }
+
+    /// `now` の時点の解放済みとロック中の残高
+    pub async fn report(&self, address: &str, now: u64) -> Result<VestingReport> {
+        let balance = self.views.balance(address).await?;
+        let grants = self.account(address).await?.map(|a| a.grants).unwrap_or_default();
+        let locked = grants.iter().fold(0u64, |sum, grant| sum.saturating_add(grant.locked(now)));
+        Ok(VestingReport {
+            address: address.to_string(),
+            balance,
+            locked,
+            unlocked: balance.saturating_sub(locked),
+            as_of: now,
+            grants: grants.into_iter()
+                .map(|grant| VestingGrantStatus { unlocked: grant.unlocked(now), locked: grant.locked(now), grant })
+                .collect(),
+        })
+    }
+
+    /// 受付時の検証（付与の形式と、送信者のロック中の残高）
+    pub async fn check(&self, tx: &PendingTransaction) -> Result<(), VestingError> {
+        let now = unix_now();
+        self.check_grant(&mut HashMap::new(), tx, 0).await?;
+        self.check_spend(&mut HashMap::new(), tx, now).await
+    }
+
+    /// ロック中の残高を使うトランザクション（と同じ送信者の後続のもの）をブロックから除く
+    pub async fn retain_valid(&self, txs: &mut Vec<PendingTransaction>) {
+        let now = unix_now();
+        let mut accounts = HashMap::new();
+        let mut spent = HashMap::new();
+        let mut dropped = HashSet::new();
+        let mut kept = Vec::with_capacity(txs.len());
+        for tx in txs.drain(..) {
+            if dropped.contains(&tx.from) {
+                continue;
+            }
+            // ブロック内の付与は次のブロックから制限に含まれるため、検証のみ行う
+            let result = match self.check_grant(&mut accounts, &tx, 0).await {
+                Ok(()) => self.check_spend(&mut spent, &tx, now).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => kept.push(tx),
+                Err(e) => {
+                    debug!("Leaving {} out of the block: {}", tx.hash, e);
+                    dropped.insert(tx.from.clone());
+                }
+            }
+        }
+        *txs = kept;
+    }
+
+    /// ブロックの取引がブロックのタイムスタンプの時点でスケジュールを守っているか検証（状態は変更しない）
+    ///
+    /// 生成時の `retain_valid` と同じく、形式の誤った付与とロック中の残高を使う送金を拒否します。
+    pub async fn check_block(&self, block: &Block) -> Result<()> {
+        let mut accounts = HashMap::new();
+        let mut spent = HashMap::new();
+        for tx in &block.transactions {
+            let result = match self.check_grant(&mut accounts, tx, 0).await {
+                Ok(()) => self.check_spend(&mut spent, tx, block.timestamp).await,
+                Err(e) => Err(e),
+            };
+            result.map_err(|e| anyhow!("Transaction {} breaks a vesting schedule: {}", tx.hash, e))?;
+        }
+        Ok(())
+    }
+
+    /// 付与を検証して `accounts` に反映する
+    async fn check_grant(
+        &self,
+        accounts: &mut HashMap<String, VestingAccount>,
+        tx: &PendingTransaction,
+        height: u64,
+    ) -> Result<(), VestingError> {
+        let schedule = match Schedule::of(tx) {
+            None => return Ok(()),
+            Some(schedule) => schedule?,
+        };
+        if tx.value == 0 {
+            return Err(VestingError::EmptyGrant);
+        }
+        if tx.to == tx.from {
+            return Err(VestingError::SelfGrant);
+        }
+        let mut account = match accounts.get(&tx.to) {
+            Some(account) => account.clone(),
+            None => self.account(&tx.to).await.map_err(|_| VestingError::Unavailable)?
+                .unwrap_or_else(|| VestingAccount { address: tx.to.clone(), grants: Vec::new(), updated_at: height }),
+        };
+        if account.grants.len() >= MAX_GRANTS {
+            return Err(VestingError::TooManyGrants(tx.to.clone()));
+        }
+        account.grants.push(VestingGrant {
+            tx_hash: tx.hash.clone(),
+            grantor: tx.from.clone(),
+            amount: tx.value,
+            start: schedule.start,
+            cliff_secs: schedule.cliff_secs,
+            duration_secs: schedule.duration_secs,
+        });
+        account.updated_at = height;
+        accounts.insert(tx.to.clone(), account);
+        Ok(())
+    }
+
+    /// 送信者のロック中でない残高で `value` を払えるか（`spent` はブロック内で使った額）
+    async fn check_spend(
+        &self,
+        spent: &mut HashMap<String, u64>,
+        tx: &PendingTransaction,
+        now: u64,
+    ) -> Result<(), VestingError> {
+        if tx.value == 0 {
+            return Ok(());
+        }
+        let Some(account) = self.account(&tx.from).await.map_err(|_| VestingError::Unavailable)? else {
+            return Ok(());
+        };
+        let locked = account.locked(now);
+        if locked == 0 {
+            return Ok(());
+        }
+        let balance = self.views.balance(&tx.from).await.map_err(|_| VestingError::Unavailable)?;
+        let used = spent.get(&tx.from).copied().unwrap_or(0);
+        let spendable = balance.saturating_sub(locked).saturating_sub(used);
+        if tx.value > spendable {
+            return Err(VestingError::Locked { locked, spendable, required: tx.value });
+        }
+        spent.insert(tx.from.clone(), used + tx.value);
+        Ok(())
+    }
+
+    /// 確定したブロックの付与を適用する
+    ///
+    /// 形式の誤った付与は通常の送金として扱います（確定前の検証の導入前のブロックのみ）。
+    /// 反映済みの高さ以下のブロックは無視します。
+    pub async fn apply_block(&self, block: &Block) -> Result<()> {
+        let _applying = self.applying.lock().await;
+        if self.applied_height().await?.is_some_and(|applied| block.height <= applied) {
+            return Ok(());
+        }
+        let mut accounts = HashMap::new();
+        for tx in &block.transactions {
+            if let Err(e) = self.check_grant(&mut accounts, tx, block.height).await {
+                warn!("Vesting grant {} in block {} was not applied: {}", tx.hash, block.height, e);
+            }
+        }
+        let mut batch = accounts.values()
+            .map(VestingAccount::put_change)
+            .collect::<Result<Vec<_>>>()?;
+        batch.push((APPLIED_HEIGHT_KEY.to_vec(), Some(block.height.to_be_bytes().to_vec())));
+        self.storage.batch_write(batch).await
+    }
+
+    /// 反映済みのブロックの高さ
+    pub async fn applied_height(&self) -> Result<Option<u64>> {
+        Ok(self.storage.get(APPLIED_HEIGHT_KEY).await?
+            .and_then(|v| v.try_into().ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// 高さ `height` までの確定したブロックをストレージから読み直して反映する
+    ///
+    /// チェーンの先頭を読まないため、確定中の検証（`BlockRule`）からも呼べます。
+    pub async fn catch_up_to(&self, chain: &Chain, height: u64) -> Result<()> {
+        let start = match self.applied_height().await? {
+            Some(applied) => applied + 1,
+            None => chain.base().await?.unwrap_or(0),
+        };
+        for height in start..=height {
+            if let Some(block) = chain.get_block(height).await? {
+                self.apply_block(&block).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 最新のブロックまで追いつかせる
+    pub async fn catch_up(&self, chain: &Chain) -> Result<()> {
+        match chain.head().await {
+            Some((head, _)) => self.catch_up_to(chain, head).await,
+            None => Ok(()),
+        }
+    }
+
+    /// 以降の確定で付与を適用する（取りこぼした場合はストレージから読み直す）
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up(&chain).await {
+                warn!("Failed to catch up the vesting ledger: {}", e);
+            }
+            loop {
+                let result = match commits.recv().await {
+                    Ok(block) => self.apply_block(&block).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(&chain).await,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply vesting grants: {}", e);
+                    if let Err(e) = self.catch_up(&chain).await {
+                        warn!("Failed to catch up the vesting ledger: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl BlockRule for VestingLedger {
+    /// 親までのビューと付与を反映してから、ブロックのタイムスタンプの時点で検証する
+    async fn check_block(&self, chain: &Chain, block: &Block) -> Result<()> {
+        if let Some(parent) = block.height.checked_sub(1) {
+            self.views.catch_up_to(chain, parent).await?;
+            self.catch_up_to(chain, parent).await?;
+        }
+        VestingLedger::check_block(self, block).await
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::core::cache::ExportedState;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    fn tx(from: &str, to: &str, value: u64, nonce: u64, data: Vec<u8>) -> PendingTransaction {
+        PendingTransaction::test_transfer(from, to, value, nonce).with_data(data)
+    }
+
+    #[tokio::test]
+    async fn test_grant_locks_balance_until_vested() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let genesis = ExportedState { balances: BTreeMap::from([("00".to_string(), 5000)]), ..Default::default() };
+        let views = Arc::new(MaterializedViews::new(storage.clone()).with_genesis(genesis));
+        let ledger = VestingLedger::new(storage, views.clone());
+        let (treasury, investor, bob) = ("a".repeat(40), "b".repeat(40), "c".repeat(40));
+
+        // 1年のクリフ、4年で全額が解放されるスケジュールで 1000 を付与する
+        let now = unix_now();
+        let year = 365 * 24 * 60 * 60;
+        let schedule = Schedule { start: now - 2 * year, cliff_secs: year, duration_secs: 4 * year };
+        assert_eq!(Schedule::decode(&schedule.encode()), Some(Ok(schedule)));
+        let mut genesis = Block::new(0, "p".to_string(), "v".to_string(), vec![
+            tx("00", &treasury, 5000, 0, vec![]),
+            tx(&treasury, &investor, 1000, 0, schedule.encode()),
+            tx(&treasury, &investor, 100, 1, vec![]),
+        ]);
+        genesis.timestamp = now;
+        views.apply_block(&genesis).await.unwrap();
+        ledger.apply_block(&genesis).await.unwrap();
+
+        // 2年経過で半分が解放済み。付与と別に受け取った 100 は制限されない
+        let report = ledger.report(&investor, now).await.unwrap();
+        assert_eq!((report.balance, report.locked, report.unlocked), (1100, 500, 600));
+        assert_eq!(ledger.report(&investor, now - year - 1).await.unwrap().locked, 1000);
+        assert_eq!(ledger.report(&investor, now + 2 * year).await.unwrap().locked, 0);
+
+        assert_eq!(ledger.check(&tx(&investor, &bob, 600, 0, vec![])).await, Ok(()));
+        assert!(matches!(
+            ledger.check(&tx(&investor, &bob, 601, 0, vec![])).await,
+            Err(VestingError::Locked { locked: 500, spendable: 600, required: 601 })
+        ));
+
+        // ブロック内では使った額を合計し、超えた送金と同じ送信者の後続を除く
+        let mut txs = vec![
+            tx(&investor, &bob, 400, 0, vec![]),
+            tx(&investor, &bob, 300, 1, vec![]),
+            tx(&investor, &bob, 0, 2, vec![]),
+            tx(&treasury, &bob, 3000, 2, vec![]),
+        ];
+        ledger.retain_valid(&mut txs).await;
+        assert_eq!(txs.iter().map(|t| t.value).collect::<Vec<_>>(), vec![400, 3000]);
+
+        // 確定前の検証はブロックのタイムスタンプの時点で行う
+        let mut spend = Block::new(1, genesis.hash.clone(), "v".to_string(), vec![tx(&investor, &bob, 900, 0, vec![])]);
+        spend.timestamp = now;
+        assert!(ledger.check_block(&spend).await.is_err());
+        spend.timestamp = now + 2 * year;
+        assert!(ledger.check_block(&spend).await.is_ok());
+
+        // 反映済みのブロックは二度反映しない
+        assert_eq!(ledger.applied_height().await.unwrap(), Some(0));
+        ledger.apply_block(&genesis).await.unwrap();
+        assert_eq!(ledger.account(&investor).await.unwrap().unwrap().grants.len(), 1);
+
+        // 不正なスケジュールは受け付けない
+        let invalid = Schedule { start: now, cliff_secs: 2, duration_secs: 1 };
+        assert_eq!(ledger.check(&tx(&treasury, &bob, 10, 3, invalid.encode())).await, Err(VestingError::InvalidSchedule));
+        assert_eq!(ledger.check(&tx(&treasury, &bob, 0, 3, schedule.encode())).await, Err(VestingError::EmptyGrant));
+    }
+}
@@ -0,0 +1,140 @@
+//! アドレスの表記
+//!
+//! 内部ではアドレスを小文字の hex（`0x` なし）で扱い、利用者向けには
+//! ネットワークごとのプレフィックスを付けた bech32m（BIP-350）表記も受け付けます。
+//! bech32m はチェックサムを含むため、入力ミスや別ネットワークのアドレスを検出できます。
+//! hex 表記にはチェックサムがないため、長さ（20または32バイト）のみを検証します。
+
+use bech32::{Bech32m, Hrp};
+use bech32::primitives::decode::CheckedHrpstring;
+use thiserror::Error;
+
+/// 既定のアドレスのプレフィックス
+pub const DEFAULT_ADDRESS_PREFIX: &str = "rsm";
+
+/// アドレスのバイト数（アカウントは公開鍵の32バイト、コントラクトは20バイト）
+const ADDRESS_LENGTHS: [usize; 2] = [20, 32];
+
+/// アドレスの解析エラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("invalid address prefix {0:?}")]
+    InvalidPrefix(String),
+
+    #[error("address {address} belongs to network {found:?}, expected {expected:?}")]
+    WrongNetwork { address: String, expected: String, found: String },
+
+    #[error("invalid bech32 address {address}: {reason}")]
+    InvalidChecksum { address: String, reason: String },
+
+    #[error("address must be {expected:?} bytes, got {length}")]
+    InvalidLength { length: usize, expected: [usize; 2] },
+
+    #[error("{0:?} is neither a bech32 nor a hex address")]
+    Malformed(String),
+}
+
+/// ネットワークのアドレス表記
+#[derive(Debug, Clone)]
+pub struct AddressFormat {
+    hrp: Hrp,
+}
+
+impl Default for AddressFormat {
+    fn default() -> Self {
+        Self { hrp: Hrp::parse_unchecked(DEFAULT_ADDRESS_PREFIX) }
+    }
+}
+
+impl AddressFormat {
+    pub fn new(prefix: &str) -> Result<Self, AddressError> {
+        let hrp = Hrp::parse(prefix).map_err(|_| AddressError::InvalidPrefix(prefix.to_string()))?;
+        Ok(Self { hrp })
+    }
+
+    /// プレフィックス
+    pub fn prefix(&self) -> &str {
+        self.hrp.as_str()
+    }
+
+    /// hex のアドレスを bech32m に変換
+    pub fn encode(&self, address: &str) -> Result<String, AddressError> {
+        let bytes = parse_hex(address)?;
+        bech32::encode::<Bech32m>(self.hrp, &bytes)
+            .map_err(|_| AddressError::Malformed(address.to_string()))
+    }
+
+    /// bech32m のアドレスを検証して hex に変換
+    pub fn decode(&self, address: &str) -> Result<String, AddressError> {
+        let checked = CheckedHrpstring::new::<Bech32m>(address)
+            .map_err(|e| AddressError::InvalidChecksum { address: address.to_string(), reason: e.to_string() })?;
+        let found = checked.hrp().to_lowercase();
+        if found != self.hrp.to_lowercase() {
+            return Err(AddressError::WrongNetwork {
+                address: address.to_string(),
+                expected: self.prefix().to_string(),
+                found,
+            });
+        }
+        let bytes: Vec<u8> = checked.byte_iter().collect();
+        check_length(bytes.len())?;
+        Ok(hex::encode(bytes))
+    }
+
+    /// bech32m または hex のアドレスを検証し、内部表記（小文字の hex）に変換
+    pub fn parse(&self, address: &str) -> Result<String, AddressError> {
+        let address = address.trim();
+        let digits = address.strip_prefix("0x").unwrap_or(address);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return parse_hex(address).map(hex::encode);
+        }
+        if address.contains('1') {
+            return self.decode(address);
+        }
+        Err(AddressError::Malformed(address.to_string()))
+    }
+}
+
+fn parse_hex(address: &str) -> Result<Vec<u8>, AddressError> {
+    let bytes = hex::decode(address.trim().trim_start_matches("0x"))
+        .map_err(|_| AddressError::Malformed(address.to_string()))?;
+    check_length(bytes.len())?;
+    Ok(bytes)
+}
+
+fn check_length(length: usize) -> Result<(), AddressError> {
+    if ADDRESS_LENGTHS.contains(&length) {
+        Ok(())
+    } else {
+        Err(AddressError::InvalidLength { length, expected: ADDRESS_LENGTHS })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bech32_roundtrip_and_validation() {
+        let format = AddressFormat::default();
+        let hex_address = "0x".to_string() + &"ab".repeat(32);
+        let encoded = format.encode(&hex_address).unwrap();
+        assert!(encoded.starts_with("rsm1"));
+        assert_eq!(format.parse(&encoded).unwrap(), "ab".repeat(32));
+        assert_eq!(format.parse(&encoded.to_uppercase()).unwrap(), "ab".repeat(32));
+        assert_eq!(format.parse(&"AB".repeat(32)).unwrap(), "ab".repeat(32));
+
+        // 1文字の入力ミスはチェックサムで検出する
+        let mut typo: Vec<char> = encoded.chars().collect();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == 'q' { 'p' } else { 'q' };
+        let typo: String = typo.into_iter().collect();
+        assert!(matches!(format.parse(&typo), Err(AddressError::InvalidChecksum { .. })));
+
+        let testnet = AddressFormat::new("trsm").unwrap().encode(&hex_address).unwrap();
+        assert!(matches!(format.parse(&testnet), Err(AddressError::WrongNetwork { .. })));
+
+        assert!(matches!(format.parse("abcd"), Err(AddressError::InvalidLength { length: 2, .. })));
+        assert!(matches!(format.parse("alice"), Err(AddressError::Malformed(_))));
+    }
+}
@@ -0,0 +1,134 @@
+//! 階層的決定性（HD）ウォレット
+//!
+//! BIP-39 のニーモニックからシードを作り、SLIP-0010 で ed25519 の鍵を導出します。
+//! 1つのニーモニックから BIP-44 形式のパス（`m/44'/<coin>'/<account>'/<change>/<index>`）で
+//! 複数のアカウントを復元できます。
+//!
+//! ed25519 は強化導出（hardened）のみをサポートするため、パスの各階層は `'` の有無に関わらず
+//! 強化導出として扱います（`m/44'/7070'/0'/0/1` と `m/44'/7070'/0'/0'/1'` は同じ鍵になります）。
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+use bip39::Mnemonic;
+use ed25519_dalek::SigningKey;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+/// 既定のコインタイプ（SLIP-44 に未登録のため暫定値）
+pub const DEFAULT_COIN_TYPE: u32 = 7070;
+
+/// 強化導出のインデックスの開始値
+const HARDENED: u32 = 0x8000_0000;
+
+/// SLIP-0010 の ed25519 のマスター鍵のHMACキー
+const ED25519_SEED_KEY: &[u8] = b"ed25519 seed";
+
+/// 導出パス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// BIP-44 形式の既定のパス（`m/44'/<coin>'/<account>'/0'/<index>'`）
+    pub fn bip44(coin_type: u32, account: u32, index: u32) -> Self {
+        Self(vec![44, coin_type, account, 0, index])
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = anyhow::Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let mut segments = path.trim().split('/');
+        if segments.next() != Some("m") {
+            return Err(anyhow!("derivation path {:?} must start with \"m\"", path));
+        }
+        let indices = segments
+            .map(|segment| {
+                let index = segment.trim_end_matches(['\'', 'h', 'H']);
+                index.parse::<u32>()
+                    .ok()
+                    .filter(|i| *i < HARDENED)
+                    .ok_or_else(|| anyhow!("invalid index {:?} in derivation path {:?}", segment, path))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// 新しいニーモニックを生成（12・15・18・21・24語）
+pub fn generate_mnemonic(words: usize) -> Result<Mnemonic> {
+    Ok(Mnemonic::generate(words)?)
+}
+
+/// ニーモニックを検証して読み込む（単語とチェックサムを確認）
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic> {
+    Ok(Mnemonic::parse_normalized(&phrase.split_whitespace().collect::<Vec<_>>().join(" "))?)
+}
+
+/// ニーモニックとパスから鍵を導出
+pub fn derive_key(mnemonic: &Mnemonic, passphrase: &str, path: &DerivationPath) -> SigningKey {
+    derive_from_seed(&mnemonic.to_seed(passphrase), path)
+}
+
+/// シードとパスから鍵を導出（SLIP-0010）
+pub fn derive_from_seed(seed: &[u8], path: &DerivationPath) -> SigningKey {
+    let (mut key, mut chain_code) = split(hmac_sha512(ED25519_SEED_KEY, &[seed]));
+    for index in &path.0 {
+        let hardened = (index | HARDENED).to_be_bytes();
+        (key, chain_code) = split(hmac_sha512(&chain_code, &[&[0], &key, &hardened]));
+    }
+    SigningKey::from_bytes(&key)
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    let mut out = [0; 64];
+    out.copy_from_slice(&mac.finalize().into_bytes());
+    out
+}
+
+fn split(bytes: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut left = [0; 32];
+    let mut right = [0; 32];
+    left.copy_from_slice(&bytes[..32]);
+    right.copy_from_slice(&bytes[32..]);
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip10_vector() {
+        // SLIP-0010 の ed25519 テストベクター1
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = derive_from_seed(&seed, &"m".parse().unwrap());
+        assert_eq!(hex::encode(master.to_bytes()), "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7");
+        let child = derive_from_seed(&seed, &"m/0'".parse().unwrap());
+        assert_eq!(hex::encode(child.to_bytes()), "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3");
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let path: DerivationPath = "m/44'/7070'/0'/0/3".parse().unwrap();
+        assert_eq!(path, DerivationPath::bip44(DEFAULT_COIN_TYPE, 0, 3));
+        assert_eq!(path.to_string(), "m/44'/7070'/0'/0'/3'");
+        assert!("44'/0'".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! 鍵の保存
+//!
+//! 秘密鍵をディレクトリに `<address>.key`（hex）として保存します。
+//! ファイルは所有者のみが読み書きできる権限で作成しますが、暗号化はしないため、
+//! オフライン署名用のマシンなどディスクを保護できる環境で使ってください。
+//!
+//! 鍵を別の端末に置くアカウントは監視専用（`<address>.watch`、中身は空）として登録でき、
+//! 署名前のトランザクションを作成して鍵のある端末で署名します。
+
+use std::path::PathBuf;
+use anyhow::{Result, anyhow};
+use ed25519_dalek::SigningKey;
+use tokio::io::AsyncWriteExt;
+use super::address_of;
+
+/// 鍵ファイルの拡張子
+const KEY_EXTENSION: &str = "key";
+/// 監視専用のアカウントのファイルの拡張子
+const WATCH_EXTENSION: &str = "watch";
+
+/// ディレクトリに保存した鍵
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 新しい鍵を生成して保存し、そのアドレスを返す
+    pub async fn generate(&self) -> Result<String> {
+        self.import(&SigningKey::generate(&mut rand::rngs::OsRng)).await
+    }
+
+    /// 鍵を保存し、そのアドレスを返す（同じアドレスの鍵がある場合はエラー）
+    pub async fn import(&self, key: &SigningKey) -> Result<String> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let address = address_of(&key.verifying_key());
+        let path = self.path(&address);
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&path).await
+            .map_err(|e| anyhow!("Failed to create {}: {}", path.display(), e))?;
+        file.write_all(hex::encode(key.to_bytes()).as_bytes()).await?;
+        file.sync_all().await?;
+        Ok(address)
+    }
+
+    /// アドレスの鍵を読み込む
+    pub async fn load(&self, address: &str) -> Result<SigningKey> {
+        let address = address.trim_start_matches("0x").to_lowercase();
+        if hex::decode(&address).is_err() {
+            return Err(anyhow!("{} is not a hex address", address));
+        }
+        let path = self.path(&address);
+        if !path.exists() && self.watch_path(&address).exists() {
+            return Err(anyhow!("{} is watch-only; sign on the device that holds its key (`rustorium tx sign`)", address));
+        }
+        let encoded = tokio::fs::read_to_string(&path).await
+            .map_err(|e| anyhow!("No key for {} in {}: {}", address, self.dir.display(), e))?;
+        let bytes: [u8; 32] = hex::decode(encoded.trim())?
+            .try_into()
+            .map_err(|_| anyhow!("{} is not a 32-byte key", path.display()))?;
+        Ok(SigningKey::from_bytes(&bytes))
+    }
+
+    /// 保存している鍵のアドレス
+    pub async fn list(&self) -> Result<Vec<String>> {
+        self.list_extension(KEY_EXTENSION).await
+    }
+
+    /// アドレスを監視専用のアカウントとして登録（鍵を持つアカウントは登録しない）
+    pub async fn add_watch_only(&self, address: &str) -> Result<()> {
+        let address = address.trim_start_matches("0x").to_lowercase();
+        if hex::decode(&address).is_err() {
+            return Err(anyhow!("{} is not a hex address", address));
+        }
+        if self.path(&address).exists() {
+            return Err(anyhow!("The key of {} is already in {}", address, self.dir.display()));
+        }
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.watch_path(&address), b"").await?;
+        Ok(())
+    }
+
+    /// 監視専用のアカウントの登録を解除
+    pub async fn remove_watch_only(&self, address: &str) -> Result<()> {
+        let address = address.trim_start_matches("0x").to_lowercase();
+        tokio::fs::remove_file(self.watch_path(&address)).await
+            .map_err(|e| anyhow!("{} is not a watch-only account: {}", address, e))
+    }
+
+    /// 監視専用のアカウントのアドレス
+    pub async fn watch_only(&self) -> Result<Vec<String>> {
+        self.list_extension(WATCH_EXTENSION).await
+    }
+
+    async fn list_extension(&self, extension: &str) -> Result<Vec<String>> {
+        let mut addresses = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(addresses),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == extension) {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    addresses.push(stem.to_string());
+                }
+            }
+        }
+        addresses.sort();
+        Ok(addresses)
+    }
+
+    fn path(&self, address: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", address, KEY_EXTENSION))
+    }
+
+    fn watch_path(&self, address: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", address, WATCH_EXTENSION))
+    }
+}
@@ -0,0 +1,294 @@
+//! ウォレット
+//!
+//! ed25519 の鍵でトランザクションに署名します。アドレスは公開鍵の hex です。
+//! 署名の対象はトランザクションのハッシュ（`core::types` の正規化JSONハッシュ）の hex 文字列で、
+//! 署名自体はハッシュの対象に含まれないため、署名の有無でハッシュは変わりません。
+//! 署名済みトランザクションはハッシュの対象にチェーンIDを含める必要があり、
+//! 別のネットワーク（テストネットとメインネットなど）向けに署名されたものは拒否されます。
+//! 主な機能：
+//! - 鍵の生成と保存（`keystore`）
+//! - bech32m のアドレス表記（`address`）
+//! - ニーモニックからの鍵の導出（`hd`）
+//! - トランザクションの署名と検証
+//! - オフライン署名用の署名前・署名済みトランザクションのファイル形式
+//! - 鍵を持たない監視専用のアカウント（`keystore`）
+
+pub mod address;
+pub mod hd;
+pub mod keystore;
+
+use anyhow::Result;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use crate::core::mempool::PendingTransaction;
+
+pub use address::{AddressError, AddressFormat, DEFAULT_ADDRESS_PREFIX};
+pub use hd::{DerivationPath, DEFAULT_COIN_TYPE};
+pub use keystore::Keystore;
+
+/// 開発用ネットワークのチェーンID
+pub const DEFAULT_CHAIN_ID: u64 = 1337;
+
+/// トランザクションの署名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TxSignature {
+    /// 署名者の公開鍵（hex）
+    pub public_key: String,
+    /// ハッシュへの署名（hex）
+    pub signature: String,
+}
+
+/// 署名の検証エラー
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("transaction is not signed")]
+    Missing,
+
+    #[error("malformed public key or signature: {0}")]
+    Malformed(String),
+
+    #[error("public key {public_key} does not belong to sender {from}")]
+    WrongSigner { public_key: String, from: String },
+
+    #[error("signature does not match transaction {0}")]
+    Invalid(String),
+
+    #[error("signed transaction does not specify a chain id (this network is chain {0})")]
+    MissingChainId(u64),
+
+    #[error("transaction is signed for chain {found}, but this network is chain {expected}")]
+    WrongChain { expected: u64, found: u64 },
+
+    #[error("transaction body does not match its hash {expected} (computed {found}); the file was modified")]
+    HashMismatch { expected: String, found: String },
+}
+
+/// 公開鍵のアドレス
+pub fn address_of(key: &VerifyingKey) -> String {
+    hex::encode(key.as_bytes())
+}
+
+/// トランザクションに署名
+pub fn sign(key: &SigningKey, tx: &PendingTransaction) -> TxSignature {
+    TxSignature {
+        public_key: address_of(&key.verifying_key()),
+        signature: hex::encode(key.sign(tx.compute_hash().as_bytes()).to_bytes()),
+    }
+}
+
+/// トランザクションの署名を検証
+///
+/// チェーンIDがこのネットワークのものであり、署名者の公開鍵が送信者のアドレスと一致し、
+/// 現在の本体のハッシュへの署名であることを確認します。
+pub fn verify(tx: &PendingTransaction, chain_id: u64) -> Result<(), SignatureError> {
+    let signed = tx.signature.as_ref().ok_or(SignatureError::Missing)?;
+    match tx.chain_id {
+        None => return Err(SignatureError::MissingChainId(chain_id)),
+        Some(found) if found != chain_id => return Err(SignatureError::WrongChain { expected: chain_id, found }),
+        Some(_) => {}
+    }
+    let public_key: [u8; 32] = hex::decode(&signed.public_key)
+        .map_err(|e| SignatureError::Malformed(e.to_string()))?
+        .try_into()
+        .map_err(|_| SignatureError::Malformed("public key must be 32 bytes".to_string()))?;
+    let public_key = VerifyingKey::from_bytes(&public_key)
+        .map_err(|e| SignatureError::Malformed(e.to_string()))?;
+    if address_of(&public_key) != tx.from.trim_start_matches("0x").to_lowercase() {
+        return Err(SignatureError::WrongSigner { public_key: signed.public_key.clone(), from: tx.from.clone() });
+    }
+    let signature = hex::decode(&signed.signature)
+        .map_err(|e| SignatureError::Malformed(e.to_string()))
+        .and_then(|bytes| Signature::from_slice(&bytes).map_err(|e| SignatureError::Malformed(e.to_string())))?;
+    let hash = tx.compute_hash();
+    public_key.verify_strict(hash.as_bytes(), &signature)
+        .map_err(|_| SignatureError::Invalid(hash))
+}
+
+/// 署名済みトランザクション
+///
+/// `POST /api/transactions` の本文と同じ形式のため、オフラインで作成したファイルをそのまま送信できます。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    /// 送信後のハッシュ（確認用で、ノードは本体から計算し直す）
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    pub value: u64,
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    /// データ（hex）
+    #[serde(default)]
+    pub data: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub signature: TxSignature,
+}
+
+impl SignedTransaction {
+    /// トランザクションに署名して作成
+    pub fn new(key: &SigningKey, tx: &PendingTransaction) -> Self {
+        Self {
+            hash: tx.compute_hash(),
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            value: tx.value,
+            nonce: tx.nonce,
+            gas_price: tx.gas_price,
+            gas_limit: tx.gas_limit,
+            data: hex::encode(&tx.data),
+            valid_until: tx.valid_until,
+            chain_id: tx.chain_id,
+            signature: sign(key, tx),
+        }
+    }
+
+    /// メモリプールのトランザクションに戻す
+    pub fn to_pending(&self, received_at: u64) -> Result<PendingTransaction> {
+        let mut tx = PendingTransaction {
+            hash: String::new(),
+            from: self.from.clone(),
+            to: self.to.clone(),
+            value: self.value,
+            nonce: self.nonce,
+            gas_price: self.gas_price,
+            gas_limit: self.gas_limit,
+            data: hex::decode(self.data.trim_start_matches("0x"))?,
+            received_at,
+            valid_until: self.valid_until,
+            chain_id: self.chain_id,
+            blob: None,
+            signature: Some(self.signature.clone()),
+        };
+        tx.hash = tx.compute_hash();
+        Ok(tx)
+    }
+}
+
+/// 署名前のトランザクション
+///
+/// 監視専用のアカウントから作成し、鍵を持つ別の端末で署名するためのファイル形式です。
+/// 署名の対象は `hash` で、署名すると [`SignedTransaction`] になります。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UnsignedTransaction {
+    /// 署名の対象のハッシュ（署名する端末は本体から計算し直して一致を確認する）
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    pub value: u64,
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    /// データ（hex）
+    #[serde(default)]
+    pub data: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+    pub chain_id: u64,
+}
+
+impl UnsignedTransaction {
+    /// `chain_id` のネットワーク向けに作成
+    pub fn new(tx: &PendingTransaction, chain_id: u64) -> Self {
+        let tx = PendingTransaction { chain_id: Some(chain_id), signature: None, ..tx.clone() };
+        Self {
+            hash: tx.compute_hash(),
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            value: tx.value,
+            nonce: tx.nonce,
+            gas_price: tx.gas_price,
+            gas_limit: tx.gas_limit,
+            data: hex::encode(&tx.data),
+            valid_until: tx.valid_until,
+            chain_id,
+        }
+    }
+
+    /// 本体とハッシュの一致を確認してメモリプールのトランザクションに戻す
+    pub fn to_pending(&self) -> Result<PendingTransaction> {
+        let mut tx = PendingTransaction {
+            hash: String::new(),
+            from: self.from.clone(),
+            to: self.to.clone(),
+            value: self.value,
+            nonce: self.nonce,
+            gas_price: self.gas_price,
+            gas_limit: self.gas_limit,
+            data: hex::decode(self.data.trim_start_matches("0x"))?,
+            received_at: 0,
+            valid_until: self.valid_until,
+            chain_id: Some(self.chain_id),
+            blob: None,
+            signature: None,
+        };
+        tx.hash = tx.compute_hash();
+        if tx.hash != self.hash {
+            return Err(SignatureError::HashMismatch { expected: self.hash.clone(), found: tx.hash }.into());
+        }
+        Ok(tx)
+    }
+
+    /// 送信者の鍵で署名
+    pub fn sign(&self, key: &SigningKey) -> Result<SignedTransaction> {
+        let tx = self.to_pending()?;
+        let public_key = address_of(&key.verifying_key());
+        if public_key != tx.from.trim_start_matches("0x").to_lowercase() {
+            return Err(SignatureError::WrongSigner { public_key, from: tx.from }.into());
+        }
+        Ok(SignedTransaction::new(key, &tx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_transaction_roundtrip() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let from = address_of(&key.verifying_key());
+        let tx = PendingTransaction { gas_price: 2, chain_id: Some(DEFAULT_CHAIN_ID), ..PendingTransaction::test_transfer(&from, "bob", 5, 3).with_data(vec![1, 2]) }.rehashed();
+        let unsigned_hash = tx.hash.clone();
+
+        let signed = SignedTransaction::new(&key, &tx);
+        let json = serde_json::to_string(&signed).unwrap();
+        let restored: SignedTransaction = serde_json::from_str(&json).unwrap();
+        let pending = restored.to_pending(100).unwrap();
+        assert_eq!(pending.hash, unsigned_hash);
+        assert_eq!(verify(&pending, DEFAULT_CHAIN_ID), Ok(()));
+
+        let mut tampered = pending.clone();
+        tampered.value = 500;
+        assert!(matches!(verify(&tampered, DEFAULT_CHAIN_ID), Err(SignatureError::Invalid(_))));
+
+        let mut other_sender = pending.clone();
+        other_sender.from = "carol".to_string();
+        assert!(matches!(verify(&other_sender, DEFAULT_CHAIN_ID), Err(SignatureError::WrongSigner { .. })));
+
+        // 別のネットワーク向けの署名は再利用できない
+        assert_eq!(verify(&pending, 1), Err(SignatureError::WrongChain { expected: 1, found: DEFAULT_CHAIN_ID }));
+        let mut replayed = pending.clone();
+        replayed.chain_id = Some(1);
+        assert!(matches!(verify(&replayed, 1), Err(SignatureError::Invalid(_))));
+        let mut unbound = pending.clone();
+        unbound.chain_id = None;
+        assert_eq!(verify(&unbound, DEFAULT_CHAIN_ID), Err(SignatureError::MissingChainId(DEFAULT_CHAIN_ID)));
+
+        // 監視専用のアカウントで作成した署名前のトランザクションを別の端末で署名する
+        let unsigned = UnsignedTransaction::new(&PendingTransaction { signature: None, ..pending }, DEFAULT_CHAIN_ID);
+        assert_eq!(unsigned.hash, unsigned_hash);
+        let signed = unsigned.sign(&key).unwrap();
+        assert_eq!(verify(&signed.to_pending(0).unwrap(), DEFAULT_CHAIN_ID), Ok(()));
+        assert!(unsigned.sign(&SigningKey::from_bytes(&[8; 32])).is_err());
+        let modified = UnsignedTransaction { value: 500, ..unsigned };
+        assert!(matches!(
+            modified.sign(&key).unwrap_err().downcast_ref(),
+            Some(SignatureError::HashMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,408 @@
+//! アドレスのウォッチリスト
+//!
+//! クライアントが登録したアドレスの送受信や、そのアドレスのコントラクトが発行した
+//! イベントを検出し、WebSocket またはWebhookで通知します。
+//! 主な機能：
+//! - APIキーごとのウォッチの永続化
+//! - 確定したブロックとウォッチの照合
+//! - Webhookの再試行付き送信
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use thiserror::Error;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use crate::core::block::{Block, Chain};
+use crate::core::storage::StorageEngine;
+
+/// ウォッチのキープレフィックス（`watch/{APIキーのハッシュ}/{ID}`）
+const WATCH_PREFIX: &str = "watch/";
+/// 1つのウォッチに登録できるアドレス数
+const MAX_ADDRESSES_PER_WATCH: usize = 100;
+/// APIキーごとのウォッチ数
+const MAX_WATCHES_PER_KEY: usize = 20;
+/// 起動時に一度に読み込むウォッチの件数
+const LOAD_BATCH: usize = 1000;
+/// Webhookの送信回数
+const WEBHOOK_ATTEMPTS: u32 = 3;
+/// Webhookのタイムアウト
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// 通知チャネルの容量
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// ウォッチリストのエラー
+#[derive(Debug, Error)]
+pub enum WatchlistError {
+    #[error("at least one address is required")]
+    NoAddresses,
+    #[error("too many addresses (limit is {0})")]
+    TooManyAddresses(usize),
+    #[error("too many watches for this API key (limit is {0})")]
+    TooManyWatches(usize),
+    #[error("invalid webhook URL: {0}")]
+    InvalidWebhook(String),
+    #[error("watch not found: {0}")]
+    NotFound(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// ウォッチの登録内容
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WatchRequest {
+    pub addresses: Vec<String>,
+    /// 通知先のURL（省略時はWebSocketのみ）
+    pub webhook_url: Option<String>,
+}
+
+/// ウォッチ
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Watch {
+    pub id: String,
+    pub addresses: Vec<String>,
+    pub webhook_url: Option<String>,
+    pub created_at: u64,
+}
+
+/// 通知の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchEventKind {
+    /// 監視中のアドレスが送信
+    Sent,
+    /// 監視中のアドレスが受信
+    Received,
+    /// 監視中のアドレスのコントラクトがイベントを発行
+    ContractEvent,
+}
+
+/// 通知
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WatchNotification {
+    pub watch_id: String,
+    pub address: String,
+    pub kind: WatchEventKind,
+    pub tx_hash: String,
+    pub block_height: u64,
+    pub block_hash: String,
+    /// 送受信の相手（イベントの場合は `None`）
+    pub counterparty: Option<String>,
+    pub value: u64,
+    /// イベントのトピック
+    pub topics: Vec<String>,
+}
+
+/// ウォッチリスト
+pub struct Watchlist {
+    storage: Arc<dyn StorageEngine>,
+    /// APIキーのハッシュ → ウォッチ
+    watches: RwLock<HashMap<String, HashMap<String, Watch>>>,
+    notifications: broadcast::Sender<(String, WatchNotification)>,
+    client: reqwest::Client,
+}
+
+impl Watchlist {
+    pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
+        let (notifications, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            storage,
+            watches: RwLock::new(HashMap::new()),
+            notifications,
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 保存済みのウォッチを読み込む
+    pub async fn load(&self) -> Result<usize> {
+        let mut watches = self.watches.write().await;
+        let mut start = WATCH_PREFIX.as_bytes().to_vec();
+        let mut count = 0;
+        loop {
+            let entries = self.storage.scan(&start, LOAD_BATCH).await?;
+            let mut last = None;
+            for (key, value) in entries.iter().take_while(|(k, _)| k.starts_with(WATCH_PREFIX.as_bytes())) {
+                let key_str = String::from_utf8_lossy(&key[WATCH_PREFIX.len()..]).to_string();
+                if let Some((owner, _)) = key_str.split_once('/') {
+                    let watch: Watch = serde_json::from_slice(value)?;
+                    watches.entry(owner.to_string()).or_default().insert(watch.id.clone(), watch);
+                    count += 1;
+                }
+                last = Some(key.clone());
+            }
+            match last {
+                Some(mut key) if entries.len() == LOAD_BATCH => {
+                    // 最後のキーの直後から続ける
+                    key.push(0);
+                    start = key;
+                }
+                _ => break,
+            }
+        }
+        info!("Loaded {} watches", count);
+        Ok(count)
+    }
+
+    /// ウォッチを登録
+    pub async fn register(&self, api_key: &str, request: WatchRequest) -> Result<Watch, WatchlistError> {
+        let addresses: Vec<String> = request.addresses.iter()
+            .map(|a| normalize_address(a))
+            .filter(|a| !a.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if addresses.is_empty() {
+            return Err(WatchlistError::NoAddresses);
+        }
+        if addresses.len() > MAX_ADDRESSES_PER_WATCH {
+            return Err(WatchlistError::TooManyAddresses(MAX_ADDRESSES_PER_WATCH));
+        }
+        if let Some(url) = &request.webhook_url {
+            let parsed = reqwest::Url::parse(url).map_err(|e| WatchlistError::InvalidWebhook(e.to_string()))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(WatchlistError::InvalidWebhook(format!("unsupported scheme {}", parsed.scheme())));
+            }
+        }
+
+        let owner = hash_key(api_key);
+        let mut watches = self.watches.write().await;
+        let owned = watches.entry(owner.clone()).or_default();
+        if owned.len() >= MAX_WATCHES_PER_KEY {
+            return Err(WatchlistError::TooManyWatches(MAX_WATCHES_PER_KEY));
+        }
+
+        let created_at = now();
+        let watch = Watch {
+            id: watch_id(&owner, &addresses, created_at, owned.len()),
+            addresses,
+            webhook_url: request.webhook_url,
+            created_at,
+        };
+        self.storage.put(&watch_key(&owner, &watch.id), &serde_json::to_vec(&watch).map_err(anyhow::Error::from)?).await?;
+        owned.insert(watch.id.clone(), watch.clone());
+        Ok(watch)
+    }
+
+    /// APIキーのウォッチ一覧
+    pub async fn list(&self, api_key: &str) -> Vec<Watch> {
+        let watches = self.watches.read().await;
+        let mut list: Vec<Watch> = watches.get(&hash_key(api_key))
+            .map(|owned| owned.values().cloned().collect())
+            .unwrap_or_default();
+        list.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        list
+    }
+
+    /// ウォッチを削除
+    pub async fn remove(&self, api_key: &str, id: &str) -> Result<(), WatchlistError> {
+        let owner = hash_key(api_key);
+        let mut watches = self.watches.write().await;
+        watches.get_mut(&owner)
+            .and_then(|owned| owned.remove(id))
+            .ok_or_else(|| WatchlistError::NotFound(id.to_string()))?;
+        self.storage.delete(&watch_key(&owner, id)).await?;
+        Ok(())
+    }
+
+    /// APIキー宛ての通知を購読
+    pub fn subscribe(&self, api_key: &str) -> WatchSubscription {
+        WatchSubscription {
+            owner: hash_key(api_key),
+            receiver: self.notifications.subscribe(),
+        }
+    }
+
+    /// ブロックとウォッチを照合
+    pub async fn match_block(&self, block: &Block) -> Vec<(String, Watch, WatchNotification)> {
+        let watches = self.watches.read().await;
+        let mut matches = Vec::new();
+        let notification = |watch: &Watch, address: &str, kind, tx_hash: &str, counterparty: Option<String>, value, topics| WatchNotification {
+            watch_id: watch.id.clone(),
+            address: address.to_string(),
+            kind,
+            tx_hash: tx_hash.to_string(),
+            block_height: block.height,
+            block_hash: block.hash.clone(),
+            counterparty,
+            value,
+            topics,
+        };
+
+        for (owner, owned) in watches.iter() {
+            for watch in owned.values() {
+                for tx in &block.transactions {
+                    let from = normalize_address(&tx.from);
+                    let to = normalize_address(&tx.to);
+                    if watch.addresses.contains(&from) {
+                        matches.push((owner.clone(), watch.clone(),
+                            notification(watch, &from, WatchEventKind::Sent, &tx.hash, Some(to.clone()), tx.value, vec![])));
+                    }
+                    if watch.addresses.contains(&to) && to != from {
+                        matches.push((owner.clone(), watch.clone(),
+                            notification(watch, &to, WatchEventKind::Received, &tx.hash, Some(from), tx.value, vec![])));
+                    }
+                }
+                for event in &block.events {
+                    let address = normalize_address(&event.address);
+                    if watch.addresses.contains(&address) {
+                        matches.push((owner.clone(), watch.clone(),
+                            notification(watch, &address, WatchEventKind::ContractEvent, &event.tx_hash, None, 0, event.topics.clone())));
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    /// ブロックの確定を購読して通知を送り続ける
+    pub fn spawn(self: Arc<Self>, chain: Arc<Chain>) -> tokio::task::JoinHandle<()> {
+        let mut commits = chain.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let block = match commits.recv().await {
+                    Ok(block) => block,
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Watchlist skipped {} blocks", n);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                for (owner, watch, notification) in self.match_block(&block).await {
+                    // WebSocketの購読者がいない場合の送信エラーは無視する
+                    let _ = self.notifications.send((owner, notification.clone()));
+                    if let Some(url) = watch.webhook_url {
+                        let client = self.client.clone();
+                        tokio::spawn(async move { deliver_webhook(&client, &url, &notification).await });
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// APIキー宛ての通知の購読
+pub struct WatchSubscription {
+    owner: String,
+    receiver: broadcast::Receiver<(String, WatchNotification)>,
+}
+
+impl WatchSubscription {
+    /// 次の通知を待つ（購読が終了した場合は `None`）
+    pub async fn recv(&mut self) -> Option<WatchNotification> {
+        loop {
+            match self.receiver.recv().await {
+                Ok((owner, notification)) if owner == self.owner => return Some(notification),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("Watch subscriber lagged behind by {} notifications", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Webhookを送信（失敗時は間隔を空けて再試行）
+async fn deliver_webhook(client: &reqwest::Client, url: &str, notification: &WatchNotification) {
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let result = client
+            .post(url)
+            .header("X-Rustorium-Watch", notification.watch_id.as_str())
+            .json(notification)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        match result {
+            Ok(_) => return,
+            Err(e) if attempt < WEBHOOK_ATTEMPTS => {
+                warn!("Webhook {} failed (attempt {}): {}", url, attempt, e);
+                tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+            }
+            Err(e) => warn!("Giving up on webhook {}: {}", url, e),
+        }
+    }
+}
+
+/// APIキーは保存せず、ハッシュで識別する
+fn hash_key(api_key: &str) -> String {
+    hex::encode(Sha256::digest(api_key.as_bytes()))
+}
+
+fn watch_key(owner: &str, id: &str) -> Vec<u8> {
+    format!("{}{}/{}", WATCH_PREFIX, owner, id).into_bytes()
+}
+
+fn watch_id(owner: &str, addresses: &[String], created_at: u64, seq: usize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(owner.as_bytes());
+    for address in addresses {
+        hasher.update(address.as_bytes());
+    }
+    hasher.update(created_at.to_be_bytes());
+    hasher.update(seq.to_be_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
+
+fn normalize_address(address: &str) -> String {
+    address.trim().trim_start_matches("0x").to_lowercase()
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::block::Event;
+    use crate::core::mempool::PendingTransaction;
+    use crate::core::storage::redb_storage::RedbStorage;
+
+    #[tokio::test]
+    async fn test_watches_persist_and_match_blocks() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+
+        let watchlist = Watchlist::new(storage.clone());
+        let watch = watchlist.register("key-a", WatchRequest {
+            addresses: vec!["0xAAAA".to_string(), "cccc".to_string()],
+            webhook_url: None,
+        }).await.unwrap();
+        assert!(matches!(
+            watchlist.register("key-a", WatchRequest { addresses: vec![], webhook_url: None }).await,
+            Err(WatchlistError::NoAddresses)
+        ));
+        assert!(watchlist.list("key-b").await.is_empty());
+
+        // 再起動後も読み込める
+        let reloaded = Watchlist::new(storage);
+        assert_eq!(reloaded.load().await.unwrap(), 1);
+        assert_eq!(reloaded.list("key-a").await[0].id, watch.id);
+
+        let tx = PendingTransaction::test_transfer("bbbb", "0xaaaa", 5, 0);
+        let mut block = Block::new(0, String::new(), "v".to_string(), vec![tx.clone()]);
+        block.events.push(Event {
+            tx_hash: tx.hash,
+            index: 0,
+            address: "CCCC".to_string(),
+            topics: vec!["ddf252ad".to_string()],
+            data: vec![],
+        });
+
+        let matches = reloaded.match_block(&block).await;
+        let kinds: Vec<_> = matches.iter().map(|(_, _, n)| (n.address.as_str(), n.kind)).collect();
+        assert_eq!(kinds, vec![("aaaa", WatchEventKind::Received), ("cccc", WatchEventKind::ContractEvent)]);
+
+        reloaded.remove("key-a", &watch.id).await.unwrap();
+        assert!(reloaded.match_block(&block).await.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+//! メッセージファイルの読み込み
+//!
+//! Fluent形式のサブセットを扱います。
+//! - `key = value` の1行のメッセージ
+//! - インデントした行は直前のメッセージの続き（改行で連結）
+//! - `#` で始まる行はコメント
+//! - 値の中の `{ $name }` は表示時に引数で置き換える
+
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("line {line}: expected `key = value`")]
+    MissingSeparator { line: usize },
+    #[error("line {line}: invalid message key '{key}'")]
+    InvalidKey { line: usize, key: String },
+    #[error("line {line}: indented line does not continue a message")]
+    DanglingContinuation { line: usize },
+}
+
+/// メッセージファイルを解析
+pub fn parse(source: &str) -> Result<HashMap<String, String>, ParseError> {
+    let mut messages = HashMap::new();
+    let mut current: Option<(String, String)> = None;
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        if raw.trim().is_empty() {
+            continue;
+        }
+        if raw.starts_with(char::is_whitespace) {
+            let (_, value) = current.as_mut().ok_or(ParseError::DanglingContinuation { line })?;
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(raw.trim());
+            continue;
+        }
+        if raw.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = raw.split_once('=').ok_or(ParseError::MissingSeparator { line })?;
+        let key = key.trim();
+        if !is_valid_key(key) {
+            return Err(ParseError::InvalidKey { line, key: key.to_string() });
+        }
+        if let Some((key, value)) = current.take() {
+            messages.insert(key, value);
+        }
+        current = Some((key.to_string(), value.trim().to_string()));
+    }
+    if let Some((key, value)) = current {
+        messages.insert(key, value);
+    }
+    Ok(messages)
+}
+
+/// 英字で始まり、英数字・`_`・`-` のみを含むキー
+fn is_valid_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// `{ $name }` を引数の値で置き換える（引数にない変数はそのまま残す）
+pub fn format(pattern: &str, args: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + end + 1];
+        let name = placeholder[1..placeholder.len() - 1].trim().strip_prefix('$');
+        match name.and_then(|name| args.iter().find(|(arg, _)| *arg == name)) {
+            Some((_, value)) => output.push_str(value),
+            None => output.push_str(placeholder),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let messages = parse("# comment\nwelcome = Hello, { $name }!\nhelp = First line\n    second line\n\nexit = Exit\n").unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages["help"], "First line\nsecond line");
+        assert_eq!(format(&messages["welcome"], &[("name", "Alice")]), "Hello, Alice!");
+        assert_eq!(format(&messages["welcome"], &[]), "Hello, { $name }!");
+
+        assert_eq!(parse("no separator"), Err(ParseError::MissingSeparator { line: 1 }));
+        assert_eq!(parse("  orphan"), Err(ParseError::DanglingContinuation { line: 1 }));
+        assert!(matches!(parse("1key = x"), Err(ParseError::InvalidKey { line: 1, .. })));
+    }
+}
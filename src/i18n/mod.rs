@@ -1,72 +1,245 @@
-use std::collections::HashMap;
+//! 表示言語（i18n）
+//!
+//! CLIとWeb UIのメッセージを言語ごとのメッセージファイル（`locales/<言語>.ftl`）で管理します。
+//! 主な機能：
+//! - バイナリに組み込んだ翻訳と、設定のディレクトリのメッセージファイルの読み込み
+//! - 言語ごとのフォールバック（`pt-BR` → `pt` → 設定の `fallback` → `en`）
+//! - 実行中の表示言語の切り替え
 
-#[derive(Debug, Clone)]
+mod messages;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use anyhow::{Context, bail};
+use tracing::{info, warn};
+use crate::config::I18nSettings;
+
+pub use messages::{format, parse, ParseError};
+
+/// 最後のフォールバックに使う言語
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// バイナリに組み込んだ翻訳
+const EMBEDDED: [(&str, &str); 4] = [
+    ("en", include_str!("../../locales/en.ftl")),
+    ("ja", include_str!("../../locales/ja.ftl")),
+    ("zh", include_str!("../../locales/zh.ftl")),
+    ("ko", include_str!("../../locales/ko.ftl")),
+];
+
+/// 言語ごとのメッセージ
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    locales: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// 組み込みの翻訳
+    pub fn embedded() -> Self {
+        let mut catalog = Self::default();
+        for (language, source) in EMBEDDED {
+            let messages = parse(source)
+                .unwrap_or_else(|e| panic!("embedded locale {} is invalid: {}", language, e));
+            catalog.insert(language, messages);
+        }
+        catalog
+    }
+
+    /// 組み込みの翻訳に `dir` の `<言語>.ftl` を重ねて読み込む（`dir` がない場合は組み込みのみ）
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let mut catalog = Self::embedded();
+        if !dir.is_dir() {
+            return Ok(catalog);
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("ftl") {
+                continue;
+            }
+            let Some(language) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let messages = parse(&source).with_context(|| format!("Invalid message file {}", path.display()))?;
+            info!("Loaded {} messages for locale {} from {}", messages.len(), normalize(language), path.display());
+            catalog.insert(language, messages);
+        }
+        Ok(catalog)
+    }
+
+    /// メッセージを追加（同じキーは上書き）
+    pub fn insert(&mut self, language: &str, messages: HashMap<String, String>) {
+        self.locales.entry(normalize(language)).or_default().extend(messages);
+    }
+
+    /// 利用できる言語
+    pub fn languages(&self) -> Vec<String> {
+        self.locales.keys().cloned().collect()
+    }
+
+    /// `language` またはその基本の言語（`pt-BR` の場合は `pt`）の翻訳があるか
+    pub fn supports(&self, language: &str) -> bool {
+        let language = normalize(language);
+        self.locales.contains_key(&language) || self.locales.contains_key(base(&language))
+    }
+
+    /// メッセージを探す言語の順序
+    pub fn fallback_chain(&self, language: &str, fallback: &str) -> Vec<String> {
+        let language = normalize(language);
+        let fallback = normalize(fallback);
+        let candidates = [
+            language.clone(),
+            base(&language).to_string(),
+            fallback.clone(),
+            base(&fallback).to_string(),
+            DEFAULT_LANGUAGE.to_string(),
+        ];
+        let mut chain: Vec<String> = Vec::new();
+        for candidate in candidates {
+            if self.locales.contains_key(&candidate) && !chain.contains(&candidate) {
+                chain.push(candidate);
+            }
+        }
+        chain
+    }
+
+    /// フォールバックの順にメッセージを探す
+    pub fn lookup(&self, chain: &[String], key: &str) -> Option<&str> {
+        chain.iter()
+            .filter_map(|language| self.locales.get(language)?.get(key))
+            .map(String::as_str)
+            .next()
+    }
+
+    /// フォールバックを適用した、`language` のすべてのメッセージ
+    pub fn resolve(&self, language: &str, fallback: &str) -> HashMap<String, String> {
+        let mut resolved = HashMap::new();
+        for language in self.fallback_chain(language, fallback).iter().rev() {
+            resolved.extend(self.locales[language].clone());
+        }
+        resolved
+    }
+}
+
+/// 言語タグの表記をそろえる（`pt_br.UTF-8` → `pt-BR`）
+fn normalize(tag: &str) -> String {
+    let tag = tag.split('.').next().unwrap_or_default().replace('_', "-");
+    let mut parts = tag.split('-').filter(|p| !p.is_empty());
+    let mut normalized = parts.next().unwrap_or_default().to_lowercase();
+    for part in parts {
+        normalized.push('-');
+        if part.len() == 2 {
+            normalized.push_str(&part.to_uppercase());
+        } else {
+            normalized.push_str(part);
+        }
+    }
+    normalized
+}
+
+/// 言語タグの基本の言語
+fn base(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or(tag)
+}
+
+/// 表示言語とメッセージ（実行中に言語を切り替えられる）
+#[derive(Debug)]
 pub struct LocaleConfig {
-    pub language: String,
-    messages: HashMap<String, String>,
+    catalog: Arc<Catalog>,
+    fallback: String,
+    language: RwLock<String>,
 }
 
 impl LocaleConfig {
+    /// 組み込みの翻訳で作成
     pub fn new(language: &str) -> Self {
-        let messages = match language {
-            "ja" => {
-                let mut m = HashMap::new();
-                m.insert("welcome".to_string(), "Rustoriumへようこそ！".to_string());
-                m.insert("select_action".to_string(), "実行したいアクションを選択してください：".to_string());
-                m.insert("account".to_string(), "アカウント管理".to_string());
-                m.insert("transaction".to_string(), "トランザクション".to_string());
-                m.insert("smart_contract".to_string(), "スマートコントラクト".to_string());
-                m.insert("blockchain".to_string(), "ブロックチェーン情報".to_string());
-                m.insert("settings".to_string(), "設定".to_string());
-                m.insert("exit".to_string(), "終了".to_string());
-                m
-            },
-            "en" => {
-                let mut m = HashMap::new();
-                m.insert("welcome".to_string(), "Welcome to Rustorium!".to_string());
-                m.insert("select_action".to_string(), "Select an action to perform:".to_string());
-                m.insert("account".to_string(), "Account Management".to_string());
-                m.insert("transaction".to_string(), "Transactions".to_string());
-                m.insert("smart_contract".to_string(), "Smart Contracts".to_string());
-                m.insert("blockchain".to_string(), "Blockchain Info".to_string());
-                m.insert("settings".to_string(), "Settings".to_string());
-                m.insert("exit".to_string(), "Exit".to_string());
-                m
-            },
-            "zh" => {
-                let mut m = HashMap::new();
-                m.insert("welcome".to_string(), "欢迎使用 Rustorium！".to_string());
-                m.insert("select_action".to_string(), "请选择要执行的操作：".to_string());
-                m.insert("account".to_string(), "账户管理".to_string());
-                m.insert("transaction".to_string(), "交易".to_string());
-                m.insert("smart_contract".to_string(), "智能合约".to_string());
-                m.insert("blockchain".to_string(), "区块链信息".to_string());
-                m.insert("settings".to_string(), "设置".to_string());
-                m.insert("exit".to_string(), "退出".to_string());
-                m
-            },
-            "ko" => {
-                let mut m = HashMap::new();
-                m.insert("welcome".to_string(), "Rustorium에 오신 것을 환영합니다!".to_string());
-                m.insert("select_action".to_string(), "실행할 작업을 선택하세요:".to_string());
-                m.insert("account".to_string(), "계정 관리".to_string());
-                m.insert("transaction".to_string(), "트랜잭션".to_string());
-                m.insert("smart_contract".to_string(), "스마트 컨트랙트".to_string());
-                m.insert("blockchain".to_string(), "블록체인 정보".to_string());
-                m.insert("settings".to_string(), "설정".to_string());
-                m.insert("exit".to_string(), "종료".to_string());
-                m
-            },
-            _ => HashMap::new(),
-        };
+        Self::with_catalog(Catalog::embedded(), language, DEFAULT_LANGUAGE)
+    }
 
+    pub fn with_catalog(catalog: Catalog, language: &str, fallback: &str) -> Self {
         Self {
-            language: language.to_string(),
-            messages,
+            catalog: Arc::new(catalog),
+            fallback: normalize(fallback),
+            language: RwLock::new(normalize(language)),
         }
     }
 
-    pub fn get_message<'a>(&'a self, key: &'a str) -> &'a str {
-        self.messages.get(key).map(|s| s.as_str()).unwrap_or(key)
+    /// 設定から作成（メッセージファイルを読み込めない場合は組み込みの翻訳を使う）
+    pub fn from_settings(settings: &I18nSettings) -> Self {
+        let catalog = Catalog::load(&settings.dir).unwrap_or_else(|e| {
+            warn!("Failed to load message files from {}: {:#}", settings.dir.display(), e);
+            Catalog::embedded()
+        });
+        if !catalog.supports(&settings.language) {
+            warn!("No translation for language '{}', falling back to '{}'", settings.language, settings.fallback);
+        }
+        Self::with_catalog(catalog, &settings.language, &settings.fallback)
     }
-}
\ No newline at end of file
+
+    /// 現在の表示言語
+    pub fn language(&self) -> String {
+        self.language.read().unwrap().clone()
+    }
+
+    /// 表示言語を切り替える
+    pub fn set_language(&self, language: &str) -> anyhow::Result<()> {
+        if !self.supports(language) {
+            bail!("No translation for language '{}' (available: {})", language, self.languages().join(", "));
+        }
+        *self.language.write().unwrap() = normalize(language);
+        Ok(())
+    }
+
+    /// 利用できる言語
+    pub fn languages(&self) -> Vec<String> {
+        self.catalog.languages()
+    }
+
+    /// `language` の翻訳があるか
+    pub fn supports(&self, language: &str) -> bool {
+        self.catalog.supports(language)
+    }
+
+    /// メッセージ（どの言語にもない場合はキー）
+    pub fn get_message(&self, key: &str) -> String {
+        let chain = self.catalog.fallback_chain(&self.language(), &self.fallback);
+        self.catalog.lookup(&chain, key).unwrap_or(key).to_string()
+    }
+
+    /// 引数を埋め込んだメッセージ
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        format(&self.get_message(key), args)
+    }
+
+    /// フォールバックを適用した `language`（省略時は現在の表示言語）のすべてのメッセージ
+    pub fn messages(&self, language: Option<&str>) -> HashMap<String, String> {
+        let language = language.map_or_else(|| self.language(), str::to_string);
+        self.catalog.resolve(&language, &self.fallback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_and_switching() {
+        let mut catalog = Catalog::embedded();
+        catalog.insert("pt", HashMap::from([("exit".to_string(), "Sair".to_string())]));
+        catalog.insert("pt_br", HashMap::from([("settings".to_string(), "Configurações".to_string())]));
+        assert_eq!(catalog.fallback_chain("pt-br.UTF-8", "ja"), ["pt-BR", "pt", "ja", "en"]);
+
+        let locale = LocaleConfig::with_catalog(catalog, "pt-BR", "en");
+        assert_eq!(locale.get_message("settings"), "Configurações");
+        assert_eq!(locale.get_message("exit"), "Sair");
+        assert_eq!(locale.get_message("welcome"), "Welcome to Rustorium!");
+        assert_eq!(locale.get_message("no_such_key"), "no_such_key");
+
+        locale.set_language("ja").unwrap();
+        assert_eq!(locale.get_message("exit"), "終了");
+        assert!(locale.set_language("xx").is_err());
+        assert_eq!(locale.language(), "ja");
+    }
+}
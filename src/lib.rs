@@ -1,4 +1,5 @@
 pub mod api;
+pub mod bench;
 pub mod blockchain;
 pub mod mempool;
 pub mod storage;
@@ -1,22 +1,56 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use rustorium::{
-    cli::console::InteractiveConsole,
-    config::NodeConfig,
+    bench,
+    cli::{console::InteractiveConsole, setup},
+    config::{ChaosSettings, LinkChaosSettings, NodeConfig, PartitionSettings},
     services::ServiceManager,
+    web::{api, error_code::{ApiError, ErrorCode}},
+    util::{daemon, debug_report::{self, ReportOptions}, endpoints::ServiceManifest, log_filter::LogFilter, log_rotation::{Rotation, RotationConfig, RotatingFile}},
     core::{
-        storage::redb_storage::{RedbStorage, StorageConfig},
-        network::quic::{QuicNetwork, NetworkConfig},
-        ai::AiOptimizer,
+        block::Chain,
+        cache::{MaterializedViews, reindex::Reindexer},
+        storage::{
+            backup::{BackupConfig, BackupKind, BackupManager},
+            migration::{MigrationReport, Migrator, migrations},
+            redb_storage::{RedbStorage, StorageConfig, DB_FILE},
+            StorageEngine,
+        },
+        network::{chaos::{self, ChaosConfig}, diversity::DiversityPolicy, quic::{QuicNetwork, NetworkConfig}, roles::NodeRole, seeds::PeeringConfig, sentry::SentryConfig},
+        ai::{AiConfig, AiOptimizer},
+        consensus::{performance::PerformanceReport, registry::{self, Registration, ValidatorCandidate}},
+        fees::FeeSuggestion,
+        genesis,
+        memo::Memo,
+        mempool::PendingTransaction,
+        htlc::{self, HtlcOp, HTLC_ADDRESS},
+        names::{self, NameOp, NAME_REGISTRY_ADDRESS},
+        wallet::{
+            self, hd, AddressFormat, DerivationPath, Keystore, SignedTransaction, UnsignedTransaction,
+            DEFAULT_ADDRESS_PREFIX, DEFAULT_COIN_TYPE,
+        },
     },
 };
 
+#[cfg(feature = "das")]
+use rustorium::core::network::das;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{info, warn, error, Level};
-use tracing_subscriber::fmt;
+use tracing::{info, warn, error};
+use tracing_subscriber::{fmt::{self, writer::BoxMakeWriter}, layer::SubscriberExt};
 use console::style;
 
+// ヒーププロファイル（`/api/admin/debug/pprof/heap`）のため jemalloc で確保し、
+// 512KiBごとにサンプリングする
+#[cfg(all(feature = "profiling", target_os = "linux"))]
+#[global_allocator]
+static ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "profiling", target_os = "linux"))]
+#[allow(non_upper_case_globals)]
+#[export_name = "malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:19\0";
+
 #[derive(Parser)]
 #[clap(name = "rustorium", about = "Next-generation blockchain platform")]
 struct Opts {
@@ -32,15 +66,91 @@ struct Opts {
     #[clap(long, default_value = "9070")]
     port: u16,
 
+    /// ノードの役割（省略時は設定ファイルの値）
+    #[clap(long, value_parser = ["auto", "validator", "full", "light", "rpc-replica"])]
+    role: Option<String>,
+
+    /// シャドーバリデーターとして実行（ブロックの作成と投票を送信せず、ログと計測のみ）
+    #[clap(long)]
+    shadow: bool,
+
+    /// 表示言語（省略時は設定ファイルの値、例: `ja`）
+    #[clap(long)]
+    lang: Option<String>,
+
+    /// 空のチェーンをこのブロックから始める（`<高さ>:<ハッシュ>`、rpc-replica のみ）
+    #[clap(long, value_name = "HEIGHT:HASH")]
+    trust_checkpoint: Option<String>,
+
     /// 開発モード
     #[clap(long)]
     dev: bool,
 
+    /// 記録したスナップショット（`system snapshot`）からAPIのみを提供（コンセンサスとネットワークなし）
+    #[clap(long, conflicts_with_all = ["dev", "role", "shadow"])]
+    fixture: Option<std::path::PathBuf>,
+
+    /// 開発モードの設定とメモリ上のストレージで実行（終了するとデータは消える、`demo` フィーチャーが必要）
+    #[clap(long, conflicts_with_all = ["fixture", "role", "shadow"])]
+    demo: bool,
+
+    /// 開発モードで全リンクの送信に加える遅延（ミリ秒）
+    #[clap(long, requires = "dev")]
+    chaos_latency: Option<u64>,
+
+    /// 開発モードで遅延に加えるランダムな揺らぎの最大値（ミリ秒）
+    #[clap(long, requires = "dev")]
+    chaos_jitter: Option<u64>,
+
+    /// 開発モードでメッセージを捨てる割合（0.0〜1.0）
+    #[clap(long, requires = "dev")]
+    chaos_loss: Option<f64>,
+
+    /// 開発モードのリンクごとの帯域の上限（kbps）
+    #[clap(long, requires = "dev")]
+    chaos_bandwidth: Option<u64>,
+
+    /// ピアごとの障害（`PEER=latency:100,jitter:20,loss:0.05,bandwidth:1000`、複数指定可）
+    #[clap(long, requires = "dev", value_parser = chaos::parse_link)]
+    chaos_link: Vec<LinkChaosSettings>,
+
+    /// ネットワーク分断の予定（`START+DURATION=PEER,PEER`、起動からの秒数、複数指定可）
+    #[clap(long, requires = "dev", value_parser = chaos::parse_partition)]
+    chaos_partition: Vec<PartitionSettings>,
+
     /// インタラクティブモードを無効化
     #[clap(long)]
     no_interactive: bool,
 
-    /// ログレベル
+    /// 端末から切り離してバックグラウンドで実行（インタラクティブモードは無効）
+    #[clap(long)]
+    daemon: bool,
+
+    /// PIDファイルのパス（省略時はデータディレクトリの `rustorium.pid`、`--daemon` なしでも指定すれば作成）
+    #[clap(long)]
+    pid_file: Option<std::path::PathBuf>,
+
+    /// ログの出力先ディレクトリ（省略時は標準出力、`--daemon` ではデータディレクトリの `logs`）
+    #[clap(long)]
+    log_dir: Option<std::path::PathBuf>,
+
+    /// ログファイルを切り替える間隔
+    #[clap(long, value_enum, default_value = "daily")]
+    log_rotation: Rotation,
+
+    /// ログファイルを切り替えるサイズ（MB、0 は無制限）
+    #[clap(long, default_value = "100")]
+    log_max_size_mb: u64,
+
+    /// 残す切り替え済みのログファイル数（0 は無制限）
+    #[clap(long, default_value = "14")]
+    log_max_files: usize,
+
+    /// 切り替えたログファイルを圧縮しない
+    #[clap(long)]
+    no_log_compress: bool,
+
+    /// ログのフィルター（`info` のようなレベル、または `info,consensus=debug,network=warn` のようなモジュールごとの指定）
     #[clap(long, default_value = "info")]
     log_level: String,
 
@@ -55,37 +165,778 @@ struct Opts {
     /// デバッグモード
     #[clap(long)]
     debug: bool,
+
+    /// bech32m アドレスのプレフィックス（アカウント・トランザクションのコマンドで使用）
+    #[clap(long, default_value = DEFAULT_ADDRESS_PREFIX)]
+    address_prefix: String,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 対話形式でノードを初期設定（鍵の生成・設定ファイルの作成・systemdのユニットの表示）
+    Setup {
+        /// systemdのユニットでノードを実行するユーザー
+        #[clap(long, default_value = "rustorium")]
+        user: String,
+
+        /// 確認せずに既存の設定ファイルを上書きする
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// ベンチマークを実行
+    Bench {
+        #[clap(subcommand)]
+        target: BenchTarget,
+    },
+
+    /// ノードの保守
+    System {
+        #[clap(subcommand)]
+        command: SystemCommand,
+    },
+
+    /// 保存しているブロックから導出する索引の管理（ノードを停止した状態で実行）
+    Index {
+        #[clap(subcommand)]
+        command: IndexCommand,
+    },
+
+    /// 署名用の鍵の管理（データディレクトリの `keystore` に保存）
+    Account {
+        #[clap(subcommand)]
+        command: AccountCommand,
+    },
+
+    /// トランザクションの作成と送信
+    Tx {
+        #[clap(subcommand)]
+        command: TxCommand,
+    },
+
+    /// ネームサービスの名前の登録と解決
+    Name {
+        #[clap(subcommand)]
+        command: NameCommand,
+    },
+
+    /// ハッシュタイムロック（HTLC）によるアトミックスワップ
+    Htlc {
+        #[clap(subcommand)]
+        command: HtlcCommand,
+    },
+
+    /// バリデーターの情報
+    Validator {
+        #[clap(subcommand)]
+        command: ValidatorCommand,
+    },
+
+    /// 会計用の仕訳帳のエクスポート
+    Accounting {
+        #[clap(subcommand)]
+        command: AccountingCommand,
+    },
+
+    /// アドレスを検証して hex と bech32m の両方の表記を表示
+    Address {
+        /// hex または bech32m のアドレス
+        address: String,
+    },
+
+    /// ブロックのデータ可用性をシェアのサンプリングで確認（ライトノード向け）
+    #[cfg(feature = "das")]
+    Das {
+        /// ブロックの高さ
+        height: u64,
+
+        /// サンプリングするノードのAPIのベースURL（複数指定可、コミットメントが一致することも確認する）
+        #[clap(long = "peer", default_value = "http://localhost:9071/api")]
+        peers: Vec<String>,
+
+        /// 目標の信頼度（サンプル数はここから決まる）
+        #[clap(long, default_value = "0.9999")]
+        confidence: f64,
+
+        /// JSON形式で出力
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// APIのOpenAPIドキュメントを出力（ノードの起動は不要）
+    Openapi {
+        /// 出力先ファイル（省略時は標準出力）
+        #[clap(long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// 不具合の調査
+    Debug {
+        #[clap(subcommand)]
+        command: DebugCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DebugCommand {
+    /// バージョン・設定（秘密を伏せたもの）・ログ・クラッシュレポート・メトリクスを1つの tar.gz にまとめる
+    Report {
+        /// 出力先ファイル（省略時は `rustorium-debug-<日時>.tar.gz`）
+        #[clap(long, short)]
+        output: Option<std::path::PathBuf>,
+
+        /// 含めるログの最大サイズ（MB、書き込み中のファイルの末尾を優先する）
+        #[clap(long, default_value = "20")]
+        log_size_mb: u64,
+
+        /// 実行中のノードからヘルスとメトリクスを取得しない
+        #[clap(long)]
+        offline: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SystemCommand {
+    /// `--daemon` で実行中のノードを停止
+    Stop {
+        /// 停止を待つ秒数
+        #[clap(long, default_value = "30")]
+        timeout: u64,
+    },
+
+    /// `--daemon` で実行中のノードを同じ引数で再起動
+    Restart {
+        /// 停止と起動をそれぞれ待つ秒数
+        #[clap(long, default_value = "30")]
+        timeout: u64,
+    },
+
+    /// 実行中のノードが待ち受けているアドレスを表示（データディレクトリの `endpoints.json`）
+    Endpoints {
+        /// JSONで出力
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// 実行中のノードのログのフィルターを表示・変更（`consensus=debug,network=info` など、再起動は不要）
+    LogFilter {
+        /// 新しいフィルター（省略時は現在のフィルターを表示）
+        #[clap(conflicts_with = "reset")]
+        filter: Option<String>,
+
+        /// この秒数の後に変更前のフィルターへ戻す
+        #[clap(long, requires = "filter")]
+        revert_after: Option<u64>,
+
+        /// 起動時のフィルターに戻す
+        #[clap(long)]
+        reset: bool,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+
+        /// 管理者トークン（省略時は設定ファイルの `api.admin_token`）
+        #[clap(long)]
+        token: Option<String>,
+    },
+
+    /// バックアップを作成（ノードを停止した状態で実行）
+    Backup {
+        /// 直前のバックアップとの差分のみ保存
+        #[clap(long)]
+        incremental: bool,
+
+        /// 作成後にS3へアップロード
+        #[clap(long)]
+        upload: bool,
+    },
+
+    /// バックアップを検証して復元（ノードを停止した状態で実行）
+    Restore {
+        /// バックアップID（省略時は最新）
+        id: Option<String>,
+    },
+
+    /// バックアップの整合性を検証
+    Verify {
+        /// バックアップID（省略時はすべて）
+        id: Option<String>,
+    },
+
+    /// バックアップの一覧を表示
+    Backups,
+
+    /// ストレージのスナップショットを1ファイルに書き出す（`--fixture` で読み込む、ノードを停止した状態で実行）
+    Snapshot {
+        /// 出力先ファイル
+        #[clap(long)]
+        output: std::path::PathBuf,
+    },
+
+    /// ストレージスキーマのマイグレーションを適用
+    Migrate {
+        /// 適用するバージョン（省略時は最新）
+        #[clap(long)]
+        to: Option<u32>,
+
+        /// 変更内容を表示するだけで書き込まない
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// 指定した高さの状態をジェネシスの形式で書き出す（ネットワークの再起動や分岐用、ノードを停止した状態で実行）
+    ExportGenesis {
+        /// 書き出す高さ（省略時は最新のブロック）
+        #[clap(long)]
+        at_block: Option<u64>,
+
+        /// 出力先ファイル
+        #[clap(long, default_value = "genesis.json")]
+        output: std::path::PathBuf,
+    },
+
+    /// ストレージスキーマを指定したバージョンまで巻き戻す
+    Rollback {
+        /// 巻き戻し先のバージョン
+        #[clap(long)]
+        to: u32,
+
+        /// 変更内容を表示するだけで書き込まない
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// 保存しているブロックから索引を作り直す（索引の形式の変更や破損の後）
+    Rebuild {
+        /// この高さから作り直す（0 は残高などを含む全てのビュー、それ以外は履歴の索引のみ）
+        #[clap(long, default_value = "0")]
+        from: u64,
+
+        /// 1秒あたりに処理する最大ブロック数（省略時は無制限）
+        #[clap(long)]
+        rate: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AccountCommand {
+    /// 鍵を生成してアドレスを表示
+    New {
+        /// ニーモニックを生成し、そこから最初のアカウントを導出する
+        #[clap(long)]
+        mnemonic: bool,
+
+        /// ニーモニックの単語数
+        #[clap(long, default_value = "24", value_parser = ["12", "15", "18", "21", "24"])]
+        words: String,
+
+        /// BIP-44 のコインタイプ
+        #[clap(long, default_value_t = DEFAULT_COIN_TYPE)]
+        coin_type: u32,
+    },
+
+    /// ニーモニック（標準入力から読む）からアカウントを復元
+    Import {
+        /// 復元するアカウント数（インデックス0から）
+        #[clap(long, default_value = "1")]
+        count: u32,
+
+        /// BIP-44 のコインタイプ
+        #[clap(long, default_value_t = DEFAULT_COIN_TYPE)]
+        coin_type: u32,
+    },
+
+    /// ニーモニック（標準入力から読む）から指定したパスの鍵を導出して保存
+    Derive {
+        /// 導出パス（例: m/44'/7070'/0'/0/1）
+        #[clap(long)]
+        path: DerivationPath,
+    },
+
+    /// 鍵を別の端末に置くアドレスを監視専用のアカウントとして登録
+    Watch {
+        /// hex または bech32m のアドレス
+        address: String,
+    },
+
+    /// 監視専用のアカウントの登録を解除
+    Unwatch {
+        address: String,
+    },
+
+    /// 保存している鍵と監視専用のアカウントのアドレスを表示
+    List,
+}
+
+#[derive(Subcommand)]
+enum TxCommand {
+    /// 署名済みトランザクションを作成
+    Build {
+        /// 送信者のアドレス（hex または bech32m、`--unsigned` 以外はキーストアに鍵があること）
+        #[clap(long)]
+        from: String,
+
+        /// 受信者のアドレス（hex または bech32m）
+        #[clap(long)]
+        to: String,
+
+        /// 送金額
+        #[clap(long, default_value = "0")]
+        value: u64,
+
+        /// ノンス（省略時はノードに問い合わせる）
+        #[clap(long)]
+        nonce: Option<u64>,
+
+        /// ガス価格（省略時はノードが `--speed` に提案する価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// `--gas-price` を省略した場合に使う提案の速さ
+        #[clap(long, default_value = "standard", value_parser = ["slow", "standard", "fast"], conflicts_with = "gas_price")]
+        speed: String,
+
+        /// ガス上限
+        #[clap(long, default_value = "21000")]
+        gas_limit: u64,
+
+        /// データ（hex）
+        #[clap(long, default_value = "", conflicts_with = "memo")]
+        data: String,
+
+        /// データの代わりに付けるメモ（`<tag>:<payload>`、例: `deposit:user-1042`）
+        #[clap(long)]
+        memo: Option<String>,
+
+        /// 有効期限（UNIX秒）
+        #[clap(long)]
+        valid_until: Option<u64>,
+
+        /// 送信先のネットワークのチェーンID（省略時はノードに問い合わせる）
+        #[clap(long)]
+        chain_id: Option<u64>,
+
+        /// ノードに接続しない（--nonce、--gas-price、--chain-id が必須）
+        #[clap(long)]
+        offline: bool,
+
+        /// 署名せずに署名前のトランザクションを出力する（監視専用のアカウント用、`tx sign` で署名する）
+        #[clap(long)]
+        unsigned: bool,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+
+        /// 出力先ファイル（省略時は標準出力）
+        #[clap(long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// 署名前のトランザクション（`tx build --unsigned` や Web UI で作成したもの）に署名
+    Sign {
+        /// 署名前のトランザクションのファイル
+        file: std::path::PathBuf,
+
+        /// 出力先ファイル（省略時は標準出力）
+        #[clap(long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// 署名済みトランザクションのファイルを送信
+    Broadcast {
+        /// `tx build` で作成したファイル
+        file: std::path::PathBuf,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NameCommand {
+    /// 名前を登録（手数料をレジストリに支払う）
+    Register {
+        name: String,
+
+        /// 所有者になるアドレス（キーストアに鍵があること）
+        #[clap(long)]
+        from: String,
+
+        /// 登録する年数
+        #[clap(long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..=10))]
+        years: u8,
+
+        /// ガス価格（省略時はノードが提案する標準の価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+
+    /// 名前の期限を延長（所有者以外も延長できる）
+    Renew {
+        name: String,
+
+        /// 手数料を支払うアドレス（キーストアに鍵があること）
+        #[clap(long)]
+        from: String,
+
+        /// 延長する年数
+        #[clap(long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..=10))]
+        years: u8,
+
+        /// ガス価格（省略時はノードが提案する標準の価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+
+    /// 名前を譲渡
+    Transfer {
+        name: String,
+
+        /// 現在の所有者（キーストアに鍵があること）
+        #[clap(long)]
+        from: String,
+
+        /// 新しい所有者（hex または bech32m）
+        #[clap(long)]
+        to: String,
+
+        /// ガス価格（省略時はノードが提案する標準の価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+
+    /// 名前をアドレスに解決
+    Resolve {
+        name: String,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HtlcCommand {
+    /// 資金をロック（--hashlock を省略すると原像を生成して表示する）
+    Create {
+        /// ロックする資金の送信者（キーストアに鍵があること）
+        #[clap(long)]
+        from: String,
+
+        /// 原像を公開して受け取れるアドレス（hex または bech32m）
+        #[clap(long)]
+        to: String,
+
+        /// ロックする額
+        #[clap(long)]
+        amount: u64,
+
+        /// 原像の SHA-256（hex）。相手が作ったロックに応じる場合は相手と同じ値にする
+        #[clap(long)]
+        hashlock: Option<String>,
+
+        /// 期限（現在からの秒数、最長30日）。スワップを始める側は相手より長くする
+        #[clap(long, default_value = "86400")]
+        timeout: u64,
+
+        /// ガス価格（省略時はノードが提案する標準の価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+
+    /// 原像を公開してロックを受け取る
+    Claim {
+        /// ロックID（作成したトランザクションのハッシュ）
+        id: String,
+
+        /// 送信者（キーストアに鍵があること。受け取るのはロックの受取人）
+        #[clap(long)]
+        from: String,
+
+        /// 原像（hex）
+        #[clap(long)]
+        preimage: String,
+
+        /// ガス価格（省略時はノードが提案する標準の価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+
+    /// 期限切れのロックを作成者に返金
+    Refund {
+        /// ロックID（作成したトランザクションのハッシュ）
+        id: String,
+
+        /// 送信者（キーストアに鍵があること。返金されるのはロックの作成者）
+        #[clap(long)]
+        from: String,
+
+        /// ガス価格（省略時はノードが提案する標準の価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+
+    /// ロックの状態と公開された原像を表示
+    Show {
+        id: String,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum ValidatorCommand {
+    /// バリデーターごとの提案数・ミス率・投票の遅延を表示
+    Perf {
+        /// 集計期間
+        #[clap(long, default_value = "24h", value_parser = ["1h", "24h", "7d"])]
+        window: String,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+
+        /// JSON形式で出力
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// コンセンサス鍵の所有証明を作成し、バリデーターの候補に登録
+    Register {
+        /// コンセンサス鍵のアドレス（キーストアに鍵があること、自己ステークはこのアドレスの残高から出す）
+        #[clap(long)]
+        key: String,
+
+        /// 自己ステーク（`validator.min_stake` 以上）
+        #[clap(long)]
+        stake: u64,
+
+        /// 手数料率（ベーシスポイント、500 = 5%）
+        #[clap(long, default_value = "0", value_parser = clap::value_parser!(u16).range(0..=10_000))]
+        commission_bps: u16,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AccountingCommand {
+    /// アカウントの期間の送金・手数料・報酬を複式簿記の仕訳として出力
+    Export {
+        /// 対象のアドレス（hex または bech32m、複数指定可）
+        #[clap(long = "account", required = true)]
+        accounts: Vec<String>,
+
+        /// 開始日（UTC、YYYY-MM-DD）
+        #[clap(long)]
+        from: String,
+
+        /// 終了日（UTC、YYYY-MM-DD、この日を含む）
+        #[clap(long)]
+        to: String,
+
+        /// 出力形式
+        #[clap(long, default_value = "csv", value_parser = ["csv", "quickbooks", "json"])]
+        format: String,
+
+        /// 金額の小数の桁数（0 は最小単位の整数）
+        #[clap(long, default_value = "0")]
+        decimals: u32,
+
+        /// ノードのAPIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+
+        /// 出力先ファイル（省略時は標準出力）
+        #[clap(long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BenchTarget {
+    /// ストレージバックエンドの比較
+    Storage {
+        /// 計測対象のバックエンド（複数指定可）
+        #[clap(long = "backend", value_enum, default_values = ["rocksdb", "redb"])]
+        backends: Vec<bench::storage::Backend>,
+
+        /// 各操作の実行回数
+        #[clap(long, default_value = "10000")]
+        ops: usize,
+
+        /// 値のサイズ（バイト）
+        #[clap(long, default_value = "256")]
+        value_size: usize,
+
+        /// バッチ書き込みのサイズ
+        #[clap(long, default_value = "100")]
+        batch_size: usize,
+
+        /// TiKVのPDエンドポイント
+        #[clap(long, default_value = "127.0.0.1:2379")]
+        tikv_pd: Vec<String>,
+
+        /// JSON形式で出力
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// 送金トランザクションの負荷生成
+    Tps {
+        /// 送信先APIのベースURL
+        #[clap(long, default_value = "http://localhost:9071/api")]
+        endpoint: String,
+
+        /// 送金に使うアカウント数
+        #[clap(long, default_value = "1000")]
+        accounts: usize,
+
+        /// 目標送信レート（tx/s）
+        #[clap(long, default_value = "5000")]
+        rate: u64,
+
+        /// 送信を続ける秒数
+        #[clap(long, default_value = "30")]
+        duration: u64,
+
+        /// 確定待ちのタイムアウト（秒）
+        #[clap(long, default_value = "30")]
+        confirm_timeout: u64,
+
+        /// アカウント生成用のシード
+        #[clap(long, default_value = "0")]
+        seed: u64,
+
+        /// ガス価格（省略時はノードが受け付ける最低価格）
+        #[clap(long)]
+        gas_price: Option<u64>,
+
+        /// 計測の前に各アカウントへ資金を配るアドレス（キーストアに鍵があること）
+        #[clap(long)]
+        funder: Option<String>,
+
+        /// 各アカウントに配る額
+        #[clap(long, default_value = "1000000")]
+        fund_amount: u64,
+
+        /// 出力形式
+        #[clap(long, value_enum, default_value = "table")]
+        format: bench::tps::OutputFormat,
+
+        /// 出力先ファイル（省略時は標準出力）
+        #[clap(long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
     // コマンドライン引数の解析
     let opts = Opts::parse();
 
-    // ロギングの設定
-    let log_level = match opts.log_level.to_lowercase().as_str() {
-        "debug" => Level::DEBUG,
-        "info" => Level::INFO,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        _ => Level::INFO,
+    // フォークはランタイムのスレッドを引き継がないため、ランタイムの起動前に切り離す
+    if opts.daemon && opts.command.is_none() {
+        daemon::detach(&daemon::pid_path(opts.pid_file.as_deref(), std::path::Path::new(&opts.data_dir)))?;
+    }
+
+    tokio::runtime::Runtime::new()?.block_on(run(opts))
+}
+
+async fn run(opts: Opts) -> Result<()> {
+    // ロギングの設定（フィルターは実行中に管理者APIから変更できる）
+    let (filter_layer, log_filter) = LogFilter::new(&opts.log_level)?;
+
+    // ログファイル（`--daemon` では標準出力が閉じられるため、既定でデータディレクトリに書く）
+    let log_dir = opts.log_dir.clone()
+        .or_else(|| opts.daemon.then(|| std::path::Path::new(&opts.data_dir).join("logs")));
+    let (writer, _log_guard) = match &log_dir {
+        Some(dir) => {
+            let file = RotatingFile::open(RotationConfig {
+                dir: dir.clone(),
+                rotation: opts.log_rotation,
+                max_bytes: opts.log_max_size_mb * 1024 * 1024,
+                max_files: opts.log_max_files,
+                compress: !opts.no_log_compress,
+            })?;
+            let (writer, guard) = tracing_appender::non_blocking(file);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stdout), None),
     };
 
-    let subscriber = fmt::fmt()
-        .with_max_level(log_level)
+    let format_layer = fmt::layer()
+        .with_writer(writer)
         .with_target(opts.debug)
         .with_thread_ids(opts.debug)
         .with_file(opts.debug)
         .with_line_number(opts.debug)
         .with_thread_names(opts.debug)
         .with_level(true)
-        .with_ansi(true)
-        .pretty()
-        .finish();
+        .with_ansi(log_dir.is_none())
+        .pretty();
+    let subscriber = tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(format_layer);
 
     tracing::subscriber::set_global_default(subscriber)
         .expect("Failed to set tracing subscriber");
 
+    // サブコマンドの実行
+    if let Some(command) = opts.command {
+        let addresses = AddressFormat::new(&opts.address_prefix)?;
+        return run_command(command, &opts.config, &opts.data_dir, opts.pid_file.as_deref(), log_dir.as_deref(), &addresses).await;
+    }
+
+    // パニックをデータディレクトリのクラッシュレポートに書き出す
+    debug_report::install_panic_hook(std::path::Path::new(&opts.data_dir));
+
+    // PIDファイル（停止時に削除）
+    let _pid_file = if opts.daemon || opts.pid_file.is_some() {
+        let path = daemon::pid_path(opts.pid_file.as_deref(), std::path::Path::new(&opts.data_dir));
+        let pid_file = daemon::PidFile::create(&path)?;
+        info!("Wrote PID {} to {}", std::process::id(), path.display());
+        Some(pid_file)
+    } else {
+        None
+    };
+
     // 開発モードのログ
     if opts.dev {
         info!("Running in development mode");
@@ -94,7 +945,9 @@ async fn main() -> Result<()> {
     }
 
     // 設定の読み込みと更新
-    let mut config = if opts.dev {
+    let mut config = if let Some(snapshot) = opts.fixture.clone() {
+        NodeConfig::fixture(snapshot, opts.data_dir.clone().into())
+    } else if opts.dev || opts.demo {
         NodeConfig::development()
     } else {
         NodeConfig::from_file(&opts.config)?
@@ -104,9 +957,53 @@ async fn main() -> Result<()> {
     config.node.data_dir = opts.data_dir.into();
     config.network.port = opts.port;
     config.web.enabled = true;
+    if let Some(role) = opts.role {
+        config.node.role = role;
+    }
+    if opts.shadow {
+        config.validator.shadow = true;
+    }
+    if let Some(checkpoint) = opts.trust_checkpoint {
+        config.replica.trust_checkpoint = Some(checkpoint);
+    }
+    if let Some(lang) = opts.lang {
+        config.i18n.language = lang;
+    }
+
+    // ネットワーク障害の注入（開発モードのみ）
+    if opts.dev {
+        let chaos = &mut config.dev.chaos;
+        if let Some(latency) = opts.chaos_latency {
+            chaos.latency_ms = latency;
+        }
+        if let Some(jitter) = opts.chaos_jitter {
+            chaos.jitter_ms = jitter;
+        }
+        if let Some(loss) = opts.chaos_loss {
+            chaos.loss = loss;
+        }
+        if let Some(bandwidth) = opts.chaos_bandwidth {
+            chaos.bandwidth_kbps = bandwidth;
+        }
+        chaos.links.extend(opts.chaos_link);
+        chaos.partitions.extend(opts.chaos_partition);
+    } else if config.dev.chaos != ChaosSettings::default() {
+        warn!("Ignoring [dev.chaos]: network chaos is only injected in development mode");
+        config.dev.chaos = ChaosSettings::default();
+    }
 
     // ディレクトリの作成
     tokio::fs::create_dir_all(&config.node.data_dir).await?;
+    if let Some(snapshot) = &config.dev.fixture {
+        // 記録したファイルを変更せず、毎回同じ状態から始めるよう複製を開く
+        if tokio::fs::try_exists(&config.storage.path).await? {
+            tokio::fs::remove_dir_all(&config.storage.path).await?;
+        }
+        tokio::fs::create_dir_all(&config.storage.path).await?;
+        tokio::fs::copy(snapshot, config.storage.path.join(DB_FILE)).await
+            .map_err(|e| anyhow::anyhow!("Failed to open fixture {}: {}", snapshot.display(), e))?;
+        info!("Loaded fixture snapshot {}", snapshot.display());
+    }
     tokio::fs::create_dir_all(&config.storage.path).await?;
 
     info!("Initializing storage...");
@@ -118,62 +1015,85 @@ async fn main() -> Result<()> {
         encryption_enabled: true,
         replication_factor: 3,
     };
-    let storage = Arc::new(RedbStorage::new(storage_config)?);
+    let storage = if opts.demo {
+        #[cfg(feature = "demo")]
+        {
+            info!("Running in demo mode: storage is kept in memory and discarded on exit");
+            Arc::new(RedbStorage::in_memory(storage_config)?)
+        }
+        #[cfg(not(feature = "demo"))]
+        anyhow::bail!("Demo mode is not available in this build; rebuild with `--features demo`");
+    } else {
+        Arc::new(RedbStorage::new(storage_config)?)
+    };
+
+    // フィクスチャモードではP2Pネットワークと最適化タスクを起動しない
+    let fixture = config.dev.fixture.is_some();
 
     info!("Initializing network...");
     // ネットワークの設定と初期化
     let network_config = NetworkConfig {
-        listen_addr: format!("0.0.0.0:{}", config.network.port).parse()?,
+        listen_addr: config.bind_addr(None, 0)?,
         bootstrap_nodes: config.network.bootstrap_nodes.clone(),
         max_concurrent_streams: 1000,
         keep_alive_interval: std::time::Duration::from_secs(10),
         handshake_timeout: std::time::Duration::from_secs(10),
         idle_timeout: std::time::Duration::from_secs(30),
+        gossip: (&config.network.gossip).into(),
+        peers: (&config.network.peers).into(),
+        diversity: DiversityPolicy::from_settings(&config.network.diversity)?,
+        sentry: SentryConfig::from_settings(&config.network.sentry)?,
+        peering: PeeringConfig::from_settings(&config.network.peering, config.network.port)?,
+        chaos: ChaosConfig::from_settings(&config.dev.chaos)?,
+        role: NodeRole::from_config(&config.node.role),
     };
-    let network = Arc::new(QuicNetwork::new(network_config).await?);
+    let _network = if fixture { None } else { Some(Arc::new(QuicNetwork::new(network_config).await?)) };
 
     info!("Initializing AI optimizer...");
     // AI最適化エンジンの初期化
-    let ai_optimizer = Arc::new(Mutex::new(AiOptimizer::new()));
+    let ai_optimizer = Arc::new(Mutex::new(AiOptimizer::new(AiConfig::from(&config.ai), &config.node.data_dir)?));
 
     // 最適化タスクの開始
-    if !opts.dev {
+    if !opts.dev && !fixture {
         let ai_optimizer_clone = ai_optimizer.clone();
+        let interval = config.ai.interval.max(1);
         tokio::spawn(async move {
             loop {
-                let mut optimizer = ai_optimizer_clone.lock().await;
-                if let Err(e) = optimizer.optimize_system().await {
+                // 待機中は他のタスクが監査ログを参照できるようロックを解放する
+                if let Err(e) = ai_optimizer_clone.lock().await.optimize_system().await {
                     error!("AI optimization error: {}", e);
                 }
-                tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
             }
         });
     }
 
     info!("Starting services...");
     // サービスマネージャーを作成して起動
-    let mut service_manager = ServiceManager::new(config.clone());
+    let mut service_manager = ServiceManager::new(config.clone())?;
     service_manager.set_storage(storage);
-    service_manager.set_ai_optimizer(ai_optimizer);
+    service_manager.set_log_filter(log_filter);
+    if !fixture {
+        service_manager.set_ai_optimizer(ai_optimizer);
+    }
     service_manager.start().await?;
 
     info!("Rustorium node started successfully!");
-    info!("API endpoint: http://localhost:{}", config.network.port);
-    info!("Web UI: http://localhost:{}", config.network.port + 1);
-    info!("WebSocket: ws://localhost:{}", config.network.port + 2);
 
     // メトリクスの有効化
     if opts.metrics {
-        info!("Metrics enabled at http://localhost:{}/metrics", config.network.port);
+        if let Some(web) = service_manager.endpoints().and_then(|manifest| manifest.get("web")) {
+            info!("Metrics enabled at {}/metrics", web.url);
+        }
     }
 
     // インタラクティブコンソールを起動（--no-interactiveが指定されていない場合）
-    if !opts.no_interactive {
+    if !opts.no_interactive && !opts.daemon {
         InteractiveConsole::run(&service_manager).await?;
     } else {
-        info!("Running in non-interactive mode. Press Ctrl+C to stop.");
-        tokio::signal::ctrl_c().await?;
-        println!("\n{}", style("Received Ctrl+C, shutting down...").dim());
+        info!("Running in non-interactive mode. Press Ctrl+C or send SIGTERM to stop.");
+        let signal = daemon::shutdown_signal().await?;
+        info!("Received {}, shutting down...", signal);
     }
 
     // シャットダウン処理
@@ -182,4 +1102,747 @@ async fn main() -> Result<()> {
     info!("Shutdown complete.");
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// ノードの応答を確認し、失敗した場合はエラーコード付きのエラー（`ApiError`）にする
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.bytes().await?;
+    Err(ApiError::from_response(status.as_u16(), &body).into())
+}
+
+/// ノードが返したエラーのコード
+fn error_code(error: &anyhow::Error) -> Option<ErrorCode> {
+    error.downcast_ref::<ApiError>().and_then(ApiError::error_code)
+}
+
+/// サブコマンドを実行
+async fn run_command(
+    command: Command,
+    config_path: &str,
+    data_dir: &str,
+    pid_file: Option<&std::path::Path>,
+    log_dir: Option<&std::path::Path>,
+    addresses: &AddressFormat,
+) -> Result<()> {
+    match command {
+        Command::Setup { user, force } => {
+            setup::run(std::path::Path::new(config_path), std::path::Path::new(data_dir), &user, force).await?;
+        }
+        // 実行中のノードへの操作は設定ファイルを読まない
+        Command::System { command: SystemCommand::Stop { timeout } } => {
+            let path = daemon::pid_path(pid_file, std::path::Path::new(data_dir));
+            let pid = daemon::stop(&path, std::time::Duration::from_secs(timeout)).await?;
+            println!("{} Stopped Rustorium (PID {})", style("✓").green(), pid);
+        }
+        Command::System { command: SystemCommand::Restart { timeout } } => {
+            let path = daemon::pid_path(pid_file, std::path::Path::new(data_dir));
+            let pid = daemon::restart(&path, std::time::Duration::from_secs(timeout)).await?;
+            println!("{} Restarted Rustorium (PID {})", style("✓").green(), pid);
+        }
+        Command::System { command: SystemCommand::Endpoints { json } } => {
+            let manifest = ServiceManifest::read(std::path::Path::new(data_dir))?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&manifest)?);
+                return Ok(());
+            }
+            println!("{} (PID {})", manifest.node, manifest.pid);
+            for endpoint in &manifest.endpoints {
+                println!("{:<10} {:<40} {}", endpoint.name, endpoint.address, endpoint.url);
+            }
+        }
+        Command::System { command: SystemCommand::LogFilter { filter, revert_after, reset, endpoint, token } } => {
+            let token = token
+                .or_else(|| NodeConfig::from_file(config_path).ok().and_then(|config| config.api.admin_token))
+                .ok_or_else(|| anyhow::anyhow!("An admin token is required: pass --token or set api.admin_token in {}", config_path))?;
+            let client = reqwest::Client::new();
+            let url = format!("{}/admin/log-filter", endpoint.trim_end_matches('/'));
+            let request = match filter {
+                Some(filter) => client.put(&url).json(&serde_json::json!({ "filter": filter, "revert_after_secs": revert_after })),
+                None if reset => client.delete(&url),
+                None => client.get(&url),
+            };
+            let body: serde_json::Value = check_response(request.bearer_auth(token).send().await?).await?.json().await?;
+            let status = &body["log_filter"];
+            println!("Log filter: {}", status["filter"].as_str().unwrap_or_default());
+            println!("At startup: {}", status["initial"].as_str().unwrap_or_default());
+            if let Some(at) = status["reverts_at"].as_u64() {
+                let at = chrono::DateTime::from_timestamp(at as i64, 0).unwrap_or_default();
+                println!("Reverts at: {}", at.format("%Y-%m-%d %H:%M:%S UTC"));
+            }
+            if let Some(modules) = body["modules"].as_array() {
+                println!("Modules:");
+                for module in modules {
+                    println!("  {:<12} {}", module["name"].as_str().unwrap_or_default(), module["target"].as_str().unwrap_or_default());
+                }
+            }
+        }
+        Command::Debug { command: DebugCommand::Report { output, log_size_mb, offline } } => {
+            let data_dir = std::path::Path::new(data_dir);
+            let options = ReportOptions {
+                config_path: config_path.into(),
+                data_dir: data_dir.to_path_buf(),
+                log_dir: log_dir.map_or_else(|| data_dir.join("logs"), |dir| dir.to_path_buf()),
+                log_bytes: log_size_mb * 1024 * 1024,
+                query_node: !offline,
+            };
+            let output = output.unwrap_or_else(|| {
+                format!("rustorium-debug-{}.tar.gz", chrono::Utc::now().format("%Y%m%dT%H%M%SZ")).into()
+            });
+            let report = debug_report::create(&options, &output).await?;
+            for note in &report.notes {
+                println!("{} {}", style("!").yellow(), note);
+            }
+            println!("{} Wrote {} files to {}", style("✓").green(), report.files.len() + 1, output.display());
+            println!("Review the bundle before attaching it to a support ticket");
+        }
+        Command::System { command } => {
+            let mut config = NodeConfig::from_file(config_path)?;
+            config.node.data_dir = data_dir.into();
+            run_system_command(command, &config).await?;
+        }
+        Command::Index { command: IndexCommand::Rebuild { from, rate } } => {
+            let mut config = NodeConfig::from_file(config_path)?;
+            config.node.data_dir = data_dir.into();
+            let storage: Arc<dyn StorageEngine> = Arc::new(RedbStorage::new(StorageConfig {
+                path: config.storage_path().to_string_lossy().to_string(),
+                ..Default::default()
+            })?);
+            let chain = Arc::new(Chain::open(storage.clone()).await?);
+            let views = Arc::new(MaterializedViews::new(storage).with_genesis(genesis::initial_state(&config.consensus)?));
+            let progress = Reindexer::new(chain, views, rate).run(from, |progress| {
+                let eta = progress.eta().map_or_else(|| "-".to_string(), |eta| format!("{}s", eta.as_secs()));
+                eprintln!("  {}/{} blocks ({:.1}%), {:.0} blocks/s, ETA {}",
+                    progress.done, progress.total(), progress.percent(), progress.rate(), eta);
+            }).await?;
+            println!("{} Reindexed blocks {} to {} in {:.1}s",
+                style("✓").green(), progress.first, progress.last, progress.elapsed.as_secs_f64());
+        }
+        Command::Account { command } => {
+            let keystore = Keystore::new(std::path::Path::new(data_dir).join("keystore"));
+            match command {
+                AccountCommand::New { mnemonic: false, .. } => {
+                    let address = keystore.generate().await?;
+                    println!("{} Created {} ({})", style("✓").green(), addresses.encode(&address)?, address);
+                }
+                AccountCommand::New { mnemonic: true, words, coin_type } => {
+                    let mnemonic = hd::generate_mnemonic(words.parse()?)?;
+                    let path = DerivationPath::bip44(coin_type, 0, 0);
+                    let address = keystore.import(&hd::derive_key(&mnemonic, "", &path)).await?;
+                    println!("{}", style("Write down this recovery phrase and keep it offline. It is shown only once:").yellow());
+                    println!("\n{}\n", mnemonic);
+                    println!("{} Created {} ({}) at {}", style("✓").green(), addresses.encode(&address)?, address, path);
+                }
+                AccountCommand::Import { count, coin_type } => {
+                    let mnemonic = hd::parse_mnemonic(&read_mnemonic()?)?;
+                    for index in 0..count {
+                        let path = DerivationPath::bip44(coin_type, 0, index);
+                        let address = keystore.import(&hd::derive_key(&mnemonic, "", &path)).await?;
+                        println!("{} Restored {} at {}", style("✓").green(), addresses.encode(&address)?, path);
+                    }
+                }
+                AccountCommand::Derive { path } => {
+                    let mnemonic = hd::parse_mnemonic(&read_mnemonic()?)?;
+                    let address = keystore.import(&hd::derive_key(&mnemonic, "", &path)).await?;
+                    println!("{} Derived {} at {}", style("✓").green(), addresses.encode(&address)?, path);
+                }
+                AccountCommand::Watch { address } => {
+                    let address = addresses.parse(&address)?;
+                    keystore.add_watch_only(&address).await?;
+                    println!("{} Watching {} (sign its transactions with `tx sign` on the device that holds the key)",
+                        style("✓").green(), addresses.encode(&address)?);
+                }
+                AccountCommand::Unwatch { address } => {
+                    keystore.remove_watch_only(&addresses.parse(&address)?).await?;
+                    println!("{} Removed watch-only account", style("✓").green());
+                }
+                AccountCommand::List => {
+                    for address in keystore.list().await? {
+                        println!("{}  {}", addresses.encode(&address)?, address);
+                    }
+                    for address in keystore.watch_only().await? {
+                        println!("{}  {}  (watch-only)", addresses.encode(&address)?, address);
+                    }
+                }
+            }
+        }
+        Command::Address { address } => {
+            let hex = addresses.parse(&address)?;
+            println!("hex:    {}", hex);
+            println!("bech32: {}", addresses.encode(&hex)?);
+        }
+        Command::Tx { command } => run_tx_command(command, data_dir, addresses).await?,
+        Command::Name { command } => run_name_command(command, data_dir, addresses).await?,
+        Command::Htlc { command } => run_htlc_command(command, data_dir, addresses).await?,
+        Command::Validator { command } => run_validator_command(command, data_dir, addresses).await?,
+        #[cfg(feature = "das")]
+        Command::Das { height, peers, confidence, json } => {
+            let report = das::Sampler::new(peers)?
+                .check(height, das::samples_for_confidence(confidence))
+                .await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!(
+                    "Block {} ({}) is available with confidence {:.4}% ({} of {} shares sampled)",
+                    height,
+                    report.commitment.block_hash,
+                    report.confidence * 100.0,
+                    report.sampled.len(),
+                    report.commitment.total_shards(),
+                );
+            }
+        }
+        Command::Accounting { command: AccountingCommand::Export { accounts, from, to, format, decimals, endpoint, output } } => {
+            let accounts = accounts.iter()
+                .map(|a| addresses.parse(a))
+                .collect::<Result<Vec<String>, _>>()?;
+            let response = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(300))
+                .build()?
+                .get(format!("{}/accounting/ledger", endpoint.trim_end_matches('/')))
+                .query(&[
+                    ("accounts", accounts.join(",")),
+                    ("from", from),
+                    ("to", to),
+                    ("format", format),
+                    ("decimals", decimals.to_string()),
+                ])
+                .send().await?;
+            let status = response.status();
+            let body = response.bytes().await?;
+            if !status.is_success() {
+                return Err(ApiError::from_response(status.as_u16(), &body).into());
+            }
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, &body).await?;
+                    println!("{} Wrote {}", style("✓").green(), path.display());
+                }
+                None => print!("{}", String::from_utf8_lossy(&body)),
+            }
+        }
+        Command::Openapi { output } => {
+            let document = api::openapi().to_pretty_json()?;
+            match output {
+                Some(path) => tokio::fs::write(path, document).await?,
+                None => println!("{}", document),
+            }
+        }
+        Command::Bench { target } => match target {
+            BenchTarget::Storage { backends, ops, value_size, batch_size, tikv_pd, json } => {
+                let config = bench::storage::StorageBenchConfig {
+                    backends,
+                    operations: ops,
+                    value_size,
+                    batch_size,
+                    tikv_endpoints: tikv_pd,
+                    ..Default::default()
+                };
+                let report = bench::storage::run(&config).await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    println!("{}", report.to_table());
+                }
+            }
+            BenchTarget::Tps {
+                endpoint, accounts, rate, duration, confirm_timeout, seed, gas_price, funder, fund_amount, format, output,
+            } => {
+                let funder = match funder {
+                    Some(address) => Some(
+                        Keystore::new(std::path::Path::new(data_dir).join("keystore"))
+                            .load(&addresses.parse(&address)?)
+                            .await?,
+                    ),
+                    None => None,
+                };
+                let config = bench::tps::TpsBenchConfig {
+                    endpoint,
+                    accounts,
+                    rate,
+                    duration: std::time::Duration::from_secs(duration),
+                    confirm_timeout: std::time::Duration::from_secs(confirm_timeout),
+                    seed,
+                    gas_price,
+                    funder,
+                    fund_amount,
+                    ..Default::default()
+                };
+                let report = bench::tps::run(&config).await?;
+                let rendered = match format {
+                    bench::tps::OutputFormat::Table => report.to_table(),
+                    bench::tps::OutputFormat::Json => serde_json::to_string_pretty(&report)?,
+                    bench::tps::OutputFormat::Csv => report.to_csv(),
+                };
+                match output {
+                    Some(path) => tokio::fs::write(path, rendered).await?,
+                    None => println!("{}", rendered),
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+/// 標準入力からニーモニックを読む（シェルの履歴に残らないよう引数では受け取らない）
+fn read_mnemonic() -> Result<String> {
+    eprint!("Recovery phrase: ");
+    let mut phrase = String::new();
+    std::io::stdin().read_line(&mut phrase)?;
+    Ok(phrase)
+}
+
+/// 省略したノンス・ガス価格・チェーンIDをノードに問い合わせる
+async fn tx_params(
+    client: &reqwest::Client,
+    endpoint: &str,
+    from: &str,
+    nonce: Option<u64>,
+    gas_price: Option<u64>,
+    chain_id: Option<u64>,
+    speed: &str,
+) -> Result<(u64, u64, u64)> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let current: serde_json::Value = check_response(client
+        .get(format!("{}/accounts/{}/nonce", endpoint, from))
+        .send().await?).await?
+        .json().await?;
+    let field = |name: &str| current[name].as_u64()
+        .ok_or_else(|| anyhow::anyhow!("Node response is missing {}", name));
+    let gas_price = match gas_price {
+        Some(p) => p,
+        None => {
+            let suggestion: FeeSuggestion = check_response(client
+                .get(format!("{}/fees/suggest", endpoint))
+                .send().await?).await?
+                .json().await?;
+            let estimate = match speed {
+                "slow" => suggestion.slow,
+                "fast" => suggestion.fast,
+                _ => suggestion.standard,
+            };
+            // 標準出力は署名済みトランザクションの出力先になりうる
+            eprintln!("Using {} gas price {} (within about {} blocks)", speed, estimate.gas_price, estimate.target_blocks);
+            estimate.gas_price
+        }
+    };
+    Ok((
+        match nonce { Some(n) => n, None => field("next_nonce")? },
+        gas_price,
+        match chain_id { Some(c) => c, None => field("chain_id")? },
+    ))
+}
+
+/// ネイティブモジュール宛ての操作
+struct ModuleCall {
+    to: &'static str,
+    value: u64,
+    data: Vec<u8>,
+}
+
+/// モジュール宛ての操作にキーストアの鍵で署名して送信し、ハッシュを返す
+async fn send_module_call(
+    client: &reqwest::Client,
+    endpoint: &str,
+    data_dir: &str,
+    from: String,
+    call: ModuleCall,
+    gas_price: Option<u64>,
+) -> Result<String> {
+    let (nonce, gas_price, chain_id) = tx_params(client, endpoint, &from, None, gas_price, None, "standard").await?;
+    let key = Keystore::new(std::path::Path::new(data_dir).join("keystore")).load(&from).await?;
+    let tx = PendingTransaction {
+        hash: String::new(),
+        from,
+        to: call.to.to_string(),
+        value: call.value,
+        nonce,
+        gas_price,
+        gas_limit: 21_000,
+        data: call.data,
+        received_at: 0,
+        valid_until: None,
+        chain_id: Some(chain_id),
+        blob: None,
+        signature: None,
+    };
+    let response = client
+        .post(format!("{}/transactions", endpoint.trim_end_matches('/')))
+        .json(&SignedTransaction::new(&key, &tx))
+        .send().await?;
+    check_response(response).await.context("Node rejected the transaction")?;
+    Ok(tx.compute_hash())
+}
+
+fn name_call(value: u64, op: &NameOp) -> ModuleCall {
+    ModuleCall { to: NAME_REGISTRY_ADDRESS, value, data: op.encode() }
+}
+
+/// ネームサービスのコマンドを実行
+async fn run_name_command(command: NameCommand, data_dir: &str, addresses: &AddressFormat) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    match command {
+        NameCommand::Register { name, from, years, gas_price, endpoint } => {
+            let name = names::validate_name(&name.to_lowercase())?;
+            let fee = names::annual_fee(&name) * years as u64;
+            let op = NameOp::Register { name: name.clone(), years };
+            let hash = send_module_call(&client, &endpoint, data_dir, addresses.parse(&from)?, name_call(fee, &op), gas_price).await?;
+            println!("{} Registering {} for {} year(s), paying {} ({})", style("✓").green(), name, years, fee, hash);
+        }
+        NameCommand::Renew { name, from, years, gas_price, endpoint } => {
+            let name = names::validate_name(&name.to_lowercase())?;
+            let fee = names::annual_fee(&name) * years as u64;
+            let op = NameOp::Renew { name: name.clone(), years };
+            let hash = send_module_call(&client, &endpoint, data_dir, addresses.parse(&from)?, name_call(fee, &op), gas_price).await?;
+            println!("{} Renewing {} for {} year(s), paying {} ({})", style("✓").green(), name, years, fee, hash);
+        }
+        NameCommand::Transfer { name, from, to, gas_price, endpoint } => {
+            let name = names::validate_name(&name.to_lowercase())?;
+            let op = NameOp::Transfer { name: name.clone(), owner: addresses.parse(&to)? };
+            let hash = send_module_call(&client, &endpoint, data_dir, addresses.parse(&from)?, name_call(0, &op), gas_price).await?;
+            println!("{} Transferring {} to {} ({})", style("✓").green(), name, to, hash);
+        }
+        NameCommand::Resolve { name, endpoint } => {
+            let response = client
+                .get(format!("{}/names/{}/resolve", endpoint.trim_end_matches('/'), name))
+                .send().await?;
+            let resolved: serde_json::Value = match check_response(response).await {
+                Ok(response) => response.json().await?,
+                Err(e) if error_code(&e) == Some(ErrorCode::NameNotFound) => {
+                    anyhow::bail!("{} is not registered or has expired", name);
+                }
+                Err(e) => return Err(e),
+            };
+            let address = resolved["address"].as_str()
+                .ok_or_else(|| anyhow::anyhow!("Node response is missing address"))?;
+            println!("{}  {}", addresses.encode(address)?, address);
+        }
+    }
+    Ok(())
+}
+
+/// HTLC のコマンドを実行
+async fn run_htlc_command(command: HtlcCommand, data_dir: &str, addresses: &AddressFormat) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let htlc_call = |op: &HtlcOp, value: u64| ModuleCall { to: HTLC_ADDRESS, value, data: op.encode() };
+
+    match command {
+        HtlcCommand::Create { from, to, amount, hashlock, timeout, gas_price, endpoint } => {
+            let hashlock: [u8; 32] = match hashlock {
+                Some(hashlock) => hex::decode(hashlock.trim_start_matches("0x"))?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("--hashlock must be 32 bytes"))?,
+                None => {
+                    let secret: [u8; 32] = rand::random();
+                    println!("Secret (reveal it only by claiming the counterparty's lock): {}", hex::encode(secret));
+                    htlc::hashlock(&secret)
+                }
+            };
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+            let op = HtlcOp::Create { hashlock, timeout: now + timeout, recipient: addresses.parse(&to)? };
+            let id = send_module_call(&client, &endpoint, data_dir, addresses.parse(&from)?, htlc_call(&op, amount), gas_price).await?;
+            println!("{} Locked {} for {} until {} (hashlock {})", style("✓").green(), amount, to, now + timeout, hex::encode(hashlock));
+            println!("HTLC id: {}", id);
+        }
+        HtlcCommand::Claim { id, from, preimage, gas_price, endpoint } => {
+            let op = HtlcOp::Claim {
+                id: id.trim_start_matches("0x").to_lowercase(),
+                preimage: hex::decode(preimage.trim_start_matches("0x"))?,
+            };
+            let hash = send_module_call(&client, &endpoint, data_dir, addresses.parse(&from)?, htlc_call(&op, 0), gas_price).await?;
+            println!("{} Claiming {} ({})", style("✓").green(), id, hash);
+        }
+        HtlcCommand::Refund { id, from, gas_price, endpoint } => {
+            let op = HtlcOp::Refund { id: id.trim_start_matches("0x").to_lowercase() };
+            let hash = send_module_call(&client, &endpoint, data_dir, addresses.parse(&from)?, htlc_call(&op, 0), gas_price).await?;
+            println!("{} Refunding {} ({})", style("✓").green(), id, hash);
+        }
+        HtlcCommand::Show { id, endpoint } => {
+            let response = client
+                .get(format!("{}/htlc/{}", endpoint.trim_end_matches('/'), id.trim_start_matches("0x")))
+                .send().await?;
+            let lock: serde_json::Value = match check_response(response).await {
+                Ok(response) => response.json().await?,
+                Err(e) if error_code(&e) == Some(ErrorCode::HtlcNotFound) => {
+                    anyhow::bail!("HTLC {} is not known to the node (it may not be committed yet)", id);
+                }
+                Err(e) => return Err(e),
+            };
+            println!("{}", serde_json::to_string_pretty(&lock)?);
+        }
+    }
+    Ok(())
+}
+
+/// トランザクションのコマンドを実行
+async fn run_tx_command(command: TxCommand, data_dir: &str, addresses: &AddressFormat) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+
+    match command {
+        TxCommand::Build {
+            from, to, value, nonce, gas_price, speed, gas_limit, data, memo, valid_until, chain_id, offline, unsigned, endpoint, output,
+        } => {
+            // 入力ミスをチェックサムで検出し、内部表記にそろえる
+            let from = addresses.parse(&from)?;
+            let to = addresses.parse(&to)?;
+            let (nonce, gas_price, chain_id) = match (nonce, gas_price, chain_id) {
+                (Some(nonce), Some(gas_price), Some(chain_id)) => (nonce, gas_price, chain_id),
+                _ if offline => anyhow::bail!("--nonce, --gas-price and --chain-id are required with --offline"),
+                (nonce, gas_price, chain_id) => tx_params(&client, &endpoint, &from, nonce, gas_price, chain_id, &speed).await?,
+            };
+
+            let tx = PendingTransaction {
+                hash: String::new(),
+                from,
+                to,
+                value,
+                nonce,
+                gas_price,
+                gas_limit,
+                data: match memo {
+                    Some(memo) => {
+                        let (tag, payload) = memo.split_once(':')
+                            .ok_or_else(|| anyhow::anyhow!("--memo must be <tag>:<payload>"))?;
+                        Memo::new(tag, payload)?.encode()
+                    }
+                    None => hex::decode(data.trim_start_matches("0x"))?,
+                },
+                received_at: 0,
+                valid_until,
+                chain_id: Some(chain_id),
+                blob: None,
+                signature: None,
+            };
+            if unsigned {
+                let unsigned = UnsignedTransaction::new(&tx, chain_id);
+                let json = serde_json::to_string_pretty(&unsigned)?;
+                match output {
+                    Some(path) => {
+                        tokio::fs::write(&path, json).await?;
+                        println!("{} Wrote unsigned {} (nonce {}) to {}; sign it with `tx sign`",
+                            style("✓").green(), unsigned.hash, nonce, path.display());
+                    }
+                    None => println!("{}", json),
+                }
+                return Ok(());
+            }
+            let key = Keystore::new(std::path::Path::new(data_dir).join("keystore")).load(&tx.from).await?;
+            let signed = serde_json::to_string_pretty(&SignedTransaction::new(&key, &tx))?;
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, signed).await?;
+                    println!("{} Signed {} (nonce {}) to {}",
+                        style("✓").green(), tx.compute_hash(), nonce, path.display());
+                }
+                None => println!("{}", signed),
+            }
+        }
+        TxCommand::Sign { file, output } => {
+            let unsigned: UnsignedTransaction = serde_json::from_slice(&tokio::fs::read(&file).await?)?;
+            // 署名する内容を確認できるように表示する（標準出力は署名済みトランザクションの出力先になりうる）
+            eprintln!("Signing {} on chain {}: {} -> {}, value {}, nonce {}, gas {} x {}, {} bytes of data",
+                unsigned.hash, unsigned.chain_id, addresses.encode(&unsigned.from)?, addresses.encode(&unsigned.to)?,
+                unsigned.value, unsigned.nonce, unsigned.gas_limit, unsigned.gas_price, unsigned.data.len() / 2);
+            let key = Keystore::new(std::path::Path::new(data_dir).join("keystore")).load(&unsigned.from).await?;
+            let signed = serde_json::to_string_pretty(&unsigned.sign(&key)?)?;
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, signed).await?;
+                    println!("{} Signed {} to {}", style("✓").green(), unsigned.hash, path.display());
+                }
+                None => println!("{}", signed),
+            }
+        }
+        TxCommand::Broadcast { file, endpoint } => {
+            let signed: SignedTransaction = serde_json::from_slice(&tokio::fs::read(&file).await?)?;
+            // 送信前に改ざんや壊れたファイルを検出する
+            let tx = signed.to_pending(0)?;
+            let chain_id = tx.chain_id
+                .ok_or_else(|| anyhow::anyhow!("{} does not specify a chain id", file.display()))?;
+            wallet::verify(&tx, chain_id)?;
+            let response = client
+                .post(format!("{}/transactions", endpoint.trim_end_matches('/')))
+                .json(&signed)
+                .send().await?;
+            check_response(response).await.with_context(|| format!("Node rejected {}", tx.hash))?;
+            println!("{} Broadcast {}", style("✓").green(), tx.hash);
+        }
+    }
+    Ok(())
+}
+
+/// バリデーターのコマンドを実行
+async fn run_validator_command(command: ValidatorCommand, data_dir: &str, addresses: &AddressFormat) -> Result<()> {
+    match command {
+        ValidatorCommand::Perf { window, endpoint, json } => {
+            let response = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()?
+                .get(format!("{}/validators/performance", endpoint.trim_end_matches('/')))
+                .query(&[("window", window.as_str()), ("limit", "1000")])
+                .send().await?;
+            let report: PerformanceReport = check_response(response).await?.json().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+            if report.validators.is_empty() {
+                println!("No validator activity in the last {}", report.window);
+                return Ok(());
+            }
+            println!("{:<42} {:>9} {:>7} {:>7} {:>7} {:>12} {:>12}",
+                "VALIDATOR", "PROPOSED", "MISSED", "MISS%", "VOTES", "AVG VOTE ms", "MAX VOTE ms");
+            let ms = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+            for v in &report.validators {
+                println!("{:<42} {:>9} {:>7} {:>6.2}% {:>7} {:>12} {:>12}",
+                    v.validator, v.proposed, v.missed, v.miss_rate * 100.0, v.votes,
+                    ms(v.avg_vote_latency_ms.map(|l| format!("{:.1}", l))),
+                    ms(v.max_vote_latency_ms.map(|l| l.to_string())));
+            }
+        }
+        ValidatorCommand::Register { key, stake, commission_bps, endpoint } => {
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()?;
+            let address = addresses.parse(&key)?;
+            let signing_key = Keystore::new(std::path::Path::new(data_dir).join("keystore")).load(&address).await?;
+            // 所有証明はノードのチェーンIDに対して作る
+            let (_, _, chain_id) = tx_params(&client, &endpoint, &address, None, Some(0), None, "standard").await?;
+            let registration = Registration {
+                consensus_key: address.clone(),
+                self_stake: stake,
+                commission_bps,
+                proof: registry::prove_possession(&signing_key, chain_id),
+            };
+            let response = client
+                .post(format!("{}/validators/candidates", endpoint.trim_end_matches('/')))
+                .json(&registration)
+                .send().await?;
+            let candidate: ValidatorCandidate = check_response(response).await
+                .context("Node rejected the registration")?
+                .json().await?;
+            println!("{} Registered validator {} with self-stake {} and commission {:.2}%",
+                style("✓").green(), addresses.encode(&candidate.address)?, candidate.self_stake,
+                candidate.commission_bps as f64 / 100.0);
+        }
+    }
+    Ok(())
+}
+
+/// 保守コマンドを実行
+async fn run_system_command(command: SystemCommand, config: &NodeConfig) -> Result<()> {
+    let backups = BackupManager::new(BackupConfig::new(&config.backup, &config.node.data_dir));
+    let open_storage = || RedbStorage::new(StorageConfig {
+        path: config.storage_path().to_string_lossy().to_string(),
+        ..Default::default()
+    });
+
+    match command {
+        SystemCommand::Backup { incremental, upload } => {
+            let storage = open_storage()?;
+            let kind = if incremental { BackupKind::Incremental } else { BackupKind::Full };
+            let manifest = backups.create(&storage, kind).await?;
+            backups.verify(&manifest.id).await?;
+            println!("{} {:?} backup {} ({} bytes, {} bytes stored)",
+                style("✓").green(), manifest.kind, manifest.id, manifest.total_size(), manifest.stored_bytes);
+            if upload {
+                backups.upload(&manifest.id).await?;
+                println!("{} Uploaded {}", style("✓").green(), manifest.id);
+            }
+        }
+        SystemCommand::Restore { id } => {
+            let id = match id {
+                Some(id) => id,
+                None => backups.list().await?.pop()
+                    .ok_or_else(|| anyhow::anyhow!("No backups found"))?
+                    .id,
+            };
+            let manifest = backups.restore(&id, &config.storage_path()).await?;
+            println!("{} Restored {} ({} bytes) to {}",
+                style("✓").green(), manifest.id, manifest.total_size(), config.storage_path().display());
+        }
+        SystemCommand::Verify { id } => {
+            let ids = match id {
+                Some(id) => vec![id],
+                None => backups.list().await?.into_iter().map(|b| b.id).collect(),
+            };
+            let mut failed = 0;
+            for id in ids {
+                match backups.verify(&id).await {
+                    Ok(_) => println!("{} {}", style("✓").green(), id),
+                    Err(e) => {
+                        failed += 1;
+                        println!("{} {}: {}", style("✗").red(), id, e);
+                    }
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!("{} backups failed verification", failed);
+            }
+        }
+        SystemCommand::Migrate { to, dry_run } => {
+            let migrator = Migrator::new(Arc::new(open_storage()?), migrations());
+            print_migrations(&migrator.migrate(to, dry_run).await?);
+            println!("Schema version: {}", migrator.current_version().await?);
+        }
+        SystemCommand::ExportGenesis { at_block, output } => {
+            let storage: Arc<dyn StorageEngine> = Arc::new(open_storage()?);
+            let chain = Chain::open(storage.clone()).await?;
+            // ビューを反映し直す一時的なストレージ（終了時に削除する）
+            let scratch_dir = config.node.data_dir.join(".genesis-export");
+            let _ = std::fs::remove_dir_all(&scratch_dir);
+            let scratch = Arc::new(RedbStorage::new(StorageConfig {
+                path: scratch_dir.to_string_lossy().to_string(),
+                ..Default::default()
+            })?);
+            let views = MaterializedViews::new(scratch).with_genesis(genesis::initial_state(&config.consensus)?);
+            let result = genesis::export(&chain, storage.as_ref(), views, at_block, config.consensus.chain_id).await;
+            let _ = std::fs::remove_dir_all(&scratch_dir);
+            let genesis = result?;
+            genesis.write(&output)?;
+            println!("{} Exported the state at block {} to {} ({} accounts, {} contracts, {} validators)",
+                style("✓").green(), genesis.exported_from.height, output.display(),
+                genesis.balances.len(), genesis.contracts.len(), genesis.validators.len());
+        }
+        SystemCommand::Rollback { to, dry_run } => {
+            let migrator = Migrator::new(Arc::new(open_storage()?), migrations());
+            print_migrations(&migrator.rollback(to, dry_run).await?);
+            println!("Schema version: {}", migrator.current_version().await?);
+        }
+        SystemCommand::Stop { .. } | SystemCommand::Restart { .. } | SystemCommand::Endpoints { .. } | SystemCommand::LogFilter { .. } => {
+            unreachable!("handled in run_command")
+        }
+        SystemCommand::Snapshot { output } => {
+            let bytes = open_storage()?.snapshot(&output).await?;
+            println!("{} Wrote snapshot {} ({} bytes)", style("✓").green(), output.display(), bytes);
+        }
+        SystemCommand::Backups => {
+            for backup in backups.list().await? {
+                println!("{}  {:<11}  {:>12} bytes  {:>12} stored",
+                    backup.id, format!("{:?}", backup.kind), backup.total_size(), backup.stored_bytes);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// マイグレーションの結果を表示
+fn print_migrations(reports: &[MigrationReport]) {
+    if reports.is_empty() {
+        println!("Nothing to do");
+    }
+    for report in reports {
+        let mark = if report.dry_run { style("[dry-run]").yellow() } else { style("✓").green() };
+        println!("{} v{} {} ({} puts, {} deletes)", mark, report.version, report.description, report.puts, report.deletes);
+    }
+}
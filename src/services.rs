@@ -1,36 +1,132 @@
 use std::sync::Arc;
 use anyhow::Result;
-use tracing::{info, error};
+use tracing::{debug, info, warn, error};
 use crate::{
     config::NodeConfig,
-    web::WebServer,
+    i18n::LocaleConfig,
+    util::{endpoints::{Endpoint, ManifestFile, ServiceManifest}, log_filter::LogFilter},
+    web::{
+        self, AppState, WebServer, auth::PasskeyAuth, geo::GeoProxy, idempotency::IdempotencyCache,
+        mitigation::RpcPause, replica::TxForwarder,
+    },
     core::{
-        storage::redb_storage::{RedbStorage, StorageConfig},
-        network::quic::QuicNetwork,
-        ai::AiOptimizer,
+        blob::{self, BlobStore},
+        block::{Chain, limits::ConsensusParams, relay::BlockRelay, replica::BlockFollower},
+        cache::MaterializedViews,
+        fees::FeeOracle,
+        genesis,
+        consensus::{messages::Proposal, performance::PerformanceTracker, registry::ValidatorRegistry, safety::SafetyRules, shadow::ShadowValidator},
+        coordination::{BeaconChain, BeaconOp},
+        telemetry::TelemetryReporter,
+        transaction::ChainSink,
+        wallet::{self, AddressFormat, Keystore},
+        watchlist::Watchlist,
+        storage::{
+            StorageEngine,
+            backup::{BackupConfig, BackupManager},
+            migration::{Migrator, migrations},
+            redb_storage::{RedbStorage, StorageConfig},
+            watchdog::DiskWatchdog,
+        },
+        contract::{metrics::ContractMetrics, AnalysisConfig, CompilerMatrix, ContractVerifier, DeploymentLedger, ProxyRegistry},
+        sharding::{ShardManager, rebalance::RebalanceConfig},
+        network::{chaos::ChaosConfig, diversity::DiversityPolicy, quic::QuicNetwork, roles::NodeRole, seeds::PeeringConfig, sentry::SentryConfig},
+        ai::{AiConfig, AiOptimizer, SnapshotHook},
+        mempool::{self, AccessPolicy, Mempool, MempoolConfig},
+        htlc::HtlcLedger,
+        names::NameRegistry,
+        vesting::VestingLedger,
     },
 };
-use tokio::sync::Mutex;
+#[cfg(feature = "confidential-tx")]
+use crate::core::confidential::ConfidentialLedger;
+#[cfg(feature = "sql")]
+use crate::core::columnar::{ColumnarIndex, sql::SqlEngine};
+#[cfg(feature = "export")]
+use crate::core::columnar::export::Exporter;
+#[cfg(feature = "grpc")]
+use crate::web::grpc;
+use tokio::sync::{Mutex, RwLock};
+
+/// 開発モードで1ブロックに含める最大トランザクション数
+const MAX_BLOCK_TXS: usize = 1000;
+/// 期限切れのトランザクションを取り除く間隔
+const EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// ブロック生成時にトランザクションを絞り込むサービス
+#[derive(Clone)]
+struct BlockFilters {
+    /// サイドカーを保持していないブロブのトランザクションを除く
+    blobs: Arc<BlobStore>,
+    /// 適用できない名前の操作を除く
+    names: Arc<NameRegistry>,
+    /// 適用できない HTLC の操作を除く
+    htlc: Arc<HtlcLedger>,
+    /// 静的解析で拒否されるデプロイを除く
+    deployments: Arc<DeploymentLedger>,
+    /// 権限のないプロキシの登録・アップグレードを除く
+    proxies: Arc<ProxyRegistry>,
+    /// ロック中の残高を使うトランザクションを除く
+    vesting: Arc<VestingLedger>,
+    /// 残高を超える送金を除く
+    views: Arc<MaterializedViews>,
+    /// シャードで確定済みのノンスを再び使うトランザクションを除く
+    shards: Arc<RwLock<ShardManager>>,
+    /// 検証に失敗する機密トランザクションを除く
+    #[cfg(feature = "confidential-tx")]
+    confidential: Arc<ConfidentialLedger>,
+}
 
 /// サービスマネージャー
 pub struct ServiceManager {
     config: NodeConfig,
     storage: Option<Arc<RedbStorage>>,
     network: Option<Arc<QuicNetwork>>,
-    web_server: Option<WebServer>,
+    /// Web UI・API・WebSocketのサーバー
+    web_servers: Vec<WebServer>,
+    /// 待ち受けているアドレス（起動後）
+    manifest: Option<ServiceManifest>,
+    /// データディレクトリに書き出したマニフェスト（停止時に削除する）
+    manifest_file: Option<ManifestFile>,
     ai_optimizer: Option<Arc<Mutex<AiOptimizer>>>,
+    mempool: Arc<RwLock<Mempool>>,
+    /// 表示言語
+    locale: Arc<LocaleConfig>,
+    /// 実行中に変更できるログのフィルター
+    log_filter: Option<LogFilter>,
 }
 
 impl ServiceManager {
     /// 新しいサービスマネージャーを作成
-    pub fn new(config: NodeConfig) -> Self {
-        Self {
+    ///
+    /// アクセスリストのファイルを読み込めない場合は起動しません（許可リストが空のまま、
+    /// または拒否リストなしで受け付けを始めないため）。
+    pub fn new(config: NodeConfig) -> Result<Self> {
+        let mut access = AccessPolicy::new(config.mempool.access_list_mode);
+        if let Some(path) = &config.mempool.access_list_path {
+            access.load_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load access list from {}: {}", path.display(), e))?;
+        }
+        let mempool = Mempool::new(MempoolConfig::from(&config.mempool))
+            .with_access_policy(access)
+            .with_chain_id(config.consensus.chain_id)
+            .with_allow_unsigned(config.dev.allow_unsigned);
+        if config.dev.allow_unsigned {
+            warn!("dev.allow_unsigned is set: unsigned transactions are accepted and committed");
+        }
+        let locale = Arc::new(LocaleConfig::from_settings(&config.i18n));
+        Ok(Self {
             config,
             storage: None,
             network: None,
-            web_server: None,
+            web_servers: Vec::new(),
+            manifest: None,
+            manifest_file: None,
             ai_optimizer: None,
-        }
+            mempool: Arc::new(RwLock::new(mempool)),
+            locale,
+            log_filter: None,
+        })
     }
 
     /// ストレージエンジンを設定
@@ -43,11 +139,26 @@ impl ServiceManager {
         self.ai_optimizer = Some(optimizer);
     }
 
+    /// ログのフィルターを設定
+    pub fn set_log_filter(&mut self, filter: LogFilter) {
+        self.log_filter = Some(filter);
+    }
+
     /// 設定を取得
     pub fn config(&self) -> &NodeConfig {
         &self.config
     }
 
+    /// 待ち受けているアドレス（起動前は `None`）
+    pub fn endpoints(&self) -> Option<&ServiceManifest> {
+        self.manifest.as_ref()
+    }
+
+    /// 表示言語を取得
+    pub fn locale(&self) -> &Arc<LocaleConfig> {
+        &self.locale
+    }
+
     /// ピア数を取得
     pub async fn get_peer_count(&self) -> u32 {
         // TODO: 実際のP2Pネットワークからピア数を取得
@@ -74,7 +185,12 @@ impl ServiceManager {
     }
 
     /// サービスを起動
+    ///
+    /// フィクスチャモード（`dev.fixture`）では、AI最適化エンジン・ブロックの生成と中継・同期を起動せず、
+    /// P2Pネットワークはループバックで待ち受けるだけにします。
     pub async fn start(&mut self) -> Result<()> {
+        let fixture = self.config.dev.fixture.is_some();
+
         // データディレクトリを作成
         tokio::fs::create_dir_all(&self.config.node.data_dir).await?;
 
@@ -82,11 +198,7 @@ impl ServiceManager {
         if let Some(storage) = &self.storage {
             info!("Storage engine initialized");
         } else {
-            let storage_path = if self.config.storage.path.as_os_str().is_empty() {
-                self.config.node.data_dir.join("storage")
-            } else {
-                self.config.storage.path.clone()
-            };
+            let storage_path = self.config.storage_path();
             tokio::fs::create_dir_all(&storage_path).await?;
             let storage_config = StorageConfig {
                 path: storage_path.to_string_lossy().to_string(),
@@ -103,81 +215,572 @@ impl ServiceManager {
         // AI最適化エンジンの初期化確認
         if let Some(optimizer) = &self.ai_optimizer {
             info!("AI optimization engine initialized");
-        } else {
-            let optimizer = Arc::new(Mutex::new(AiOptimizer::new()));
+        } else if !fixture {
+            let optimizer = Arc::new(Mutex::new(AiOptimizer::new(AiConfig::from(&self.config.ai), &self.config.node.data_dir)?));
             self.ai_optimizer = Some(optimizer);
             info!("AI optimization engine initialized");
         }
 
         // QUICネットワークを初期化
         info!("Initializing QUIC network...");
+        let listen_addr = if fixture {
+            std::net::SocketAddr::from(([127, 0, 0, 1], 0))
+        } else {
+            self.config.bind_addr(None, 0)?
+        };
+        // フィクスチャモードのP2Pは外部に公開しない
+        let mut endpoints = Vec::new();
+        if !fixture {
+            endpoints.push(Endpoint::new("p2p", "quic", listen_addr, ""));
+        }
         let network_config = crate::core::network::quic::NetworkConfig {
-            listen_addr: format!("0.0.0.0:{}", self.config.network.port).parse()?,
+            listen_addr,
             bootstrap_nodes: self.config.network.bootstrap_nodes.clone(),
             max_concurrent_streams: 1000,
             keep_alive_interval: std::time::Duration::from_secs(10),
             handshake_timeout: std::time::Duration::from_secs(10),
             idle_timeout: std::time::Duration::from_secs(30),
+            gossip: (&self.config.network.gossip).into(),
+            peers: (&self.config.network.peers).into(),
+            diversity: DiversityPolicy::from_settings(&self.config.network.diversity)?,
+            sentry: SentryConfig::from_settings(&self.config.network.sentry)?,
+            peering: PeeringConfig::from_settings(&self.config.network.peering, self.config.network.port)?,
+            chaos: ChaosConfig::from_settings(&self.config.dev.chaos)?,
+            role: NodeRole::from_config(&self.config.node.role),
         };
         let network = Arc::new(QuicNetwork::new(network_config).await?);
         self.network = Some(network.clone());
 
+        // 障害予測の緩和フックとピアの観測
+        let rpc_pause = RpcPause::default();
+        if let Some(optimizer) = &self.ai_optimizer {
+            let mut ai = optimizer.lock().await;
+            ai.register_mitigation(Arc::new(rpc_pause.clone()));
+            if let Some(storage) = &self.storage {
+                ai.register_mitigation(Arc::new(SnapshotHook::new(
+                    storage.clone(),
+                    self.config.node.data_dir.join("snapshots"),
+                )));
+            }
+            drop(ai);
+            self.spawn_peer_observer(network.clone(), optimizer.clone());
+        }
+
+        // チェーンを開き、ブロックの確定を購読するサービスを起動
+        let storage: Arc<dyn StorageEngine> = self.storage.clone()
+            .ok_or_else(|| anyhow::anyhow!("Storage engine is not initialized"))?;
+        Migrator::new(storage.clone(), migrations()).migrate(None, false).await?;
+        let mut chain = Chain::open(storage.clone()).await?
+            .with_params(ConsensusParams::from(&self.config.consensus))
+            .with_allow_unsigned(self.config.dev.allow_unsigned);
+        // ディスクが満杯になる前にブロックの確定を止める
+        let disk = if self.config.disk.enabled {
+            let watchdog = Arc::new(DiskWatchdog::new(&self.config.disk, &self.config.node.data_dir, self.storage.clone()));
+            chain = chain.with_disk_guard(watchdog.guard());
+            let guard = watchdog.guard();
+            watchdog.spawn();
+            Some(guard)
+        } else {
+            None
+        };
+        let chain = Arc::new(chain);
+        // 読み取り専用レプリカはブロックを生成せず、上流から同期する（チェックポイントはビューより先に反映する）
+        let follower = if self.config.is_rpc_replica() && self.config.dev.fixture.is_none() {
+            let follower = Arc::new(BlockFollower::new(&self.config.replica)?);
+            follower.bootstrap(&chain).await?;
+            Some(follower)
+        } else if self.config.replica.trust_checkpoint.is_some() {
+            anyhow::bail!("replica.trust_checkpoint only applies to the rpc-replica role");
+        } else {
+            None
+        };
+        let views = Arc::new(MaterializedViews::new(storage.clone())
+            .with_genesis(genesis::initial_state(&self.config.consensus)?));
+        views.clone().spawn(chain.clone());
+        let watchlist = Arc::new(Watchlist::new(storage.clone()));
+        watchlist.load().await?;
+        watchlist.clone().spawn(chain.clone());
+        let performance = Arc::new(PerformanceTracker::new());
+        performance.clone().spawn(chain.clone());
+        let contract_metrics = Arc::new(ContractMetrics::new());
+        contract_metrics.clone().spawn(chain.clone());
+        let fees = Arc::new(FeeOracle::new());
+        fees.clone().spawn(chain.clone());
+        let blobs = Arc::new(BlobStore::new(storage.clone(), self.config.blobs.clone()));
+        blobs.clone().spawn(chain.clone());
+        let names = Arc::new(NameRegistry::new(storage.clone()));
+        names.clone().spawn(chain.clone());
+        let htlc = Arc::new(HtlcLedger::new(storage.clone()));
+        htlc.clone().spawn(chain.clone());
+        let deployments = Arc::new(DeploymentLedger::new(
+            storage.clone(),
+            AnalysisConfig::from(&self.config.contracts),
+        ));
+        deployments.clone().spawn(chain.clone());
+        let proxies = Arc::new(ProxyRegistry::new(storage.clone(), deployments.clone()));
+        proxies.clone().spawn(chain.clone());
+        let vesting = Arc::new(VestingLedger::new(storage.clone(), views.clone()));
+        vesting.clone().spawn(chain.clone());
+        chain.add_rule(vesting.clone());
+        let beacon = Arc::new(self.beacon(storage.clone()).await?);
+        let relay_beacon = self.config.beacon.validators.len() > 1;
+        // シャードのノンスドメインは確定前の検証に使うため、Web UI の有無に関わらず起動する
+        let shards = self.shards(storage.clone(), beacon.clone(), chain.clone());
+        let validators = Arc::new(ValidatorRegistry::new(
+            storage.clone(),
+            vesting.clone(),
+            self.config.consensus.chain_id,
+            self.config.validator.min_stake,
+        ));
+        #[cfg(feature = "confidential-tx")]
+        let confidential = {
+            info!("Confidential transactions are enabled (experimental)");
+            let ledger = Arc::new(ConfidentialLedger::new(storage.clone()));
+            ledger.clone().spawn(chain.clone());
+            ledger
+        };
+        #[cfg(feature = "sql")]
+        let sql = if self.config.sql.enabled {
+            info!("Writing columnar chain data for SQL queries");
+            let index = Arc::new(ColumnarIndex::new(
+                self.config.node.data_dir.join("columnar"),
+                self.config.sql.segment_blocks,
+            ));
+            index.clone().spawn(chain.clone());
+            Some(Arc::new(SqlEngine::new(index, self.config.sql.clone())))
+        } else {
+            None
+        };
+        let filters = BlockFilters {
+            blobs: blobs.clone(),
+            names: names.clone(),
+            htlc: htlc.clone(),
+            deployments: deployments.clone(),
+            proxies: proxies.clone(),
+            vesting: vesting.clone(),
+            views: views.clone(),
+            shards: shards.clone(),
+            #[cfg(feature = "confidential-tx")]
+            confidential: confidential.clone(),
+        };
+        if self.config.streaming.enabled {
+            info!("Starting chain data stream...");
+            let sink = ChainSink::new(&self.config.streaming, storage.clone()).await?;
+            Arc::new(sink).spawn(chain.clone());
+        }
+        #[cfg(feature = "export")]
+        if self.config.export.enabled {
+            info!("Exporting chain data every {} seconds", self.config.export.interval);
+            let exporter = Exporter::new(&self.config.export, &self.config.node.data_dir, storage.clone())?;
+            Arc::new(exporter).spawn(chain.clone());
+        }
+        self.spawn_expiry();
+        let mut shadow = None;
+        if let Some(snapshot) = &self.config.dev.fixture {
+            info!("Serving fixture snapshot {} (no consensus or block production)", snapshot.display());
+        } else if let Some(follower) = follower {
+            info!("Running as RPC replica of {}", self.config.replica.upstream);
+            follower.spawn(chain.clone());
+        } else {
+            let mut relay = BlockRelay::new(
+                &self.config.network.relay,
+                chain.clone(),
+                self.mempool.clone(),
+                network.clone(),
+            );
+            if relay_beacon {
+                relay = relay.with_beacon(beacon.clone());
+            }
+            Arc::new(relay).spawn().await;
+            if self.config.validator.shadow {
+                // 担当スロットでもブロックを確定・送信しない
+                let signing_key = self.signing_key().await?;
+                let validator = self.validator_name(signing_key.as_ref());
+                let shadow_validator = Arc::new(ShadowValidator::new(
+                    validator,
+                    signing_key,
+                    chain.params().clone(),
+                    self.mempool.clone(),
+                    MAX_BLOCK_TXS,
+                ));
+                shadow_validator.clone().spawn(chain.clone());
+                shadow = Some(shadow_validator);
+            } else if self.config.dev.auto_mining {
+                self.spawn_block_producer(storage.clone(), chain.clone(), filters).await?;
+            }
+        }
+        if self.config.telemetry.enabled {
+            let reporter = TelemetryReporter::new(
+                &self.config.telemetry,
+                &self.config.node.data_dir,
+                self.config.consensus.chain_id,
+                &self.config.node.role,
+            ).await?;
+            Arc::new(reporter).spawn(chain.clone(), network.clone());
+        }
+        if self.config.backup.enabled {
+            if let Some(redb) = &self.storage {
+                info!("Starting backup scheduler...");
+                let config = BackupConfig::new(&self.config.backup, &self.config.node.data_dir);
+                Arc::new(BackupManager::new(config)).spawn(redb.clone());
+            }
+        }
+
         // Web UIサーバーを起動
         if self.config.web.enabled {
             info!("Starting Web UI server...");
 
-            // ダッシュボード
-            let web_server = WebServer::new(
-                self.config.network.port,  // 9070
-                self.config.clone(),
+            let compilers = CompilerMatrix::new(
+                &self.config.contracts.compilers_dir,
+                std::time::Duration::from_secs(self.config.contracts.compile_timeout),
             );
-            self.web_server = Some(web_server.clone());
-            tokio::spawn(async move {
-                if let Err(e) = web_server.run().await {
-                    error!("Web server error: {}", e);
+
+            let auth = if self.config.api.webauthn.enabled {
+                Some(Arc::new(PasskeyAuth::from_settings(&self.config.api.webauthn, storage.clone())?))
+            } else {
+                None
+            };
+            let state = AppState {
+                config: Arc::new(self.config.clone()),
+                mempool: self.mempool.clone(),
+                contracts: Arc::new(
+                    ContractVerifier::new(Arc::new(compilers), storage.clone())
+                        .with_max_concurrent(self.config.contracts.max_concurrent_verifications),
+                ),
+                proxies,
+                shards: shards.clone(),
+                beacon,
+                chain,
+                views,
+                watchlist,
+                performance,
+                contract_metrics,
+                shadow,
+                fees,
+                blobs,
+                names,
+                htlc,
+                deployments,
+                vesting,
+                #[cfg(feature = "confidential-tx")]
+                confidential,
+                #[cfg(feature = "sql")]
+                sql,
+                network: network.clone(),
+                ai: self.ai_optimizer.clone(),
+                rpc_pause,
+                disk,
+                geo: if self.config.geo.enabled {
+                    Some(Arc::new(GeoProxy::from_settings(&self.config.geo)?))
+                } else {
+                    None
+                },
+                forwarder: if self.config.is_rpc_replica() {
+                    Some(Arc::new(TxForwarder::new(&self.config.replica)?))
+                } else {
+                    None
+                },
+                auth,
+                locale: self.locale.clone(),
+                log_filter: self.log_filter.clone(),
+                addresses: AddressFormat::new(&self.config.network.address_prefix)?,
+                idempotency: Arc::new(IdempotencyCache::new(&self.config.api.idempotency)),
+                validators,
+            };
+
+            // ダッシュボード・API・WebSocket・gRPC のサーバー
+            // 全てのアドレスで待ち受けてから起動し、ポートの競合は起動のエラーにする
+            #[allow(unused_mut)]
+            let mut servers = vec![
+                ("web", true, self.config.web.bind.as_deref(), self.config.web.port_offset, "http", ""),
+                ("api", self.config.api.enabled, self.config.api.bind.as_deref(), self.config.api.port_offset, "http", "/api"),
+                ("websocket", self.config.websocket.enabled, self.config.websocket.bind.as_deref(), self.config.websocket.port_offset, "ws", "/ws"),
+            ];
+            #[cfg(feature = "grpc")]
+            servers.push(("grpc", self.config.grpc.enabled, self.config.grpc.bind.as_deref(), self.config.grpc.port_offset, "http", ""));
+            let mut listeners = Vec::new();
+            for (name, enabled, bind, port_offset, scheme, path) in servers {
+                if !enabled {
+                    continue;
+                }
+                let addr = self.config.bind_addr(bind, port_offset)?;
+                if let Some(other) = endpoints.iter().find(|e| e.name != "p2p" && e.address == addr) {
+                    anyhow::bail!("The {} and {} servers are both configured to listen on {}; give them different port offsets",
+                        other.name, name, addr);
                 }
-            });
+                let listener = web::listen(name, addr).await?;
+                endpoints.push(Endpoint::new(name, scheme, listener.local_addr()?, path));
+                listeners.push((name, listener));
+            }
+            for (name, listener) in listeners {
+                #[cfg(feature = "grpc")]
+                if name == "grpc" {
+                    let service = grpc::ChainStreamService::new(state.chain.clone(), &self.config.grpc);
+                    tokio::spawn(async move {
+                        if let Err(e) = grpc::serve(service, listener).await {
+                            error!("grpc server error: {}", e);
+                        }
+                    });
+                    continue;
+                }
+                let server = WebServer::new(state.clone());
+                self.web_servers.push(server.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = server.run(listener).await {
+                        error!("{} server error: {}", name, e);
+                    }
+                });
+            }
+            info!("Web UI server started");
+        }
 
-            // APIサーバー
-            let api_server = WebServer::new(
-                self.config.network.port + 1,  // 9071
-                self.config.clone(),
-            );
-            tokio::spawn(async move {
-                if let Err(e) = api_server.run().await {
-                    error!("API server error: {}", e);
+        // 待ち受けているアドレスをデータディレクトリに書き出す
+        let manifest = ServiceManifest::new(&self.config.node.name, endpoints);
+        for endpoint in &manifest.endpoints {
+            info!("{} listening on {} ({})", endpoint.name, endpoint.address, endpoint.url);
+        }
+        self.manifest_file = Some(manifest.write(&self.config.node.data_dir)?);
+        self.manifest = Some(manifest);
+
+        Ok(())
+    }
+
+    /// ビーコンチェーンを開く
+    ///
+    /// バリデーターが複数の場合は提案と投票をノード間で中継するため、バリデーターは署名鍵の
+    /// アドレスで指定します。`validator.signing_key` の鍵のアドレスがこのノードのバリデーター名になり、
+    /// 鍵がなければ確定したブロックを受け取るだけのノードとして動作します。
+    async fn beacon(&self, storage: Arc<dyn StorageEngine>) -> Result<BeaconChain> {
+        let validators = &self.config.beacon.validators;
+        if validators.len() <= 1 {
+            return BeaconChain::open(storage, &self.config.node.name, validators.clone()).await;
+        }
+        let is_address = |v: &String| v.len() == 64 && v.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+        if let Some(invalid) = validators.iter().find(|v| !is_address(v)) {
+            anyhow::bail!("beacon.validators must list validator addresses (64 lowercase hex characters) when there are several; got {}", invalid);
+        }
+        Ok(match self.signing_key().await? {
+            Some(key) => {
+                let address = wallet::address_of(&key.verifying_key());
+                if !validators.contains(&address) {
+                    info!("Signing key {} is not a beacon validator; following the beacon chain only", address);
                 }
-            });
+                BeaconChain::open(storage, &address, validators.clone()).await?.with_signing_key(key)
+            }
+            None => {
+                info!("validator.signing_key is not set; following the beacon chain without voting");
+                BeaconChain::open(storage, &self.config.node.name, validators.clone()).await?
+            }
+        })
+    }
 
-            // WebSocketサーバー
-            let ws_server = WebServer::new(
-                self.config.network.port + 2,  // 9072
-                self.config.clone(),
-            );
-            tokio::spawn(async move {
-                if let Err(e) = ws_server.run().await {
-                    error!("WebSocket server error: {}", e);
+    /// シャードマネージャーを作成し、スケーリング・再分配・チェックポイントとビーコンチェーンを定期実行
+    ///
+    /// ノンスドメインの検証はブロックの確定前の検証に追加します。
+    /// シャードの追加はビーコンチェーンへ提案し、確定した構成をビーコンチェーンから反映します。
+    fn shards(&self, storage: Arc<dyn StorageEngine>, beacon: Arc<BeaconChain>, chain: Arc<Chain>) -> Arc<RwLock<ShardManager>> {
+        let shards = Arc::new(RwLock::new(
+            ShardManager::new(storage)
+                .with_rebalance_config(RebalanceConfig::from(&self.config.sharding)),
+        ));
+
+        let interval = std::time::Duration::from_secs(self.config.sharding.rebalance_interval.max(1));
+        let manager = shards.clone();
+        let coordinator = beacon.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match manager.write().await.check_and_scale().await {
+                    Ok(proposed) => {
+                        for shard in proposed {
+                            coordinator.submit(BeaconOp::AddShard { shard }).await;
+                        }
+                    }
+                    Err(e) => error!("Shard scaling check failed: {}", e),
+                }
+                if let Err(e) = manager.read().await.run_pending_rebalance().await {
+                    error!("Shard rebalance failed: {}", e);
                 }
-            });
+            }
+        });
 
-            // サーバーの起動を待機（実際のリクエストで確認する方が望ましい）
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            info!("Web UI server started");
-        }
+        let interval = std::time::Duration::from_secs(self.config.sharding.checkpoint_interval.max(1));
+        let manager = shards.clone();
+        let coordinator = beacon.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match manager.read().await.checkpoint_shards().await {
+                    Ok(checkpoints) => {
+                        for checkpoint in checkpoints {
+                            debug!(
+                                "Shard {} checkpoint #{} with {} receipts",
+                                checkpoint.shard, checkpoint.sequence, checkpoint.receipt_count
+                            );
+                            coordinator.submit(BeaconOp::Checkpoint { checkpoint }).await;
+                        }
+                    }
+                    Err(e) => error!("Shard checkpoint failed: {}", e),
+                }
+            }
+        });
+
+        chain.add_rule(shards.clone());
+        ShardManager::spawn(shards.clone(), chain);
+        beacon.spawn(shards.clone(), std::time::Duration::from_secs(self.config.beacon.block_interval.max(1)));
+        shards
+    }
 
+    /// 接続中のピアを定期的に障害予測へ渡す
+    fn spawn_peer_observer(&self, network: Arc<QuicNetwork>, optimizer: Arc<Mutex<AiOptimizer>>) {
+        let interval = std::time::Duration::from_secs(self.config.ai.interval.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let peers = network.connected_peers().await.iter().map(|p| p.to_string()).collect();
+                optimizer.lock().await.observe_peers(peers);
+            }
+        });
+    }
+
+    /// 期限切れのトランザクションを定期的にメモリプールから取り除く
+    fn spawn_expiry(&self) {
+        let mempool = self.mempool.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(EXPIRY_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let evicted = mempool.write().await.evict_expired(now);
+                if evicted > 0 {
+                    info!("Evicted {} expired transactions from the mempool", evicted);
+                }
+            }
+        });
+    }
+
+    /// 開発モードのブロック生成
+    ///
+    /// 一定間隔でメモリプールからトランザクションを取り出し、ブロックとして確定します。
+    /// ガス価格が基本手数料を下回るトランザクション（と同じ送信者の後続のもの）はメモリプールに残します。
+    /// ブロブ手数料を払えないもの、サイドカーを保持していないもの、適用できない名前の操作、
+    /// 検証に失敗する機密トランザクションも同様です。
+    /// `validator.signing_key` の鍵のアドレスを生成者としてブロックに署名し、同じ鍵で乱数ビーコンの VRF 証明を付けます。
+    /// 署名のないブロックは確定できないため、鍵を設定していない場合は起動ごとに生成する一時的な鍵を使います。
+    /// 署名の前に安全性の状態（最後に署名した高さとラウンド）を保存し、再起動後も確定済みの高さ以下の
+    /// ブロックや同じラウンドの別のブロックには署名しません（状態を読めない場合は起動しない）。
+    /// 確定できなかった高さで作り直す場合は、ラウンドを進めて署名します。
+    async fn spawn_block_producer(&self, storage: Arc<dyn StorageEngine>, chain: Arc<Chain>, filters: BlockFilters) -> Result<()> {
+        let mempool = self.mempool.clone();
+        let signing_key = match self.signing_key().await? {
+            Some(key) => key,
+            None => {
+                warn!("validator.signing_key is not set; signing blocks with an ephemeral key");
+                ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng)
+            }
+        };
+        let validator = self.validator_name(Some(&signing_key));
+        let mut safety = SafetyRules::open(storage, &validator).await?;
+        if let Some(vote) = &safety.state().last_vote {
+            info!("Restored consensus safety state: last signed block at height {} round {}", vote.height, vote.round);
+        }
+        let interval = std::time::Duration::from_millis(self.config.dev.block_time.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                // ディスクの空きが少ない間はメモリプールから取り出さない
+                if chain.check_disk().is_err() {
+                    continue;
+                }
+                let gas_limit = chain.next_gas_limit().await;
+                let max_bytes = chain.params().transaction_bytes();
+                let mut txs = mempool.write().await.select_within(MAX_BLOCK_TXS, gas_limit, max_bytes);
+                mempool::retain_payable(&mut txs, chain.next_base_fee().await);
+                filters.blobs.retain_available(&mut txs).await;
+                blob::retain_blobs(&mut txs, chain.next_blob_fee().await, chain.params().max_blob_bytes);
+                filters.names.retain_valid(&mut txs).await;
+                filters.htlc.retain_valid(&mut txs).await;
+                filters.deployments.retain_valid(&mut txs).await;
+                filters.proxies.retain_valid(&mut txs).await;
+                filters.vesting.retain_valid(&mut txs).await;
+                filters.views.retain_funded(&mut txs).await;
+                filters.shards.read().await.retain_valid(&mut txs).await;
+                #[cfg(feature = "confidential-tx")]
+                filters.confidential.retain_valid(&mut txs).await;
+                if txs.is_empty() {
+                    continue;
+                }
+                let block = chain.next_block(validator.clone(), txs).await;
+                let block = chain.prove_randomness(block, &signing_key).await;
+                let round = match &safety.state().last_vote {
+                    Some(last) if last.height == block.height => last.round + 1,
+                    _ => 0,
+                };
+                let proposal = Proposal {
+                    height: block.height,
+                    round,
+                    block_hash: block.hash.clone(),
+                    parent_hash: block.parent_hash.clone(),
+                    proposer: validator.clone(),
+                    justify: None,
+                };
+                if let Err(e) = safety.vote(&proposal).await {
+                    error!("Refusing to sign block {}: {}", block.height, e);
+                    continue;
+                }
+                let block = block.sign(&signing_key);
+                let hashes: Vec<String> = block.transactions.iter().map(|tx| tx.hash.clone()).collect();
+                match chain.commit(block).await {
+                    Ok(()) => {
+                        let mut mempool = mempool.write().await;
+                        for hash in &hashes {
+                            mempool.remove(hash);
+                        }
+                    }
+                    Err(e) => error!("Failed to commit block: {}", e),
+                }
+            }
+        });
         Ok(())
     }
 
+    /// `validator.signing_key` の鍵をキーストアから読み込む
+    async fn signing_key(&self) -> Result<Option<ed25519_dalek::SigningKey>> {
+        Ok(match &self.config.validator.signing_key {
+            Some(address) => Some(Keystore::new(self.config.node.data_dir.join("keystore")).load(address).await?),
+            None => None,
+        })
+    }
+
+    /// ブロックの生成者として使う名前（署名鍵のアドレス、なければノード名）
+    fn validator_name(&self, signing_key: Option<&ed25519_dalek::SigningKey>) -> String {
+        match signing_key {
+            Some(key) => wallet::address_of(&key.verifying_key()),
+            None => self.config.node.name.clone(),
+        }
+    }
+
     /// サービスを停止
     pub async fn stop(&mut self) -> Result<()> {
         info!("Stopping services...");
 
         // 各サービスを停止
-        if let Some(web_server) = self.web_server.take() {
+        if !self.web_servers.is_empty() {
             info!("Stopping Web UI server...");
-            web_server.shutdown();
+            for web_server in self.web_servers.drain(..) {
+                web_server.shutdown();
+            }
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         }
+        self.manifest = None;
+        self.manifest_file = None;
 
         if let Some(network) = self.network.take() {
             info!("Stopping P2P network...");
@@ -210,4 +813,9 @@ impl ServiceManager {
     pub fn ai_optimizer(&self) -> Option<&Arc<Mutex<AiOptimizer>>> {
         self.ai_optimizer.as_ref()
     }
+
+    // メモリプールへのアクセス
+    pub fn mempool(&self) -> &Arc<RwLock<Mempool>> {
+        &self.mempool
+    }
 }
@@ -0,0 +1,177 @@
+//! cgroup v2 のリソース制限
+//!
+//! コンテナ（Docker・Kubernetes・systemd のスライス）の中では、ホスト全体のCPU数とメモリ量は
+//! ノードが実際に使える量より大きくなります。`/proc/self/cgroup` からノードの cgroup を見つけ、
+//! `cpu.max`・`cpuset.cpus.effective`・`memory.max` の制限と、`cpu.stat`・`memory.current` の
+//! 使用量を読みます。祖先の cgroup の制限（Kubernetes のPodなど）も適用されるため、ルートまでで
+//! 最も小さい制限を使います。cgroup v1 と Linux 以外では制限なしとして扱います。
+
+use std::path::{Path, PathBuf};
+
+/// cgroup v2 のマウントポイント
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// ノードのプロセスが属する cgroup
+#[derive(Debug, Clone)]
+pub struct Cgroup {
+    root: PathBuf,
+    dir: PathBuf,
+}
+
+impl Cgroup {
+    /// 現在のプロセスの cgroup（cgroup v2 でない場合は `None`）
+    pub fn detect() -> Option<Self> {
+        let membership = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+        Self::from_membership(Path::new(CGROUP_ROOT), &membership)
+    }
+
+    /// `/proc/self/cgroup` の内容から、`root` にマウントした階層の cgroup を求める
+    fn from_membership(root: &Path, membership: &str) -> Option<Self> {
+        if !root.join("cgroup.controllers").exists() {
+            return None;
+        }
+        // cgroup v2 の行は `0::<パス>` の1行のみ
+        let path = membership.lines().find_map(|line| line.strip_prefix("0::"))?;
+        let dir = root.join(path.trim().trim_start_matches('/'));
+        // cgroup 名前空間の中ではパスが見えないことがあり、その場合はマウントしたルートが自身の cgroup
+        let dir = if dir.join("cgroup.controllers").exists() { dir } else { root.to_path_buf() };
+        Some(Self { root: root.to_path_buf(), dir })
+    }
+
+    /// 自身からルートまでの cgroup
+    fn ancestors(&self) -> impl Iterator<Item = &Path> {
+        self.dir.ancestors().take_while(|dir| dir.starts_with(&self.root))
+    }
+
+    fn read(dir: &Path, file: &str) -> Option<String> {
+        std::fs::read_to_string(dir.join(file)).ok()
+    }
+
+    /// 使えるCPU数（`cpu.max` のクォータと `cpuset` のCPU数の小さい方、制限がなければ `None`）
+    pub fn cpus(&self) -> Option<f64> {
+        let quota = self.ancestors()
+            .filter_map(|dir| Self::read(dir, "cpu.max").and_then(|max| parse_cpu_max(&max)))
+            .min_by(f64::total_cmp);
+        let cpuset = Self::read(&self.dir, "cpuset.cpus.effective")
+            .and_then(|cpus| parse_cpu_list(&cpus))
+            .map(|count| count as f64);
+        match (quota, cpuset) {
+            (Some(quota), Some(cpuset)) => Some(quota.min(cpuset)),
+            (quota, cpuset) => quota.or(cpuset),
+        }
+    }
+
+    /// メモリの上限（バイト、制限がなければ `None`）
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.ancestors()
+            .filter_map(|dir| Self::read(dir, "memory.max").and_then(|max| max.trim().parse().ok()))
+            .min()
+    }
+
+    /// 使用中のメモリ（バイト、回収できるページキャッシュを除く）
+    pub fn memory_used(&self) -> Option<u64> {
+        let current: u64 = Self::read(&self.dir, "memory.current")?.trim().parse().ok()?;
+        let inactive_file = Self::read(&self.dir, "memory.stat")
+            .and_then(|stat| stat_value(&stat, "inactive_file"))
+            .unwrap_or(0);
+        Some(current.saturating_sub(inactive_file))
+    }
+
+    /// これまでに使ったCPU時間（マイクロ秒）
+    pub fn cpu_usage_usec(&self) -> Option<u64> {
+        stat_value(&Self::read(&self.dir, "cpu.stat")?, "usage_usec")
+    }
+
+    /// ホストのCPU数とメモリ量に cgroup の制限を適用
+    pub fn apply_limits(&self, cpus: f64, memory: u64) -> (f64, u64) {
+        let cpus = self.cpus().map_or(cpus, |limit| limit.min(cpus));
+        let memory = match self.memory_limit() {
+            Some(limit) if memory == 0 || limit < memory => limit,
+            _ => memory,
+        };
+        (cpus, memory)
+    }
+}
+
+/// ノードが使えるCPU数とメモリ量（バイト）
+pub fn available_resources() -> (f64, u64) {
+    let cpus = sys_info::cpu_num().unwrap_or(1) as f64;
+    let memory = sys_info::mem_info().map(|m| m.total * 1024).unwrap_or(0);
+    match Cgroup::detect() {
+        Some(cgroup) => cgroup.apply_limits(cpus, memory),
+        None => (cpus, memory),
+    }
+}
+
+/// `cpu.max`（`<クォータ> <周期>`、クォータが `max` なら無制限）
+fn parse_cpu_max(max: &str) -> Option<f64> {
+    let mut fields = max.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next().unwrap_or("100000").parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// `0-3,6` のようなCPUの一覧のCPU数
+fn parse_cpu_list(list: &str) -> Option<usize> {
+    let list = list.trim();
+    if list.is_empty() {
+        return None;
+    }
+    list.split(',').try_fold(0, |count, range| {
+        let count = match range.split_once('-') {
+            Some((first, last)) => count + last.parse::<usize>().ok()?.checked_sub(first.parse().ok()?)? + 1,
+            None => {
+                range.parse::<usize>().ok()?;
+                count + 1
+            }
+        };
+        Some(count)
+    })
+}
+
+/// `<キー> <値>` の行が並ぶ統計ファイルの値
+fn stat_value(stat: &str, key: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_use_the_tightest_ancestor() {
+        let root = tempfile::tempdir().unwrap();
+        let pod = root.path().join("kubepods/pod1");
+        let container = pod.join("ctr");
+        std::fs::create_dir_all(&container).unwrap();
+        for dir in [root.path(), &pod, &container] {
+            std::fs::write(dir.join("cgroup.controllers"), "cpuset cpu memory").unwrap();
+        }
+        std::fs::write(pod.join("cpu.max"), "100000 100000\n").unwrap();
+        std::fs::write(pod.join("memory.max"), "1073741824\n").unwrap();
+        std::fs::write(container.join("cpu.max"), "150000 100000\n").unwrap();
+        std::fs::write(container.join("memory.max"), "max\n").unwrap();
+        std::fs::write(container.join("cpuset.cpus.effective"), "0-3,6\n").unwrap();
+        std::fs::write(container.join("memory.current"), "600000000\n").unwrap();
+        std::fs::write(container.join("memory.stat"), "anon 400000000\ninactive_file 100000000\n").unwrap();
+        std::fs::write(container.join("cpu.stat"), "usage_usec 2500000\nuser_usec 2000000\n").unwrap();
+
+        let cgroup = Cgroup::from_membership(root.path(), "0::/kubepods/pod1/ctr\n").unwrap();
+        assert_eq!(cgroup.cpus(), Some(1.0));
+        assert_eq!(cgroup.memory_limit(), Some(1 << 30));
+        assert_eq!(cgroup.memory_used(), Some(500_000_000));
+        assert_eq!(cgroup.cpu_usage_usec(), Some(2_500_000));
+        assert_eq!(cgroup.apply_limits(16.0, 64 << 30), (1.0, 1 << 30));
+        assert_eq!(cgroup.apply_limits(0.5, 512 << 20), (0.5, 512 << 20));
+
+        // 名前空間の中では見えないパスの代わりにルートを使う
+        let cgroup = Cgroup::from_membership(root.path(), "0::/../other\n").unwrap();
+        assert_eq!(cgroup.cpus(), None);
+        // cgroup v1
+        assert!(Cgroup::from_membership(root.path(), "12:cpu,cpuacct:/docker/abc\n").is_none());
+        assert_eq!(parse_cpu_max("max 100000"), None);
+        assert_eq!(parse_cpu_list("3-1"), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! デーモンモード
+//!
+//! 主な機能：
+//! - 端末から切り離してバックグラウンドで実行（`--daemon`）
+//! - PIDファイルの作成と、実行中のノードの二重起動の検出
+//! - `system stop`・`system restart` のための実行中のノードへのシグナルの送信
+//!
+//! 再起動できるよう、PIDファイルの隣（`<PIDファイル>.cmdline`）に起動時の作業ディレクトリと引数を保存します。
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+/// データディレクトリのPIDファイルの名前
+pub const PID_FILE_NAME: &str = "rustorium.pid";
+/// 停止を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// PIDファイルのパス（指定がない場合はデータディレクトリの `rustorium.pid`）
+pub fn pid_path(pid_file: Option<&Path>, data_dir: &Path) -> PathBuf {
+    pid_file.map_or_else(|| data_dir.join(PID_FILE_NAME), Path::to_path_buf)
+}
+
+/// 起動時のコマンドライン（再起動に使う）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CommandLine {
+    cwd: PathBuf,
+    args: Vec<String>,
+}
+
+fn command_line_path(pid_path: &Path) -> PathBuf {
+    let mut path = pid_path.as_os_str().to_owned();
+    path.push(".cmdline");
+    PathBuf::from(path)
+}
+
+/// PIDファイルのプロセスID（ファイルがない場合は `None`）
+pub fn read_pid(path: &Path) -> Result<Option<i32>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents.trim().parse()
+            .with_context(|| format!("{} does not contain a process ID", path.display()))?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// プロセスが実行中か
+#[cfg(unix)]
+pub fn is_running(pid: i32) -> bool {
+    use nix::{sys::signal::kill, unistd::Pid};
+    // シグナルを送らずに存在と権限だけを確認する
+    matches!(kill(Pid::from_raw(pid), None), Ok(()) | Err(nix::errno::Errno::EPERM))
+}
+
+#[cfg(not(unix))]
+pub fn is_running(_pid: i32) -> bool {
+    false
+}
+
+/// 実行中のノードがPIDファイルを使っていないことを確認
+pub fn ensure_not_running(path: &Path) -> Result<()> {
+    if let Some(pid) = read_pid(path)? {
+        if is_running(pid) {
+            bail!("Rustorium is already running (PID {}, {})", pid, path.display());
+        }
+    }
+    Ok(())
+}
+
+/// 端末から切り離してバックグラウンドで実行する
+///
+/// 非同期ランタイムのスレッドはフォークで引き継がれないため、ランタイムを起動する前に呼び出してください。
+/// 親プロセスはこの中で終了します。作業ディレクトリは維持するため、相対パスの設定はそのまま使えます。
+#[cfg(unix)]
+pub fn detach(pid_path: &Path) -> Result<()> {
+    ensure_not_running(pid_path)?;
+    let cwd = std::env::current_dir()?;
+    daemonize::Daemonize::new()
+        .working_directory(cwd)
+        .umask(0o027)
+        .start()
+        .map_err(|e| anyhow!("Failed to detach from the terminal: {}", e))
+}
+
+#[cfg(not(unix))]
+pub fn detach(_pid_path: &Path) -> Result<()> {
+    bail!("--daemon is only supported on Unix; use a service manager instead")
+}
+
+/// 実行中のノードのPIDファイル（破棄時に削除する）
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// 現在のプロセスのPIDファイルと、再起動用のコマンドラインを書き込む
+    pub fn create(path: &Path) -> Result<Self> {
+        ensure_not_running(path)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        let command_line = CommandLine {
+            cwd: std::env::current_dir()?,
+            args: std::env::args().collect(),
+        };
+        std::fs::write(command_line_path(path), serde_json::to_vec(&command_line)?)?;
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // 別のプロセスが書き込んだPIDファイルは消さない
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id() as i32) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 実行中のノードに SIGTERM を送り、終了を待つ（停止したプロセスIDを返す）
+#[cfg(unix)]
+pub async fn stop(pid_path: &Path, timeout: Duration) -> Result<i32> {
+    use nix::{sys::signal::{kill, Signal}, unistd::Pid};
+
+    let pid = read_pid(pid_path)?
+        .ok_or_else(|| anyhow!("No PID file at {}; is the node running with --daemon?", pid_path.display()))?;
+    if !is_running(pid) {
+        warn!("Removing stale PID file {} (PID {} is not running)", pid_path.display(), pid);
+        std::fs::remove_file(pid_path)?;
+        bail!("Rustorium is not running");
+    }
+    kill(Pid::from_raw(pid), Signal::SIGTERM).map_err(|e| anyhow!("Failed to signal PID {}: {}", pid, e))?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    while is_running(pid) {
+        if tokio::time::Instant::now() >= deadline {
+            bail!("PID {} did not stop within {}s; check the logs or stop it with `kill -9 {}`", pid, timeout.as_secs(), pid);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Ok(pid)
+}
+
+#[cfg(not(unix))]
+pub async fn stop(_pid_path: &Path, _timeout: Duration) -> Result<i32> {
+    bail!("system stop is only supported on Unix")
+}
+
+/// 実行中のノードを停止し、同じコマンドラインで起動し直す（新しいプロセスIDを返す）
+pub async fn restart(pid_path: &Path, timeout: Duration) -> Result<i32> {
+    let path = command_line_path(pid_path);
+    let command_line: CommandLine = serde_json::from_slice(&std::fs::read(&path)
+        .with_context(|| format!("Failed to read {}; was the node started with --daemon?", path.display()))?)?;
+    let (program, args) = command_line.args.split_first()
+        .ok_or_else(|| anyhow!("{} is empty", path.display()))?;
+    if !args.iter().any(|arg| arg == "--daemon") {
+        bail!("The node was not started with --daemon; restart it with its service manager (e.g. `systemctl restart rustorium`)");
+    }
+
+    let old = stop(pid_path, timeout).await?;
+    // --daemon で起動するため、起動したプロセスは切り離した後すぐに終了する
+    let status = tokio::process::Command::new(program)
+        .args(args)
+        .current_dir(&command_line.cwd)
+        .status()
+        .await
+        .with_context(|| format!("Failed to start {}", program))?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Some(pid) = read_pid(pid_path)?.filter(|pid| *pid != old && is_running(*pid)) {
+            return Ok(pid);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            bail!("The node did not start within {}s; check the logs", timeout.as_secs());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Ctrl+C または SIGTERM を待つ（受け取ったシグナルの名前を返す）
+pub async fn shutdown_signal() -> Result<&'static str> {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|_| "Ctrl+C").map_err(Into::into),
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("Ctrl+C")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pid_file_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = pid_path(None, dir.path());
+        assert_eq!(read_pid(&path).unwrap(), None);
+
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id() as i32));
+        assert!(command_line_path(&path).exists());
+        // 実行中のプロセスのPIDファイルがある場合は二重起動を拒否する
+        assert!(PidFile::create(&path).is_err());
+
+        drop(pid_file);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,146 @@
+//! サービスマニフェスト
+//!
+//! 起動したノードが実際に待ち受けたアドレスを、データディレクトリの `endpoints.json` に書き出します。
+//! `rustorium system endpoints` やスクリプトは、設定からポートを推測せずにこのファイルで接続先を知ることができます。
+//! ファイルはノードの停止時に削除します（異常終了で残ったファイルはPIDで見分ける）。
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use serde::{Serialize, Deserialize};
+
+use super::daemon;
+
+/// データディレクトリのマニフェストの名前
+pub const MANIFEST_FILE: &str = "endpoints.json";
+
+/// 待ち受けているサービス
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Endpoint {
+    /// サービスの名前（`p2p`・`web`・`api`・`websocket`）
+    pub name: String,
+    /// 待ち受けているアドレス
+    pub address: SocketAddr,
+    /// 接続に使うURL（全てのアドレスで待ち受けている場合はループバックのアドレス）
+    pub url: String,
+}
+
+impl Endpoint {
+    pub fn new(name: &str, scheme: &str, address: SocketAddr, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            address,
+            url: format!("{}://{}{}", scheme, connect_addr(address), path),
+        }
+    }
+}
+
+/// 接続に使うアドレス（未指定のアドレスはループバックにする）
+fn connect_addr(address: SocketAddr) -> SocketAddr {
+    match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::new(Ipv4Addr::LOCALHOST.into(), address.port()),
+        IpAddr::V6(ip) if ip.is_unspecified() => SocketAddr::new(Ipv6Addr::LOCALHOST.into(), address.port()),
+        _ => address,
+    }
+}
+
+/// 実行中のノードの待ち受けアドレスの一覧
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceManifest {
+    /// 書き出したノードのプロセスID
+    pub pid: u32,
+    /// ノードの名前
+    pub node: String,
+    /// 起動した時刻（UNIX時刻、秒）
+    pub started_at: u64,
+    pub endpoints: Vec<Endpoint>,
+}
+
+impl ServiceManifest {
+    pub fn new(node: &str, endpoints: Vec<Endpoint>) -> Self {
+        Self {
+            pid: std::process::id(),
+            node: node.to_string(),
+            started_at: chrono::Utc::now().timestamp().max(0) as u64,
+            endpoints,
+        }
+    }
+
+    /// 名前の一致するサービス
+    pub fn get(&self, name: &str) -> Option<&Endpoint> {
+        self.endpoints.iter().find(|endpoint| endpoint.name == name)
+    }
+
+    /// データディレクトリに書き出す（破棄時に削除する）
+    pub fn write(&self, data_dir: &Path) -> Result<ManifestFile> {
+        let path = data_dir.join(MANIFEST_FILE);
+        // 書き込み途中のファイルを読まれないよう、一時ファイルから置き換える
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(ManifestFile { path, pid: self.pid })
+    }
+
+    /// 実行中のノードのマニフェストを読み込む
+    pub fn read(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(MANIFEST_FILE);
+        let manifest: Self = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("{} is not a valid service manifest", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                bail!("No service manifest at {}; is the node running with this data directory?", path.display())
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        if !daemon::is_running(manifest.pid as i32) {
+            bail!("{} was written by PID {}, which is not running", path.display(), manifest.pid);
+        }
+        Ok(manifest)
+    }
+}
+
+/// 書き出したマニフェスト（破棄時に削除する）
+#[derive(Debug)]
+pub struct ManifestFile {
+    path: PathBuf,
+    pid: u32,
+}
+
+impl Drop for ManifestFile {
+    fn drop(&mut self) {
+        // 後から起動した別のノードが書き込んだファイルは消さない
+        let owned = std::fs::read(&self.path).ok()
+            .and_then(|bytes| serde_json::from_slice::<ServiceManifest>(&bytes).ok())
+            .is_some_and(|manifest| manifest.pid == self.pid);
+        if owned {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_manifest_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ServiceManifest::read(dir.path()).is_err());
+
+        let manifest = ServiceManifest::new("node-1", vec![
+            Endpoint::new("api", "http", "0.0.0.0:9071".parse().unwrap(), "/api"),
+            Endpoint::new("websocket", "ws", "[::]:9072".parse().unwrap(), "/ws"),
+            Endpoint::new("web", "http", "10.0.0.5:9070".parse().unwrap(), ""),
+        ]);
+        let file = manifest.write(dir.path()).unwrap();
+        let read = ServiceManifest::read(dir.path()).unwrap();
+        assert_eq!(read.get("api").unwrap().url, "http://127.0.0.1:9071/api");
+        assert_eq!(read.get("websocket").unwrap().url, "ws://[::1]:9072/ws");
+        assert_eq!(read.get("web").unwrap().url, "http://10.0.0.5:9070");
+
+        drop(file);
+        assert!(!dir.path().join(MANIFEST_FILE).exists());
+    }
+}
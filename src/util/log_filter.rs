@@ -0,0 +1,178 @@
+//! 実行中に変更できるログのフィルター
+//!
+//! `--log-level` は `info,consensus=debug` のような `tracing` のフィルターを受け付け、
+//! 起動後も管理者API（`/api/admin/log-filter`）から再起動せずに変更できます。
+//! `consensus` のような `MODULE_TARGETS` のモジュール名は `rustorium::core::consensus` に
+//! 展開します。それ以外のターゲット（`quinn`、`audit` など）はそのまま使います。
+//! 変更は `revert_after` 秒後に変更前のフィルターへ戻すこともできます（障害調査で
+//! `debug` を有効にしたままにしないため）。
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+use utoipa::ToSchema;
+
+/// 短い名前で指定できるモジュールと、そのターゲット
+pub const MODULE_TARGETS: &[(&str, &str)] = &[
+    ("block", "rustorium::core::block"),
+    ("consensus", "rustorium::core::consensus"),
+    ("contract", "rustorium::core::contract"),
+    ("mempool", "rustorium::core::mempool"),
+    ("network", "rustorium::core::network"),
+    ("sharding", "rustorium::core::sharding"),
+    ("storage", "rustorium::core::storage"),
+    ("transaction", "rustorium::core::transaction"),
+    ("web", "rustorium::web"),
+];
+
+/// フィルターの状態
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LogFilterStatus {
+    /// 現在のフィルター（モジュール名を展開したもの）
+    pub filter: String,
+    /// 起動時のフィルター
+    pub initial: String,
+    /// 変更前のフィルターへ戻す時刻（UNIX秒、戻さない場合は `None`）
+    pub reverts_at: Option<u64>,
+}
+
+#[derive(Debug)]
+struct State {
+    filter: String,
+    reverts_at: Option<u64>,
+    /// 変更のたびに増やし、古い変更の取り消しが新しい変更を戻さないようにする
+    generation: u64,
+}
+
+/// 実行中に変更できるログのフィルター
+#[derive(Clone)]
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    initial: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl LogFilter {
+    /// 起動時のフィルターから、購読者に加えるレイヤーと変更用のハンドルを作成
+    pub fn new(directives: &str) -> Result<(reload::Layer<EnvFilter, Registry>, Self)> {
+        let filter = expand(directives)?;
+        let (layer, handle) = reload::Layer::new(EnvFilter::try_new(&filter)?);
+        let state = State { filter: filter.clone(), reverts_at: None, generation: 0 };
+        Ok((layer, Self { handle, initial: filter, state: Arc::new(Mutex::new(state)) }))
+    }
+
+    pub fn status(&self) -> LogFilterStatus {
+        let state = self.state.lock().unwrap();
+        LogFilterStatus {
+            filter: state.filter.clone(),
+            initial: self.initial.clone(),
+            reverts_at: state.reverts_at,
+        }
+    }
+
+    /// フィルターを変更（`revert_after` を指定した場合はその後に変更前のフィルターへ戻す）
+    pub fn set(&self, directives: &str, revert_after: Option<Duration>) -> Result<LogFilterStatus> {
+        let filter = expand(directives)?;
+        let (previous, generation) = {
+            let mut state = self.state.lock().unwrap();
+            self.handle.reload(EnvFilter::try_new(&filter)?)?;
+            let previous = std::mem::replace(&mut state.filter, filter);
+            state.generation += 1;
+            state.reverts_at = revert_after.map(|after| unix_now() + after.as_secs());
+            (previous, state.generation)
+        };
+
+        if let Some(after) = revert_after {
+            let this = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(after).await;
+                let mut state = this.state.lock().unwrap();
+                // 待っている間に別の変更があった場合はそちらを優先する
+                if state.generation != generation {
+                    return;
+                }
+                if this.handle.reload(EnvFilter::new(&previous)).is_ok() {
+                    tracing::info!(target: "audit", "Log filter reverted to {}", previous);
+                    state.filter = previous;
+                    state.reverts_at = None;
+                    state.generation += 1;
+                }
+            });
+        }
+        Ok(self.status())
+    }
+
+    /// 起動時のフィルターに戻す
+    pub fn reset(&self) -> Result<LogFilterStatus> {
+        self.set(&self.initial.clone(), None)
+    }
+}
+
+/// モジュール名をターゲットに展開し、フィルターとして正しいことを確認する
+pub fn expand(directives: &str) -> Result<String> {
+    let expanded: Vec<String> = directives.split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| {
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) => (target, Some(level)),
+                None => (directive, None),
+            };
+            let target = MODULE_TARGETS.iter()
+                .find(|(name, _)| *name == target)
+                .map_or(target, |(_, module)| module);
+            match level {
+                Some(level) => format!("{}={}", target, level),
+                None => target.to_string(),
+            }
+        })
+        .collect();
+    if expanded.is_empty() {
+        return Err(anyhow!("The log filter is empty"));
+    }
+    let filter = expanded.join(",");
+    EnvFilter::try_new(&filter).map_err(|e| anyhow!("Invalid log filter {:?}: {}", directives, e))?;
+    Ok(filter)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_expands_modules_and_reverts() {
+        assert_eq!(
+            expand("info, consensus=debug,quinn=warn,network").unwrap(),
+            "info,rustorium::core::consensus=debug,quinn=warn,rustorium::core::network",
+        );
+        assert!(expand("consensus=loud").is_err());
+        assert!(expand(" , ").is_err());
+
+        let (_layer, filter) = LogFilter::new("info").unwrap();
+        let status = filter.set("consensus=debug", None).unwrap();
+        assert_eq!((status.filter.as_str(), status.initial.as_str()), ("rustorium::core::consensus=debug", "info"));
+
+        // 期限付きの変更は変更前のフィルターへ戻る
+        let status = filter.set("trace", Some(Duration::from_millis(50))).unwrap();
+        assert!(status.reverts_at.is_some());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(filter.status().filter, "rustorium::core::consensus=debug");
+        assert!(filter.status().reverts_at.is_none());
+
+        // 期限の前に別の変更があった場合は戻さない
+        filter.set("trace", Some(Duration::from_millis(50))).unwrap();
+        filter.set("warn", None).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(filter.status().filter, "warn");
+        assert_eq!(filter.reset().unwrap().filter, "info");
+    }
+}
@@ -0,0 +1,184 @@
+//! ログファイルのローテーション
+//!
+//! `<dir>/rustorium.log` に書き込み、期間（毎時・毎日）が変わるか `max_bytes` を超えたときに
+//! `rustorium-<日時>-<連番>.log` に切り替えます。切り替えたファイルはgzipで圧縮し、古いものから
+//! `max_files` を超えた分を削除します。書き込みは `tracing_appender::non_blocking` で包んで使います。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use flate2::{write::GzEncoder, Compression};
+
+/// 書き込み中のログファイルの名前
+pub const ACTIVE_NAME: &str = "rustorium.log";
+/// 切り替えたログファイルの名前の接頭辞
+pub const ROTATED_PREFIX: &str = "rustorium-";
+
+/// 期間によるローテーション
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Rotation {
+    Hourly,
+    Daily,
+    /// 期間では切り替えない（サイズのみ）
+    Never,
+}
+
+impl Rotation {
+    /// 期間の識別子（変わったら切り替える）
+    fn period(self, now: DateTime<Utc>) -> String {
+        match self {
+            Rotation::Hourly => now.format("%Y%m%d%H").to_string(),
+            Rotation::Daily => now.format("%Y%m%d").to_string(),
+            Rotation::Never => String::new(),
+        }
+    }
+}
+
+/// ローテーションの設定
+#[derive(Debug, Clone)]
+pub struct RotationConfig {
+    pub dir: PathBuf,
+    pub rotation: Rotation,
+    /// ファイルの最大バイト数（0 は無制限）
+    pub max_bytes: u64,
+    /// 残す切り替え済みのファイル数（0 は無制限）
+    pub max_files: usize,
+    /// 切り替えたファイルをgzipで圧縮する
+    pub compress: bool,
+}
+
+/// ローテーションするログファイル
+pub struct RotatingFile {
+    config: RotationConfig,
+    file: BufWriter<File>,
+    written: u64,
+    period: String,
+}
+
+impl RotatingFile {
+    pub fn open(config: RotationConfig) -> io::Result<Self> {
+        std::fs::create_dir_all(&config.dir)?;
+        let path = config.dir.join(ACTIVE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        let period = config.rotation.period(Utc::now());
+        Ok(Self { config, file: BufWriter::new(file), written, period })
+    }
+
+    fn rotate(&mut self, now: DateTime<Utc>) -> io::Result<()> {
+        self.file.flush()?;
+        let active = self.config.dir.join(ACTIVE_NAME);
+        let rotated = self.rotated_path(now);
+        std::fs::rename(&active, &rotated)?;
+        self.file = BufWriter::new(OpenOptions::new().create(true).append(true).open(&active)?);
+        self.written = 0;
+
+        if self.config.compress {
+            // 圧縮中もログの書き込みを止めないよう別のスレッドで行う
+            let config = self.config.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = compress(&rotated) {
+                    eprintln!("Failed to compress {}: {}", rotated.display(), e);
+                }
+                let _ = prune(&config);
+            });
+        } else {
+            prune(&self.config)?;
+        }
+        Ok(())
+    }
+
+    /// 切り替え先のパス（名前の順が切り替えた順になるよう、同じ時刻には連番を付ける）
+    fn rotated_path(&self, now: DateTime<Utc>) -> PathBuf {
+        let stamp = now.format("%Y%m%dT%H%M%S%3fZ");
+        (0..)
+            .map(|n| self.config.dir.join(format!("{}{}-{}.log", ROTATED_PREFIX, stamp, n)))
+            .find(|path| !path.exists() && !gz_path(path).exists())
+            .expect("an unused file name")
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = Utc::now();
+        let period = self.config.rotation.period(now);
+        let full = self.config.max_bytes > 0 && self.written > 0 && self.written + buf.len() as u64 > self.config.max_bytes;
+        if period != self.period || full {
+            self.period = period;
+            if self.written > 0 {
+                self.rotate(now)?;
+            }
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut gz = path.as_os_str().to_owned();
+    gz.push(".gz");
+    PathBuf::from(gz)
+}
+
+/// ファイルを `<path>.gz` に圧縮して元のファイルを削除
+fn compress(path: &Path) -> io::Result<()> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut output = GzEncoder::new(BufWriter::new(File::create(gz_path(path))?), Compression::default());
+    io::copy(&mut input, &mut output)?;
+    output.finish()?.flush()?;
+    std::fs::remove_file(path)
+}
+
+/// 古い切り替え済みのファイルを削除（名前の日時順）
+fn prune(config: &RotationConfig) -> io::Result<()> {
+    if config.max_files == 0 {
+        return Ok(());
+    }
+    let mut rotated: Vec<PathBuf> = std::fs::read_dir(&config.dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(ROTATED_PREFIX)))
+        .collect();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(config.max_files);
+    for path in &rotated[..excess] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_rotation_and_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(RotationConfig {
+            dir: dir.path().to_path_buf(),
+            rotation: Rotation::Never,
+            max_bytes: 10,
+            max_files: 2,
+            compress: false,
+        }).unwrap();
+        for _ in 0..4 {
+            file.write_all(b"0123456789").unwrap();
+        }
+        file.flush().unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[2], ACTIVE_NAME);
+        assert!(names[..2].iter().all(|n| n.starts_with(ROTATED_PREFIX)));
+        assert_eq!(std::fs::read(dir.path().join(ACTIVE_NAME)).unwrap(), b"0123456789");
+    }
+}
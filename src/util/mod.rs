@@ -1,3 +1,10 @@
+pub mod cgroup;
+pub mod daemon;
+pub mod debug_report;
+pub mod endpoints;
+pub mod log_filter;
+pub mod log_rotation;
+
 use std::net::{TcpListener, SocketAddr};
 use anyhow::Result;
 
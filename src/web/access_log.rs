@@ -0,0 +1,236 @@
+//! APIのアクセスログ
+//!
+//! リクエストごとにメソッド、ルート、ステータス、処理時間、APIキーのID、パラメーターを
+//! JSON Lines として記録し、SIEM などに取り込めるようにします。
+//! 主な機能：
+//! - 機密性のあるパラメーター（APIキー、署名など）の伏せ字化と長い値の切り詰め
+//! - APIキーはそのまま記録せず、ハッシュの先頭をIDとして記録
+//! - クライアントのIPアドレスの匿名化（IPv4 は /24、IPv6 は /48）
+//! - 成功したリクエストのサンプリング（エラーは常に記録）
+//! - ファイルへの追記、またはログ（`access` ターゲット）への出力
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::Result;
+use axum::{
+    RequestExt,
+    extract::{ConnectInfo, MatchedPath, Query, RawPathParams, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use crate::config::AccessLogSettings;
+use crate::core::types::sha256_hex;
+
+/// 伏せ字
+const REDACTED: &str = "[redacted]";
+/// 書き込みキューの容量（溢れた行は捨てる）
+const QUEUE_CAPACITY: usize = 4096;
+/// APIキーのIDの長さ（ハッシュの hex の先頭）
+const API_KEY_ID_LENGTH: usize = 12;
+
+/// アクセスログの1行
+#[derive(Debug, Serialize)]
+pub struct AccessLogEntry {
+    /// 受信時刻（RFC 3339）
+    pub timestamp: String,
+    pub method: String,
+    /// ルートのパターン（`/api/accounts/:address/balance` など）
+    pub route: String,
+    pub status: u16,
+    /// 処理時間（ミリ秒）
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+    /// パスとクエリのパラメーター（伏せ字化・切り詰め済み）
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+}
+
+/// アクセスログの出力先
+pub struct AccessLog {
+    settings: AccessLogSettings,
+    /// ファイルへの書き込みキュー（未設定の場合はログに出力）
+    queue: Option<mpsc::Sender<String>>,
+}
+
+impl AccessLog {
+    /// 出力先を開く（ファイルの場合は書き込みタスクを起動）
+    pub async fn open(settings: &AccessLogSettings) -> Result<Self> {
+        let queue = match &settings.path {
+            Some(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+                let (queue, lines) = mpsc::channel(QUEUE_CAPACITY);
+                tokio::spawn(write_lines(BufWriter::new(file), lines));
+                info!("Writing API access log to {}", path.display());
+                Some(queue)
+            }
+            None => None,
+        };
+        Ok(Self { settings: settings.clone(), queue })
+    }
+
+    /// サンプリングの対象か
+    fn sampled(&self, status: u16) -> bool {
+        (status >= 400 && self.settings.always_log_errors) || rand::random::<f64>() < self.settings.sample_rate
+    }
+
+    fn emit(&self, entry: &AccessLogEntry) {
+        let Ok(line) = serde_json::to_string(entry) else {
+            return;
+        };
+        match &self.queue {
+            Some(queue) => {
+                if queue.try_send(line).is_err() {
+                    debug!("Access log queue is full, dropping an entry");
+                }
+            }
+            None => info!(target: "access", "{}", line),
+        }
+    }
+
+    /// パラメーターを伏せ字化・切り詰め
+    fn sanitize<'a>(&self, params: impl Iterator<Item = (&'a str, &'a str)>) -> BTreeMap<String, String> {
+        params
+            .map(|(name, value)| {
+                let redacted = self.settings.redact_params.iter().any(|r| r.eq_ignore_ascii_case(name));
+                let value = if redacted {
+                    REDACTED.to_string()
+                } else {
+                    truncate(value, self.settings.max_param_length)
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+}
+
+async fn write_lines(mut file: BufWriter<tokio::fs::File>, mut lines: mpsc::Receiver<String>) {
+    while let Some(line) = lines.recv().await {
+        let mut result = write_line(&mut file, &line).await;
+        // 溜まっている行をまとめて書いてからフラッシュする
+        while result.is_ok() {
+            let Ok(line) = lines.try_recv() else {
+                break;
+            };
+            result = write_line(&mut file, &line).await;
+        }
+        if let Err(e) = result.and(file.flush().await) {
+            warn!("Failed to write API access log: {}", e);
+        }
+    }
+}
+
+async fn write_line(file: &mut BufWriter<tokio::fs::File>, line: &str) -> std::io::Result<()> {
+    file.write_all(line.as_bytes()).await?;
+    file.write_all(b"\n").await
+}
+
+/// リクエストをアクセスログに記録するミドルウェア
+pub async fn record(State(log): State<Arc<AccessLog>>, mut request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    let method = request.method().to_string();
+    let route = request.extensions().get::<MatchedPath>()
+        .map_or_else(|| "<unmatched>".to_string(), |path| path.as_str().to_string());
+    let client_ip = request.extensions().get::<ConnectInfo<SocketAddr>>()
+        .filter(|_| log.settings.log_client_ip)
+        .map(|ConnectInfo(addr)| anonymize(addr.ip()).to_string());
+    let query = Query::<Vec<(String, String)>>::try_from_uri(request.uri())
+        .map(|Query(query)| query)
+        .unwrap_or_default();
+    let api_key_id = api_key(request.headers(), &query)
+        .map(|key| sha256_hex(key.as_bytes())[..API_KEY_ID_LENGTH].to_string());
+    let path_params = request.extract_parts::<RawPathParams>().await.ok();
+    let mut params = log.sanitize(query.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+    if let Some(path_params) = &path_params {
+        params.extend(log.sanitize(path_params.iter()));
+    }
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    if log.sampled(status) {
+        log.emit(&AccessLogEntry {
+            timestamp,
+            method,
+            route,
+            status,
+            latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+            api_key_id,
+            client_ip,
+            params,
+        });
+    }
+    response
+}
+
+/// リクエストのAPIキー（管理者トークン、`X-API-Key`、`api_key` パラメーターの順）
+fn api_key<'a>(headers: &'a HeaderMap, query: &'a [(String, String)]) -> Option<&'a str> {
+    headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .or_else(|| query.iter().find(|(name, _)| name == "api_key").map(|(_, value)| value.as_str()))
+        .filter(|key| !key.is_empty())
+}
+
+/// IPアドレスを匿名化（IPv4 は /24、IPv6 は /48）
+fn anonymize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => anonymize(IpAddr::V4(v4)),
+            None => {
+                let [a, b, c, ..] = v6.segments();
+                IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+            }
+        },
+    }
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    match value.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_redaction_and_anonymization() {
+        let log = AccessLog::open(&AccessLogSettings { max_param_length: 8, ..Default::default() }).await.unwrap();
+        let params = log.sanitize([("API_KEY", "secret-value"), ("address", "0123456789abcdef"), ("limit", "10")].into_iter());
+        assert_eq!(params["API_KEY"], REDACTED);
+        assert_eq!(params["address"], "01234567…");
+        assert_eq!(params["limit"], "10");
+
+        assert_eq!(anonymize("203.0.113.77".parse().unwrap()).to_string(), "203.0.113.0");
+        assert_eq!(anonymize("2001:db8:1:2:3::9".parse().unwrap()).to_string(), "2001:db8:1::");
+        assert_eq!(anonymize("::ffff:198.51.100.7".parse().unwrap()).to_string(), "198.51.100.0");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer admin-token".parse().unwrap());
+        assert_eq!(api_key(&headers, &[]), Some("admin-token"));
+        let query = vec![("api_key".to_string(), "watch-key".to_string())];
+        assert_eq!(api_key(&HeaderMap::new(), &query), Some("watch-key"));
+    }
+}
@@ -0,0 +1,277 @@
+//! 管理者API
+//!
+//! ノードオペレーター向けのエンドポイントです。
+//! `api.admin_token` が設定されている場合のみ有効になり、
+//! `Authorization: Bearer <token>` ヘッダーによる認証が必要です。
+
+use axum::{
+    Router,
+    routing::{get, delete, put},
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::core::ai::AiOptimizer;
+use crate::util::log_filter::{LogFilter, MODULE_TARGETS};
+
+pub fn create_router(state: AppState) -> Router {
+    let router = Router::new()
+        .route("/access-list", get(get_access_list).post(add_access_list_entry))
+        .route("/access-list/:address", delete(remove_access_list_entry))
+        .route("/access-list/audit", get(get_audit_log))
+        .route("/ai/audit", get(get_ai_audit_log))
+        .route("/ai/dry-run", put(set_ai_dry_run))
+        .route("/language", put(set_language))
+        .route("/log-filter", get(get_log_filter).put(set_log_filter).delete(reset_log_filter));
+    #[cfg(feature = "profiling")]
+    let router = router.nest("/debug/pprof", super::pprof::create_router());
+    #[cfg(not(feature = "profiling"))]
+    let router = router.route("/debug/pprof/*profile", get(profiling_disabled));
+    router.with_state(state)
+}
+
+/// 管理者トークンまたはパスキーのログインセッションを検証し、操作者名を返す
+pub(crate) fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<String> {
+    if let Some(session) = state.auth.as_ref().and_then(|auth| auth.session(headers)) {
+        return Ok(session.name);
+    }
+    let expected = state.config.api.admin_token.as_deref()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| AppError::coded(ErrorCode::AdminApiDisabled, "Admin API is disabled"))?;
+
+    let provided = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized)?;
+
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(AppError::Unauthorized);
+    }
+
+    Ok("admin".to_string())
+}
+
+/// 管理者トークン、`scope` を許可したトークン、またはパスキーのログインセッションを検証
+///
+/// トークンがない・不明な場合は401、権限のないトークンの場合は403を返します。
+pub(crate) fn require_scope(state: &AppState, headers: &HeaderMap, scope: &str) -> Result<()> {
+    if state.auth.as_ref().is_some_and(|auth| auth.session(headers).is_some()) {
+        return Ok(());
+    }
+    let provided = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized)?;
+
+    let api = &state.config.api;
+    if api.admin_token.as_deref().is_some_and(|t| !t.is_empty() && constant_time_eq(provided.as_bytes(), t.as_bytes())) {
+        return Ok(());
+    }
+    let token = api.tokens.iter()
+        .find(|t| !t.token.is_empty() && constant_time_eq(provided.as_bytes(), t.token.as_bytes()))
+        .ok_or(AppError::Unauthorized)?;
+    if !token.scopes.iter().any(|s| s == scope) {
+        return Err(AppError::coded(ErrorCode::MissingScope, format!("Token lacks the {} scope", scope)));
+    }
+    Ok(())
+}
+
+/// タイミング攻撃を避けるための比較
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessListEntry {
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DryRunRequest {
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguageRequest {
+    language: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogFilterRequest {
+    /// `info,consensus=debug` のようなフィルター
+    filter: String,
+    /// この秒数の後に変更前のフィルターへ戻す
+    #[serde(default)]
+    revert_after_secs: Option<u64>,
+}
+
+/// アクセスリストを取得
+async fn get_access_list(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    let mempool = state.mempool.read().await;
+    let access = mempool.access();
+
+    Ok(Json(json!({
+        "mode": access.mode(),
+        "addresses": access.addresses(),
+    })))
+}
+
+/// アクセスリストにアドレスを追加（`mempool.access_list_path` のファイルにも書き戻す）
+async fn add_access_list_entry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(entry): Json<AccessListEntry>,
+) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    if entry.address.trim().is_empty() {
+        return Err(AppError::BadRequest("address must not be empty".to_string()));
+    }
+
+    let mut mempool = state.mempool.write().await;
+    let added = mempool.access_mut().add(&entry.address, &actor);
+    if added {
+        mempool.access().save()?;
+    }
+    Ok(Json(json!({ "success": true, "added": added })))
+}
+
+/// アクセスリストからアドレスを削除（`mempool.access_list_path` のファイルにも書き戻す）
+async fn remove_access_list_entry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    let mut mempool = state.mempool.write().await;
+    if !mempool.access_mut().remove(&address, &actor) {
+        return Err(AppError::NotFound(format!("address {} is not in the access list", address)));
+    }
+    mempool.access().save()?;
+    Ok(Json(json!({ "success": true })))
+}
+
+/// 監査ログを取得
+async fn get_audit_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    let entries = state.mempool.read().await.access().audit_log(query.limit.unwrap_or(100));
+    Ok(Json(json!({ "entries": entries })))
+}
+
+/// AI最適化エンジンを取得
+fn ai_optimizer(state: &AppState) -> Result<&Arc<Mutex<AiOptimizer>>> {
+    state.ai.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("AI optimizer is not running".to_string()))
+}
+
+/// AI最適化エンジンの監査ログを取得
+async fn get_ai_audit_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    let optimizer = ai_optimizer(&state)?.lock().await;
+    Ok(Json(json!({
+        "dry_run": optimizer.executor().dry_run(),
+        "system": optimizer.system_metrics(),
+        "entries": optimizer.executor().audit_log(query.limit.unwrap_or(100)),
+    })))
+}
+
+/// AI最適化エンジンのドライランモードを切り替え
+async fn set_ai_dry_run(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<DryRunRequest>,
+) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    ai_optimizer(&state)?.lock().await.executor_mut().set_dry_run(request.enabled);
+    info!(target: "audit", "AI optimizer dry-run set to {} by {}", request.enabled, actor);
+    Ok(Json(json!({ "success": true, "dry_run": request.enabled })))
+}
+
+/// ノードの表示言語（CLIとWeb UIの既定）を切り替え
+async fn set_language(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<LanguageRequest>,
+) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    state.locale.set_language(&request.language).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    info!(target: "audit", "Display language set to {} by {}", request.language, actor);
+    Ok(Json(json!({ "success": true, "language": state.locale.language() })))
+}
+
+fn log_filter(state: &AppState) -> Result<&LogFilter> {
+    state.log_filter.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("Log filter cannot be changed in this process".to_string()))
+}
+
+/// 現在のログのフィルターと、短い名前で指定できるモジュール
+async fn get_log_filter(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    Ok(Json(json!({
+        "success": true,
+        "log_filter": log_filter(&state)?.status(),
+        "modules": MODULE_TARGETS.iter().map(|(name, target)| json!({ "name": name, "target": target })).collect::<Vec<_>>(),
+    })))
+}
+
+/// ログのフィルターを再起動せずに変更
+async fn set_log_filter(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<LogFilterRequest>,
+) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    let status = log_filter(&state)?
+        .set(&request.filter, request.revert_after_secs.map(std::time::Duration::from_secs))
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    match request.revert_after_secs {
+        Some(secs) => info!(target: "audit", "Log filter set to {} for {} seconds by {}", status.filter, secs, actor),
+        None => info!(target: "audit", "Log filter set to {} by {}", status.filter, actor),
+    }
+    Ok(Json(json!({ "success": true, "log_filter": status })))
+}
+
+/// ログのフィルターを起動時のものに戻す
+async fn reset_log_filter(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    let status = log_filter(&state)?.reset().map_err(|e| AppError::Internal(e.to_string()))?;
+    info!(target: "audit", "Log filter reset to {} by {}", status.filter, actor);
+    Ok(Json(json!({ "success": true, "log_filter": status })))
+}
+
+
+/// `profiling` フィーチャーなしでビルドしたノードのプロファイル
+#[cfg(not(feature = "profiling"))]
+async fn profiling_disabled(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    Err::<Json<serde_json::Value>, _>(AppError::coded(
+        ErrorCode::FeatureDisabled,
+        "Profiling is not available (build with --features profiling)",
+    ))
+}
\ No newline at end of file
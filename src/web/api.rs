@@ -1,24 +1,132 @@
 use axum::{
     Router,
+    body::{Body, Bytes},
     routing::{get, post},
-    extract::State,
-    response::{IntoResponse, Json},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json, Response, sse::{Event, KeepAlive, Sse}},
 };
-use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use futures::StreamExt;
+use serde::{Serialize, Deserialize};
+use tokio::sync::broadcast;
 use utoipa::{OpenApi, ToSchema};
 use chrono::Utc;
 
 use super::{AppState, AppError, Result};
-use crate::config::NodeConfig;
+use super::error_code::{ApiError, ErrorBody, ErrorCode, ErrorCodeEntry};
+use super::idempotency::{self, Reservation, REPLAYED_HEADER};
+use super::listing::{self, ListQuery, Page};
+use super::admin::require_scope;
+use super::geo::GeoMetrics;
+use crate::core::cache::{
+    AddressTx, ArchivePage, ArchiveRange, BalancePoint, NodeStatus, RegionMetrics, TokenHolder, TxDirection,
+};
+use crate::core::cache::views::MAX_ARCHIVE_PAGE;
+use crate::config::{NodeConfig, SCOPE_CONTRACTS_VERIFY, SCOPE_MEMPOOL_READ};
+use crate::core::blob::{BlobError, BlobRef, BlobSidecar};
+use crate::core::block::{Block, Event as BlockEvent};
+use crate::core::block::beacon::{Randomness, RandomnessSource};
+use crate::core::block::explorer::{BlockPage, BlockSummary, TransactionDetail};
+use crate::core::block::header::{BlockSignature, Receipt};
+use crate::core::block::orphans::{OrphanBlock, OrphanPage, OrphanReason};
+use crate::core::storage::watchdog::{CleanupReport, DiskState, DiskStatus};
+use crate::core::accounting::{self, AccountTotal, Category, JournalEntry, JournalLine, Ledger};
+use crate::core::consensus::{
+    market::{self, MarketEntry, RewardSimulation},
+    performance::{self, PerformanceReport, ValidatorPerformance},
+    registry::{Registration, RegistrationError, ValidatorCandidate},
+    shadow::ShadowReport,
+};
+use crate::core::contract::metrics::{ContractUsage, ContractUsageReport};
+use crate::core::memo::{Memo, MemoError};
+use crate::core::mempool::{AdmissionError, PendingTransaction};
+use crate::core::fees::{self, FeeEstimate, FeeSuggestion, InclusionQuote};
+use crate::core::mempool::content::{Bucket, ContentFilter, MempoolSummary, MAX_CONTENT_LIMIT};
+use crate::core::network::peers::{Direction, PeerSummary};
+use crate::core::network::roles::{Capability, NodeRole};
+use crate::core::types::canonical_json;
+use crate::core::vesting::{VestingGrant, VestingGrantStatus, VestingReport};
+use crate::core::wallet::{AddressError, AddressFormat, TxSignature, UnsignedTransaction};
+use crate::core::ai::{FailureKind, Prediction};
+use crate::core::coordination::{BeaconBlock, BeaconOp, BeaconState, ShardEntry};
+use crate::core::sharding::{ShardId, ShardTopology};
+use crate::core::sharding::checkpoint::{ReceiptProof, ShardCheckpoint, ShardReceipt};
+use crate::core::sharding::rebalance::{AccountMove, RebalancePlan, RebalanceState, RebalanceStatus, ShardLoad};
+use crate::core::sharding::scaling::{ScalingAction, ScalingReason, ScalingRecommendation, ScalingTrigger};
+use crate::core::contract::{
+    Deployment, DeployError, Finding, Severity,
+    Approval, CompilerKind, CompilerSettings, MatchStatus, ProxyRecord,
+    RegisterProxy, UpgradeAuthority, UpgradeEvent, UpgradeTransaction,
+    VerificationError, VerificationRequest, VerifiedContract, VerifiedContractSummary,
+};
 
 #[derive(OpenApi)]
 #[openapi(
+    info(title = "Rustorium Node API"),
+    servers((url = "/api")),
     paths(
         api_root,
+        get_openapi,
+        list_error_codes,
         health_check,
+        get_failure_predictions,
+        get_disk_status,
         get_metrics,
         get_config,
         update_config,
+        get_contract_deployment,
+        verify_contract,
+        get_contract_source,
+        get_contract_upgrades,
+        list_contracts,
+        get_proxy,
+        get_shards,
+        get_rebalance_status,
+        get_scaling_recommendation,
+        stream_scaling_recommendations,
+        get_shard_checkpoint,
+        get_receipt_proof,
+        verify_receipt_proof,
+        get_beacon_state,
+        get_beacon_head,
+        get_beacon_block,
+        get_geo_metrics,
+        get_validator_performance,
+        get_shadow_report,
+        list_candidates,
+        register_validator,
+        get_candidate,
+        get_staking_market,
+        simulate_staking,
+        get_top_contracts,
+        get_network_peers,
+        get_languages,
+        get_messages,
+        list_blocks,
+        get_block,
+        get_block_by_hash,
+        get_block_randomness,
+        get_block_orphans,
+        get_transaction,
+        get_blob,
+        get_mempool,
+        suggest_fees,
+        quote_inclusion,
+        submit_transaction,
+        build_unsigned_transaction,
+        hash_transaction,
+        render_qr,
+        get_account_nonce,
+        convert_address,
+        get_account_balance,
+        get_account_vesting,
+        get_account_transactions,
+        get_token_holders,
+        get_archive_transactions,
+        get_balance_history,
+        get_accounting_ledger,
     ),
     components(
         schemas(
@@ -26,20 +134,152 @@ use crate::config::NodeConfig;
             Documentation,
             Endpoint,
             HealthResponse,
+            PredictionsResponse,
+            Prediction,
+            FailureKind,
+            DiskStatus,
+            DiskState,
+            CleanupReport,
             MetricsResponse,
-            NodeConfig
+            NodeConfig,
+            Deployment,
+            Finding,
+            Severity,
+            CompilerKind,
+            CompilerSettings,
+            MatchStatus,
+            VerificationRequest,
+            VerifiedContract,
+            VerifiedContractSummary,
+            Page<VerifiedContractSummary>,
+            Approval,
+            ProxyRecord,
+            RegisterProxy,
+            UpgradeAuthority,
+            UpgradeEvent,
+            UpgradeTransaction,
+            AccountMove,
+            RebalancePlan,
+            RebalanceState,
+            RebalanceStatus,
+            ShardLoad,
+            ShardTopology,
+            ScalingAction,
+            ScalingReason,
+            ScalingRecommendation,
+            ScalingTrigger,
+            ShardCheckpoint,
+            ShardReceipt,
+            ReceiptProof,
+            BeaconBlock,
+            BeaconOp,
+            BeaconState,
+            ShardEntry,
+            GeoMetrics,
+            RegionMetrics,
+            NodeStatus,
+            PerformanceReport,
+            PerformancePage,
+            ValidatorPerformance,
+            ShadowReport,
+            ErrorCodeEntry,
+            ErrorBody,
+            ApiError,
+            PeersResponse,
+            LanguagesResponse,
+            PeerSummary,
+            Direction,
+            NodeRole,
+            Capability,
+            Block,
+            BlockEvent,
+            BlockSignature,
+            BlockSummary,
+            BlockPage,
+            Randomness,
+            RandomnessSource,
+            TransactionDetail,
+            Receipt,
+            OrphanBlock,
+            OrphanReason,
+            OrphanPage,
+            MempoolResponse,
+            FeeSuggestion,
+            FeeEstimate,
+            InclusionQuote,
+            MempoolSummary,
+            Bucket,
+            PendingTransaction,
+            BlobRef,
+            BlobSidecar,
+            SubmitTransactionRequest,
+            SubmitTransactionResponse,
+            UnsignedTransactionRequest,
+            UnsignedTransaction,
+            HashTxResponse,
+            NonceResponse,
+            AddressResponse,
+            TxSignature,
+            Memo,
+            BalanceResponse,
+            VestingReport,
+            VestingGrantStatus,
+            VestingGrant,
+            AddressTx,
+            TxDirection,
+            TokenHolder,
+            Page<TokenHolder>,
+            Registration,
+            ValidatorCandidate,
+            Page<ValidatorCandidate>,
+            MarketEntry,
+            MarketPage,
+            RewardSimulation,
+            ContractUsageReport,
+            ContractUsagePage,
+            ContractUsage,
+            BalancePoint,
+            ArchivePage<AddressTx>,
+            ArchivePage<BalancePoint>,
+            Ledger,
+            JournalEntry,
+            JournalLine,
+            Category,
+            AccountTotal
         )
     ),
     tags(
         (name = "root", description = "API root information"),
+        (name = "openapi", description = "Machine-readable API description"),
         (name = "health", description = "Health check endpoints"),
         (name = "metrics", description = "System metrics endpoints"),
-        (name = "config", description = "Configuration endpoints")
+        (name = "config", description = "Configuration endpoints"),
+        (name = "contracts", description = "Contract source verification endpoints"),
+        (name = "proxies", description = "Upgradeable contract proxy registry"),
+        (name = "shards", description = "Shard topology and rebalancing"),
+        (name = "beacon", description = "Shard membership, validator assignments and checkpoints agreed on the beacon chain"),
+        (name = "geo", description = "Geo-aware read routing"),
+        (name = "validators", description = "Validator performance for delegators"),
+        (name = "staking", description = "Validator comparison for delegation"),
+        (name = "analytics", description = "Per-contract load for capacity planning"),
+        (name = "network", description = "Connected P2P peers"),
+        (name = "i18n", description = "Translated messages for the Web UI"),
+        (name = "blocks", description = "Committed blocks"),
+        (name = "transactions", description = "Transaction submission and lookup"),
+        (name = "mempool", description = "Pending transactions and fee distribution"),
+        (name = "explorer", description = "Precomputed explorer queries"),
+        (name = "archive", description = "Full address history over time ranges"),
+        (name = "accounting", description = "Double-entry ledgers for bookkeeping"),
+        (name = "utils", description = "Helpers for external systems")
     )
 )]
-#[allow(dead_code)]
 struct ApiDoc;
 
+/// ハンドラーとモデルの注釈から生成したOpenAPI 3.1のドキュメント
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
 /// APIルートページのレスポンス
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ApiRootResponse {
@@ -106,10 +346,64 @@ pub struct PerformanceMetrics {
 pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/", get(api_root))
+        .route("/openapi.json", get(get_openapi))
+        .route("/errors", get(list_error_codes))
         .route("/health", get(health_check))
+        .route("/health/predictions", get(get_failure_predictions))
+        .route("/health/disk", get(get_disk_status))
         .route("/metrics", get(get_metrics))
         .route("/config", get(get_config))
         .route("/config", post(update_config))
+        .route("/contracts", get(list_contracts))
+        .route("/contracts/:address/verify", post(verify_contract))
+        .route("/contracts/:address/source", get(get_contract_source))
+        .route("/contracts/:address/deployment", get(get_contract_deployment))
+        .route("/contracts/:address/upgrades", get(get_contract_upgrades))
+        .route("/proxies/:id", get(get_proxy))
+        .route("/shards", get(get_shards))
+        .route("/shards/rebalance/status", get(get_rebalance_status))
+        .route("/shards/scaling", get(get_scaling_recommendation))
+        .route("/shards/scaling/events", get(stream_scaling_recommendations))
+        .route("/shards/:id/checkpoint", get(get_shard_checkpoint))
+        .route("/shards/receipts/verify", post(verify_receipt_proof))
+        .route("/shards/receipts/:tx_id/proof", get(get_receipt_proof))
+        .route("/beacon/state", get(get_beacon_state))
+        .route("/beacon/head", get(get_beacon_head))
+        .route("/beacon/blocks/:height", get(get_beacon_block))
+        .route("/geo/metrics", get(get_geo_metrics))
+        .route("/validators/performance", get(get_validator_performance))
+        .route("/validators/shadow", get(get_shadow_report))
+        .route("/validators/candidates", get(list_candidates).post(register_validator))
+        .route("/validators/candidates/:address", get(get_candidate))
+        .route("/staking/market", get(get_staking_market))
+        .route("/staking/simulate", get(simulate_staking))
+        .route("/analytics/contracts/top", get(get_top_contracts))
+        .route("/network/peers", get(get_network_peers))
+        .route("/i18n", get(get_languages))
+        .route("/i18n/:language", get(get_messages))
+        .route("/blocks", get(list_blocks))
+        .route("/blocks/orphans", get(get_block_orphans))
+        .route("/blocks/hash/:hash", get(get_block_by_hash))
+        .route("/blocks/:height", get(get_block))
+        .route("/blocks/:height/randomness", get(get_block_randomness))
+        .route("/mempool", get(get_mempool))
+        .route("/fees/suggest", get(suggest_fees))
+        .route("/fees/quote", get(quote_inclusion))
+        .route("/transactions", post(submit_transaction))
+        .route("/transactions/unsigned", post(build_unsigned_transaction))
+        .route("/transactions/:hash", get(get_transaction))
+        .route("/blobs/:commitment", get(get_blob))
+        .route("/utils/hash-tx", post(hash_transaction))
+        .route("/utils/qr", post(render_qr))
+        .route("/utils/address/:address", get(convert_address))
+        .route("/accounts/:address/nonce", get(get_account_nonce))
+        .route("/accounts/:address/balance", get(get_account_balance))
+        .route("/accounts/:address/vesting", get(get_account_vesting))
+        .route("/accounts/:address/transactions", get(get_account_transactions))
+        .route("/tokens/:address/holders", get(get_token_holders))
+        .route("/archive/transactions", get(get_archive_transactions))
+        .route("/archive/balance-history/:address", get(get_balance_history))
+        .route("/accounting/ledger", get(get_accounting_ledger))
         .with_state(state)
 }
 
@@ -130,7 +424,7 @@ async fn api_root() -> Result<impl IntoResponse> {
             This API provides access to node operations, metrics, and configuration.".to_string(),
         documentation: Documentation {
             swagger_ui: "/api/docs".to_string(),
-            openapi_json: "/api/api-docs/openapi.json".to_string(),
+            openapi_json: "/api/openapi.json".to_string(),
             github_repo: "https://github.com/rustorium/rustorium".to_string(),
             website: "https://rustorium.org".to_string(),
         },
@@ -161,6 +455,37 @@ async fn api_root() -> Result<impl IntoResponse> {
     Ok(Json(response))
 }
 
+/// OpenAPIドキュメントを取得
+///
+/// フロントエンドの型定義は `scripts/generate-api-client.sh` でこのドキュメントから生成します。
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "openapi",
+    responses(
+        (status = 200, description = "OpenAPI 3.1 document of this API", body = Object)
+    )
+)]
+async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+/// エラーコードの一覧
+///
+/// エラーの本文（`{"error": {"code", "slug", "message", "status"}}`）の `code` と `slug` の全ての値です。
+/// SDKはこの一覧から定数を生成できます。
+#[utoipa::path(
+    get,
+    path = "/errors",
+    tag = "openapi",
+    responses(
+        (status = 200, description = "Every error code with its slug, HTTP status and description", body = [ErrorCodeEntry])
+    )
+)]
+async fn list_error_codes() -> Json<Vec<ErrorCodeEntry>> {
+    Json(ErrorCode::ALL.into_iter().map(ErrorCode::entry).collect())
+}
+
 /// ヘルスチェック
 #[utoipa::path(
     get,
@@ -180,6 +505,53 @@ async fn health_check() -> Result<impl IntoResponse> {
     Ok(Json(response))
 }
 
+/// 障害予測レスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PredictionsResponse {
+    /// 確率の高い順
+    predictions: Vec<Prediction>,
+    /// 障害予測によりRPCを一時停止しているか
+    rpc_paused: bool,
+}
+
+/// 障害予測を取得
+#[utoipa::path(
+    get,
+    path = "/health/predictions",
+    tag = "health",
+    responses(
+        (status = 200, description = "Predicted failures with probabilities", body = PredictionsResponse),
+        (status = 503, description = "AI optimizer is not running")
+    )
+)]
+async fn get_failure_predictions(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let optimizer = state.ai.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("AI optimizer is not running".to_string()))?;
+    Ok(Json(PredictionsResponse {
+        predictions: optimizer.lock().await.predictions().to_vec(),
+        rpc_paused: state.rpc_pause.is_paused(),
+    }))
+}
+
+/// データディレクトリのディスクの状態と、ブロックの確定を停止しているか
+#[utoipa::path(
+    get,
+    path = "/health/disk",
+    tag = "health",
+    responses(
+        (status = 200, description = "Disk usage of the data directory", body = DiskStatus),
+        (status = 404, description = "Disk watchdog is disabled"),
+        (status = 503, description = "Disk usage has not been checked yet")
+    )
+)]
+async fn get_disk_status(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let disk = state.disk.as_ref()
+        .ok_or_else(|| AppError::coded(ErrorCode::FeatureDisabled, "Disk watchdog is disabled (set disk.enabled = true)"))?;
+    let status = disk.status()
+        .ok_or_else(|| AppError::ServiceUnavailable("Disk usage has not been checked yet".to_string()))?;
+    Ok(Json(status))
+}
+
 /// メトリクスを取得
 #[utoipa::path(
     get,
@@ -192,14 +564,13 @@ async fn health_check() -> Result<impl IntoResponse> {
 )]
 async fn get_metrics(State(state): State<AppState>) -> Result<impl IntoResponse> {
     let config = &state.config;
-    let cpu_cores = sys_info::cpu_num().unwrap_or(1) as i32;
+    // コンテナの中では cgroup の制限（1未満のクォータは1コアとして表示）
+    let (cpu_cores, memory) = crate::util::cgroup::available_resources();
 
     let response = MetricsResponse {
         system: SystemMetrics {
-            cpu_cores,
-            memory_gb: sys_info::mem_info()
-                .map(|m| m.total / 1024 / 1024)
-                .unwrap_or(0),
+            cpu_cores: (cpu_cores.floor() as i32).max(1),
+            memory_gb: memory / 1024 / 1024 / 1024,
             role: config.node.role.clone(),
         },
         network: NetworkMetrics {
@@ -260,4 +631,1834 @@ async fn update_config(
         "success": true,
         "message": "Configuration updated successfully"
     })))
-}
\ No newline at end of file
+}
+
+/// 検証済みコントラクトの一覧を取得
+///
+/// ソースとABIを除いた概要をアドレスの順に返します。
+#[utoipa::path(
+    get,
+    path = "/contracts",
+    tag = "contracts",
+    params(
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`address:asc` (the only order)"),
+        ("compiler" = Option<String>, Query, description = "`solc` or `vyper`"),
+        ("match_status" = Option<String>, Query, description = "`exact` or `partial`"),
+        ("min_verified_at" = Option<u64>, Query, description = "Verified at or after (UNIX seconds)"),
+        ("max_verified_at" = Option<u64>, Query, description = "Verified at or before (UNIX seconds)")
+    ),
+    responses(
+        (status = 200, description = "Verified contracts by address", body = Page<VerifiedContractSummary>),
+        (status = 400, description = "Unknown sort or filter", body = ErrorBody)
+    )
+)]
+async fn list_contracts(
+    State(state): State<AppState>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let query = ListQuery::parse(&listing::CONTRACTS, params, &state.addresses)?;
+    Ok(Json(listing::contracts(&state, &query).await?))
+}
+
+impl From<VerificationError> for AppError {
+    fn from(e: VerificationError) -> Self {
+        match e {
+            VerificationError::UnknownContract(_) => AppError::coded(ErrorCode::ContractNotFound, e.to_string()),
+            VerificationError::Internal(e) => AppError::Internal(e.to_string()),
+            VerificationError::Busy(_) => AppError::ServiceUnavailable(e.to_string()),
+            e => AppError::BadRequest(e.to_string()),
+        }
+    }
+}
+
+/// コントラクトのソースコードを検証
+#[utoipa::path(
+    post,
+    path = "/contracts/{address}/verify",
+    tag = "contracts",
+    params(("address" = String, Path, description = "Contract address")),
+    request_body = VerificationRequest,
+    responses(
+        (status = 200, description = "Source verified", body = VerifiedContract),
+        (status = 400, description = "Compilation failed or bytecode mismatch"),
+        (status = 401, description = "Missing token (when public verification is disabled)"),
+        (status = 403, description = "Token lacks the contracts:verify scope"),
+        (status = 404, description = "Unknown contract"),
+        (status = 503, description = "Too many verifications are running")
+    )
+)]
+async fn verify_contract(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(address): Path<String>,
+    Json(request): Json<VerificationRequest>,
+) -> Result<impl IntoResponse> {
+    if !state.config.contracts.public_verification {
+        require_scope(&state, &headers, SCOPE_CONTRACTS_VERIFY)?;
+    }
+    let address = state.addresses.parse(&address)?;
+    let verified = state.contracts.verify(&address, request).await?;
+    Ok(Json(verified))
+}
+
+/// 検証済みソースコードを取得
+#[utoipa::path(
+    get,
+    path = "/contracts/{address}/source",
+    tag = "contracts",
+    params(("address" = String, Path, description = "Contract address")),
+    responses(
+        (status = 200, description = "Verified source, ABI and match status", body = VerifiedContract),
+        (status = 404, description = "Contract is not verified")
+    )
+)]
+async fn get_contract_source(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let verified = state.contracts.get_source(&address).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::coded(ErrorCode::ContractNotFound, format!("contract {} is not verified", address)))?;
+    Ok(Json(verified))
+}
+
+/// コントラクトのデプロイを取得
+///
+/// デプロイは `0x0000000000000000000000000000000000000044` 宛ての署名されたトランザクションで行い、
+/// 確定すると取得できます。
+#[utoipa::path(
+    get,
+    path = "/contracts/{address}/deployment",
+    tag = "contracts",
+    params(("address" = String, Path, description = "Contract address")),
+    responses(
+        (status = 200, description = "Deployer, transaction and static analysis findings", body = Deployment),
+        (status = 404, description = "Contract is not deployed", body = ErrorBody)
+    )
+)]
+async fn get_contract_deployment(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let deployment = state.deployments.deployment(&address).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::coded(ErrorCode::ContractNotFound, format!("contract {} is not deployed", address)))?;
+    Ok(Json(deployment))
+}
+
+impl From<DeployError> for AppError {
+    fn from(e: DeployError) -> Self {
+        AppError::coded(ErrorCode::DeploymentRejected, e.to_string())
+    }
+}
+
+/// アドレスのアップグレード履歴を取得
+///
+/// プロキシのアドレスと、過去・現在の実装アドレスのいずれでも検索できます。
+#[utoipa::path(
+    get,
+    path = "/contracts/{address}/upgrades",
+    tag = "proxies",
+    params(("address" = String, Path, description = "Proxy or implementation address")),
+    responses(
+        (status = 200, description = "Proxy record with upgrade history", body = ProxyRecord),
+        (status = 404, description = "Address is not part of an upgradeable contract")
+    )
+)]
+async fn get_contract_upgrades(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let record = state.proxies.find_by_address(&address).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::coded(ErrorCode::ProxyNotFound, format!("address {} is not part of an upgradeable contract", address)))?;
+    Ok(Json(record))
+}
+
+/// プロキシを取得
+#[utoipa::path(
+    get,
+    path = "/proxies/{id}",
+    tag = "proxies",
+    params(("id" = String, Path, description = "Logical contract ID")),
+    responses(
+        (status = 200, description = "Proxy record", body = ProxyRecord),
+        (status = 404, description = "Proxy not found")
+    )
+)]
+async fn get_proxy(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let record = state.proxies.get(&id).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::coded(ErrorCode::ProxyNotFound, format!("proxy {} not found", id)))?;
+    Ok(Json(record))
+}
+
+/// シャードのトポロジーを取得
+#[utoipa::path(
+    get,
+    path = "/shards",
+    tag = "shards",
+    responses(
+        (status = 200, description = "Validator assignment, accounts, storage, TPS and cross-shard ratio of every shard", body = [ShardTopology])
+    )
+)]
+async fn get_shards(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    Ok(Json(state.shards.read().await.topology().await))
+}
+
+/// シャード再分配の進捗を取得
+#[utoipa::path(
+    get,
+    path = "/shards/rebalance/status",
+    tag = "shards",
+    responses(
+        (status = 200, description = "Current rebalance plan and progress", body = RebalanceStatus)
+    )
+)]
+async fn get_rebalance_status(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    Ok(Json(state.shards.read().await.rebalance_status().await))
+}
+
+/// スケーリングの推奨を取得
+#[utoipa::path(
+    get,
+    path = "/shards/scaling",
+    tag = "shards",
+    responses(
+        (status = 200, description = "Desired shard and node counts with reasons", body = ScalingRecommendation)
+    )
+)]
+async fn get_scaling_recommendation(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    Ok(Json(state.shards.read().await.recommend_scaling().await))
+}
+
+/// シャードの最新のチェックポイントを取得
+#[utoipa::path(
+    get,
+    path = "/shards/{id}/checkpoint",
+    tag = "shards",
+    params(("id" = u32, Path, description = "Shard ID")),
+    responses(
+        (status = 200, description = "Latest committed state root and receipt root of the shard", body = ShardCheckpoint),
+        (status = 404, description = "Shard has no checkpoint yet")
+    )
+)]
+async fn get_shard_checkpoint(
+    State(state): State<AppState>,
+    Path(id): Path<ShardId>,
+) -> Result<impl IntoResponse> {
+    let checkpoint = state.shards.read().await.checkpoints().latest(id).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("shard {} has no checkpoint", id)))?;
+    Ok(Json(checkpoint))
+}
+
+/// 取引のレシートがチェックポイントに含まれる証明を取得
+#[utoipa::path(
+    get,
+    path = "/shards/receipts/{tx_id}/proof",
+    tag = "shards",
+    params(("tx_id" = String, Path, description = "Shard transaction ID")),
+    responses(
+        (status = 200, description = "Receipt with its Merkle proof against the checkpoint's receipt root", body = ReceiptProof),
+        (status = 404, description = "Receipt is not in a checkpoint yet")
+    )
+)]
+async fn get_receipt_proof(
+    State(state): State<AppState>,
+    Path(tx_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let proof = state.shards.read().await.checkpoints().proof(&tx_id).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("receipt {} is not checkpointed", tx_id)))?;
+    Ok(Json(proof))
+}
+
+/// レシート証明を記録済みのチェックポイントに対して検証
+#[utoipa::path(
+    post,
+    path = "/shards/receipts/verify",
+    tag = "shards",
+    request_body = ReceiptProof,
+    responses(
+        (status = 200, description = "Proof is valid; returns the checkpoint that contains the receipt", body = ShardCheckpoint),
+        (status = 400, description = "Proof does not verify")
+    )
+)]
+async fn verify_receipt_proof(
+    State(state): State<AppState>,
+    Json(proof): Json<ReceiptProof>,
+) -> Result<impl IntoResponse> {
+    let checkpoint = state.shards.read().await.checkpoints().verify(&proof).await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    Ok(Json(checkpoint))
+}
+
+/// ビーコンチェーンの現在の状態を取得
+#[utoipa::path(
+    get,
+    path = "/beacon/state",
+    tag = "beacon",
+    responses(
+        (status = 200, description = "Beacon validators, shards, per-shard validator assignments and latest checkpoints", body = BeaconState)
+    )
+)]
+async fn get_beacon_state(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    Ok(Json(state.beacon.state().await))
+}
+
+/// ビーコンチェーンで最後に確定したブロックを取得
+#[utoipa::path(
+    get,
+    path = "/beacon/head",
+    tag = "beacon",
+    responses(
+        (status = 200, description = "Latest committed beacon block with its quorum certificate", body = BeaconBlock),
+        (status = 404, description = "No beacon block has been committed yet")
+    )
+)]
+async fn get_beacon_head(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let head = state.beacon.head().await
+        .ok_or_else(|| AppError::NotFound("no beacon block has been committed".to_string()))?;
+    Ok(Json(head))
+}
+
+/// 高さを指定してビーコンのブロックを取得
+#[utoipa::path(
+    get,
+    path = "/beacon/blocks/{height}",
+    tag = "beacon",
+    params(("height" = u64, Path, description = "Beacon block height")),
+    responses(
+        (status = 200, description = "Committed beacon block", body = BeaconBlock),
+        (status = 404, description = "Beacon block not found")
+    )
+)]
+async fn get_beacon_block(
+    State(state): State<AppState>,
+    Path(height): Path<u64>,
+) -> Result<impl IntoResponse> {
+    let block = state.beacon.block(height).await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("beacon block {} not found", height)))?;
+    Ok(Json(block))
+}
+
+/// スケーリングの推奨をServer-Sent Eventsで購読
+///
+/// 接続直後に現在の推奨を送り、以降は推奨が変化するたびに `recommendation` イベントを送ります。
+#[utoipa::path(
+    get,
+    path = "/shards/scaling/events",
+    tag = "shards",
+    responses(
+        (status = 200, description = "Stream of `recommendation` events", content_type = "text/event-stream", body = ScalingRecommendation)
+    )
+)]
+async fn stream_scaling_recommendations(State(state): State<AppState>) -> impl IntoResponse {
+    let (current, receiver) = {
+        let shards = state.shards.read().await;
+        (shards.recommend_scaling().await, shards.subscribe_scaling())
+    };
+
+    let stream = futures::stream::unfold((Some(current), receiver), |(pending, mut receiver)| async move {
+        let recommendation = match pending {
+            Some(recommendation) => recommendation,
+            None => loop {
+                match receiver.recv().await {
+                    Ok(recommendation) => break recommendation,
+                    // 取りこぼした推奨は最新のもので置き換わるため無視する
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            },
+        };
+        let event = Event::default()
+            .event("recommendation")
+            .id(recommendation.sequence.to_string())
+            .json_data(&recommendation);
+        Some((event, (None, receiver)))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// 地理的ルーティングのメトリクスを取得
+#[utoipa::path(
+    get,
+    path = "/geo/metrics",
+    tag = "geo",
+    responses(
+        (status = 200, description = "Per-region hit counts and replica latency", body = GeoMetrics),
+        (status = 404, description = "Geo routing is disabled")
+    )
+)]
+async fn get_geo_metrics(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let geo = state.geo.as_ref()
+        .ok_or_else(|| AppError::coded(ErrorCode::FeatureDisabled, "Geo routing is disabled"))?;
+    Ok(Json(geo.metrics().await))
+}
+
+/// バリデーターのパフォーマンスのページ
+#[derive(Debug, Serialize, ToSchema)]
+struct PerformancePage {
+    #[serde(flatten)]
+    report: PerformanceReport,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    next_cursor: Option<String>,
+}
+
+/// バリデーターのパフォーマンスを取得
+///
+/// 委任者がステーク先を選ぶための、直近の期間の提案数・ミス率・投票の遅延です。
+#[utoipa::path(
+    get,
+    path = "/validators/performance",
+    tag = "validators",
+    params(
+        ("window" = Option<String>, Query, description = "Rolling window: `1h`, `24h` (default) or `7d`"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`proposed:desc` (default), `proposed:asc`, `missed:desc`, `missed:asc`, `miss_rate:asc`, `miss_rate:desc`, `votes:desc`, `avg_vote_latency_ms:asc` or `validator:asc`"),
+        ("max_miss_rate" = Option<f64>, Query, description = "Also `min_miss_rate`, and `proposed`, `missed`, `votes` with `min_`/`max_`")
+    ),
+    responses(
+        (status = 200, description = "Per-validator statistics in the requested order", body = PerformancePage),
+        (status = 400, description = "Unknown window, sort or filter", body = ErrorBody)
+    )
+)]
+async fn get_validator_performance(
+    State(state): State<AppState>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let query = ListQuery::parse(&listing::VALIDATORS, params, &state.addresses)?;
+    let window = query.param("window").unwrap_or(performance::DEFAULT_WINDOW);
+    let mut report = state.performance.report(window, Utc::now().timestamp().max(0) as u64).await
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown window '{}' (expected 1h, 24h or 7d)", window)))?;
+    let page = listing::validators(&query, std::mem::take(&mut report.validators))?;
+    report.validators = page.items;
+    Ok(Json(PerformancePage { report, next_cursor: page.next_cursor }))
+}
+
+/// シャドーモードの計測値を取得
+///
+/// ブロックと投票を送信せずに、このノードがバリデーターとして行った処理の件数と所要時間です。
+#[utoipa::path(
+    get,
+    path = "/validators/shadow",
+    tag = "validators",
+    responses(
+        (status = 200, description = "Blocks this node would have proposed and votes it would have sent", body = ShadowReport),
+        (status = 404, description = "The node is not running in shadow mode")
+    )
+)]
+async fn get_shadow_report(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let shadow = state.shadow.as_ref()
+        .ok_or_else(|| AppError::coded(ErrorCode::FeatureDisabled, "Shadow mode is not enabled (set validator.shadow = true)"))?;
+    Ok(Json(shadow.report().await))
+}
+
+impl From<RegistrationError> for AppError {
+    fn from(e: RegistrationError) -> Self {
+        let code = match &e {
+            RegistrationError::InvalidKey | RegistrationError::InvalidProof(_) => ErrorCode::InvalidProofOfPossession,
+            RegistrationError::BelowMinimum { .. } | RegistrationError::ExceedsBalance { .. } => ErrorCode::InsufficientSelfStake,
+            RegistrationError::AlreadyRegistered(_) => ErrorCode::ValidatorAlreadyRegistered,
+            RegistrationError::InvalidCommission(_) => ErrorCode::InvalidRequest,
+            RegistrationError::Storage(inner) => return AppError::Internal(inner.to_string()),
+        };
+        AppError::coded(code, e.to_string())
+    }
+}
+
+/// バリデーターの候補の一覧を取得
+#[utoipa::path(
+    get,
+    path = "/validators/candidates",
+    tag = "validators",
+    params(
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`address:asc` (the only order)"),
+        ("min_self_stake" = Option<u64>, Query, description = "Also `max_self_stake`, and `commission_bps`, `registered_at` with `min_`/`max_`")
+    ),
+    responses(
+        (status = 200, description = "Registered candidates by address", body = Page<ValidatorCandidate>),
+        (status = 400, description = "Unknown sort or filter", body = ErrorBody)
+    )
+)]
+async fn list_candidates(
+    State(state): State<AppState>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let query = ListQuery::parse(&listing::CANDIDATES, params, &state.addresses)?;
+    Ok(Json(listing::candidates(&state, &query).await?))
+}
+
+/// バリデーターを候補に登録
+///
+/// コンセンサス鍵の所有証明（`rustorium validator register` が作成する）と、
+/// `validator.min_stake` 以上で解放済みの残高を超えない自己ステークが必要です。
+#[utoipa::path(
+    post,
+    path = "/validators/candidates",
+    tag = "validators",
+    request_body = Registration,
+    responses(
+        (status = 200, description = "Validator added to the candidate set", body = ValidatorCandidate),
+        (status = 400, description = "Invalid proof of possession or insufficient self-stake", body = ErrorBody),
+        (status = 409, description = "The consensus key is already registered", body = ErrorBody)
+    )
+)]
+async fn register_validator(
+    State(state): State<AppState>,
+    Json(registration): Json<Registration>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(state.validators.register(&registration, Utc::now().timestamp().max(0) as u64).await?))
+}
+
+/// バリデーターの候補を取得
+#[utoipa::path(
+    get,
+    path = "/validators/candidates/{address}",
+    tag = "validators",
+    params(("address" = String, Path, description = "Validator address (consensus key)")),
+    responses(
+        (status = 200, description = "Registered candidate", body = ValidatorCandidate),
+        (status = 404, description = "Not a registered candidate", body = ErrorBody)
+    )
+)]
+async fn get_candidate(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let candidate = state.validators.candidate(&address).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::ValidatorNotFound, format!("Validator {} is not a registered candidate", address)))?;
+    Ok(Json(candidate))
+}
+
+/// 委任先の比較のページ
+#[derive(Debug, Serialize, ToSchema)]
+struct MarketPage {
+    /// 集計した期間（`1h`・`24h`・`7d`）
+    window: String,
+    window_secs: u64,
+    /// 集計した時刻（UNIX秒）
+    generated_at: u64,
+    validators: Vec<MarketEntry>,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    next_cursor: Option<String>,
+}
+
+/// 委任先の比較を取得
+///
+/// 候補ごとの手数料率・自己ステークの比率・稼働率と、期間の報酬から換算したAPRを1回で返します。
+#[utoipa::path(
+    get,
+    path = "/staking/market",
+    tag = "staking",
+    params(
+        ("window" = Option<String>, Query, description = "Window for APR and uptime: `1h`, `24h` or `7d` (default)"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`apr:desc` (default), `apr:asc`, `commission_bps:asc`, `commission_bps:desc`, `uptime:desc`, `self_stake_ratio:desc`, `self_stake:desc`, `total_stake:desc` or `validator:asc`"),
+        ("max_commission_bps" = Option<u64>, Query, description = "Also `min_commission_bps`, and `apr`, `uptime`, `self_stake`, `total_stake`, `self_stake_ratio` with `min_`/`max_`")
+    ),
+    responses(
+        (status = 200, description = "Registered candidates in the requested order", body = MarketPage),
+        (status = 400, description = "Unknown window, sort or filter", body = ErrorBody)
+    )
+)]
+async fn get_staking_market(
+    State(state): State<AppState>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let query = ListQuery::parse(&listing::STAKING_MARKET, params, &state.addresses)?;
+    let window = query.param("window").unwrap_or(market::DEFAULT_WINDOW);
+    let now = Utc::now().timestamp().max(0) as u64;
+    let (window_secs, entries) = market::market(&state.validators, &state.performance, &state.views, window, now).await?
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown window '{}' (expected 1h, 24h or 7d)", window)))?;
+    let page = listing::staking_market(&query, entries)?;
+    Ok(Json(MarketPage {
+        window: window.to_string(),
+        window_secs,
+        generated_at: now,
+        validators: page.items,
+        next_cursor: page.next_cursor,
+    }))
+}
+
+/// 報酬の見込みの条件
+#[derive(Debug, Deserialize)]
+struct SimulateQuery {
+    validator: String,
+    amount: u64,
+    days: u32,
+    window: Option<String>,
+}
+
+/// 委任の報酬の見込みを計算
+///
+/// 候補の期間の報酬と稼働率が続くと仮定し、委任した額で薄まったAPRから報酬を見積もります。
+#[utoipa::path(
+    get,
+    path = "/staking/simulate",
+    tag = "staking",
+    params(
+        ("validator" = String, Query, description = "Candidate to delegate to"),
+        ("amount" = u64, Query, description = "Amount to delegate"),
+        ("days" = u32, Query, description = "Days to project (1 to 3650)"),
+        ("window" = Option<String>, Query, description = "History to project from: `1h`, `24h` or `7d` (default)")
+    ),
+    responses(
+        (status = 200, description = "Expected rewards for the delegation", body = RewardSimulation),
+        (status = 400, description = "Zero amount, days out of range or unknown window", body = ErrorBody),
+        (status = 404, description = "Not a registered candidate", body = ErrorBody)
+    )
+)]
+async fn simulate_staking(
+    State(state): State<AppState>,
+    Query(query): Query<SimulateQuery>,
+) -> Result<impl IntoResponse> {
+    if query.amount == 0 {
+        return Err(AppError::BadRequest("amount must be positive".to_string()));
+    }
+    if !(1..=market::MAX_SIMULATION_DAYS).contains(&query.days) {
+        return Err(AppError::BadRequest(format!("days must be between 1 and {}", market::MAX_SIMULATION_DAYS)));
+    }
+    let address = state.addresses.parse(&query.validator)?;
+    let candidate = state.validators.candidate(&address).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::ValidatorNotFound, format!("Validator {} is not a registered candidate", address)))?;
+    let window = query.window.as_deref().unwrap_or(market::DEFAULT_WINDOW);
+    let now = Utc::now().timestamp().max(0) as u64;
+    let (_, entry) = market::entry(&state.performance, &state.views, &candidate, window, now).await?
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown window '{}' (expected 1h, 24h or 7d)", window)))?;
+    Ok(Json(RewardSimulation::new(&entry, query.amount, query.days)))
+}
+
+/// コントラクトの利用状況の1ページ
+#[derive(Debug, Serialize, ToSchema)]
+struct ContractUsagePage {
+    #[serde(flatten)]
+    report: ContractUsageReport,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    next_cursor: Option<String>,
+}
+
+/// ガス使用量の多いコントラクトを取得
+///
+/// 直近の期間にどのコントラクトが負荷を生んでいるか（呼び出し数・ガス使用量・失敗率）です。
+#[utoipa::path(
+    get,
+    path = "/analytics/contracts/top",
+    tag = "analytics",
+    params(
+        ("window" = Option<String>, Query, description = "Rolling window: `1h`, `24h` (default) or `7d`"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`gas_used:desc` (default), `gas_used:asc`, `calls:desc`, `calls:asc`, `failure_rate:desc`, `failures:desc`, `avg_gas_per_call:desc` or `contract:asc`"),
+        ("min_calls" = Option<u64>, Query, description = "Also `max_calls`, and `gas_used`, `failures`, `failure_rate` with `min_`/`max_`")
+    ),
+    responses(
+        (status = 200, description = "Per-contract usage in the requested order", body = ContractUsagePage),
+        (status = 400, description = "Unknown window, sort or filter", body = ErrorBody)
+    )
+)]
+async fn get_top_contracts(
+    State(state): State<AppState>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let query = ListQuery::parse(&listing::TOP_CONTRACTS, params, &state.addresses)?;
+    let window = query.param("window").unwrap_or(performance::DEFAULT_WINDOW);
+    let mut report = state.contract_metrics.report(window, Utc::now().timestamp().max(0) as u64).await
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown window '{}' (expected 1h, 24h or 7d)", window)))?;
+    let page = listing::top_contracts(&query, std::mem::take(&mut report.contracts))?;
+    report.contracts = page.items;
+    Ok(Json(ContractUsagePage { report, next_cursor: page.next_cursor }))
+}
+
+/// 接続中のピアと役割の内訳
+#[derive(Debug, Serialize, ToSchema)]
+struct PeersResponse {
+    total: usize,
+    /// 役割ごとのピアの数（ハンドシェイクが済んでいないピアは `unknown`）
+    roles: BTreeMap<String, usize>,
+    peers: Vec<PeerSummary>,
+}
+
+/// 接続中のピアを取得
+///
+/// ハンドシェイクで通知された役割と機能を含みます。セントリーノードの背後のバリデーターは含みません。
+#[utoipa::path(
+    get,
+    path = "/network/peers",
+    tag = "network",
+    responses(
+        (status = 200, description = "Connected peers and the mix of their roles", body = PeersResponse)
+    )
+)]
+async fn get_network_peers(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let peers = state.network.peer_summaries().await;
+    let mut roles: BTreeMap<String, usize> = NodeRole::ALL.iter()
+        .map(|role| (role.to_string(), 0))
+        .collect();
+    for peer in &peers {
+        let role = peer.role.map_or_else(|| "unknown".to_string(), |role| role.to_string());
+        *roles.entry(role).or_default() += 1;
+    }
+    Ok(Json(PeersResponse { total: peers.len(), roles, peers }))
+}
+
+/// 表示言語
+#[derive(Debug, Serialize, ToSchema)]
+struct LanguagesResponse {
+    /// ノードの表示言語（Web UIの既定）
+    language: String,
+    /// 翻訳のある言語
+    languages: Vec<String>,
+}
+
+/// 表示言語と翻訳のある言語の一覧を取得
+#[utoipa::path(
+    get,
+    path = "/i18n",
+    tag = "i18n",
+    responses(
+        (status = 200, description = "Node language and available translations", body = LanguagesResponse)
+    )
+)]
+async fn get_languages(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    Ok(Json(LanguagesResponse {
+        language: state.locale.language(),
+        languages: state.locale.languages(),
+    }))
+}
+
+/// 言語のメッセージを取得
+///
+/// 翻訳のないメッセージはフォールバックの言語（最後は英語）で補います。
+#[utoipa::path(
+    get,
+    path = "/i18n/{language}",
+    tag = "i18n",
+    params(("language" = String, Path, description = "Language tag, e.g. ja or pt-BR")),
+    responses(
+        (status = 200, description = "Messages keyed by message ID", body = BTreeMap<String, String>),
+        (status = 404, description = "No translation for the language")
+    )
+)]
+async fn get_messages(State(state): State<AppState>, Path(language): Path<String>) -> Result<impl IntoResponse> {
+    if !state.locale.supports(&language) {
+        return Err(AppError::NotFound(format!("No translation for language '{}'", language)));
+    }
+    Ok(Json(state.locale.messages(Some(&language))))
+}
+
+/// ブロックの一覧を取得
+///
+/// 確定したブロックの概要を新しい順に返します。
+#[utoipa::path(
+    get,
+    path = "/blocks",
+    tag = "blocks",
+    params(
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of blocks (at most 100)"),
+        ("sort" = Option<String>, Query, description = "`height:desc` (default) or `height:asc`"),
+        ("validator" = Option<String>, Query, description = "Only blocks proposed by this validator"),
+        ("min_timestamp" = Option<u64>, Query, description = "Also `max_timestamp`, and `transaction_count`, `gas_used` with `min_`/`max_`")
+    ),
+    responses(
+        (status = 200, description = "Block summaries in the requested order", body = BlockPage),
+        (status = 400, description = "Invalid cursor, sort or filter", body = ErrorBody)
+    )
+)]
+async fn list_blocks(
+    State(state): State<AppState>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let query = ListQuery::parse(&listing::BLOCKS, params, &state.addresses)?;
+    Ok(Json(listing::blocks(&state, &query).await?))
+}
+
+/// ハッシュを指定してブロックを取得
+#[utoipa::path(
+    get,
+    path = "/blocks/hash/{hash}",
+    tag = "blocks",
+    params(("hash" = String, Path, description = "Block hash, with or without 0x")),
+    responses(
+        (status = 200, description = "Committed block", body = Block),
+        (status = 404, description = "No committed block with this hash")
+    )
+)]
+async fn get_block_by_hash(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse> {
+    let block = state.chain.get_block_by_hash(&hash).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::BlockNotFound, format!("Block {} not found", hash)))?;
+    Ok(Json(block))
+}
+
+/// 高さを指定してブロックを取得
+///
+/// 読み取り専用レプリカはこのエンドポイントから上流のブロックを同期します。
+#[utoipa::path(
+    get,
+    path = "/blocks/{height}",
+    tag = "blocks",
+    params(("height" = u64, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "Committed block", body = Block),
+        (status = 404, description = "No block at this height yet")
+    )
+)]
+async fn get_block(
+    State(state): State<AppState>,
+    Path(height): Path<u64>,
+) -> Result<impl IntoResponse> {
+    let block = state.chain.get_block(height).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::BlockNotFound, format!("Block {} not found", height)))?;
+    Ok(Json(block))
+}
+
+/// 高さを指定してブロックの乱数ビーコンを取得
+///
+/// `source` が `vrf` の値は生成者の VRF 出力から導出され、証明と公開鍵で検証できます。
+/// `block_hash` の値は VRF 証明のない導入前のブロックのもので、生成者が操作できます。
+/// `header_activation_height` 以降の高さでは返しません。
+#[utoipa::path(
+    get,
+    path = "/blocks/{height}/randomness",
+    tag = "blocks",
+    params(("height" = u64, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "Randomness of the committed block", body = Randomness),
+        (status = 404, description = "No block at this height yet")
+    )
+)]
+async fn get_block_randomness(
+    State(state): State<AppState>,
+    Path(height): Path<u64>,
+) -> Result<impl IntoResponse> {
+    let randomness = state.chain.randomness(height).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::BlockNotFound, format!("Block {} not found", height)))?;
+    Ok(Json(randomness))
+}
+
+/// 孤立ブロックを取得
+///
+/// フォーク選択で負けたブロックと親が不正なブロックを、新しい高さの順に返します。
+#[utoipa::path(
+    get,
+    path = "/blocks/orphans",
+    tag = "blocks",
+    params(
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of blocks (at most 1000)")
+    ),
+    responses(
+        (status = 200, description = "Orphaned blocks, highest first, with counts by reason", body = OrphanPage)
+    )
+)]
+async fn get_block_orphans(
+    State(state): State<AppState>,
+    Query(query): Query<PageQuery>,
+) -> Result<impl IntoResponse> {
+    let limit = query.limit.unwrap_or(listing::DEFAULT_LIMIT);
+    Ok(Json(state.chain.orphans(query.cursor.as_deref(), limit).await?))
+}
+
+/// メモリプールの取得条件
+#[derive(Debug, Deserialize)]
+struct MempoolQuery {
+    /// トランザクションの一覧を含める
+    #[serde(default)]
+    contents: bool,
+    from: Option<String>,
+    min_gas_price: Option<u64>,
+    max_gas_price: Option<u64>,
+    limit: Option<usize>,
+}
+
+/// メモリプールの集計と内容
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MempoolResponse {
+    #[serde(flatten)]
+    summary: MempoolSummary,
+    /// 条件に一致するトランザクション（`contents=true` の場合のみ、ガス価格の高い順）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transactions: Option<Vec<PendingTransaction>>,
+}
+
+/// メモリプールの集計と内容を取得
+///
+/// 集計は誰でも取得できます。トランザクションの一覧（`contents=true`）には
+/// `mempool:read` の権限を持つトークンが必要です。
+#[utoipa::path(
+    get,
+    path = "/mempool",
+    tag = "mempool",
+    params(
+        ("contents" = Option<bool>, Query, description = "Include matching transactions (requires the mempool:read scope)"),
+        ("from" = Option<String>, Query, description = "Only transactions from this sender"),
+        ("min_gas_price" = Option<u64>, Query, description = "Minimum gas price, inclusive"),
+        ("max_gas_price" = Option<u64>, Query, description = "Maximum gas price, inclusive"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of transactions (at most 5000)")
+    ),
+    responses(
+        (status = 200, description = "Counts, fee and age histograms, and optionally the transactions", body = MempoolResponse),
+        (status = 401, description = "Contents requested without a valid token"),
+        (status = 403, description = "Token lacks the mempool:read scope")
+    )
+)]
+async fn get_mempool(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MempoolQuery>,
+) -> Result<impl IntoResponse> {
+    if query.contents {
+        require_scope(&state, &headers, SCOPE_MEMPOOL_READ)?;
+    }
+    let filter = ContentFilter {
+        from: query.from.as_deref().map(|from| state.addresses.parse(from)).transpose()?,
+        min_gas_price: query.min_gas_price,
+        max_gas_price: query.max_gas_price,
+    };
+    let mempool = state.mempool.read().await;
+    let summary = mempool.summary(Utc::now().timestamp().max(0) as u64);
+    let transactions = query.contents
+        .then(|| mempool.content(&filter, query.limit.unwrap_or(listing::DEFAULT_LIMIT).clamp(1, MAX_CONTENT_LIMIT)));
+    Ok(Json(MempoolResponse { summary, transactions }))
+}
+
+/// ガス価格を提案
+///
+/// 直近のブロックで取り込まれた価格とメモリプールの深さから、取り込まれるまでの
+/// 目標のブロック数ごとの価格を返します。
+#[utoipa::path(
+    get,
+    path = "/fees/suggest",
+    tag = "mempool",
+    responses(
+        (status = 200, description = "Suggested gas prices for slow, standard and fast inclusion", body = FeeSuggestion)
+    )
+)]
+async fn suggest_fees(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let mempool = state.mempool.read().await;
+    Ok(Json(state.fees.suggest(&state.chain, &mempool).await))
+}
+
+/// 取り込みの見込みのクエリ
+#[derive(Debug, Deserialize)]
+struct QuoteQuery {
+    gas_price: u64,
+    gas: u64,
+    blocks: Option<u64>,
+}
+
+/// 取り込みの見込みを取得
+///
+/// 指定したガス価格とガスのトランザクションが、次の `blocks` 個のブロック以内に取り込まれる確率と
+/// 見込みの時間です。引き出しの手数料の段階を決めるためなどに使います。
+#[utoipa::path(
+    get,
+    path = "/fees/quote",
+    tag = "mempool",
+    params(
+        ("gas_price" = u64, Query, description = "Gas price to quote"),
+        ("gas" = u64, Query, description = "Gas limit of the transaction"),
+        ("blocks" = Option<u64>, Query, description = "Number of upcoming blocks (default 10, at most 100)")
+    ),
+    responses(
+        (status = 200, description = "Probability and expected time of inclusion", body = InclusionQuote),
+        (status = 400, description = "Zero gas or blocks out of range", body = ErrorBody)
+    )
+)]
+async fn quote_inclusion(
+    State(state): State<AppState>,
+    Query(query): Query<QuoteQuery>,
+) -> Result<impl IntoResponse> {
+    let blocks = query.blocks.unwrap_or(fees::DEFAULT_QUOTE_BLOCKS);
+    if !(1..=fees::MAX_QUOTE_BLOCKS).contains(&blocks) {
+        return Err(AppError::BadRequest(format!("blocks must be between 1 and {}", fees::MAX_QUOTE_BLOCKS)));
+    }
+    if query.gas == 0 {
+        return Err(AppError::BadRequest("gas must be greater than 0".to_string()));
+    }
+    let mempool = state.mempool.read().await;
+    Ok(Json(state.fees.quote(
+        &state.chain,
+        &mempool,
+        query.gas_price,
+        query.gas,
+        blocks,
+        state.config.performance.block_time,
+    ).await))
+}
+
+/// トランザクションの送信リクエスト
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SubmitTransactionRequest {
+    from: String,
+    to: String,
+    value: u64,
+    nonce: u64,
+    gas_price: u64,
+    gas_limit: u64,
+    /// データ（hex）
+    #[serde(default)]
+    data: String,
+    /// 有効期限（UNIX秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    valid_until: Option<u64>,
+    /// 署名の対象のネットワークのチェーンID（署名する場合は必須）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chain_id: Option<u64>,
+    /// 送信者の署名（省略可、指定した場合は検証される）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<TxSignature>,
+    /// メモ（`data` の代わりに指定し、メモの形式にエンコードされる）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    memo: Option<Memo>,
+    /// ブロブのデータ（hex）。サイドカーとして保持され、トランザクションはコミットメントのみを持つ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blob: Option<String>,
+    /// 支払うブロブ手数料の上限（1バイトあたり、`blob` を指定する場合は必須）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_fee_per_blob_byte: Option<u64>,
+}
+
+impl SubmitTransactionRequest {
+    /// メモリプールのトランザクションとブロブのデータに変換（アドレスは内部表記にし、ハッシュも計算する）
+    fn into_pending(self, addresses: &AddressFormat, received_at: u64) -> Result<(PendingTransaction, Option<Vec<u8>>)> {
+        let data = request_data(&self.data, self.memo)?;
+        let sidecar = match &self.blob {
+            Some(blob) => Some(hex::decode(blob.trim_start_matches("0x"))
+                .map_err(|e| AppError::coded(ErrorCode::InvalidBlob, format!("invalid blob: {}", e)))?),
+            None => None,
+        };
+        let blob = match (&sidecar, self.max_fee_per_blob_byte) {
+            (Some(data), Some(max_fee_per_byte)) => Some(BlobRef::new(data, max_fee_per_byte)),
+            (Some(_), None) => {
+                return Err(AppError::coded(ErrorCode::InvalidBlob, "max_fee_per_blob_byte is required with a blob"));
+            }
+            (None, Some(_)) => {
+                return Err(AppError::coded(ErrorCode::InvalidBlob, "max_fee_per_blob_byte is only valid with a blob"));
+            }
+            (None, None) => None,
+        };
+        let mut tx = PendingTransaction {
+            hash: String::new(),
+            from: addresses.parse(&self.from)?,
+            to: addresses.parse(&self.to)?,
+            value: self.value,
+            nonce: self.nonce,
+            gas_price: self.gas_price,
+            gas_limit: self.gas_limit,
+            data,
+            received_at,
+            valid_until: self.valid_until,
+            chain_id: self.chain_id,
+            blob,
+            signature: self.signature,
+        };
+        tx.hash = tx.compute_hash();
+        Ok((tx, sidecar))
+    }
+}
+
+/// リクエストの `data`（hex）か `memo` をトランザクションのデータにする
+fn request_data(data: &str, memo: Option<Memo>) -> Result<Vec<u8>> {
+    match memo {
+        Some(_) if !data.is_empty() => Err(AppError::coded(ErrorCode::InvalidData, "data and memo cannot both be set")),
+        Some(memo) => Ok(Memo::new(&memo.tag, &memo.payload)?.encode()),
+        None => hex::decode(data.trim_start_matches("0x"))
+            .map_err(|e| AppError::coded(ErrorCode::InvalidData, format!("invalid data: {}", e))),
+    }
+}
+
+/// 署名前のトランザクションの作成リクエスト
+///
+/// 省略した値はノードが補います（ノンスは保留中のものを含めた次の値、ガス価格は標準の提案）。
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UnsignedTransactionRequest {
+    from: String,
+    to: String,
+    #[serde(default)]
+    value: u64,
+    #[serde(default)]
+    nonce: Option<u64>,
+    #[serde(default)]
+    gas_price: Option<u64>,
+    #[serde(default = "default_gas_limit")]
+    gas_limit: u64,
+    /// データ（hex）
+    #[serde(default)]
+    data: String,
+    #[serde(default)]
+    memo: Option<Memo>,
+    /// 有効期限（UNIX秒）
+    #[serde(default)]
+    valid_until: Option<u64>,
+}
+
+fn default_gas_limit() -> u64 {
+    21_000
+}
+
+/// 監視専用のアカウントから署名前のトランザクションを作成
+///
+/// 返した本文を鍵のある端末で署名し（`rustorium tx sign`）、署名済みの本文を
+/// `POST /transactions` に送信します。メモリプールには追加しません。
+#[utoipa::path(
+    post,
+    path = "/transactions/unsigned",
+    tag = "transactions",
+    request_body = UnsignedTransactionRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction to sign on another device", body = UnsignedTransaction),
+        (status = 400, description = "Invalid address, data or memo")
+    )
+)]
+async fn build_unsigned_transaction(
+    State(state): State<AppState>,
+    Json(request): Json<UnsignedTransactionRequest>,
+) -> Result<impl IntoResponse> {
+    let from = state.addresses.parse(&request.from)?;
+    let to = state.addresses.parse(&request.to)?;
+    let data = request_data(&request.data, request.memo)?;
+    let mempool = state.mempool.read().await;
+    let nonce = match request.nonce {
+        Some(nonce) => nonce,
+        None => {
+            let committed = state.views.next_nonce(&from).await?;
+            mempool.next_nonce(&from).map_or(committed, |pending| pending.max(committed))
+        }
+    };
+    let gas_price = match request.gas_price {
+        Some(gas_price) => gas_price,
+        None => state.fees.suggest(&state.chain, &mempool).await.standard.gas_price,
+    };
+    let tx = PendingTransaction {
+        hash: String::new(),
+        from,
+        to,
+        value: request.value,
+        nonce,
+        gas_price,
+        gas_limit: request.gas_limit,
+        data,
+        received_at: 0,
+        valid_until: request.valid_until,
+        chain_id: None,
+        blob: None,
+        signature: None,
+    };
+    Ok(Json(UnsignedTransaction::new(&tx, state.config.consensus.chain_id)))
+}
+
+/// トランザクションの送信レスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubmitTransactionResponse {
+    hash: String,
+}
+
+impl From<AddressError> for AppError {
+    fn from(e: AddressError) -> Self {
+        AppError::coded(ErrorCode::InvalidAddress, e.to_string())
+    }
+}
+
+impl From<MemoError> for AppError {
+    fn from(e: MemoError) -> Self {
+        AppError::coded(ErrorCode::InvalidMemo, e.to_string())
+    }
+}
+
+impl From<AdmissionError> for AppError {
+    fn from(e: AdmissionError) -> Self {
+        let code = match &e {
+            AdmissionError::FeeTooLow { .. } => ErrorCode::FeeTooLow,
+            AdmissionError::TooManyPending { .. } => ErrorCode::TooManyPending,
+            AdmissionError::DataTooLarge { .. } => ErrorCode::DataTooLarge,
+            AdmissionError::Duplicate(_) => ErrorCode::DuplicateTransaction,
+            AdmissionError::PoolFull(_) => ErrorCode::MempoolFull,
+            AdmissionError::Denied(_) => ErrorCode::AddressDenied,
+            AdmissionError::Expired { .. } => ErrorCode::TransactionExpired,
+            AdmissionError::ReplacementUnderpriced { .. } => ErrorCode::ReplacementUnderpriced,
+            AdmissionError::InvalidSignature(_) => ErrorCode::InvalidSignature,
+            AdmissionError::InvalidMemo(_) => ErrorCode::InvalidMemo,
+        };
+        AppError::coded(code, e.to_string())
+    }
+}
+
+impl From<BlobError> for AppError {
+    fn from(e: BlobError) -> Self {
+        match e {
+            BlobError::PendingFull { .. } => AppError::coded(ErrorCode::BlobPoolFull, e.to_string()),
+            e => AppError::coded(ErrorCode::InvalidBlob, e.to_string()),
+        }
+    }
+}
+
+/// トランザクションを送信
+///
+/// 読み取り専用レプリカではメモリプールに追加せず、上流のシーケンサー／バリデーターへ転送します。
+/// `Idempotency-Key` ヘッダーを付けた場合、同じキーと内容の再送は新しいトランザクションを作らず、
+/// 最初に受け付けたハッシュを `Idempotent-Replayed: true` ヘッダー付きで返します。
+/// `blob` を指定した場合、データはブロックに含めずサイドカーとして保持し、確定後は
+/// `GET /blobs/{commitment}` で保持期間の間だけ取得できます。
+#[utoipa::path(
+    post,
+    path = "/transactions",
+    tag = "transactions",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key (1 to 255 visible ASCII characters) that makes retries return the original hash")),
+    request_body = SubmitTransactionRequest,
+    responses(
+        (status = 200, description = "Transaction accepted into the mempool, or the original hash of a retried Idempotency-Key", body = SubmitTransactionResponse),
+        (status = 400, description = "Rejected by the admission policy, expired, an underpriced replacement, an invalid signature, or an invalid blob"),
+        (status = 403, description = "Address denied by the access policy"),
+        (status = 409, description = "A request with the same Idempotency-Key is still being processed"),
+        (status = 422, description = "The Idempotency-Key was already used for a different request"),
+        (status = 503, description = "Upstream unreachable (RPC replica), or the blob does not fit in the pending sidecar pool")
+    )
+)]
+async fn submit_transaction(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SubmitTransactionRequest>,
+) -> Result<Response> {
+    let key = idempotency::key(&headers)?;
+    if let Some(forwarder) = &state.forwarder {
+        return forwarder.forward(&request, key.as_deref()).await
+            .map_err(|e| AppError::coded(ErrorCode::ForwardFailed, format!("Failed to forward transaction: {}", e)));
+    }
+
+    let Some(key) = key else {
+        let hash = add_transaction(&state, request).await?;
+        return Ok(Json(SubmitTransactionResponse { hash }).into_response());
+    };
+    let fingerprint = idempotency::fingerprint(&request)?;
+    let scope = idempotency::scope(&headers, &request.from);
+    let pending = match state.idempotency.begin(&scope, &key, &fingerprint, std::time::Instant::now())? {
+        Reservation::New(pending) => pending,
+        Reservation::Replay(hash) => {
+            return Ok(([(REPLAYED_HEADER, "true")], Json(SubmitTransactionResponse { hash })).into_response());
+        }
+    };
+    // 拒否・エラー・リクエストの中断では `pending` の破棄で予約を解除する
+    let hash = add_transaction(&state, request).await?;
+    pending.complete(&hash);
+    Ok(Json(SubmitTransactionResponse { hash }).into_response())
+}
+
+/// 送信を検証してメモリプールに追加し、ハッシュを返す
+async fn add_transaction(state: &AppState, request: SubmitTransactionRequest) -> Result<String> {
+    let (tx, sidecar) = request.into_pending(&state.addresses, Utc::now().timestamp() as u64)?;
+    if let Some(blob) = &tx.blob {
+        state.chain.params().check_blob(blob)?;
+    }
+    state.names.check(&tx).await.map_err(|e| AppError::coded(ErrorCode::NameOperationRejected, e.to_string()))?;
+    state.htlc.check(&tx).await.map_err(|e| AppError::coded(ErrorCode::HtlcOperationRejected, e.to_string()))?;
+    state.deployments.check(&tx).await?;
+    state.proxies.check(&tx).await.map_err(|e| AppError::coded(ErrorCode::ProxyOperationRejected, e.to_string()))?;
+    state.vesting.check(&tx).await.map_err(|e| AppError::coded(ErrorCode::BalanceLocked, e.to_string()))?;
+    state.shards.read().await.check(&tx).await.map_err(|e| AppError::coded(ErrorCode::NonceAlreadyUsed, e.to_string()))?;
+    // 先に実行される保留中の送金を差し引いた残高で足りるか
+    let balance = state.views.balance(&tx.from).await?;
+    let pending = state.mempool.read().await.pending_value_before(&tx.from, tx.nonce);
+    if tx.value > balance.saturating_sub(pending) {
+        return Err(AppError::coded(ErrorCode::InsufficientBalance, format!(
+            "value {} exceeds the balance {} of {} ({} pending)", tx.value, balance, tx.from, pending,
+        )));
+    }
+    #[cfg(feature = "confidential-tx")]
+    state.confidential.check(&tx).await.map_err(|e| AppError::coded(ErrorCode::ConfidentialTransferRejected, e.to_string()))?;
+    // サイドカーを先に保持する（メモリプールに拒否された場合は古いものから破棄される）
+    if let Some(data) = sidecar {
+        state.blobs.add_pending(data).await?;
+    }
+    Ok(state.mempool.write().await.add(tx)?)
+}
+
+/// コミットメントを指定してブロブのサイドカーを取得
+///
+/// 確定前のサイドカーも返します。確定後 `blobs.retention_blocks` ブロックを過ぎたものは削除されます。
+#[utoipa::path(
+    get,
+    path = "/blobs/{commitment}",
+    tag = "transactions",
+    params(("commitment" = String, Path, description = "SHA-256 of the blob data, with or without 0x")),
+    responses(
+        (status = 200, description = "Blob sidecar with its data", body = BlobSidecar),
+        (status = 404, description = "Blob unknown to this node or no longer retained")
+    )
+)]
+async fn get_blob(
+    State(state): State<AppState>,
+    Path(commitment): Path<String>,
+) -> Result<impl IntoResponse> {
+    let sidecar = state.blobs.get(&commitment).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::BlobNotFound, format!("Blob {} not found", commitment)))?;
+    Ok(Json(sidecar))
+}
+
+/// ノンスのレスポンス
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NonceResponse {
+    address: String,
+    /// 次のトランザクションに使うノンス（保留中のものを含めて計算）
+    next_nonce: u64,
+    /// 現在受け付けられる最低ガス価格
+    gas_price: u64,
+    /// 署名に含めるチェーンID
+    chain_id: u64,
+}
+
+/// アドレスの次のノンスと現在のガス価格を取得
+///
+/// `rustorium tx build` はオンラインの場合、指定のない値をここから補います。
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/nonce",
+    tag = "transactions",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "Next nonce and the current fee floor", body = NonceResponse)
+    )
+)]
+async fn get_account_nonce(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let committed = state.views.next_nonce(&address).await?;
+    let mempool = state.mempool.read().await;
+    Ok(Json(NonceResponse {
+        next_nonce: mempool.next_nonce(&address).map_or(committed, |pending| pending.max(committed)),
+        gas_price: mempool.current_fee_floor(),
+        chain_id: state.config.consensus.chain_id,
+        address,
+    }))
+}
+
+/// ハッシュを指定して確定したトランザクションを取得
+///
+/// ブロックの高さと位置、レシートを含めて返します。メモリプールのトランザクションは含みません。
+#[utoipa::path(
+    get,
+    path = "/transactions/{hash}",
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Transaction hash, with or without 0x")),
+    responses(
+        (status = 200, description = "Committed transaction with its receipt", body = TransactionDetail),
+        (status = 404, description = "No committed transaction with this hash")
+    )
+)]
+async fn get_transaction(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse> {
+    let detail = state.chain.find_transaction(&hash).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::TransactionNotFound, format!("Transaction {} not found", hash)))?;
+    Ok(Json(detail))
+}
+
+/// 正規化ハッシュのレスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HashTxResponse {
+    /// トランザクションのハッシュ（送信後のIDと同じ）
+    hash: String,
+    /// ハッシュの対象となった正規化JSON
+    canonical: String,
+}
+
+/// 署名前のトランザクション本体の正規化ハッシュを計算
+///
+/// 送信前にIDを求めるためのもので、メモリプールには追加しません。
+/// 計算手順は `core::types` のモジュールドキュメントを参照してください。
+#[utoipa::path(
+    post,
+    path = "/utils/hash-tx",
+    tag = "utils",
+    request_body = SubmitTransactionRequest,
+    responses(
+        (status = 200, description = "Canonical hash of the transaction body", body = HashTxResponse),
+        (status = 400, description = "Invalid transaction body")
+    )
+)]
+async fn hash_transaction(
+    State(state): State<AppState>,
+    Json(request): Json<SubmitTransactionRequest>,
+) -> Result<impl IntoResponse> {
+    let (tx, _) = request.into_pending(&state.addresses, 0)?;
+    Ok(Json(HashTxResponse {
+        canonical: canonical_json(&tx.canonical_body()),
+        hash: tx.hash,
+    }))
+}
+
+/// QRコードにできる最大バイト数（署名前・署名済みのトランザクションのJSONを想定）
+const MAX_QR_BYTES: usize = 2048;
+
+/// テキストのQRコード（SVG）
+///
+/// Web UI が署名前のトランザクションを別の端末に渡すためのもので、本文をそのまま符号化します。
+#[utoipa::path(
+    post,
+    path = "/utils/qr",
+    tag = "utils",
+    request_body(content = String, content_type = "text/plain"),
+    responses(
+        (status = 200, description = "QR code as SVG", content_type = "image/svg+xml", body = String),
+        (status = 400, description = "Text is empty or longer than 2048 bytes")
+    )
+)]
+async fn render_qr(text: String) -> Result<Response> {
+    if text.is_empty() || text.len() > MAX_QR_BYTES {
+        return Err(AppError::BadRequest(format!("text must be 1 to {} bytes", MAX_QR_BYTES)));
+    }
+    let code = qrcode::QrCode::with_error_correction_level(text.as_bytes(), qrcode::EcLevel::L)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let svg = code.render::<qrcode::render::svg::Color>()
+        .min_dimensions(320, 320)
+        .build();
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
+
+/// アドレスの表記
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AddressResponse {
+    /// 内部表記（小文字の hex）
+    hex: String,
+    /// このネットワークの bech32m 表記
+    bech32: String,
+}
+
+/// アドレスを検証し、hex と bech32m の両方の表記を取得
+#[utoipa::path(
+    get,
+    path = "/utils/address/{address}",
+    tag = "utils",
+    params(("address" = String, Path, description = "Address in hex or bech32m form")),
+    responses(
+        (status = 200, description = "Both forms of the address", body = AddressResponse),
+        (status = 400, description = "Invalid checksum, wrong network prefix, or malformed address")
+    )
+)]
+async fn convert_address(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let hex = state.addresses.parse(&address)?;
+    Ok(Json(AddressResponse {
+        bech32: state.addresses.encode(&hex)?,
+        hex,
+    }))
+}
+
+/// 残高レスポンス
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BalanceResponse {
+    address: String,
+    balance: u64,
+    /// ビューに反映済みのブロックの高さ
+    height: Option<u64>,
+}
+
+/// アドレスの残高を取得
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/balance",
+    tag = "explorer",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "Balance from the materialized view", body = BalanceResponse)
+    )
+)]
+async fn get_account_balance(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    Ok(Json(BalanceResponse {
+        balance: state.views.balance(&address).await?,
+        height: state.views.applied_height().await?,
+        address,
+    }))
+}
+
+/// アドレスのベスティングの状態を取得
+///
+/// スケジュールのないアドレスは `locked` が0で、残高の全額が `unlocked` です。
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/vesting",
+    tag = "explorer",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "Unlocked and locked balance with each vesting grant", body = VestingReport)
+    )
+)]
+async fn get_account_vesting(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    Ok(Json(state.vesting.report(&address, Utc::now().timestamp().max(0) as u64).await?))
+}
+
+/// 索引のページ指定
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+/// アドレスのトランザクション履歴を取得
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/transactions",
+    tag = "explorer",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of transactions (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`height:desc` (the only order)"),
+        ("direction" = Option<String>, Query, description = "`in` or `out`"),
+        ("counterparty" = Option<String>, Query, description = "Only transactions with this address"),
+        ("min_value" = Option<u64>, Query, description = "Also `max_value`, and `timestamp` with `min_`/`max_`")
+    ),
+    responses(
+        (status = 200, description = "Transactions sent or received, newest first", body = ArchivePage<AddressTx>),
+        (status = 400, description = "Invalid cursor, sort or filter", body = ErrorBody)
+    )
+)]
+async fn get_account_transactions(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let query = ListQuery::parse(&listing::ACCOUNT_TRANSACTIONS, params, &state.addresses)?;
+    Ok(Json(listing::account_transactions(&state, &address, &query).await?))
+}
+
+/// トークンの保有者を取得
+#[utoipa::path(
+    get,
+    path = "/tokens/{address}/holders",
+    tag = "explorer",
+    params(
+        ("address" = String, Path, description = "Token contract address"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of holders (at most 1000)"),
+        ("sort" = Option<String>, Query, description = "`balance:desc` (default), `balance:asc` or `address:asc`"),
+        ("min_balance" = Option<u64>, Query, description = "Also `max_balance` and `balance`")
+    ),
+    responses(
+        (status = 200, description = "Holders in the requested order", body = Page<TokenHolder>),
+        (status = 400, description = "Invalid cursor, sort or filter", body = ErrorBody)
+    )
+)]
+async fn get_token_holders(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let address = state.addresses.parse(&address)?;
+    let query = ListQuery::parse(&listing::TOKEN_HOLDERS, params, &state.addresses)?;
+    Ok(Json(listing::token_holders(&state, &address, &query).await?))
+}
+
+/// アーカイブの出力形式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ArchiveFormat {
+    #[default]
+    Json,
+    /// 全ページをCSVとしてストリーミング
+    Csv,
+}
+
+/// アーカイブの検索条件
+#[derive(Debug, Deserialize)]
+struct ArchiveQuery {
+    /// 対象のアドレス（`/archive/transactions` のみ）
+    address: Option<String>,
+    /// 受信したトランザクションをメモのタグで絞り込む（`/archive/transactions` のみ）
+    memo_tag: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    format: ArchiveFormat,
+}
+
+impl ArchiveQuery {
+    fn range(&self) -> ArchiveRange {
+        ArchiveRange { from: self.from, to: self.to }
+    }
+}
+
+/// アーカイブのアドレスの全トランザクションを取得
+#[utoipa::path(
+    get,
+    path = "/archive/transactions",
+    tag = "archive",
+    params(
+        ("address" = String, Query, description = "Account address"),
+        ("memo_tag" = Option<String>, Query, description = "Only transactions received with a memo of this tag (e.g. `deposit`)"),
+        ("from" = Option<u64>, Query, description = "Start of the time range (UNIX seconds, inclusive)"),
+        ("to" = Option<u64>, Query, description = "End of the time range (UNIX seconds, inclusive)"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (max 1000)"),
+        ("format" = Option<String>, Query, description = "`json` (default) or `csv` to stream every page")
+    ),
+    responses(
+        (status = 200, description = "Transactions sent or received, oldest first", body = ArchivePage<AddressTx>),
+        (status = 400, description = "Missing address or invalid memo tag")
+    )
+)]
+async fn get_archive_transactions(
+    State(state): State<AppState>,
+    Query(query): Query<ArchiveQuery>,
+) -> Result<Response> {
+    let address = query.address.as_deref()
+        .ok_or_else(|| AppError::BadRequest("address is required".to_string()))?;
+    let address = state.addresses.parse(address)?;
+    let range = query.range();
+    let memo_tag = match &query.memo_tag {
+        Some(tag) => Some(Memo::new(tag, "")?.tag),
+        None => None,
+    };
+
+    if let ArchiveFormat::Csv = query.format {
+        let views = state.views.clone();
+        let filename = format!("transactions-{}.csv", address);
+        return Ok(stream_csv(filename, query.cursor, move |cursor| {
+            let views = views.clone();
+            let address = address.clone();
+            let memo_tag = memo_tag.clone();
+            async move {
+                match memo_tag {
+                    Some(tag) => views.memo_transactions(&address, &tag, range, cursor.as_deref(), MAX_ARCHIVE_PAGE).await,
+                    None => views.archive_transactions(&address, range, cursor.as_deref(), MAX_ARCHIVE_PAGE).await,
+                }
+            }
+        }));
+    }
+
+    let limit = query.limit.unwrap_or(listing::DEFAULT_LIMIT);
+    let cursor = query.cursor.as_deref();
+    let page = match memo_tag {
+        Some(tag) => state.views.memo_transactions(&address, &tag, range, cursor, limit).await?,
+        None => state.views.archive_transactions(&address, range, cursor, limit).await?,
+    };
+    Ok(Json(page).into_response())
+}
+
+/// アーカイブのアドレスの残高の推移を取得
+#[utoipa::path(
+    get,
+    path = "/archive/balance-history/{address}",
+    tag = "archive",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        ("from" = Option<u64>, Query, description = "Start of the time range (UNIX seconds, inclusive)"),
+        ("to" = Option<u64>, Query, description = "End of the time range (UNIX seconds, inclusive)"),
+        ("cursor" = Option<String>, Query, description = "`next_cursor` of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Page size (max 1000)"),
+        ("format" = Option<String>, Query, description = "`json` (default) or `csv` to stream every page")
+    ),
+    responses(
+        (status = 200, description = "Balance after every block that changed it, oldest first", body = ArchivePage<BalancePoint>)
+    )
+)]
+async fn get_balance_history(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<ArchiveQuery>,
+) -> Result<Response> {
+    let address = state.addresses.parse(&address)?;
+    let range = query.range();
+
+    if let ArchiveFormat::Csv = query.format {
+        let views = state.views.clone();
+        let filename = format!("balance-history-{}.csv", address);
+        return Ok(stream_csv(filename, query.cursor, move |cursor| {
+            let views = views.clone();
+            let address = address.clone();
+            async move { views.balance_history(&address, range, cursor.as_deref(), MAX_ARCHIVE_PAGE).await }
+        }));
+    }
+
+    let limit = query.limit.unwrap_or(listing::DEFAULT_LIMIT);
+    let page = state.views.balance_history(&address, range, query.cursor.as_deref(), limit).await?;
+    Ok(Json(page).into_response())
+}
+
+/// 仕訳帳の出力形式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LedgerFormat {
+    #[default]
+    Json,
+    Csv,
+    /// QuickBooks のIIF
+    Quickbooks,
+}
+
+/// 仕訳帳の条件
+#[derive(Debug, Deserialize)]
+struct LedgerQuery {
+    /// カンマ区切りのアドレス
+    accounts: String,
+    from: String,
+    to: String,
+    #[serde(default)]
+    format: LedgerFormat,
+    #[serde(default)]
+    decimals: u32,
+}
+
+/// アカウントの期間の仕訳帳を取得
+///
+/// 送金・手数料・ブロックの報酬を、借方と貸方の一致する仕訳にします。
+#[utoipa::path(
+    get,
+    path = "/accounting/ledger",
+    tag = "accounting",
+    params(
+        ("accounts" = String, Query, description = "Comma-separated account addresses (max 100)"),
+        ("from" = String, Query, description = "First day, `YYYY-MM-DD` in UTC"),
+        ("to" = String, Query, description = "Last day (inclusive), `YYYY-MM-DD` in UTC"),
+        ("format" = Option<String>, Query, description = "`json` (default), `csv` or `quickbooks` (IIF general journal)"),
+        ("decimals" = Option<u32>, Query, description = "Render amounts with this many decimal places (default 0, base units)")
+    ),
+    responses(
+        (status = 200, description = "Balanced journal entries ordered by time, with per-account totals", body = Ledger),
+        (status = 400, description = "Invalid account, date range or decimals")
+    )
+)]
+async fn get_accounting_ledger(
+    State(state): State<AppState>,
+    Query(query): Query<LedgerQuery>,
+) -> Result<Response> {
+    let accounts = query.accounts.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| state.addresses.parse(a))
+        .collect::<std::result::Result<Vec<String>, _>>()?;
+    if accounts.is_empty() || accounts.len() > accounting::MAX_ACCOUNTS {
+        return Err(AppError::BadRequest(format!("accounts must list 1 to {} addresses", accounting::MAX_ACCOUNTS)));
+    }
+    if query.decimals > accounting::MAX_DECIMALS {
+        return Err(AppError::BadRequest(format!("decimals must be at most {}", accounting::MAX_DECIMALS)));
+    }
+    let range = accounting::date_range(&query.from, &query.to)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let ledger = accounting::build_ledger(&state.views, &state.chain, &accounts, range).await?;
+
+    let (body, content_type, extension) = match query.format {
+        LedgerFormat::Json => return Ok(Json(ledger).into_response()),
+        LedgerFormat::Csv => (ledger.to_csv(query.decimals), "text/csv; charset=utf-8", "csv"),
+        LedgerFormat::Quickbooks => (ledger.to_iif(query.decimals), "text/plain; charset=utf-8", "iif"),
+    };
+    let filename = format!("ledger-{}-{}.{}", query.from, query.to, extension);
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// CSVの1行として出力できる型
+trait CsvRow {
+    const HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+}
+
+impl CsvRow for AddressTx {
+    const HEADER: &'static str = "hash,height,timestamp,direction,counterparty,value,memo_tag,memo";
+
+    fn csv_row(&self) -> String {
+        let direction = match self.direction {
+            TxDirection::In => "in",
+            TxDirection::Out => "out",
+        };
+        let (memo_tag, memo) = self.memo.as_ref().map_or(("", ""), |m| (m.tag.as_str(), m.payload.as_str()));
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            csv_field(&self.hash), self.height, self.timestamp, direction, csv_field(&self.counterparty), self.value,
+            csv_field(memo_tag), csv_field(memo)
+        )
+    }
+}
+
+impl CsvRow for BalancePoint {
+    const HEADER: &'static str = "height,timestamp,balance";
+
+    fn csv_row(&self) -> String {
+        format!("{},{},{}", self.height, self.timestamp, self.balance)
+    }
+}
+
+/// 区切り文字や引用符を含むフィールドを引用符で囲む
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// アーカイブの全ページを順に読み込み、CSVとしてストリーミング
+///
+/// ページごとに読み込むため、長期間の履歴でもメモリに全件を保持しません。
+fn stream_csv<T, F, Fut>(filename: String, cursor: Option<String>, fetch: F) -> Response
+where
+    T: CsvRow + Send + 'static,
+    F: Fn(Option<String>) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<ArchivePage<T>>> + Send,
+{
+    let head = futures::stream::once(async { Ok::<_, anyhow::Error>(Bytes::from(format!("{}\n", T::HEADER))) });
+    let rows = futures::stream::try_unfold((fetch, Some(cursor)), |(fetch, cursor)| async move {
+        let Some(cursor) = cursor else {
+            return Ok(None);
+        };
+        let page = fetch(cursor).await?;
+        let mut chunk = String::new();
+        for item in &page.items {
+            chunk.push_str(&item.csv_row());
+            chunk.push('\n');
+        }
+        Ok::<_, anyhow::Error>(Some((Bytes::from(chunk), (fetch, page.next_cursor.map(Some)))))
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(head.chain(rows)),
+    )
+        .into_response()
+}
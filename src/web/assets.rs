@@ -0,0 +1,234 @@
+//! Web UIの静的ファイル
+//!
+//! `frontend/` のファイルをバイナリに埋め込んで提供します（ノードと別にファイルを配置する必要はない）。
+//! テキストのファイルは最初の参照時にgzipとbrotliで圧縮して保持し、`Accept-Encoding` に応じて返します。
+//! 全てのファイルにETagを付け、`If-None-Match` が一致すれば 304 を返します。
+//! 拡張子のないパスはSPAのルートとして `index.html` を返します（APIのパスは除く）。
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::OnceLock;
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use rust_embed::RustEmbed;
+
+use super::AppError;
+
+#[derive(RustEmbed)]
+#[folder = "frontend/"]
+struct Frontend;
+
+/// SPAのフォールバック先
+const INDEX: &str = "index.html";
+/// 圧縮する最小のサイズ（これより小さいファイルはそのまま返す）
+const MIN_COMPRESS_SIZE: usize = 256;
+/// HTMLのキャッシュ（毎回ETagで検証する）
+const HTML_CACHE_CONTROL: &str = "no-cache";
+/// HTML以外のキャッシュ（ファイル名にハッシュを含まないため短くする）
+const ASSET_CACHE_CONTROL: &str = "public, max-age=300";
+/// SPAのフォールバックにしないパス（存在しないAPIはJSONの404を返す）
+const API_PREFIXES: &[&str] = &["api", "rpc", "graphql", "graphiql", "ws", "metrics"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    fn header(self) -> Option<&'static str> {
+        match self {
+            Self::Identity => None,
+            Self::Gzip => Some("gzip"),
+            Self::Brotli => Some("br"),
+        }
+    }
+}
+
+/// 埋め込んだファイルと圧縮済みの本文
+struct Asset {
+    content_type: HeaderValue,
+    cache_control: &'static str,
+    /// 内容のハッシュ（ETagは圧縮方式ごとに区別する）
+    digest: String,
+    identity: Bytes,
+    gzip: Option<Bytes>,
+    brotli: Option<Bytes>,
+}
+
+impl Asset {
+    fn load(path: &str) -> Option<Self> {
+        let file = Frontend::get(path)?;
+        let mime = file.metadata.mimetype();
+        let text = mime.starts_with("text/") || mime.ends_with("javascript") || mime.ends_with("json") || mime.ends_with("svg+xml");
+        let content_type = if mime.starts_with("text/") || mime.ends_with("javascript") {
+            format!("{mime}; charset=utf-8")
+        } else {
+            mime.to_string()
+        };
+        let identity = Bytes::from(file.data.into_owned());
+        // 圧縮しても小さくならない場合は保持しない
+        let smaller = |compressed: Vec<u8>| (compressed.len() < identity.len()).then(|| Bytes::from(compressed));
+        let (gzip, brotli) = if text && identity.len() >= MIN_COMPRESS_SIZE {
+            (gzip(&identity).and_then(smaller), brotli(&identity).and_then(smaller))
+        } else {
+            (None, None)
+        };
+        Some(Self {
+            content_type: HeaderValue::from_str(&content_type).ok()?,
+            cache_control: if mime == "text/html" { HTML_CACHE_CONTROL } else { ASSET_CACHE_CONTROL },
+            digest: hex::encode(&file.metadata.sha256_hash()[..8]),
+            identity,
+            gzip,
+            brotli,
+        })
+    }
+
+    /// クライアントが受け付ける最も小さい表現
+    fn negotiate(&self, request: &HeaderMap) -> (Encoding, &Bytes) {
+        if let Some(body) = self.brotli.as_ref().filter(|_| accepts(request, "br")) {
+            return (Encoding::Brotli, body);
+        }
+        if let Some(body) = self.gzip.as_ref().filter(|_| accepts(request, "gzip")) {
+            return (Encoding::Gzip, body);
+        }
+        (Encoding::Identity, &self.identity)
+    }
+
+    fn respond(&self, request: &HeaderMap) -> Response {
+        let (encoding, body) = self.negotiate(request);
+        let etag = match encoding.header() {
+            Some(suffix) => format!("\"{}-{}\"", self.digest, suffix),
+            None => format!("\"{}\"", self.digest),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(self.cache_control));
+        headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            headers.insert(header::ETAG, value);
+        }
+        if none_match(request, &etag) {
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        }
+        headers.insert(header::CONTENT_TYPE, self.content_type.clone());
+        if let Some(value) = encoding.header() {
+            headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(value));
+        }
+        (headers, body.clone()).into_response()
+    }
+}
+
+fn gzip(data: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+fn brotli(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut out, 4096, 11, 22);
+        encoder.write_all(data).ok()?;
+    }
+    Some(out)
+}
+
+/// `Accept-Encoding` が指定の方式を受け付けるか（`q=0` は拒否）
+fn accepts(request: &HeaderMap, encoding: &str) -> bool {
+    request.get_all(header::ACCEPT_ENCODING).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|item| {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let rejected = parts.any(|param| {
+                param.trim().strip_prefix("q=").and_then(|q| q.trim().parse::<f32>().ok()) == Some(0.0)
+            });
+            (name.eq_ignore_ascii_case(encoding) || name == "*") && !rejected
+        })
+}
+
+/// `If-None-Match` がETagと一致するか
+fn none_match(request: &HeaderMap, etag: &str) -> bool {
+    request.get_all(header::IF_NONE_MATCH).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// 埋め込んだ全てのファイル（最初の参照時に圧縮する）
+fn assets() -> &'static HashMap<String, Asset> {
+    static ASSETS: OnceLock<HashMap<String, Asset>> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        Frontend::iter()
+            .filter_map(|path| Asset::load(&path).map(|asset| (path.into_owned(), asset)))
+            .collect()
+    })
+}
+
+/// 拡張子のないAPI以外のパス（SPAのルート）
+fn is_route(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let api = API_PREFIXES.iter().any(|prefix| {
+        path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+    !name.contains('.') && !api
+}
+
+fn lookup(path: &str) -> Option<&'static Asset> {
+    let path = path.trim_start_matches('/');
+    let assets = assets();
+    if path.is_empty() {
+        return assets.get(INDEX);
+    }
+    assets.get(path).or_else(|| is_route(path).then(|| assets.get(INDEX)).flatten())
+}
+
+/// 静的ファイルのハンドラー（ルーターのフォールバック）
+pub async fn serve(uri: Uri, headers: HeaderMap) -> Response {
+    match lookup(uri.path()) {
+        Some(asset) => asset.respond(&headers),
+        None => AppError::NotFound(uri.path().to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serves_compressed_assets_with_etag_and_spa_fallback() {
+        let index = lookup("/").unwrap();
+        assert!(std::ptr::eq(lookup("/wallet").unwrap(), index));
+        assert!(std::ptr::eq(lookup("/explorer/blocks").unwrap(), index));
+        assert!(lookup("/api/unknown").is_none());
+        assert!(lookup("/graphql").is_none());
+        assert!(lookup("/js/missing.js").is_none());
+
+        let mut request = HeaderMap::new();
+        request.insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip, br;q=0"));
+        let response = index.respond(&request);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], HTML_CACHE_CONTROL);
+        let etag = response.headers()[header::ETAG].clone();
+
+        // 同じ表現のETagなら304、別の圧縮方式のETagは一致しない
+        request.insert(header::IF_NONE_MATCH, etag);
+        assert_eq!(index.respond(&request).status(), StatusCode::NOT_MODIFIED);
+        request.insert(header::ACCEPT_ENCODING, HeaderValue::from_static("br"));
+        let response = index.respond(&request);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+
+        let script = lookup("/js/app.js").unwrap().respond(&HeaderMap::new());
+        assert_eq!(script.headers()[header::CACHE_CONTROL], ASSET_CACHE_CONTROL);
+        assert!(script.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+}
@@ -0,0 +1,449 @@
+//! パスキー（WebAuthn）によるログイン
+//!
+//! Web UIと管理者APIをlocalhostの外に公開できるよう、オペレーターをパスキーで認証し、
+//! セッションCookieを発行します。
+//! 主な機能：
+//! - パスキーの登録（管理者トークンが必要）とログイン・ログアウト
+//! - `HttpOnly`・`SameSite=Strict` のセッションCookie（期限付き）
+//! - Cookieで認証したリクエストの状態変更に `X-CSRF-Token` ヘッダーを要求
+//! - `login_required` の場合、Web UIの表示にログインを要求
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use anyhow::{Context, anyhow};
+use axum::{
+    Router,
+    routing::{get, post, delete},
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Json, Redirect, Response},
+};
+use rand::RngCore;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use tracing::{info, warn};
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, Passkey, PasskeyAuthentication, PasskeyRegistration,
+    PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse, Url, Uuid,
+    Webauthn, WebauthnBuilder,
+};
+
+use super::admin::{constant_time_eq, require_admin};
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::config::WebAuthnSettings;
+use crate::core::storage::StorageEngine;
+
+/// セッションCookieの名前
+pub const SESSION_COOKIE: &str = "rustorium_session";
+/// Cookieで認証したリクエストに必要なCSRFトークンのヘッダー
+pub const CSRF_HEADER: &str = "x-csrf-token";
+/// パスキーのキープレフィックス（`<資格情報IDのhex>`）
+const PASSKEY_PREFIX: &str = "webauthn/passkey/";
+/// 登録できるパスキーの上限
+const MAX_PASSKEYS: usize = 32;
+/// 登録・ログインの手続きの有効期間
+const CEREMONY_TTL: Duration = Duration::from_secs(300);
+/// 同時に進められる手続きの上限（超えた場合は古いものから破棄する）
+const MAX_PENDING_CEREMONIES: usize = 64;
+/// ログインなしで表示できるWeb UIのパス
+const PUBLIC_UI_PATHS: [&str; 3] = ["/login.html", "/js/login.js", "/css/style.css"];
+/// オペレーターのユーザーID（ノードのオペレーターは1人として扱い、パスキーを名前で区別する）
+const OPERATOR_ID: Uuid = Uuid::from_u128(0x7275_7374_6f72_6975_6d2d_6f70_6572_6174);
+
+/// 保存したパスキー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredPasskey {
+    name: String,
+    passkey: Passkey,
+    /// 登録した時刻（UNIX秒）
+    created_at: u64,
+    /// 最後にログインした時刻（UNIX秒）
+    last_used_at: Option<u64>,
+}
+
+/// パスキーの概要
+#[derive(Debug, Clone, Serialize)]
+pub struct PasskeySummary {
+    pub id: String,
+    pub name: String,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+/// ログイン中のセッション
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// ログインに使ったパスキーの名前
+    pub name: String,
+    pub csrf_token: String,
+    expires_at: Instant,
+}
+
+/// 進行中の登録・ログインの手続き
+struct Ceremony<T> {
+    state: T,
+    name: String,
+    expires_at: Instant,
+}
+
+/// パスキーによるログイン
+pub struct PasskeyAuth {
+    webauthn: Webauthn,
+    settings: WebAuthnSettings,
+    storage: Arc<dyn StorageEngine>,
+    registrations: Mutex<HashMap<String, Ceremony<PasskeyRegistration>>>,
+    logins: Mutex<HashMap<String, Ceremony<PasskeyAuthentication>>>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl PasskeyAuth {
+    pub fn from_settings(settings: &WebAuthnSettings, storage: Arc<dyn StorageEngine>) -> anyhow::Result<Self> {
+        let origin = Url::parse(&settings.rp_origin)
+            .with_context(|| format!("Invalid api.webauthn.rp_origin '{}'", settings.rp_origin))?;
+        let webauthn = WebauthnBuilder::new(&settings.rp_id, &origin)
+            .and_then(|builder| builder.rp_name(&settings.rp_name).build())
+            .map_err(|e| anyhow!("Invalid WebAuthn settings (rp_id must be the origin's domain): {}", e))?;
+        if !settings.secure_cookie {
+            warn!("Passkey sessions use cookies without the Secure attribute; serve the Web UI over HTTPS before exposing it");
+        }
+        Ok(Self {
+            webauthn,
+            settings: settings.clone(),
+            storage,
+            registrations: Mutex::new(HashMap::new()),
+            logins: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn passkeys(&self) -> anyhow::Result<Vec<StoredPasskey>> {
+        self.storage.scan(PASSKEY_PREFIX.as_bytes(), MAX_PASSKEYS).await?
+            .into_iter()
+            .take_while(|(key, _)| key.starts_with(PASSKEY_PREFIX.as_bytes()))
+            .map(|(_, value)| Ok(serde_json::from_slice(&value)?))
+            .collect()
+    }
+
+    async fn save(&self, stored: &StoredPasskey) -> anyhow::Result<()> {
+        let key = format!("{}{}", PASSKEY_PREFIX, hex::encode(stored.passkey.cred_id()));
+        self.storage.put(key.as_bytes(), &serde_json::to_vec(stored)?).await
+    }
+
+    /// 登録したパスキーの一覧
+    pub async fn list(&self) -> anyhow::Result<Vec<PasskeySummary>> {
+        Ok(self.passkeys().await?.into_iter()
+            .map(|stored| PasskeySummary {
+                id: hex::encode(stored.passkey.cred_id()),
+                name: stored.name,
+                created_at: stored.created_at,
+                last_used_at: stored.last_used_at,
+            })
+            .collect())
+    }
+
+    /// パスキーを削除（削除した場合は `true`）
+    pub async fn remove(&self, id: &str) -> anyhow::Result<bool> {
+        let key = format!("{}{}", PASSKEY_PREFIX, id.to_lowercase());
+        if self.storage.get(key.as_bytes()).await?.is_none() {
+            return Ok(false);
+        }
+        self.storage.delete(key.as_bytes()).await?;
+        Ok(true)
+    }
+
+    /// 登録を開始
+    pub async fn start_registration(&self, name: &str) -> Result<(String, CreationChallengeResponse)> {
+        let passkeys = self.passkeys().await?;
+        if passkeys.len() >= MAX_PASSKEYS {
+            return Err(AppError::BadRequest(format!("At most {} passkeys can be registered", MAX_PASSKEYS)));
+        }
+        if passkeys.iter().any(|stored| stored.name == name) {
+            return Err(AppError::BadRequest(format!("A passkey named '{}' is already registered", name)));
+        }
+        let exclude = passkeys.iter().map(|stored| stored.passkey.cred_id().clone()).collect();
+        let (challenge, state) = self.webauthn
+            .start_passkey_registration(OPERATOR_ID, "operator", name, Some(exclude))
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let id = insert_ceremony(&self.registrations, state, name);
+        Ok((id, challenge))
+    }
+
+    /// 登録を完了してパスキーを保存
+    pub async fn finish_registration(&self, ceremony: &str, credential: &RegisterPublicKeyCredential) -> Result<String> {
+        let pending = take_ceremony(&self.registrations, ceremony)?;
+        let passkey = self.webauthn.finish_passkey_registration(credential, &pending.state)
+            .map_err(|e| AppError::BadRequest(format!("Passkey registration failed: {}", e)))?;
+        self.save(&StoredPasskey { name: pending.name.clone(), passkey, created_at: now(), last_used_at: None }).await?;
+        info!("Registered passkey '{}'", pending.name);
+        Ok(pending.name)
+    }
+
+    /// ログインを開始
+    pub async fn start_login(&self) -> Result<(String, RequestChallengeResponse)> {
+        let passkeys: Vec<Passkey> = self.passkeys().await?.into_iter().map(|stored| stored.passkey).collect();
+        if passkeys.is_empty() {
+            return Err(AppError::BadRequest("No passkeys are registered".to_string()));
+        }
+        let (challenge, state) = self.webauthn.start_passkey_authentication(&passkeys)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let id = insert_ceremony(&self.logins, state, "");
+        Ok((id, challenge))
+    }
+
+    /// ログインを完了してセッションを作成（セッションIDとセッションを返す）
+    pub async fn finish_login(&self, ceremony: &str, credential: &PublicKeyCredential) -> Result<(String, Session)> {
+        let pending = take_ceremony(&self.logins, ceremony)?;
+        let result = self.webauthn.finish_passkey_authentication(credential, &pending.state)
+            .map_err(|_| AppError::Unauthorized)?;
+
+        let mut stored = self.passkeys().await?.into_iter()
+            .find(|stored| stored.passkey.cred_id() == result.cred_id())
+            .ok_or(AppError::Unauthorized)?;
+        // 署名カウンターを更新し、複製された認証器を検出できるようにする
+        stored.passkey.update_credential(&result);
+        stored.last_used_at = Some(now());
+        self.save(&stored).await?;
+
+        let session = Session {
+            name: stored.name.clone(),
+            csrf_token: random_token(),
+            expires_at: Instant::now() + Duration::from_secs(self.settings.session_ttl_secs),
+        };
+        let id = random_token();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, s| s.expires_at > Instant::now());
+        sessions.insert(id.clone(), session.clone());
+        info!("Operator logged in with passkey '{}'", stored.name);
+        Ok((id, session))
+    }
+
+    /// リクエストのCookieのセッション（期限切れの場合は `None`）
+    pub fn session(&self, headers: &HeaderMap) -> Option<Session> {
+        let id = session_id(headers)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(&id) {
+            Some(session) if session.expires_at > Instant::now() => Some(session.clone()),
+            Some(_) => {
+                sessions.remove(&id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn logout(&self, headers: &HeaderMap) {
+        if let Some(id) = session_id(headers) {
+            self.sessions.lock().unwrap().remove(&id);
+        }
+    }
+
+    /// セッションCookieの `Set-Cookie` の値（`id` が `None` の場合は削除）
+    fn cookie(&self, id: Option<&str>) -> HeaderValue {
+        let (value, max_age) = match id {
+            Some(id) => (id, self.settings.session_ttl_secs),
+            None => ("", 0),
+        };
+        let secure = if self.settings.secure_cookie { "; Secure" } else { "" };
+        let cookie = format!("{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict{}", SESSION_COOKIE, value, max_age, secure);
+        HeaderValue::from_str(&cookie).expect("session cookie is valid ASCII")
+    }
+}
+
+fn insert_ceremony<T>(ceremonies: &Mutex<HashMap<String, Ceremony<T>>>, state: T, name: &str) -> String {
+    let mut ceremonies = ceremonies.lock().unwrap();
+    let now = Instant::now();
+    ceremonies.retain(|_, c| c.expires_at > now);
+    while ceremonies.len() >= MAX_PENDING_CEREMONIES {
+        let oldest = ceremonies.iter().min_by_key(|(_, c)| c.expires_at).map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            ceremonies.remove(&oldest);
+        }
+    }
+    let id = random_token();
+    ceremonies.insert(id.clone(), Ceremony { state, name: name.to_string(), expires_at: now + CEREMONY_TTL });
+    id
+}
+
+fn take_ceremony<T>(ceremonies: &Mutex<HashMap<String, Ceremony<T>>>, id: &str) -> Result<Ceremony<T>> {
+    ceremonies.lock().unwrap().remove(id)
+        .filter(|c| c.expires_at > Instant::now())
+        .ok_or_else(|| AppError::BadRequest("Unknown or expired ceremony; start again".to_string()))
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// `Cookie` ヘッダーのセッションID
+fn session_id(headers: &HeaderMap) -> Option<String> {
+    headers.get_all(header::COOKIE).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Cookieで認証した状態変更のリクエストにCSRFトークンを要求
+///
+/// `Authorization` ヘッダーで認証するリクエストはブラウザが自動で送らないため対象外です。
+pub async fn csrf_guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let safe = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let headers = request.headers();
+    if let (false, Some(auth), false) = (safe, state.auth.as_ref(), headers.contains_key(header::AUTHORIZATION)) {
+        if let Some(session) = auth.session(headers) {
+            let provided = headers.get(CSRF_HEADER).and_then(|v| v.to_str().ok()).unwrap_or_default();
+            if !constant_time_eq(provided.as_bytes(), session.csrf_token.as_bytes()) {
+                return AppError::coded(ErrorCode::CsrfTokenInvalid, "Missing or invalid CSRF token").into_response();
+            }
+        }
+    }
+    next.run(request).await
+}
+
+/// `login_required` の場合、ログインしていないWeb UIの表示をログインページへ転送
+pub async fn require_login(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if let Some(auth) = state.auth.as_ref().filter(|auth| auth.settings.login_required) {
+        let path = request.uri().path();
+        if !PUBLIC_UI_PATHS.contains(&path) && auth.session(request.headers()).is_none() {
+            return Redirect::to("/login.html").into_response();
+        }
+    }
+    next.run(request).await
+}
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/session", get(get_session))
+        .route("/register/start", post(start_registration))
+        .route("/register/finish", post(finish_registration))
+        .route("/login/start", post(start_login))
+        .route("/login/finish", post(finish_login))
+        .route("/logout", post(logout))
+        .route("/passkeys", get(list_passkeys))
+        .route("/passkeys/:id", delete(remove_passkey))
+        .with_state(state)
+}
+
+fn auth(state: &AppState) -> Result<&Arc<PasskeyAuth>> {
+    state.auth.as_ref().ok_or_else(|| AppError::coded(ErrorCode::FeatureDisabled, "Passkey login is disabled"))
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistrationRequest {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinishRequest<T> {
+    ceremony: String,
+    credential: T,
+}
+
+/// ログイン状態（ログイン中の場合はCSRFトークンを含む）
+async fn get_session(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    let auth = auth(&state)?;
+    Ok(Json(match auth.session(&headers) {
+        Some(session) => json!({ "authenticated": true, "name": session.name, "csrf_token": session.csrf_token }),
+        None => json!({ "authenticated": false, "login_required": auth.settings.login_required }),
+    }))
+}
+
+/// パスキーの登録を開始（管理者トークンが必要）
+async fn start_registration(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<RegistrationRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > 64 {
+        return Err(AppError::BadRequest("name must be 1 to 64 characters".to_string()));
+    }
+    let (ceremony, challenge) = auth(&state)?.start_registration(name).await?;
+    Ok(Json(json!({ "ceremony": ceremony, "options": challenge })))
+}
+
+/// パスキーの登録を完了（管理者トークンが必要）
+async fn finish_registration(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<FinishRequest<RegisterPublicKeyCredential>>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    let name = auth(&state)?.finish_registration(&request.ceremony, &request.credential).await?;
+    Ok(Json(json!({ "success": true, "name": name })))
+}
+
+async fn start_login(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let (ceremony, challenge) = auth(&state)?.start_login().await?;
+    Ok(Json(json!({ "ceremony": ceremony, "options": challenge })))
+}
+
+/// ログインを完了し、セッションCookieを設定
+async fn finish_login(
+    State(state): State<AppState>,
+    Json(request): Json<FinishRequest<PublicKeyCredential>>,
+) -> Result<impl IntoResponse> {
+    let auth = auth(&state)?;
+    let (id, session) = auth.finish_login(&request.ceremony, &request.credential).await?;
+    Ok((
+        [(header::SET_COOKIE, auth.cookie(Some(&id)))],
+        Json(json!({ "success": true, "name": session.name, "csrf_token": session.csrf_token })),
+    ))
+}
+
+async fn logout(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    let auth = auth(&state)?;
+    auth.logout(&headers);
+    Ok(([(header::SET_COOKIE, auth.cookie(None))], Json(json!({ "success": true }))))
+}
+
+/// 登録したパスキーの一覧（管理者）
+async fn list_passkeys(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    Ok(Json(auth(&state)?.list().await?))
+}
+
+/// パスキーを削除（管理者）
+async fn remove_passkey(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &headers)?;
+    if !auth(&state)?.remove(&id).await? {
+        return Err(AppError::NotFound(format!("passkey {} not found", id)));
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_cookie_parsing() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(session_id(&headers), None);
+        headers.append(header::COOKIE, HeaderValue::from_static("theme=dark; rustorium_session=abc123"));
+        assert_eq!(session_id(&headers).as_deref(), Some("abc123"));
+
+        let mut headers = HeaderMap::new();
+        headers.append(header::COOKIE, HeaderValue::from_static("rustorium_session="));
+        assert_eq!(session_id(&headers), None);
+    }
+}
@@ -0,0 +1,45 @@
+//! 機密トランザクションAPI（`confidential-tx` フィーチャー）
+//!
+//! アカウントの機密残高のコミットメントと、範囲証明の検証の統計を提供します（`/api/confidential`）。
+//! 統計はノードの起動からの値で、機密トランザクションの性能への影響の評価に使います。
+
+use std::sync::Arc;
+use axum::{
+    Router,
+    routing::get,
+    extract::{Path, State},
+    response::{IntoResponse, Json},
+};
+
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::core::confidential::ConfidentialLedger;
+use crate::core::wallet::AddressFormat;
+
+struct ConfidentialServer {
+    ledger: Arc<ConfidentialLedger>,
+    addresses: AddressFormat,
+}
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/accounts/:address", get(get_account))
+        .route("/stats", get(get_stats))
+        .with_state(Arc::new(ConfidentialServer { ledger: state.confidential, addresses: state.addresses }))
+}
+
+/// アカウントの機密残高のコミットメント
+async fn get_account(
+    State(server): State<Arc<ConfidentialServer>>,
+    Path(address): Path<String>,
+) -> Result<impl IntoResponse> {
+    let address = server.addresses.parse(&address)?;
+    let account = server.ledger.account(&address).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::AccountNotFound, format!("Account {} has no confidential balance", address)))?;
+    Ok(Json(account))
+}
+
+/// 範囲証明の検証の統計
+async fn get_stats(State(server): State<Arc<ConfidentialServer>>) -> Result<impl IntoResponse> {
+    Ok(Json(server.ledger.stats().await))
+}
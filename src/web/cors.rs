@@ -0,0 +1,80 @@
+//! CORSポリシー
+//!
+//! 設定（`api.cors` / `api.admin_cors`）から CORS のレイヤーを作ります。
+//! 資格情報の許可とワイルドカードの併用はブラウザが拒否するため、起動時にエラーにします。
+
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use crate::config::CorsSettings;
+
+/// 設定から CORS のレイヤーを作成
+pub fn layer(settings: &CorsSettings) -> Result<CorsLayer> {
+    let wildcard = |values: &[String]| values.iter().any(|v| v.trim() == "*");
+    if settings.allow_credentials
+        && (wildcard(&settings.allowed_origins) || wildcard(&settings.allowed_methods) || wildcard(&settings.allowed_headers))
+    {
+        return Err(anyhow!("CORS allow_credentials cannot be combined with \"*\" origins, methods or headers"));
+    }
+
+    let origins = if wildcard(&settings.allowed_origins) {
+        AllowOrigin::any()
+    } else {
+        let origins = settings.allowed_origins.iter()
+            .map(|origin| HeaderValue::from_str(origin.trim().trim_end_matches('/'))
+                .map_err(|_| anyhow!("Invalid CORS origin {:?}", origin)))
+            .collect::<Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    let methods = if wildcard(&settings.allowed_methods) {
+        AllowMethods::any()
+    } else {
+        let methods = settings.allowed_methods.iter()
+            .map(|method| Method::from_bytes(method.trim().to_uppercase().as_bytes())
+                .map_err(|_| anyhow!("Invalid CORS method {:?}", method)))
+            .collect::<Result<Vec<_>>>()?;
+        AllowMethods::list(methods)
+    };
+    let headers = if wildcard(&settings.allowed_headers) {
+        AllowHeaders::any()
+    } else {
+        let headers = settings.allowed_headers.iter()
+            .map(|header| HeaderName::from_bytes(header.trim().to_lowercase().as_bytes())
+                .map_err(|_| anyhow!("Invalid CORS header {:?}", header)))
+            .collect::<Result<Vec<_>>>()?;
+        AllowHeaders::list(headers)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(settings.allow_credentials)
+        .max_age(Duration::from_secs(settings.max_age_secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(layer(&CorsSettings::default()).is_ok());
+        assert!(layer(&CorsSettings::admin()).is_ok());
+
+        let allowlist = CorsSettings {
+            allowed_origins: vec!["https://explorer.example.com/".to_string()],
+            allow_credentials: true,
+            ..CorsSettings::default()
+        };
+        assert!(layer(&allowlist).is_ok());
+
+        // ワイルドカードと資格情報の併用はブラウザが拒否する
+        let wildcard = CorsSettings { allow_credentials: true, ..CorsSettings::default() };
+        assert!(layer(&wildcard).is_err());
+
+        let bad_method = CorsSettings { allowed_methods: vec!["GE T".to_string()], ..CorsSettings::admin() };
+        assert!(layer(&bad_method).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! データ可用性サンプリングAPI（`das` フィーチャー）
+//!
+//! ライトノードのサンプリングに、確定したブロックのコミットメントとシェアを提供します（`/api/das`）。
+//! 符号化は要求されたときに行い、直近のブロックの結果を保持します。
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use axum::{
+    Router,
+    routing::get,
+    extract::{Path, State},
+    response::{IntoResponse, Json},
+};
+use tokio::sync::Mutex;
+
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::core::block::Chain;
+use crate::core::network::das::{DasError, ExtendedBlock};
+
+/// 符号化の結果を保持するブロック数
+const CACHE_BLOCKS: usize = 16;
+
+struct DasServer {
+    chain: Arc<Chain>,
+    cache: Mutex<VecDeque<(u64, Arc<ExtendedBlock>)>>,
+}
+
+impl DasServer {
+    /// 高さを指定して符号化したブロックを取得
+    async fn extended(&self, height: u64) -> Result<Arc<ExtendedBlock>> {
+        if let Some((_, extended)) = self.cache.lock().await.iter().find(|(h, _)| *h == height) {
+            return Ok(extended.clone());
+        }
+        let block = self.chain.get_block(height).await?
+            .ok_or_else(|| AppError::coded(ErrorCode::BlockNotFound, format!("Block {} not found", height)))?;
+        let extended = Arc::new(ExtendedBlock::encode(&block)?);
+        let mut cache = self.cache.lock().await;
+        cache.push_back((height, extended.clone()));
+        if cache.len() > CACHE_BLOCKS {
+            cache.pop_front();
+        }
+        Ok(extended)
+    }
+}
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/blocks/:height", get(get_commitment))
+        .route("/blocks/:height/samples/:index", get(get_sample))
+        .with_state(Arc::new(DasServer { chain: state.chain, cache: Mutex::new(VecDeque::new()) }))
+}
+
+impl From<DasError> for AppError {
+    fn from(err: DasError) -> Self {
+        match err {
+            DasError::OutOfRange { .. } => Self::coded(ErrorCode::BlockNotFound, err.to_string()),
+            _ => Self::Internal(err.to_string()),
+        }
+    }
+}
+
+/// ブロックのコミットメント（`data_root` とシェアの数）
+async fn get_commitment(
+    State(server): State<Arc<DasServer>>,
+    Path(height): Path<u64>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(server.extended(height).await?.commitment().clone()))
+}
+
+/// シェアとマークル証明
+async fn get_sample(
+    State(server): State<Arc<DasServer>>,
+    Path((height, index)): Path<(u64, usize)>,
+) -> Result<impl IntoResponse> {
+    Ok(Json(server.extended(height).await?.sample(index)?))
+}
@@ -0,0 +1,335 @@
+//! APIのエラーコード
+//!
+//! REST・JSON-RPC（WebSocket）・GraphQL のエラーとCLIの表示で共通の、安定したエラーコードです。
+//! SDKはメッセージの文字列ではなく、数値のコードかスラッグで分岐します。
+//! 一度公開したコードとスラッグは変更・再利用しません（廃止したものは欠番にする）。
+//!
+//! 数値は分類ごとに分けています：
+//! - 1xxx: リクエストの形式と値
+//! - 2xxx: 認証と権限
+//! - 3xxx: 見つからない・無効な機能
+//! - 4xxx: トランザクションの受け付け
+//! - 5xxx: ノードの内部エラーと一時的な利用不可
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// エラーコード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InvalidRequest,
+    InvalidAddress,
+    InvalidCursor,
+    InvalidData,
+    InvalidMemo,
+    InvalidBlob,
+    MethodNotFound,
+    InvalidIdempotencyKey,
+    InvalidSort,
+    InvalidFilter,
+    InvalidQuery,
+    Unauthorized,
+    Forbidden,
+    CsrfTokenInvalid,
+    AdminApiDisabled,
+    MissingScope,
+    NotFound,
+    BlockNotFound,
+    TransactionNotFound,
+    AccountNotFound,
+    ContractNotFound,
+    ProxyNotFound,
+    NameNotFound,
+    HtlcNotFound,
+    BlobNotFound,
+    FeatureDisabled,
+    ValidatorNotFound,
+    TransactionRejected,
+    FeeTooLow,
+    TooManyPending,
+    DataTooLarge,
+    DuplicateTransaction,
+    MempoolFull,
+    AddressDenied,
+    TransactionExpired,
+    ReplacementUnderpriced,
+    InvalidSignature,
+    NameOperationRejected,
+    HtlcOperationRejected,
+    BalanceLocked,
+    ConfidentialTransferRejected,
+    IdempotencyKeyInProgress,
+    IdempotencyKeyReused,
+    InvalidProofOfPossession,
+    InsufficientSelfStake,
+    ValidatorAlreadyRegistered,
+    QueryLimitExceeded,
+    InsufficientBalance,
+    DeploymentRejected,
+    ProxyOperationRejected,
+    NonceAlreadyUsed,
+    Internal,
+    ServiceUnavailable,
+    RpcPaused,
+    ForwardFailed,
+    BlobPoolFull,
+}
+
+impl ErrorCode {
+    /// 全てのコード（数値の順）
+    pub const ALL: [ErrorCode; 56] = [
+        Self::InvalidRequest,
+        Self::InvalidAddress,
+        Self::InvalidCursor,
+        Self::InvalidData,
+        Self::InvalidMemo,
+        Self::InvalidBlob,
+        Self::MethodNotFound,
+        Self::InvalidIdempotencyKey,
+        Self::InvalidSort,
+        Self::InvalidFilter,
+        Self::InvalidQuery,
+        Self::Unauthorized,
+        Self::Forbidden,
+        Self::CsrfTokenInvalid,
+        Self::AdminApiDisabled,
+        Self::MissingScope,
+        Self::NotFound,
+        Self::BlockNotFound,
+        Self::TransactionNotFound,
+        Self::AccountNotFound,
+        Self::ContractNotFound,
+        Self::ProxyNotFound,
+        Self::NameNotFound,
+        Self::HtlcNotFound,
+        Self::BlobNotFound,
+        Self::FeatureDisabled,
+        Self::ValidatorNotFound,
+        Self::TransactionRejected,
+        Self::FeeTooLow,
+        Self::TooManyPending,
+        Self::DataTooLarge,
+        Self::DuplicateTransaction,
+        Self::MempoolFull,
+        Self::AddressDenied,
+        Self::TransactionExpired,
+        Self::ReplacementUnderpriced,
+        Self::InvalidSignature,
+        Self::NameOperationRejected,
+        Self::HtlcOperationRejected,
+        Self::BalanceLocked,
+        Self::ConfidentialTransferRejected,
+        Self::IdempotencyKeyInProgress,
+        Self::IdempotencyKeyReused,
+        Self::InvalidProofOfPossession,
+        Self::InsufficientSelfStake,
+        Self::ValidatorAlreadyRegistered,
+        Self::QueryLimitExceeded,
+        Self::InsufficientBalance,
+        Self::DeploymentRejected,
+        Self::ProxyOperationRejected,
+        Self::NonceAlreadyUsed,
+        Self::Internal,
+        Self::ServiceUnavailable,
+        Self::RpcPaused,
+        Self::ForwardFailed,
+        Self::BlobPoolFull,
+    ];
+
+    /// 数値のコード、スラッグ、HTTPステータス、説明
+    fn info(self) -> (u32, &'static str, StatusCode, &'static str) {
+        use StatusCode as S;
+        match self {
+            Self::InvalidRequest => (1000, "invalid_request", S::BAD_REQUEST, "The request is malformed or has an invalid value"),
+            Self::InvalidAddress => (1001, "invalid_address", S::BAD_REQUEST, "An address is not valid hex or bech32m for this network"),
+            Self::InvalidCursor => (1002, "invalid_cursor", S::BAD_REQUEST, "A pagination cursor is not valid"),
+            Self::InvalidData => (1003, "invalid_data", S::BAD_REQUEST, "Hex data or bytecode could not be decoded"),
+            Self::InvalidMemo => (1004, "invalid_memo", S::BAD_REQUEST, "A memo tag or payload is not valid"),
+            Self::InvalidBlob => (1005, "invalid_blob", S::BAD_REQUEST, "A blob or its fee cap is not valid"),
+            Self::MethodNotFound => (1006, "method_not_found", S::NOT_FOUND, "The JSON-RPC method does not exist"),
+            Self::InvalidIdempotencyKey => (1007, "invalid_idempotency_key", S::BAD_REQUEST, "The Idempotency-Key header is not valid"),
+            Self::InvalidSort => (1008, "invalid_sort", S::BAD_REQUEST, "The sort field or direction is not supported by this list"),
+            Self::InvalidFilter => (1009, "invalid_filter", S::BAD_REQUEST, "A list filter is unknown or has an invalid value"),
+            Self::InvalidQuery => (1010, "invalid_query", S::BAD_REQUEST, "The SQL query is not valid or is not read-only"),
+            Self::Unauthorized => (2000, "unauthorized", S::UNAUTHORIZED, "Authentication is required"),
+            Self::Forbidden => (2001, "forbidden", S::FORBIDDEN, "The caller is not permitted to do this"),
+            Self::CsrfTokenInvalid => (2002, "csrf_token_invalid", S::FORBIDDEN, "The CSRF token of a session request is missing or wrong"),
+            Self::AdminApiDisabled => (2003, "admin_api_disabled", S::FORBIDDEN, "The admin API is disabled"),
+            Self::MissingScope => (2004, "missing_scope", S::FORBIDDEN, "The admin token lacks the required scope"),
+            Self::NotFound => (3000, "not_found", S::NOT_FOUND, "The resource does not exist"),
+            Self::BlockNotFound => (3001, "block_not_found", S::NOT_FOUND, "The block does not exist"),
+            Self::TransactionNotFound => (3002, "transaction_not_found", S::NOT_FOUND, "The transaction is neither pending nor committed"),
+            Self::AccountNotFound => (3003, "account_not_found", S::NOT_FOUND, "The account has no state of the requested kind"),
+            Self::ContractNotFound => (3004, "contract_not_found", S::NOT_FOUND, "The contract is unknown or not verified"),
+            Self::ProxyNotFound => (3005, "proxy_not_found", S::NOT_FOUND, "The upgradeable proxy does not exist"),
+            Self::NameNotFound => (3006, "name_not_found", S::NOT_FOUND, "The name is not registered or has expired"),
+            Self::HtlcNotFound => (3007, "htlc_not_found", S::NOT_FOUND, "The hash time-locked contract does not exist"),
+            Self::BlobNotFound => (3008, "blob_not_found", S::NOT_FOUND, "The blob is unknown or no longer retained"),
+            Self::FeatureDisabled => (3009, "feature_disabled", S::NOT_FOUND, "The feature is disabled on this node"),
+            Self::ValidatorNotFound => (3010, "validator_not_found", S::NOT_FOUND, "The validator is not a registered candidate"),
+            Self::TransactionRejected => (4000, "transaction_rejected", S::BAD_REQUEST, "The transaction was rejected"),
+            Self::FeeTooLow => (4001, "fee_too_low", S::BAD_REQUEST, "The gas price is below the fee floor"),
+            Self::TooManyPending => (4002, "too_many_pending", S::BAD_REQUEST, "The sender has too many pending transactions"),
+            Self::DataTooLarge => (4003, "data_too_large", S::BAD_REQUEST, "The transaction data exceeds the size limit"),
+            Self::DuplicateTransaction => (4004, "duplicate_transaction", S::BAD_REQUEST, "The transaction is already in the mempool"),
+            Self::MempoolFull => (4005, "mempool_full", S::BAD_REQUEST, "The mempool is full and the gas price does not outbid the cheapest entry"),
+            Self::AddressDenied => (4006, "address_denied", S::FORBIDDEN, "An address is denied by the node's access policy"),
+            Self::TransactionExpired => (4007, "transaction_expired", S::BAD_REQUEST, "The transaction's valid_until has passed"),
+            Self::ReplacementUnderpriced => (4008, "replacement_underpriced", S::BAD_REQUEST, "A replacement does not raise the gas price enough"),
+            Self::InvalidSignature => (4009, "invalid_signature", S::BAD_REQUEST, "The signature or chain ID is not valid"),
+            Self::NameOperationRejected => (4010, "name_operation_rejected", S::BAD_REQUEST, "The name registry operation cannot be applied"),
+            Self::HtlcOperationRejected => (4011, "htlc_operation_rejected", S::BAD_REQUEST, "The HTLC operation cannot be applied"),
+            Self::BalanceLocked => (4012, "balance_locked", S::BAD_REQUEST, "The transaction spends balance locked by a vesting grant"),
+            Self::ConfidentialTransferRejected => (4013, "confidential_transfer_rejected", S::BAD_REQUEST, "The confidential transfer or its proofs are not valid"),
+            Self::IdempotencyKeyInProgress => (4014, "idempotency_key_in_progress", S::CONFLICT, "A request with the same Idempotency-Key is still being processed"),
+            Self::IdempotencyKeyReused => (4015, "idempotency_key_reused", S::UNPROCESSABLE_ENTITY, "The Idempotency-Key was already used for a different request"),
+            Self::InvalidProofOfPossession => (4016, "invalid_proof_of_possession", S::BAD_REQUEST, "The consensus key or its proof of possession is not valid"),
+            Self::InsufficientSelfStake => (4017, "insufficient_self_stake", S::BAD_REQUEST, "The self-stake is below the minimum or exceeds the unlocked balance"),
+            Self::ValidatorAlreadyRegistered => (4018, "validator_already_registered", S::CONFLICT, "The consensus key is already a registered candidate"),
+            Self::QueryLimitExceeded => (4019, "query_limit_exceeded", S::UNPROCESSABLE_ENTITY, "The SQL query exceeded the time or memory limit"),
+            Self::InsufficientBalance => (4020, "insufficient_balance", S::BAD_REQUEST, "The value exceeds the sender's balance after its pending transactions"),
+            Self::DeploymentRejected => (4021, "deployment_rejected", S::BAD_REQUEST, "The deployment is malformed or rejected by static analysis"),
+            Self::ProxyOperationRejected => (4022, "proxy_operation_rejected", S::BAD_REQUEST, "The proxy registration or upgrade is not authorized or cannot be applied"),
+            Self::NonceAlreadyUsed => (4023, "nonce_already_used", S::BAD_REQUEST, "The sender's nonce was already finalized in its shard"),
+            Self::Internal => (5000, "internal", S::INTERNAL_SERVER_ERROR, "The node failed to handle the request"),
+            Self::ServiceUnavailable => (5001, "service_unavailable", S::SERVICE_UNAVAILABLE, "A service the request needs is not running"),
+            Self::RpcPaused => (5002, "rpc_paused", S::SERVICE_UNAVAILABLE, "RPC is paused due to a predicted failure"),
+            Self::ForwardFailed => (5003, "forward_failed", S::SERVICE_UNAVAILABLE, "The replica could not forward the transaction upstream"),
+            Self::BlobPoolFull => (5004, "blob_pool_full", S::SERVICE_UNAVAILABLE, "The blob does not fit in the pending sidecar pool"),
+        }
+    }
+
+    /// 数値のコード
+    pub fn code(self) -> u32 {
+        self.info().0
+    }
+
+    /// 機械向けのスラッグ（`snake_case`）
+    pub fn slug(self) -> &'static str {
+        self.info().1
+    }
+
+    /// RESTのレスポンスのHTTPステータス
+    pub fn status(self) -> StatusCode {
+        self.info().2
+    }
+
+    /// コードの説明（個々のエラーのメッセージは状況に応じて詳しくなる）
+    pub fn description(self) -> &'static str {
+        self.info().3
+    }
+
+    /// 数値のコードから取得
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+
+    /// HTTPステータスに対応する汎用のコード（構造化されていない応答の解釈用）
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            503 => Self::ServiceUnavailable,
+            400..=499 => Self::InvalidRequest,
+            _ => Self::Internal,
+        }
+    }
+
+    /// コードの一覧の項目
+    pub fn entry(self) -> ErrorCodeEntry {
+        ErrorCodeEntry {
+            code: self.code(),
+            slug: self.slug().to_string(),
+            status: self.status().as_u16(),
+            description: self.description().to_string(),
+        }
+    }
+}
+
+/// コードの一覧の項目（`GET /errors`）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorCodeEntry {
+    pub code: u32,
+    pub slug: String,
+    pub status: u16,
+    pub description: String,
+}
+
+/// エラーのレスポンスの本文（`{"error": {...}}`）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: ApiError,
+}
+
+/// APIのエラー
+///
+/// RESTのエラーの本文の `error` で、CLIはノードの応答をこの形に読んでから表示します。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, thiserror::Error)]
+#[error("{message} [{slug} {code}]")]
+pub struct ApiError {
+    /// 数値のエラーコード
+    pub code: u32,
+    /// 機械向けのスラッグ
+    pub slug: String,
+    /// 人向けのメッセージ（変わることがあるため、分岐には使わない）
+    pub message: String,
+    /// HTTPステータス
+    pub status: u16,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code.code(),
+            slug: code.slug().to_string(),
+            message: message.into(),
+            status: code.status().as_u16(),
+        }
+    }
+
+    /// ノードの応答から読む（古いノードなど構造化されていない本文はステータスから汎用のコードにする）
+    pub fn from_response(status: u16, body: &[u8]) -> Self {
+        serde_json::from_slice::<ErrorBody>(body)
+            .map(|body| body.error)
+            .unwrap_or_else(|_| Self::new(ErrorCode::from_status(status), String::from_utf8_lossy(body).trim()))
+    }
+
+    /// 既知のコード（新しいノードが追加したコードは `None`）
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::from_code(self.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique_and_round_trip() {
+        let codes: HashSet<u32> = ErrorCode::ALL.iter().map(|c| c.code()).collect();
+        let slugs: HashSet<&str> = ErrorCode::ALL.iter().map(|c| c.slug()).collect();
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+        assert_eq!(slugs.len(), ErrorCode::ALL.len());
+        assert!(ErrorCode::ALL.windows(2).all(|w| w[0].code() < w[1].code()));
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_code(code.code()), Some(code));
+            // 分類ごとの数値の範囲とHTTPステータスの対応
+            let class = code.code() / 1000;
+            assert!((1..=5).contains(&class));
+            assert_eq!(class == 5, code.status().is_server_error(), "{:?}", code);
+        }
+
+        let error = ApiError::new(ErrorCode::FeeTooLow, "gas price 1 is below the current fee floor 5");
+        let body = serde_json::to_vec(&ErrorBody { error }).unwrap();
+        let parsed = ApiError::from_response(400, &body);
+        assert_eq!(parsed.error_code(), Some(ErrorCode::FeeTooLow));
+        assert_eq!(parsed.to_string(), "gas price 1 is below the current fee floor 5 [fee_too_low 4001]");
+
+        let legacy = ApiError::from_response(404, b"Not Found");
+        assert_eq!(legacy.error_code(), Some(ErrorCode::NotFound));
+        assert_eq!(legacy.message, "Not Found");
+    }
+}
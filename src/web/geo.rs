@@ -0,0 +1,211 @@
+//! 読み取りAPIの地理的ルーティング
+//!
+//! `GET /api/*` のリクエストをクライアントに最も近いレプリカへ転送し、
+//! 失敗した場合は次に近いノード、最後に自ノードで処理します。
+//! 転送したリクエストには `x-rustorium-routed` ヘッダーを付与し、再転送を防ぎます。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use anyhow::anyhow;
+use axum::{
+    extract::{ConnectInfo, OriginalUri, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, Deserialize};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use super::AppState;
+use crate::config::GeoSettings;
+use crate::core::cache::{GeoConfig, GeoIpTable, GeoRouter, NodeStatus, RegionMetrics};
+use crate::core::cache::NodeId;
+use crate::core::transaction::GeoLocation;
+
+/// 転送済みを示すヘッダー
+const ROUTED_HEADER: &str = "x-rustorium-routed";
+/// クライアントが明示するリージョン
+const REGION_HEADER: &str = "x-client-region";
+/// 処理したノード
+const SERVED_BY_HEADER: &str = "x-served-by";
+/// 自ノードのID
+const LOCAL_NODE: &str = "local";
+/// 常に自ノードで処理するパス
+const LOCAL_PATHS: &[&str] = &["/health", "/geo"];
+
+/// 地理的ルーティングのメトリクス
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GeoMetrics {
+    pub region: String,
+    pub regions: Vec<RegionMetrics>,
+    pub nodes: Vec<NodeStatus>,
+}
+
+/// 読み取りリクエストの転送
+pub struct GeoProxy {
+    router: RwLock<GeoRouter>,
+    geoip: GeoIpTable,
+    /// リージョン名から代表地点への対応
+    regions: HashMap<String, GeoLocation>,
+    local: GeoLocation,
+    client: reqwest::Client,
+}
+
+impl GeoProxy {
+    pub fn from_settings(settings: &GeoSettings) -> anyhow::Result<Self> {
+        let local = GeoLocation::new(settings.latitude, settings.longitude, settings.region.clone());
+        let mut router = GeoRouter::new(GeoConfig::from(settings));
+        let mut regions = HashMap::new();
+
+        router.add_node(LOCAL_NODE.to_string(), local.clone(), None);
+        regions.insert(local.region.clone(), local.clone());
+        for replica in &settings.replicas {
+            let location = GeoLocation::new(replica.latitude, replica.longitude, replica.region.clone());
+            regions.entry(replica.region.clone()).or_insert_with(|| location.clone());
+            router.add_node(replica.id.clone(), location, Some(replica.endpoint.clone()));
+        }
+
+        let geoip = match &settings.geoip_path {
+            Some(path) => GeoIpTable::load(path)?,
+            None => GeoIpTable::default(),
+        };
+        info!("Geo routing enabled with {} replicas", settings.replicas.len());
+
+        Ok(Self {
+            router: RwLock::new(router),
+            geoip,
+            regions,
+            local,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(settings.request_timeout))
+                .build()?,
+        })
+    }
+
+    /// クライアントの位置を解決
+    ///
+    /// `x-client-region` ヘッダー、GeoIP（`x-forwarded-for` または接続元アドレス）の順に参照し、
+    /// 解決できない場合は自ノードの位置とみなします。
+    pub fn client_location(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> GeoLocation {
+        if let Some(location) = headers
+            .get(REGION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|region| self.regions.get(region))
+        {
+            return location.clone();
+        }
+
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+
+        forwarded
+            .or(peer)
+            .and_then(|ip| self.geoip.lookup(ip))
+            .cloned()
+            .unwrap_or_else(|| self.local.clone())
+    }
+
+    pub async fn metrics(&self) -> GeoMetrics {
+        let router = self.router.read().await;
+        GeoMetrics {
+            region: self.local.region.clone(),
+            regions: router.metrics(),
+            nodes: router.nodes(),
+        }
+    }
+
+    /// レプリカへリクエストを転送
+    async fn forward(&self, node: &NodeId, base: &str, request: &Request) -> anyhow::Result<Response> {
+        let uri = request.extensions().get::<OriginalUri>().map_or(request.uri(), |u| &u.0);
+        let path = uri.path_and_query().map_or("/", |p| p.as_str());
+
+        let mut builder = self.client
+            .get(format!("{}{}", base.trim_end_matches('/'), path))
+            .header(ROUTED_HEADER, "1");
+        for (name, value) in request.headers() {
+            if name != header::HOST {
+                if let Ok(value) = value.to_str() {
+                    builder = builder.header(name.as_str(), value);
+                }
+            }
+        }
+
+        let upstream = builder.send().await?;
+        if upstream.status().is_server_error() {
+            return Err(anyhow!("replica returned {}", upstream.status()));
+        }
+        let status = StatusCode::from_u16(upstream.status().as_u16())?;
+        let content_type = upstream.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(HeaderValue::from_str)
+            .transpose()?;
+        let body = upstream.bytes().await?;
+
+        let mut response = (status, body.to_vec()).into_response();
+        if let Some(content_type) = content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        }
+        response.headers_mut().insert(SERVED_BY_HEADER, HeaderValue::from_str(node)?);
+        Ok(response)
+    }
+}
+
+/// 読み取りリクエストを最寄りのノードへ振り分けるミドルウェア
+pub async fn route_reads(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(geo) = state.geo.clone() else {
+        return next.run(request).await;
+    };
+    if request.method() != Method::GET
+        || request.headers().contains_key(ROUTED_HEADER)
+        || LOCAL_PATHS.iter().any(|p| request.uri().path().starts_with(p))
+    {
+        return next.run(request).await;
+    }
+
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0.ip());
+    let location = geo.client_location(request.headers(), peer);
+    let candidates = geo.router.read().await.route(&location);
+    let mut failovers = 0;
+
+    for node in candidates {
+        let endpoint = match geo.router.read().await.node(&node) {
+            Some(replica) => replica.endpoint.clone(),
+            None => continue,
+        };
+        let started = Instant::now();
+
+        let Some(base) = endpoint else {
+            let mut response = next.run(request).await;
+            let mut router = geo.router.write().await;
+            router.record_success(&node, started.elapsed());
+            router.record_request(&location.region, Some(&node), failovers);
+            response.headers_mut().insert(SERVED_BY_HEADER, HeaderValue::from_static(LOCAL_NODE));
+            return response;
+        };
+
+        match geo.forward(&node, &base, &request).await {
+            Ok(response) => {
+                let mut router = geo.router.write().await;
+                router.record_success(&node, started.elapsed());
+                router.record_request(&location.region, Some(&node), failovers);
+                return response;
+            }
+            Err(e) => {
+                warn!("Failed to route read to {}: {}", node, e);
+                geo.router.write().await.record_failure(&node);
+                failovers += 1;
+            }
+        }
+    }
+
+    // 候補のノードがすべて失敗した場合は自ノードで処理する
+    geo.router.write().await.record_request(&location.region, None, failovers);
+    next.run(request).await
+}
@@ -0,0 +1,481 @@
+//! GraphQL API
+//!
+//! ブロック・トランザクション・アカウントをGraphQLで公開します（`POST /graphql`、`GET /graphiql`）。
+//! 主な機能：
+//! - ブロック・トランザクション・アカウントのクエリ
+//! - 一覧はRESTと同じ `cursor`・`limit`・`sort` と絞り込み（`filter` にクエリパラメーターと同じ名前で指定）
+//! - Apollo Federation（`_service` のSDLと `_entities`）。APIゲートウェイが独自のアダプターなしに
+//!   チェーンのデータを既存のグラフへ統合できます
+//! - クエリの深さと複雑さの制限
+//! - エラーの `extensions` にRESTと共通のエラーコード（`code` と `slug`）
+//!
+//! エンティティのキーは `Block`（`hash` または `number`）、`Transaction`（`hash`）、`Account`（`address`）です。
+
+use std::collections::BTreeMap;
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, ErrorExtensions, Json as JsonScalar, Object,
+    Result, Schema,
+};
+use axum::{
+    Json, Router,
+    extract::State,
+    response::{Html, IntoResponse},
+    routing::{get, post},
+};
+use crate::core::block::{Block, explorer::TransactionDetail};
+use crate::core::cache::{AddressTx, TxDirection};
+use crate::core::mempool::PendingTransaction;
+use super::{AppError, AppState};
+use super::error_code::ErrorCode;
+use super::listing::{self, ListQuery, ListSpec};
+
+/// クエリの最大の深さ
+const MAX_DEPTH: usize = 16;
+/// クエリの最大の複雑さ（フィールド数の目安）
+const MAX_COMPLEXITY: usize = 2000;
+
+pub type RustoriumSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Federationを有効にしたスキーマを作成
+pub fn schema(state: AppState) -> RustoriumSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .enable_federation()
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/graphql", post(execute))
+        .route("/graphiql", get(graphiql))
+        .with_state(schema(state))
+}
+
+async fn execute(
+    State(schema): State<RustoriumSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let mut response = schema.execute(request).await;
+    // コードのないエラーは、クエリの検証のエラー（パスなし）とリゾルバーの内部エラーに分ける
+    for error in &mut response.errors {
+        let extensions = error.extensions.get_or_insert_with(Default::default);
+        if extensions.get("code").is_none() {
+            let code = if error.path.is_empty() { ErrorCode::InvalidRequest } else { ErrorCode::Internal };
+            extensions.set("code", code.code());
+            extensions.set("slug", code.slug());
+        }
+    }
+    Json(response)
+}
+
+/// RESTと共通のエラーコードを付けたエラー
+fn coded_error(code: ErrorCode, message: impl std::fmt::Display) -> async_graphql::Error {
+    async_graphql::Error::new(message.to_string()).extend_with(|_, e| {
+        e.set("code", code.code());
+        e.set("slug", code.slug());
+    })
+}
+
+/// APIのエラーをコードを保ったままGraphQLのエラーにする
+fn app_error(e: AppError) -> async_graphql::Error {
+    coded_error(e.code(), e)
+}
+
+/// 一覧の引数をRESTのクエリパラメーターと同じ規約で解析
+fn list_query(
+    ctx: &Context<'_>,
+    spec: &ListSpec,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    sort: Option<String>,
+    filter: Option<JsonScalar<BTreeMap<String, serde_json::Value>>>,
+) -> Result<ListQuery> {
+    let mut params: BTreeMap<String, String> = filter.map(|filter| filter.0).unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(value) => (name, value),
+            value => (name, value.to_string()),
+        })
+        .collect();
+    params.extend(cursor.map(|cursor| ("cursor".to_string(), cursor)));
+    params.extend(limit.map(|limit| ("limit".to_string(), limit.to_string())));
+    params.extend(sort.map(|sort| ("sort".to_string(), sort)));
+    ListQuery::parse(spec, params, &app(ctx).addresses).map_err(app_error)
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+fn app<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<AppState>()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// 高さを指定してブロックを取得
+    async fn block(&self, ctx: &Context<'_>, number: u64) -> Result<Option<BlockNode>> {
+        Ok(app(ctx).chain.get_block(number).await?.map(BlockNode))
+    }
+
+    /// ハッシュを指定してブロックを取得
+    async fn block_by_hash(&self, ctx: &Context<'_>, hash: String) -> Result<Option<BlockNode>> {
+        Ok(app(ctx).chain.get_block_by_hash(&hash).await?.map(BlockNode))
+    }
+
+    /// ブロックの一覧（既定は新しい順、`sort` は `height:desc` か `height:asc`）
+    ///
+    /// `filter` は `{"validator": "...", "min_timestamp": 1700000000}` のようにRESTのクエリパラメーターと
+    /// 同じ名前で指定します。
+    async fn blocks(
+        &self,
+        ctx: &Context<'_>,
+        cursor: Option<String>,
+        limit: Option<usize>,
+        sort: Option<String>,
+        filter: Option<JsonScalar<BTreeMap<String, serde_json::Value>>>,
+    ) -> Result<BlockPage> {
+        let state = app(ctx);
+        let query = list_query(ctx, &listing::BLOCKS, cursor, limit, sort, filter)?;
+        let page = listing::blocks(state, &query).await.map_err(app_error)?;
+        let mut items = Vec::with_capacity(page.items.len());
+        for summary in page.items {
+            items.extend(state.chain.get_block(summary.height).await?.map(BlockNode));
+        }
+        Ok(BlockPage { items, next_cursor: page.next_cursor })
+    }
+
+    /// 最新のブロック（ブロックがまだない場合は `null`）
+    async fn latest_block(&self, ctx: &Context<'_>) -> Result<Option<BlockNode>> {
+        let state = app(ctx);
+        match state.chain.head().await {
+            Some((height, _)) => Ok(state.chain.get_block(height).await?.map(BlockNode)),
+            None => Ok(None),
+        }
+    }
+
+    /// ハッシュを指定して確定したトランザクションを取得
+    async fn transaction(&self, ctx: &Context<'_>, hash: String) -> Result<Option<TransactionNode>> {
+        Ok(app(ctx).chain.find_transaction(&hash).await?.map(TransactionNode::committed))
+    }
+
+    /// アカウント（hex と bech32m のどちらの表記でも指定できる）
+    async fn account(&self, ctx: &Context<'_>, address: String) -> Result<AccountNode> {
+        let address = app(ctx).addresses.parse(&address)
+            .map_err(|e| coded_error(ErrorCode::InvalidAddress, e))?;
+        Ok(AccountNode { address })
+    }
+
+    #[graphql(entity)]
+    async fn find_block_by_hash(&self, ctx: &Context<'_>, hash: String) -> Result<Option<BlockNode>> {
+        self.block_by_hash(ctx, hash).await
+    }
+
+    #[graphql(entity)]
+    async fn find_block_by_number(&self, ctx: &Context<'_>, number: u64) -> Result<Option<BlockNode>> {
+        self.block(ctx, number).await
+    }
+
+    #[graphql(entity)]
+    async fn find_transaction_by_hash(&self, ctx: &Context<'_>, hash: String) -> Result<Option<TransactionNode>> {
+        self.transaction(ctx, hash).await
+    }
+
+    #[graphql(entity)]
+    async fn find_account_by_address(&self, ctx: &Context<'_>, address: String) -> Result<AccountNode> {
+        self.account(ctx, address).await
+    }
+}
+
+/// ブロックの一覧のページ
+pub struct BlockPage {
+    items: Vec<BlockNode>,
+    next_cursor: Option<String>,
+}
+
+#[Object]
+impl BlockPage {
+    async fn items(&self) -> &Vec<BlockNode> {
+        &self.items
+    }
+
+    /// 次のページのカーソル（最後のページは `null`）
+    async fn next_cursor(&self) -> Option<&str> {
+        self.next_cursor.as_deref()
+    }
+}
+
+/// 確定したブロック
+pub struct BlockNode(Block);
+
+#[Object(name = "Block")]
+impl BlockNode {
+    async fn number(&self) -> u64 {
+        self.0.height
+    }
+
+    async fn hash(&self) -> &str {
+        &self.0.hash
+    }
+
+    async fn parent_hash(&self) -> &str {
+        &self.0.parent_hash
+    }
+
+    /// 生成時刻（UNIX秒）
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    /// 生成したバリデーター
+    async fn validator(&self) -> &str {
+        &self.0.validator
+    }
+
+    async fn gas_used(&self) -> u64 {
+        self.0.gas_used
+    }
+
+    async fn gas_limit(&self) -> u64 {
+        self.0.gas_limit
+    }
+
+    async fn base_fee(&self) -> u64 {
+        self.0.base_fee
+    }
+
+    async fn receipts_root(&self) -> &str {
+        &self.0.receipts_root
+    }
+
+    /// JSONでのバイト数
+    async fn size(&self) -> usize {
+        self.0.encoded_size()
+    }
+
+    async fn transactions(&self) -> Vec<TransactionNode> {
+        self.0.transactions.iter().enumerate()
+            .map(|(index, tx)| TransactionNode {
+                transaction: tx.clone(),
+                location: Location {
+                    block_number: self.0.height,
+                    block_hash: self.0.hash.clone(),
+                    index: index as u32,
+                    timestamp: self.0.timestamp,
+                },
+            })
+            .collect()
+    }
+}
+
+/// トランザクションのブロック内の位置
+struct Location {
+    block_number: u64,
+    block_hash: String,
+    index: u32,
+    timestamp: u64,
+}
+
+/// 確定したトランザクション
+pub struct TransactionNode {
+    transaction: PendingTransaction,
+    location: Location,
+}
+
+impl TransactionNode {
+    fn committed(detail: TransactionDetail) -> Self {
+        Self {
+            transaction: detail.transaction,
+            location: Location {
+                block_number: detail.block_height,
+                block_hash: detail.block_hash,
+                index: detail.index,
+                timestamp: detail.timestamp,
+            },
+        }
+    }
+}
+
+#[Object(name = "Transaction")]
+impl TransactionNode {
+    async fn hash(&self) -> &str {
+        &self.transaction.hash
+    }
+
+    async fn from(&self) -> AccountNode {
+        AccountNode { address: self.transaction.from.clone() }
+    }
+
+    async fn to(&self) -> AccountNode {
+        AccountNode { address: self.transaction.to.clone() }
+    }
+
+    async fn value(&self) -> u64 {
+        self.transaction.value
+    }
+
+    async fn nonce(&self) -> u64 {
+        self.transaction.nonce
+    }
+
+    async fn gas_price(&self) -> u64 {
+        self.transaction.gas_price
+    }
+
+    async fn gas_limit(&self) -> u64 {
+        self.transaction.gas_limit
+    }
+
+    /// データ（hex）
+    async fn data(&self) -> String {
+        hex::encode(&self.transaction.data)
+    }
+
+    async fn block_number(&self) -> u64 {
+        self.location.block_number
+    }
+
+    /// ブロック内の位置
+    async fn index(&self) -> u32 {
+        self.location.index
+    }
+
+    /// ブロックの生成時刻（UNIX秒）
+    async fn timestamp(&self) -> u64 {
+        self.location.timestamp
+    }
+
+    async fn block(&self, ctx: &Context<'_>) -> Result<Option<BlockNode>> {
+        Ok(app(ctx).chain.get_block_by_hash(&self.location.block_hash).await?.map(BlockNode))
+    }
+
+    /// 確定後に積まれたブロック数（自身のブロックを含む）
+    async fn confirmations(&self, ctx: &Context<'_>) -> u64 {
+        app(ctx).chain.head().await
+            .map_or(1, |(head, _)| head.saturating_sub(self.location.block_number) + 1)
+    }
+}
+
+/// アカウント（残高と履歴はマテリアライズドビューから取得する）
+pub struct AccountNode {
+    address: String,
+}
+
+#[Object(name = "Account")]
+impl AccountNode {
+    /// 内部表記（小文字の hex）
+    async fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// このネットワークの bech32m 表記
+    async fn bech32(&self, ctx: &Context<'_>) -> Result<String> {
+        Ok(app(ctx).addresses.encode(&self.address)?)
+    }
+
+    async fn balance(&self, ctx: &Context<'_>) -> Result<u64> {
+        Ok(app(ctx).views.balance(&self.address).await?)
+    }
+
+    /// 次に使うノンス（確定したトランザクションのみ）
+    async fn nonce(&self, ctx: &Context<'_>) -> Result<u64> {
+        Ok(app(ctx).views.next_nonce(&self.address).await?)
+    }
+
+    /// 送受信したトランザクション（新しい順）
+    ///
+    /// `filter` は `direction`・`counterparty`・`value`・`timestamp`（`min_`・`max_` を付けた範囲も可）です。
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        cursor: Option<String>,
+        limit: Option<usize>,
+        sort: Option<String>,
+        filter: Option<JsonScalar<BTreeMap<String, serde_json::Value>>>,
+    ) -> Result<AccountTransactionPage> {
+        let query = list_query(ctx, &listing::ACCOUNT_TRANSACTIONS, cursor, limit, sort, filter)?;
+        let page = listing::account_transactions(app(ctx), &self.address, &query).await.map_err(app_error)?;
+        Ok(AccountTransactionPage { items: page.items, next_cursor: page.next_cursor })
+    }
+}
+
+/// アカウントの履歴のページ
+pub struct AccountTransactionPage {
+    items: Vec<AddressTx>,
+    next_cursor: Option<String>,
+}
+
+#[Object]
+impl AccountTransactionPage {
+    async fn items(&self) -> Vec<AccountTransaction<'_>> {
+        self.items.iter().map(AccountTransaction).collect()
+    }
+
+    /// 次のページのカーソル（最後のページは `null`）
+    async fn next_cursor(&self) -> Option<&str> {
+        self.next_cursor.as_deref()
+    }
+}
+
+/// アカウントの履歴の1件
+pub struct AccountTransaction<'a>(&'a AddressTx);
+
+#[Object]
+impl AccountTransaction<'_> {
+    async fn hash(&self) -> &str {
+        &self.0.hash
+    }
+
+    async fn block_number(&self) -> u64 {
+        self.0.height
+    }
+
+    /// 送信（`true`）か受信（`false`）か
+    async fn outgoing(&self) -> bool {
+        matches!(self.0.direction, TxDirection::Out)
+    }
+
+    /// 相手のアドレス
+    async fn counterparty(&self) -> &str {
+        &self.0.counterparty
+    }
+
+    async fn value(&self) -> u64 {
+        self.0.value
+    }
+
+    /// 送信時に支払った手数料
+    async fn fee(&self) -> Option<u64> {
+        self.0.fee
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_federation_sdl_declares_entity_keys() {
+        // `_service` はデータを参照しないため、状態なしのスキーマで確認できる
+        let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .enable_federation()
+            .finish();
+        let response = schema.execute("{ _service { sdl } }").await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let sdl = response.data.into_json().unwrap()["_service"]["sdl"].as_str().unwrap().to_string();
+
+        assert!(sdl.contains(r#"type Block @key(fields: "hash") @key(fields: "number")"#), "{}", sdl);
+        assert!(sdl.contains(r#"type Transaction @key(fields: "hash")"#), "{}", sdl);
+        assert!(sdl.contains(r#"type Account @key(fields: "address")"#), "{}", sdl);
+        // エンティティの検索はSDLに含めない（ゲートウェイは `_entities` を使う）
+        assert!(!sdl.contains("findBlockByHash"), "{}", sdl);
+    }
+}
@@ -0,0 +1,280 @@
+//! 確定したブロックとイベントの gRPC ストリーミング（`grpc` フィーチャー）
+//!
+//! 内部サービス向けに `proto/stream.proto` の `ChainStream` を提供します。
+//! - `SubscribeBlocks`：確定したブロック（高さの順、欠けも重複もない）
+//! - `SubscribeEvents`：アドレスとトピックで絞り込んだイベント
+//!
+//! `from_height` を指定すると保存済みのブロックから送り、最新ブロックに追いついたら
+//! 新しいブロックの送信に切り替えます。切断した場合は最後に受け取った高さ + 1 から再開できます
+//! （イベントは同じ高さから再開し、`log_index` で重複を除きます）。
+//! 送信はストリームごとに `buffer` 件まで先行し、受信側が遅い場合は送信を待ちます。
+//! 待っている間に取りこぼした通知はストレージから読み直すため、遅い受信側もブロックを失いません。
+
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+use crate::config::GrpcSettings;
+use crate::core::block::{Block, Chain};
+use super::rpc::same_hex;
+
+pub mod proto {
+    tonic::include_proto!("rustorium.stream.v1");
+}
+
+use proto::chain_stream_server::{ChainStream, ChainStreamServer};
+
+/// `ChainStream` の実装
+pub struct ChainStreamService {
+    chain: Arc<Chain>,
+    buffer: usize,
+    streams: Arc<Semaphore>,
+}
+
+impl ChainStreamService {
+    pub fn new(chain: Arc<Chain>, settings: &GrpcSettings) -> Self {
+        Self {
+            chain,
+            buffer: settings.buffer.max(1),
+            streams: Arc::new(Semaphore::new(settings.max_streams.max(1))),
+        }
+    }
+
+    /// `from` から（省略時は次に確定するブロックから）ブロックを変換して送るストリームを開く
+    async fn open<T, F>(&self, from: Option<u64>, convert: F) -> Result<Response<ReceiverStream<Result<T, Status>>>, Status>
+    where
+        T: Send + 'static,
+        F: Fn(&Block) -> Vec<T> + Send + 'static,
+    {
+        let permit = self.streams.clone().try_acquire_owned()
+            .map_err(|_| Status::resource_exhausted("Too many open streams"))?;
+        // 先に購読してから高さを確認し、その間に確定したブロックを取りこぼさない
+        let commits = self.chain.subscribe();
+        let next = match from {
+            Some(from) => {
+                // チェックポイントから開始したノードは基点より前のブロックを持たない
+                let base = self.chain.base().await.map_err(internal)?.unwrap_or(0);
+                if from < base {
+                    return Err(Status::out_of_range(format!("Blocks before {} are not stored on this node", base)));
+                }
+                from
+            }
+            None => self.chain.head().await.map_or(0, |(height, _)| height + 1),
+        };
+
+        let (tx, rx) = mpsc::channel(self.buffer);
+        let chain = self.chain.clone();
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Err(status) = follow(&chain, commits, next, &tx, convert).await {
+                let _ = tx.send(Err(status)).await;
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+#[tonic::async_trait]
+impl ChainStream for ChainStreamService {
+    type SubscribeBlocksStream = ReceiverStream<Result<proto::Block, Status>>;
+    type SubscribeEventsStream = ReceiverStream<Result<proto::Event, Status>>;
+
+    async fn subscribe_blocks(
+        &self,
+        request: Request<proto::SubscribeBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeBlocksStream>, Status> {
+        let request = request.into_inner();
+        let include_transactions = request.include_transactions;
+        self.open(request.from_height, move |block| vec![block_message(block, include_transactions)]).await
+    }
+
+    async fn subscribe_events(
+        &self,
+        request: Request<proto::SubscribeEventsRequest>,
+    ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
+        let request = request.into_inner();
+        let filter = EventFilter {
+            addresses: request.addresses,
+            topics: request.topics.into_iter().map(|topic| topic.any_of).collect(),
+        };
+        self.open(request.from_height, move |block| filter.events(block)).await
+    }
+}
+
+/// gRPC サーバーを実行
+pub async fn serve(service: ChainStreamService, listener: tokio::net::TcpListener) -> anyhow::Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(ChainStreamServer::new(service))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await?;
+    Ok(())
+}
+
+/// `next` から高さの順にブロックを送り続ける（受信側が閉じた場合は終了）
+async fn follow<T, F>(
+    chain: &Chain,
+    mut commits: broadcast::Receiver<Arc<Block>>,
+    mut next: u64,
+    tx: &mpsc::Sender<Result<T, Status>>,
+    convert: F,
+) -> Result<(), Status>
+where
+    F: Fn(&Block) -> Vec<T>,
+{
+    loop {
+        // 保存済みのブロック（再開時と、通知を取りこぼした場合）
+        let head = chain.head().await.map(|(height, _)| height);
+        while head.is_some_and(|head| next <= head) {
+            let block = chain.get_block(next).await.map_err(internal)?
+                .ok_or_else(|| Status::internal(format!("Block {} is missing", next)))?;
+            if !send(tx, convert(&block)).await {
+                return Ok(());
+            }
+            next += 1;
+        }
+
+        // 新しいブロック
+        loop {
+            let received = tokio::select! {
+                _ = tx.closed() => return Ok(()),
+                received = commits.recv() => received,
+            };
+            match received {
+                Ok(block) if block.height < next => continue,
+                Ok(block) if block.height == next => {
+                    if !send(tx, convert(&block)).await {
+                        return Ok(());
+                    }
+                    next += 1;
+                }
+                // 間のブロックを取りこぼした場合はストレージから読み直す
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => break,
+                Err(broadcast::error::RecvError::Closed) => return Err(Status::unavailable("The node is shutting down")),
+            }
+        }
+    }
+}
+
+/// 受信側が閉じた場合は `false`
+async fn send<T>(tx: &mpsc::Sender<Result<T, Status>>, items: Vec<T>) -> bool {
+    for item in items {
+        if tx.send(Ok(item)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+fn internal(e: anyhow::Error) -> Status {
+    Status::internal(e.to_string())
+}
+
+fn block_message(block: &Block, include_transactions: bool) -> proto::Block {
+    proto::Block {
+        height: block.height,
+        hash: block.hash.clone(),
+        parent_hash: block.parent_hash.clone(),
+        timestamp: block.timestamp,
+        validator: block.validator.clone(),
+        gas_limit: block.gas_limit,
+        gas_used: block.gas_used,
+        base_fee: block.base_fee,
+        receipts_root: block.receipts_root.clone(),
+        logs_bloom: block.logs_bloom.clone(),
+        transaction_count: block.transactions.len() as u32,
+        event_count: block.events.len() as u32,
+        transactions: if include_transactions {
+            block.transactions.iter()
+                .map(|tx| proto::Transaction {
+                    hash: tx.hash.clone(),
+                    from: tx.from.clone(),
+                    to: tx.to.clone(),
+                    value: tx.value,
+                    nonce: tx.nonce,
+                    gas_price: tx.gas_price,
+                    gas_limit: tx.gas_limit,
+                    data: tx.data.clone(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// `SubscribeEvents` の条件
+#[derive(Debug, Clone, Default)]
+struct EventFilter {
+    /// 発行したコントラクト（いずれか、空はすべて）
+    addresses: Vec<String>,
+    /// 位置ごとのトピック（空は任意）
+    topics: Vec<Vec<String>>,
+}
+
+impl EventFilter {
+    /// ブロック内で条件に一致するイベント
+    fn events(&self, block: &Block) -> Vec<proto::Event> {
+        block.events.iter()
+            .enumerate()
+            .filter(|(_, event)| self.matches(&event.address, &event.topics))
+            .map(|(log_index, event)| proto::Event {
+                block_height: block.height,
+                block_hash: block.hash.clone(),
+                log_index: log_index as u32,
+                tx_hash: event.tx_hash.clone(),
+                address: event.address.clone(),
+                topics: event.topics.clone(),
+                data: event.data.clone(),
+            })
+            .collect()
+    }
+
+    fn matches(&self, address: &str, topics: &[String]) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.iter().any(|a| same_hex(a, address)) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(i, any_of)| {
+            any_of.is_empty() || topics.get(i).is_some_and(|topic| any_of.iter().any(|t| same_hex(t, topic)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+    use crate::core::storage::{StorageEngine, redb_storage::RedbStorage};
+
+    #[tokio::test]
+    async fn test_subscribe_blocks_resumes_then_follows() {
+        let storage: Arc<dyn StorageEngine> = RedbStorage::memory();
+        let chain = Arc::new(Chain::open(storage).await.unwrap());
+        for _ in 0..3 {
+            chain.commit(chain.next_signed_block(vec![]).await).await.unwrap();
+        }
+        let service = ChainStreamService::new(chain.clone(), &GrpcSettings { buffer: 1, max_streams: 1, ..Default::default() });
+
+        // 保存済みの高さ1から再開し、確定した新しいブロックに続く
+        let request = proto::SubscribeBlocksRequest { from_height: Some(1), include_transactions: false };
+        let mut stream = service.subscribe_blocks(Request::new(request.clone())).await.unwrap().into_inner();
+        assert_eq!(stream.next().await.unwrap().unwrap().height, 1);
+        assert_eq!(stream.next().await.unwrap().unwrap().height, 2);
+        chain.commit(chain.next_signed_block(vec![]).await).await.unwrap();
+        let block = stream.next().await.unwrap().unwrap();
+        assert_eq!((block.height, block.parent_hash), (3, chain.get_block(2).await.unwrap().unwrap().hash));
+
+        // ストリーム数の上限
+        let status = service.subscribe_blocks(Request::new(request)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        let filter = EventFilter {
+            addresses: vec!["0xAbC".to_string()],
+            topics: vec![vec![], vec!["0x01".to_string(), "0x02".to_string()]],
+        };
+        let topics = vec!["ff".to_string(), "02".to_string()];
+        assert!(filter.matches("abc", &topics));
+        assert!(!filter.matches("123", &topics));
+        assert!(!filter.matches("abc", &topics[..1]));
+        assert!(EventFilter::default().matches("anything", &[]));
+    }
+}
@@ -0,0 +1,37 @@
+//! ハッシュタイムロック（HTLC）API
+//!
+//! ロックの状態と、受け取りで公開された原像を提供します（`/api/htlc`）。
+//! 作成・受け取り・返金は HTLC のアドレス宛てのトランザクションで行います（`core::htlc`）。
+
+use std::sync::Arc;
+use axum::{
+    Router,
+    routing::get,
+    extract::{Path, State},
+    response::{IntoResponse, Json},
+};
+
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::core::htlc::HtlcLedger;
+
+struct HtlcServer {
+    ledger: Arc<HtlcLedger>,
+}
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/:id", get(get_lock))
+        .with_state(Arc::new(HtlcServer { ledger: state.htlc }))
+}
+
+/// ロック（IDは作成したトランザクションのハッシュ）
+async fn get_lock(
+    State(server): State<Arc<HtlcServer>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let id = id.trim_start_matches("0x").to_lowercase();
+    let lock = server.ledger.lock(&id).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::HtlcNotFound, format!("HTLC {} not found", id)))?;
+    Ok(Json(lock))
+}
@@ -0,0 +1,257 @@
+//! トランザクション送信の冪等キー
+//!
+//! `POST /transactions` に `Idempotency-Key` ヘッダーを付けると、ネットワークのタイムアウト後に
+//! 同じ内容を再送しても二重の送金を作らず、最初の送信のハッシュを返します。
+//! 受け付けたキーは `api.idempotency.ttl` 秒の間メモリに保持します（ノードの再起動で消える）。
+//! キーは呼び出し元ごとの名前空間（APIキー、なければトランザクションの送信者）で区別するため、
+//! 他の利用者が同じキーを先に使って送信を妨げることはできません。
+//! 拒否された送信（とエラーや切断で処理を終えなかった送信）はキーを保持しないため、
+//! 同じキーで修正して再送できます。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use axum::http::{header, HeaderMap};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::AppError;
+use super::error_code::ErrorCode;
+use crate::config::IdempotencySettings;
+
+/// 冪等キーのヘッダー
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// 保持していた結果を返したことを示すヘッダー
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+/// キーの最大の長さ
+const MAX_KEY_LENGTH: usize = 255;
+
+#[derive(Debug, Error)]
+pub enum IdempotencyError {
+    #[error("Idempotency-Key must be 1 to {MAX_KEY_LENGTH} visible ASCII characters")]
+    InvalidKey,
+
+    #[error("a request with Idempotency-Key {0} is still being processed")]
+    InProgress(String),
+
+    #[error("Idempotency-Key {0} was already used for a different request")]
+    Mismatch(String),
+}
+
+impl From<IdempotencyError> for AppError {
+    fn from(e: IdempotencyError) -> Self {
+        let code = match e {
+            IdempotencyError::InvalidKey => ErrorCode::InvalidIdempotencyKey,
+            IdempotencyError::InProgress(_) => ErrorCode::IdempotencyKeyInProgress,
+            IdempotencyError::Mismatch(_) => ErrorCode::IdempotencyKeyReused,
+        };
+        AppError::coded(code, e.to_string())
+    }
+}
+
+/// キーの予約の結果
+pub enum Reservation<'a> {
+    /// 初めてのキー（送信を処理し、受け付けたら `complete` を呼ぶ）
+    New(Pending<'a>),
+    /// 同じ内容で受け付け済み（元のトランザクションのハッシュ）
+    Replay(String),
+}
+
+/// 処理中のキーの予約
+///
+/// `complete` を呼ばずに破棄すると（拒否やエラー、リクエストの中断）予約を解除し、
+/// 同じキーで再送できるようにします。
+pub struct Pending<'a> {
+    cache: &'a IdempotencyCache,
+    key: String,
+    completed: bool,
+}
+
+impl Pending<'_> {
+    /// 受け付けたトランザクションのハッシュを記録
+    pub fn complete(mut self, hash: &str) {
+        self.cache.complete(&self.key, hash);
+        self.completed = true;
+    }
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.cache.abandon(&self.key);
+        }
+    }
+}
+
+struct Entry {
+    /// リクエストの本文のハッシュ
+    fingerprint: String,
+    /// 受け付けたトランザクションのハッシュ（処理中は `None`）
+    hash: Option<String>,
+    created: Instant,
+}
+
+#[derive(Default)]
+struct Keys {
+    entries: HashMap<String, Entry>,
+    /// 作成順（期限切れと上限超過の破棄に使う）
+    order: VecDeque<(Instant, String)>,
+}
+
+/// 冪等キーと送信結果
+pub struct IdempotencyCache {
+    ttl: Duration,
+    max_keys: usize,
+    keys: Mutex<Keys>,
+}
+
+impl IdempotencyCache {
+    pub fn new(settings: &IdempotencySettings) -> Self {
+        Self {
+            ttl: Duration::from_secs(settings.ttl),
+            max_keys: settings.max_keys.max(1),
+            keys: Mutex::default(),
+        }
+    }
+
+    /// 呼び出し元 `scope` のキーを予約する
+    ///
+    /// 同じ内容で受け付け済みなら元のハッシュを返します。処理中のキーと、
+    /// 別の内容に使われたキーはエラーです。別の呼び出し元の同じキーとは区別します。
+    pub fn begin(&self, scope: &str, key: &str, fingerprint: &str, now: Instant) -> Result<Reservation<'_>, IdempotencyError> {
+        let scoped = format!("{}\n{}", scope, key);
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict(&mut keys, now);
+        if let Some(entry) = keys.entries.get(&scoped) {
+            if entry.fingerprint != fingerprint {
+                return Err(IdempotencyError::Mismatch(key.to_string()));
+            }
+            return match &entry.hash {
+                Some(hash) => Ok(Reservation::Replay(hash.clone())),
+                None => Err(IdempotencyError::InProgress(key.to_string())),
+            };
+        }
+        keys.entries.insert(scoped.clone(), Entry { fingerprint: fingerprint.to_string(), hash: None, created: now });
+        keys.order.push_back((now, scoped.clone()));
+        Ok(Reservation::New(Pending { cache: self, key: scoped, completed: false }))
+    }
+
+    /// 受け付けたトランザクションのハッシュを記録
+    fn complete(&self, key: &str, hash: &str) {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = keys.entries.get_mut(key) {
+            entry.hash = Some(hash.to_string());
+        }
+    }
+
+    /// 拒否された送信の予約を解除（同じキーで再送できるようにする）
+    fn abandon(&self, key: &str) {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        if keys.entries.get(key).is_some_and(|entry| entry.hash.is_none()) {
+            keys.entries.remove(key);
+        }
+    }
+
+    /// 期限切れのキーと、上限を超えた古いキーを破棄
+    fn evict(&self, keys: &mut Keys, now: Instant) {
+        while let Some((created, key)) = keys.order.front().cloned() {
+            if now.duration_since(created) < self.ttl && keys.entries.len() < self.max_keys {
+                break;
+            }
+            keys.order.pop_front();
+            // 解除後に同じキーで作り直したものは残す
+            if keys.entries.get(&key).is_some_and(|entry| entry.created == created) {
+                keys.entries.remove(&key);
+            }
+        }
+    }
+}
+
+/// リクエストの冪等キー（ヘッダーがない場合は `None`）
+pub fn key(headers: &HeaderMap) -> Result<Option<String>, IdempotencyError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value.to_str().map_err(|_| IdempotencyError::InvalidKey)?;
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(IdempotencyError::InvalidKey);
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// キーの名前空間（APIキーを送った場合はそのハッシュ、なければトランザクションの送信者）
+pub fn scope(headers: &HeaderMap, sender: &str) -> String {
+    let credential = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .filter(|credential| !credential.is_empty());
+    match credential {
+        Some(credential) => format!("token:{}", hex::encode(Sha256::digest(credential.as_bytes()))),
+        None => format!("sender:{}", sender.trim().trim_start_matches("0x").to_lowercase()),
+    }
+}
+
+/// リクエストの本文のハッシュ（同じキーで別の内容を送ったことの検出に使う）
+pub fn fingerprint<T: Serialize>(request: &T) -> Result<String, serde_json::Error> {
+    Ok(hex::encode(Sha256::digest(serde_json::to_vec(request)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_new(reservation: Result<Reservation<'_>, IdempotencyError>) -> bool {
+        matches!(reservation, Ok(Reservation::New(_)))
+    }
+
+    fn replayed(reservation: Result<Reservation<'_>, IdempotencyError>) -> Option<String> {
+        match reservation {
+            Ok(Reservation::Replay(hash)) => Some(hash),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_replays_accepted_submission_and_rejects_reuse() {
+        let cache = IdempotencyCache::new(&IdempotencySettings { ttl: 60, max_keys: 2 });
+        let now = Instant::now();
+
+        let Ok(Reservation::New(pending)) = cache.begin("alice", "pay-1", "a", now) else {
+            panic!("pay-1 is a new key");
+        };
+        assert!(matches!(cache.begin("alice", "pay-1", "a", now), Err(IdempotencyError::InProgress(_))));
+        pending.complete("hash-1");
+        assert_eq!(replayed(cache.begin("alice", "pay-1", "a", now)).as_deref(), Some("hash-1"));
+        assert!(matches!(cache.begin("alice", "pay-1", "b", now), Err(IdempotencyError::Mismatch(_))));
+        // 別の呼び出し元の同じキーは別のもの
+        assert!(is_new(cache.begin("mallory", "pay-1", "b", now)));
+
+        // 完了せずに破棄した予約（拒否・エラー・中断）は同じキーで再送できる
+        assert!(is_new(cache.begin("alice", "pay-2", "c", now)));
+        let Ok(Reservation::New(pending)) = cache.begin("alice", "pay-2", "d", now) else {
+            panic!("pay-2 was released");
+        };
+        pending.complete("hash-2");
+        assert_eq!(replayed(cache.begin("alice", "pay-2", "d", now)).as_deref(), Some("hash-2"));
+
+        // 上限を超えると古いキーから、期限を過ぎると全て破棄される
+        assert!(is_new(cache.begin("alice", "pay-3", "e", now)));
+        assert!(is_new(cache.begin("alice", "pay-1", "b", now)));
+        assert!(is_new(cache.begin("alice", "pay-2", "x", now + Duration::from_secs(61))));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(key(&headers).unwrap(), None);
+        headers.insert(IDEMPOTENCY_KEY_HEADER, "order 42".parse().unwrap());
+        assert!(matches!(key(&headers), Err(IdempotencyError::InvalidKey)));
+        headers.insert(IDEMPOTENCY_KEY_HEADER, "5f0c2a9e-order-42".parse().unwrap());
+        assert_eq!(key(&headers).unwrap().as_deref(), Some("5f0c2a9e-order-42"));
+
+        // APIキーがあればそれで、なければ送信者で区別する
+        assert_eq!(scope(&headers, "0xABCD"), "sender:abcd");
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        let scoped = scope(&headers, "0xABCD");
+        assert!(scoped.starts_with("token:") && !scoped.contains("secret"));
+    }
+}
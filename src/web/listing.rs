@@ -0,0 +1,726 @@
+//! 一覧のエンドポイントの共通のクエリパラメーター
+//!
+//! ブロック・トランザクション・アカウント・コントラクト・バリデーターの一覧は、RESTとGraphQLで
+//! 同じ規約のページ・並び順・絞り込みを受け付けます。
+//! - `cursor` と `limit`: 前のページの `next_cursor` を渡して次のページを取得する（カーソルは不透明な文字列）
+//! - `sort=<フィールド>:<asc|desc>`: 並び順（使えるフィールドと向きは一覧ごとに決まる）
+//! - 絞り込み: `<フィールド>=<値>` は一致、数値のフィールドの `min_<フィールド>` と
+//!   `max_<フィールド>` は範囲（両端を含む）
+//!
+//! 一覧が知らないパラメーターと不正な値は、黙って無視せずに400（`invalid_sort`・`invalid_filter`）に
+//! します。絞り込んだページは `limit` より少ないことがあるため、`next_cursor` が `null` になるまで
+//! 続けて取得します。
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{AppError, AppState, Result};
+use super::error_code::ErrorCode;
+use crate::core::block::explorer::{BlockSummary, MAX_BLOCK_PAGE};
+use crate::core::cache::{AddressTx, TokenHolder, TxDirection};
+use crate::core::cache::views::MAX_ARCHIVE_PAGE;
+use crate::core::consensus::market::MarketEntry;
+use crate::core::consensus::performance::ValidatorPerformance;
+use crate::core::consensus::registry::ValidatorCandidate;
+use crate::core::contract::{MatchStatus, VerifiedContractSummary};
+use crate::core::contract::metrics::ContractUsage;
+use crate::core::wallet::AddressFormat;
+
+/// 既定の件数
+pub const DEFAULT_LIMIT: usize = 50;
+/// メモリ上の一覧の `limit` の上限
+const MAX_PAGE: usize = 1000;
+/// 絞り込みで1ページを埋めるために読む要素の上限
+const MAX_SCANNED: usize = 5_000;
+
+/// 並びの向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// 並び順（`height:desc` など）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: &'static str,
+    pub direction: Direction,
+}
+
+impl Sort {
+    pub const fn asc(field: &'static str) -> Self {
+        Self { field, direction: Direction::Asc }
+    }
+
+    pub const fn desc(field: &'static str) -> Self {
+        Self { field, direction: Direction::Desc }
+    }
+
+    pub fn is_ascending(self) -> bool {
+        self.direction == Direction::Asc
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::Asc => "asc",
+            Direction::Desc => "desc",
+        };
+        write!(f, "{}:{}", self.field, direction)
+    }
+}
+
+/// 絞り込みの値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    /// 符号なし整数（`min_` と `max_` で範囲を指定できる）
+    Number,
+    /// 小数（`min_` と `max_` で範囲を指定できる）
+    Decimal,
+    /// アドレス（hex か bech32m。内部表記で比較する）
+    Address,
+    /// 列挙値などの文字列（大文字と小文字を区別しない）
+    Text,
+}
+
+/// 一覧が受け付けるパラメーター
+#[derive(Debug)]
+pub struct ListSpec {
+    /// 使える並び順（先頭が既定）
+    pub sorts: &'static [Sort],
+    /// 絞り込めるフィールド
+    pub filters: &'static [(&'static str, FilterKind)],
+    /// 一覧に固有のその他のパラメーター（`window` など）
+    pub params: &'static [&'static str],
+    /// `limit` の上限
+    pub max_limit: usize,
+}
+
+/// フィールドの条件
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Number { min: Option<u64>, max: Option<u64> },
+    Decimal { min: Option<f64>, max: Option<f64> },
+    Text(String),
+}
+
+/// 絞り込みのパラメーターの種類
+#[derive(Debug, Clone, Copy)]
+enum Bound {
+    Eq,
+    Min,
+    Max,
+}
+
+/// 解析した一覧のクエリ
+#[derive(Debug, Clone)]
+pub struct ListQuery {
+    pub cursor: Option<String>,
+    pub limit: usize,
+    pub sort: Sort,
+    filters: BTreeMap<&'static str, Condition>,
+    params: BTreeMap<String, String>,
+}
+
+impl ListQuery {
+    /// クエリパラメーターを一覧の指定に従って解析
+    pub fn parse(spec: &ListSpec, params: BTreeMap<String, String>, addresses: &AddressFormat) -> Result<Self> {
+        let mut query = Self {
+            cursor: None,
+            limit: DEFAULT_LIMIT.min(spec.max_limit),
+            sort: spec.sorts[0],
+            filters: BTreeMap::new(),
+            params: BTreeMap::new(),
+        };
+        for (name, value) in params {
+            match name.as_str() {
+                "cursor" => query.cursor = Some(value).filter(|cursor| !cursor.is_empty()),
+                "limit" => {
+                    let limit = value.parse::<usize>()
+                        .map_err(|_| AppError::BadRequest(format!("Invalid limit '{}'", value)))?;
+                    query.limit = limit.clamp(1, spec.max_limit);
+                }
+                "sort" => query.sort = parse_sort(spec, &value)?,
+                _ if spec.params.contains(&name.as_str()) => {
+                    query.params.insert(name, value);
+                }
+                _ => query.add_filter(spec, &name, &value, addresses)?,
+            }
+        }
+        Ok(query)
+    }
+
+    fn add_filter(&mut self, spec: &ListSpec, name: &str, value: &str, addresses: &AddressFormat) -> Result<()> {
+        let (field, kind, bound) = lookup_filter(spec, name).ok_or_else(|| {
+            let known: Vec<String> = spec.filters.iter()
+                .flat_map(|(field, kind)| match kind {
+                    FilterKind::Number | FilterKind::Decimal => {
+                        vec![field.to_string(), format!("min_{}", field), format!("max_{}", field)]
+                    }
+                    FilterKind::Address | FilterKind::Text => vec![field.to_string()],
+                })
+                .collect();
+            AppError::coded(
+                ErrorCode::InvalidFilter,
+                format!("Unknown filter '{}' (expected one of: {})", name, known.join(", ")),
+            )
+        })?;
+        let invalid = || AppError::coded(ErrorCode::InvalidFilter, format!("Invalid value '{}' for {}", value, name));
+        match kind {
+            FilterKind::Number => {
+                let value: u64 = value.parse().map_err(|_| invalid())?;
+                let condition = self.filters.entry(field).or_insert(Condition::Number { min: None, max: None });
+                if let Condition::Number { min, max } = condition {
+                    set_bound(min, max, bound, value);
+                }
+            }
+            FilterKind::Decimal => {
+                let value: f64 = value.parse().ok().filter(|v: &f64| v.is_finite()).ok_or_else(invalid)?;
+                let condition = self.filters.entry(field).or_insert(Condition::Decimal { min: None, max: None });
+                if let Condition::Decimal { min, max } = condition {
+                    set_bound(min, max, bound, value);
+                }
+            }
+            FilterKind::Address => {
+                self.filters.insert(field, Condition::Text(addresses.parse(value)?));
+            }
+            FilterKind::Text => {
+                self.filters.insert(field, Condition::Text(value.to_ascii_lowercase()));
+            }
+        }
+        Ok(())
+    }
+
+    /// 数値のフィールドが条件を満たすか（指定がない場合は満たす）
+    pub fn number(&self, field: &str, value: u64) -> bool {
+        match self.filters.get(field) {
+            Some(Condition::Number { min, max }) => {
+                min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+            }
+            _ => true,
+        }
+    }
+
+    /// 小数のフィールドが条件を満たすか（値がない要素は範囲の指定があれば満たさない）
+    pub fn decimal(&self, field: &str, value: Option<f64>) -> bool {
+        match (self.filters.get(field), value) {
+            (Some(Condition::Decimal { min, max }), Some(value)) => {
+                min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+            }
+            (Some(Condition::Decimal { .. }), None) => false,
+            _ => true,
+        }
+    }
+
+    /// 文字列かアドレスのフィールドが条件を満たすか
+    pub fn text(&self, field: &str, value: &str) -> bool {
+        match self.filters.get(field) {
+            Some(Condition::Text(expected)) => {
+                value.trim_start_matches("0x").eq_ignore_ascii_case(expected.trim_start_matches("0x"))
+            }
+            _ => true,
+        }
+    }
+
+    /// 一覧に固有のパラメーター
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+fn parse_sort(spec: &ListSpec, value: &str) -> Result<Sort> {
+    let (field, direction) = value.split_once(':').unwrap_or((value, "asc"));
+    let direction = match direction.to_ascii_lowercase().as_str() {
+        "asc" => Some(Direction::Asc),
+        "desc" => Some(Direction::Desc),
+        _ => None,
+    };
+    spec.sorts.iter()
+        .find(|sort| sort.field == field && Some(sort.direction) == direction)
+        .copied()
+        .ok_or_else(|| {
+            let supported: Vec<String> = spec.sorts.iter().map(Sort::to_string).collect();
+            AppError::coded(
+                ErrorCode::InvalidSort,
+                format!("Unsupported sort '{}' (expected one of: {})", value, supported.join(", ")),
+            )
+        })
+}
+
+fn lookup_filter(spec: &ListSpec, name: &str) -> Option<(&'static str, FilterKind, Bound)> {
+    if let Some((field, kind)) = spec.filters.iter().find(|(field, _)| *field == name) {
+        return Some((field, *kind, Bound::Eq));
+    }
+    let (bound, field) = match (name.strip_prefix("min_"), name.strip_prefix("max_")) {
+        (Some(field), _) => (Bound::Min, field),
+        (_, Some(field)) => (Bound::Max, field),
+        _ => return None,
+    };
+    spec.filters.iter()
+        .find(|(name, kind)| *name == field && matches!(kind, FilterKind::Number | FilterKind::Decimal))
+        .map(|(field, kind)| (*field, *kind, bound))
+}
+
+fn set_bound<T: Copy>(min: &mut Option<T>, max: &mut Option<T>, bound: Bound, value: T) {
+    match bound {
+        Bound::Eq => {
+            *min = Some(value);
+            *max = Some(value);
+        }
+        Bound::Min => *min = Some(value),
+        Bound::Max => *max = Some(value),
+    }
+}
+
+/// 一覧のページ
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// 次のページのカーソル（最後のページの場合は `None`）
+    pub next_cursor: Option<String>,
+}
+
+/// ストレージの順に読む一覧を、絞り込みを満たす要素で1ページ分埋める
+///
+/// `fetch` はカーソルの次から最大 `limit` 件と次のカーソルを返します。読んだ要素が
+/// `MAX_SCANNED` 件に達した場合は、`limit` に満たなくても途中のカーソルを返します。
+pub async fn fill<T, F, Fut>(query: &ListQuery, mut fetch: F, keep: impl Fn(&T) -> bool) -> Result<Page<T>>
+where
+    F: FnMut(Option<String>, usize) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, Option<String>)>>,
+{
+    let mut items = Vec::with_capacity(query.limit);
+    let mut cursor = query.cursor.clone();
+    let mut scanned = 0;
+    loop {
+        let (batch, next) = fetch(cursor.take(), query.limit - items.len()).await?;
+        let empty = batch.is_empty();
+        scanned += batch.len();
+        items.extend(batch.into_iter().filter(|item| keep(item)));
+        cursor = next;
+        if empty || cursor.is_none() || items.len() >= query.limit || scanned >= MAX_SCANNED {
+            return Ok(Page { items, next_cursor: cursor });
+        }
+    }
+}
+
+/// メモリ上の一覧の並び替えのキー
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortKey {
+    Number(u64),
+    Decimal(f64),
+    Text(String),
+}
+
+impl SortKey {
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.cmp(b),
+            (Self::Decimal(a), Self::Decimal(b)) => a.total_cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// メモリ上のカーソル（並び順のフィールド、最後の要素のキーとID）
+#[derive(Serialize, Deserialize)]
+struct KeyCursor(String, SortKey, String);
+
+/// メモリ上の一覧を絞り込み、並び替えて1ページにする
+///
+/// `key` は並び順のフィールドの値を、`id` は要素を一意に識別する値（同じキーの要素の順序に使う）を
+/// 返します。カーソルは最後の要素のキーとIDのため、ページの間に要素が増減しても重複しません。
+pub fn paginate<T>(
+    query: &ListQuery,
+    items: Vec<T>,
+    keep: impl Fn(&T) -> bool,
+    key: impl Fn(&T, &str) -> SortKey,
+    id: impl Fn(&T) -> String,
+) -> Result<Page<T>> {
+    let invalid = || AppError::coded(ErrorCode::InvalidCursor, "Invalid cursor for this list and sort");
+    let after = match &query.cursor {
+        Some(cursor) => {
+            let bytes = hex::decode(cursor).map_err(|_| invalid())?;
+            let KeyCursor(field, key, id) = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+            if field != query.sort.field {
+                return Err(invalid());
+            }
+            Some((key, id))
+        }
+        None => None,
+    };
+    let order = |a: &(SortKey, String), b: &(SortKey, String)| {
+        let ordering = a.0.compare(&b.0).then_with(|| a.1.cmp(&b.1));
+        if query.sort.is_ascending() { ordering } else { ordering.reverse() }
+    };
+
+    let mut keyed: Vec<((SortKey, String), T)> = items.into_iter()
+        .filter(|item| keep(item))
+        .map(|item| ((key(&item, query.sort.field), id(&item)), item))
+        .collect();
+    keyed.sort_by(|a, b| order(&a.0, &b.0));
+    let start = after.map_or(0, |after| keyed.partition_point(|(key, _)| order(key, &after) != Ordering::Greater));
+    let more = keyed.len() > start + query.limit;
+    let page: Vec<_> = keyed.into_iter().skip(start).take(query.limit).collect();
+    let next_cursor = match page.last() {
+        Some(((key, id), _)) if more => {
+            let cursor = KeyCursor(query.sort.field.to_string(), key.clone(), id.clone());
+            Some(hex::encode(serde_json::to_vec(&cursor)?))
+        }
+        _ => None,
+    };
+    Ok(Page { items: page.into_iter().map(|(_, item)| item).collect(), next_cursor })
+}
+
+/// ブロックの一覧
+pub const BLOCKS: ListSpec = ListSpec {
+    sorts: &[Sort::desc("height"), Sort::asc("height")],
+    filters: &[
+        ("validator", FilterKind::Text),
+        ("timestamp", FilterKind::Number),
+        ("transaction_count", FilterKind::Number),
+        ("gas_used", FilterKind::Number),
+    ],
+    params: &[],
+    max_limit: MAX_BLOCK_PAGE,
+};
+
+/// アカウントのトランザクションの一覧（索引の順のみ）
+pub const ACCOUNT_TRANSACTIONS: ListSpec = ListSpec {
+    sorts: &[Sort::desc("height")],
+    filters: &[
+        ("direction", FilterKind::Text),
+        ("counterparty", FilterKind::Address),
+        ("value", FilterKind::Number),
+        ("timestamp", FilterKind::Number),
+    ],
+    params: &[],
+    max_limit: MAX_ARCHIVE_PAGE,
+};
+
+/// トークンの保有者（アカウント）の一覧
+pub const TOKEN_HOLDERS: ListSpec = ListSpec {
+    sorts: &[Sort::desc("balance"), Sort::asc("balance"), Sort::asc("address")],
+    filters: &[("balance", FilterKind::Number)],
+    params: &[],
+    max_limit: MAX_PAGE,
+};
+
+/// 検証済みコントラクトの一覧（アドレスの順のみ）
+pub const CONTRACTS: ListSpec = ListSpec {
+    sorts: &[Sort::asc("address")],
+    filters: &[
+        ("compiler", FilterKind::Text),
+        ("match_status", FilterKind::Text),
+        ("verified_at", FilterKind::Number),
+    ],
+    params: &[],
+    max_limit: MAX_PAGE,
+};
+
+/// バリデーターのパフォーマンスの一覧
+pub const VALIDATORS: ListSpec = ListSpec {
+    sorts: &[
+        Sort::desc("proposed"),
+        Sort::asc("proposed"),
+        Sort::desc("missed"),
+        Sort::asc("missed"),
+        Sort::asc("miss_rate"),
+        Sort::desc("miss_rate"),
+        Sort::desc("votes"),
+        Sort::asc("avg_vote_latency_ms"),
+        Sort::asc("validator"),
+    ],
+    filters: &[
+        ("validator", FilterKind::Text),
+        ("proposed", FilterKind::Number),
+        ("missed", FilterKind::Number),
+        ("miss_rate", FilterKind::Decimal),
+        ("votes", FilterKind::Number),
+    ],
+    params: &["window"],
+    max_limit: MAX_PAGE,
+};
+
+/// バリデーターの候補の一覧
+pub const CANDIDATES: ListSpec = ListSpec {
+    sorts: &[Sort::asc("address")],
+    filters: &[
+        ("self_stake", FilterKind::Number),
+        ("commission_bps", FilterKind::Number),
+        ("registered_at", FilterKind::Number),
+    ],
+    params: &[],
+    max_limit: MAX_PAGE,
+};
+
+/// ガス使用量の多いコントラクトの一覧
+pub const TOP_CONTRACTS: ListSpec = ListSpec {
+    sorts: &[
+        Sort::desc("gas_used"),
+        Sort::asc("gas_used"),
+        Sort::desc("calls"),
+        Sort::asc("calls"),
+        Sort::desc("failure_rate"),
+        Sort::desc("failures"),
+        Sort::desc("avg_gas_per_call"),
+        Sort::asc("contract"),
+    ],
+    filters: &[
+        ("contract", FilterKind::Text),
+        ("calls", FilterKind::Number),
+        ("gas_used", FilterKind::Number),
+        ("failures", FilterKind::Number),
+        ("failure_rate", FilterKind::Decimal),
+    ],
+    params: &["window"],
+    max_limit: MAX_PAGE,
+};
+
+/// 委任先の比較の一覧
+pub const STAKING_MARKET: ListSpec = ListSpec {
+    sorts: &[
+        Sort::desc("apr"),
+        Sort::asc("apr"),
+        Sort::asc("commission_bps"),
+        Sort::desc("commission_bps"),
+        Sort::desc("uptime"),
+        Sort::desc("self_stake_ratio"),
+        Sort::desc("self_stake"),
+        Sort::desc("total_stake"),
+        Sort::asc("validator"),
+    ],
+    filters: &[
+        ("validator", FilterKind::Text),
+        ("commission_bps", FilterKind::Number),
+        ("self_stake", FilterKind::Number),
+        ("total_stake", FilterKind::Number),
+        ("self_stake_ratio", FilterKind::Decimal),
+        ("apr", FilterKind::Decimal),
+        ("uptime", FilterKind::Decimal),
+    ],
+    params: &["window"],
+    max_limit: MAX_PAGE,
+};
+
+/// ブロックの概要の一覧
+pub async fn blocks(state: &AppState, query: &ListQuery) -> Result<Page<BlockSummary>> {
+    let chain = &state.chain;
+    let ascending = query.sort.is_ascending();
+    let fetch = |cursor: Option<String>, limit| async move {
+        let from = cursor.as_deref()
+            .map(|cursor| cursor.parse::<u64>()
+                .map_err(|_| AppError::coded(ErrorCode::InvalidCursor, format!("Invalid cursor '{}'", cursor))))
+            .transpose()?;
+        let page = chain.blocks(from, ascending, limit).await?;
+        Ok::<_, AppError>((page.items, page.next_cursor))
+    };
+    fill(query, fetch, |block: &BlockSummary| {
+        query.text("validator", &block.validator)
+            && query.number("timestamp", block.timestamp)
+            && query.number("transaction_count", block.transaction_count as u64)
+            && query.number("gas_used", block.gas_used)
+    })
+    .await
+}
+
+/// アカウントが送受信したトランザクションの一覧（新しい順）
+pub async fn account_transactions(state: &AppState, address: &str, query: &ListQuery) -> Result<Page<AddressTx>> {
+    let views = &state.views;
+    let fetch = |cursor: Option<String>, limit| async move {
+        let page = views.transactions(address, cursor.as_deref(), limit).await?;
+        Ok::<_, AppError>((page.items, page.next_cursor))
+    };
+    fill(query, fetch, |tx: &AddressTx| {
+        let direction = match tx.direction {
+            TxDirection::In => "in",
+            TxDirection::Out => "out",
+        };
+        query.text("direction", direction)
+            && query.text("counterparty", &tx.counterparty)
+            && query.number("value", tx.value)
+            && query.number("timestamp", tx.timestamp)
+    })
+    .await
+}
+
+/// トークンの保有者の一覧
+pub async fn token_holders(state: &AppState, token: &str, query: &ListQuery) -> Result<Page<TokenHolder>> {
+    let holders = state.views.token_holders(token, usize::MAX).await?;
+    paginate(
+        query,
+        holders,
+        |holder| query.number("balance", holder.balance),
+        |holder, field| match field {
+            "balance" => SortKey::Number(holder.balance),
+            _ => SortKey::Text(holder.address.clone()),
+        },
+        |holder| holder.address.clone(),
+    )
+}
+
+/// 検証済みコントラクトの一覧（アドレスの順）
+pub async fn contracts(state: &AppState, query: &ListQuery) -> Result<Page<VerifiedContractSummary>> {
+    let contracts = &state.contracts;
+    let fetch = |cursor: Option<String>, limit| async move {
+        let items = contracts.list_verified(cursor.as_deref(), limit).await?;
+        let next_cursor = items.last().filter(|_| items.len() == limit).map(|contract| contract.address.clone());
+        Ok::<_, AppError>((items.iter().map(VerifiedContractSummary::of).collect(), next_cursor))
+    };
+    fill(query, fetch, |contract: &VerifiedContractSummary| {
+        query.text("compiler", contract.compiler.name())
+            && query.text("match_status", match contract.match_status {
+                MatchStatus::Exact => "exact",
+                MatchStatus::Partial => "partial",
+            })
+            && query.number("verified_at", contract.verified_at)
+    })
+    .await
+}
+
+/// バリデーターの候補の一覧（アドレスの順）
+pub async fn candidates(state: &AppState, query: &ListQuery) -> Result<Page<ValidatorCandidate>> {
+    let validators = &state.validators;
+    let fetch = |cursor: Option<String>, limit| async move {
+        let items = validators.list(cursor.as_deref(), limit).await?;
+        let next_cursor = items.last().filter(|_| items.len() == limit).map(|candidate| candidate.address.clone());
+        Ok::<_, AppError>((items, next_cursor))
+    };
+    fill(query, fetch, |candidate: &ValidatorCandidate| {
+        query.number("self_stake", candidate.self_stake)
+            && query.number("commission_bps", candidate.commission_bps as u64)
+            && query.number("registered_at", candidate.registered_at)
+    })
+    .await
+}
+
+/// バリデーターのパフォーマンスの一覧
+pub fn validators(query: &ListQuery, validators: Vec<ValidatorPerformance>) -> Result<Page<ValidatorPerformance>> {
+    paginate(
+        query,
+        validators,
+        |v| {
+            query.text("validator", &v.validator)
+                && query.number("proposed", v.proposed)
+                && query.number("missed", v.missed)
+                && query.decimal("miss_rate", Some(v.miss_rate))
+                && query.number("votes", v.votes)
+        },
+        |v, field| match field {
+            "proposed" => SortKey::Number(v.proposed),
+            "missed" => SortKey::Number(v.missed),
+            "miss_rate" => SortKey::Decimal(v.miss_rate),
+            "votes" => SortKey::Number(v.votes),
+            // 投票のないバリデーターは遅延の昇順で最後に並べる
+            "avg_vote_latency_ms" => SortKey::Decimal(v.avg_vote_latency_ms.unwrap_or(f64::INFINITY)),
+            _ => SortKey::Text(v.validator.clone()),
+        },
+        |v| v.validator.clone(),
+    )
+}
+
+/// 委任先の比較の一覧
+pub fn staking_market(query: &ListQuery, entries: Vec<MarketEntry>) -> Result<Page<MarketEntry>> {
+    paginate(
+        query,
+        entries,
+        |e| {
+            query.text("validator", &e.validator)
+                && query.number("commission_bps", e.commission_bps as u64)
+                && query.number("self_stake", e.self_stake)
+                && query.number("total_stake", e.total_stake)
+                && query.decimal("self_stake_ratio", Some(e.self_stake_ratio))
+                && query.decimal("apr", Some(e.apr))
+                && query.decimal("uptime", e.uptime)
+        },
+        |e, field| match field {
+            "apr" => SortKey::Decimal(e.apr),
+            "commission_bps" => SortKey::Number(e.commission_bps as u64),
+            // 担当スロットのない候補は稼働率の降順で最後に並べる
+            "uptime" => SortKey::Decimal(e.uptime.unwrap_or(-1.0)),
+            "self_stake_ratio" => SortKey::Decimal(e.self_stake_ratio),
+            "self_stake" => SortKey::Number(e.self_stake),
+            "total_stake" => SortKey::Number(e.total_stake),
+            _ => SortKey::Text(e.validator.clone()),
+        },
+        |e| e.validator.clone(),
+    )
+}
+
+/// ガス使用量の多いコントラクトの一覧
+pub fn top_contracts(query: &ListQuery, contracts: Vec<ContractUsage>) -> Result<Page<ContractUsage>> {
+    paginate(
+        query,
+        contracts,
+        |c| {
+            query.text("contract", &c.contract)
+                && query.number("calls", c.calls)
+                && query.number("gas_used", c.gas_used)
+                && query.number("failures", c.failures)
+                && query.decimal("failure_rate", Some(c.failure_rate))
+        },
+        |c, field| match field {
+            "gas_used" => SortKey::Number(c.gas_used),
+            "calls" => SortKey::Number(c.calls),
+            "failure_rate" => SortKey::Decimal(c.failure_rate),
+            "failures" => SortKey::Number(c.failures),
+            "avg_gas_per_call" => SortKey::Decimal(c.avg_gas_per_call),
+            _ => SortKey::Text(c.contract.clone()),
+        },
+        |c| c.contract.clone(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::wallet::DEFAULT_ADDRESS_PREFIX;
+
+    const SPEC: ListSpec = ListSpec {
+        sorts: &[Sort::desc("balance"), Sort::asc("balance"), Sort::asc("address")],
+        filters: &[("balance", FilterKind::Number), ("kind", FilterKind::Text)],
+        params: &["window"],
+        max_limit: 2,
+    };
+
+    fn parse(params: &[(&str, &str)]) -> Result<ListQuery> {
+        let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        ListQuery::parse(&SPEC, params, &AddressFormat::new(DEFAULT_ADDRESS_PREFIX).unwrap())
+    }
+
+    #[test]
+    fn test_parses_conventions_and_pages_by_key() {
+        let query = parse(&[("sort", "balance:asc"), ("min_balance", "10"), ("kind", "EOA"), ("window", "1h"), ("limit", "9")]).unwrap();
+        assert_eq!(query.sort, Sort::asc("balance"));
+        assert_eq!(query.limit, 2);
+        assert_eq!(query.param("window"), Some("1h"));
+        assert!(query.number("balance", 10) && !query.number("balance", 9));
+        assert!(query.text("kind", "eoa") && !query.text("kind", "contract"));
+
+        let error = parse(&[("sort", "balance:sideways")]).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidSort);
+        let error = parse(&[("min_kind", "a")]).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidFilter);
+        let error = parse(&[("balance", "lots")]).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidFilter);
+
+        // 同じキーの要素はIDの順に並び、カーソルの次から続く
+        let items = vec![("a", 30), ("b", 10), ("c", 30), ("d", 5)];
+        let mut query = parse(&[("min_balance", "10")]).unwrap();
+        let page = paginate(&query, items.clone(), |i| query.number("balance", i.1),
+            |i, _| SortKey::Number(i.1), |i| i.0.to_string()).unwrap();
+        assert_eq!(page.items, vec![("c", 30), ("a", 30)]);
+        query.cursor = page.next_cursor;
+        let page = paginate(&query, items, |i| query.number("balance", i.1),
+            |i, _| SortKey::Number(i.1), |i| i.0.to_string()).unwrap();
+        assert_eq!(page.items, vec![("b", 10)]);
+        assert_eq!(page.next_cursor, None);
+    }
+}
@@ -0,0 +1,55 @@
+//! 障害予測による緩和措置
+//!
+//! 障害が予測されている間、RPCの提供を一時停止します。
+//! ヘルスチェック系のエンドポイントは停止中も応答します。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::{AppState, AppError};
+use super::error_code::ErrorCode;
+use crate::core::ai::{MitigationHook, Prediction};
+
+/// 停止中も応答するパス
+const ALWAYS_SERVED: &[&str] = &["/health"];
+
+/// RPCの一時停止フラグ
+#[derive(Debug, Clone, Default)]
+pub struct RpcPause(Arc<AtomicBool>);
+
+impl RpcPause {
+    pub fn is_paused(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl MitigationHook for RpcPause {
+    fn name(&self) -> &str {
+        "pause_rpc"
+    }
+
+    fn trigger(&self, _predictions: &[Prediction]) -> anyhow::Result<()> {
+        self.0.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn resolve(&self) -> anyhow::Result<()> {
+        self.0.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// 一時停止中はRPCリクエストを拒否するミドルウェア
+pub async fn reject_when_paused(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if state.rpc_pause.is_paused()
+        && !ALWAYS_SERVED.iter().any(|p| request.uri().path().starts_with(p))
+    {
+        return AppError::coded(ErrorCode::RpcPaused, "RPC is paused due to a predicted failure").into_response();
+    }
+    next.run(request).await
+}
@@ -3,28 +3,89 @@
 //! このモジュールは、RustoriumのWebサーバーを実装します。
 //! 主な機能：
 //! - HTTP/WebSocket サーバー
-//! - 静的ファイルの提供
-//! - CORS対応
+//! - バイナリに埋め込んだWeb UIの提供（ETag・圧縮・SPAのフォールバック）
+//! - 設定に基づくCORSポリシー（管理者APIは別のポリシー）
+//! - 伏せ字化したアクセスログ
+//! - GraphQL API（Apollo Federation対応）
+//! - ライトノードのデータ可用性サンプリング（`das` フィーチャー）
+//! - ネームサービスの名前の参照と解決
+//! - ハッシュタイムロック（HTLC）の参照
+//! - 機密残高と範囲証明の検証の統計（`confidential-tx` フィーチャー）
+//! - 索引したチェーンデータへの読み取り専用のSQL（`sql` フィーチャー）
+//! - 内部サービス向けのブロックとイベントの gRPC ストリーミング（`grpc` フィーチャー）
+//! - 管理者APIのCPUとメモリのプロファイル（`profiling` フィーチャー）
+//! - 全てのAPIで共通のエラーコード（`error_code`）
+//! - 一覧のページ・並び順・絞り込みの共通の規約（`listing`）
 
+pub mod access_log;
+pub mod admin;
 pub mod api;
+pub mod assets;
+pub mod auth;
+#[cfg(feature = "confidential-tx")]
+pub mod confidential;
+pub mod cors;
+#[cfg(feature = "das")]
+pub mod das;
+pub mod error_code;
+pub mod geo;
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod htlc;
+pub mod idempotency;
+pub mod listing;
+pub mod mitigation;
+pub mod names;
+#[cfg(feature = "profiling")]
+pub mod pprof;
+pub mod replica;
+#[cfg(feature = "sql")]
+pub mod sql;
+pub mod rpc;
+pub mod watchlist;
 
 use std::sync::Arc;
 use axum::{
     Router,
-    routing::get_service,
+    routing::get,
+    middleware,
     response::{IntoResponse, Response},
-    http::StatusCode,
     Json,
 };
-use tower_http::{
-    services::ServeDir,
-    cors::CorsLayer,
-};
 use tracing::{info, error};
-use serde_json::json;
 use thiserror::Error;
+use tokio::sync::{Mutex, RwLock};
 use crate::config::NodeConfig;
+use crate::core::ai::AiOptimizer;
+use crate::core::blob::BlobStore;
+use crate::core::block::Chain;
+use crate::core::cache::MaterializedViews;
+use crate::core::storage::watchdog::DiskGuard;
+#[cfg(feature = "confidential-tx")]
+use crate::core::confidential::ConfidentialLedger;
+#[cfg(feature = "sql")]
+use crate::core::columnar::sql::SqlEngine;
+use crate::core::consensus::{performance::PerformanceTracker, registry::ValidatorRegistry, shadow::ShadowValidator};
+use crate::core::fees::FeeOracle;
+use crate::core::contract::{metrics::ContractMetrics, ContractVerifier, DeploymentLedger, ProxyRegistry};
+use crate::core::htlc::HtlcLedger;
+use crate::core::mempool::Mempool;
+use crate::core::names::NameRegistry;
+use crate::core::vesting::VestingLedger;
+use crate::core::network::quic::QuicNetwork;
+use crate::core::coordination::BeaconChain;
+use crate::core::sharding::ShardManager;
+use crate::core::wallet::AddressFormat;
+use crate::core::watchlist::Watchlist;
+use crate::i18n::LocaleConfig;
+use crate::util::log_filter::LogFilter;
+use error_code::{ApiError, ErrorBody, ErrorCode};
 
+/// APIのエラー
+///
+/// 汎用の変種は分類ごとの汎用のコードになります。SDKが分岐する必要のあるエラーは
+/// `AppError::coded` で個別のコードを付けます。
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("Authentication required")]
@@ -44,28 +105,45 @@ pub enum AppError {
 
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
+
+    /// 個別のエラーコード（HTTPステータスはコードから決まる）
+    #[error("{1}")]
+    Coded(ErrorCode, String),
+}
+
+impl AppError {
+    pub fn coded(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self::Coded(code, message.into())
+    }
+
+    /// エラーコード
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Unauthorized => ErrorCode::Unauthorized,
+            Self::Forbidden(_) => ErrorCode::Forbidden,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::BadRequest(_) => ErrorCode::InvalidRequest,
+            Self::Internal(_) => ErrorCode::Internal,
+            Self::ServiceUnavailable(_) => ErrorCode::ServiceUnavailable,
+            Self::Coded(code, _) => *code,
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            Self::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
-            Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
-            Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            Self::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            Self::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
+        let code = self.code();
+        let message = match self {
+            Self::Unauthorized => self.to_string(),
+            Self::Forbidden(msg)
+            | Self::NotFound(msg)
+            | Self::BadRequest(msg)
+            | Self::Internal(msg)
+            | Self::ServiceUnavailable(msg)
+            | Self::Coded(_, msg) => msg,
         };
-
-        let body = Json(json!({
-            "error": {
-                "message": error_message,
-                "code": status.as_u16(),
-                "type": status.canonical_reason().unwrap_or("Unknown")
-            }
-        }));
-
-        (status, body).into_response()
+        let body = Json(ErrorBody { error: ApiError::new(code, message) });
+        (code.status(), body).into_response()
     }
 }
 
@@ -93,40 +171,141 @@ pub type Result<T> = std::result::Result<T, AppError>;
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<NodeConfig>,
+    pub mempool: Arc<RwLock<Mempool>>,
+    pub contracts: Arc<ContractVerifier>,
+    pub proxies: Arc<ProxyRegistry>,
+    pub shards: Arc<RwLock<ShardManager>>,
+    /// シャードを調整するビーコンチェーン
+    pub beacon: Arc<BeaconChain>,
+    pub chain: Arc<Chain>,
+    /// よく使われるクエリのマテリアライズドビュー
+    pub views: Arc<MaterializedViews>,
+    /// アドレスのウォッチリスト
+    pub watchlist: Arc<Watchlist>,
+    /// バリデーターのパフォーマンス
+    pub performance: Arc<PerformanceTracker>,
+    /// コントラクトごとの利用状況
+    pub contract_metrics: Arc<ContractMetrics>,
+    /// シャドーバリデーター（シャドーモード以外は `None`）
+    pub shadow: Option<Arc<ShadowValidator>>,
+    /// 手数料の推定
+    pub fees: Arc<FeeOracle>,
+    /// ブロブのサイドカー
+    pub blobs: Arc<BlobStore>,
+    /// ネームサービスのレジストリ
+    pub names: Arc<NameRegistry>,
+    /// ハッシュタイムロックの台帳
+    pub htlc: Arc<HtlcLedger>,
+    /// コントラクトのデプロイの台帳
+    pub deployments: Arc<DeploymentLedger>,
+    /// ベスティングの台帳
+    pub vesting: Arc<VestingLedger>,
+    /// 機密残高の台帳
+    #[cfg(feature = "confidential-tx")]
+    pub confidential: Arc<ConfidentialLedger>,
+    /// 読み取り専用のSQL（`sql.enabled = false` の場合は `None`）
+    #[cfg(feature = "sql")]
+    pub sql: Option<Arc<SqlEngine>>,
+    /// P2Pネットワーク
+    pub network: Arc<QuicNetwork>,
+    /// AI最適化エンジン
+    pub ai: Option<Arc<Mutex<AiOptimizer>>>,
+    /// 障害予測によるRPCの一時停止
+    pub rpc_pause: mitigation::RpcPause,
+    /// ディスク容量の監視（`disk.enabled = false` の場合は `None`）
+    pub disk: Option<DiskGuard>,
+    /// 地理的ルーティング（無効の場合は `None`）
+    pub geo: Option<Arc<geo::GeoProxy>>,
+    /// トランザクションの転送先（読み取り専用レプリカ以外は `None`）
+    pub forwarder: Option<Arc<replica::TxForwarder>>,
+    /// パスキーによるログイン（無効の場合は `None`）
+    pub auth: Option<Arc<auth::PasskeyAuth>>,
+    /// 表示言語（Web UIのメッセージ）
+    pub locale: Arc<LocaleConfig>,
+    /// 実行中に変更できるログのフィルター（管理者API）
+    pub log_filter: Option<LogFilter>,
+    /// ネットワークのアドレス表記（入力のアドレスは `parse` で内部表記にしてから使う）
+    pub addresses: AddressFormat,
+    /// トランザクション送信の冪等キー
+    pub idempotency: Arc<idempotency::IdempotencyCache>,
+    /// バリデーターの候補
+    pub validators: Arc<ValidatorRegistry>,
+}
+
+/// サーバーのアドレスで待ち受ける（使用中のポートは設定の変更を促すエラーにする）
+pub async fn listen(name: &str, addr: std::net::SocketAddr) -> anyhow::Result<tokio::net::TcpListener> {
+    tokio::net::TcpListener::bind(addr).await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::AddrInUse => anyhow::anyhow!(
+            "Cannot start the {} server: {} is already in use; stop the other process or change network.port or [{}] port_offset",
+            name, addr, name),
+        std::io::ErrorKind::AddrNotAvailable => anyhow::anyhow!(
+            "Cannot start the {} server: {} is not an address of this host; check network.host or [{}] bind",
+            name, addr.ip(), name),
+        _ => anyhow::anyhow!("Cannot start the {} server on {}: {}", name, addr, e),
+    })
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct WebServer {
-    port: u16,
-    config: Arc<NodeConfig>,
+    state: AppState,
     shutdown: Arc<tokio::sync::Notify>,
 }
 
 impl WebServer {
-    pub fn new(port: u16, config: NodeConfig) -> Self {
+    pub fn new(state: AppState) -> Self {
         Self {
-            port,
-            config: Arc::new(config),
+            state,
             shutdown: Arc::new(tokio::sync::Notify::new()),
         }
     }
 
-    pub async fn run(&self) -> anyhow::Result<()> {
-        // 静的ファイルのハンドラー
-        let serve_dir = ServeDir::new("frontend");
+    /// 待ち受けを始めたリスナー（`listen`）でリクエストを処理する
+    pub async fn run(&self, listener: tokio::net::TcpListener) -> anyhow::Result<()> {
+        // CORSポリシー（管理者APIは公開APIと別に設定する）
+        let api_settings = &self.state.config.api;
+        let public_cors = cors::layer(&api_settings.cors)?;
+        let admin_cors = cors::layer(&api_settings.admin_cors)?;
 
         // ルーターの作成
-        let app = Router::new()
-            .nest("/api", api::create_router(AppState { config: self.config.clone() }))
-            .nest_service("/", get_service(serve_dir))
-            .layer(CorsLayer::permissive());
+        // 公開ルートのCORSは管理者APIを包まないよう、公開ルートのみに適用する
+        let public = Router::new()
+            .nest("/api/watchlist", watchlist::create_router(self.state.clone()))
+            .nest("/rpc", rpc::create_router(self.state.clone()))
+            .nest("/api", api::create_router(self.state.clone())
+                .layer(middleware::from_fn_with_state(self.state.clone(), geo::route_reads))
+                .layer(middleware::from_fn_with_state(self.state.clone(), mitigation::reject_when_paused)))
+            .nest("/api/auth", auth::create_router(self.state.clone()))
+            .nest("/api/names", names::create_router(self.state.clone())
+                .layer(middleware::from_fn_with_state(self.state.clone(), mitigation::reject_when_paused)))
+            .nest("/api/htlc", htlc::create_router(self.state.clone())
+                .layer(middleware::from_fn_with_state(self.state.clone(), mitigation::reject_when_paused)))
+            .merge(graphql::create_router(self.state.clone())
+                .layer(middleware::from_fn_with_state(self.state.clone(), mitigation::reject_when_paused)))
+            // APIに一致しないパスはWeb UI（埋め込んだ静的ファイル）
+            .fallback(get(assets::serve)
+                .layer(middleware::from_fn_with_state(self.state.clone(), auth::require_login)));
+        #[cfg(feature = "das")]
+        let public = public.nest("/api/das", das::create_router(self.state.clone())
+            .layer(middleware::from_fn_with_state(self.state.clone(), mitigation::reject_when_paused)));
+        #[cfg(feature = "confidential-tx")]
+        let public = public.nest("/api/confidential", confidential::create_router(self.state.clone())
+            .layer(middleware::from_fn_with_state(self.state.clone(), mitigation::reject_when_paused)));
+        #[cfg(feature = "sql")]
+        let public = public.nest("/api/sql", sql::create_router(self.state.clone()));
+        let public = public.layer(public_cors);
+        // セッションCookieで認証したリクエストは全てのルートでCSRFトークンを検証する
+        let mut app = Router::new()
+            .nest("/api/admin", admin::create_router(self.state.clone()).layer(admin_cors))
+            .merge(public)
+            .layer(middleware::from_fn_with_state(self.state.clone(), auth::csrf_guard));
+        if api_settings.access_log.enabled {
+            let log = Arc::new(access_log::AccessLog::open(&api_settings.access_log).await?);
+            app = app.layer(middleware::from_fn_with_state(log, access_log::record));
+        }
 
         // サーバーの起動
-        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], self.port));
-        info!("Starting web server on {}", addr);
-
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        let server = axum::serve(listener, app);
+        info!("Starting web server on {}", listener.local_addr()?);
+        let server = axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>());
 
         // シャットダウンシグナルを待機
         let shutdown_signal = self.shutdown.clone();
@@ -0,0 +1,92 @@
+//! ネームサービスAPI
+//!
+//! 名前の登録の状態と手数料の参照、名前からアドレスへの解決を提供します（`/api/names`）。
+//! 登録・更新・譲渡はレジストリ宛てのトランザクションで行います（`core::names`）。
+
+use std::sync::Arc;
+use axum::{
+    Router,
+    routing::get,
+    extract::{Path, State},
+    response::{IntoResponse, Json},
+};
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::core::names::{self, NameRecord, NameRegistry, NameStatus, NAME_REGISTRY_ADDRESS};
+
+struct NameServer {
+    registry: Arc<NameRegistry>,
+}
+
+/// 名前の状態
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NameInfo {
+    pub name: String,
+    pub status: NameStatus,
+    /// 登録（登録されたことがない名前は `None`）
+    pub record: Option<NameRecord>,
+    /// 解決先のアドレス（期限内のみ）
+    pub address: Option<String>,
+    /// 1年あたりの登録・更新の手数料
+    pub annual_fee: u64,
+    /// 操作の宛先
+    pub registry: String,
+}
+
+/// 名前の解決
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResolvedName {
+    pub name: String,
+    pub address: String,
+}
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/:name", get(get_name))
+        .route("/:name/resolve", get(resolve_name))
+        .with_state(Arc::new(NameServer { registry: state.names }))
+}
+
+fn parse_name(name: &str) -> Result<String> {
+    names::validate_name(&name.trim().to_lowercase()).map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+/// 名前の登録の状態と手数料
+async fn get_name(
+    State(server): State<Arc<NameServer>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse> {
+    let name = parse_name(&name)?;
+    let now = unix_now();
+    let record = server.registry.record(&name).await?;
+    let status = record.as_ref().map_or(NameStatus::Available, |r| r.status(now));
+    let address = record.as_ref()
+        .filter(|_| status == NameStatus::Active)
+        .map(|record| record.owner.clone());
+    Ok(Json(NameInfo {
+        annual_fee: names::annual_fee(&name),
+        name,
+        status,
+        record,
+        address,
+        registry: NAME_REGISTRY_ADDRESS.to_string(),
+    }))
+}
+
+/// 名前をアドレスに解決（期限切れの名前は404）
+async fn resolve_name(
+    State(server): State<Arc<NameServer>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse> {
+    let name = parse_name(&name)?;
+    let owner = server.registry.resolve(&name, unix_now()).await?
+        .ok_or_else(|| AppError::coded(ErrorCode::NameNotFound, format!("Name {} is not registered", name)))?;
+    Ok(Json(ResolvedName { name, address: owner }))
+}
@@ -0,0 +1,177 @@
+//! CPUとメモリのプロファイル（`profiling` フィーチャー）
+//!
+//! 管理者APIの `/api/admin/debug/pprof` で、実行中のノードのプロファイルを外部のツールを
+//! 接続せずに取得できます。
+//! - `profile?seconds=30`: `pprof-rs` で指定した秒数だけCPUをサンプリングし、pprof形式
+//!   （`go tool pprof` で読める）または `format=flamegraph` でSVGのフレームグラフを返す
+//! - `heap`: jemalloc がサンプリングしている確保中のメモリを pprof 形式で返す
+//!
+//! CPUのサンプリングはシグナルを使うためプロセスで同時に1つだけ実行します。
+//! ヒーププロファイルは jemalloc を使う Linux のビルドでのみ利用できます。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use axum::{
+    Router,
+    routing::get,
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+};
+use pprof::protos::Message;
+use serde::Deserialize;
+use tracing::info;
+
+use super::{AppState, AppError, Result};
+use super::admin::require_admin;
+
+/// CPUプロファイルの既定の秒数
+const DEFAULT_SECONDS: u64 = 30;
+/// CPUプロファイルの最大の秒数
+const MAX_SECONDS: u64 = 300;
+/// 既定のサンプリング周波数（Hz、他の周期的な処理と重ならないよう100を避ける）
+const DEFAULT_FREQUENCY: i32 = 99;
+/// サンプリングから除くライブラリ（シグナルハンドラー内で安全に巻き戻せない）
+const BLOCKLIST: &[&str] = &["libc", "libgcc", "pthread", "vdso"];
+
+/// CPUプロファイルを実行中か
+static CPU_PROFILE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/profile", get(cpu_profile))
+        .route("/heap", get(heap_profile))
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileQuery {
+    seconds: Option<u64>,
+    frequency: Option<i32>,
+    #[serde(default)]
+    format: ProfileFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ProfileFormat {
+    #[default]
+    Pprof,
+    Flamegraph,
+}
+
+/// 実行中のCPUプロファイル（破棄時に解放する）
+struct Running;
+
+impl Running {
+    fn acquire() -> Option<Self> {
+        CPU_PROFILE_RUNNING.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).ok().map(|_| Running)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        CPU_PROFILE_RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// CPUを指定した秒数サンプリング
+async fn cpu_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ProfileQuery>,
+) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    let seconds = query.seconds.unwrap_or(DEFAULT_SECONDS);
+    if seconds == 0 || seconds > MAX_SECONDS {
+        return Err(AppError::BadRequest(format!("seconds must be between 1 and {}", MAX_SECONDS)));
+    }
+    let frequency = query.frequency.unwrap_or(DEFAULT_FREQUENCY);
+    if !(1..=1000).contains(&frequency) {
+        return Err(AppError::BadRequest("frequency must be between 1 and 1000".to_string()));
+    }
+    let running = Running::acquire()
+        .ok_or_else(|| AppError::ServiceUnavailable("Another CPU profile is running".to_string()))?;
+    info!(target: "audit", "CPU profile for {} seconds at {} Hz started by {}", seconds, frequency, actor);
+
+    // サンプリング中はランタイムのスレッドを塞がないよう専用のスレッドで待つ
+    let format = query.format;
+    let body = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<u8>> {
+        let _running = running;
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(frequency)
+            .blocklist(BLOCKLIST)
+            .build()?;
+        std::thread::sleep(Duration::from_secs(seconds));
+        let report = guard.report().build()?;
+        let mut body = Vec::new();
+        match format {
+            ProfileFormat::Pprof => report.pprof()?.encode(&mut body)?,
+            ProfileFormat::Flamegraph => report.flamegraph(&mut body)?,
+        }
+        Ok(body)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(|e| AppError::Internal(format!("CPU profile failed: {}", e)))?;
+
+    let (content_type, file) = match format {
+        ProfileFormat::Pprof => ("application/octet-stream", "profile.pb"),
+        ProfileFormat::Flamegraph => ("image/svg+xml", "flamegraph.svg"),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file)),
+        ],
+        body,
+    ))
+}
+
+/// 確保中のメモリのプロファイル（gzipで圧縮した pprof 形式）
+async fn heap_profile(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    let actor = require_admin(&state, &headers)?;
+    let body = dump_heap().await?;
+    info!(target: "audit", "Heap profile taken by {}", actor);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"heap.pb.gz\""),
+        ],
+        body,
+    ))
+}
+
+#[cfg(target_os = "linux")]
+async fn dump_heap() -> Result<Vec<u8>> {
+    let ctl = jemalloc_pprof::PROF_CTL.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("jemalloc heap profiling is not available".to_string()))?;
+    let mut ctl = ctl.lock().await;
+    if !ctl.activated() {
+        return Err(AppError::ServiceUnavailable("jemalloc heap profiling is not active".to_string()));
+    }
+    ctl.dump_pprof().map_err(|e| AppError::Internal(format!("Heap profile failed: {}", e)))
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn dump_heap() -> Result<Vec<u8>> {
+    Err(AppError::ServiceUnavailable("Heap profiles are only available on Linux".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_one_cpu_profile_runs() {
+        let running = Running::acquire().unwrap();
+        assert!(Running::acquire().is_none());
+        drop(running);
+        assert!(Running::acquire().is_some());
+
+        let query: ProfileQuery = serde_json::from_value(serde_json::json!({ "seconds": 5, "format": "flamegraph" })).unwrap();
+        assert_eq!(query.seconds, Some(5));
+        assert!(matches!(query.format, ProfileFormat::Flamegraph));
+        let query: ProfileQuery = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(matches!(query.format, ProfileFormat::Pprof));
+    }
+}
@@ -0,0 +1,60 @@
+//! 読み取り専用レプリカのトランザクション転送
+//!
+//! `rpc-replica` の役割ではローカルのメモリプールを使わず、
+//! 受け付けたトランザクションを上流のシーケンサー／バリデーターへ転送して、その応答をそのまま返します。
+
+use std::time::Duration;
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use crate::config::RpcReplicaSettings;
+use super::idempotency::IDEMPOTENCY_KEY_HEADER;
+
+/// 転送先を示すヘッダー
+const FORWARDED_TO_HEADER: &str = "x-forwarded-to";
+
+/// トランザクションの転送
+pub struct TxForwarder {
+    client: reqwest::Client,
+    upstream: String,
+}
+
+impl TxForwarder {
+    pub fn new(settings: &RpcReplicaSettings) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(settings.request_timeout))
+                .build()?,
+            upstream: settings.upstream.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// 上流の `POST /api/transactions` へ転送
+    ///
+    /// 冪等キーは上流でまとめて扱うよう、ヘッダーをそのまま付けて転送します。
+    pub async fn forward<T: Serialize>(&self, transaction: &T, idempotency_key: Option<&str>) -> anyhow::Result<Response> {
+        let mut request = self.client
+            .post(format!("{}/api/transactions", self.upstream))
+            .json(transaction);
+        if let Some(key) = idempotency_key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        let upstream = request.send().await?;
+        let status = StatusCode::from_u16(upstream.status().as_u16())?;
+        let content_type = upstream.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(HeaderValue::from_str)
+            .transpose()?;
+        let body = upstream.bytes().await?;
+
+        let mut response = (status, body.to_vec()).into_response();
+        if let Some(content_type) = content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        }
+        response.headers_mut().insert(FORWARDED_TO_HEADER, HeaderValue::from_str(&self.upstream)?);
+        Ok(response)
+    }
+}
@@ -0,0 +1,388 @@
+//! WebSocket JSON-RPC（Ethereum互換）
+//!
+//! ethers.js などの `WebSocketProvider` をそのまま接続できるよう、
+//! `eth_subscribe` / `eth_unsubscribe` を JSON-RPC 2.0 で提供します。
+//! 主な機能：
+//! - `newHeads`：確定したブロックのヘッダー
+//! - `logs`：アドレスとトピックで絞り込んだイベント
+//! - `newPendingTransactions`：メモリプールが受け付けたトランザクションのハッシュ
+//! - プロバイダーの接続時に呼ばれる `eth_chainId` / `net_version` / `eth_blockNumber`
+//!
+//! ハッシュやアドレスは `0x` 付き、数値は Ethereum と同じ hex の quantity で返します。
+//! エラーの `code` は JSON-RPC のコードで、`data` にRESTと共通のエラーコードを付けます。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use axum::{
+    Router,
+    routing::get,
+    extract::{State, ws::{Message, WebSocket, WebSocketUpgrade}},
+    response::Response,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use super::AppState;
+use super::error_code::ErrorCode;
+use crate::core::block::Block;
+
+/// 接続ごとの送信キューの容量
+const OUTBOX_CAPACITY: usize = 256;
+
+// JSON-RPC 2.0 のエラーコード
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(upgrade))
+        .with_state(state)
+}
+
+/// JSON-RPCのリクエスト
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// JSON-RPCのエラー
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// `logs` 購読の条件
+#[derive(Debug, Clone, Default, Deserialize)]
+struct LogFilter {
+    /// 発行したコントラクト（1つまたは配列、省略時はすべて）
+    #[serde(default)]
+    address: Option<OneOrMany>,
+    /// 位置ごとのトピック（`null` は任意、配列はいずれか）
+    #[serde(default)]
+    topics: Vec<Option<OneOrMany>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            OneOrMany::One(v) => same_hex(v, value),
+            OneOrMany::Many(vs) => vs.is_empty() || vs.iter().any(|v| same_hex(v, value)),
+        }
+    }
+}
+
+impl LogFilter {
+    /// イベントが条件に一致するか
+    fn matches(&self, address: &str, topics: &[String]) -> bool {
+        if let Some(expected) = &self.address {
+            if !expected.matches(address) {
+                return false;
+            }
+        }
+        self.topics.iter().enumerate().all(|(i, expected)| match expected {
+            None => true,
+            Some(expected) => topics.get(i).is_some_and(|topic| expected.matches(topic)),
+        })
+    }
+}
+
+/// 購読の種類
+enum Subscription {
+    NewHeads,
+    Logs(LogFilter),
+    NewPendingTransactions,
+}
+
+impl Subscription {
+    fn parse(params: &[Value]) -> Result<Self, RpcError> {
+        let kind = params.first().and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing subscription type"))?;
+        match kind {
+            "newHeads" => Ok(Subscription::NewHeads),
+            "newPendingTransactions" => Ok(Subscription::NewPendingTransactions),
+            "logs" => {
+                let filter = match params.get(1) {
+                    Some(value) => serde_json::from_value(value.clone())
+                        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("invalid log filter: {}", e)))?,
+                    None => LogFilter::default(),
+                };
+                Ok(Subscription::Logs(filter))
+            }
+            other => Err(RpcError::new(INVALID_PARAMS, format!("unsupported subscription: {}", other))),
+        }
+    }
+}
+
+/// WebSocketへアップグレード
+async fn upgrade(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| serve(socket, state))
+}
+
+/// 接続を処理する
+///
+/// 購読ごとにタスクを起動し、通知は送信キューを通してこの接続のループから送ります。
+async fn serve(mut socket: WebSocket, state: AppState) {
+    let (outbox, mut queued) = mpsc::channel::<String>(OUTBOX_CAPACITY);
+    let mut subscriptions: HashMap<String, JoinHandle<()>> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            Some(text) = queued.recv() => {
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => continue,
+                };
+                let response = handle(&text, &state, &outbox, &mut subscriptions).await;
+                if socket.send(Message::Text(response.to_string())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    for (_, task) in subscriptions {
+        task.abort();
+    }
+    debug!("JSON-RPC client disconnected");
+}
+
+/// 1つのリクエストを処理してレスポンスを返す
+async fn handle(
+    text: &str,
+    state: &AppState,
+    outbox: &mpsc::Sender<String>,
+    subscriptions: &mut HashMap<String, JoinHandle<()>>,
+) -> Value {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => return error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+    };
+    let request: RpcRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => return error_response(Value::Null, RpcError::new(INVALID_REQUEST, e.to_string())),
+    };
+
+    let result = match request.method.as_str() {
+        "eth_subscribe" => Subscription::parse(&request.params).map(|subscription| {
+            let id = subscription_id();
+            let task = spawn_subscription(subscription, id.clone(), state, outbox.clone());
+            subscriptions.insert(id.clone(), task);
+            Value::String(id)
+        }),
+        "eth_unsubscribe" => match request.params.first().and_then(Value::as_str) {
+            Some(id) => Ok(Value::Bool(subscriptions.remove(id).map(|task| task.abort()).is_some())),
+            None => Err(RpcError::new(INVALID_PARAMS, "missing subscription id")),
+        },
+        "eth_chainId" => Ok(Value::String(quantity(state.config.consensus.chain_id))),
+        "net_version" => Ok(Value::String(state.config.consensus.chain_id.to_string())),
+        "eth_blockNumber" => Ok(Value::String(quantity(
+            state.chain.head().await.map_or(0, |(height, _)| height),
+        ))),
+        method => Err(RpcError::new(METHOD_NOT_FOUND, format!("method not found: {}", method))),
+    };
+
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err(e) => error_response(request.id, e),
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let code = match error.code {
+        METHOD_NOT_FOUND => ErrorCode::MethodNotFound,
+        PARSE_ERROR | INVALID_REQUEST | INVALID_PARAMS => ErrorCode::InvalidRequest,
+        _ => ErrorCode::Internal,
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": error.code,
+            "message": error.message,
+            "data": { "code": code.code(), "slug": code.slug() },
+        },
+    })
+}
+
+/// 購読の通知を送るタスクを起動
+fn spawn_subscription(
+    subscription: Subscription,
+    id: String,
+    state: &AppState,
+    outbox: mpsc::Sender<String>,
+) -> JoinHandle<()> {
+    match subscription {
+        Subscription::NewHeads => {
+            let blocks = state.chain.subscribe();
+            tokio::spawn(forward(blocks, id, outbox, |block: Arc<Block>| vec![block_header(&block)]))
+        }
+        Subscription::Logs(filter) => {
+            let blocks = state.chain.subscribe();
+            tokio::spawn(forward(blocks, id, outbox, move |block: Arc<Block>| block_logs(&block, &filter)))
+        }
+        Subscription::NewPendingTransactions => {
+            // 書き込みロックの取得待ちで止まらないよう、購読だけを短く行う
+            let mempool = state.mempool.clone();
+            tokio::spawn(async move {
+                let arrivals = mempool.read().await.subscribe();
+                forward(arrivals, id, outbox, |hash: String| vec![Value::String(prefixed(&hash))]).await
+            })
+        }
+    }
+}
+
+/// 購読元のメッセージを通知に変換して送り続ける
+async fn forward<T, F>(mut source: broadcast::Receiver<T>, id: String, outbox: mpsc::Sender<String>, convert: F)
+where
+    T: Clone + Send + 'static,
+    F: Fn(T) -> Vec<Value> + Send + 'static,
+{
+    loop {
+        let item = match source.recv().await {
+            Ok(item) => item,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                debug!("Subscription {} skipped {} messages", id, skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        for result in convert(item) {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": id, "result": result },
+            });
+            if outbox.send(notification.to_string()).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// `newHeads` の通知内容（拡張フィールドの導入前のブロックは `receiptsRoot` 等を含めない）
+fn block_header(block: &Block) -> Value {
+    let mut header = json!({
+        "number": quantity(block.height),
+        "hash": prefixed(&block.hash),
+        "parentHash": prefixed(&block.parent_hash),
+        "timestamp": quantity(block.timestamp),
+        "miner": block.validator,
+        "gasLimit": quantity(block.gas_limit),
+        "gasUsed": quantity(block.gas_used),
+        "nonce": "0x0000000000000000",
+        "difficulty": "0x0",
+        "extraData": "0x",
+    });
+    if !block.receipts_root.is_empty() {
+        header["receiptsRoot"] = json!(prefixed(&block.receipts_root));
+        header["logsBloom"] = json!(prefixed(&block.logs_bloom));
+        header["baseFeePerGas"] = json!(quantity(block.base_fee));
+    }
+    header
+}
+
+/// ブロック内で条件に一致するイベント（`logs` の通知内容）
+fn block_logs(block: &Block, filter: &LogFilter) -> Vec<Value> {
+    block.events.iter()
+        .enumerate()
+        .filter(|(_, event)| filter.matches(&event.address, &event.topics))
+        .map(|(log_index, event)| {
+            let tx_index = block.transactions.iter()
+                .position(|tx| tx.hash == event.tx_hash)
+                .unwrap_or_default();
+            json!({
+                "address": event.address,
+                "topics": event.topics.iter().map(|t| prefixed(t)).collect::<Vec<_>>(),
+                "data": format!("0x{}", hex::encode(&event.data)),
+                "blockNumber": quantity(block.height),
+                "blockHash": prefixed(&block.hash),
+                "transactionHash": prefixed(&event.tx_hash),
+                "transactionIndex": quantity(tx_index as u64),
+                "logIndex": quantity(log_index as u64),
+                "removed": false,
+            })
+        })
+        .collect()
+}
+
+/// 購読ID（128ビットの乱数）
+fn subscription_id() -> String {
+    format!("0x{:032x}", rand::random::<u128>())
+}
+
+/// Ethereumの quantity 表記（先頭のゼロなしの hex）
+fn quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+/// `0x` を付ける（既に付いている場合はそのまま）
+fn prefixed(hex: &str) -> String {
+    if hex.starts_with("0x") {
+        hex.to_string()
+    } else {
+        format!("0x{}", hex)
+    }
+}
+
+/// `0x` の有無と大文字小文字を無視して比較
+pub(crate) fn same_hex(a: &str, b: &str) -> bool {
+    a.trim_start_matches("0x").eq_ignore_ascii_case(b.trim_start_matches("0x"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter_matches_address_and_topics() {
+        let filter: LogFilter = serde_json::from_value(json!({
+            "address": ["0xAbC", "0xdef"],
+            "topics": [null, ["0x01", "0x02"]],
+        })).unwrap();
+        let topics = vec!["ff".to_string(), "02".to_string()];
+        assert!(filter.matches("abc", &topics));
+        assert!(!filter.matches("123", &topics));
+        assert!(!filter.matches("abc", &topics[..1]));
+        assert!(!filter.matches("abc", &["ff".to_string(), "03".to_string()]));
+
+        assert!(LogFilter::default().matches("anything", &[]));
+    }
+
+    #[test]
+    fn test_subscription_params() {
+        assert!(matches!(Subscription::parse(&[json!("newHeads")]), Ok(Subscription::NewHeads)));
+        assert!(matches!(Subscription::parse(&[json!("logs")]), Ok(Subscription::Logs(_))));
+        assert!(Subscription::parse(&[json!("syncing")]).is_err());
+        assert!(Subscription::parse(&[]).is_err());
+        assert_eq!(quantity(255), "0xff");
+    }
+}
@@ -0,0 +1,53 @@
+//! SQL API（`sql` フィーチャー）
+//!
+//! 索引したチェーンデータへの読み取り専用のSQL（`POST /api/sql`）です。`sql:query` 権限を許可した
+//! トークン、管理者トークン、またはパスキーのログインセッションが必要です。
+
+use axum::{
+    Router,
+    routing::post,
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Json},
+};
+use serde::Deserialize;
+
+use super::admin::require_scope;
+use super::{AppState, AppError, Result};
+use super::error_code::ErrorCode;
+use crate::config::SCOPE_SQL_QUERY;
+use crate::core::columnar::sql::SqlError;
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/", post(run_query))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct SqlRequest {
+    query: String,
+}
+
+/// クエリを実行
+async fn run_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SqlRequest>,
+) -> Result<impl IntoResponse> {
+    require_scope(&state, &headers, SCOPE_SQL_QUERY)?;
+    let engine = state.sql.as_ref()
+        .ok_or_else(|| AppError::coded(ErrorCode::FeatureDisabled, "SQL is not enabled (set sql.enabled = true)"))?;
+    Ok(Json(engine.query(&request.query).await?))
+}
+
+impl From<SqlError> for AppError {
+    fn from(e: SqlError) -> Self {
+        match e {
+            SqlError::Invalid(message) => AppError::coded(ErrorCode::InvalidQuery, message),
+            SqlError::LimitExceeded(_) => AppError::coded(ErrorCode::QueryLimitExceeded, e.to_string()),
+            SqlError::Busy(_) => AppError::ServiceUnavailable(e.to_string()),
+            SqlError::Internal(inner) => AppError::Internal(inner.to_string()),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! ウォッチリストAPI
+//!
+//! `X-Api-Key` ヘッダー（WebSocket では `api_key` クエリも可）で利用者を識別し、
+//! 登録したアドレスの送受信やイベントを通知します。
+
+use axum::{
+    Router,
+    routing::{get, delete},
+    extract::{Path, Query, State, ws::{Message, WebSocket, WebSocketUpgrade}},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use tracing::debug;
+
+use super::{AppState, AppError, Result};
+use crate::core::watchlist::{WatchRequest, WatchSubscription, WatchlistError};
+
+/// APIキーのヘッダー
+const API_KEY_HEADER: &str = "x-api-key";
+
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(list_watches).post(register_watch))
+        .route("/:id", delete(remove_watch))
+        .route("/ws", get(subscribe))
+        .with_state(state)
+}
+
+impl From<WatchlistError> for AppError {
+    fn from(err: WatchlistError) -> Self {
+        match err {
+            WatchlistError::NotFound(_) => Self::NotFound(err.to_string()),
+            WatchlistError::Internal(e) => Self::Internal(e.to_string()),
+            _ => Self::BadRequest(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKeyQuery {
+    api_key: Option<String>,
+}
+
+/// リクエストのAPIキーを取得
+fn api_key(headers: &HeaderMap, query: Option<&ApiKeyQuery>) -> Result<String> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| query.and_then(|q| q.api_key.clone()))
+        .filter(|key| !key.is_empty())
+        .ok_or(AppError::Unauthorized)
+}
+
+/// ウォッチを登録
+async fn register_watch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<WatchRequest>,
+) -> Result<impl IntoResponse> {
+    let key = api_key(&headers, None)?;
+    request.addresses = request.addresses.iter()
+        .map(|address| state.addresses.parse(address))
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(Json(state.watchlist.register(&key, request).await?))
+}
+
+/// ウォッチ一覧を取得
+async fn list_watches(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse> {
+    let key = api_key(&headers, None)?;
+    Ok(Json(state.watchlist.list(&key).await))
+}
+
+/// ウォッチを削除
+async fn remove_watch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let key = api_key(&headers, None)?;
+    state.watchlist.remove(&key, &id).await?;
+    Ok(Json(serde_json::json!({ "removed": id })))
+}
+
+/// 通知をWebSocketで購読
+async fn subscribe(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ApiKeyQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let key = api_key(&headers, Some(&query))?;
+    let subscription = state.watchlist.subscribe(&key);
+    Ok(ws.on_upgrade(move |socket| push_notifications(socket, subscription)))
+}
+
+/// 通知をクライアントへ送り続ける
+async fn push_notifications(mut socket: WebSocket, mut subscription: WatchSubscription) {
+    loop {
+        tokio::select! {
+            notification = subscription.recv() => {
+                let Some(notification) = notification else {
+                    break;
+                };
+                let Ok(text) = serde_json::to_string(&notification) else {
+                    continue;
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+    debug!("Watchlist subscriber disconnected");
+}